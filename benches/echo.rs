@@ -0,0 +1,47 @@
+// Benchmark the throughput of echo on large arguments.
+//
+// Run with `cargo bench --bench echo`. See benches/text.rs for comparing against a baseline.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use rust_coreutils::echo::{render, EchoOptions};
+use std::ffi::OsString;
+
+const SIZE: usize = 10 * 1024 * 1024;
+
+/// Generate an argument of `SIZE` bytes with an escape sequence in every line.
+fn large_argument() -> OsString {
+    let line = r"some text with a\ttab and a \x41 hexadecimal escape\n";
+    line.repeat(SIZE / line.len() + 1)[..SIZE].into()
+}
+
+/// Generate an argument of `SIZE` bytes made of escape sequences only.
+fn escapes_argument() -> OsString {
+    let escapes = r"\t\x41\0101\\\q\uffff";
+    escapes.repeat(SIZE / escapes.len() + 1)[..SIZE].into()
+}
+
+fn bench_echo(c: &mut Criterion) {
+    let args = [large_argument()];
+
+    let mut group = c.benchmark_group("echo");
+    group.throughput(Throughput::Bytes(SIZE as u64));
+
+    let options = EchoOptions::default();
+    group.bench_function("10MB", |b| b.iter(|| render(black_box(&args), &options)));
+
+    let options = EchoOptions {
+        escapes: true,
+        ..EchoOptions::default()
+    };
+    group.bench_function("10MB -e", |b| b.iter(|| render(black_box(&args), &options)));
+
+    let args = [escapes_argument()];
+    group.bench_function("10MB -e escapes only", |b| {
+        b.iter(|| render(black_box(&args), &options))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_echo);
+criterion_main!(benches);
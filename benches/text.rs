@@ -0,0 +1,197 @@
+// Benchmark the inner loops of the text utilities over generated fixtures, in memory.
+//
+// Run with `cargo bench --bench text`, or a subset with a filter, e.g. `cargo bench --bench text -- "short
+// lines"`. To measure a change, save a baseline before it with `cargo bench --bench text -- --save-baseline
+// before`, and compare with `cargo bench --bench text -- --baseline before` after it. The reports end up in
+// target/criterion.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rust_coreutils::common::input::InputIter;
+use std::io::Read;
+
+/// The inputs every inner loop runs over.
+fn fixtures() -> Vec<(&'static str, Vec<u8>)> {
+    vec![
+        ("1M short lines", short_lines()),
+        ("100 long lines", long_lines()),
+        ("binary", binary()),
+    ]
+}
+
+/// One million lines of a dozen bytes.
+fn short_lines() -> Vec<u8> {
+    (0..1_000_000)
+        .flat_map(|i| format!("line {i:06}\n").into_bytes())
+        .collect()
+}
+
+/// A hundred lines of 100 KiB of words.
+fn long_lines() -> Vec<u8> {
+    let line = "lorem ipsum dolor sit amet ".repeat(100 * 1024 / 27);
+    (0..100)
+        .flat_map(|_| format!("{line}\n").into_bytes())
+        .collect()
+}
+
+/// 10 MiB of pseudorandom bytes.
+fn binary() -> Vec<u8> {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    (0..10 * 1024 * 1024)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+/// The inner loop of a utility, which takes its whole input from memory and returns something derived from
+/// the output, so that it is not optimized away.
+type InnerLoop = fn(&[u8]) -> usize;
+
+/// The inner loops, by name. New utilities add theirs here.
+const LOOPS: &[(&str, InnerLoop)] = &[
+    ("input", read_input),
+    #[cfg(feature = "feat_cat")]
+    ("cat", cat),
+    #[cfg(feature = "feat_cat")]
+    ("cat -n", cat_number),
+    #[cfg(feature = "feat_cut")]
+    ("cut", cut),
+    #[cfg(feature = "feat_sort")]
+    ("sort", sort),
+    #[cfg(feature = "feat_tr")]
+    ("tr a-z A-Z", tr),
+    #[cfg(feature = "feat_uniq")]
+    ("uniq", uniq),
+    #[cfg(feature = "feat_wc")]
+    ("wc", wc),
+];
+
+/// Read standard input through the shared operand iterator, like the utilities do.
+fn read_input(data: &[u8]) -> usize {
+    let mut inputs = InputIter::with_stdin(Vec::new(), Box::new(data));
+    let mut total = 0;
+    let mut buffer = vec![0; 64 * 1024];
+    while let Some(input) = inputs.next_raw() {
+        let mut reader = input.unwrap().reader;
+        loop {
+            match reader.read(&mut buffer).unwrap() {
+                0 => break,
+                n => total += n,
+            }
+        }
+    }
+    total
+}
+
+/// Copy standard input to a sink with cat.
+#[cfg(feature = "feat_cat")]
+fn cat(data: &[u8]) -> usize {
+    cat_with(data, &rust_coreutils::cat::CatOptions::default())
+}
+
+/// Number the lines of standard input with cat.
+#[cfg(feature = "feat_cat")]
+fn cat_number(data: &[u8]) -> usize {
+    let options = rust_coreutils::cat::CatOptions {
+        number: rust_coreutils::cat::Numbering::All,
+        ..Default::default()
+    };
+    cat_with(data, &options)
+}
+
+#[cfg(feature = "feat_cat")]
+fn cat_with(data: &[u8], options: &rust_coreutils::cat::CatOptions) -> usize {
+    let mut inputs = InputIter::with_stdin(Vec::new(), Box::new(data));
+    let mut stdout = Counter(0);
+    let mut reporter = rust_coreutils::common::error::Reporter::new("cat", std::io::sink());
+    rust_coreutils::cat::cat(&mut inputs, options, &mut stdout, &mut reporter).unwrap();
+    stdout.0
+}
+
+/// Print the bytes of the lines of standard input with cut, without writing them.
+#[cfg(feature = "feat_cut")]
+fn cut(data: &[u8]) -> usize {
+    let options = rust_coreutils::cut::CutOptions::default();
+    let mut inputs = InputIter::with_stdin(Vec::new(), Box::new(data));
+    let mut stdout = Counter(0);
+    let mut reporter = rust_coreutils::common::error::Reporter::new("cut", std::io::sink());
+    rust_coreutils::cut::cut(&mut inputs, &options, &mut stdout, &mut reporter).unwrap();
+    stdout.0
+}
+
+/// Sort the lines of standard input with sort, without writing them.
+#[cfg(feature = "feat_sort")]
+fn sort(data: &[u8]) -> usize {
+    let options = rust_coreutils::sort::SortOptions::default();
+    let mut inputs = InputIter::with_stdin(Vec::new(), Box::new(data));
+    let data = rust_coreutils::sort::read_all(&mut inputs, options.delimiter).unwrap();
+    rust_coreutils::sort::sort_lines(&data, &options).len()
+}
+
+/// Translate standard input to upper case with tr, without writing it.
+#[cfg(feature = "feat_tr")]
+fn tr(data: &[u8]) -> usize {
+    use rust_coreutils::tr::{Flags, Set, TrOptions};
+
+    let set1 = Set::parse(b"a-z", &mut Vec::new()).unwrap();
+    let set2 = Set::parse(b"A-Z", &mut Vec::new()).unwrap();
+    let options = TrOptions::new(&set1, Some(&set2), Flags::default()).unwrap();
+    let mut stdout = Counter(0);
+    rust_coreutils::tr::tr(&mut &data[..], &options, &mut stdout).unwrap();
+    stdout.0
+}
+
+/// Collapse the adjacent duplicate lines of standard input with uniq.
+#[cfg(feature = "feat_uniq")]
+fn uniq(data: &[u8]) -> usize {
+    let options = rust_coreutils::uniq::UniqOptions::default();
+    let mut stdout = Vec::new();
+    rust_coreutils::uniq::uniq(&mut &data[..], "-", &options, &mut stdout).unwrap();
+    stdout.len()
+}
+
+/// Count the lines, words, and bytes of standard input with wc.
+#[cfg(feature = "feat_wc")]
+fn wc(data: &[u8]) -> usize {
+    let mut data = data;
+    let columns = rust_coreutils::wc::Columns::default();
+    rust_coreutils::wc::count(&mut data, &columns)
+        .unwrap()
+        .words as usize
+}
+
+/// A writer which only counts the bytes written to it.
+#[cfg(any(feature = "feat_cat", feature = "feat_cut", feature = "feat_tr"))]
+struct Counter(usize);
+
+#[cfg(any(feature = "feat_cat", feature = "feat_cut", feature = "feat_tr"))]
+impl std::io::Write for Counter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn bench_text(c: &mut Criterion) {
+    let fixtures = fixtures();
+    for &(name, inner_loop) in LOOPS {
+        let mut group = c.benchmark_group(name);
+        for (fixture, data) in &fixtures {
+            group.throughput(Throughput::Bytes(data.len() as u64));
+            group.bench_with_input(BenchmarkId::from_parameter(fixture), data, |b, data| {
+                b.iter(|| inner_loop(black_box(data)))
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_text);
+criterion_main!(benches);
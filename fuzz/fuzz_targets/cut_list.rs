@@ -0,0 +1,37 @@
+// Parse an arbitrary list of cut, of positions or of fields as selected by the first byte.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_coreutils::cut::{List, Unit};
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&unit, list)) = data.split_first() else {
+        return;
+    };
+    let Ok(list) = std::str::from_utf8(list) else {
+        return;
+    };
+    let unit = if unit & 1 != 0 {
+        Unit::Fields
+    } else {
+        Unit::Positions
+    };
+    let Ok(list) = List::parse(list, unit) else {
+        return;
+    };
+
+    // The ranges are sorted and apart, and together with the complement they cover every position once.
+    let complement = list.complement();
+    let mut covered = 0usize;
+    for ranges in [list.ranges(), complement.ranges()] {
+        for pair in ranges.windows(2) {
+            assert!(pair[0].end <= pair[1].start, "{ranges:?}");
+        }
+        for range in ranges {
+            assert!(range.start < range.end, "{ranges:?}");
+            covered += range.len();
+        }
+    }
+    assert_eq!(covered, usize::MAX);
+});
@@ -0,0 +1,26 @@
+// Render arbitrary arguments, separated by NUL bytes, with the options selected by the first byte.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_coreutils::echo::{render, EchoOptions};
+use std::ffi::OsString;
+use std::os::unix::ffi::OsStringExt;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&flags, data)) = data.split_first() else {
+        return;
+    };
+    let options = EchoOptions {
+        newline: flags & 1 != 0,
+        separator: if flags & 2 != 0 { b" ".to_vec() } else { Vec::new() },
+        escapes: flags & 4 != 0,
+    };
+    let args: Vec<OsString> = data
+        .split(|&byte| byte == 0)
+        .map(|argument| OsString::from_vec(argument.to_vec()))
+        .collect();
+
+    let output = render(&args, &options);
+    assert!(output.len() <= data.len() + usize::from(options.newline));
+});
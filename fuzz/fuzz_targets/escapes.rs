@@ -0,0 +1,24 @@
+// Expand the escape sequences of arbitrary input in every flavor.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_coreutils::common::escapes::{expand_escapes, EscapeFlavor};
+
+const FLAVORS: [EscapeFlavor; 4] = [
+    EscapeFlavor::Echo,
+    EscapeFlavor::Printf,
+    EscapeFlavor::PrintfArgument,
+    EscapeFlavor::Tr,
+];
+
+fuzz_target!(|data: &[u8]| {
+    for flavor in FLAVORS {
+        let result = expand_escapes(data, flavor);
+        assert!(result.output.len() <= data.len(), "{flavor:?}");
+        if !data.contains(&b'\\') {
+            assert_eq!(result.output, data);
+            assert!(!result.terminated);
+        }
+    }
+});
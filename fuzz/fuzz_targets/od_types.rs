@@ -0,0 +1,25 @@
+// Parse an arbitrary argument of `od -t`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_coreutils::od::{Kind, OutputType, LONG_DOUBLE_SIZE};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(spec) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(types) = OutputType::parse(spec) else {
+        return;
+    };
+    // Every type starts with a letter, and has a size of the machine.
+    assert!(types.len() <= spec.len());
+    for output_type in types {
+        let sizes: &[usize] = match output_type.kind {
+            Kind::Named | Kind::Character => &[1],
+            Kind::Float => &[4, 8, LONG_DOUBLE_SIZE],
+            _ => &[1, 2, 4, 8],
+        };
+        assert!(sizes.contains(&output_type.size), "{spec}: {output_type:?}");
+    }
+});
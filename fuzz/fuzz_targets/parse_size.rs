@@ -0,0 +1,27 @@
+// Parse an arbitrary size in every syntax of common::size.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_coreutils::common::size::{
+    parse_dd_size, parse_prefixed_size, parse_signed_size, parse_size, parse_size_u64,
+};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(s) = std::str::from_utf8(data) else {
+        return;
+    };
+    let size = parse_size(s);
+    match parse_size_u64(s) {
+        Ok(small) => assert_eq!(size.as_ref().ok(), Some(&u128::from(small))),
+        Err(_) => assert!(size
+            .as_ref()
+            .map_or(true, |&size| size > u128::from(u64::MAX))),
+    }
+    // The sizes without a sign are the same when a sign is allowed.
+    if let Ok(size) = size {
+        assert_eq!(parse_signed_size(s).map(|(_, size)| size).ok(), Some(size));
+    }
+    let _ = parse_prefixed_size(s);
+    let _ = parse_dd_size(s);
+});
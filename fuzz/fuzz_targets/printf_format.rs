@@ -0,0 +1,65 @@
+// Parse an arbitrary conversion specification of printf, and format a number with it.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_coreutils::common::format::{Count, Spec, MAX_COUNT};
+use std::io::{self, Write};
+
+/// The most bytes which are written for a field, while the widths and the precisions can make more.
+const MAX_OUTPUT: usize = 1 << 20;
+
+/// An output which keeps the first [`MAX_OUTPUT`] bytes, and then fails.
+struct Limited(Vec<u8>);
+
+impl Write for Limited {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        if self.0.len() + buffer.len() > MAX_OUTPUT {
+            return Err(io::Error::new(io::ErrorKind::WriteZero, "too much output"));
+        }
+        self.0.extend_from_slice(buffer);
+        Ok(buffer.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let mut format = b"%".to_vec();
+    format.extend_from_slice(data);
+    let Ok((spec, length)) = Spec::parse(&format) else {
+        return;
+    };
+    assert!((2..=format.len()).contains(&length));
+    assert_eq!(format[length - 1], spec.conversion);
+
+    // The value is read from the bytes after the specification, and so are the counts given with `*`.
+    let mut value = [0; 8];
+    let rest = &format[length..];
+    value[..rest.len().min(8)].copy_from_slice(&rest[..rest.len().min(8)]);
+    let value = u64::from_le_bytes(value);
+    let count = |count: Option<Count>| match count {
+        Some(Count::Fixed(count)) => {
+            assert!(count <= MAX_COUNT);
+            Some(count)
+        }
+        Some(Count::Argument) => Some(value as usize % (MAX_COUNT + 1)),
+        None => None,
+    };
+    let (width, precision) = (count(spec.width), count(spec.precision));
+    let field = match spec.conversion {
+        b'd' | b'i' => spec.signed(value as i64, precision),
+        b'o' | b'u' | b'x' | b'X' => spec.unsigned(value, precision),
+        b'c' | b's' => return,
+        _ => spec.float(f64::from_bits(value), precision),
+    };
+    let mut output = Limited(Vec::new());
+    if field
+        .write(spec.left, width.unwrap_or(0), &mut output)
+        .is_ok()
+    {
+        assert!(output.0.len() >= width.unwrap_or(0));
+    }
+});
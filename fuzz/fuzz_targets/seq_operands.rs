@@ -0,0 +1,25 @@
+// Parse arbitrary operands of seq, separated by NUL bytes, and the format of the last one given with -f.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_coreutils::seq::{Format, Number, Operand, Sequence};
+
+fuzz_target!(|data: &[u8]| {
+    let operands: Vec<&[u8]> = data.split(|&byte| byte == 0).collect();
+    for operand in &operands {
+        // An operand is a number if and only if it can be parsed with how it is written.
+        assert_eq!(
+            Number::parse(operand).is_ok(),
+            Operand::parse(operand).is_ok()
+        );
+    }
+    if let Ok(sequence) = Sequence::parse(&operands) {
+        assert!(operands.len() <= 3);
+        match sequence.increment.number {
+            Number::Integer(increment) => assert_ne!(increment, 0),
+            Number::Float(increment) => assert_ne!(increment, 0.0),
+        }
+    }
+    let _ = Format::parse(operands.last().unwrap_or(&&b""[..]));
+});
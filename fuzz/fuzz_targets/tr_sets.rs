@@ -0,0 +1,44 @@
+// Parse SET1 and SET2 of tr, separated by a NUL byte, and use them with the flags selected by the first byte.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_coreutils::tr::{Flags, Set, TrOptions};
+
+/// The largest sets whose bytes are listed, as the repeats can make them as long as `usize::MAX`.
+const MAX_LENGTH: usize = 1 << 16;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&flags, data)) = data.split_first() else {
+        return;
+    };
+    let flags = Flags {
+        complement: flags & 1 != 0,
+        delete: flags & 2 != 0,
+        squeeze: flags & 4 != 0,
+        truncate: flags & 8 != 0,
+    };
+    let mut warnings = Vec::new();
+    let Ok(sets) = data
+        .splitn(2, |&byte| byte == 0)
+        .map(|arg| Set::parse(arg, &mut warnings))
+        .collect::<Result<Vec<Set>, String>>()
+    else {
+        return;
+    };
+    if sets.iter().any(|set| set.len() > MAX_LENGTH) {
+        return;
+    }
+
+    // The members are the bytes of the set, without going through them.
+    for set in &sets {
+        let members = set.members(0);
+        let mut length = 0;
+        for byte in set.bytes(0) {
+            assert!(members[usize::from(byte)], "{set:?}");
+            length += 1;
+        }
+        assert_eq!(length, set.len(), "{set:?}");
+    }
+    let _ = TrOptions::new(&sets[0], sets.get(1), flags);
+});
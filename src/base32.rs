@@ -0,0 +1,199 @@
+//! Encode or decode base32, as described in RFC 4648, where a block of 5 bytes is 8 characters.
+//!
+//! The data is streamed by [`common::encoding`](crate::common::encoding), so that inputs of any size are
+//! read in constant memory.
+
+use crate::common::encoding::{self, BASE32};
+use crate::common::error::Reporter;
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use clap::Parser;
+use std::ffi::OsString;
+use std::io::{Read, Write};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "base32",
+    author,
+    version,
+    about = "Encode FILE to base32, or decode it, to standard output.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Decode the data instead of encoding it. Newlines are ignored, and any other byte outside of the
+    /// alphabet is an error.
+    #[arg(short, long, default_value_t = false)]
+    decode: bool,
+
+    /// Wrap the encoded lines after COLS characters, or never with 0.
+    #[arg(short, long, value_name = "COLS", allow_hyphen_values = true)]
+    wrap: Option<String>,
+
+    /// The file to read. With no FILE, or when FILE is -, standard input is read.
+    #[arg(value_name = "FILE")]
+    files: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "base32";
+}
+
+/// The description of base32 for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run base32 with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run base32 with the given command line, including the program name, on the given streams, and return
+/// the exit code.
+pub fn run(
+    args: &[OsString],
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(args.iter().cloned(), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let result = encoding::parse_wrap(args.wrap.as_deref())
+        .and_then(|wrap| encoding::process(&BASE32, args.decode, wrap, args.files, stdin, stdout));
+    Reporter::new("base32", stderr).finish(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::run_util_bytes;
+    use std::io;
+
+    #[test]
+    fn test_rfc_vectors() {
+        // The empty input, and every length of the last block.
+        for (data, encoding) in [
+            ("", ""),
+            ("f", "MY======\n"),
+            ("fo", "MZXQ====\n"),
+            ("foo", "MZXW6===\n"),
+            ("foob", "MZXW6YQ=\n"),
+            ("fooba", "MZXW6YTB\n"),
+            ("foobar", "MZXW6YTBOI======\n"),
+        ] {
+            assert_eq!(
+                run_util_bytes(run, &["base32"], data.as_bytes()),
+                (0, encoding.as_bytes().to_vec(), String::new()),
+                "{data:?}"
+            );
+            assert_eq!(
+                run_util_bytes(run, &["base32", "-d"], encoding.as_bytes()),
+                (0, data.as_bytes().to_vec(), String::new()),
+                "{data:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let data: Vec<u8> = (0..=255).cycle().take(100_000).collect();
+        let (code, encoded, _) = run_util_bytes(run, &["base32", "-w", "0"], &data);
+        assert_eq!(code, 0);
+        assert_eq!(encoded.len(), data.len().div_ceil(5) * 8);
+        assert_eq!(
+            run_util_bytes(run, &["base32", "-d"], &encoded),
+            (0, data, String::new())
+        );
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(
+            run_util_bytes(run, &["base32"], [0; 50]).1,
+            [&[b'A'; 76][..], b"\nAAAA\n"].concat()
+        );
+        assert_eq!(
+            run_util_bytes(run, &["base32", "-w", "5"], b"foob").1,
+            b"MZXW6\nYQ=\n"
+        );
+        assert_eq!(
+            run_util_bytes(run, &["base32", "--wrap=0"], b"foob").1,
+            b"MZXW6YQ="
+        );
+        assert_eq!(
+            run_util_bytes(run, &["base32", "-w", "x"], b""),
+            (1, Vec::new(), "base32: invalid wrap size: 'x'\n".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        for (input, stdout) in [
+            // Unlike some other implementations, GNU rejects lowercase letters.
+            (&b"mzxw6ytb"[..], &b""[..]),
+            (b"MZXW6YT*", b"foob"),
+            (b"MZXW6YTB====", b"fooba"),
+            (b"MZX=====", b"f"),
+            (b"MZXW6Y==", b"foo"),
+            (b"M=======", b""),
+            (b"MZXW6YTB1AAAAAAA", b"fooba"),
+            // A last block cut short is not decoded at all.
+            (b"MY", b""),
+            (b"MY=====", b""),
+            (b"MZXW6YTBOI", b"fooba"),
+        ] {
+            assert_eq!(
+                run_util_bytes(run, &["base32", "-d"], input),
+                (1, stdout.to_vec(), "base32: invalid input\n".to_owned()),
+                "{:?}",
+                input.escape_ascii().to_string()
+            );
+        }
+        assert_eq!(
+            run_util_bytes(run, &["base32", "-d"], b"MZ======MZ==\n====").1,
+            b"ff"
+        );
+    }
+
+    #[test]
+    fn test_operands() {
+        assert_eq!(
+            run_util_bytes(run, &["base32", "a", "b"], b""),
+            (
+                1,
+                Vec::new(),
+                "base32: extra operand 'b'\nTry 'base32 --help' for more information.\n".to_owned()
+            )
+        );
+        assert_eq!(
+            run_util_bytes(run, &["base32", "-d", "missing"], b""),
+            (
+                1,
+                Vec::new(),
+                "base32: missing: No such file or directory\n".to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn test_run_write_error() {
+        let args: Vec<OsString> = vec!["base32".into()];
+        let mut stdout = io::Cursor::new([0; 2]);
+        let mut stderr = Vec::new();
+        let code = run(&args, &mut &b"data"[..], &mut stdout, &mut stderr);
+        assert_eq!(code, 1);
+        assert_eq!(
+            stderr,
+            b"base32: write error: failed to write the buffered data\n"
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! Encode or decode base64, as described in RFC 4648.
+//!
+//! The data is streamed by [`common::encoding`](crate::common::encoding), so that inputs of any size are
+//! read in constant memory.
+
+use crate::common::encoding::{self, BASE64};
+use crate::common::error::Reporter;
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use clap::Parser;
+use std::ffi::OsString;
+use std::io::{Read, Write};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "base64",
+    author,
+    version,
+    about = "Encode FILE to base64, or decode it, to standard output.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Decode the data instead of encoding it. Newlines are ignored, and any other byte outside of the
+    /// alphabet is an error.
+    #[arg(short, long, default_value_t = false)]
+    decode: bool,
+
+    /// Wrap the encoded lines after COLS characters, or never with 0.
+    #[arg(short, long, value_name = "COLS", allow_hyphen_values = true)]
+    wrap: Option<String>,
+
+    /// The file to read. With no FILE, or when FILE is -, standard input is read.
+    #[arg(value_name = "FILE")]
+    files: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "base64";
+}
+
+/// The description of base64 for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run base64 with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run base64 with the given command line, including the program name, on the given streams, and return
+/// the exit code.
+pub fn run(
+    args: &[OsString],
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(args.iter().cloned(), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let result = encoding::parse_wrap(args.wrap.as_deref())
+        .and_then(|wrap| encoding::process(&BASE64, args.decode, wrap, args.files, stdin, stdout));
+    Reporter::new("base64", stderr).finish(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::encoding::DEFAULT_WRAP;
+    use crate::common::error::UResult;
+    use crate::common::testing::run_util_bytes;
+    use std::io;
+
+    fn encoded(data: &[u8], wrap: usize) -> Vec<u8> {
+        let mut output = Vec::new();
+        encoding::encode(&BASE64, &mut &data[..], wrap, &mut output).unwrap();
+        output
+    }
+
+    fn decoded(data: &[u8]) -> UResult<Vec<u8>> {
+        let mut output = Vec::new();
+        encoding::decode(&BASE64, &mut &data[..], &mut output).map(|()| output)
+    }
+
+    #[test]
+    fn test_rfc_vectors() {
+        for (data, encoding) in [
+            ("", ""),
+            ("f", "Zg==\n"),
+            ("fo", "Zm8=\n"),
+            ("foo", "Zm9v\n"),
+            ("foob", "Zm9vYg==\n"),
+            ("fooba", "Zm9vYmE=\n"),
+            ("foobar", "Zm9vYmFy\n"),
+        ] {
+            assert_eq!(
+                encoded(data.as_bytes(), DEFAULT_WRAP),
+                encoding.as_bytes(),
+                "{data:?}"
+            );
+            assert_eq!(
+                decoded(encoding.as_bytes()).unwrap(),
+                data.as_bytes(),
+                "{data:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(encoded(b"hello world", 0), b"aGVsbG8gd29ybGQ=");
+        assert_eq!(encoded(b"hello world", 5), b"aGVsb\nG8gd2\n9ybGQ\n=\n");
+        // The last line is not followed by an empty one.
+        assert_eq!(encoded(b"hello", 4), b"aGVs\nbG8=\n");
+        assert_eq!(
+            encoded(&[0; 57], DEFAULT_WRAP),
+            [&[b'A'; 76][..], b"\n"].concat()
+        );
+        assert_eq!(
+            run_util_bytes(run, &["base64", "-w", "3"], b"ab").1,
+            b"YWI\n=\n"
+        );
+        assert_eq!(
+            run_util_bytes(run, &["base64", "--wrap=99999999999999999999"], b"x").1,
+            b"eA=="
+        );
+        for wrap in ["x", "-1", ""] {
+            assert_eq!(
+                run_util_bytes(run, &["base64", "-w", wrap], b"x"),
+                (
+                    1,
+                    Vec::new(),
+                    format!("base64: invalid wrap size: '{wrap}'\n")
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode() {
+        // Newlines are ignored anywhere, and every block may be padded.
+        assert_eq!(decoded(b"YW\nJj\n\n").unwrap(), b"abc");
+        assert_eq!(decoded(b"YQ\n==").unwrap(), b"a");
+        assert_eq!(decoded(b"YQ==YQ==").unwrap(), b"aa");
+        assert_eq!(decoded(b"YWI=\nYQ==").unwrap(), b"aba");
+        // The bits after the last byte are ignored.
+        assert_eq!(decoded(b"YR==").unwrap(), b"a");
+        assert_eq!(decoded(b"\n").unwrap(), b"");
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        for (input, stdout) in [
+            (&b"YWJj*"[..], &b"abc"[..]),
+            (b"*YWJj", b""),
+            (b"YW*Jj", b"a"),
+            (b"YWJj\r\n", b"abc"),
+            (b"Y Q==", b""),
+            // The last block is cut short, or wrongly padded.
+            (b"YQ", b"a"),
+            (b"YQ=", b"a"),
+            (b"YWJjZA", b"abcd"),
+            (b"YWJjZ", b"abc"),
+            (b"YQ=a", b"a"),
+            (b"Y===", b""),
+            (b"=", b""),
+        ] {
+            // The data before the error is still written.
+            assert_eq!(
+                run_util_bytes(run, &["base64", "-d"], input),
+                (1, stdout.to_vec(), "base64: invalid input\n".to_owned()),
+                "{:?}",
+                input.escape_ascii().to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn test_operands() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("file");
+        std::fs::write(&path, "foobar").unwrap();
+        let path = path.to_str().unwrap();
+        assert_eq!(run_util_bytes(run, &["base64", path], b"").1, b"Zm9vYmFy\n");
+        assert_eq!(run_util_bytes(run, &["base64", "-"], b"f").1, b"Zg==\n");
+        assert_eq!(
+            run_util_bytes(run, &["base64", path, "-"], b""),
+            (
+                1,
+                Vec::new(),
+                "base64: extra operand '-'\nTry 'base64 --help' for more information.\n".to_owned()
+            )
+        );
+        assert_eq!(
+            run_util_bytes(run, &["base64", "missing"], b""),
+            (
+                1,
+                Vec::new(),
+                "base64: missing: No such file or directory\n".to_owned()
+            )
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_error() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().to_str().unwrap();
+        for decode in [false, true] {
+            let command_line: &[&str] = if decode {
+                &["base64", "-d", path]
+            } else {
+                &["base64", path]
+            };
+            assert_eq!(
+                run_util_bytes(run, command_line, b""),
+                (
+                    1,
+                    Vec::new(),
+                    "base64: read error: Is a directory\n".to_owned()
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_run_write_error() {
+        let args: Vec<OsString> = vec!["base64".into()];
+        let mut stdout = io::Cursor::new([0; 2]);
+        let mut stderr = Vec::new();
+        let code = run(&args, &mut &b"data"[..], &mut stdout, &mut stderr);
+        assert_eq!(code, 1);
+        assert_eq!(
+            stderr,
+            b"base64: write error: failed to write the buffered data\n"
+        );
+    }
+}
@@ -0,0 +1,359 @@
+//! Encode or decode data in one of the encodings of RFC 4648, in bits, or in Z85.
+//!
+//! The data is streamed by [`common::encoding`](crate::common::encoding), so that inputs of any size are
+//! read in constant memory.
+
+use crate::common::encoding::{
+    self, Encoding, BASE16, BASE2LSBF, BASE2MSBF, BASE32, BASE32HEX, BASE64, BASE64URL, Z85,
+};
+use crate::common::error::{Reporter, UError, EXIT_FAILURE};
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use clap::Parser;
+use std::ffi::OsString;
+use std::io::{Read, Write};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "basenc",
+    author,
+    version,
+    about = "Encode FILE in the given encoding, or decode it, to standard output.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Use the standard base64 alphabet, like base64.
+    #[arg(long, default_value_t = false, overrides_with_all = ENCODINGS)]
+    base64: bool,
+
+    /// Use the URL and filename safe base64 alphabet, with - and _ instead of + and /.
+    #[arg(long, default_value_t = false, overrides_with_all = ENCODINGS)]
+    base64url: bool,
+
+    /// Use the standard base32 alphabet, like base32.
+    #[arg(long, default_value_t = false, overrides_with_all = ENCODINGS)]
+    base32: bool,
+
+    /// Use the base32 alphabet of the extended hexadecimal digits.
+    #[arg(long, default_value_t = false, overrides_with_all = ENCODINGS)]
+    base32hex: bool,
+
+    /// Use uppercase hexadecimal digits.
+    #[arg(long, default_value_t = false, overrides_with_all = ENCODINGS)]
+    base16: bool,
+
+    /// Write every byte as 8 bits, from the most significant one.
+    #[arg(long, default_value_t = false, overrides_with_all = ENCODINGS)]
+    base2msbf: bool,
+
+    /// Write every byte as 8 bits, from the least significant one.
+    #[arg(long, default_value_t = false, overrides_with_all = ENCODINGS)]
+    base2lsbf: bool,
+
+    /// Use the Z85 encoding of ZeroMQ, in which the length of the data must be a multiple of 4 bytes, and the
+    /// length of the encoded data a multiple of 5 characters.
+    #[arg(long, default_value_t = false, overrides_with_all = ENCODINGS)]
+    z85: bool,
+
+    /// Decode the data instead of encoding it. Newlines are ignored, and any other byte outside of the
+    /// alphabet is an error.
+    #[arg(short, long, default_value_t = false)]
+    decode: bool,
+
+    /// Wrap the encoded lines after COLS characters, or never with 0.
+    #[arg(short, long, value_name = "COLS", allow_hyphen_values = true)]
+    wrap: Option<String>,
+
+    /// The file to read. With no FILE, or when FILE is -, standard input is read.
+    #[arg(value_name = "FILE")]
+    files: Vec<OsString>,
+}
+
+/// The options selecting the encoding, of which the last one is used.
+const ENCODINGS: [&str; 8] = [
+    "base64",
+    "base64url",
+    "base32",
+    "base32hex",
+    "base16",
+    "base2msbf",
+    "base2lsbf",
+    "z85",
+];
+
+impl Args {
+    fn encoding(&self) -> Option<&'static Encoding> {
+        [
+            (self.base64, &BASE64),
+            (self.base64url, &BASE64URL),
+            (self.base32, &BASE32),
+            (self.base32hex, &BASE32HEX),
+            (self.base16, &BASE16),
+            (self.base2msbf, &BASE2MSBF),
+            (self.base2lsbf, &BASE2LSBF),
+            (self.z85, &Z85),
+        ]
+        .into_iter()
+        .find_map(|(selected, encoding)| selected.then_some(encoding))
+    }
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "basenc";
+}
+
+/// The description of basenc for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run basenc with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run basenc with the given command line, including the program name, on the given streams, and return
+/// the exit code.
+pub fn run(
+    args: &[OsString],
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(args.iter().cloned(), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let result =
+        encoding::parse_wrap(args.wrap.as_deref()).and_then(|wrap| match args.encoding() {
+            Some(encoding) => {
+                encoding::process(encoding, args.decode, wrap, args.files, stdin, stdout)
+            }
+            None => Err(UError::usage(EXIT_FAILURE, "missing encoding type")),
+        });
+    Reporter::new("basenc", stderr).finish(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::run_util_bytes;
+    use std::io;
+
+    /// Get pseudo-random bytes, the same ones for every run.
+    fn random_bytes(length: usize) -> Vec<u8> {
+        let mut state = 0x9e37_79b9_7f4a_7c15_u64;
+        (0..length)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 32) as u8
+            })
+            .collect()
+    }
+
+    fn assert_vectors(option: &str, vectors: &[(&[u8], &str)]) {
+        for &(data, encoding) in vectors {
+            assert_eq!(
+                run_util_bytes(run, &["basenc", option], data),
+                (0, encoding.as_bytes().to_vec(), String::new()),
+                "{option} {data:?}"
+            );
+            assert_eq!(
+                run_util_bytes(run, &["basenc", option, "-d"], encoding.as_bytes()),
+                (0, data.to_vec(), String::new()),
+                "{option} {encoding:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_rfc_vectors() {
+        assert_vectors(
+            "--base32hex",
+            &[
+                (b"", ""),
+                (b"f", "CO======\n"),
+                (b"fo", "CPNG====\n"),
+                (b"foo", "CPNMU===\n"),
+                (b"foob", "CPNMUOG=\n"),
+                (b"fooba", "CPNMUOJ1\n"),
+                (b"foobar", "CPNMUOJ1E8======\n"),
+            ],
+        );
+        assert_vectors("--base16", &[(b"", ""), (b"foobar", "666F6F626172\n")]);
+        assert_vectors(
+            "--base64url",
+            &[(b"\xfb\xff", "-_8=\n"), (b"foob", "Zm9vYg==\n")],
+        );
+        assert_vectors("--base64", &[(b"\xfb\xff", "+/8=\n")]);
+        assert_vectors("--base32", &[(b"foob", "MZXW6YQ=\n")]);
+    }
+
+    #[test]
+    fn test_z85() {
+        // The example of the specification.
+        assert_vectors(
+            "--z85",
+            &[
+                (b"", ""),
+                (b"\x86\x4f\xd2\x6f\xb5\x59\xf7\x5b", "HelloWorld\n"),
+                (b"\xff\xff\xff\xff", "%nSc0\n"),
+            ],
+        );
+        assert_eq!(
+            run_util_bytes(
+                run,
+                &["basenc", "--z85", "-w", "4"],
+                b"\x86\x4f\xd2\x6f\xb5\x59\xf7\x5b"
+            )
+            .1,
+            b"Hell\noWor\nld\n"
+        );
+        for data in [&b"abc"[..], b"abcdefg"] {
+            assert_eq!(
+                run_util_bytes(run, &["basenc", "--z85"], data),
+                (
+                    1,
+                    Vec::new(),
+                    "basenc: invalid input (length must be multiple of 4 characters)\n".to_owned()
+                )
+            );
+        }
+        for (input, stdout) in [
+            // A last block cut short, an invalid character, or a number too large for 4 bytes.
+            (&b"HelloWorl"[..], &b"\x86\x4f\xd2\x6f"[..]),
+            (b"Hello\nWor~d", b"\x86\x4f\xd2\x6f"),
+            (b"%nSc1", b""),
+            (b"#####", b""),
+        ] {
+            assert_eq!(
+                run_util_bytes(run, &["basenc", "--z85", "-d"], input),
+                (1, stdout.to_vec(), "basenc: invalid input\n".to_owned())
+            );
+        }
+    }
+
+    #[test]
+    fn test_base2() {
+        assert_vectors(
+            "--base2msbf",
+            &[(b"\x01\x80a", "000000011000000001100001\n")],
+        );
+        // The bits of every byte are reversed.
+        assert_vectors(
+            "--base2lsbf",
+            &[(b"\x01\x80a", "100000000000000110000110\n")],
+        );
+        assert_eq!(
+            run_util_bytes(run, &["basenc", "--base2msbf", "-w", "6"], b"a").1,
+            b"011000\n01\n"
+        );
+        for (input, stdout) in [
+            (&b"0110011"[..], &b""[..]),
+            (b"01100110=", b"f"),
+            (b"0110011a", b""),
+        ] {
+            assert_eq!(
+                run_util_bytes(run, &["basenc", "--base2msbf", "-d"], input),
+                (1, stdout.to_vec(), "basenc: invalid input\n".to_owned())
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        for (option, input, stdout) in [
+            // Like GNU, lowercase hexadecimal digits are invalid.
+            ("--base16", &b"666f"[..], &b"f"[..]),
+            ("--base16", b"666", b"f"),
+            ("--base16", b"66==", b"f"),
+            ("--base64url", b"+/8=", b""),
+            ("--base64url", b"Y-_", b"c\xef"),
+            ("--base32hex", b"MZXW6YQ=", b""),
+            ("--base32hex", b"CO", b""),
+        ] {
+            assert_eq!(
+                run_util_bytes(run, &["basenc", option, "-d"], input),
+                (1, stdout.to_vec(), "basenc: invalid input\n".to_owned()),
+                "{option} {input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let data = random_bytes(100_000);
+        for option in ENCODINGS {
+            let option = format!("--{option}");
+            let (code, encoded, _) = run_util_bytes(run, &["basenc", &option], &data);
+            assert_eq!(code, 0, "{option}");
+            assert_eq!(
+                run_util_bytes(run, &["basenc", &option, "-d"], &encoded),
+                (0, data.clone(), String::new()),
+                "{option}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_encoding_options() {
+        assert_eq!(
+            run_util_bytes(run, &["basenc"], b"data"),
+            (
+                1,
+                Vec::new(),
+                "basenc: missing encoding type\nTry 'basenc --help' for more information.\n"
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            run_util_bytes(run, &["basenc", "-d", "a", "b"], b""),
+            (
+                1,
+                Vec::new(),
+                "basenc: missing encoding type\nTry 'basenc --help' for more information.\n"
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            run_util_bytes(run, &["basenc", "-w", "x"], b""),
+            (1, Vec::new(), "basenc: invalid wrap size: 'x'\n".to_owned())
+        );
+        // The last encoding is used.
+        assert_eq!(
+            run_util_bytes(run, &["basenc", "--base64", "--base16"], b"hi").1,
+            b"6869\n"
+        );
+        assert_eq!(
+            run_util_bytes(
+                run,
+                &["basenc", "--base16", "--base64", "--base16", "--z85"],
+                b"abcd"
+            )
+            .1,
+            b"vpA.S\n"
+        );
+    }
+
+    #[test]
+    fn test_run_write_error() {
+        let args: Vec<OsString> = vec!["basenc".into(), "--base16".into()];
+        let mut stdout = io::Cursor::new([0; 2]);
+        let mut stderr = Vec::new();
+        let code = run(&args, &mut &b"data"[..], &mut stdout, &mut stderr);
+        assert_eq!(code, 1);
+        assert_eq!(
+            stderr,
+            b"basenc: write error: failed to write the buffered data\n"
+        );
+    }
+}
@@ -0,0 +1,4 @@
+fn main() {
+    rust_coreutils::common::pipes::init();
+    std::process::exit(rust_coreutils::base64::uumain(std::env::args_os()));
+}
@@ -0,0 +1,680 @@
+//! Concatenate files to standard output.
+//!
+//! The inputs are copied with [`cat`], so that they can be read from anywhere and written anywhere:
+//!
+//! ```
+//! use rust_coreutils::cat::{cat, CatOptions, Numbering};
+//! use rust_coreutils::common::error::Reporter;
+//! use rust_coreutils::common::input::InputIter;
+//!
+//! let mut inputs = InputIter::with_stdin(vec!["-".into(), "-".into()], Box::new(&b"data\n\nmore\n"[..]));
+//! let options = CatOptions {
+//!     number: Numbering::NonBlank,
+//!     ..CatOptions::default()
+//! };
+//! let mut stdout = Vec::new();
+//! let mut reporter = Reporter::new("cat", Vec::new());
+//! cat(&mut inputs, &options, &mut stdout, &mut reporter).unwrap();
+//! assert_eq!(stdout, b"     1\tdata\n\n     2\tmore\n");
+//! ```
+
+use crate::common::error::{Reporter, UError, UResult};
+use crate::common::input::{InputIter, BUFFER_SIZE};
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use clap::Parser;
+use std::ffi::OsString;
+use std::io::{self, BufReader, ErrorKind, Read, Write};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "cat",
+    author,
+    version,
+    about = "Concatenate files to standard output.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Number all output lines.
+    #[arg(short = 'n', long, default_value_t = false)]
+    number: bool,
+
+    /// Number nonempty output lines, overriding -n.
+    #[arg(short = 'b', long, default_value_t = false)]
+    number_nonblank: bool,
+
+    /// Suppress repeated empty output lines.
+    #[arg(short, long, default_value_t = false)]
+    squeeze_blank: bool,
+
+    /// Equivalent to -vET.
+    #[arg(short = 'A', long, default_value_t = false)]
+    show_all: bool,
+
+    /// Equivalent to -vE.
+    #[arg(short = 'e', default_value_t = false)]
+    e: bool,
+
+    /// Display $ at the end of each line.
+    #[arg(short = 'E', long, default_value_t = false)]
+    show_ends: bool,
+
+    /// Equivalent to -vT.
+    #[arg(short = 't', default_value_t = false)]
+    t: bool,
+
+    /// Display tabs as ^I.
+    #[arg(short = 'T', long, default_value_t = false)]
+    show_tabs: bool,
+
+    /// Use ^ and M- notation, except for newlines and tabs.
+    #[arg(short = 'v', long, default_value_t = false)]
+    show_nonprinting: bool,
+
+    /// (ignored; the output is never buffered longer than a read)
+    #[arg(short = 'u', default_value_t = false)]
+    u: bool,
+
+    /// The files to concatenate. With no FILE, or when FILE is -, standard input is read.
+    #[arg(value_name = "FILE")]
+    files: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "cat";
+}
+
+/// The description of cat for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run cat with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run cat with the given command line, including the program name, on the given streams, and return the
+/// exit code.
+pub fn run(
+    args: &[OsString],
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(args.iter().cloned(), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let options = CatOptions {
+        number: if args.number_nonblank {
+            Numbering::NonBlank
+        } else if args.number {
+            Numbering::All
+        } else {
+            Numbering::Off
+        },
+        squeeze_blank: args.squeeze_blank,
+        show_ends: args.show_ends || args.show_all || args.e,
+        show_tabs: args.show_tabs || args.show_all || args.t,
+        show_nonprinting: args.show_nonprinting || args.show_all || args.e || args.t,
+    };
+    let mut inputs = InputIter::with_stdin(
+        args.files,
+        Box::new(BufReader::with_capacity(BUFFER_SIZE, stdin)),
+    );
+    let mut reporter = Reporter::new("cat", stderr);
+    let result = cat(&mut inputs, &options, stdout, &mut reporter);
+    reporter.finish(result)
+}
+
+/// Which lines to number.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Numbering {
+    /// Do not number lines.
+    #[default]
+    Off,
+    /// Number the lines which are not empty.
+    NonBlank,
+    /// Number all lines.
+    All,
+}
+
+/// The behaviour of cat. The default copies the inputs unchanged.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CatOptions {
+    /// Which lines to prefix with their number.
+    pub number: Numbering,
+    /// Print a single empty line for each run of adjacent empty lines.
+    pub squeeze_blank: bool,
+    /// Print `$` at the end of each line. Unlike recent versions of GNU cat, a carriage return before it is
+    /// only shown as `^M` with [`show_nonprinting`](Self::show_nonprinting).
+    pub show_ends: bool,
+    /// Print tabs as `^I`.
+    pub show_tabs: bool,
+    /// Print the bytes which are not printable ASCII, other than tabs and newlines, in `^` and `M-` notation.
+    pub show_nonprinting: bool,
+}
+
+impl CatOptions {
+    /// Check whether the inputs are copied unchanged, so that they do not need to be scanned for lines.
+    fn is_plain(&self) -> bool {
+        *self == CatOptions::default()
+    }
+}
+
+/// Copy the inputs to `stdout` in order, transformed according to `options`. The inputs form a single
+/// stream: lines are numbered across them, and an input which does not end with a newline continues its
+/// last line into the next one.
+///
+/// An input which cannot be opened or read is reported, and the remaining ones are still copied. A failure
+/// to write is returned, as there is no point in going on. A closed pipe stops the copy without an error.
+pub fn cat<W: Write>(
+    inputs: &mut InputIter,
+    options: &CatOptions,
+    stdout: &mut dyn Write,
+    reporter: &mut Reporter<W>,
+) -> UResult<()> {
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut lines = (!options.is_plain()).then(|| LineFormatter::new(options));
+    while let Some(input) = inputs.next_raw() {
+        let mut input = match input {
+            Ok(input) => input,
+            Err(error) => {
+                reporter.io_error(error.display_name(), &error.error);
+                continue;
+            }
+        };
+        match copy(&mut input.reader, stdout, &mut buffer, lines.as_mut()) {
+            Ok(()) => {}
+            Err(Failure::Read(error)) => reporter.io_error(input.display_name(), &error),
+            Err(Failure::Write(error)) => return UError::write(error),
+        }
+    }
+    stdout.flush().or_else(UError::write)
+}
+
+/// Why copying an input stopped.
+enum Failure {
+    Read(io::Error),
+    Write(io::Error),
+}
+
+/// Copy one input to the output through `buffer`, formatting its lines with `lines` if given. Each read is
+/// written as soon as it is done, so that interactive input is passed on a line at a time.
+fn copy(
+    reader: &mut dyn Read,
+    stdout: &mut dyn Write,
+    buffer: &mut [u8],
+    mut lines: Option<&mut LineFormatter>,
+) -> Result<(), Failure> {
+    loop {
+        let n = match reader.read(buffer) {
+            Ok(0) => return Ok(()),
+            Ok(n) => n,
+            Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+            Err(error) => return Err(Failure::Read(error)),
+        };
+        let output = match lines.as_deref_mut() {
+            Some(lines) => lines.format(&buffer[..n]),
+            None => &buffer[..n],
+        };
+        stdout.write_all(output).map_err(Failure::Write)?;
+    }
+}
+
+/// Formats the lines of the inputs, which can be split anywhere across reads and inputs.
+struct LineFormatter {
+    options: CatOptions,
+    /// The number of the last numbered line.
+    line: u64,
+    /// Whether the next byte starts a line.
+    at_line_start: bool,
+    /// The number of empty lines just seen.
+    blank_lines: u64,
+    output: Vec<u8>,
+}
+
+impl LineFormatter {
+    fn new(options: &CatOptions) -> Self {
+        LineFormatter {
+            options: options.clone(),
+            line: 0,
+            at_line_start: true,
+            blank_lines: 0,
+            output: Vec::with_capacity(BUFFER_SIZE),
+        }
+    }
+
+    /// Format the next piece of the stream, returning the output for it.
+    fn format(&mut self, mut data: &[u8]) -> &[u8] {
+        self.output.clear();
+        while let Some(&first) = data.first() {
+            if self.at_line_start {
+                if first == b'\n' {
+                    self.blank_lines += 1;
+                    data = &data[1..];
+                    if self.options.squeeze_blank && self.blank_lines > 1 {
+                        continue;
+                    }
+                    if self.options.number == Numbering::All {
+                        self.write_number();
+                    }
+                    self.end_line();
+                    continue;
+                }
+                self.blank_lines = 0;
+                self.at_line_start = false;
+                if self.options.number != Numbering::Off {
+                    self.write_number();
+                }
+            }
+
+            match data.iter().position(|&byte| byte == b'\n') {
+                Some(newline) => {
+                    self.write_content(&data[..newline]);
+                    self.end_line();
+                    self.at_line_start = true;
+                    data = &data[newline + 1..];
+                }
+                None => {
+                    self.write_content(data);
+                    data = &[];
+                }
+            }
+        }
+        &self.output
+    }
+
+    /// Write the number of the next line, right-aligned in six columns like GNU, and a tab.
+    fn write_number(&mut self) {
+        self.line += 1;
+        // Writing to a vector cannot fail.
+        let _ = write!(self.output, "{:>6}\t", self.line);
+    }
+
+    /// Write a piece of a line, without newlines.
+    fn write_content(&mut self, content: &[u8]) {
+        let CatOptions {
+            show_tabs,
+            show_nonprinting,
+            ..
+        } = self.options;
+        if !show_tabs && !show_nonprinting {
+            self.output.extend_from_slice(content);
+            return;
+        }
+        for &byte in content {
+            match byte {
+                b'\t' if show_tabs => self.output.extend_from_slice(b"^I"),
+                b'\t' => self.output.push(byte),
+                _ if show_nonprinting => push_visible(&mut self.output, byte),
+                _ => self.output.push(byte),
+            }
+        }
+    }
+
+    fn end_line(&mut self) {
+        if self.options.show_ends {
+            self.output.push(b'$');
+        }
+        self.output.push(b'\n');
+    }
+}
+
+/// Write a byte in the notation of `cat -v`: control characters as `^` and the character 64 positions
+/// later, like `^@` for a null byte, delete as `^?`, and bytes with the high bit set as `M-` and the notation
+/// of the byte without it, like `M-^I`.
+fn push_visible(output: &mut Vec<u8>, byte: u8) {
+    let byte = if byte >= 0x80 {
+        output.extend_from_slice(b"M-");
+        byte - 0x80
+    } else {
+        byte
+    };
+    match byte {
+        0x00..0x20 => output.extend_from_slice(&[b'^', byte + 0x40]),
+        0x7f => output.extend_from_slice(b"^?"),
+        _ => output.push(byte),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::{run_util_bytes, Fixture};
+    use assert_cmd::Command;
+    use std::fs;
+
+    /// The files `a`, with two lines, and `b`, without a final newline.
+    fn fixture() -> Fixture {
+        Fixture::new(&[("a", "first\nfile\n"), ("b", "second file")])
+    }
+
+    #[test]
+    fn test_files() {
+        let fixture = fixture();
+        let (a, b) = (&fixture.path("a"), &fixture.path("b"));
+        assert_eq!(
+            run_util_bytes(run, &["cat", a, b], b"unused"),
+            (0, b"first\nfile\nsecond file".to_vec(), String::new())
+        );
+        assert_eq!(
+            run_util_bytes(run, &["cat", b, a, b], b""),
+            (
+                0,
+                b"second filefirst\nfile\nsecond file".to_vec(),
+                String::new()
+            )
+        );
+    }
+
+    #[test]
+    fn test_stdin() {
+        assert_eq!(
+            run_util_bytes(run, &["cat"], b"from\nstdin"),
+            (0, b"from\nstdin".to_vec(), String::new())
+        );
+
+        let fixture = fixture();
+        let (a, b) = (&fixture.path("a"), &fixture.path("b"));
+        assert_eq!(
+            run_util_bytes(run, &["cat", a, "-", b], b"stdin\n"),
+            (
+                0,
+                b"first\nfile\nstdin\nsecond file".to_vec(),
+                String::new()
+            )
+        );
+        // Standard input is exhausted after the first `-`.
+        assert_eq!(
+            run_util_bytes(run, &["cat", "-", a, "-"], b"stdin\n"),
+            (0, b"stdin\nfirst\nfile\n".to_vec(), String::new())
+        );
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let fixture = fixture();
+        let missing = &fixture.path("missing");
+        let (code, stdout, stderr) = run_util_bytes(
+            run,
+            &["cat", &fixture.path("a"), missing, &fixture.path("b")],
+            b"",
+        );
+        assert_eq!(code, 1);
+        assert_eq!(stdout, b"first\nfile\nsecond file");
+        assert_eq!(
+            stderr,
+            format!("cat: {missing}: No such file or directory\n")
+        );
+    }
+
+    #[test]
+    fn test_directory() {
+        let fixture = fixture();
+        let directory = &fixture.path("");
+        let (code, stdout, stderr) =
+            run_util_bytes(run, &["cat", directory, &fixture.path("a")], b"");
+        assert_eq!(code, 1);
+        assert_eq!(stdout, b"first\nfile\n");
+        assert!(stderr.starts_with(&format!("cat: {directory}: ")));
+    }
+
+    #[test]
+    fn test_binary_data() {
+        let data: Vec<u8> = (0..3 * BUFFER_SIZE + 1).map(|i| (i % 251) as u8).collect();
+        let fixture = fixture();
+        let path = &fixture.path("binary");
+        fs::write(path, &data).unwrap();
+
+        let (code, stdout, stderr) = run_util_bytes(run, &["cat", path, "-"], b"\0\xff\0");
+        assert_eq!((code, stderr.as_str()), (0, ""));
+        assert_eq!(stdout.len(), data.len() + 3);
+        assert!(stdout[..data.len()] == data[..]);
+        assert_eq!(&stdout[data.len()..], b"\0\xff\0");
+    }
+
+    #[test]
+    fn test_number() {
+        assert_eq!(
+            run_util_bytes(run, &["cat", "-n"], b"a\n\nb"),
+            (0, b"     1\ta\n     2\t\n     3\tb".to_vec(), String::new())
+        );
+        assert_eq!(
+            run_util_bytes(run, &["cat", "-b"], b"a\n\nb"),
+            (0, b"     1\ta\n\n     2\tb".to_vec(), String::new())
+        );
+        // -b overrides -n, whatever their order.
+        for args in [["cat", "-n", "-b"], ["cat", "-b", "-n"]] {
+            assert_eq!(run_util_bytes(run, &args, b"\na\n").1, b"\n     1\ta\n");
+        }
+        assert_eq!(run_util_bytes(run, &["cat", "-n"], b"").1, b"");
+
+        let lines: Vec<u8> = (0..1_000_000).flat_map(|_| *b"\n").collect();
+        let (_, stdout, _) = run_util_bytes(run, &["cat", "-n"], &lines);
+        assert!(stdout.ends_with(b"999999\t\n1000000\t\n"));
+    }
+
+    #[test]
+    fn test_number_across_files() {
+        let fixture = fixture();
+        let (a, b) = (&fixture.path("a"), &fixture.path("b"));
+        // The file without a final newline continues its last line into the next file.
+        assert_eq!(
+            run_util_bytes(run, &["cat", "-n", a, b, a], b"").1,
+            b"     1\tfirst\n     2\tfile\n     3\tsecond filefirst\n     4\tfile\n"
+        );
+        assert_eq!(
+            run_util_bytes(run, &["cat", "-b", b, "-"], b"\n\nstdin\n").1,
+            b"     1\tsecond file\n\n     2\tstdin\n"
+        );
+    }
+
+    #[test]
+    fn test_squeeze_blank() {
+        assert_eq!(
+            run_util_bytes(run, &["cat", "-s"], b"\n\n\na\n\n\n\nb\n\n"),
+            (0, b"\na\n\nb\n\n".to_vec(), String::new())
+        );
+        // Lines with only spaces are not empty.
+        assert_eq!(
+            run_util_bytes(run, &["cat", "-s"], b"a\n \n \n").1,
+            b"a\n \n \n"
+        );
+        assert_eq!(
+            run_util_bytes(run, &["cat", "-sn"], b"a\n\n\nb\n").1,
+            b"     1\ta\n     2\t\n     3\tb\n"
+        );
+
+        let fixture = fixture();
+        let (blank_end, blank_start) = (&fixture.path("blank_end"), &fixture.path("blank_start"));
+        fs::write(blank_end, "x\n\n\n").unwrap();
+        fs::write(blank_start, "\n\ny\n").unwrap();
+        assert_eq!(
+            run_util_bytes(run, &["cat", "-s", blank_end, blank_start], b"").1,
+            b"x\n\ny\n"
+        );
+        assert_eq!(
+            run_util_bytes(run, &["cat", "-sb", blank_end, "-", blank_start], b"\n").1,
+            b"     1\tx\n\n     2\ty\n"
+        );
+    }
+
+    /// The output of GNU `cat -v` for every byte from 0 to 255.
+    const GOLDEN_V: &str = concat!(
+        "^@^A^B^C^D^E^F^G^H\t\n^K^L^M^N^O^P^Q^R^S^T^U^V^W^X^Y^Z^[^\\^]^^^_ !",
+        r##""#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\]^_`a"##,
+        r##"bcdefghijklmnopqrstuvwxyz{|}~^?M-^@M-^AM-^BM-^CM-^DM-^EM-^FM-^GM"##,
+        r##"-^HM-^IM-^JM-^KM-^LM-^MM-^NM-^OM-^PM-^QM-^RM-^SM-^TM-^UM-^VM-^WM"##,
+        r##"-^XM-^YM-^ZM-^[M-^\M-^]M-^^M-^_M- M-!M-"M-#M-$M-%M-&M-'M-(M-)M-*"##,
+        r##"M-+M-,M--M-.M-/M-0M-1M-2M-3M-4M-5M-6M-7M-8M-9M-:M-;M-<M-=M->M-?M"##,
+        r##"-@M-AM-BM-CM-DM-EM-FM-GM-HM-IM-JM-KM-LM-MM-NM-OM-PM-QM-RM-SM-TM-"##,
+        r##"UM-VM-WM-XM-YM-ZM-[M-\M-]M-^M-_M-`M-aM-bM-cM-dM-eM-fM-gM-hM-iM-j"##,
+        r##"M-kM-lM-mM-nM-oM-pM-qM-rM-sM-tM-uM-vM-wM-xM-yM-zM-{M-|M-}M-~M-^?"##,
+    );
+
+    /// The output of GNU `cat -A` for every byte from 0 to 255.
+    const GOLDEN_A: &str = concat!(
+        "^@^A^B^C^D^E^F^G^H^I$\n^K^L^M^N^O^P^Q^R^S^T^U^V^W^X^Y^Z^[^\\^]^^^_",
+        r##" !"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\]^_"##,
+        r##"`abcdefghijklmnopqrstuvwxyz{|}~^?M-^@M-^AM-^BM-^CM-^DM-^EM-^FM-^"##,
+        r##"GM-^HM-^IM-^JM-^KM-^LM-^MM-^NM-^OM-^PM-^QM-^RM-^SM-^TM-^UM-^VM-^"##,
+        r##"WM-^XM-^YM-^ZM-^[M-^\M-^]M-^^M-^_M- M-!M-"M-#M-$M-%M-&M-'M-(M-)M"##,
+        r##"-*M-+M-,M--M-.M-/M-0M-1M-2M-3M-4M-5M-6M-7M-8M-9M-:M-;M-<M-=M->M-"##,
+        r##"?M-@M-AM-BM-CM-DM-EM-FM-GM-HM-IM-JM-KM-LM-MM-NM-OM-PM-QM-RM-SM-T"##,
+        r##"M-UM-VM-WM-XM-YM-ZM-[M-\M-]M-^M-_M-`M-aM-bM-cM-dM-eM-fM-gM-hM-iM"##,
+        r##"-jM-kM-lM-mM-nM-oM-pM-qM-rM-sM-tM-uM-vM-wM-xM-yM-zM-{M-|M-}M-~M-"##,
+        r##"^?"##,
+    );
+
+    #[test]
+    fn test_show_nonprinting_every_byte() {
+        let input: Vec<u8> = (0..=255).collect();
+        let output =
+            |args: &[&str]| String::from_utf8(run_util_bytes(run, args, &input).1).unwrap();
+        assert_eq!(output(&["cat", "-v"]), GOLDEN_V);
+        assert_eq!(output(&["cat", "-A"]), GOLDEN_A);
+        assert_eq!(output(&["cat", "-vET"]), GOLDEN_A);
+        assert_eq!(output(&["cat", "-e"]), GOLDEN_V.replace('\n', "$\n"));
+        assert_eq!(output(&["cat", "-t"]), GOLDEN_V.replace('\t', "^I"));
+        // Without -v, only tabs and line ends are shown.
+        let mut expected = input.clone();
+        expected.splice(9..11, *b"^I$\n");
+        assert_eq!(run_util_bytes(run, &["cat", "-ET"], &input).1, expected);
+    }
+
+    #[test]
+    fn test_show_ends_and_tabs() {
+        assert_eq!(
+            run_util_bytes(run, &["cat", "-E"], b"a\tb\r\n\nc").1,
+            b"a\tb\r$\n$\nc"
+        );
+        // The carriage return is only shown with -v.
+        assert_eq!(
+            run_util_bytes(run, &["cat", "-A"], b"a\tb\r\n\nc").1,
+            b"a^Ib^M$\n$\nc"
+        );
+        assert_eq!(
+            run_util_bytes(run, &["cat", "-T"], b"\t\ta\t\n").1,
+            b"^I^Ia^I\n"
+        );
+        assert_eq!(
+            run_util_bytes(run, &["cat", "-nE"], b"a\n\n").1,
+            b"     1\ta$\n     2\t$\n"
+        );
+        assert_eq!(
+            run_util_bytes(run, &["cat", "-bsA"], b"\x89\n\n\n\x7f").1,
+            b"     1\tM-^I$\n$\n     2\t^?"
+        );
+        assert_eq!(
+            run_util_bytes(
+                run,
+                &["cat", "--show-ends", "--show-tabs", "--show-nonprinting"],
+                b"\t\x00\n"
+            )
+            .1,
+            run_util_bytes(run, &["cat", "--show-all"], b"\t\x00\n").1
+        );
+    }
+
+    #[test]
+    fn test_line_formatter_splits() {
+        let input = b"a\n\n\nb\tc\r\n\n\xffd";
+        for options in [
+            CatOptions {
+                number: Numbering::All,
+                squeeze_blank: true,
+                ..CatOptions::default()
+            },
+            CatOptions {
+                number: Numbering::NonBlank,
+                show_ends: true,
+                show_nonprinting: true,
+                ..CatOptions::default()
+            },
+        ] {
+            let whole = LineFormatter::new(&options).format(input).to_vec();
+            let mut lines = LineFormatter::new(&options);
+            let split: Vec<u8> = input
+                .chunks(1)
+                .flat_map(|byte| lines.format(byte).to_vec())
+                .collect();
+            assert_eq!(split, whole);
+        }
+    }
+
+    #[test]
+    fn test_run_write_error() {
+        let mut buffer = [0; 4];
+        let mut stderr = Vec::new();
+        let code = run(
+            &[OsString::from("cat")],
+            &mut &b"data\n"[..],
+            &mut &mut buffer[..],
+            &mut stderr,
+        );
+        assert_eq!(code, 1);
+        assert_eq!(stderr, b"cat: write error: failed to write whole buffer\n");
+        assert_eq!(&buffer, b"data");
+    }
+
+    #[test]
+    fn test_binary() {
+        let fixture = fixture();
+        let mut cmd = Command::cargo_bin("cat").unwrap();
+        cmd.current_dir(fixture.path(""))
+            .args(["a", "-", "missing", "b"])
+            .write_stdin(&b"\0stdin\0"[..])
+            .assert()
+            .code(1)
+            .stdout(&b"first\nfile\n\0stdin\0second file"[..])
+            .stderr("cat: missing: No such file or directory\n");
+
+        let mut cmd = Command::cargo_bin("cat").unwrap();
+        cmd.arg("-u")
+            .write_stdin("data")
+            .assert()
+            .success()
+            .stdout("data");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_binary_broken_pipe() {
+        use assert_cmd::cargo::CommandCargoExt;
+        use std::os::unix::process::ExitStatusExt;
+
+        let (reader, writer) = std::io::pipe().unwrap();
+        drop(reader);
+
+        let fixture = fixture();
+        let mut cmd = std::process::Command::cargo_bin("cat").unwrap();
+        let output = cmd.arg(fixture.path("a")).stdout(writer).output().unwrap();
+        assert_eq!(output.status.signal(), Some(libc::SIGPIPE));
+        assert!(output.stderr.is_empty());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_binary_write_error() {
+        use assert_cmd::prelude::*;
+
+        let full = fs::File::create("/dev/full").unwrap();
+
+        let fixture = fixture();
+        let mut cmd = std::process::Command::cargo_bin("cat").unwrap();
+        cmd.arg(fixture.path("a"))
+            .stdout(full)
+            .assert()
+            .code(1)
+            .stderr("cat: write error: No space left on device\n");
+    }
+}
@@ -0,0 +1,326 @@
+//! Change the group of files, and of the directory trees below them with `-R`.
+//!
+//! This is chown with only a group, a name or a number, and messages about the group alone. The changes are
+//! made by [`common::chown_core`](crate::common::chown_core).
+
+use crate::common::chown_core::{
+    chown, name_for_gid, reference_ids, ChownOptions, Owner, SystemChown, Verbosity,
+};
+use crate::common::error::{Reporter, UError, UResult, EXIT_FAILURE};
+#[cfg(unix)]
+use crate::common::users;
+use crate::common::utility::{parse_args, uumain as run_uumain, UtilCommand, Utility};
+use clap::Parser;
+use std::ffi::OsString;
+use std::io::{Read, Write};
+use std::path::Path;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "chgrp",
+    author,
+    version,
+    about = "Change the group of each FILE to GROUP, or to that of RFILE.",
+    long_about = None,
+    args_override_self = true,
+    disable_help_flag = true
+)]
+struct Args {
+    /// Print help.
+    #[arg(long, action = clap::ArgAction::Help)]
+    help: Option<bool>,
+
+    /// Print a message for each FILE whose group is changed.
+    #[arg(short = 'c', long, default_value_t = false, overrides_with = "verbose")]
+    changes: bool,
+
+    /// Do not print most of the error messages.
+    #[arg(short = 'f', long, visible_alias = "quiet", default_value_t = false)]
+    silent: bool,
+
+    /// Print a message for each FILE, whether its group is changed or not.
+    #[arg(short = 'v', long, default_value_t = false, overrides_with = "changes")]
+    verbose: bool,
+
+    /// Change the targets of the symbolic links given as FILEs, which is the default.
+    #[arg(long, default_value_t = false, overrides_with = "no_dereference")]
+    dereference: bool,
+
+    /// Change the symbolic links themselves rather than their targets.
+    #[arg(
+        short = 'h',
+        long,
+        default_value_t = false,
+        overrides_with = "dereference"
+    )]
+    no_dereference: bool,
+
+    /// Use the group of RFILE instead of GROUP.
+    #[arg(long, value_name = "RFILE")]
+    reference: Option<OsString>,
+
+    /// Change the files and directories below the directories too, and the symbolic links themselves.
+    #[arg(short = 'R', long, default_value_t = false)]
+    recursive: bool,
+
+    /// The new group, unless given with --reference, then the files to change.
+    #[arg(value_name = "GROUP FILE")]
+    operands: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "chgrp";
+}
+
+/// The description of chgrp for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run chgrp with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    run_uumain(run, args)
+}
+
+/// Run chgrp with the given command line, including the program name, on the given streams, and return
+/// the exit code.
+pub fn run(
+    args: &[OsString],
+    _stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(args.iter().cloned(), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let mut reporter = Reporter::new("chgrp", stderr);
+    let result = options(&args).and_then(|(options, files)| {
+        chown(files, &options, &mut SystemChown, stdout, &mut reporter)
+    });
+    reporter.finish(result)
+}
+
+fn options(args: &Args) -> UResult<(ChownOptions, &[OsString])> {
+    let (owner, files) = match (&args.reference, &args.operands[..]) {
+        (_, []) => return Err(UError::usage(EXIT_FAILURE, "missing operand")),
+        (Some(reference), files) => {
+            let (_, gid) = reference_ids(Path::new(reference))?;
+            (group_owner(gid, name_for_gid(gid)), files)
+        }
+        (None, [group]) => {
+            return Err(UError::usage(
+                EXIT_FAILURE,
+                format!("missing operand after '{}'", group.to_string_lossy()),
+            ))
+        }
+        (None, [group, files @ ..]) => {
+            let group = group.to_string_lossy();
+            // Like `chown :`, an empty group changes nothing.
+            let owner = match group.is_empty() {
+                true => Owner::default(),
+                false => group_owner(parse_group(&group)?, group.into_owned()),
+            };
+            (owner, files)
+        }
+    };
+    let options = ChownOptions {
+        owner,
+        no_dereference: args.no_dereference,
+        recursive: args.recursive,
+        silent: args.silent,
+        verbosity: match (args.verbose, args.changes) {
+            (true, _) => Verbosity::Verbose,
+            (_, true) => Verbosity::Changes,
+            _ => Verbosity::Errors,
+        },
+        ..ChownOptions::default()
+    };
+    Ok((options, files))
+}
+
+/// Give only a group, so that the messages are about the group alone, like `changed group of 'file'`.
+fn group_owner(gid: u32, name: String) -> Owner {
+    Owner {
+        gid: Some(gid),
+        group_name: Some(name),
+        ..Owner::default()
+    }
+}
+
+#[cfg(unix)]
+fn parse_group(group: &str) -> UResult<u32> {
+    users::parse_group(group).map_err(|error| UError::new(EXIT_FAILURE, error.to_string()))
+}
+
+/// Parse a group, which cannot be looked up on this system, where there are no groups.
+#[cfg(not(unix))]
+fn parse_group(group: &str) -> UResult<u32> {
+    Err(UError::new(
+        EXIT_FAILURE,
+        format!("invalid group: '{group}'"),
+    ))
+}
+
+// Files have no groups on the other systems.
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::common::chown_core::Recorder;
+    use crate::common::testing::run_util;
+    use std::fs;
+    use std::os::unix::fs::MetadataExt;
+    use std::path::PathBuf;
+
+    /// A change that chgrp asked for: the path, the ids, and whether to follow a symbolic link.
+    type Call = (PathBuf, Option<u32>, Option<u32>, bool);
+
+    /// Run chgrp in-process, recording the changes instead of making them, and return them with the exit
+    /// code, stdout, and stderr.
+    fn record(command_line: &[&str]) -> (Vec<Call>, i32, String, String) {
+        let args = Args::try_parse_from(command_line).unwrap();
+        let mut recorder = Recorder::default();
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let mut reporter = Reporter::new("chgrp", &mut stderr);
+        let result = options(&args).and_then(|(options, files)| {
+            chown(files, &options, &mut recorder, &mut stdout, &mut reporter)
+        });
+        let code = reporter.finish(result);
+        (
+            recorder.0,
+            code,
+            String::from_utf8(stdout).unwrap(),
+            String::from_utf8(stderr).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_group() {
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("file");
+        fs::write(&file, "").unwrap();
+        let path = file.display().to_string();
+        let old = name_for_gid(fs::metadata(&file).unwrap().gid());
+
+        // Only the group is changed, and shown like it is written.
+        for group in ["654321", "+654321"] {
+            assert_eq!(
+                record(&["chgrp", "-v", group, &path]),
+                (
+                    vec![(file.clone(), None, Some(654321), true)],
+                    0,
+                    format!("changed group of '{path}' from {old} to {group}\n"),
+                    String::new()
+                )
+            );
+        }
+        assert_eq!(
+            record(&["chgrp", "-v", "", &path]),
+            (
+                vec![(file.clone(), None, None, true)],
+                0,
+                format!("ownership of '{path}' retained\n"),
+                String::new()
+            )
+        );
+
+        assert_eq!(
+            record(&["chgrp", "no such group", &path]),
+            (
+                Vec::new(),
+                EXIT_FAILURE,
+                String::new(),
+                "chgrp: invalid group: 'no such group'\n".to_owned()
+            )
+        );
+        assert_eq!(
+            record(&["chgrp"]).3,
+            "chgrp: missing operand\nTry 'chgrp --help' for more information.\n"
+        );
+        assert_eq!(
+            record(&["chgrp", "654321"]).3,
+            "chgrp: missing operand after '654321'\nTry 'chgrp --help' for more information.\n"
+        );
+    }
+
+    #[test]
+    fn test_recursive_and_reference() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path().join("root");
+        fs::create_dir(&root).unwrap();
+        fs::write(root.join("file"), "").unwrap();
+        std::os::unix::fs::symlink("file", root.join("link")).unwrap();
+        let path = root.display().to_string();
+        let link = root.join("link").display().to_string();
+        let call = |path: PathBuf, follow| (path, None, Some(654321), follow);
+
+        assert_eq!(
+            record(&["chgrp", "-R", "654321", &path]).0,
+            [
+                call(root.join("file"), false),
+                call(root.join("link"), false),
+                call(root.clone(), false),
+            ]
+        );
+        assert_eq!(
+            record(&["chgrp", "654321", &link]).0,
+            [call(root.join("link"), true)]
+        );
+        assert_eq!(
+            record(&["chgrp", "-h", "654321", &link]).0,
+            [call(root.join("link"), false)]
+        );
+
+        let gid = fs::metadata(&root).unwrap().gid();
+        assert_eq!(
+            record(&["chgrp", "--reference", &path, &link]).0,
+            [(root.join("link"), None, Some(gid), true)]
+        );
+        let missing = directory.path().join("missing").display().to_string();
+        assert_eq!(
+            record(&["chgrp", "--reference", &missing, &path]).3,
+            format!("chgrp: failed to get attributes of '{missing}': No such file or directory\n")
+        );
+    }
+
+    #[test]
+    fn test_current_group() {
+        // Anyone can give their files to their own group.
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("file");
+        fs::write(&file, "").unwrap();
+        let gid = fs::metadata(&file).unwrap().gid();
+        let path = file.display().to_string();
+
+        assert_eq!(
+            run_util(run, &["chgrp", "-c", &gid.to_string(), &path]),
+            (0, String::new(), String::new())
+        );
+        assert_eq!(
+            run_util(run, &["chgrp", "-v", &gid.to_string(), &path]),
+            (
+                0,
+                format!("group of '{path}' retained as {}\n", name_for_gid(gid)),
+                String::new()
+            )
+        );
+
+        let missing = directory.path().join("missing").display().to_string();
+        assert_eq!(
+            run_util(run, &["chgrp", "-v", &gid.to_string(), &missing]),
+            (
+                EXIT_FAILURE,
+                format!("failed to change group of '{missing}' to {gid}\n"),
+                format!("chgrp: cannot access '{missing}': No such file or directory\n")
+            )
+        );
+    }
+}
@@ -0,0 +1,695 @@
+//! Change the permissions of files, and of the directory trees below them with `-R`.
+//!
+//! The mode is either octal, like `755` or `4755` with the setuid bit, or symbolic, like `u+x,go-w`, see
+//! [`common::mode`](crate::common::mode). A symbolic mode is applied to the current permissions of each
+//! file:
+//!
+//! ```
+//! use rust_coreutils::chmod::{chmod, ChmodOptions, Verbosity};
+//! use rust_coreutils::common::error::Reporter;
+//! use std::ffi::OsString;
+//!
+//! let directory = tempfile::tempdir().unwrap();
+//! let file = directory.path().join("file");
+//! std::fs::write(&file, "").unwrap();
+//! let options = ChmodOptions {
+//!     verbosity: Verbosity::Changes,
+//!     ..ChmodOptions::default()
+//! };
+//!
+//! let mut stdout = Vec::new();
+//! let mut stderr = Vec::new();
+//! let mut reporter = Reporter::new("chmod", &mut stderr);
+//! chmod("u=rw,g=r,o=", &[OsString::from(&file)], &options, &mut stdout, &mut reporter).unwrap();
+//! # #[cfg(unix)]
+//! assert!(String::from_utf8(stdout).unwrap().ends_with("to 0640 (rw-r-----)\n"));
+//! ```
+
+use crate::common::error::{io_error_message, Reporter, UError, UResult, EXIT_FAILURE};
+use crate::common::mode::{format_mode, parse_mode, st_mode, umask};
+use crate::common::os::os_str_bytes;
+use crate::common::utility::{parse_args, uumain as run_uumain, UtilCommand, Utility};
+use crate::common::walk::{Entry, Follow, Walk, WalkError, WalkOptions};
+use clap::Parser;
+use std::ffi::OsString;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "chmod",
+    author,
+    version,
+    about = "Change the permissions of each FILE to MODE, octal like 755, or symbolic like u+x,go-w.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Print a message for each FILE whose permissions are changed.
+    #[arg(short = 'c', long, default_value_t = false, overrides_with = "verbose")]
+    changes: bool,
+
+    /// Do not print most of the error messages.
+    #[arg(short = 'f', long, visible_alias = "quiet", default_value_t = false)]
+    silent: bool,
+
+    /// Print a message for each FILE, whether its permissions are changed or not.
+    #[arg(short = 'v', long, default_value_t = false, overrides_with = "changes")]
+    verbose: bool,
+
+    /// Change the files and directories below the directories too, skipping the symbolic links.
+    #[arg(short = 'R', long, default_value_t = false)]
+    recursive: bool,
+
+    /// The new mode, then the files to change.
+    #[arg(value_name = "MODE FILE")]
+    operands: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "chmod";
+}
+
+/// The description of chmod for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run chmod with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    run_uumain(run, args)
+}
+
+/// Run chmod with the given command line, including the program name, on the given streams, and return
+/// the exit code.
+pub fn run(
+    args: &[OsString],
+    _stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let (option_modes, args) = split_option_modes(args);
+    let args: Args = match parse_args(args, stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let options = ChmodOptions {
+        recursive: args.recursive,
+        silent: args.silent,
+        umask_warnings: !option_modes.is_empty(),
+        verbosity: match (args.verbose, args.changes) {
+            (true, _) => Verbosity::Verbose,
+            (_, true) => Verbosity::Changes,
+            _ => Verbosity::Errors,
+        },
+    };
+    let mut reporter = Reporter::new("chmod", stderr);
+    let result = match &args.operands[..] {
+        [] => Err(UError::usage(EXIT_FAILURE, "missing operand")),
+        files if !option_modes.is_empty() => {
+            let mode = option_modes.join(",");
+            chmod(&mode, files, &options, stdout, &mut reporter)
+        }
+        [mode] => Err(UError::usage(
+            EXIT_FAILURE,
+            format!("missing operand after '{}'", mode.to_string_lossy()),
+        )),
+        [mode, files @ ..] => match mode.to_str() {
+            Some(mode) => chmod(mode, files, &options, stdout, &mut reporter),
+            None => Err(invalid_mode(&mode.to_string_lossy())),
+        },
+    };
+    reporter.finish(result)
+}
+
+/// Take out the modes which look like options, like `-w` or `-x,+r`, from the options before `--`, and get
+/// them and the rest of the command line.
+///
+/// Like in GNU, an option is a mode if its first letter is one of a mode rather than an option of chmod, and
+/// several modes are joined with commas.
+fn split_option_modes(args: &[OsString]) -> (Vec<String>, Vec<OsString>) {
+    let mut modes = Vec::new();
+    let mut rest = Vec::with_capacity(args.len());
+    let mut options_ended = false;
+    for (index, arg) in args.iter().enumerate() {
+        let bytes = os_str_bytes(arg);
+        options_ended |= &bytes[..] == b"--";
+        let is_mode = index > 0
+            && !options_ended
+            && bytes.first() == Some(&b'-')
+            && bytes
+                .get(1)
+                .is_some_and(|letter| b"rwxXstugoa,+=01234567".contains(letter));
+        match is_mode {
+            true => modes.push(arg.to_string_lossy().into_owned()),
+            false => rest.push(arg.clone()),
+        }
+    }
+    (modes, rest)
+}
+
+/// Which messages chmod prints besides the errors.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Verbosity {
+    /// Only the errors.
+    #[default]
+    Errors,
+    /// A message for each file whose permissions are changed (`-c`).
+    Changes,
+    /// A message for each file (`-v`).
+    Verbose,
+}
+
+/// How chmod changes the permissions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChmodOptions {
+    /// Change the permissions below the directories too. The symbolic links below them are skipped.
+    pub recursive: bool,
+    /// Do not print the errors about the files, which still make chmod fail.
+    pub silent: bool,
+    /// Which messages to print to stdout.
+    pub verbosity: Verbosity,
+    /// Report the files which end up with permissions that the mode does not give because of the umask, as
+    /// errors. GNU does it when the mode looks like an option, like `-w`, which may be a mistake.
+    pub umask_warnings: bool,
+}
+
+/// Change the permissions of each operand according to `mode`, continuing after the failures.
+///
+/// A symbolic mode is applied to the permissions of each file in turn, so they can end up different. The
+/// symbolic links given as operands are followed, but the ones found with `-R` are skipped, since
+/// their own permissions cannot be changed.
+pub fn chmod<W: Write>(
+    mode: &str,
+    operands: &[OsString],
+    options: &ChmodOptions,
+    stdout: &mut dyn Write,
+    reporter: &mut Reporter<W>,
+) -> UResult<()> {
+    parse_mode(mode, 0, false, 0).map_err(|error| invalid_mode(&error.input))?;
+
+    let mut changer = Changer {
+        mode,
+        options,
+        umask: umask(),
+        stdout,
+        reporter,
+    };
+    for operand in operands {
+        if let Err(error) = changer.change_tree(Path::new(operand)) {
+            return UError::write(error);
+        }
+    }
+    changer.stdout.flush().or_else(UError::write)
+}
+
+fn invalid_mode(mode: &str) -> UError {
+    UError::new(EXIT_FAILURE, format!("invalid mode: '{mode}'"))
+}
+
+/// Changes the permissions of the trees.
+struct Changer<'a, W: Write> {
+    mode: &'a str,
+    options: &'a ChmodOptions,
+    umask: u32,
+    stdout: &'a mut dyn Write,
+    reporter: &'a mut Reporter<W>,
+}
+
+impl<W: Write> Changer<'_, W> {
+    /// Change the permissions of `root`, and of the files below it with `-R`.
+    fn change_tree(&mut self, root: &Path) -> io::Result<()> {
+        let walk_options = WalkOptions {
+            follow: Follow::Roots,
+            ..WalkOptions::default()
+        };
+        let mut walk = Walk::new(vec![root.to_path_buf()], walk_options);
+        // The last directory which was changed, whose contents are read when descending into it.
+        let mut last_directory: Option<PathBuf> = None;
+        while let Some(entry) = walk.next() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(WalkError::Io { path, error }) => {
+                    let action = match last_directory.as_ref() == Some(&path) {
+                        true => "cannot read directory",
+                        false => "cannot access",
+                    };
+                    self.error(format_args!(
+                        "{action} '{}': {}",
+                        path.display(),
+                        io_error_message(&error)
+                    ));
+                    if action == "cannot access" && self.options.verbosity == Verbosity::Verbose {
+                        writeln!(self.stdout, "'{}' could not be accessed", path.display())?;
+                    }
+                    continue;
+                }
+                // Only the roots are followed, so there are no loops.
+                Err(WalkError::Loop { .. }) => continue,
+            };
+
+            if entry.metadata.is_symlink() {
+                self.skip_symlink(&entry)?;
+                continue;
+            }
+            self.change(&entry)?;
+            match self.options.recursive && entry.is_dir() {
+                true => last_directory = Some(entry.path),
+                false => walk.skip_current_dir(),
+            }
+        }
+        Ok(())
+    }
+
+    /// Report a symbolic link which is not changed: a dangling one given as an operand, or one found with
+    /// `-R`.
+    fn skip_symlink(&mut self, entry: &Entry) -> io::Result<()> {
+        let path = entry.path.display();
+        if entry.depth == 0 {
+            self.error(format_args!("cannot operate on dangling symlink '{path}'"));
+            if self.options.verbosity == Verbosity::Verbose {
+                writeln!(self.stdout, "'{path}' could not be accessed")?;
+            }
+        } else if self.options.verbosity == Verbosity::Verbose {
+            writeln!(
+                self.stdout,
+                "neither symbolic link '{path}' nor referent has been changed"
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Change the permissions of a file, and report it as requested.
+    fn change(&mut self, entry: &Entry) -> io::Result<()> {
+        let old = st_mode(&entry.metadata);
+        let permissions = parse_mode(self.mode, old & 0o7777, entry.is_dir(), self.umask)
+            .expect("the mode was checked");
+        let new = (old & !0o7777) | permissions;
+        let path = entry.path.display();
+        let verbose = self.options.verbosity == Verbosity::Verbose;
+        if let Err(error) = set_mode(&entry.path, permissions) {
+            self.error(format_args!(
+                "changing permissions of '{path}': {}",
+                io_error_message(&error)
+            ));
+            if verbose {
+                writeln!(
+                    self.stdout,
+                    "failed to change mode of '{path}' from {} to {}",
+                    describe(old),
+                    describe(new)
+                )?;
+            }
+            return Ok(());
+        }
+
+        if new != old && self.options.verbosity != Verbosity::Errors {
+            writeln!(
+                self.stdout,
+                "mode of '{path}' changed from {} to {}",
+                describe(old),
+                describe(new)
+            )?;
+        } else if verbose {
+            writeln!(
+                self.stdout,
+                "mode of '{path}' retained as {}",
+                describe(old)
+            )?;
+        }
+        if self.options.umask_warnings {
+            // What the mode gives, ignoring the umask.
+            let expected = parse_mode(self.mode, old & 0o7777, entry.is_dir(), 0)
+                .expect("the mode was checked");
+            if permissions & !expected != 0 {
+                self.reporter.error(format_args!(
+                    "{path}: new permissions are {}, not {}",
+                    &format_mode(new)[1..],
+                    &format_mode((old & !0o7777) | expected)[1..]
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Report an error about a file, unless silenced with `-f`, and remember the failure.
+    fn error(&mut self, message: std::fmt::Arguments) {
+        match self.options.silent {
+            true => self.reporter.set_failed(),
+            false => self.reporter.error(message),
+        }
+    }
+}
+
+/// Describe a mode like GNU, like `0755 (rwxr-xr-x)`.
+fn describe(st_mode: u32) -> String {
+    format!("{:04o} ({})", st_mode & 0o7777, &format_mode(st_mode)[1..])
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+}
+
+/// Change the permissions of a file, of which only whether it can be written exists on this system.
+#[cfg(not(unix))]
+fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+    let mut permissions = std::fs::metadata(path)?.permissions();
+    permissions.set_readonly(mode & 0o200 == 0);
+    std::fs::set_permissions(path, permissions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::run_util;
+    use std::fs;
+    #[cfg(unix)]
+    use std::os::unix::fs::PermissionsExt;
+
+    #[cfg(unix)]
+    fn mode_of(path: &Path) -> u32 {
+        st_mode(&fs::symlink_metadata(path).unwrap()) & 0o7777
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_octal() {
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("file");
+        fs::write(&file, "").unwrap();
+        let path = file.display().to_string();
+        for (mode, expected) in [
+            ("644", 0o644),
+            ("4755", 0o4755),
+            ("1777", 0o1777),
+            ("7", 0o7),
+            ("0", 0),
+            ("00644", 0o644),
+        ] {
+            assert_eq!(run_util(run, &["chmod", mode, &path]).0, 0, "{mode}");
+            assert_eq!(mode_of(&file), expected, "{mode}");
+        }
+
+        // Without five digits, the setuid and setgid bits of directories are kept unless they are set.
+        let sub = directory.path().join("sub");
+        fs::create_dir(&sub).unwrap();
+        let sub_path = sub.display().to_string();
+        for (mode, expected) in [
+            ("2755", 0o2755),
+            ("755", 0o2755),
+            ("0700", 0o2700),
+            ("00755", 0o755),
+        ] {
+            assert_eq!(run_util(run, &["chmod", mode, &sub_path]).0, 0, "{mode}");
+            assert_eq!(mode_of(&sub), expected, "{mode}");
+        }
+
+        for mode in ["8", "75a", "12345", "a+z", "", ","] {
+            assert_eq!(
+                run_util(run, &["chmod", mode, &path]),
+                (
+                    EXIT_FAILURE,
+                    String::new(),
+                    format!("chmod: invalid mode: '{mode}'\n")
+                )
+            );
+        }
+        assert_eq!(
+            run_util(run, &["chmod"]).2,
+            "chmod: missing operand\nTry 'chmod --help' for more information.\n"
+        );
+        assert_eq!(
+            run_util(run, &["chmod", "644"]).2,
+            "chmod: missing operand after '644'\nTry 'chmod --help' for more information.\n"
+        );
+    }
+
+    /// Change the permissions of a file in-process with the given umask instead of that of the process,
+    /// returning whether it failed and stderr.
+    #[cfg(unix)]
+    fn change_with_umask(
+        mode: &str,
+        path: &Path,
+        umask: u32,
+        umask_warnings: bool,
+    ) -> (bool, String) {
+        let options = ChmodOptions {
+            umask_warnings,
+            ..ChmodOptions::default()
+        };
+        let mut stderr = Vec::new();
+        let mut reporter = Reporter::new("chmod", &mut stderr);
+        let mut changer = Changer {
+            mode,
+            options: &options,
+            umask,
+            stdout: &mut io::sink(),
+            reporter: &mut reporter,
+        };
+        changer.change_tree(path).unwrap();
+        (reporter.failed(), String::from_utf8(stderr).unwrap())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_symbolic() {
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("file");
+        fs::write(&file, "").unwrap();
+        let sub = directory.path().join("sub");
+        fs::create_dir(&sub).unwrap();
+        for (path, old, mode, umask, expected) in [
+            // X only gives execute permissions to directories and to files which some can execute.
+            (&file, 0o644, "a+X", 0, 0o644),
+            (&file, 0o744, "a+X", 0, 0o755),
+            (&sub, 0o644, "a+X", 0, 0o755),
+            (&file, 0o600, "u=rwx,go=rx", 0o077, 0o755),
+            // The umask only applies to the clauses which do not say who they apply to.
+            (&file, 0o666, "o-w", 0o022, 0o664),
+            (&file, 0o666, "-w", 0o022, 0o466),
+            (&file, 0o644, "+x", 0o022, 0o755),
+            (&file, 0o644, "+x", 0o077, 0o744),
+            (&file, 0o640, "g=u", 0, 0o660),
+            (&file, 0o751, "go=u-w,u-x", 0, 0o655),
+            (&file, 0o4755, "=r", 0, 0o444),
+            // A directory keeps its setgid bit unless the mode mentions it.
+            (&sub, 0o2755, "=r", 0, 0o2444),
+            (&sub, 0o2755, "g-s", 0, 0o755),
+            (&sub, 0o755, "+t,g+s", 0o022, 0o3755),
+        ] {
+            fs::set_permissions(path, fs::Permissions::from_mode(old)).unwrap();
+            assert_eq!(
+                change_with_umask(mode, path, umask, false),
+                (false, String::new())
+            );
+            assert_eq!(mode_of(path), expected, "{old:o} {mode} {umask:o}");
+        }
+
+        // Each file gets the mode applied to its own permissions.
+        let other = directory.path().join("other");
+        fs::write(&other, "").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o700)).unwrap();
+        fs::set_permissions(&other, fs::Permissions::from_mode(0o600)).unwrap();
+        let paths = [file.display().to_string(), other.display().to_string()];
+        assert_eq!(run_util(run, &["chmod", "go=u", &paths[0], &paths[1]]).0, 0);
+        assert_eq!((mode_of(&file), mode_of(&other)), (0o777, 0o666));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_option_modes() {
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("file");
+        fs::write(&file, "").unwrap();
+        let path = file.display().to_string();
+
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o777)).unwrap();
+        // The permissions kept by the umask of the process, which depends on the environment, are reported.
+        let (code, _, stderr) = run_util(run, &["chmod", "-x", "-R", "-w", &path]);
+        assert_eq!(code == 0, stderr.is_empty());
+        assert_eq!(mode_of(&file) & !umask(), 0o444 & !umask());
+        assert_eq!(
+            run_util(run, &["chmod", "-w"]).2,
+            "chmod: missing operand\nTry 'chmod --help' for more information.\n"
+        );
+        // After `--`, only the first operand is the mode.
+        assert_eq!(run_util(run, &["chmod", "--", "-r", &path]).0, 0);
+        assert_eq!(mode_of(&file) & !umask(), 0);
+
+        // The permissions that the umask keeps are reported for such modes only.
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o666)).unwrap();
+        assert_eq!(
+            change_with_umask("-w", &file, 0o077, true),
+            (
+                true,
+                format!("chmod: {path}: new permissions are r--rw-rw-, not r--r--r--\n")
+            )
+        );
+        assert_eq!(mode_of(&file), 0o466);
+        assert_eq!(
+            change_with_umask("-w", &file, 0o077, false),
+            (false, String::new())
+        );
+        // Giving fewer permissions than expected is not reported.
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o0)).unwrap();
+        assert_eq!(
+            change_with_umask("-r,+r", &file, 0o077, true),
+            (false, String::new())
+        );
+        assert_eq!(mode_of(&file), 0o400);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_recursive() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path().join("root");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("sub/file"), "").unwrap();
+        fs::set_permissions(root.join("sub/file"), fs::Permissions::from_mode(0o644)).unwrap();
+        let target = directory.path().join("target");
+        fs::write(&target, "").unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o644)).unwrap();
+        std::os::unix::fs::symlink(&target, root.join("link")).unwrap();
+        let path = |name: &str| root.join(name).display().to_string();
+
+        let (code, stdout, stderr) =
+            run_util(run, &["chmod", "-vR", "700", &root.display().to_string()]);
+        assert_eq!((code, stderr.as_str()), (0, ""));
+        let lines: Vec<&str> = stdout.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with(&format!("mode of '{}' ", root.display())));
+        assert_eq!(
+            lines[1],
+            format!(
+                "neither symbolic link '{}' nor referent has been changed",
+                path("link")
+            )
+        );
+        assert!(lines[2].starts_with(&format!("mode of '{}' ", path("sub"))));
+        assert_eq!(
+            lines[3],
+            format!(
+                "mode of '{}' changed from 0644 (rw-r--r--) to 0700 (rwx------)",
+                path("sub/file")
+            )
+        );
+        for name in ["", "sub", "sub/file"] {
+            assert_eq!(mode_of(&root.join(name)), 0o700, "{name}");
+        }
+        assert_eq!(mode_of(&target), 0o644);
+
+        // The directories are changed before they are read.
+        let (code, _, stderr) = run_util(run, &["chmod", "-R", "0", &root.display().to_string()]);
+        match fs::read_dir(&root) {
+            // Only root can still read it.
+            Ok(_) => assert_eq!((code, stderr.as_str()), (0, "")),
+            Err(_) => assert_eq!(
+                (code, stderr),
+                (
+                    EXIT_FAILURE,
+                    format!(
+                        "chmod: cannot read directory '{}': Permission denied\n",
+                        root.display()
+                    )
+                )
+            ),
+        }
+        assert_eq!(
+            run_util(run, &["chmod", "-R", "755", &root.display().to_string()]).0,
+            0
+        );
+        assert_eq!(mode_of(&root.join("sub/file")), 0o755);
+
+        // The symbolic links given as operands are followed, even with -R.
+        assert_eq!(run_util(run, &["chmod", "-R", "600", &path("link")]).0, 0);
+        assert_eq!(mode_of(&target), 0o600);
+        assert_eq!(mode_of(&root.join("sub")), 0o755);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_changes() {
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("file");
+        fs::write(&file, "").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o644)).unwrap();
+        let path = file.display().to_string();
+
+        let changed =
+            format!("mode of '{path}' changed from 0644 (rw-r--r--) to 0755 (rwxr-xr-x)\n");
+        assert_eq!(
+            run_util(run, &["chmod", "-c", "755", &path]),
+            (0, changed, String::new())
+        );
+        assert_eq!(
+            run_util(run, &["chmod", "-c", "755", &path]),
+            (0, String::new(), String::new())
+        );
+        assert_eq!(
+            run_util(run, &["chmod", "-v", "755", &path]),
+            (
+                0,
+                format!("mode of '{path}' retained as 0755 (rwxr-xr-x)\n"),
+                String::new()
+            )
+        );
+        assert_eq!(
+            run_util(run, &["chmod", "755", &path]),
+            (0, String::new(), String::new())
+        );
+        // The last of -c and -v wins.
+        assert_eq!(run_util(run, &["chmod", "-v", "-c", "755", &path]).1, "");
+    }
+
+    #[test]
+    fn test_errors() {
+        let directory = tempfile::tempdir().unwrap();
+        let missing = directory.path().join("missing");
+        let file = directory.path().join("file");
+        fs::write(&file, "").unwrap();
+        let missing_path = missing.display().to_string();
+        let file_path = file.display().to_string();
+
+        let (code, stdout, stderr) = run_util(run, &["chmod", "600", &missing_path, &file_path]);
+        assert_eq!((code, stdout.as_str()), (EXIT_FAILURE, ""));
+        assert_eq!(
+            stderr,
+            format!("chmod: cannot access '{missing_path}': No such file or directory\n")
+        );
+        #[cfg(unix)]
+        assert_eq!(mode_of(&file), 0o600);
+
+        assert_eq!(
+            run_util(run, &["chmod", "-v", "600", &missing_path]).1,
+            format!("'{missing_path}' could not be accessed\n")
+        );
+        assert_eq!(
+            run_util(run, &["chmod", "-f", "600", &missing_path]),
+            (EXIT_FAILURE, String::new(), String::new())
+        );
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&missing, directory.path().join("dangling")).unwrap();
+            let dangling = directory.path().join("dangling").display().to_string();
+            assert_eq!(
+                run_util(run, &["chmod", "600", &dangling]),
+                (
+                    EXIT_FAILURE,
+                    String::new(),
+                    format!("chmod: cannot operate on dangling symlink '{dangling}'\n")
+                )
+            );
+        }
+    }
+}
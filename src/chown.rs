@@ -0,0 +1,517 @@
+//! Change the owner and group of files, and of the directory trees below them with `-R`.
+//!
+//! The owner is `USER[:GROUP]`, with names or numbers, like `alice:staff`, `1000`, `alice:` for the login
+//! group of alice, or `:staff` to change only the group. The changes are made by
+//! [`common::chown_core`](crate::common::chown_core), which chgrp shares.
+
+use crate::common::chown_core::{
+    chown, name_for_gid, name_for_uid, reference_ids, ChownOptions, Owner, SystemChown, Verbosity,
+};
+use crate::common::error::{Reporter, UError, UResult, EXIT_FAILURE};
+#[cfg(unix)]
+use crate::common::users;
+use crate::common::utility::{parse_args, uumain as run_uumain, UtilCommand, Utility};
+use clap::Parser;
+use std::ffi::OsString;
+use std::io::{Read, Write};
+use std::path::Path;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "chown",
+    author,
+    version,
+    about = "Change the owner and group of each FILE to OWNER[:GROUP], or to those of RFILE.",
+    long_about = None,
+    args_override_self = true,
+    disable_help_flag = true
+)]
+struct Args {
+    /// Print help.
+    #[arg(long, action = clap::ArgAction::Help)]
+    help: Option<bool>,
+
+    /// Print a message for each FILE whose owner is changed.
+    #[arg(short = 'c', long, default_value_t = false, overrides_with = "verbose")]
+    changes: bool,
+
+    /// Do not print most of the error messages.
+    #[arg(short = 'f', long, visible_alias = "quiet", default_value_t = false)]
+    silent: bool,
+
+    /// Print a message for each FILE, whether its owner is changed or not.
+    #[arg(short = 'v', long, default_value_t = false, overrides_with = "changes")]
+    verbose: bool,
+
+    /// Change the targets of the symbolic links given as FILEs, which is the default.
+    #[arg(long, default_value_t = false, overrides_with = "no_dereference")]
+    dereference: bool,
+
+    /// Change the symbolic links themselves rather than their targets.
+    #[arg(
+        short = 'h',
+        long,
+        default_value_t = false,
+        overrides_with = "dereference"
+    )]
+    no_dereference: bool,
+
+    /// Only change the files whose owner and group are currently CURRENT_OWNER and CURRENT_GROUP, either
+    /// of which may be left out.
+    #[arg(long, value_name = "CURRENT_OWNER:CURRENT_GROUP")]
+    from: Option<String>,
+
+    /// Use the owner and group of RFILE instead of OWNER[:GROUP].
+    #[arg(long, value_name = "RFILE")]
+    reference: Option<OsString>,
+
+    /// Change the files and directories below the directories too, and the symbolic links themselves.
+    #[arg(short = 'R', long, default_value_t = false)]
+    recursive: bool,
+
+    /// The new owner, unless given with --reference, then the files to change.
+    #[arg(value_name = "OWNER[:GROUP] FILE")]
+    operands: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "chown";
+}
+
+/// The description of chown for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run chown with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    run_uumain(run, args)
+}
+
+/// Run chown with the given command line, including the program name, on the given streams, and return
+/// the exit code.
+pub fn run(
+    args: &[OsString],
+    _stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(args.iter().cloned(), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let mut reporter = Reporter::new("chown", stderr);
+    let result = options(&args, &mut reporter).and_then(|(options, files)| {
+        chown(files, &options, &mut SystemChown, stdout, &mut reporter)
+    });
+    reporter.finish(result)
+}
+
+fn options<'a, W: Write>(
+    args: &'a Args,
+    reporter: &mut Reporter<W>,
+) -> UResult<(ChownOptions, &'a [OsString])> {
+    let (owner, files) = match (&args.reference, &args.operands[..]) {
+        (_, []) => return Err(UError::usage(EXIT_FAILURE, "missing operand")),
+        (Some(reference), files) => (reference_owner(Path::new(reference))?, files),
+        (None, [spec]) => {
+            return Err(UError::usage(
+                EXIT_FAILURE,
+                format!("missing operand after '{}'", spec.to_string_lossy()),
+            ))
+        }
+        (None, [spec, files @ ..]) => {
+            let owner = match spec.to_str() {
+                Some(spec) => parse_owner(spec, reporter)?,
+                None => {
+                    return Err(UError::new(
+                        EXIT_FAILURE,
+                        format!("invalid user: '{}'", spec.to_string_lossy()),
+                    ))
+                }
+            };
+            (owner, files)
+        }
+    };
+    let (from_uid, from_gid) = match &args.from {
+        Some(spec) => parse_spec(spec, reporter)?,
+        None => (None, None),
+    };
+    let options = ChownOptions {
+        owner,
+        from_uid,
+        from_gid,
+        no_dereference: args.no_dereference,
+        recursive: args.recursive,
+        silent: args.silent,
+        verbosity: match (args.verbose, args.changes) {
+            (true, _) => Verbosity::Verbose,
+            (_, true) => Verbosity::Changes,
+            _ => Verbosity::Errors,
+        },
+    };
+    Ok((options, files))
+}
+
+/// Parse `OWNER[:GROUP]`, and describe the owner like it is written, or with the id if it is a number.
+fn parse_owner<W: Write>(spec: &str, reporter: &mut Reporter<W>) -> UResult<Owner> {
+    let (uid, gid) = parse_spec(spec, reporter)?;
+    let (user, group) = match spec.split_once(':') {
+        Some((user, group)) => (user, group),
+        None => spec
+            .split_once('.')
+            .filter(|_| gid.is_some())
+            .unwrap_or((spec, "")),
+    };
+    let user_name = uid.map(|uid| match uid_for_name(user) == Some(uid) {
+        true => user.to_string(),
+        false => uid.to_string(),
+    });
+    let group_name = gid.map(|gid| match gid_for_name(group) == Some(gid) {
+        true => group.to_string(),
+        // The login group of `OWNER:`.
+        false if group.is_empty() => name_for_gid(gid),
+        false => gid.to_string(),
+    });
+    Ok(Owner {
+        uid,
+        gid,
+        // Only the group is changed, which is described as `:GROUP`.
+        user_name: user_name.or_else(|| group_name.as_ref().map(|_| String::new())),
+        group_name,
+    })
+}
+
+/// Parse an owner spec into ids, warning about the old `.` separator.
+fn parse_spec<W: Write>(
+    spec: &str,
+    reporter: &mut Reporter<W>,
+) -> UResult<(Option<u32>, Option<u32>)> {
+    let ids = parse_owner_spec(spec).map_err(|message| UError::new(EXIT_FAILURE, message))?;
+    if !spec.contains(':') && ids.1.is_some() {
+        reporter.warning(format_args!("warning: '.' should be ':': '{spec}'"));
+    }
+    Ok(ids)
+}
+
+/// Get the owner and group of a file, for `--reference`.
+fn reference_owner(path: &Path) -> UResult<Owner> {
+    let (uid, gid) = reference_ids(path)?;
+    Ok(Owner {
+        uid: Some(uid),
+        gid: Some(gid),
+        user_name: Some(name_for_uid(uid)),
+        group_name: Some(name_for_gid(gid)),
+    })
+}
+
+#[cfg(unix)]
+fn uid_for_name(name: &str) -> Option<u32> {
+    users::uid_for_name(name).ok().flatten()
+}
+
+#[cfg(not(unix))]
+fn uid_for_name(_name: &str) -> Option<u32> {
+    None
+}
+
+#[cfg(unix)]
+fn gid_for_name(name: &str) -> Option<u32> {
+    users::gid_for_name(name).ok().flatten()
+}
+
+#[cfg(not(unix))]
+fn gid_for_name(_name: &str) -> Option<u32> {
+    None
+}
+
+#[cfg(unix)]
+fn parse_owner_spec(spec: &str) -> Result<(Option<u32>, Option<u32>), String> {
+    users::parse_owner_spec(spec).map_err(|error| error.to_string())
+}
+
+/// Parse an owner spec, which can only be empty on this system, where there are no users and groups.
+#[cfg(not(unix))]
+fn parse_owner_spec(spec: &str) -> Result<(Option<u32>, Option<u32>), String> {
+    match spec {
+        "" | ":" => Ok((None, None)),
+        _ => Err(format!("invalid user: '{spec}'")),
+    }
+}
+
+// Files have no owners on the other systems.
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::common::chown_core::{ids, Recorder};
+    use crate::common::testing::run_util;
+    use std::fs;
+    use std::os::unix::fs::MetadataExt;
+    use std::path::PathBuf;
+
+    /// A change that chown asked for: the path, the ids, and whether to follow a symbolic link.
+    type Call = (PathBuf, Option<u32>, Option<u32>, bool);
+
+    /// Run chown in-process, recording the changes instead of making them, and return them with the exit
+    /// code, stdout, and stderr.
+    fn record(command_line: &[&str]) -> (Vec<Call>, i32, String, String) {
+        let args = Args::try_parse_from(command_line).unwrap();
+        let mut recorder = Recorder::default();
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let mut reporter = Reporter::new("chown", &mut stderr);
+        let result = options(&args, &mut reporter).and_then(|(options, files)| {
+            chown(files, &options, &mut recorder, &mut stdout, &mut reporter)
+        });
+        let code = reporter.finish(result);
+        (
+            recorder.0,
+            code,
+            String::from_utf8(stdout).unwrap(),
+            String::from_utf8(stderr).unwrap(),
+        )
+    }
+
+    /// Describe the current owner and group of a file like the messages.
+    fn owner_of(path: &Path) -> String {
+        let (uid, gid) = ids(&fs::symlink_metadata(path).unwrap());
+        format!("{}:{}", name_for_uid(uid), name_for_gid(gid))
+    }
+
+    #[test]
+    fn test_spec() {
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("file");
+        fs::write(&file, "").unwrap();
+        let path = file.display().to_string();
+        let old = owner_of(&file);
+        let old_user = old.split_once(':').unwrap().0;
+
+        assert_eq!(
+            record(&["chown", "-v", "123456:+654321", &path]),
+            (
+                vec![(file.clone(), Some(123456), Some(654321), true)],
+                0,
+                format!("changed ownership of '{path}' from {old} to 123456:654321\n"),
+                String::new()
+            )
+        );
+        assert_eq!(
+            record(&["chown", "-c", "123456", &path]).2,
+            format!("changed ownership of '{path}' from {old_user} to 123456\n")
+        );
+        // Changing only the group is still described as a change of ownership.
+        assert_eq!(
+            record(&["chown", "-c", ":654321", &path]),
+            (
+                vec![(file.clone(), None, Some(654321), true)],
+                0,
+                format!("changed ownership of '{path}' from {old} to :654321\n"),
+                String::new()
+            )
+        );
+        assert_eq!(
+            record(&["chown", "-v", ":", &path]),
+            (
+                vec![(file.clone(), None, None, true)],
+                0,
+                format!("ownership of '{path}' retained\n"),
+                String::new()
+            )
+        );
+        assert_eq!(
+            record(&["chown", "123456.654321", &path]).3,
+            "chown: warning: '.' should be ':': '123456.654321'\n"
+        );
+
+        if let Ok(Some(root)) = users::name_for_uid(0) {
+            // The login group of the user.
+            let gid = users::parse_owner_spec(&format!("{root}:")).unwrap().1;
+            let (calls, _, stdout, _) = record(&["chown", "-v", &format!("{root}:"), &path]);
+            assert_eq!(calls, [(file.clone(), Some(0), gid, true)]);
+            assert!(stdout.ends_with(&format!(" {root}:{}\n", name_for_gid(gid.unwrap()))));
+        }
+
+        assert_eq!(
+            record(&["chown", "no such user", &path]),
+            (
+                Vec::new(),
+                EXIT_FAILURE,
+                String::new(),
+                "chown: invalid user: 'no such user'\n".to_owned()
+            )
+        );
+        assert_eq!(
+            record(&["chown"]).3,
+            "chown: missing operand\nTry 'chown --help' for more information.\n"
+        );
+        assert_eq!(
+            record(&["chown", "123456"]).3,
+            "chown: missing operand after '123456'\nTry 'chown --help' for more information.\n"
+        );
+    }
+
+    #[test]
+    fn test_symlinks() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path().join("root");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("sub/file"), "").unwrap();
+        let target = directory.path().join("target");
+        fs::write(&target, "").unwrap();
+        std::os::unix::fs::symlink(&target, root.join("link")).unwrap();
+        let link = root.join("link").display().to_string();
+        let call = |path: PathBuf, follow| (path, Some(123456), None, follow);
+
+        // The symbolic links given as operands are followed, unless -h is given.
+        assert_eq!(
+            record(&["chown", "123456", &link]).0,
+            [call(root.join("link"), true)]
+        );
+        assert_eq!(
+            record(&["chown", "-h", "123456", &link]).0,
+            [call(root.join("link"), false)]
+        );
+        assert_eq!(
+            record(&["chown", "-h", "--dereference", "123456", &link]).0,
+            [call(root.join("link"), true)]
+        );
+
+        // With -R, they never are, and the directories are changed after their contents.
+        let (calls, code, _, stderr) =
+            record(&["chown", "-R", "123456", &root.display().to_string()]);
+        assert_eq!((code, stderr.as_str()), (0, ""));
+        assert_eq!(
+            calls,
+            [
+                call(root.join("link"), false),
+                call(root.join("sub/file"), false),
+                call(root.join("sub"), false),
+                call(root.clone(), false),
+            ]
+        );
+        assert_eq!(
+            record(&["chown", "-R", "123456", &link]).0,
+            [call(root.join("link"), false)]
+        );
+
+        let dangling = directory.path().join("dangling");
+        std::os::unix::fs::symlink(directory.path().join("missing"), &dangling).unwrap();
+        let dangling_path = dangling.display().to_string();
+        assert_eq!(
+            record(&["chown", "123456", &dangling_path]),
+            (
+                Vec::new(),
+                EXIT_FAILURE,
+                String::new(),
+                format!("chown: cannot dereference '{dangling_path}': No such file or directory\n")
+            )
+        );
+        assert_eq!(
+            record(&["chown", "-h", "123456", &dangling_path]).0,
+            [call(dangling, false)]
+        );
+    }
+
+    #[test]
+    fn test_from_and_reference() {
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("file");
+        fs::write(&file, "").unwrap();
+        let path = file.display().to_string();
+        let metadata = fs::metadata(&file).unwrap();
+        let (uid, gid) = (metadata.uid(), metadata.gid());
+
+        // Only the files with the current owner and group of --from are changed.
+        for (from, changed) in [
+            (format!("{uid}"), true),
+            (format!(":{gid}"), true),
+            (format!("{uid}:{gid}"), true),
+            (format!("{}", uid + 1), false),
+            (format!("{uid}:{}", gid + 1), false),
+        ] {
+            let (calls, code, _, _) =
+                record(&["chown", &format!("--from={from}"), "123456", &path]);
+            assert_eq!((calls.len(), code), (usize::from(changed), 0), "{from}");
+        }
+        assert_eq!(
+            record(&[
+                "chown",
+                "-v",
+                &format!("--from={}", uid + 1),
+                "123456",
+                &path
+            ])
+            .2,
+            format!("ownership of '{path}' retained as {}\n", name_for_uid(uid))
+        );
+
+        // With --reference, all the operands are files.
+        let reference = directory.path().join("reference").display().to_string();
+        assert_eq!(
+            record(&["chown", "--reference", &reference, &path]).3,
+            format!(
+                "chown: failed to get attributes of '{reference}': No such file or directory\n"
+            )
+        );
+        assert_eq!(
+            record(&["chown", "-v", "--reference", &path, &path]),
+            (
+                vec![(file.clone(), Some(uid), Some(gid), true)],
+                0,
+                format!("ownership of '{path}' retained as {}\n", owner_of(&file)),
+                String::new()
+            )
+        );
+    }
+
+    #[test]
+    fn test_current_owner() {
+        // Anyone can give their files to themselves.
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path().join("root");
+        fs::create_dir(&root).unwrap();
+        fs::write(root.join("file"), "").unwrap();
+        let metadata = fs::metadata(&root).unwrap();
+        let spec = format!("{}:{}", metadata.uid(), metadata.gid());
+        let path = root.display().to_string();
+        let owner = owner_of(&root);
+
+        assert_eq!(
+            run_util(run, &["chown", "-c", &spec, &path]),
+            (0, String::new(), String::new())
+        );
+        assert_eq!(
+            run_util(run, &["chown", "-vR", &spec, &path]),
+            (
+                0,
+                format!(
+                    "ownership of '{path}/file' retained as {owner}\n\
+                     ownership of '{path}' retained as {owner}\n"
+                ),
+                String::new()
+            )
+        );
+
+        let missing = directory.path().join("missing").display().to_string();
+        assert_eq!(
+            run_util(run, &["chown", "-v", &spec, &missing]),
+            (
+                EXIT_FAILURE,
+                format!("failed to change ownership of '{missing}' to {spec}\n"),
+                format!("chown: cannot access '{missing}': No such file or directory\n")
+            )
+        );
+        assert_eq!(
+            run_util(run, &["chown", "-f", &spec, &missing]),
+            (EXIT_FAILURE, String::new(), String::new())
+        );
+    }
+}
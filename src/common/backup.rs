@@ -0,0 +1,490 @@
+//! The `--backup[=CONTROL]`, `-b`, and `-S SUFFIX` options of cp, mv, ln, and install.
+//!
+//! A utility flattens [`BackupArgs`] into its command line, and calls [`back_up`] right before it
+//! overwrites or removes a destination, which renames it with [`make_backup`]:
+//!
+//! ```
+//! use rust_coreutils::common::backup::{make_backup, BackupMode, BackupOptions};
+//!
+//! let directory = tempfile::tempdir().unwrap();
+//! let destination = directory.path().join("file");
+//! std::fs::write(&destination, "old").unwrap();
+//!
+//! let options = BackupOptions {
+//!     mode: BackupMode::Numbered,
+//!     suffix: "~".into(),
+//! };
+//! let backup = make_backup(&destination, &options).unwrap().unwrap();
+//! assert_eq!(backup, directory.path().join("file.~1~"));
+//! assert!(!destination.exists());
+//! ```
+
+use crate::common::error::{io_error_message, UError, UResult, EXIT_FAILURE};
+use crate::common::paths::same_file;
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The suffix of simple backups when neither `-S` nor `SIMPLE_BACKUP_SUFFIX` is set.
+pub const DEFAULT_SUFFIX: &str = "~";
+
+/// When to make backups, and how to name them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BackupMode {
+    /// Never make backups (`none`, `off`).
+    #[default]
+    None,
+    /// Append the suffix (`simple`, `never`).
+    Simple,
+    /// Make numbered backups if some already exist, simple backups otherwise (`existing`, `nil`).
+    Existing,
+    /// Append `.~N~`, with the next free number (`numbered`, `t`).
+    Numbered,
+}
+
+/// The accepted values of `CONTROL`, which can be abbreviated as long as they are unambiguous.
+const CONTROLS: &[(&str, BackupMode)] = &[
+    ("none", BackupMode::None),
+    ("off", BackupMode::None),
+    ("simple", BackupMode::Simple),
+    ("never", BackupMode::Simple),
+    ("existing", BackupMode::Existing),
+    ("nil", BackupMode::Existing),
+    ("numbered", BackupMode::Numbered),
+    ("t", BackupMode::Numbered),
+];
+
+impl BackupMode {
+    /// Parse a `CONTROL` value, where `context` names where it comes from in the error message.
+    pub fn parse(control: &str, context: &str) -> UResult<Self> {
+        if let Some(&(_, mode)) = CONTROLS.iter().find(|(name, _)| *name == control) {
+            return Ok(mode);
+        }
+
+        let mut matches = CONTROLS
+            .iter()
+            .filter(|(name, _)| !control.is_empty() && name.starts_with(control))
+            .map(|&(_, mode)| mode);
+        let problem = match matches.next() {
+            Some(mode) if matches.all(|other| other == mode) => return Ok(mode),
+            Some(_) => "ambiguous",
+            None => "invalid",
+        };
+        Err(UError::usage(
+            EXIT_FAILURE,
+            format!(
+                "{problem} argument '{control}' for '{context}'\n\
+                 Valid arguments are:\n  \
+                 - 'none', 'off'\n  \
+                 - 'simple', 'never'\n  \
+                 - 'existing', 'nil'\n  \
+                 - 'numbered', 't'"
+            ),
+        ))
+    }
+}
+
+/// The backup options of a command line.
+#[derive(clap::Args, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BackupArgs {
+    /// Make a backup of each existing destination file. CONTROL is none, simple, existing (default), or
+    /// numbered, from VERSION_CONTROL by default.
+    #[arg(
+        long,
+        value_name = "CONTROL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = ""
+    )]
+    pub backup: Option<String>,
+
+    /// Like --backup, but does not accept an argument.
+    #[arg(short = 'b')]
+    pub b: bool,
+
+    /// Override the usual backup suffix, which is SIMPLE_BACKUP_SUFFIX or ~ by default. Implies -b.
+    #[arg(short = 'S', long, value_name = "SUFFIX")]
+    pub suffix: Option<OsString>,
+}
+
+impl BackupArgs {
+    /// Check whether any of the options is given, even `--backup=none`, which GNU does not allow with
+    /// `--no-clobber`.
+    pub fn given(&self) -> bool {
+        self.backup.is_some() || self.b || self.suffix.is_some()
+    }
+
+    /// Get the options, using the environment for the values which are not given.
+    pub fn options(&self) -> UResult<BackupOptions> {
+        self.options_with_env(
+            std::env::var_os("VERSION_CONTROL"),
+            std::env::var_os("SIMPLE_BACKUP_SUFFIX"),
+        )
+    }
+
+    /// Get the options, using the given values of `VERSION_CONTROL` and `SIMPLE_BACKUP_SUFFIX` for the
+    /// values which are not given.
+    fn options_with_env(
+        &self,
+        version_control: Option<OsString>,
+        simple_backup_suffix: Option<OsString>,
+    ) -> UResult<BackupOptions> {
+        // Like in GNU, a suffix enables backups too.
+        let implied = self.b || self.suffix.is_some();
+        let mode = match (self.backup.as_deref(), implied) {
+            (Some(control), _) if !control.is_empty() => BackupMode::parse(control, "backup type")?,
+            (Some(_), _) | (None, true) => match version_control {
+                Some(control) if !control.is_empty() => {
+                    BackupMode::parse(&control.to_string_lossy(), "$VERSION_CONTROL")?
+                }
+                _ => BackupMode::Existing,
+            },
+            (None, false) => BackupMode::None,
+        };
+
+        // A suffix which is not a single file name component would move the backup elsewhere.
+        let suffix = [self.suffix.clone(), simple_backup_suffix]
+            .into_iter()
+            .flatten()
+            .find(|suffix| !suffix.is_empty())
+            .filter(|suffix| !suffix.to_string_lossy().contains('/'))
+            .unwrap_or_else(|| DEFAULT_SUFFIX.into());
+
+        Ok(BackupOptions { mode, suffix })
+    }
+}
+
+/// How to make backups.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BackupOptions {
+    /// When to make backups, and how to name them.
+    pub mode: BackupMode,
+    /// The suffix of simple backups.
+    pub suffix: OsString,
+}
+
+impl Default for BackupOptions {
+    /// No backups.
+    fn default() -> Self {
+        BackupOptions {
+            mode: BackupMode::None,
+            suffix: DEFAULT_SUFFIX.into(),
+        }
+    }
+}
+
+/// Get the path to back `destination` up to, or `None` if no backups are made.
+///
+/// The directory of `destination` is read to find the next number of numbered backups.
+pub fn backup_path(destination: &Path, options: &BackupOptions) -> io::Result<Option<PathBuf>> {
+    let last_number = match options.mode {
+        BackupMode::None => return Ok(None),
+        BackupMode::Simple => None,
+        BackupMode::Existing => Some(last_backup_number(destination)?).filter(|&last| last > 0),
+        BackupMode::Numbered => Some(last_backup_number(destination)?),
+    };
+
+    let mut name = destination.as_os_str().to_owned();
+    match last_number {
+        Some(last) => name.push(format!(".~{}~", last + 1)),
+        None => name.push(&options.suffix),
+    }
+    Ok(Some(PathBuf::from(name)))
+}
+
+/// Rename `destination` to its backup path if it exists, and return the backup path.
+///
+/// Returns `None` if no backups are made or if `destination` does not exist.
+pub fn make_backup(destination: &Path, options: &BackupOptions) -> io::Result<Option<PathBuf>> {
+    if options.mode == BackupMode::None || destination.symlink_metadata().is_err() {
+        return Ok(None);
+    }
+    let Some(backup) = backup_path(destination, options)? else {
+        return Ok(None);
+    };
+    std::fs::rename(destination, &backup)?;
+    Ok(Some(backup))
+}
+
+/// Back `destination` up with [`make_backup`], describing the failure like GNU.
+pub fn back_up(destination: &Path, options: &BackupOptions) -> UResult<Option<PathBuf>> {
+    make_backup(destination, options).map_err(|error| {
+        let message = format!(
+            "cannot backup '{}': {}",
+            destination.display(),
+            io_error_message(&error)
+        );
+        UError::new(EXIT_FAILURE, message)
+    })
+}
+
+/// Check that the backup of `destination` would not replace `source`, which is about to replace it, and
+/// describe the refusal with `verb`, like `copied`.
+pub fn check_source(
+    source: &Path,
+    destination: &Path,
+    options: &BackupOptions,
+    verb: &str,
+) -> UResult<()> {
+    let Ok(Some(backup)) = backup_path(destination, options) else {
+        return Ok(());
+    };
+    match (source.symlink_metadata(), backup.symlink_metadata()) {
+        (Ok(a), Ok(b)) if same_file(&a, &b, source, &backup) => {
+            let message = format!(
+                "backing up '{}' might destroy source;  '{}' not {verb}",
+                destination.display(),
+                source.display()
+            );
+            Err(UError::new(EXIT_FAILURE, message))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Find the highest number of the numbered backups of `destination`, or 0 if there are none.
+fn last_backup_number(destination: &Path) -> io::Result<u64> {
+    let Some(file_name) = destination.file_name() else {
+        return Ok(0);
+    };
+    let directory = match destination.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    let mut last = 0;
+    for entry in std::fs::read_dir(directory)? {
+        if let Some(number) = backup_number(file_name, &entry?.file_name()) {
+            last = last.max(number);
+        }
+    }
+    Ok(last)
+}
+
+/// Parse `N` from a name of the form `<file_name>.~N~`.
+fn backup_number(file_name: &OsStr, name: &OsStr) -> Option<u64> {
+    let file_name = file_name.to_str()?;
+    let number = name
+        .to_str()?
+        .strip_prefix(file_name)?
+        .strip_prefix(".~")?
+        .strip_suffix('~')?;
+    if number.is_empty() || !number.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    number.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::fs;
+
+    #[derive(Parser)]
+    struct Args {
+        #[command(flatten)]
+        backup: BackupArgs,
+    }
+
+    fn parse(args: &[&str]) -> BackupArgs {
+        Args::parse_from(std::iter::once("cp").chain(args.iter().copied())).backup
+    }
+
+    fn mode(args: &[&str], version_control: Option<&str>) -> UResult<BackupMode> {
+        parse(args)
+            .options_with_env(version_control.map(OsString::from), None)
+            .map(|options| options.mode)
+    }
+
+    fn numbered() -> BackupOptions {
+        BackupOptions {
+            mode: BackupMode::Numbered,
+            suffix: DEFAULT_SUFFIX.into(),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(BackupMode::parse("off", "x"), Ok(BackupMode::None));
+        assert_eq!(BackupMode::parse("never", "x"), Ok(BackupMode::Simple));
+        assert_eq!(BackupMode::parse("nil", "x"), Ok(BackupMode::Existing));
+        assert_eq!(BackupMode::parse("t", "x"), Ok(BackupMode::Numbered));
+        assert_eq!(BackupMode::parse("nu", "x"), Ok(BackupMode::Numbered));
+        assert_eq!(BackupMode::parse("ex", "x"), Ok(BackupMode::Existing));
+
+        let error = BackupMode::parse("n", "backup type").unwrap_err();
+        assert_eq!((error.code, error.usage), (EXIT_FAILURE, true));
+        assert!(error
+            .message
+            .starts_with("ambiguous argument 'n' for 'backup type'\nValid arguments are:\n"));
+
+        let error = BackupMode::parse("nope", "backup type").unwrap_err();
+        assert!(error
+            .message
+            .starts_with("invalid argument 'nope' for 'backup type'\n"));
+        assert!(error.message.ends_with("\n  - 'numbered', 't'"));
+    }
+
+    #[test]
+    fn test_mode_from_args_and_env() {
+        assert_eq!(mode(&[], Some("numbered")), Ok(BackupMode::None));
+        assert_eq!(mode(&["-b"], None), Ok(BackupMode::Existing));
+        assert_eq!(mode(&["--backup"], None), Ok(BackupMode::Existing));
+        assert_eq!(mode(&["-b"], Some("numbered")), Ok(BackupMode::Numbered));
+        assert_eq!(mode(&["--backup"], Some("simple")), Ok(BackupMode::Simple));
+        assert_eq!(mode(&["--backup"], Some("")), Ok(BackupMode::Existing));
+        assert_eq!(
+            mode(&["--backup=simple"], Some("numbered")),
+            Ok(BackupMode::Simple)
+        );
+        assert_eq!(
+            mode(&["-b", "--backup=off"], Some("numbered")),
+            Ok(BackupMode::None)
+        );
+        assert_eq!(mode(&["-S", ".bak"], Some("t")), Ok(BackupMode::Numbered));
+
+        let error = mode(&["-b"], Some("nope")).unwrap_err();
+        assert!(error
+            .message
+            .starts_with("invalid argument 'nope' for '$VERSION_CONTROL'\n"));
+    }
+
+    #[test]
+    fn test_suffix_from_args_and_env() {
+        let suffix = |args: &[&str], env: Option<&str>| {
+            parse(args)
+                .options_with_env(None, env.map(OsString::from))
+                .unwrap()
+                .suffix
+        };
+        assert_eq!(suffix(&[], None), "~");
+        assert_eq!(suffix(&[], Some(".orig")), ".orig");
+        assert_eq!(suffix(&[], Some("")), "~");
+        assert_eq!(suffix(&[], Some("dir/.orig")), "~");
+        assert_eq!(suffix(&["-S", ".bak"], Some(".orig")), ".bak");
+        assert_eq!(suffix(&["--suffix=.bak"], None), ".bak");
+        assert_eq!(suffix(&["-S", "a/b"], Some(".orig")), "~");
+    }
+
+    #[test]
+    fn test_simple_backup() {
+        let directory = tempfile::tempdir().unwrap();
+        let destination = directory.path().join("file");
+        fs::write(&destination, "old").unwrap();
+
+        let options = BackupOptions {
+            mode: BackupMode::Simple,
+            suffix: ".orig".into(),
+        };
+        let backup = make_backup(&destination, &options).unwrap().unwrap();
+        assert_eq!(backup, directory.path().join("file.orig"));
+        assert_eq!(fs::read_to_string(backup).unwrap(), "old");
+        assert!(!destination.exists());
+
+        // Nothing to back up.
+        assert_eq!(make_backup(&destination, &options).unwrap(), None);
+        assert_eq!(
+            make_backup(&destination, &BackupOptions::default()).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_numbered_rollover() {
+        let directory = tempfile::tempdir().unwrap();
+        let destination = directory.path().join("file");
+        for name in [
+            "file",
+            "file.~1~",
+            "file.~2~",
+            "file.~x~",
+            "other.~7~",
+            "file.~~",
+        ] {
+            fs::write(directory.path().join(name), name).unwrap();
+        }
+
+        let backup = make_backup(&destination, &numbered()).unwrap().unwrap();
+        assert_eq!(backup, directory.path().join("file.~3~"));
+        assert_eq!(fs::read_to_string(backup).unwrap(), "file");
+
+        fs::write(directory.path().join("file.~10~"), "").unwrap();
+        assert_eq!(
+            backup_path(&destination, &numbered()).unwrap(),
+            Some(directory.path().join("file.~11~"))
+        );
+    }
+
+    #[test]
+    fn test_existing() {
+        let directory = tempfile::tempdir().unwrap();
+        let destination = directory.path().join("file");
+        let options = BackupOptions {
+            mode: BackupMode::Existing,
+            suffix: DEFAULT_SUFFIX.into(),
+        };
+
+        // Simple backups until there is a numbered one.
+        assert_eq!(
+            backup_path(&destination, &options).unwrap(),
+            Some(directory.path().join("file~"))
+        );
+        fs::write(directory.path().join("file.~1~"), "").unwrap();
+        assert_eq!(
+            backup_path(&destination, &options).unwrap(),
+            Some(directory.path().join("file.~2~"))
+        );
+    }
+
+    #[test]
+    fn test_check_source() {
+        let directory = tempfile::tempdir().unwrap();
+        let (destination, source) = (
+            directory.path().join("file"),
+            directory.path().join("file~"),
+        );
+        fs::write(&destination, "old").unwrap();
+        fs::write(&source, "new").unwrap();
+        let options = BackupOptions {
+            mode: BackupMode::Simple,
+            ..BackupOptions::default()
+        };
+
+        let error = check_source(&source, &destination, &options, "copied").unwrap_err();
+        assert_eq!(
+            error.message,
+            format!(
+                "backing up '{}' might destroy source;  '{}' not copied",
+                destination.display(),
+                source.display()
+            )
+        );
+        assert_eq!(
+            check_source(&source, &destination, &numbered(), "copied"),
+            Ok(())
+        );
+        assert_eq!(
+            back_up(&destination, &numbered()),
+            Ok(Some(directory.path().join("file.~1~")))
+        );
+    }
+
+    #[test]
+    fn test_relative_destination() {
+        assert_eq!(
+            backup_path(
+                Path::new("file"),
+                &BackupOptions {
+                    mode: BackupMode::Simple,
+                    ..BackupOptions::default()
+                }
+            )
+            .unwrap(),
+            Some(PathBuf::from("file~"))
+        );
+        assert!(backup_path(Path::new("file"), &numbered())
+            .unwrap()
+            .is_some());
+    }
+}
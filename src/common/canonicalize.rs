@@ -0,0 +1,386 @@
+//! Canonical paths, with the symbolic links, `.`, and `..` resolved, as printed by readlink and realpath,
+//! and paths relative to each other.
+//!
+//! Unlike with [`std::fs::canonicalize`], the files can be missing, depending on the [`Mode`]:
+//!
+//! ```
+//! use rust_coreutils::common::canonicalize::{canonicalize, relative_path, Mode};
+//! use std::path::Path;
+//!
+//! let directory = tempfile::tempdir().unwrap();
+//! let root = std::fs::canonicalize(directory.path()).unwrap();
+//! std::fs::create_dir(root.join("real")).unwrap();
+//! # #[cfg(unix)]
+//! # {
+//! std::os::unix::fs::symlink("real", root.join("link")).unwrap();
+//! let path = canonicalize(&root.join("link/../link/missing/./file"), Mode::Missing).unwrap();
+//! assert_eq!(path, root.join("real/missing/file"));
+//! assert!(canonicalize(&root.join("link/missing/file"), Mode::AllButLast).is_err());
+//! assert_eq!(
+//!     canonicalize(&root.join("link/missing"), Mode::AllButLast).unwrap(),
+//!     root.join("real/missing")
+//! );
+//! # }
+//!
+//! assert_eq!(
+//!     relative_path(Path::new("/a/b/file"), Path::new("/a/c/d")),
+//!     Path::new("../../b/file")
+//! );
+//! ```
+
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::is_separator;
+use std::path::{Component, Path, PathBuf};
+
+/// The number of symbolic links which can be followed while resolving a path, like in Linux.
+const MAX_LINKS: usize = 40;
+
+/// Which components of a path have to exist.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// All of them (`-e`).
+    Existing,
+    /// All of them except the last one, once the symbolic links are resolved (`-f`).
+    AllButLast,
+    /// None of them (`-m`). The components which are missing, or which follow a file which is not a
+    /// directory, are kept as they are, and so are the symbolic links in a loop.
+    Missing,
+}
+
+/// Make a path absolute, and resolve its symbolic links, `.`, and `..`, requiring the components given by
+/// `mode` to exist.
+///
+/// A `..` after a symbolic link goes to the parent of its target. It fails for an empty path, for a path
+/// going through a file which is not a directory, and with too many levels of symbolic links, except with
+/// [`Mode::Missing`].
+pub fn canonicalize(path: &Path, mode: Mode) -> io::Result<PathBuf> {
+    resolve(path, mode)
+}
+
+/// Make a path absolute, and resolve its `.` and `..` without looking at the files, like `realpath -s`.
+///
+/// The symbolic links are kept, and a `..` after one goes to the directory of the link. Only the file named
+/// by the result is checked, which has to exist with [`Mode::Existing`], can be missing with
+/// [`Mode::AllButLast`], and is not looked at with [`Mode::Missing`]. A trailing slash requires it to be a
+/// directory.
+pub fn normalize(path: &Path, mode: Mode) -> io::Result<PathBuf> {
+    if path.as_os_str().is_empty() {
+        return Err(not_found());
+    }
+    let absolute = match path.is_absolute() {
+        true => path.to_path_buf(),
+        false => env::current_dir()?.join(path),
+    };
+    let mut normal = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normal.pop();
+            }
+            component => normal.push(component),
+        }
+    }
+    if mode == Mode::Missing {
+        return Ok(normal);
+    }
+    let trailing = components(path).next().is_some_and(|last| last == ".");
+    let checked = match trailing {
+        true => normal.join(""),
+        false => normal.clone(),
+    };
+    match fs::metadata(&checked) {
+        Ok(_) => Ok(normal),
+        Err(error) if mode == Mode::AllButLast && error.kind() == ErrorKind::NotFound => Ok(normal),
+        Err(error) => Err(error),
+    }
+}
+
+fn resolve(path: &Path, mode: Mode) -> io::Result<PathBuf> {
+    if path.as_os_str().is_empty() {
+        return Err(not_found());
+    }
+    let path = match path.is_absolute() {
+        true => path.to_path_buf(),
+        false => env::current_dir()?.join(path),
+    };
+    // The components left to resolve, from the last one, which grow with the targets of the links.
+    let mut pending: Vec<OsString> = components(&path).collect();
+    let mut resolved = PathBuf::new();
+    let mut links = 0;
+    while let Some(component) = pending.pop() {
+        match Path::new(&component).components().next() {
+            Some(Component::CurDir) | None => {}
+            Some(Component::ParentDir) => {
+                resolved.pop();
+            }
+            Some(Component::Normal(name)) => {
+                resolved.push(name);
+                // Only `.` can follow the last component, for a trailing slash.
+                let last = pending.iter().all(|component| component == ".");
+                let metadata = match (fs::symlink_metadata(&resolved), mode) {
+                    (Ok(metadata), _) => metadata,
+                    (Err(_), Mode::Missing) => continue,
+                    (Err(error), Mode::AllButLast)
+                        if last && error.kind() == ErrorKind::NotFound =>
+                    {
+                        continue
+                    }
+                    (Err(error), _) => return Err(error),
+                };
+                if metadata.file_type().is_symlink() {
+                    links += 1;
+                    if links > MAX_LINKS {
+                        match mode {
+                            Mode::Missing => continue,
+                            _ => return Err(too_many_links()),
+                        }
+                    }
+                    let target = fs::read_link(&resolved)?;
+                    resolved.pop();
+                    pending.extend(components(&target));
+                    continue;
+                }
+                if mode == Mode::Missing || pending.is_empty() {
+                    continue;
+                }
+                if !metadata.is_dir() {
+                    return Err(not_a_directory());
+                }
+            }
+            // The root, or a prefix on Windows, which an absolute link target starts again from.
+            Some(root) => resolved.push(root),
+        }
+    }
+    Ok(resolved)
+}
+
+/// Get the components of a path from the last one, as strings to parse again, with a `.` for a trailing
+/// slash, which requires a directory.
+fn components(path: &Path) -> impl Iterator<Item = OsString> + '_ {
+    let bytes = path.as_os_str().as_encoded_bytes();
+    let trailing = bytes
+        .last()
+        .is_some_and(|&byte| is_separator(char::from(byte)))
+        || bytes.ends_with(b"/.");
+    let slash = trailing.then(|| OsString::from("."));
+    slash.into_iter().chain(
+        path.components()
+            .rev()
+            .map(|component| component.as_os_str().to_os_string()),
+    )
+}
+
+#[cfg(unix)]
+fn not_found() -> io::Error {
+    io::Error::from_raw_os_error(libc::ENOENT)
+}
+
+#[cfg(not(unix))]
+fn not_found() -> io::Error {
+    ErrorKind::NotFound.into()
+}
+
+#[cfg(unix)]
+fn too_many_links() -> io::Error {
+    io::Error::from_raw_os_error(libc::ELOOP)
+}
+
+#[cfg(not(unix))]
+fn too_many_links() -> io::Error {
+    io::Error::other("Too many levels of symbolic links")
+}
+
+#[cfg(unix)]
+fn not_a_directory() -> io::Error {
+    io::Error::from_raw_os_error(libc::ENOTDIR)
+}
+
+#[cfg(not(unix))]
+fn not_a_directory() -> io::Error {
+    ErrorKind::NotADirectory.into()
+}
+
+/// Get the path which leads from the directory `base` to `path`, with `..` for the components of `base`
+/// which are not in `path`, or `.` for `base` itself.
+///
+/// Both paths are expected to be canonical, like the ones of [`canonicalize`].
+pub fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    let common = path
+        .components()
+        .zip(base.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut relative: PathBuf = base.components().skip(common).map(|_| "..").collect();
+    relative.extend(path.components().skip(common));
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+    relative
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn test_canonicalize() {
+        use std::os::unix::fs::symlink;
+
+        let directory = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(directory.path()).unwrap();
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        fs::write(root.join("file"), "").unwrap();
+        symlink("a/b", root.join("relative")).unwrap();
+        symlink(root.join("a"), root.join("absolute")).unwrap();
+        symlink("../relative", root.join("a/chain")).unwrap();
+        symlink("missing", root.join("dangling")).unwrap();
+
+        // The paths which exist are the same in every mode.
+        for (path, canonical) in [
+            ("a/./b/", "a/b"),
+            ("relative/..", "a"),
+            ("absolute/b/../../file", "file"),
+            ("a/chain/../../file", "file"),
+        ] {
+            for mode in [Mode::Existing, Mode::AllButLast, Mode::Missing] {
+                assert_eq!(
+                    canonicalize(&root.join(path), mode).unwrap(),
+                    root.join(canonical),
+                    "{path} {mode:?}"
+                );
+            }
+        }
+        assert_eq!(
+            canonicalize(Path::new("/.."), Mode::Existing).unwrap(),
+            Path::new("/")
+        );
+        assert_eq!(
+            canonicalize(Path::new("src/../Cargo.toml"), Mode::Existing).unwrap(),
+            env::current_dir().unwrap().join("Cargo.toml")
+        );
+
+        let error = |path: &str, mode| {
+            canonicalize(&root.join(path), mode)
+                .unwrap_err()
+                .raw_os_error()
+        };
+        // The last component, once the links are resolved, can be missing with -f.
+        for (path, canonical) in [
+            ("dangling", "missing"),
+            ("a/chain/missing", "a/b/missing"),
+            ("missing/", "missing"),
+        ] {
+            assert_eq!(
+                canonicalize(&root.join(path), Mode::AllButLast).unwrap(),
+                root.join(canonical),
+                "{path}"
+            );
+            assert_eq!(error(path, Mode::Existing), Some(libc::ENOENT), "{path}");
+        }
+        for mode in [Mode::Existing, Mode::AllButLast] {
+            assert_eq!(error("dangling/x", mode), Some(libc::ENOENT));
+            assert_eq!(error("file/x", mode), Some(libc::ENOTDIR));
+            assert_eq!(error("file/", mode), Some(libc::ENOTDIR));
+        }
+        for (path, canonical) in [
+            ("a/chain/missing/c", "a/b/missing/c"),
+            ("dangling/x", "missing/x"),
+            ("missing/../a", "a"),
+            ("file/x/../y", "file/y"),
+        ] {
+            assert_eq!(
+                canonicalize(&root.join(path), Mode::Missing).unwrap(),
+                root.join(canonical),
+                "{path}"
+            );
+        }
+
+        // A loop is kept as it is with -m.
+        symlink("loop", root.join("loop")).unwrap();
+        for mode in [Mode::Existing, Mode::AllButLast] {
+            assert_eq!(error("loop/x", mode), Some(libc::ELOOP));
+        }
+        assert_eq!(
+            canonicalize(&root.join("loop/x"), Mode::Missing).unwrap(),
+            root.join("loop/x")
+        );
+        let error = canonicalize(Path::new(""), Mode::Missing).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_normalize() {
+        use std::os::unix::fs::symlink;
+
+        let directory = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(directory.path()).unwrap();
+        fs::create_dir_all(root.join("d/sub")).unwrap();
+        fs::write(root.join("file"), "").unwrap();
+        symlink("d", root.join("dir_link")).unwrap();
+        symlink("file", root.join("file_link")).unwrap();
+
+        for (path, normal) in [
+            ("dir_link/sub/", "dir_link/sub"),
+            ("dir_link/../file_link", "file_link"),
+            ("dir_link/missing", "dir_link/missing"),
+            // The components before the last one are not looked at.
+            ("missing/x", "missing/x"),
+            ("missing/../file", "file"),
+            ("missing/", "missing"),
+        ] {
+            assert_eq!(
+                normalize(&root.join(path), Mode::AllButLast).unwrap(),
+                root.join(normal),
+                "{path}"
+            );
+        }
+        assert_eq!(
+            normalize(Path::new("//d//./sub/"), Mode::Missing).unwrap(),
+            Path::new("/d/sub")
+        );
+        let error = |path: &str, mode| {
+            normalize(&root.join(path), mode)
+                .unwrap_err()
+                .raw_os_error()
+        };
+        assert_eq!(
+            error("dir_link/missing", Mode::Existing),
+            Some(libc::ENOENT)
+        );
+        assert_eq!(error("missing/x", Mode::Existing), Some(libc::ENOENT));
+        assert_eq!(error("file_link/", Mode::Existing), Some(libc::ENOTDIR));
+        assert_eq!(error("file_link/", Mode::AllButLast), Some(libc::ENOTDIR));
+        assert_eq!(error("file_link/x", Mode::AllButLast), Some(libc::ENOTDIR));
+        assert_eq!(
+            normalize(&root.join("file_link/x"), Mode::Missing).unwrap(),
+            root.join("file_link/x")
+        );
+        let error = normalize(Path::new(""), Mode::Missing).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_relative_path() {
+        for (path, base, relative) in [
+            ("/a/other/file", "/a/b", "../other/file"),
+            ("/a/b/c", "/a/b", "c"),
+            ("/a/b", "/a/b", "."),
+            ("/a", "/a/b/c", "../.."),
+            ("/etc/passwd", "/home/user", "../../etc/passwd"),
+            ("/", "/a/b", "../.."),
+            ("/a", "/", "a"),
+        ] {
+            assert_eq!(
+                relative_path(Path::new(path), Path::new(base)),
+                Path::new(relative),
+                "{path} from {base}"
+            );
+        }
+    }
+}
@@ -0,0 +1,442 @@
+//! Changing the owners and groups of files, shared by chown and chgrp.
+//!
+//! The changes go through a [`Chown`], so that they can be recorded instead of made, since only root can
+//! give files to other users:
+//!
+//! ```
+//! use rust_coreutils::common::chown_core::{chown, Chown, ChownOptions, Owner, Verbosity};
+//! use rust_coreutils::common::error::Reporter;
+//! use std::ffi::OsString;
+//! use std::io;
+//! use std::path::{Path, PathBuf};
+//!
+//! #[derive(Default)]
+//! struct Recorder(Vec<(PathBuf, Option<u32>, Option<u32>)>);
+//!
+//! impl Chown for Recorder {
+//!     fn chown(&mut self, path: &Path, uid: Option<u32>, gid: Option<u32>, _: bool) -> io::Result<()> {
+//!         self.0.push((path.to_path_buf(), uid, gid));
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let directory = tempfile::tempdir().unwrap();
+//! let file = directory.path().join("file");
+//! std::fs::write(&file, "").unwrap();
+//! let options = ChownOptions {
+//!     owner: Owner {
+//!         uid: Some(123456),
+//!         user_name: Some("123456".to_string()),
+//!         ..Owner::default()
+//!     },
+//!     verbosity: Verbosity::Changes,
+//!     ..ChownOptions::default()
+//! };
+//!
+//! let mut recorder = Recorder::default();
+//! let mut stdout = Vec::new();
+//! let mut stderr = Vec::new();
+//! let mut reporter = Reporter::new("chown", &mut stderr);
+//! let files = [OsString::from(&file)];
+//! chown(&files, &options, &mut recorder, &mut stdout, &mut reporter).unwrap();
+//! assert_eq!(recorder.0, [(file, Some(123456), None)]);
+//! assert!(String::from_utf8(stdout).unwrap().ends_with(" to 123456\n"));
+//! ```
+
+use crate::common::error::{io_error_message, Reporter, UError, UResult, EXIT_FAILURE};
+#[cfg(unix)]
+use crate::common::users;
+use crate::common::walk::{Walk, WalkError, WalkOptions};
+use std::ffi::OsString;
+use std::fs::{self, Metadata};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Changes the owner and group of files, which tests can replace to record the changes instead.
+pub trait Chown {
+    /// Change the owner and group of a file, or of the target of a symbolic link if `follow` is set. The
+    /// ids which are `None` are left unchanged.
+    fn chown(
+        &mut self,
+        path: &Path,
+        uid: Option<u32>,
+        gid: Option<u32>,
+        follow: bool,
+    ) -> io::Result<()>;
+}
+
+/// Changes the owner and group of files with the system calls.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemChown;
+
+impl Chown for SystemChown {
+    #[cfg(unix)]
+    fn chown(
+        &mut self,
+        path: &Path,
+        uid: Option<u32>,
+        gid: Option<u32>,
+        follow: bool,
+    ) -> io::Result<()> {
+        match follow {
+            true => std::os::unix::fs::chown(path, uid, gid),
+            false => std::os::unix::fs::lchown(path, uid, gid),
+        }
+    }
+
+    /// Files have no owners and groups on this system.
+    #[cfg(not(unix))]
+    fn chown(
+        &mut self,
+        _path: &Path,
+        _uid: Option<u32>,
+        _gid: Option<u32>,
+        _follow: bool,
+    ) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+/// Records the changes instead of making them, for the tests: the path, the ids, and whether to follow a
+/// symbolic link.
+#[cfg(all(test, unix))]
+#[derive(Default)]
+pub(crate) struct Recorder(pub(crate) Vec<(PathBuf, Option<u32>, Option<u32>, bool)>);
+
+#[cfg(all(test, unix))]
+impl Chown for Recorder {
+    fn chown(
+        &mut self,
+        path: &Path,
+        uid: Option<u32>,
+        gid: Option<u32>,
+        follow: bool,
+    ) -> io::Result<()> {
+        self.0.push((path.to_path_buf(), uid, gid, follow));
+        Ok(())
+    }
+}
+
+/// Which messages chown and chgrp print besides the errors.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Verbosity {
+    /// Only the errors.
+    #[default]
+    Errors,
+    /// A message for each file whose owner or group is changed (`-c`).
+    Changes,
+    /// A message for each file (`-v`).
+    Verbose,
+}
+
+/// The owner and group given to the files, and how they are described in the messages.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Owner {
+    /// The new owner, or `None` to keep it.
+    pub uid: Option<u32>,
+    /// The new group, or `None` to keep it.
+    pub gid: Option<u32>,
+    /// How the new owner is shown in the messages, like `alice` or `1000`. With a group but an empty user,
+    /// the messages are about the ownership and show `:GROUP`; without a user, they are about the group.
+    pub user_name: Option<String>,
+    /// How the new group is shown in the messages.
+    pub group_name: Option<String>,
+}
+
+/// How chown changes the owners.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChownOptions {
+    /// The owner and group to give to the files.
+    pub owner: Owner,
+    /// Only change the files with this owner (`--from`).
+    pub from_uid: Option<u32>,
+    /// Only change the files in this group (`--from`).
+    pub from_gid: Option<u32>,
+    /// Change the symbolic links given as operands rather than their targets (`-h`). The symbolic links
+    /// below the directories are never followed.
+    pub no_dereference: bool,
+    /// Change the files below the directories too.
+    pub recursive: bool,
+    /// Do not print the errors about the files, which still make chown fail.
+    pub silent: bool,
+    /// Which messages to print to stdout.
+    pub verbosity: Verbosity,
+}
+
+/// What happened to a file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Status {
+    Changed,
+    Retained,
+    Failed,
+}
+
+/// Change the owner and group of each operand with `chowner`, continuing after the failures, and report it
+/// from `reporter`'s utility.
+pub fn chown<W: Write>(
+    operands: &[OsString],
+    options: &ChownOptions,
+    chowner: &mut dyn Chown,
+    stdout: &mut dyn Write,
+    reporter: &mut Reporter<W>,
+) -> UResult<()> {
+    let mut changer = Changer {
+        options,
+        chowner,
+        stdout,
+        reporter,
+    };
+    for operand in operands {
+        let result = match options.recursive {
+            true => changer.change_tree(Path::new(operand)),
+            false => changer.change_operand(Path::new(operand)),
+        };
+        if let Err(error) = result {
+            return UError::write(error);
+        }
+    }
+    changer.stdout.flush().or_else(UError::write)
+}
+
+/// Changes the owners of the files.
+struct Changer<'a, W: Write> {
+    options: &'a ChownOptions,
+    chowner: &'a mut dyn Chown,
+    stdout: &'a mut dyn Write,
+    reporter: &'a mut Reporter<W>,
+}
+
+impl<W: Write> Changer<'_, W> {
+    /// Change an operand, or the target of a symbolic link unless `-h` is given.
+    fn change_operand(&mut self, path: &Path) -> io::Result<()> {
+        let follow = !self.options.no_dereference;
+        let metadata = match follow {
+            true => fs::metadata(path),
+            false => fs::symlink_metadata(path),
+        };
+        let metadata = match metadata {
+            Ok(metadata) => metadata,
+            Err(error) => {
+                let link = fs::symlink_metadata(path).ok().filter(Metadata::is_symlink);
+                let action = match link {
+                    Some(_) => "cannot dereference",
+                    None => "cannot access",
+                };
+                self.error(format_args!(
+                    "{action} '{}': {}",
+                    path.display(),
+                    io_error_message(&error)
+                ));
+                return self.report(path, Status::Failed, link.as_ref().map(ids));
+            }
+        };
+        self.change(path, &metadata, follow)
+    }
+
+    /// Change the owners of `root` and of the files below it, never following the symbolic links. The
+    /// directories are changed after their contents.
+    fn change_tree(&mut self, root: &Path) -> io::Result<()> {
+        let walk_options = WalkOptions {
+            post_order: true,
+            ..WalkOptions::default()
+        };
+        let mut walk = Walk::new(vec![root.to_path_buf()], walk_options);
+        // The last error, which is about reading a directory if the directory comes next.
+        let mut last_error: Option<(PathBuf, io::Error)> = None;
+        loop {
+            let item = walk.next();
+            if let Some((path, error)) = last_error.take() {
+                let message = io_error_message(&error);
+                match &item {
+                    // Like in GNU, a directory which cannot be read is left alone.
+                    Some(Ok(entry)) if entry.path == path && entry.is_dir() => {
+                        self.error(format_args!(
+                            "cannot read directory '{}': {message}",
+                            path.display()
+                        ));
+                        self.report(&path, Status::Failed, None)?;
+                        continue;
+                    }
+                    _ => {
+                        self.error(format_args!(
+                            "cannot access '{}': {message}",
+                            path.display()
+                        ));
+                        self.report(&path, Status::Failed, None)?;
+                    }
+                }
+            }
+            match item {
+                None => return Ok(()),
+                Some(Ok(entry)) => self.change(&entry.path, &entry.metadata, false)?,
+                Some(Err(WalkError::Io { path, error })) => last_error = Some((path, error)),
+                // The symbolic links are not followed, so there are no loops.
+                Some(Err(WalkError::Loop { .. })) => {}
+            }
+        }
+    }
+
+    /// Change the owner and group of a file whose metadata are known, unless they do not match `--from`.
+    fn change(&mut self, path: &Path, metadata: &Metadata, follow: bool) -> io::Result<()> {
+        let old = ids(metadata);
+        let options = self.options;
+        let matches = options.from_uid.is_none_or(|uid| uid == old.0)
+            && options.from_gid.is_none_or(|gid| gid == old.1);
+        if !matches {
+            return self.report(path, Status::Retained, Some(old));
+        }
+
+        let (uid, gid) = (options.owner.uid, options.owner.gid);
+        let status = match self.chowner.chown(path, uid, gid, follow) {
+            Ok(()) if (uid.unwrap_or(old.0), gid.unwrap_or(old.1)) == old => Status::Retained,
+            Ok(()) => Status::Changed,
+            Err(error) => {
+                let action = match uid {
+                    Some(_) => "changing ownership of",
+                    None => "changing group of",
+                };
+                self.error(format_args!(
+                    "{action} '{}': {}",
+                    path.display(),
+                    io_error_message(&error)
+                ));
+                Status::Failed
+            }
+        };
+        self.report(path, status, Some(old))
+    }
+
+    /// Describe what happened to a file like GNU, as requested, given its old owner and group if they are
+    /// known.
+    fn report(&mut self, path: &Path, status: Status, old: Option<(u32, u32)>) -> io::Result<()> {
+        let shown = match self.options.verbosity {
+            Verbosity::Errors => false,
+            Verbosity::Changes => status == Status::Changed,
+            Verbosity::Verbose => true,
+        };
+        if !shown {
+            return Ok(());
+        }
+
+        let owner = &self.options.owner;
+        let new = describe(owner.user_name.clone(), owner.group_name.clone());
+        let old = old.and_then(|(uid, gid)| {
+            describe(
+                owner.user_name.as_ref().map(|_| name_for_uid(uid)),
+                owner.group_name.as_ref().map(|_| name_for_gid(gid)),
+            )
+        });
+        // What the messages are about: the ownership, the group alone like chgrp, or nothing.
+        let about = match (&owner.user_name, &owner.group_name) {
+            (Some(_), _) => Some("ownership"),
+            (None, Some(_)) => Some("group"),
+            (None, None) => None,
+        };
+        let path = path.display();
+        match (status, about, old, new) {
+            (Status::Changed, Some(about), Some(old), Some(new)) => {
+                writeln!(
+                    self.stdout,
+                    "changed {about} of '{path}' from {old} to {new}"
+                )
+            }
+            (Status::Changed, _, _, _) => {
+                writeln!(self.stdout, "no change to ownership of '{path}'")
+            }
+            (Status::Retained, Some(about), Some(old), _) => {
+                writeln!(self.stdout, "{about} of '{path}' retained as {old}")
+            }
+            (Status::Retained, _, _, _) => writeln!(self.stdout, "ownership of '{path}' retained"),
+            (Status::Failed, Some(about), Some(old), Some(new)) => writeln!(
+                self.stdout,
+                "failed to change {about} of '{path}' from {old} to {new}"
+            ),
+            (Status::Failed, Some(about), None, Some(new)) => {
+                writeln!(self.stdout, "failed to change {about} of '{path}' to {new}")
+            }
+            (Status::Failed, _, _, _) => {
+                writeln!(self.stdout, "failed to change ownership of '{path}'")
+            }
+        }
+    }
+
+    /// Report an error about a file, unless silenced with `-f`, and remember the failure.
+    fn error(&mut self, message: std::fmt::Arguments) {
+        match self.options.silent {
+            true => self.reporter.set_failed(),
+            false => self.reporter.error(message),
+        }
+    }
+}
+
+/// Get the owner and group of a file, for `--reference`.
+pub fn reference_ids(path: &Path) -> UResult<(u32, u32)> {
+    match fs::metadata(path) {
+        Ok(metadata) => Ok(ids(&metadata)),
+        Err(error) => Err(UError::new(
+            EXIT_FAILURE,
+            format!(
+                "failed to get attributes of '{}': {}",
+                path.display(),
+                io_error_message(&error)
+            ),
+        )),
+    }
+}
+
+/// Describe an owner and group like GNU, like `alice:staff`, `alice`, or `staff`.
+fn describe(user: Option<String>, group: Option<String>) -> Option<String> {
+    match (user, group) {
+        (Some(user), Some(group)) => Some(format!("{user}:{group}")),
+        (user, group) => user.or(group),
+    }
+}
+
+/// Get the owner and group of a file.
+#[cfg(unix)]
+pub fn ids(metadata: &Metadata) -> (u32, u32) {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.uid(), metadata.gid())
+}
+
+/// Get the owner and group of a file, which do not exist on this system.
+#[cfg(not(unix))]
+pub fn ids(_metadata: &Metadata) -> (u32, u32) {
+    (0, 0)
+}
+
+/// Get the name of a user, or its number if it has none.
+pub fn name_for_uid(uid: u32) -> String {
+    #[cfg(unix)]
+    if let Ok(Some(name)) = users::name_for_uid(uid) {
+        return name;
+    }
+    uid.to_string()
+}
+
+/// Get the name of a group, or its number if it has none.
+pub fn name_for_gid(gid: u32) -> String {
+    #[cfg(unix)]
+    if let Ok(Some(name)) = users::name_for_gid(gid) {
+        return name;
+    }
+    gid.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        let name = |name: &str| Some(name.to_string());
+        assert_eq!(
+            describe(name("alice"), name("staff")).as_deref(),
+            Some("alice:staff")
+        );
+        assert_eq!(describe(name(""), name("staff")).as_deref(), Some(":staff"));
+        assert_eq!(describe(name("alice"), None).as_deref(), Some("alice"));
+        assert_eq!(describe(None, name("staff")).as_deref(), Some("staff"));
+        assert_eq!(describe(None, None), None);
+    }
+}
@@ -0,0 +1,374 @@
+//! The colors of file names, as configured by the `LS_COLORS` environment variable that dircolors sets.
+//!
+//! `LS_COLORS` is a colon-separated list of `key=sequence` entries, where the key is either one of the
+//! two-letter names of an [`Indicator`], like `di` for directories, or `*suffix` for the files whose name
+//! ends with the suffix, like `*.tar`. The sequences are the parameters of SGR escape sequences, like
+//! `01;34`, and can contain backslash escapes like `\e` and `\x1b`, and caret notation like `^[`:
+//!
+//! ```
+//! use rust_coreutils::common::colors::{parse_ls_colors, Indicator};
+//!
+//! let colors = parse_ls_colors(b"di=01;31:*.tar=01;35").unwrap();
+//! assert_eq!(colors.get(Indicator::Directory), Some(&b"01;31"[..]));
+//! assert_eq!(colors.get(Indicator::Link), Some(&b"01;36"[..]));
+//! assert_eq!(colors.extension(b"backup.TAR"), Some(&b"01;35"[..]));
+//! assert!(parse_ls_colors(b"zz=1").is_err());
+//! ```
+
+use std::fmt;
+
+/// The kinds of files that have a color, and the parts of the escape sequences, with their keys.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Indicator {
+    /// `lc`: the start of an escape sequence.
+    Left,
+    /// `rc`: the end of an escape sequence.
+    Right,
+    /// `ec`: the whole sequence after a name, in place of `lc`, `rs`, and `rc`.
+    End,
+    /// `rs`: the reset to the ordinary colors.
+    Reset,
+    /// `no`: the default color of everything else.
+    Normal,
+    /// `fi`: regular files.
+    File,
+    /// `di`: directories.
+    Directory,
+    /// `ln`: symbolic links, or `target` to color them like the files they point to.
+    Link,
+    /// `pi`: named pipes.
+    Fifo,
+    /// `so`: sockets.
+    Socket,
+    /// `bd`: block devices.
+    BlockDevice,
+    /// `cd`: character devices.
+    CharDevice,
+    /// `mi`: the missing targets of symbolic links.
+    Missing,
+    /// `or`: the symbolic links whose target is missing.
+    Orphan,
+    /// `ex`: executable files.
+    Executable,
+    /// `do`: doors, which only exist on Solaris.
+    Door,
+    /// `su`: setuid files.
+    Setuid,
+    /// `sg`: setgid files.
+    Setgid,
+    /// `st`: directories with the sticky bit.
+    Sticky,
+    /// `ow`: directories which others can write to.
+    OtherWritable,
+    /// `tw`: directories which others can write to, with the sticky bit.
+    StickyOtherWritable,
+    /// `ca`: files with capabilities.
+    Capability,
+    /// `mh`: files with several hard links.
+    MultiHardlink,
+    /// `cl`: the sequence which clears to the end of the line.
+    ClearToEol,
+}
+
+/// Every indicator, in the order of their values, with its key.
+const INDICATORS: [(Indicator, &[u8; 2]); 24] = [
+    (Indicator::Left, b"lc"),
+    (Indicator::Right, b"rc"),
+    (Indicator::End, b"ec"),
+    (Indicator::Reset, b"rs"),
+    (Indicator::Normal, b"no"),
+    (Indicator::File, b"fi"),
+    (Indicator::Directory, b"di"),
+    (Indicator::Link, b"ln"),
+    (Indicator::Fifo, b"pi"),
+    (Indicator::Socket, b"so"),
+    (Indicator::BlockDevice, b"bd"),
+    (Indicator::CharDevice, b"cd"),
+    (Indicator::Missing, b"mi"),
+    (Indicator::Orphan, b"or"),
+    (Indicator::Executable, b"ex"),
+    (Indicator::Door, b"do"),
+    (Indicator::Setuid, b"su"),
+    (Indicator::Setgid, b"sg"),
+    (Indicator::Sticky, b"st"),
+    (Indicator::OtherWritable, b"ow"),
+    (Indicator::StickyOtherWritable, b"tw"),
+    (Indicator::Capability, b"ca"),
+    (Indicator::MultiHardlink, b"mh"),
+    (Indicator::ClearToEol, b"cl"),
+];
+
+/// The colors to use, which are those of GNU by default.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LsColors {
+    /// The sequence of every indicator, in the order of [`INDICATORS`], if it has one.
+    indicators: [Option<Vec<u8>>; INDICATORS.len()],
+    /// The suffixes and their sequences, in the order in which they were defined.
+    extensions: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+impl Default for LsColors {
+    fn default() -> Self {
+        let sequence = |indicator| -> Option<&[u8]> {
+            match indicator {
+                Indicator::Left => Some(b"\x1b["),
+                Indicator::Right => Some(b"m"),
+                Indicator::Reset => Some(b"0"),
+                Indicator::Directory => Some(b"01;34"),
+                Indicator::Link => Some(b"01;36"),
+                Indicator::Fifo => Some(b"33"),
+                Indicator::Socket | Indicator::Door => Some(b"01;35"),
+                Indicator::BlockDevice | Indicator::CharDevice => Some(b"01;33"),
+                Indicator::Executable => Some(b"01;32"),
+                Indicator::Setuid => Some(b"37;41"),
+                Indicator::Setgid => Some(b"30;43"),
+                Indicator::Sticky => Some(b"37;44"),
+                Indicator::OtherWritable => Some(b"34;42"),
+                Indicator::StickyOtherWritable => Some(b"30;42"),
+                Indicator::ClearToEol => Some(b"\x1b[K"),
+                _ => None,
+            }
+        };
+        LsColors {
+            indicators: INDICATORS.map(|(indicator, _)| sequence(indicator).map(<[u8]>::to_vec)),
+            extensions: Vec::new(),
+        }
+    }
+}
+
+impl LsColors {
+    /// Get the sequence of an indicator, if it has one.
+    pub fn get(&self, indicator: Indicator) -> Option<&[u8]> {
+        self.indicators[indicator as usize].as_deref()
+    }
+
+    /// Check whether an indicator has a sequence which changes the colors: not an empty one, nor `0` or
+    /// `00`, which are the ordinary colors.
+    pub fn is_colored(&self, indicator: Indicator) -> bool {
+        !matches!(self.get(indicator), None | Some(b"" | b"0" | b"00"))
+    }
+
+    /// Get the sequence of the last suffix which ends `name`, ignoring the case of ASCII letters.
+    pub fn extension(&self, name: &[u8]) -> Option<&[u8]> {
+        self.extensions
+            .iter()
+            .rev()
+            .find(|(suffix, _)| {
+                name.len() >= suffix.len()
+                    && name[name.len() - suffix.len()..].eq_ignore_ascii_case(suffix)
+            })
+            .map(|(_, sequence)| &sequence[..])
+    }
+
+    /// Check whether symbolic links are colored like the files they point to, with `ln=target`.
+    pub fn link_as_target(&self) -> bool {
+        self.get(Indicator::Link) == Some(b"target")
+    }
+}
+
+/// The error of an `LS_COLORS` value which cannot be parsed, whose colors should not be used at all.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LsColorsError {
+    /// The key of the entry that failed, if it was not a suffix. Like in GNU, it is reported as unrecognized
+    /// even if it is only its sequence that is invalid.
+    pub prefix: Option<Vec<u8>>,
+}
+
+impl fmt::Display for LsColorsError {
+    /// Format the messages of GNU, one per line.
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(prefix) = &self.prefix {
+            writeln!(
+                formatter,
+                "unrecognized prefix: '{}'",
+                String::from_utf8_lossy(prefix)
+            )?;
+        }
+        write!(
+            formatter,
+            "unparsable value for LS_COLORS environment variable"
+        )
+    }
+}
+
+impl std::error::Error for LsColorsError {}
+
+/// Parse the value of `LS_COLORS` over the default colors.
+///
+/// Empty entries are skipped, and a later entry overrides an earlier one with the same key. Like GNU, a
+/// suffix can also be a whole name, like `*README`.
+pub fn parse_ls_colors(value: &[u8]) -> Result<LsColors, LsColorsError> {
+    let mut colors = LsColors::default();
+    let mut rest = value;
+    loop {
+        match rest {
+            [] => return Ok(colors),
+            [b':', tail @ ..] => rest = tail,
+            [b'*', tail @ ..] => {
+                let fail = || LsColorsError { prefix: None };
+                let (suffix, tail) = parse_sequence(tail, true).ok_or_else(fail)?;
+                let [b'=', tail @ ..] = tail else {
+                    return Err(fail());
+                };
+                let (sequence, tail) = parse_sequence(tail, false).ok_or_else(fail)?;
+                colors.extensions.push((suffix, sequence));
+                rest = tail;
+            }
+            [first, second, b'=', tail @ ..] => {
+                let key = [*first, *second];
+                let fail = || LsColorsError {
+                    prefix: Some(key.to_vec()),
+                };
+                let index = INDICATORS
+                    .iter()
+                    .position(|(_, name)| **name == key)
+                    .ok_or_else(fail)?;
+                let (sequence, tail) = parse_sequence(tail, false).ok_or_else(fail)?;
+                colors.indicators[index] = Some(sequence);
+                rest = tail;
+            }
+            // A key without a sequence.
+            _ => return Err(LsColorsError { prefix: None }),
+        }
+    }
+}
+
+/// Parse a key or a sequence up to the next `:`, or `=` as well for a key, expanding the backslash escapes
+/// and the caret notation. Return the bytes and what follows them, or `None` if an escape is incomplete.
+fn parse_sequence(input: &[u8], is_key: bool) -> Option<(Vec<u8>, &[u8])> {
+    let mut output = Vec::new();
+    let mut rest = input;
+    loop {
+        match rest {
+            [] | [b':', ..] => return Some((output, rest)),
+            [b'=', ..] if is_key => return Some((output, rest)),
+            [b'\\', tail @ ..] => {
+                let (byte, tail) = parse_escape(tail)?;
+                output.push(byte);
+                rest = tail;
+            }
+            [b'^', b'?', tail @ ..] => {
+                output.push(0x7f);
+                rest = tail;
+            }
+            [b'^', byte @ b'@'..=b'~', tail @ ..] => {
+                output.push(byte & 0x1f);
+                rest = tail;
+            }
+            [b'^', ..] => return None,
+            [byte, tail @ ..] => {
+                output.push(*byte);
+                rest = tail;
+            }
+        }
+    }
+}
+
+/// Parse what follows a backslash. Like in GNU, octal and hexadecimal values take as many digits as there
+/// are, and keep the lowest byte.
+fn parse_escape(input: &[u8]) -> Option<(u8, &[u8])> {
+    fn number(digits: &[u8], radix: u32) -> (u8, &[u8]) {
+        let count = digits
+            .iter()
+            .take_while(|&&digit| char::from(digit).is_digit(radix))
+            .count();
+        let value = digits[..count].iter().fold(0u8, |value, &digit| {
+            let digit = char::from(digit).to_digit(radix).expect("a digit") as u8;
+            value.wrapping_mul(radix as u8).wrapping_add(digit)
+        });
+        (value, &digits[count..])
+    }
+    let (&first, tail) = input.split_first()?;
+    Some(match first {
+        b'0'..=b'7' => number(input, 8),
+        b'x' | b'X' => number(tail, 16),
+        b'a' => (0x07, tail),
+        b'b' => (0x08, tail),
+        b'e' => (0x1b, tail),
+        b'f' => (0x0c, tail),
+        b'n' => (b'\n', tail),
+        b'r' => (b'\r', tail),
+        b't' => (b'\t', tail),
+        b'v' => (0x0b, tail),
+        b'?' => (0x7f, tail),
+        b'_' => (b' ', tail),
+        other => (other, tail),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults() {
+        let colors = LsColors::default();
+        assert_eq!(colors.get(Indicator::Directory), Some(&b"01;34"[..]));
+        assert_eq!(colors.get(Indicator::File), None);
+        assert!(colors.is_colored(Indicator::Executable));
+        assert!(!colors.is_colored(Indicator::Reset));
+        assert!(!colors.is_colored(Indicator::Orphan));
+        assert!(!colors.link_as_target());
+        assert_eq!(parse_ls_colors(b""), Ok(colors));
+    }
+
+    #[test]
+    fn test_parse() {
+        let colors =
+            parse_ls_colors(b"::di=1:fi=:no=00:ln=target:*.tar=31:*README=32:*.TAR=33:di=4:")
+                .unwrap();
+        assert_eq!(colors.get(Indicator::Directory), Some(&b"4"[..]));
+        assert_eq!(colors.get(Indicator::File), Some(&b""[..]));
+        assert!(!colors.is_colored(Indicator::File));
+        assert!(!colors.is_colored(Indicator::Normal));
+        assert!(colors.link_as_target());
+        // The last suffix wins, whatever the case.
+        assert_eq!(colors.extension(b"a.tar"), Some(&b"33"[..]));
+        assert_eq!(colors.extension(b"README"), Some(&b"32"[..]));
+        assert_eq!(colors.extension(b"tar"), None);
+        // The sequences may contain `=`, but not the keys.
+        let colors = parse_ls_colors(b"*x=1=3:rs=a=b").unwrap();
+        assert_eq!(colors.extension(b"x"), Some(&b"1=3"[..]));
+        assert_eq!(colors.get(Indicator::Reset), Some(&b"a=b"[..]));
+    }
+
+    #[test]
+    fn test_escapes() {
+        let colors =
+            parse_ls_colors(br"lc=\e[:rc=\x6d\x:ec=^[[0m^?:di=\1234\_\\\:\q:ln=\101\0").unwrap();
+        assert_eq!(colors.get(Indicator::Left), Some(&b"\x1b["[..]));
+        assert_eq!(colors.get(Indicator::Right), Some(&b"m\0"[..]));
+        assert_eq!(colors.get(Indicator::End), Some(&b"\x1b[0m\x7f"[..]));
+        // The octal values wrap around, and a backslash escapes the separators.
+        assert_eq!(colors.get(Indicator::Directory), Some(&b"\x9c \\:q"[..]));
+        assert_eq!(colors.get(Indicator::Link), Some(&b"A\0"[..]));
+    }
+
+    #[test]
+    fn test_malformed() {
+        let unparsable = |prefix: Option<&[u8]>| {
+            Err(LsColorsError {
+                prefix: prefix.map(<[u8]>::to_vec),
+            })
+        };
+        assert_eq!(parse_ls_colors(b"di=1:zz=1"), unparsable(Some(b"zz")));
+        assert_eq!(parse_ls_colors(b"di"), unparsable(None));
+        assert_eq!(parse_ls_colors(b"di:fi=1"), unparsable(None));
+        assert_eq!(parse_ls_colors(b"d"), unparsable(None));
+        assert_eq!(parse_ls_colors(b"*.tar"), unparsable(None));
+        assert_eq!(parse_ls_colors(b"*.tar:di=1"), unparsable(None));
+        assert_eq!(parse_ls_colors(b"*.t^ =1"), unparsable(None));
+        // An invalid sequence blames the key.
+        assert_eq!(parse_ls_colors(b"di=^"), unparsable(Some(b"di")));
+        assert_eq!(parse_ls_colors(b"di=\\"), unparsable(Some(b"di")));
+        assert_eq!(parse_ls_colors(b"di=^ "), unparsable(Some(b"di")));
+        assert_eq!(
+            parse_ls_colors(b"zz=1").unwrap_err().to_string(),
+            "unrecognized prefix: 'zz'\nunparsable value for LS_COLORS environment variable"
+        );
+        assert_eq!(
+            parse_ls_colors(b"*").unwrap_err().to_string(),
+            "unparsable value for LS_COLORS environment variable"
+        );
+    }
+}
@@ -0,0 +1,392 @@
+//! Orders of text other than byte by byte: decimal numbers for `sort -n`, sizes with units for `sort -h`,
+//! and versions for `sort -V` and `ls -v`, all like GNU's in the C locale.
+//!
+//! ```
+//! use rust_coreutils::common::compare::{compare_human_numbers, compare_numbers, compare_versions};
+//! use std::cmp::Ordering;
+//!
+//! assert_eq!(compare_numbers(b"9", b"10"), Ordering::Less);
+//! assert_eq!(compare_human_numbers(b"900K", b"10M"), Ordering::Less);
+//! assert_eq!(compare_human_numbers(b"10M", b"2G"), Ordering::Less);
+//! assert_eq!(compare_versions(b"1.0.5", b"1.0.10"), Ordering::Less);
+//! assert_eq!(compare_versions(b"foo~beta", b"foo"), Ordering::Less);
+//! ```
+
+use std::cmp::Ordering;
+
+/// Check whether a byte is a blank, which is skipped before numbers and separates fields in `sort` and
+/// `uniq`. A newline is only in lines which end with NUL.
+pub fn is_blank(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\n')
+}
+
+/// Skip the blanks at the start of some text.
+fn trim_blanks(text: &[u8]) -> &[u8] {
+    let start = text
+        .iter()
+        .position(|&byte| !is_blank(byte))
+        .unwrap_or(text.len());
+    &text[start..]
+}
+
+/// A decimal number at the start of some text, split into its parts without leading or trailing zeros so
+/// that numbers compare without being converted.
+struct Number<'a> {
+    negative: bool,
+    integer: &'a [u8],
+    fraction: &'a [u8],
+}
+
+impl<'a> Number<'a> {
+    /// Parse the number after the leading blanks, which is zero if there is none.
+    fn parse(text: &'a [u8]) -> Self {
+        let mut rest = trim_blanks(text);
+        let negative = rest.first() == Some(&b'-');
+        if negative {
+            rest = &rest[1..];
+        }
+
+        let digits = |data: &'a [u8]| {
+            let length = data.iter().take_while(|byte| byte.is_ascii_digit()).count();
+            data.split_at(length)
+        };
+        let (integer, rest) = digits(rest);
+        let fraction = match rest.strip_prefix(b".") {
+            Some(rest) => digits(rest).0,
+            None => &[],
+        };
+
+        let integer = &integer[integer.iter().take_while(|&&byte| byte == b'0').count()..];
+        let zeros = fraction
+            .iter()
+            .rev()
+            .take_while(|&&byte| byte == b'0')
+            .count();
+        let fraction = &fraction[..fraction.len() - zeros];
+        Number {
+            // Negative zero is zero.
+            negative: negative && !(integer.is_empty() && fraction.is_empty()),
+            integer,
+            fraction,
+        }
+    }
+
+    /// Compare the absolute values: the longer integer part is larger, then the digits decide.
+    fn compare_magnitude(&self, other: &Number) -> Ordering {
+        self.integer
+            .len()
+            .cmp(&other.integer.len())
+            .then_with(|| self.integer.cmp(other.integer))
+            .then_with(|| self.fraction.cmp(other.fraction))
+    }
+
+    /// Compare the values.
+    fn compare(&self, other: &Number) -> Ordering {
+        match (self.negative, other.negative) {
+            (true, true) => other.compare_magnitude(self),
+            (false, false) => self.compare_magnitude(other),
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+        }
+    }
+}
+
+/// The order of magnitude of the unit after the number at the start of some text, after blanks: from 1
+/// for `K` or `k` to 8 for `Y`, negative for negative numbers. Zero has no unit.
+fn unit_order(text: &[u8]) -> i8 {
+    let text = trim_blanks(text);
+    let negative = text.first() == Some(&b'-');
+    let number = &text[usize::from(negative)..];
+    let digits = |from: usize| {
+        number[from..]
+            .iter()
+            .take_while(|byte| byte.is_ascii_digit())
+            .count()
+    };
+    let mut length = digits(0);
+    if number.get(length) == Some(&b'.') {
+        length += 1 + digits(length + 1);
+    }
+    if number[..length]
+        .iter()
+        .all(|&byte| byte == b'0' || byte == b'.')
+    {
+        return 0;
+    }
+    let order = match number.get(length) {
+        Some(b'K' | b'k') => 1,
+        Some(b'M') => 2,
+        Some(b'G') => 3,
+        Some(b'T') => 4,
+        Some(b'P') => 5,
+        Some(b'E') => 6,
+        Some(b'Z') => 7,
+        Some(b'Y') => 8,
+        _ => 0,
+    };
+    if negative {
+        -order
+    } else {
+        order
+    }
+}
+
+/// Compare the decimal numbers at the start of two texts, after blanks, with an optional `-` sign and
+/// decimal point. Texts which do not start with a number compare as zero.
+pub fn compare_numbers(a: &[u8], b: &[u8]) -> Ordering {
+    Number::parse(a).compare(&Number::parse(b))
+}
+
+/// Compare the sizes at the start of two texts, like `du -h` prints them: a number with a larger unit is
+/// larger, whatever its digits, so that `900K` is below `10M`, which is below `2G`. Numbers without a unit
+/// are below the ones with a unit, and numbers with the same unit compare like [`compare_numbers`].
+pub fn compare_human_numbers(a: &[u8], b: &[u8]) -> Ordering {
+    unit_order(a)
+        .cmp(&unit_order(b))
+        .then_with(|| compare_numbers(a, b))
+}
+
+/// Compare two file names or versions, like GNU's `filevercmp`.
+///
+/// Runs of digits compare as numbers, so that `1.0.5` is below `1.0.10`. Between them, letters come
+/// before the other bytes, and `~` before everything, even the end: `foo~beta` is below `foo`. The empty
+/// name comes first, then `.`, `..`, and the other names starting with a dot. Suffixes like `.tar.gz` are
+/// only compared when the rest of the names are equal.
+pub fn compare_versions(a: &[u8], b: &[u8]) -> Ordering {
+    compare_file_versions(a, b, false)
+}
+
+/// Compare two versions like [`compare_versions`], with lower case ASCII letters as upper case, for
+/// `sort -fV`.
+pub fn compare_versions_ignore_case(a: &[u8], b: &[u8]) -> Ordering {
+    compare_file_versions(a, b, true)
+}
+
+fn compare_file_versions(a: &[u8], b: &[u8], fold_case: bool) -> Ordering {
+    match (a, b) {
+        ([], []) => return Ordering::Equal,
+        ([], _) => return Ordering::Less,
+        (_, []) => return Ordering::Greater,
+        _ => {}
+    }
+    let (a_dot, b_dot) = (a[0] == b'.', b[0] == b'.');
+    if a_dot || b_dot {
+        if a_dot != b_dot {
+            return b_dot.cmp(&a_dot);
+        }
+        for special in [&b"."[..], b".."] {
+            match (a == special, b == special) {
+                (true, true) => return Ordering::Equal,
+                (true, false) => return Ordering::Less,
+                (false, true) => return Ordering::Greater,
+                (false, false) => {}
+            }
+        }
+    }
+
+    let (a_prefix, b_prefix) = (&a[..prefix_length(a)], &b[..prefix_length(b)]);
+    let ordering = compare_version_parts(a_prefix, b_prefix, fold_case);
+    if ordering.is_ne() || (a_prefix.len() == a.len() && b_prefix.len() == b.len()) {
+        return ordering;
+    }
+    compare_version_parts(a, b, fold_case)
+}
+
+/// The length of a name without its longest suffix matching `(\.[A-Za-z~][A-Za-z0-9~]*)*$`, which can be
+/// the whole name, like `.bashrc`.
+fn prefix_length(name: &[u8]) -> usize {
+    let mut i = 0;
+    loop {
+        let prefix = i;
+        while i + 1 < name.len()
+            && name[i] == b'.'
+            && (name[i + 1].is_ascii_alphabetic() || name[i + 1] == b'~')
+        {
+            i += 2;
+            while i < name.len() && (name[i].is_ascii_alphanumeric() || name[i] == b'~') {
+                i += 1;
+            }
+        }
+        if i >= name.len() {
+            return prefix;
+        }
+        i += 1;
+    }
+}
+
+/// The weight of a byte outside of digits, or of the end, in a version: `~` comes first, then the end,
+/// letters, and the other bytes.
+fn version_weight(byte: Option<u8>, fold_case: bool) -> i32 {
+    match byte {
+        None => -1,
+        Some(b'~') => -2,
+        Some(byte) if byte.is_ascii_digit() => 0,
+        Some(byte) if byte.is_ascii_alphabetic() && fold_case => {
+            i32::from(byte.to_ascii_uppercase())
+        }
+        Some(byte) if byte.is_ascii_alphabetic() => i32::from(byte),
+        Some(byte) => i32::from(byte) + 256,
+    }
+}
+
+/// Compare two versions in parts, like Debian's `verrevcmp`: the bytes before digits, then the runs of
+/// digits as numbers, and so on.
+fn compare_version_parts(a: &[u8], b: &[u8], fold_case: bool) -> Ordering {
+    let (mut i, mut j) = (0, 0);
+    let is_digit = |text: &[u8], at: usize| text.get(at).is_some_and(u8::is_ascii_digit);
+    while i < a.len() || j < b.len() {
+        while (i < a.len() && !is_digit(a, i)) || (j < b.len() && !is_digit(b, j)) {
+            let ordering = version_weight(a.get(i).copied(), fold_case)
+                .cmp(&version_weight(b.get(j).copied(), fold_case));
+            if ordering.is_ne() {
+                return ordering;
+            }
+            i += 1;
+            j += 1;
+        }
+
+        while a.get(i) == Some(&b'0') {
+            i += 1;
+        }
+        while b.get(j) == Some(&b'0') {
+            j += 1;
+        }
+        let mut first_difference = Ordering::Equal;
+        while is_digit(a, i) && is_digit(b, j) {
+            first_difference = first_difference.then(a[i].cmp(&b[j]));
+            i += 1;
+            j += 1;
+        }
+        if is_digit(a, i) {
+            return Ordering::Greater;
+        }
+        if is_digit(b, j) {
+            return Ordering::Less;
+        }
+        if first_difference.is_ne() {
+            return first_difference;
+        }
+    }
+    Ordering::Equal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check that the texts are in order by a comparison, both ways and with each other.
+    fn assert_sorted(compare: fn(&[u8], &[u8]) -> Ordering, texts: &[&str]) {
+        for (i, a) in texts.iter().enumerate() {
+            for (j, b) in texts.iter().enumerate() {
+                assert_eq!(
+                    compare(a.as_bytes(), b.as_bytes()),
+                    i.cmp(&j),
+                    "{a:?} {b:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_numbers() {
+        assert_sorted(
+            compare_numbers,
+            &["-10", "-2", "-1.5", "-0.01", "0", ".009", "0.01", "2", "10"],
+        );
+        for (a, b) in [
+            (" 1.50", "1.5"),
+            ("-0", ""),
+            ("-0.0", "0"),
+            ("00", "x"),
+            ("+1", "0"),
+            ("\n\t 5", "5"),
+        ] {
+            assert_eq!(compare_numbers(a.as_bytes(), b.as_bytes()), Ordering::Equal);
+        }
+    }
+
+    #[test]
+    fn test_human_numbers() {
+        // From the GNU documentation: the unit decides before the digits.
+        assert_sorted(
+            compare_human_numbers,
+            &[
+                "-1G", "-2K", "-5", "0", "2", "1000", "1.5K2", "900K", "3M", "10M", "2G", "1T",
+                "1P", "1E", "1Z", "1Y",
+            ],
+        );
+        // Lower case k is kilo too, and zero has no unit, nor do unknown letters.
+        for (a, b) in [
+            ("1k", "1K"),
+            ("0K", "0"),
+            ("-0M", "0.0G"),
+            ("5x", "5"),
+            (" 2M", "2M"),
+        ] {
+            assert_eq!(
+                compare_human_numbers(a.as_bytes(), b.as_bytes()),
+                Ordering::Equal,
+                "{a:?} {b:?}"
+            );
+        }
+        // The unit follows the number, with at most one decimal point.
+        assert_eq!(compare_human_numbers(b"1.K", b"2"), Ordering::Greater);
+        assert_eq!(compare_human_numbers(b"1..5K", b"2"), Ordering::Less);
+        assert_eq!(compare_human_numbers(b"1.2.3K", b"2"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_versions() {
+        // The examples of the GNU documentation.
+        assert_sorted(compare_versions, &["1.0.5", "1.0.10"]);
+        assert_sorted(compare_versions, &["foo~beta", "foo"]);
+        assert_sorted(
+            compare_versions,
+            &["8.1", "8.2~rc1", "8.2", "8.10", "9.0", "9.0.1"],
+        );
+        assert_sorted(
+            compare_versions,
+            &["~", "a", "a1", "a2", "a10", "a10b", "a10-b", "b", "z", "_"][..],
+        );
+        // The empty name, then the dot files.
+        assert_sorted(compare_versions, &["", ".", "..", ".a", ".b", "a", "b"]);
+        assert_sorted(compare_versions, &[".~x", ".bashrc", "..bashrc", ".1"]);
+        // The suffixes are compared last.
+        assert_sorted(
+            compare_versions,
+            &[
+                "hello-8.txt",
+                "hello-8.2.txt",
+                "hello-9.txt",
+                "hello-10.txt",
+            ],
+        );
+        assert_sorted(
+            compare_versions,
+            &["foo-1.tar", "foo-1.tar.gz", "foo-1.2", "foo-1.10"],
+        );
+        // Leading zeros do not count, unless everything else is equal.
+        assert_eq!(compare_versions(b"a01", b"a1"), Ordering::Equal);
+        assert_eq!(compare_versions(b"a01", b"a001"), Ordering::Equal);
+        assert_eq!(compare_versions(b"\xff1", b"\xfe2"), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_versions_ignore_case() {
+        assert_eq!(compare_versions(b"a", b"B"), Ordering::Greater);
+        assert_eq!(compare_versions_ignore_case(b"a", b"B"), Ordering::Less);
+        assert_eq!(compare_versions_ignore_case(b"a2", b"A10"), Ordering::Less);
+        assert_eq!(compare_versions_ignore_case(b"a", b"A"), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_prefix_length() {
+        assert_eq!(prefix_length(b"foo.tar.gz"), 3);
+        assert_eq!(prefix_length(b"foo-1.2.tar"), 7);
+        assert_eq!(prefix_length(b".bashrc"), 0);
+        assert_eq!(prefix_length(b"..bashrc"), 1);
+        assert_eq!(prefix_length(b"a.~"), 1);
+        assert_eq!(prefix_length(b"a.1"), 3);
+        assert_eq!(prefix_length(b""), 0);
+    }
+}
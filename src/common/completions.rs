@@ -0,0 +1,113 @@
+//! Shell completion scripts for the utilities, generated from their command-line definitions.
+
+use crate::common::error::{Reporter, EXIT_USAGE};
+use crate::common::utility::Utility;
+use crate::show_error;
+use clap::Parser;
+use clap_complete::Shell;
+use std::ffi::OsString;
+use std::io::Write;
+
+/// Print shell completion scripts for the utilities.
+#[derive(Parser, Debug)]
+#[command(name = "completions", about, long_about = None)]
+struct Args {
+    /// The shell to generate the completions for.
+    shell: Shell,
+
+    /// The utilities to generate the completions for (default: all).
+    utilities: Vec<OsString>,
+}
+
+/// Write the completion script of a utility for the given shell.
+pub fn write_completions(shell: Shell, utility: &Utility, output: &mut dyn Write) {
+    let mut command = (utility.command)();
+    clap_complete::generate(shell, &mut command, utility.name, output);
+}
+
+/// Run the completions applet with the given command line, including the program name, and return the exit
+/// code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    let args = match Args::try_parse_from(args) {
+        Ok(args) => args,
+        Err(error) => {
+            let _ = error.print();
+            return error.exit_code();
+        }
+    };
+
+    let mut reporter = Reporter::new("completions", std::io::stderr());
+    let utilities: Vec<&Utility> = if args.utilities.is_empty() {
+        crate::UTILITIES.iter().collect()
+    } else {
+        let mut utilities = Vec::new();
+        for name in &args.utilities {
+            match Utility::find(name) {
+                Some(utility) => utilities.push(utility),
+                None => show_error!(reporter, "unknown utility '{}'", name.to_string_lossy()),
+            }
+        }
+        if reporter.failed() {
+            return EXIT_USAGE;
+        }
+        utilities
+    };
+
+    let mut stdout = std::io::stdout().lock();
+    for utility in utilities {
+        write_completions(args.shell, utility, &mut stdout);
+    }
+    reporter.exit_code()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_cmd::prelude::*;
+    use std::process::Command;
+
+    #[test]
+    fn test_write_completions() {
+        for utility in crate::UTILITIES {
+            for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+                let mut output = Vec::new();
+                write_completions(shell, utility, &mut output);
+                let output = String::from_utf8(output).unwrap();
+                assert!(output.contains(utility.name), "{shell} {}", utility.name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_binary_bash_echo() {
+        let mut cmd = Command::cargo_bin("coreutils").unwrap();
+        let output = cmd
+            .arg("completions")
+            .arg("bash")
+            .arg("echo")
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("_echo()"));
+        assert!(output.contains("-n"));
+        assert!(output.contains("--no-newline"));
+    }
+
+    #[test]
+    fn test_binary_unknown_utility() {
+        let mut cmd = Command::cargo_bin("coreutils").unwrap();
+        cmd.arg("completions")
+            .arg("bash")
+            .arg("nope")
+            .assert()
+            .code(EXIT_USAGE)
+            .stdout("")
+            .stderr("completions: unknown utility 'nope'\n");
+    }
+}
@@ -0,0 +1,592 @@
+//! Free-form dates, as accepted by `date -d` and `touch -d`.
+//!
+//! A date string is a sequence of items separated by spaces, all of them optional:
+//!
+//! - a calendar date, `2024-01-31`, optionally followed by `T` and a time of day;
+//! - a time of day, `13:45`, `13:45:30`, or `13:45:30.25`;
+//! - `UTC`, `GMT`, or `Z` (also directly after a time), for UTC instead of local time;
+//! - a day of the week, `monday` or `mon`, optionally preceded by `this`, `next`, or `last`, which is
+//!   ignored when there is a calendar date;
+//! - relative items, `+3 hours`, `2 days ago`, `next week`, `yesterday`, `tomorrow`, `now`, `today`;
+//!
+//! or `@` followed by a number of seconds since the epoch. Missing fields are taken from the base time, except
+//! that the time of day is midnight when a calendar date or a day of the week is given without one, or when
+//! the string is empty.
+//!
+//! ```
+//! use rust_coreutils::common::datetime::parse_datetime;
+//! use std::time::{Duration, UNIX_EPOCH};
+//!
+//! let base = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+//! let time = parse_datetime("2023-11-15 10:00 UTC", base).unwrap();
+//! assert_eq!(time, UNIX_EPOCH + Duration::from_secs(1_700_042_400));
+//! assert_eq!(parse_datetime("@1700000000", base).unwrap(), base);
+//! assert_eq!(
+//!     parse_datetime("2 days ago", base).unwrap(),
+//!     base - Duration::from_secs(2 * 24 * 60 * 60)
+//! );
+//! ```
+
+use chrono::{
+    DateTime, Datelike, Duration, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+    Weekday,
+};
+use std::fmt;
+use std::iter::Peekable;
+use std::time::SystemTime;
+
+/// A date string that could not be parsed, or which does not name an existing time.
+///
+/// It is displayed as the quoted string, so that utilities can prefix it with their own message, like GNU's
+/// `invalid date '...'`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// The string that could not be parsed.
+    pub input: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}'", self.input)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parse a date string in local time, relative to `base`.
+pub fn parse_datetime(s: &str, base: SystemTime) -> Result<SystemTime, ParseError> {
+    parse_datetime_in(s, base, &chrono::Local)
+}
+
+/// Parse a date string in the given time zone, relative to `base`.
+pub fn parse_datetime_in<Tz: TimeZone>(
+    s: &str,
+    base: SystemTime,
+    tz: &Tz,
+) -> Result<SystemTime, ParseError> {
+    let error = || ParseError {
+        input: s.to_string(),
+    };
+    let items = Items::parse(s).ok_or_else(error)?;
+    items
+        .resolve(base, tz)
+        .map(SystemTime::from)
+        .ok_or_else(error)
+}
+
+/// Relative changes, where years, months, and days move on the calendar, and seconds move in time.
+#[derive(Debug, Default)]
+struct Relative {
+    years: i64,
+    months: i64,
+    days: i64,
+    seconds: i64,
+}
+
+/// The items of a date string.
+#[derive(Debug, Default)]
+struct Items {
+    epoch: Option<DateTime<Utc>>,
+    date: Option<NaiveDate>,
+    time: Option<NaiveTime>,
+    utc: bool,
+    /// The day of the week, with its ordinal: 0 for `this`, 1 for `next`, -1 for `last`.
+    weekday: Option<(Weekday, i64)>,
+    relative: Relative,
+}
+
+impl Items {
+    fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        if let Some(seconds) = s.strip_prefix('@') {
+            return Some(Items {
+                epoch: Some(parse_epoch(seconds)?),
+                ..Items::default()
+            });
+        }
+
+        // Like in GNU, an empty string is the start of the day.
+        let mut items = Items::default();
+        if s.is_empty() {
+            items.time = Some(NaiveTime::MIN);
+        }
+        let words: Vec<String> = s.split_whitespace().map(str::to_lowercase).collect();
+        let mut words = words.iter().map(String::as_str).peekable();
+        while let Some(word) = words.next() {
+            match word {
+                "now" | "today" => {}
+                "yesterday" => items.relative.days -= 1,
+                "tomorrow" => items.relative.days += 1,
+                "utc" | "gmt" | "z" => items.utc = true,
+                "this" | "next" | "last" => {
+                    let ordinal = match word {
+                        "this" => 0,
+                        "next" => 1,
+                        _ => -1,
+                    };
+                    let next = words.next()?;
+                    if let Some(weekday) = parse_weekday(next) {
+                        items.set_weekday(weekday, ordinal)?;
+                    } else {
+                        items.add_relative(ordinal, next)?;
+                    }
+                }
+                _ if parse_weekday(word).is_some() => {
+                    items.set_weekday(parse_weekday(word)?, 0)?;
+                }
+                _ if unit(word).is_some() => {
+                    let count = ago(&mut words, 1);
+                    items.add_relative(count, word)?;
+                }
+                _ if word.starts_with(['+', '-']) || word.bytes().all(|b| b.is_ascii_digit()) => {
+                    let count = word.parse::<i64>().ok()?;
+                    let unit = words.next()?;
+                    items.add_relative(ago(&mut words, count), unit)?;
+                }
+                _ if word.contains('-') => {
+                    let (date, time) = match word.split_once('t') {
+                        Some((date, time)) => (date, Some(time)),
+                        None => (word, None),
+                    };
+                    items.set_date(parse_date(date)?)?;
+                    if let Some(time) = time {
+                        items.set_time(time)?;
+                    }
+                }
+                _ if word.contains(':') => items.set_time(word)?,
+                _ => return None,
+            }
+        }
+        Some(items)
+    }
+
+    fn set_date(&mut self, date: NaiveDate) -> Option<()> {
+        self.date.replace(date).is_none().then_some(())
+    }
+
+    /// Set the time of day, which can be directly followed by `z`.
+    fn set_time(&mut self, time: &str) -> Option<()> {
+        let time = match time.strip_suffix('z') {
+            Some(time) => {
+                self.utc = true;
+                time
+            }
+            None => time,
+        };
+        self.time.replace(parse_time(time)?).is_none().then_some(())
+    }
+
+    fn set_weekday(&mut self, weekday: Weekday, ordinal: i64) -> Option<()> {
+        self.weekday
+            .replace((weekday, ordinal))
+            .is_none()
+            .then_some(())
+    }
+
+    /// Add `count` times the given unit.
+    fn add_relative(&mut self, count: i64, unit_name: &str) -> Option<()> {
+        let relative = &mut self.relative;
+        let (field, multiplier) = match unit(unit_name)? {
+            Unit::Years => (&mut relative.years, 1),
+            Unit::Months => (&mut relative.months, 1),
+            Unit::Days(days) => (&mut relative.days, days),
+            Unit::Seconds(seconds) => (&mut relative.seconds, seconds),
+        };
+        *field = field.checked_add(count.checked_mul(multiplier)?)?;
+        Some(())
+    }
+
+    /// Compute the time the items describe.
+    fn resolve<Tz: TimeZone>(&self, base: SystemTime, tz: &Tz) -> Option<DateTime<Utc>> {
+        if let Some(epoch) = self.epoch {
+            return Some(epoch);
+        }
+
+        let base = DateTime::<Utc>::from(base);
+        let base = if self.utc {
+            base.naive_utc()
+        } else {
+            base.with_timezone(tz).naive_local()
+        };
+
+        let mut date = self.date.unwrap_or(base.date());
+        // Like in GNU, the day of the week is ignored when there is a calendar date.
+        if let (Some((weekday, ordinal)), None) = (self.weekday, self.date) {
+            date = move_to_weekday(date, weekday, ordinal)?;
+        }
+        date = add_months(date, self.relative.years * 12 + self.relative.months)?;
+        date = date.checked_add_signed(Duration::try_days(self.relative.days)?)?;
+
+        let time = match self.time {
+            Some(time) => time,
+            None if self.date.is_some() || self.weekday.is_some() => NaiveTime::MIN,
+            None => base.time(),
+        };
+
+        let local = NaiveDateTime::new(date, time);
+        let time = if self.utc {
+            local.and_utc()
+        } else {
+            match tz.from_local_datetime(&local) {
+                LocalResult::Single(time) => time.with_timezone(&Utc),
+                // The clocks were turned back: take the first of the two times.
+                LocalResult::Ambiguous(earliest, _) => earliest.with_timezone(&Utc),
+                // The clocks were turned forward: the time does not exist.
+                LocalResult::None => return None,
+            }
+        };
+        time.checked_add_signed(Duration::try_seconds(self.relative.seconds)?)
+    }
+}
+
+/// Negate the count of a relative item if the next word is `ago`.
+fn ago<'a>(words: &mut Peekable<impl Iterator<Item = &'a str>>, count: i64) -> i64 {
+    match words.next_if_eq(&"ago") {
+        Some(_) => -count,
+        None => count,
+    }
+}
+
+/// The units of relative items.
+enum Unit {
+    Years,
+    Months,
+    /// A number of days.
+    Days(i64),
+    /// A number of seconds.
+    Seconds(i64),
+}
+
+fn unit(name: &str) -> Option<Unit> {
+    let name = name.strip_suffix('s').unwrap_or(name);
+    Some(match name {
+        "year" => Unit::Years,
+        "month" => Unit::Months,
+        "fortnight" => Unit::Days(14),
+        "week" => Unit::Days(7),
+        "day" => Unit::Days(1),
+        "hour" => Unit::Seconds(60 * 60),
+        "minute" | "min" => Unit::Seconds(60),
+        "second" | "sec" => Unit::Seconds(1),
+        _ => return None,
+    })
+}
+
+fn parse_weekday(name: &str) -> Option<Weekday> {
+    const NAMES: [(&str, Weekday); 7] = [
+        ("monday", Weekday::Mon),
+        ("tuesday", Weekday::Tue),
+        ("wednesday", Weekday::Wed),
+        ("thursday", Weekday::Thu),
+        ("friday", Weekday::Fri),
+        ("saturday", Weekday::Sat),
+        ("sunday", Weekday::Sun),
+    ];
+    NAMES
+        .iter()
+        .find(|(full, _)| name == *full || (name.len() == 3 && full.starts_with(name)))
+        .map(|&(_, weekday)| weekday)
+}
+
+/// Parse `@`-less seconds since the epoch, with an optional sign and fraction.
+fn parse_epoch(s: &str) -> Option<DateTime<Utc>> {
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let (seconds, nanoseconds) = parse_seconds(digits)?;
+    let (seconds, nanoseconds) = match (negative, nanoseconds) {
+        (false, _) => (seconds, nanoseconds),
+        (true, 0) => (-seconds, 0),
+        (true, _) => (-seconds - 1, 1_000_000_000 - nanoseconds),
+    };
+    DateTime::from_timestamp(seconds, nanoseconds)
+}
+
+/// Parse `SS` or `SS.FFF` into seconds and nanoseconds.
+fn parse_seconds(s: &str) -> Option<(i64, u32)> {
+    let (seconds, fraction) = s.split_once('.').unwrap_or((s, ""));
+    if seconds.is_empty() || !seconds.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    if !fraction.bytes().all(|b| b.is_ascii_digit()) || (s.contains('.') && fraction.is_empty()) {
+        return None;
+    }
+    // Digits beyond nanoseconds are truncated.
+    let nanoseconds = format!("{:0<9.9}", fraction).parse().ok()?;
+    Some((seconds.parse().ok()?, nanoseconds))
+}
+
+/// Parse `YYYY-MM-DD`.
+fn parse_date(s: &str) -> Option<NaiveDate> {
+    let mut fields = s.splitn(3, '-');
+    let mut field = |max_digits: usize| -> Option<u32> {
+        let field = fields.next()?;
+        if field.is_empty()
+            || field.len() > max_digits
+            || !field.bytes().all(|b| b.is_ascii_digit())
+        {
+            return None;
+        }
+        field.parse().ok()
+    };
+    let (year, month, day) = (field(4)?, field(2)?, field(2)?);
+    NaiveDate::from_ymd_opt(i32::try_from(year).ok()?, month, day)
+}
+
+/// Parse `HH:MM`, `HH:MM:SS`, or `HH:MM:SS.FFF`.
+fn parse_time(s: &str) -> Option<NaiveTime> {
+    let mut fields = s.splitn(3, ':');
+    let mut field = || -> Option<u32> {
+        let field = fields.next()?;
+        if field.is_empty() || field.len() > 2 || !field.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        field.parse().ok()
+    };
+    let (hour, minute) = (field()?, field()?);
+    let (second, nanosecond) = match fields.next() {
+        Some(seconds) if seconds.split('.').next()?.len() == 2 => parse_seconds(seconds)?,
+        Some(_) => return None,
+        None => (0, 0),
+    };
+    NaiveTime::from_hms_nano_opt(hour, minute, u32::try_from(second).ok()?, nanosecond)
+}
+
+/// Move to a day of the week: the same or next for ordinal 0, then by weeks forward or backward.
+fn move_to_weekday(date: NaiveDate, weekday: Weekday, ordinal: i64) -> Option<NaiveDate> {
+    let current = i64::from(date.weekday().num_days_from_monday());
+    let target = i64::from(weekday.num_days_from_monday());
+    let forward = (target - current).rem_euclid(7);
+    let days = match ordinal {
+        0 => forward,
+        1.. if forward == 0 => 7 * ordinal,
+        1.. => forward + 7 * (ordinal - 1),
+        _ => {
+            let backward = (current - target).rem_euclid(7);
+            let backward = if backward == 0 { 7 } else { backward };
+            -backward + 7 * (ordinal + 1)
+        }
+    };
+    date.checked_add_signed(Duration::try_days(days)?)
+}
+
+/// Add months on the calendar, where days past the end of the month overflow into the next one like in
+/// GNU, so that January 31 plus one month is March 2 or 3.
+fn add_months(date: NaiveDate, months: i64) -> Option<NaiveDate> {
+    let month = i64::from(date.year()) * 12 + i64::from(date.month0()) + months;
+    let year = i32::try_from(month.div_euclid(12)).ok()?;
+    let first = NaiveDate::from_ymd_opt(year, month.rem_euclid(12) as u32 + 1, 1)?;
+    first.checked_add_signed(Duration::try_days(i64::from(date.day0()))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, NaiveDate, Offset};
+    use std::time::{Duration as StdDuration, UNIX_EPOCH};
+
+    /// Tuesday 2023-11-14 22:13:20 UTC.
+    const BASE: u64 = 1_700_000_000;
+
+    fn epoch(seconds: i64) -> SystemTime {
+        if seconds >= 0 {
+            UNIX_EPOCH + StdDuration::from_secs(seconds as u64)
+        } else {
+            UNIX_EPOCH - StdDuration::from_secs(seconds.unsigned_abs())
+        }
+    }
+
+    fn parse_in<Tz: TimeZone>(s: &str, tz: &Tz) -> Option<i64> {
+        let time = parse_datetime_in(s, epoch(BASE as i64), tz).ok()?;
+        Some(DateTime::<Utc>::from(time).timestamp())
+    }
+
+    /// Central European Time in 2024, with summer time from March 31 01:00 UTC to October 27 01:00 UTC.
+    #[derive(Clone, Copy, Debug)]
+    struct Cet;
+
+    impl Cet {
+        fn offset(summer: bool) -> FixedOffset {
+            FixedOffset::east_opt(if summer { 2 * 3600 } else { 3600 }).unwrap()
+        }
+    }
+
+    impl TimeZone for Cet {
+        type Offset = FixedOffset;
+
+        fn from_offset(_: &FixedOffset) -> Self {
+            Cet
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            let candidates: Vec<FixedOffset> = [true, false]
+                .into_iter()
+                .map(Cet::offset)
+                .filter(|offset| {
+                    let utc = *local - Duration::seconds(offset.local_minus_utc().into());
+                    self.offset_from_utc_datetime(&utc) == *offset
+                })
+                .collect();
+            match candidates[..] {
+                [offset] => LocalResult::Single(offset),
+                [earliest, latest] => LocalResult::Ambiguous(earliest, latest),
+                _ => LocalResult::None,
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            let start = NaiveDate::from_ymd_opt(2024, 3, 31)
+                .unwrap()
+                .and_hms_opt(1, 0, 0);
+            let end = NaiveDate::from_ymd_opt(2024, 10, 27)
+                .unwrap()
+                .and_hms_opt(1, 0, 0);
+            Cet::offset(start.unwrap() <= *utc && *utc < end.unwrap()).fix()
+        }
+    }
+
+    #[test]
+    fn test_known_epochs() {
+        let cases: &[(&str, i64)] = &[
+            ("@0", 0),
+            ("@1700000000", 1_700_000_000),
+            ("@-1", -1),
+            ("@+86400", 86400),
+            ("1970-01-01 UTC", 0),
+            ("1970-01-01T00:00:01Z", 1),
+            ("1970-01-01 00:00:01z", 1),
+            ("2024-01-02 03:04:05 UTC", 1_704_164_645),
+            ("2024-1-2 3:04:05 utc", 1_704_164_645),
+            ("GMT 2024-01-02 03:04", 1_704_164_640),
+            ("2000-02-29 UTC", 951_782_400),
+            ("now", 1_700_000_000),
+            ("today", 1_700_000_000),
+            ("", 1_699_920_000),
+            ("yesterday", 1_700_000_000 - 86400),
+            ("tomorrow", 1_700_000_000 + 86400),
+            ("tomorrow 10:00", 1_700_042_400),
+            ("12:30", 1_699_965_000),
+            ("2 days ago", 1_700_000_000 - 2 * 86400),
+            ("+3 hours", 1_700_000_000 + 3 * 3600),
+            ("-90 minutes", 1_700_000_000 - 90 * 60),
+            ("1 week ago", 1_700_000_000 - 7 * 86400),
+            ("2 fortnights", 1_700_000_000 + 28 * 86400),
+            ("hour", 1_700_000_000 + 3600),
+            // Only the last item is in the past.
+            ("1 day 2 hours ago", 1_700_000_000 + 86400 - 7200),
+            ("2 days ago 10:00", 1_699_956_000 - 2 * 86400),
+            ("next week", 1_700_000_000 + 7 * 86400),
+            ("last year", 1_668_464_000),
+            ("tuesday", 1_699_920_000),
+            ("this tue", 1_699_920_000),
+            ("next monday", 1_700_438_400),
+            ("next tuesday", 1_700_524_800),
+            ("last monday", 1_699_833_600),
+            ("last tuesday", 1_699_315_200),
+            ("Friday 08:00", 1_700_208_000),
+            ("2023-11-14 next monday", 1_699_920_000),
+            ("2023-01-31 +1 month", 1_677_801_600),
+            ("2023-03-31 -1 month", 1_677_801_600),
+        ];
+        for &(input, expected) in cases {
+            assert_eq!(parse_in(input, &Utc), Some(expected), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn test_fractions() {
+        let time = parse_datetime_in("2023-11-14 22:13:20.5 UTC", epoch(0), &Utc).unwrap();
+        assert_eq!(time, epoch(BASE as i64) + StdDuration::from_millis(500));
+
+        let time = parse_datetime_in("@-1.25", epoch(0), &Utc).unwrap();
+        assert_eq!(time, UNIX_EPOCH - StdDuration::from_millis(1250));
+
+        // The fraction of the base is kept when only relative items are given.
+        let base = epoch(BASE as i64) + StdDuration::from_millis(250);
+        let time = parse_datetime_in("1 second ago", base, &Utc).unwrap();
+        assert_eq!(time, base - StdDuration::from_secs(1));
+    }
+
+    #[test]
+    fn test_invalid() {
+        for input in [
+            "garbage",
+            "3",
+            "2 parsecs",
+            "next",
+            "next blue",
+            "2023-13-01",
+            "2023-02-30",
+            "20231-01-01",
+            "2023-01",
+            "25:00",
+            "12:60",
+            "12:30:5",
+            "12:30:30:30",
+            "12:30.5",
+            "@",
+            "@1.",
+            "@abc",
+            "@1 day",
+            "2023-01-01 2023-01-02",
+            "monday tuesday",
+            "9999999999999999999 days",
+        ] {
+            assert_eq!(parse_in(input, &Utc), None, "{input:?}");
+        }
+
+        assert_eq!(
+            parse_datetime_in("garbage", epoch(0), &Utc)
+                .unwrap_err()
+                .to_string(),
+            "'garbage'"
+        );
+    }
+
+    #[test]
+    fn test_local_time() {
+        let offset = FixedOffset::east_opt(5 * 3600 + 1800).unwrap();
+        assert_eq!(
+            parse_in("2024-01-02 03:04:05", &offset),
+            Some(1_704_164_645 - 19800)
+        );
+        // UTC wins over the local time zone.
+        assert_eq!(
+            parse_in("2024-01-02 03:04:05 UTC", &offset),
+            Some(1_704_164_645)
+        );
+        // The base date is the local one: it is already Wednesday at 03:43.
+        assert_eq!(parse_in("00:00", &offset), Some(1_699_986_600));
+    }
+
+    #[test]
+    fn test_daylight_saving_time() {
+        // The last second before the clocks are turned forward, and the first one after.
+        assert_eq!(parse_in("2024-03-31 01:59:59", &Cet), Some(1_711_846_799));
+        assert_eq!(parse_in("2024-03-31 03:00", &Cet), Some(1_711_846_800));
+        // The skipped hour does not exist.
+        assert_eq!(parse_in("2024-03-31 02:30", &Cet), None);
+        // The repeated hour is the first one, in summer time.
+        assert_eq!(parse_in("2024-10-27 02:30", &Cet), Some(1_729_989_000));
+        assert_eq!(parse_in("2024-10-27 03:30", &Cet), Some(1_729_996_200));
+
+        // Days move on the calendar, hours move in time.
+        assert_eq!(
+            parse_in("2024-03-30 12:00 +1 day", &Cet),
+            Some(1_711_879_200)
+        );
+        assert_eq!(
+            parse_in("2024-03-30 12:00 +24 hours", &Cet),
+            Some(1_711_882_800)
+        );
+    }
+}
@@ -0,0 +1,633 @@
+//! Streaming encoding and decoding of the RFC 4648 alphabets and of Z85, shared by base64, base32 and basenc.
+//!
+//! An [`Encoding`] maps a block of bytes to a whole number of characters, like 3 bytes to 4 characters for
+//! base64, where each character is 6 bits. The data is streamed in chunks of whole blocks, and only the last
+//! block is padded with `=`:
+//!
+//! ```
+//! use rust_coreutils::common::encoding::{decode, encode, BASE32};
+//!
+//! let mut encoded = Vec::new();
+//! encode(&BASE32, &mut &b"hello"[..], 0, &mut encoded).unwrap();
+//! assert_eq!(encoded, b"NBSWY3DP");
+//! let mut decoded = Vec::new();
+//! decode(&BASE32, &mut &b"NBSW\nY3DP\n"[..], &mut decoded).unwrap();
+//! assert_eq!(decoded, b"hello");
+//! ```
+
+use crate::common::error::{UError, UResult, EXIT_FAILURE};
+use crate::common::input::{InputIter, BUFFER_SIZE};
+use std::ffi::OsString;
+use std::io::{self, BufReader, BufWriter, ErrorKind, Read, Write};
+
+/// The number of characters of the encoded lines by default.
+pub const DEFAULT_WRAP: usize = 76;
+
+/// An alphabet, and how its characters make up the bytes.
+#[derive(Clone, Debug)]
+pub struct Encoding {
+    alphabet: &'static [u8],
+    /// The value of every byte as a character, or [`INVALID`].
+    values: [u8; 256],
+    scheme: Scheme,
+    /// Whether the bits of every byte are in the reverse order, from the least significant one.
+    lsb_first: bool,
+    /// Whether the bytes of a last block which is cut short are decoded before the error, like GNU does for
+    /// base64 but not for base32.
+    partial_blocks: bool,
+}
+
+/// How the characters of an [`Encoding`] make up the bytes.
+#[derive(Clone, Copy, Debug)]
+enum Scheme {
+    /// Every character is this number of bits, from the most significant bits of the block.
+    Bits(u32),
+    /// Every block of 4 bytes is a big-endian number written as 5 digits in base 85, as in ZeroMQ's Z85. The
+    /// blocks are never padded.
+    Z85,
+}
+
+/// The standard base64 alphabet.
+pub const BASE64: Encoding = Encoding::new(
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/",
+    Scheme::Bits(6),
+    true,
+);
+
+/// The URL and filename safe base64 alphabet, with `-` and `_` instead of `+` and `/`.
+pub const BASE64URL: Encoding = Encoding::new(
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_",
+    Scheme::Bits(6),
+    true,
+);
+
+/// The standard base32 alphabet, in which lowercase letters are invalid.
+pub const BASE32: Encoding =
+    Encoding::new(b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567", Scheme::Bits(5), false);
+
+/// The base32 alphabet with the extended hexadecimal digits, which keeps the order of the data.
+pub const BASE32HEX: Encoding =
+    Encoding::new(b"0123456789ABCDEFGHIJKLMNOPQRSTUV", Scheme::Bits(5), false);
+
+/// Uppercase hexadecimal digits.
+pub const BASE16: Encoding = Encoding::new(b"0123456789ABCDEF", Scheme::Bits(4), true);
+
+/// The bits of every byte, from the most significant one.
+pub const BASE2MSBF: Encoding = Encoding::new(b"01", Scheme::Bits(1), true);
+
+/// The bits of every byte, from the least significant one.
+pub const BASE2LSBF: Encoding = Encoding {
+    lsb_first: true,
+    ..BASE2MSBF
+};
+
+/// The Z85 alphabet of ZeroMQ, whose data must be a whole number of blocks of 4 bytes.
+pub const Z85: Encoding = Encoding::new(
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#",
+    Scheme::Z85,
+    false,
+);
+
+const INVALID: u8 = 0xff;
+
+/// Get the number of bits of a block, which is the least multiple of 8 and of the bits of a character.
+const fn block_bits(bits: u32) -> u32 {
+    let mut block = bits;
+    while !block.is_multiple_of(8) {
+        block += bits;
+    }
+    block
+}
+
+impl Encoding {
+    const fn new(alphabet: &'static [u8], scheme: Scheme, partial_blocks: bool) -> Self {
+        let mut values = [INVALID; 256];
+        let mut index = 0;
+        while index < alphabet.len() {
+            values[alphabet[index] as usize] = index as u8;
+            index += 1;
+        }
+        Encoding {
+            alphabet,
+            values,
+            scheme,
+            lsb_first: false,
+            partial_blocks,
+        }
+    }
+
+    /// The number of bytes of a block.
+    const fn block_bytes(&self) -> usize {
+        match self.scheme {
+            Scheme::Bits(bits) => (block_bits(bits) / 8) as usize,
+            Scheme::Z85 => 4,
+        }
+    }
+
+    /// The number of characters of a block.
+    const fn block_characters(&self) -> usize {
+        match self.scheme {
+            Scheme::Bits(bits) => (block_bits(bits) / bits) as usize,
+            Scheme::Z85 => 5,
+        }
+    }
+
+    /// Encode bytes, padding the last block with `=` if the length is not a multiple of the block.
+    fn encode_chunk(&self, input: &[u8], output: &mut Vec<u8>) {
+        let (blocks, rest) = input.split_at(input.len() / self.block_bytes() * self.block_bytes());
+        self.encode_blocks(blocks, output);
+        if let (false, Scheme::Bits(bits)) = (rest.is_empty(), self.scheme) {
+            let mut block = [0; 8];
+            block[..rest.len()].copy_from_slice(rest);
+            let start = output.len();
+            self.encode_blocks(&block[..self.block_bytes()], output);
+            // Only the characters which hold any bit of the bytes are kept.
+            let used = (rest.len() * 8).div_ceil(bits as usize);
+            output[start + used..].fill(b'=');
+        }
+    }
+
+    /// Encode whole blocks, with the number of bits known at compile time so that the loops are unrolled.
+    fn encode_blocks(&self, input: &[u8], output: &mut Vec<u8>) {
+        match self.scheme {
+            Scheme::Bits(6) => self.encode_blocks_with(6, input, output),
+            Scheme::Bits(5) => self.encode_blocks_with(5, input, output),
+            Scheme::Bits(4) => self.encode_blocks_with(4, input, output),
+            Scheme::Bits(1) => self.encode_blocks_with(1, input, output),
+            Scheme::Bits(bits) => unreachable!("no encoding has {bits} bits per character"),
+            Scheme::Z85 => {
+                for block in input.chunks_exact(4) {
+                    let mut value = u32::from_be_bytes(block.try_into().unwrap());
+                    let mut digits = [0; 5];
+                    for digit in digits.iter_mut().rev() {
+                        *digit = self.alphabet[(value % 85) as usize];
+                        value /= 85;
+                    }
+                    output.extend_from_slice(&digits);
+                }
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn encode_blocks_with(&self, bits: u32, input: &[u8], output: &mut Vec<u8>) {
+        let block_bits = block_bits(bits);
+        let (bytes, characters) = ((block_bits / 8) as usize, (block_bits / bits) as usize);
+        let start = output.len();
+        output.resize(start + input.len() / bytes * characters, 0);
+        let blocks = input.chunks_exact(bytes);
+        for (block, encoded) in blocks.zip(output[start..].chunks_exact_mut(characters)) {
+            let value = block
+                .iter()
+                .fold(0u64, |value, &byte| value << 8 | u64::from(byte));
+            for (index, character) in encoded.iter_mut().enumerate() {
+                let shift = block_bits - bits * (index as u32 + 1);
+                *character = self.alphabet[(value >> shift) as usize & (self.alphabet.len() - 1)];
+            }
+        }
+    }
+
+    /// Decode the whole blocks at the start of `input` up to the first one with a newline, padding, or an
+    /// invalid character, and get the number of characters decoded.
+    fn decode_blocks(&self, input: &[u8], output: &mut Vec<u8>) -> usize {
+        match self.scheme {
+            Scheme::Bits(6) => self.decode_blocks_with(6, input, output),
+            Scheme::Bits(5) => self.decode_blocks_with(5, input, output),
+            Scheme::Bits(4) => self.decode_blocks_with(4, input, output),
+            Scheme::Bits(1) => self.decode_blocks_with(1, input, output),
+            Scheme::Bits(bits) => unreachable!("no encoding has {bits} bits per character"),
+            Scheme::Z85 => {
+                let mut decoded = 0;
+                for block in input.chunks_exact(5) {
+                    match self.decode_z85(block) {
+                        Some(value) => output.extend_from_slice(&value.to_be_bytes()),
+                        None => break,
+                    }
+                    decoded += 5;
+                }
+                decoded
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn decode_blocks_with(&self, bits: u32, input: &[u8], output: &mut Vec<u8>) -> usize {
+        let block_bits = block_bits(bits);
+        let (bytes, characters) = ((block_bits / 8) as usize, (block_bits / bits) as usize);
+        let mut decoded = 0;
+        for block in input.chunks_exact(characters) {
+            let mut value = 0u64;
+            let mut valid = true;
+            for &character in block {
+                let character = self.values[usize::from(character)];
+                valid &= character != INVALID;
+                value = value << bits | u64::from(character);
+            }
+            if !valid {
+                break;
+            }
+            output.extend_from_slice(&value.to_be_bytes()[8 - bytes..]);
+            decoded += characters;
+        }
+        decoded
+    }
+
+    /// Get the number of a block of Z85, unless it has an invalid character or is too large for 4 bytes.
+    fn decode_z85(&self, block: &[u8]) -> Option<u32> {
+        let mut value = 0u64;
+        for &character in block {
+            let character = self.values[usize::from(character)];
+            if character == INVALID {
+                return None;
+            }
+            value = value * 85 + u64::from(character);
+        }
+        u32::try_from(value).ok()
+    }
+
+    /// Decode a block, or the last characters of the input, and get whether they are a valid block. The bytes
+    /// are decoded up to the first invalid character.
+    fn decode_block(&self, block: &[u8], output: &mut Vec<u8>) -> bool {
+        if block.len() < self.block_characters() && !self.partial_blocks {
+            return false;
+        }
+        let bits = match self.scheme {
+            Scheme::Bits(bits) => bits,
+            Scheme::Z85 => match self.decode_z85(block) {
+                Some(value) => {
+                    output.extend_from_slice(&value.to_be_bytes());
+                    return true;
+                }
+                None => return false,
+            },
+        };
+        let mut value = 0u64;
+        let mut pending = 0;
+        for (index, &character) in block.iter().enumerate() {
+            if character == b'=' {
+                // The padding starts after the last character holding bits of a byte, and ends the block.
+                return index > 0
+                    && pending < bits
+                    && block.len() == self.block_characters()
+                    && block[index..].iter().all(|&character| character == b'=');
+            }
+            let character = self.values[usize::from(character)];
+            if character == INVALID {
+                return false;
+            }
+            value = value << bits | u64::from(character);
+            pending += bits;
+            if pending >= 8 {
+                pending -= 8;
+                output.push((value >> pending) as u8);
+            }
+        }
+        block.len() == self.block_characters()
+    }
+}
+
+/// Why encoding or decoding stopped.
+enum Failure {
+    Read(io::Error),
+    Write(io::Error),
+    /// The input is not valid in the encoding.
+    Invalid,
+    /// The input is not a whole number of blocks, which cannot be padded.
+    Unaligned,
+}
+
+/// Encode or decode the file operand of base64, base32 or basenc, or standard input without one, to `stdout`.
+pub fn process(
+    encoding: &Encoding,
+    decoding: bool,
+    wrap: usize,
+    files: Vec<OsString>,
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+) -> UResult<()> {
+    if let Some(extra) = files.get(1) {
+        let message = format!("extra operand '{}'", extra.to_string_lossy());
+        return Err(UError::usage(EXIT_FAILURE, message));
+    }
+    let mut inputs = InputIter::with_stdin(
+        files,
+        Box::new(BufReader::with_capacity(BUFFER_SIZE, stdin)),
+    );
+    let result = match inputs.next_raw().expect("an input") {
+        Ok(mut input) if decoding => decode(encoding, &mut input.reader, stdout),
+        Ok(mut input) => encode(encoding, &mut input.reader, wrap, stdout),
+        Err(error) => Err(UError::io(error.display_name(), &error.error)),
+    };
+    result
+}
+
+/// Parse the argument of `-w`, or get [`DEFAULT_WRAP`] without one. A number too large to be a width means no
+/// wrapping, like in GNU.
+pub fn parse_wrap(wrap: Option<&str>) -> UResult<usize> {
+    let Some(wrap) = wrap else {
+        return Ok(DEFAULT_WRAP);
+    };
+    if wrap.is_empty() || !wrap.bytes().all(|byte| byte.is_ascii_digit()) {
+        let message = format!("invalid wrap size: '{wrap}'");
+        return Err(UError::new(EXIT_FAILURE, message));
+    }
+    Ok(wrap.parse().unwrap_or(0))
+}
+
+/// Encode the data of `reader` to `stdout`, with a newline after every `wrap` characters and at the end, or
+/// without newlines if `wrap` is 0.
+///
+/// The input is read in chunks of whole blocks. A closed pipe stops without an error.
+pub fn encode(
+    encoding: &Encoding,
+    reader: &mut dyn Read,
+    wrap: usize,
+    stdout: &mut dyn Write,
+) -> UResult<()> {
+    let mut writer = BufWriter::with_capacity(BUFFER_SIZE, stdout);
+    let result = encode_chunks(encoding, reader, wrap, &mut writer);
+    finish(result, &mut writer)
+}
+
+fn encode_chunks(
+    encoding: &Encoding,
+    reader: &mut dyn Read,
+    wrap: usize,
+    writer: &mut dyn Write,
+) -> Result<(), Failure> {
+    let mut buffer = vec![0; encoding.block_bytes() * 16 * 1024];
+    let mut encoded = Vec::with_capacity(encoding.block_characters() * 16 * 1024);
+    // The number of characters already on the current line.
+    let mut column = 0;
+    loop {
+        let length = fill(reader, &mut buffer).map_err(Failure::Read)?;
+        if let Scheme::Z85 = encoding.scheme {
+            if !length.is_multiple_of(encoding.block_bytes()) {
+                return Err(Failure::Unaligned);
+            }
+        }
+        if encoding.lsb_first {
+            reverse_bits(&mut buffer[..length]);
+        }
+        encoded.clear();
+        encoding.encode_chunk(&buffer[..length], &mut encoded);
+        let mut rest = &encoded[..];
+        while wrap > 0 && column + rest.len() >= wrap {
+            let (line, next) = rest.split_at(wrap - column);
+            writer.write_all(line).map_err(Failure::Write)?;
+            writer.write_all(b"\n").map_err(Failure::Write)?;
+            rest = next;
+            column = 0;
+        }
+        writer.write_all(rest).map_err(Failure::Write)?;
+        column += rest.len();
+        if length < buffer.len() {
+            break;
+        }
+    }
+    if wrap > 0 && column > 0 {
+        writer.write_all(b"\n").map_err(Failure::Write)?;
+    }
+    Ok(())
+}
+
+/// Read from `reader` until `buffer` is full or the input ends, and get the number of bytes read.
+fn fill(reader: &mut dyn Read, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut length = 0;
+    while length < buffer.len() {
+        match reader.read(&mut buffer[length..]) {
+            Ok(0) => break,
+            Ok(n) => length += n,
+            Err(error) if error.kind() == ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(length)
+}
+
+/// Decode the data of `reader` to `stdout`, ignoring newlines.
+///
+/// Like GNU, every block may be padded, and the bytes of a block are decoded up to its first invalid
+/// character, so that the data before an error is written before the error is returned. A closed pipe stops
+/// without an error.
+pub fn decode(encoding: &Encoding, reader: &mut dyn Read, stdout: &mut dyn Write) -> UResult<()> {
+    let mut writer = BufWriter::with_capacity(BUFFER_SIZE, stdout);
+    let result = decode_chunks(encoding, reader, &mut writer);
+    finish(result, &mut writer)
+}
+
+fn decode_chunks(
+    encoding: &Encoding,
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+) -> Result<(), Failure> {
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut decoded = Vec::with_capacity(BUFFER_SIZE);
+    let mut decoder = Decoder::new(encoding);
+    loop {
+        decoded.clear();
+        let (valid, end) = match reader.read(&mut buffer) {
+            Ok(0) => (decoder.finish(&mut decoded), true),
+            Ok(length) => (decoder.decode(&buffer[..length], &mut decoded), false),
+            Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+            Err(error) => return Err(Failure::Read(error)),
+        };
+        if encoding.lsb_first {
+            reverse_bits(&mut decoded);
+        }
+        writer.write_all(&decoded).map_err(Failure::Write)?;
+        match (valid, end) {
+            (false, _) => return Err(Failure::Invalid),
+            (true, true) => return Ok(()),
+            (true, false) => {}
+        }
+    }
+}
+
+/// Reverse the order of the bits of every byte.
+fn reverse_bits(bytes: &mut [u8]) {
+    for byte in bytes {
+        *byte = byte.reverse_bits();
+    }
+}
+
+/// The state of decoding between two reads, which may split a block.
+struct Decoder<'a> {
+    encoding: &'a Encoding,
+    /// The characters of the current block, without newlines.
+    block: [u8; 8],
+    length: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(encoding: &'a Encoding) -> Self {
+        Decoder {
+            encoding,
+            block: [0; 8],
+            length: 0,
+        }
+    }
+
+    /// Decode the characters of `input` into `output`, and get whether they are all valid.
+    fn decode(&mut self, input: &[u8], output: &mut Vec<u8>) -> bool {
+        let encoding = self.encoding;
+        let characters = encoding.block_characters();
+        let mut index = 0;
+        while index < input.len() {
+            // Decode the whole blocks without padding or newlines at once, which is the common case.
+            if self.length == 0 {
+                index += encoding.decode_blocks(&input[index..], output);
+                if index == input.len() {
+                    break;
+                }
+            }
+            if input[index] != b'\n' {
+                self.block[self.length] = input[index];
+                self.length += 1;
+                if self.length == characters {
+                    self.length = 0;
+                    if !encoding.decode_block(&self.block[..characters], output) {
+                        return false;
+                    }
+                }
+            }
+            index += 1;
+        }
+        true
+    }
+
+    /// Decode the last block at the end of the input, which is invalid if it is cut short.
+    fn finish(&mut self, output: &mut Vec<u8>) -> bool {
+        self.length == 0
+            || self
+                .encoding
+                .decode_block(&self.block[..self.length], output)
+    }
+}
+
+/// Flush the output, even after an invalid input, and get the error to report, if any.
+fn finish(result: Result<(), Failure>, writer: &mut dyn Write) -> UResult<()> {
+    let result = match result {
+        Err(Failure::Write(error)) => Err(Failure::Write(error)),
+        result => writer.flush().map_err(Failure::Write).and(result),
+    };
+    match result {
+        Ok(()) => Ok(()),
+        Err(Failure::Read(error)) => Err(UError::io("read error", &error)),
+        Err(Failure::Write(error)) => UError::write(error),
+        Err(Failure::Invalid) => Err(UError::new(EXIT_FAILURE, "invalid input")),
+        Err(Failure::Unaligned) => Err(UError::new(
+            EXIT_FAILURE,
+            "invalid input (length must be multiple of 4 characters)",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Get pseudo-random bytes, the same ones for every run.
+    fn random_bytes(length: usize) -> Vec<u8> {
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        (0..length)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 32) as u8
+            })
+            .collect()
+    }
+
+    /// A reader returning at most one byte at a time.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            let length = self.0.len().min(buffer.len()).min(1);
+            buffer[..length].copy_from_slice(&self.0[..length]);
+            self.0 = &self.0[length..];
+            Ok(length)
+        }
+    }
+
+    fn encoded(encoding: &Encoding, data: &[u8], wrap: usize) -> Vec<u8> {
+        let mut output = Vec::new();
+        encode(encoding, &mut &data[..], wrap, &mut output).unwrap();
+        output
+    }
+
+    fn decoded(encoding: &Encoding, reader: &mut dyn Read) -> UResult<Vec<u8>> {
+        let mut output = Vec::new();
+        decode(encoding, reader, &mut output).map(|()| output)
+    }
+
+    const ENCODINGS: [&Encoding; 8] = [
+        &BASE64, &BASE64URL, &BASE32, &BASE32HEX, &BASE16, &BASE2MSBF, &BASE2LSBF, &Z85,
+    ];
+
+    /// Get the longest part of `data` which the encoding can encode.
+    fn aligned<'a>(encoding: &Encoding, data: &'a [u8]) -> &'a [u8] {
+        match encoding.scheme {
+            Scheme::Bits(_) => data,
+            Scheme::Z85 => &data[..data.len() / 4 * 4],
+        }
+    }
+
+    #[test]
+    fn test_blocks() {
+        let blocks =
+            ENCODINGS.map(|encoding| (encoding.block_bytes(), encoding.block_characters()));
+        assert_eq!(
+            blocks,
+            [
+                (3, 4),
+                (3, 4),
+                (5, 8),
+                (5, 8),
+                (1, 2),
+                (1, 8),
+                (1, 8),
+                (4, 5)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        // Larger than the chunks, with a length which is not a multiple of the blocks.
+        let data = random_bytes(5 * 16 * 1024 + 1001);
+        for encoding in ENCODINGS {
+            let data = aligned(encoding, &data);
+            for wrap in [0, 5, DEFAULT_WRAP] {
+                let encoding_text = encoded(encoding, data, wrap);
+                assert_eq!(
+                    decoded(encoding, &mut &encoding_text[..]).unwrap(),
+                    data,
+                    "{:?} {wrap}",
+                    encoding.scheme
+                );
+            }
+            let encoding_text = encoded(encoding, data, DEFAULT_WRAP);
+            assert!(encoding_text
+                .split(|&byte| byte == b'\n')
+                .all(|line| line.len() <= DEFAULT_WRAP));
+        }
+    }
+
+    #[test]
+    fn test_split_reads() {
+        // The blocks, and their padding, may be split across reads.
+        let data = random_bytes(1000);
+        for encoding in ENCODINGS {
+            for length in [0, 1, 2, 3, 4, 999, 1000] {
+                let data = aligned(encoding, &data[..length]);
+                let encoding_text = encoded(encoding, data, 7);
+                let mut reader = Trickle(&encoding_text);
+                assert_eq!(decoded(encoding, &mut reader).unwrap(), data);
+            }
+        }
+        assert!(decoded(&BASE32, &mut Trickle(b"MZXW6YT*")).is_err());
+    }
+}
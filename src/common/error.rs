@@ -0,0 +1,290 @@
+//! GNU-style error reporting and exit codes shared by all utilities.
+//!
+//! Diagnostics have the form `utilname: operand: OS error text`. A utility keeps processing the remaining
+//! operands after a failure, and exits with [`EXIT_FAILURE`] at the end:
+//!
+//! ```
+//! use rust_coreutils::common::error::{Reporter, EXIT_FAILURE};
+//! use rust_coreutils::show_error;
+//!
+//! let mut stderr = Vec::new();
+//! let mut reporter = Reporter::new("cat", &mut stderr);
+//! for operand in ["missing"] {
+//!     if let Err(error) = std::fs::read(operand) {
+//!         reporter.io_error(operand, &error);
+//!     }
+//! }
+//! show_error!(reporter, "{} operands failed", 1);
+//! assert_eq!(reporter.exit_code(), EXIT_FAILURE);
+//! assert_eq!(stderr, b"cat: missing: No such file or directory\ncat: 1 operands failed\n");
+//! ```
+
+use std::fmt::{self, Display};
+use std::io::{self, Write};
+
+/// The exit code of a successful run.
+pub const EXIT_SUCCESS: i32 = 0;
+/// The exit code of a run where at least one operation failed.
+pub const EXIT_FAILURE: i32 = 1;
+/// The exit code of a run with an invalid command line.
+pub const EXIT_USAGE: i32 = 2;
+
+/// The result of an operation that can fail with a message and an exit code.
+pub type UResult<T> = Result<T, UError>;
+
+/// An error that stops a utility, with the message to show and the exit code to return.
+#[derive(Debug, PartialEq, Eq)]
+pub struct UError {
+    /// The exit code to return.
+    pub code: i32,
+    /// The message, without the utility name.
+    pub message: String,
+    /// Whether the command line is invalid, in which case the message is followed by a hint to read the
+    /// help, like in GNU.
+    pub usage: bool,
+}
+
+impl UError {
+    /// Create an error with the given exit code.
+    pub fn new(code: i32, message: impl Into<String>) -> Self {
+        UError {
+            code,
+            message: message.into(),
+            usage: false,
+        }
+    }
+
+    /// Create an error for an invalid command line with the given exit code, which is [`EXIT_FAILURE`] for
+    /// most utilities in GNU. The message is followed by `Try 'util --help' for more information.`
+    pub fn usage(code: i32, message: impl Into<String>) -> Self {
+        UError {
+            usage: true,
+            ..UError::new(code, message)
+        }
+    }
+
+    /// Create an error for an I/O error related to an operand, like `file: No such file or directory`.
+    pub fn io(operand: impl Display, error: &io::Error) -> Self {
+        UError::new(
+            EXIT_FAILURE,
+            format!("{operand}: {}", io_error_message(error)),
+        )
+    }
+
+    /// Handle an error writing the output, which is reported like `write error: No space left on device`.
+    ///
+    /// A broken pipe is not an error: the consumer is not interested in the rest of the output. The binaries
+    /// are terminated by SIGPIPE before getting here, see [`common::pipes`](crate::common::pipes), so this
+    /// only happens in-process and outside of Unix.
+    pub fn write(error: io::Error) -> UResult<()> {
+        UError::write_with(error, |error| UError::io("write error", &error))
+    }
+
+    /// Handle an error writing the output like [`UError::write`], reporting the errors other than a broken
+    /// pipe with `other`.
+    pub fn write_with(error: io::Error, other: impl FnOnce(io::Error) -> UError) -> UResult<()> {
+        match error.kind() {
+            io::ErrorKind::BrokenPipe => Ok(()),
+            _ => Err(other(error)),
+        }
+    }
+}
+
+impl Display for UError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for UError {}
+
+impl From<io::Error> for UError {
+    fn from(error: io::Error) -> Self {
+        UError::new(EXIT_FAILURE, io_error_message(&error))
+    }
+}
+
+/// Format an I/O error the way GNU does, without the " (os error N)" suffix that Rust appends.
+pub fn io_error_message(error: &io::Error) -> String {
+    let message = error.to_string();
+    match message.rfind(" (os error ") {
+        Some(index) => message[..index].to_string(),
+        None => message,
+    }
+}
+
+/// Prints diagnostics prefixed with the utility name and remembers whether any failure occurred.
+pub struct Reporter<W: Write> {
+    util: &'static str,
+    stderr: W,
+    failed: bool,
+}
+
+impl<W: Write> Reporter<W> {
+    /// Create a reporter for the given utility, printing to `stderr`.
+    pub fn new(util: &'static str, stderr: W) -> Self {
+        Reporter {
+            util,
+            stderr,
+            failed: false,
+        }
+    }
+
+    /// Get the name of the utility.
+    pub fn util(&self) -> &'static str {
+        self.util
+    }
+
+    /// Get the stream the diagnostics are printed to, to print other messages there, like questions.
+    pub fn stderr(&mut self) -> &mut W {
+        &mut self.stderr
+    }
+
+    /// Print an error message and remember the failure. See also [`show_error!`](crate::show_error).
+    pub fn error(&mut self, message: fmt::Arguments) {
+        self.failed = true;
+        // There is nowhere left to report a failure to write to stderr.
+        let _ = writeln!(self.stderr, "{}: {message}", self.util);
+    }
+
+    /// Print a message without remembering a failure.
+    pub fn warning(&mut self, message: fmt::Arguments) {
+        let _ = writeln!(self.stderr, "{}: {message}", self.util);
+    }
+
+    /// Print an I/O error for an operand, like `util: file: No such file or directory`, and remember the
+    /// failure.
+    pub fn io_error(&mut self, operand: impl Display, error: &io::Error) {
+        self.error(format_args!("{operand}: {}", io_error_message(error)));
+    }
+
+    /// Remember a failure without printing anything.
+    pub fn set_failed(&mut self) {
+        self.failed = true;
+    }
+
+    /// Check whether any failure occurred.
+    pub fn failed(&self) -> bool {
+        self.failed
+    }
+
+    /// Get the exit code for the failures reported so far.
+    pub fn exit_code(&self) -> i32 {
+        if self.failed {
+            EXIT_FAILURE
+        } else {
+            EXIT_SUCCESS
+        }
+    }
+
+    /// Print the hint which follows the errors of an invalid command line in GNU, to be used after a warning
+    /// which is one of them. See [`UError::usage`].
+    pub fn usage_hint(&mut self) {
+        let _ = writeln!(
+            self.stderr,
+            "Try '{} --help' for more information.",
+            self.util
+        );
+    }
+
+    /// Print the error that stopped the utility, if any, and get the final exit code.
+    pub fn finish(mut self, result: UResult<()>) -> i32 {
+        match result {
+            Ok(()) => self.exit_code(),
+            Err(error) => {
+                self.error(format_args!("{error}"));
+                if error.usage {
+                    self.usage_hint();
+                }
+                error.code
+            }
+        }
+    }
+}
+
+/// Print an error message prefixed with the utility name through a [`Reporter`], and remember the failure.
+#[macro_export]
+macro_rules! show_error {
+    ($reporter:expr, $($arg:tt)+) => {
+        $reporter.error(format_args!($($arg)+))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_io_error_message() {
+        let error = io::Error::from_raw_os_error(2);
+        assert_eq!(io_error_message(&error), "No such file or directory");
+
+        let error = io::Error::other("custom message");
+        assert_eq!(io_error_message(&error), "custom message");
+    }
+
+    #[test]
+    fn test_write() {
+        assert_eq!(
+            UError::write(io::Error::from(io::ErrorKind::BrokenPipe)),
+            Ok(())
+        );
+        assert_eq!(
+            UError::write(io::Error::from_raw_os_error(28)),
+            Err(UError::new(
+                EXIT_FAILURE,
+                "write error: No space left on device"
+            ))
+        );
+        assert_eq!(
+            UError::write_with(io::Error::from_raw_os_error(28), |_| UError::usage(
+                EXIT_USAGE, "other"
+            )),
+            Err(UError::usage(EXIT_USAGE, "other"))
+        );
+    }
+
+    #[test]
+    fn test_reporter() {
+        let mut stderr = Vec::new();
+        let mut reporter = Reporter::new("util", &mut stderr);
+        assert_eq!(reporter.exit_code(), EXIT_SUCCESS);
+
+        reporter.warning(format_args!("just a warning"));
+        assert!(!reporter.failed());
+
+        reporter.io_error("file", &io::Error::from_raw_os_error(13));
+        show_error!(reporter, "invalid {}", "input");
+        assert!(reporter.failed());
+        assert_eq!(reporter.exit_code(), EXIT_FAILURE);
+        assert_eq!(reporter.finish(Ok(())), EXIT_FAILURE);
+
+        assert_eq!(
+            String::from_utf8(stderr).unwrap(),
+            "util: just a warning\nutil: file: Permission denied\nutil: invalid input\n"
+        );
+    }
+
+    #[test]
+    fn test_finish() {
+        let mut stderr = Vec::new();
+        let reporter = Reporter::new("util", &mut stderr);
+        assert_eq!(reporter.finish(Ok(())), EXIT_SUCCESS);
+
+        let reporter = Reporter::new("util", &mut stderr);
+        assert_eq!(
+            reporter.finish(Err(UError::usage(EXIT_USAGE, "missing operand"))),
+            EXIT_USAGE
+        );
+
+        let reporter = Reporter::new("util", &mut stderr);
+        let error = UError::io("file", &io::Error::from_raw_os_error(2));
+        assert_eq!(reporter.finish(Err(error)), EXIT_FAILURE);
+
+        assert_eq!(
+            String::from_utf8(stderr).unwrap(),
+            "util: missing operand\nTry 'util --help' for more information.\n\
+             util: file: No such file or directory\n"
+        );
+    }
+}
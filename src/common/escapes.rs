@@ -0,0 +1,408 @@
+//! Backslash escape sequences, as interpreted by echo, printf, and tr.
+//!
+//! All of them recognize the same basic table (`\\`, `\a`, `\b`, `\f`, `\n`, `\r`, `\t`, `\v`, and octal
+//! values), but they differ in the details, which are selected with an [`EscapeFlavor`]:
+//!
+//! | Escape          | [`Echo`]         | [`Printf`]      | [`PrintfArgument`]        | [`Tr`]          |
+//! |-----------------|------------------|-----------------|---------------------------|-----------------|
+//! | octal           | `\0NNN` (0 to 3) | `\NNN` (1 to 3) | `\0NNN` (0 to 3), `\NNN`  | `\NNN` (1 to 3) |
+//! | `\c`            | stops the output | stops the output| stops the output          | `c`             |
+//! | `\e`            | escape           | escape          | escape                    | `e`             |
+//! | `\"`            | `\"`             | `"`             | `"`                       | `"`             |
+//! | `\xHH`, `\u`... | yes              | yes             | yes                       | no              |
+//! | unknown `\q`    | `\q`             | `\q`            | `\q`                      | `q`             |
+//!
+//! [`Echo`]: EscapeFlavor::Echo
+//! [`Printf`]: EscapeFlavor::Printf
+//! [`PrintfArgument`]: EscapeFlavor::PrintfArgument
+//! [`Tr`]: EscapeFlavor::Tr
+
+/// The dialect of backslash escapes to interpret.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EscapeFlavor {
+    /// `echo -e`: octal values are introduced by `\0`, unknown escapes are printed as they are.
+    Echo,
+    /// The format string of printf: octal values are introduced by any octal digit.
+    Printf,
+    /// The arguments of printf's `%b`: octal values are introduced by `\0` (followed by up to three digits)
+    /// or by any other octal digit.
+    PrintfArgument,
+    /// The sets of tr: no `\c` and no hexadecimal or Unicode escapes, and the backslash is dropped from
+    /// unknown escapes.
+    Tr,
+}
+
+/// The result of expanding the escape sequences in a string.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExpandResult {
+    /// The expanded bytes, up to the terminate-output escape if there was one.
+    pub output: Vec<u8>,
+    /// Whether a terminate-output escape (`\c`) was found, which means that no further output should be
+    /// produced by the caller.
+    pub terminated: bool,
+}
+
+/// Expand all escape sequences in `input`.
+///
+/// ```
+/// use rust_coreutils::common::escapes::{expand_escapes, EscapeFlavor};
+///
+/// let result = expand_escapes(br"a\tb\0101\c ignored", EscapeFlavor::Echo);
+/// assert_eq!(result.output, b"a\tbA");
+/// assert!(result.terminated);
+/// ```
+pub fn expand_escapes(input: &[u8], flavor: EscapeFlavor) -> ExpandResult {
+    let mut output = Vec::with_capacity(input.len());
+    let terminated = expand_escapes_into(input, flavor, &mut output);
+    ExpandResult { output, terminated }
+}
+
+/// Expand all escape sequences in `input`, appending the result to `output`.
+///
+/// The expansion is a single left-to-right pass, so that every escape is consumed exactly once and the
+/// output of one escape is never reinterpreted as the start of another. The output is never longer than
+/// the input.
+///
+/// Returns `true` if a terminate-output escape was found.
+pub fn expand_escapes_into(input: &[u8], flavor: EscapeFlavor, output: &mut Vec<u8>) -> bool {
+    let mut index = 0;
+    while index < input.len() {
+        match memchr_backslash(&input[index..]) {
+            Some(offset) => {
+                output.extend_from_slice(&input[index..index + offset]);
+                index += offset;
+            }
+            None => {
+                output.extend_from_slice(&input[index..]);
+                break;
+            }
+        }
+
+        match expand_escape(&input[index..], flavor, output) {
+            Some(length) => index += length,
+            None => return true,
+        }
+    }
+    false
+}
+
+/// Expand the escape sequence at the start of `input`, which must begin with a backslash, appending the
+/// result to `output`.
+///
+/// Returns the length of the sequence, or `None` for a terminate-output escape, in which case nothing is
+/// appended. A backslash at the end of the input is kept as it is.
+///
+/// This is useful for callers that interpret other syntax between the escapes, like the printf format.
+pub fn expand_escape(input: &[u8], flavor: EscapeFlavor, output: &mut Vec<u8>) -> Option<usize> {
+    debug_assert_eq!(input.first(), Some(&b'\\'));
+
+    let Some(&escape) = input.get(1) else {
+        output.push(b'\\');
+        return Some(1);
+    };
+
+    let tr = flavor == EscapeFlavor::Tr;
+    let byte = match escape {
+        b'\\' => b'\\',
+        b'a' => b'\x07',
+        b'b' => b'\x08',
+        b'f' => b'\x0c',
+        b'n' => b'\n',
+        b'r' => b'\r',
+        b't' => b'\t',
+        b'v' => b'\x0b',
+        b'c' if !tr => return None,
+        b'e' if !tr => b'\x1b',
+        b'"' if flavor != EscapeFlavor::Echo => b'"',
+        b'0' if matches!(flavor, EscapeFlavor::Echo | EscapeFlavor::PrintfArgument) => {
+            // `\0` without any digits is a NUL byte. Values above 0o377 wrap around like in GNU.
+            let (value, digits) = take_digits(&input[2..], 8, 3);
+            output.push(value.unwrap_or(0) as u8);
+            return Some(2 + digits);
+        }
+        b'0'..=b'7' if flavor != EscapeFlavor::Echo => {
+            let (value, digits) = take_digits(&input[1..], 8, 3);
+            output.push(value.unwrap_or(0) as u8);
+            return Some(1 + digits);
+        }
+        b'x' if !tr => {
+            let (value, digits) = take_digits(&input[2..], 16, 2);
+            match value {
+                Some(value) => output.push(value as u8),
+                // `\x` without any digits is printed as it is.
+                None => output.extend_from_slice(br"\x"),
+            }
+            return Some(2 + digits);
+        }
+        b'u' | b'U' if !tr => {
+            let max_digits = if escape == b'u' { 4 } else { 8 };
+            let (value, digits) = take_digits(&input[2..], 16, max_digits);
+            let length = 2 + digits;
+            match value.and_then(char::from_u32) {
+                Some(c) => output.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+                // Missing digits, surrogates, and values above U+10FFFF are printed as they are.
+                None => output.extend_from_slice(&input[..length]),
+            }
+            return Some(length);
+        }
+        // Unknown escapes are printed as they are, except in tr, which drops the backslash.
+        _ if tr => escape,
+        _ => {
+            output.push(b'\\');
+            return Some(1);
+        }
+    };
+
+    output.push(byte);
+    Some(2)
+}
+
+/// Find the first backslash in `data`.
+fn memchr_backslash(data: &[u8]) -> Option<usize> {
+    data.iter().position(|&byte| byte == b'\\')
+}
+
+/// Parse up to `max_digits` leading digits of `data` in the given radix.
+///
+/// Returns their value, or `None` if `data` does not start with a digit, and the number of digits.
+fn take_digits(data: &[u8], radix: u32, max_digits: usize) -> (Option<u32>, usize) {
+    let mut value = None;
+    let mut digits = 0;
+    for &byte in data.iter().take(max_digits) {
+        let Some(digit) = char::from(byte).to_digit(radix) else {
+            break;
+        };
+        value = Some(value.unwrap_or(0) * radix + digit);
+        digits += 1;
+    }
+    (value, digits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLAVORS: [EscapeFlavor; 4] = [
+        EscapeFlavor::Echo,
+        EscapeFlavor::Printf,
+        EscapeFlavor::PrintfArgument,
+        EscapeFlavor::Tr,
+    ];
+
+    fn expand(input: &[u8], flavor: EscapeFlavor) -> Vec<u8> {
+        expand_escapes(input, flavor).output
+    }
+
+    #[test]
+    fn test_no_escapes() {
+        for flavor in FLAVORS {
+            assert_eq!(expand_escapes(b"", flavor), ExpandResult::default());
+            assert_eq!(expand(b"plain text", flavor), b"plain text");
+            assert_eq!(expand(b"\xff\x00", flavor), b"\xff\x00");
+        }
+    }
+
+    #[test]
+    fn test_common_escapes() {
+        for flavor in FLAVORS {
+            assert_eq!(expand(br"\\", flavor), b"\\");
+            assert_eq!(expand(br"\a", flavor), b"\x07");
+            assert_eq!(expand(br"\b", flavor), b"\x08");
+            assert_eq!(expand(br"\f", flavor), b"\x0c");
+            assert_eq!(expand(br"\n", flavor), b"\n");
+            assert_eq!(expand(br"\r", flavor), b"\r");
+            assert_eq!(expand(br"\t", flavor), b"\t");
+            assert_eq!(expand(br"\v", flavor), b"\x0b");
+            assert_eq!(expand(br"a\tb\nc", flavor), b"a\tb\nc");
+        }
+    }
+
+    #[test]
+    fn test_escaped_backslash_is_consumed_once() {
+        for flavor in FLAVORS {
+            assert_eq!(expand(br"\\n", flavor), b"\\n");
+            assert_eq!(expand(br"\\\n", flavor), b"\\\n");
+            assert_eq!(expand(br"\\\\n", flavor), b"\\\\n");
+            assert_eq!(expand(br"\\101", flavor), b"\\101");
+        }
+    }
+
+    #[test]
+    fn test_trailing_backslash() {
+        for flavor in FLAVORS {
+            assert_eq!(expand(b"\\", flavor), b"\\");
+            assert_eq!(expand(b"data\\", flavor), b"data\\");
+        }
+    }
+
+    #[test]
+    fn test_terminate() {
+        for flavor in [
+            EscapeFlavor::Echo,
+            EscapeFlavor::Printf,
+            EscapeFlavor::PrintfArgument,
+        ] {
+            let result = expand_escapes(br"a\cb", flavor);
+            assert_eq!(result.output, b"a");
+            assert!(result.terminated);
+
+            let result = expand_escapes(br"\\c", flavor);
+            assert_eq!(result.output, b"\\c");
+            assert!(!result.terminated);
+        }
+
+        let result = expand_escapes(br"a\cb", EscapeFlavor::Tr);
+        assert_eq!(result.output, b"acb");
+        assert!(!result.terminated);
+    }
+
+    #[test]
+    fn test_escape_and_quote() {
+        assert_eq!(expand(br"\e", EscapeFlavor::Echo), b"\x1b");
+        assert_eq!(expand(br"\e", EscapeFlavor::Printf), b"\x1b");
+        assert_eq!(expand(br"\e", EscapeFlavor::PrintfArgument), b"\x1b");
+        assert_eq!(expand(br"\e", EscapeFlavor::Tr), b"e");
+
+        assert_eq!(expand(br#"\""#, EscapeFlavor::Echo), br#"\""#);
+        assert_eq!(expand(br#"\""#, EscapeFlavor::Printf), b"\"");
+        assert_eq!(expand(br#"\""#, EscapeFlavor::PrintfArgument), b"\"");
+        assert_eq!(expand(br#"\""#, EscapeFlavor::Tr), b"\"");
+    }
+
+    #[test]
+    fn test_octal_echo() {
+        let flavor = EscapeFlavor::Echo;
+        assert_eq!(expand(br"\0", flavor), b"\0");
+        assert_eq!(expand(br"\08", flavor), b"\x008");
+        assert_eq!(expand(br"\0101", flavor), b"A");
+        assert_eq!(expand(br"\01234", flavor), b"S4");
+        assert_eq!(expand(br"\0777", flavor), b"\xff");
+        assert_eq!(expand(br"\0400", flavor), b"\0");
+        assert_eq!(expand(br"\101", flavor), br"\101");
+    }
+
+    #[test]
+    fn test_octal_printf() {
+        let flavor = EscapeFlavor::Printf;
+        assert_eq!(expand(br"\0", flavor), b"\0");
+        assert_eq!(expand(br"\101", flavor), b"A");
+        assert_eq!(expand(br"\1010", flavor), b"A0");
+        assert_eq!(expand(br"\0101", flavor), b"\x081");
+        assert_eq!(expand(br"\7", flavor), b"\x07");
+        assert_eq!(expand(br"\8", flavor), br"\8");
+        assert_eq!(expand(br"\777", flavor), b"\xff");
+    }
+
+    #[test]
+    fn test_octal_printf_argument() {
+        let flavor = EscapeFlavor::PrintfArgument;
+        assert_eq!(expand(br"\0", flavor), b"\0");
+        assert_eq!(expand(br"\0101", flavor), b"A");
+        assert_eq!(expand(br"\101", flavor), b"A");
+        assert_eq!(expand(br"\01010", flavor), b"A0");
+        assert_eq!(expand(br"\1010", flavor), b"A0");
+    }
+
+    #[test]
+    fn test_octal_tr() {
+        let flavor = EscapeFlavor::Tr;
+        assert_eq!(expand(br"\0", flavor), b"\0");
+        assert_eq!(expand(br"\101", flavor), b"A");
+        assert_eq!(expand(br"\0101", flavor), b"\x081");
+        assert_eq!(expand(br"\12a", flavor), b"\na");
+    }
+
+    #[test]
+    fn test_hexadecimal() {
+        for flavor in [
+            EscapeFlavor::Echo,
+            EscapeFlavor::Printf,
+            EscapeFlavor::PrintfArgument,
+        ] {
+            assert_eq!(expand(br"\x41", flavor), b"A");
+            assert_eq!(expand(br"\x414", flavor), b"A4");
+            assert_eq!(expand(br"\xA", flavor), b"\n");
+            assert_eq!(expand(br"\xff", flavor), b"\xff");
+            assert_eq!(expand(br"\xfG", flavor), b"\x0fG");
+            assert_eq!(expand(br"\x", flavor), br"\x");
+            assert_eq!(expand(br"\xg", flavor), br"\xg");
+        }
+        assert_eq!(expand(br"\x41", EscapeFlavor::Tr), b"x41");
+    }
+
+    #[test]
+    fn test_unicode() {
+        for flavor in [
+            EscapeFlavor::Echo,
+            EscapeFlavor::Printf,
+            EscapeFlavor::PrintfArgument,
+        ] {
+            assert_eq!(expand(br"\u00e9", flavor), "é".as_bytes());
+            assert_eq!(expand(br"\u00e9ff", flavor), "éff".as_bytes());
+            assert_eq!(expand(br"\u41\U42", flavor), b"AB");
+            assert_eq!(expand(br"\U0001F600", flavor), "😀".as_bytes());
+            assert_eq!(expand(br"\U0001F6000", flavor), "😀0".as_bytes());
+            assert_eq!(expand(br"\u \U", flavor), br"\u \U");
+            assert_eq!(expand(br"\uD800", flavor), br"\uD800");
+            assert_eq!(expand(br"\U00110000", flavor), br"\U00110000");
+            assert_eq!(expand(br"\UFFFFFFFF", flavor), br"\UFFFFFFFF");
+        }
+        assert_eq!(expand(br"\u00e9", EscapeFlavor::Tr), b"u00e9");
+    }
+
+    #[test]
+    fn test_unknown_escapes() {
+        for flavor in [
+            EscapeFlavor::Echo,
+            EscapeFlavor::Printf,
+            EscapeFlavor::PrintfArgument,
+        ] {
+            assert_eq!(expand(br"\q", flavor), br"\q");
+            assert_eq!(expand(br"\\\q", flavor), br"\\q");
+            assert_eq!(expand("\\é".as_bytes(), flavor), "\\é".as_bytes());
+        }
+        assert_eq!(expand(br"\q\-", EscapeFlavor::Tr), b"q-");
+    }
+
+    #[test]
+    fn test_expand_escape() {
+        let mut output = Vec::new();
+        assert_eq!(
+            expand_escape(br"\x41zz", EscapeFlavor::Printf, &mut output),
+            Some(4)
+        );
+        assert_eq!(
+            expand_escape(br"\101%d", EscapeFlavor::Printf, &mut output),
+            Some(4)
+        );
+        assert_eq!(
+            expand_escape(br"\q", EscapeFlavor::Printf, &mut output),
+            Some(1)
+        );
+        assert_eq!(
+            expand_escape(br"\", EscapeFlavor::Printf, &mut output),
+            Some(1)
+        );
+        assert_eq!(
+            expand_escape(br"\c", EscapeFlavor::Printf, &mut output),
+            None
+        );
+        assert_eq!(output, b"AA\\\\");
+    }
+
+    #[test]
+    fn test_expand_escapes_into() {
+        let mut output = b"prefix ".to_vec();
+        assert!(!expand_escapes_into(
+            br"a\tb",
+            EscapeFlavor::Echo,
+            &mut output
+        ));
+        assert!(expand_escapes_into(
+            br"c\cd",
+            EscapeFlavor::Echo,
+            &mut output
+        ));
+        assert_eq!(output, b"prefix a\tbc");
+    }
+}
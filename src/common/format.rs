@@ -0,0 +1,527 @@
+//! The conversion specifications of printf-style formats, like `%-8.3f`, as used by printf and seq, and the
+//! parsing of their floating point arguments.
+
+use std::io::{self, Write};
+
+/// The largest width or precision, which is the largest `int` of C, like in GNU.
+pub const MAX_COUNT: usize = i32::MAX as usize;
+
+/// A conversion specification of the format, like `%-8.3f`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Spec {
+    /// Justify to the left of the field instead of the right (`-`).
+    pub left: bool,
+    /// The sign of the numbers which are not negative, `+` or a space, if any.
+    pub sign: Option<u8>,
+    /// Use the alternate form (`#`): `0` before octal, `0x` before hexadecimal, and always a decimal point.
+    pub alternate: bool,
+    /// Pad the numbers with zeros instead of spaces (`0`).
+    pub zero: bool,
+    /// The minimum width of the field.
+    pub width: Option<Count>,
+    /// The minimum number of digits of the integers, the number of digits after the decimal point of `%f`
+    /// and `%e`, the number of significant digits of `%g`, or the maximum number of bytes of `%s`.
+    pub precision: Option<Count>,
+    /// The byte which selects the conversion, like `d`.
+    pub conversion: u8,
+}
+
+/// A width or a precision.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Count {
+    /// A number in the format.
+    Fixed(usize),
+    /// `*`, for the next argument.
+    Argument,
+}
+
+impl Spec {
+    /// Parse the specification at the start of `format`, which starts with `%`, getting it and its length,
+    /// or the GNU message if it is invalid, including a width or a precision larger than [`MAX_COUNT`].
+    ///
+    /// The length modifiers of C, like `l` in `%ld`, are accepted and ignored, and so is the `'` flag, as
+    /// there is no grouping of the digits in the C locale. Like in GNU, `%b` takes no flags.
+    pub fn parse(format: &[u8]) -> Result<(Spec, usize), String> {
+        debug_assert_eq!(format.first(), Some(&b'%'));
+        if format.get(1) == Some(&b'b') {
+            let spec = Spec {
+                conversion: b'b',
+                ..Spec::default()
+            };
+            return Ok((spec, 2));
+        }
+
+        let mut spec = Spec::default();
+        // Like GNU, some flags are not valid for some conversions.
+        let mut invalid: Vec<u8> = Vec::new();
+        let mut index = 1;
+        loop {
+            match format.get(index) {
+                Some(b'-') => spec.left = true,
+                Some(b'+') => spec.sign = Some(b'+'),
+                Some(b' ') => {
+                    spec.sign.get_or_insert(b' ');
+                }
+                Some(b'#') => {
+                    spec.alternate = true;
+                    invalid.extend_from_slice(b"cdisu");
+                }
+                Some(b'0') => {
+                    spec.zero = true;
+                    invalid.extend_from_slice(b"cs");
+                }
+                Some(b'\'' | b'I') => invalid.extend_from_slice(b"ceEosxX"),
+                _ => break,
+            }
+            index += 1;
+        }
+        let (width, length) = parse_count(&format[index..], "invalid field width")?;
+        spec.width = width;
+        index += length;
+        if format.get(index) == Some(&b'.') {
+            let (precision, length) = parse_count(&format[index + 1..], "invalid precision")?;
+            spec.precision = Some(precision.unwrap_or(Count::Fixed(0)));
+            index += 1 + length;
+        }
+        while format
+            .get(index)
+            .is_some_and(|byte| b"hjlLtz".contains(byte))
+        {
+            index += 1;
+        }
+        match format.get(index) {
+            Some(&conversion)
+                if b"cdeEfFgGiosuxX".contains(&conversion) && !invalid.contains(&conversion) =>
+            {
+                spec.conversion = conversion;
+                Ok((spec, index + 1))
+            }
+            _ => Err(format!(
+                "{}: invalid conversion specification",
+                String::from_utf8_lossy(&format[..(index + 1).min(format.len())])
+            )),
+        }
+    }
+
+    /// Format the value of `%d` or `%i`.
+    pub fn signed(self, value: i64, precision: Option<usize>) -> Field {
+        let sign = if value < 0 { Some(b'-') } else { self.sign };
+        let digits = integer_digits(value.unsigned_abs().to_string(), precision);
+        Field::digits(
+            sign.map(|sign| vec![sign]).unwrap_or_default(),
+            digits,
+            self.zero && precision.is_none(),
+        )
+    }
+
+    /// Format the value of `%o`, `%u`, `%x`, or `%X`, which have no sign.
+    pub fn unsigned(self, value: u64, precision: Option<usize>) -> Field {
+        let digits = match self.conversion {
+            b'o' => format!("{value:o}"),
+            b'u' => value.to_string(),
+            b'x' => format!("{value:x}"),
+            _ => format!("{value:X}"),
+        };
+        let mut digits = integer_digits(digits, precision);
+        let mut prefix = Vec::new();
+        if self.alternate {
+            match self.conversion {
+                b'o' if digits.zeros == 0 && !digits.text.starts_with('0') => {
+                    digits.text.insert(0, '0')
+                }
+                b'x' if value != 0 => prefix.extend_from_slice(b"0x"),
+                b'X' if value != 0 => prefix.extend_from_slice(b"0X"),
+                _ => {}
+            }
+        }
+        Field::digits(prefix, digits, self.zero && precision.is_none())
+    }
+
+    /// Format the value of `%f`, `%F`, `%e`, `%E`, `%g`, or `%G`, with 6 digits by default.
+    pub fn float(self, value: f64, precision: Option<usize>) -> Field {
+        let sign = if value.is_sign_negative() {
+            Some(b'-')
+        } else {
+            self.sign
+        };
+        let sign = sign.map(|sign| vec![sign]).unwrap_or_default();
+        let upper = self.conversion.is_ascii_uppercase();
+        if !value.is_finite() {
+            let text = if value.is_nan() { "nan" } else { "inf" };
+            let text = if upper {
+                text.to_ascii_uppercase()
+            } else {
+                text.to_owned()
+            };
+            // Like in C, the zeros are not padded before infinity and NaN.
+            return Field::number(sign, text, false);
+        }
+
+        let value = value.abs();
+        let precision = precision.unwrap_or(6);
+        let mut digits = match self.conversion.to_ascii_lowercase() {
+            b'f' => fixed(value, precision, self.alternate),
+            b'e' => exponent(value, precision, self.alternate),
+            _ => {
+                // The number of significant digits, which decides between the two forms with the exponent.
+                let precision = precision.max(1);
+                let exponent_form = exponent(value, precision - 1, false);
+                let (_, power) = exponent_form.text.split_once('e').expect("an exponent");
+                let power: isize = power.parse().expect("a decimal exponent");
+                let mut digits = if -4 <= power && power < precision as isize {
+                    fixed(
+                        value,
+                        (precision as isize - 1 - power) as usize,
+                        self.alternate,
+                    )
+                } else {
+                    exponent(value, precision - 1, self.alternate)
+                };
+                if !self.alternate {
+                    trim_zeros(&mut digits);
+                }
+                digits
+            }
+        };
+        if upper {
+            digits.text.make_ascii_uppercase();
+        }
+        Field::digits(sign, digits, self.zero)
+    }
+}
+
+/// Parse a width or a precision at the start of `format`, getting it, if any, and its length, or the
+/// message starting with `problem` if it is larger than [`MAX_COUNT`].
+fn parse_count(format: &[u8], problem: &str) -> Result<(Option<Count>, usize), String> {
+    if format.first() == Some(&b'*') {
+        return Ok((Some(Count::Argument), 1));
+    }
+    let length = format
+        .iter()
+        .take_while(|byte| byte.is_ascii_digit())
+        .count();
+    let count = format[..length].iter().try_fold(0usize, |count, &digit| {
+        count
+            .checked_mul(10)?
+            .checked_add(usize::from(digit - b'0'))
+            .filter(|&count| count <= MAX_COUNT)
+    });
+    match count {
+        Some(count) => Ok(((length > 0).then_some(Count::Fixed(count)), length)),
+        None => Err(format!(
+            "{problem}: '{}'",
+            String::from_utf8_lossy(&format[..length])
+        )),
+    }
+}
+
+/// The digits of a number, with zeros which are inserted at `at` only when they are written, as a precision
+/// can make too many of them to keep in memory.
+struct Digits {
+    text: String,
+    zeros: usize,
+    at: usize,
+}
+
+impl Digits {
+    /// Get the digits without zeros to insert.
+    fn new(text: String) -> Digits {
+        Digits {
+            text,
+            zeros: 0,
+            at: 0,
+        }
+    }
+}
+
+/// Pad the digits of an integer with zeros to the precision, where a precision of 0 prints no digits for 0,
+/// like in C.
+fn integer_digits(digits: String, precision: Option<usize>) -> Digits {
+    match precision {
+        Some(0) if digits == "0" => Digits::new(String::new()),
+        Some(precision) if digits.len() < precision => Digits {
+            zeros: precision - digits.len(),
+            ..Digits::new(digits)
+        },
+        _ => Digits::new(digits),
+    }
+}
+
+/// The number of digits after the decimal point which are enough to write any `f64` exactly, the rest being
+/// zeros. Rust cannot format a precision larger than `u16::MAX` itself.
+const EXACT_DIGITS: usize = 1100;
+
+/// Format a number which is not negative like `%f`, where `alternate` keeps the decimal point without
+/// digits after it.
+fn fixed(value: f64, precision: usize, alternate: bool) -> Digits {
+    let exact = precision.min(EXACT_DIGITS);
+    let mut text = format!("{value:.exact$}");
+    if alternate && precision == 0 {
+        text.push('.');
+    }
+    Digits {
+        zeros: precision - exact,
+        at: text.len(),
+        text,
+    }
+}
+
+/// Format a number which is not negative like `%e`, with a sign and at least two digits in the exponent
+/// unlike in Rust.
+fn exponent(value: f64, precision: usize, alternate: bool) -> Digits {
+    let exact = precision.min(EXACT_DIGITS);
+    let formatted = format!("{value:.exact$e}");
+    let (mantissa, power) = formatted.split_once('e').expect("an exponent");
+    let (sign, power) = match power.strip_prefix('-') {
+        Some(power) => ('-', power),
+        None => ('+', power),
+    };
+    let point = if alternate && precision == 0 { "." } else { "" };
+    Digits {
+        text: format!("{mantissa}{point}e{sign}{power:0>2}"),
+        zeros: precision - exact,
+        at: mantissa.len(),
+    }
+}
+
+/// Remove the zeros at the end of the fractional part of `%g`, and the decimal point if nothing is left
+/// after it.
+fn trim_zeros(digits: &mut Digits) {
+    // The zeros to insert are at the end of the fractional part.
+    digits.zeros = 0;
+    digits.at = 0;
+    let text = &mut digits.text;
+    let exponent = text
+        .find('e')
+        .map_or(String::new(), |index| text.split_off(index));
+    if text.contains('.') {
+        let length = text.trim_end_matches('0').trim_end_matches('.').len();
+        text.truncate(length);
+    }
+    text.push_str(&exponent);
+}
+
+/// A formatted value, before it is padded to the width of its field.
+pub struct Field {
+    /// The sign or the `0x` of a number, which go before the zeros of the padding.
+    prefix: Vec<u8>,
+    body: Vec<u8>,
+    /// The zeros of the precision, which are inserted at `zeros_at` in the body only when it is written.
+    zeros: usize,
+    zeros_at: usize,
+    /// Pad with zeros instead of spaces, when justifying to the right.
+    zero: bool,
+}
+
+impl Field {
+    /// Get the field of some text, which is padded with spaces.
+    pub fn text(text: &[u8]) -> Field {
+        Field {
+            prefix: Vec::new(),
+            body: text.to_vec(),
+            zeros: 0,
+            zeros_at: 0,
+            zero: false,
+        }
+    }
+
+    /// Get the field of a number, with its sign or its `0x` in `prefix`, which is padded with zeros with
+    /// `zero`.
+    pub fn number(prefix: Vec<u8>, digits: String, zero: bool) -> Field {
+        Field::digits(prefix, Digits::new(digits), zero)
+    }
+
+    /// Get the field of a number like [`Field::number`], with the zeros of its precision.
+    fn digits(prefix: Vec<u8>, digits: Digits, zero: bool) -> Field {
+        Field {
+            prefix,
+            body: digits.text.into_bytes(),
+            zeros: digits.zeros,
+            zeros_at: digits.at,
+            zero,
+        }
+    }
+
+    /// Print the field, padded to `width` bytes. The padding and the zeros are written a chunk at a time,
+    /// so that a large width or precision does not take memory.
+    pub fn write<W: Write + ?Sized>(
+        self,
+        left: bool,
+        width: usize,
+        output: &mut W,
+    ) -> io::Result<()> {
+        let padding = width.saturating_sub(self.prefix.len() + self.body.len() + self.zeros);
+        let (before_zeros, after_zeros) = self.body.split_at(self.zeros_at);
+        if !left && !self.zero {
+            write_repeated(output, b' ', padding)?;
+        }
+        output.write_all(&self.prefix)?;
+        if !left && self.zero {
+            write_repeated(output, b'0', padding)?;
+        }
+        output.write_all(before_zeros)?;
+        write_repeated(output, b'0', self.zeros)?;
+        output.write_all(after_zeros)?;
+        if left {
+            write_repeated(output, b' ', padding)?;
+        }
+        Ok(())
+    }
+}
+
+/// Write `count` copies of `byte`.
+fn write_repeated<W: Write + ?Sized>(output: &mut W, byte: u8, mut count: usize) -> io::Result<()> {
+    let chunk = [byte; 4096];
+    while count > 0 {
+        let length = count.min(chunk.len());
+        output.write_all(&chunk[..length])?;
+        count -= length;
+    }
+    Ok(())
+}
+
+/// Parse a floating point argument, like `strtod`: it may start with spaces and a sign, and be in decimal
+/// with an optional exponent like `1.5e3`, in hexadecimal with an optional binary exponent like `0x1.8p1`,
+/// or `inf`, `infinity`, or `nan`, in any case. An empty argument is 0.
+///
+/// The value is a double, while GNU uses a long double, so that it has a smaller range and fewer digits.
+/// The result is the value, the message if the argument is not only a number, and whether the value does
+/// not fit.
+pub fn parse_float(argument: &[u8]) -> (f64, Option<&'static str>, bool) {
+    let start = argument
+        .iter()
+        .position(|byte| !b" \t\n\x0b\x0c\r".contains(byte))
+        .unwrap_or(argument.len());
+    let (negative, rest) = match &argument[start..] {
+        [b'-', rest @ ..] => (true, rest),
+        [b'+', rest @ ..] => (false, rest),
+        rest => (false, rest),
+    };
+    let starts_with =
+        |word: &[u8]| rest.len() >= word.len() && rest[..word.len()].eq_ignore_ascii_case(word);
+    let digits = |bytes: &[u8], radix: u32| {
+        bytes
+            .iter()
+            .take_while(|&&byte| char::from(byte).is_digit(radix))
+            .count()
+    };
+
+    let (value, length, range) = if starts_with(b"infinity") {
+        (f64::INFINITY, 8, false)
+    } else if starts_with(b"inf") {
+        (f64::INFINITY, 3, false)
+    } else if starts_with(b"nan") {
+        // Like in C, `nan` may be followed by letters, digits, and underscores in parentheses.
+        let payload = rest[3..]
+            .strip_prefix(b"(")
+            .map(|payload| {
+                payload
+                    .iter()
+                    .take_while(|byte| byte.is_ascii_alphanumeric() || **byte == b'_')
+                    .count()
+            })
+            .filter(|&length| rest.get(4 + length) == Some(&b')'))
+            .map_or(0, |length| length + 2);
+        (f64::NAN, 3 + payload, false)
+    } else if matches!(rest, [b'0', b'x' | b'X', ..])
+        && (digits(&rest[2..], 16) > 0 || rest.get(2) == Some(&b'.') && digits(&rest[3..], 16) > 0)
+    {
+        let integer = digits(&rest[2..], 16);
+        let mut length = 2 + integer;
+        let mut fraction = 0;
+        if rest.get(length) == Some(&b'.') {
+            fraction = digits(&rest[length + 1..], 16);
+            length += 1 + fraction;
+        }
+        let mantissa: Vec<u8> = rest[2..length]
+            .iter()
+            .copied()
+            .filter(|&byte| byte != b'.')
+            .collect();
+        let mut power = -4 * fraction as i64;
+        if let Some((exponent, exponent_length)) = parse_exponent(&rest[length..], b'p') {
+            power = power.saturating_add(exponent);
+            length += exponent_length;
+        }
+        let value = hexadecimal(&mantissa, power);
+        let nonzero = mantissa.iter().any(|&byte| byte != b'0');
+        (
+            value,
+            length,
+            value.is_infinite() || nonzero && value == 0.0,
+        )
+    } else {
+        let integer = digits(rest, 10);
+        let mut length = integer;
+        let mut fraction = 0;
+        if rest.get(length) == Some(&b'.') {
+            fraction = digits(&rest[length + 1..], 10);
+            length += 1 + fraction;
+        }
+        if integer + fraction == 0 {
+            let trailing = (!argument.is_empty()).then_some("expected a numeric value");
+            return (0.0, trailing, false);
+        }
+        if let Some((_, exponent_length)) = parse_exponent(&rest[length..], b'e') {
+            length += exponent_length;
+        }
+        let text = std::str::from_utf8(&rest[..length]).expect("ASCII digits");
+        let value: f64 = text.parse().expect("a decimal number");
+        let nonzero = rest[..length]
+            .iter()
+            .take(integer + 1 + fraction)
+            .any(|byte| (b'1'..=b'9').contains(byte));
+        (
+            value,
+            length,
+            value.is_infinite() || nonzero && value == 0.0,
+        )
+    };
+    let value = if negative { -value } else { value };
+    let trailing = (length < rest.len()).then_some("value not completely converted");
+    (value, trailing, range)
+}
+
+/// Parse an exponent like `e-3` at the start of `bytes`, getting it and its length, or `None` if there are
+/// no digits after the letter and the sign.
+fn parse_exponent(bytes: &[u8], letter: u8) -> Option<(i64, usize)> {
+    let (first, rest) = bytes.split_first()?;
+    if !first.eq_ignore_ascii_case(&letter) {
+        return None;
+    }
+    let (negative, rest, sign) = match rest {
+        [b'-', rest @ ..] => (true, rest, 1),
+        [b'+', rest @ ..] => (false, rest, 1),
+        rest => (false, rest, 0),
+    };
+    let length = rest.iter().take_while(|byte| byte.is_ascii_digit()).count();
+    if length == 0 {
+        return None;
+    }
+    let exponent = rest[..length].iter().fold(0i64, |exponent, &digit| {
+        exponent
+            .saturating_mul(10)
+            .saturating_add(i64::from(digit - b'0'))
+    });
+    Some((
+        if negative { -exponent } else { exponent },
+        1 + sign + length,
+    ))
+}
+
+/// Get the value of hexadecimal digits multiplied by 2 to the power of `power`.
+fn hexadecimal(digits: &[u8], mut power: i64) -> f64 {
+    let mut mantissa = 0u64;
+    for &digit in digits {
+        let digit = char::from(digit).to_digit(16).expect("a hexadecimal digit");
+        if mantissa >> 60 == 0 {
+            mantissa = mantissa * 16 + u64::from(digit);
+        } else {
+            // The digits which do not fit only change the rounding, which is ignored here.
+            power = power.saturating_add(4);
+        }
+    }
+    let power = power.clamp(-2200, 2200) as i32;
+    // Two steps, so that the power of two is not out of range when the mantissa makes up for it.
+    mantissa as f64 * 2f64.powi(power / 2) * 2f64.powi(power - power / 2)
+}
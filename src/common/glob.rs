@@ -0,0 +1,172 @@
+//! Shell wildcard patterns, like the ones of `du --exclude`, matched like `fnmatch` without flags.
+//!
+//! `*` matches any sequence of bytes, including slashes, `?` any byte, and `[...]` any byte of a set, with
+//! ranges like `a-z`, classes like `[:digit:]`, and a leading `!` or `^` to negate it. A backslash makes the
+//! next byte literal:
+//!
+//! ```
+//! use rust_coreutils::common::glob::fnmatch;
+//!
+//! assert!(fnmatch(b"*.[ch]", b"src/main.c"));
+//! assert!(fnmatch(b"file?[!0-9]", b"file1a"));
+//! assert!(!fnmatch(b"\\*", b"file"));
+//! ```
+
+/// Check whether the whole of `name` matches `pattern`.
+pub fn fnmatch(pattern: &[u8], name: &[u8]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    // After a `*`, the position in the pattern following it, and the next position in the name from which
+    // to retry if the rest of the pattern does not match.
+    let mut retry = None;
+    loop {
+        if pattern.get(p) == Some(&b'*') {
+            p += 1;
+            retry = Some((p, n + 1));
+            continue;
+        }
+        let step = match (pattern.get(p), name.get(n)) {
+            (None, None) => return true,
+            (Some(_), Some(&byte)) => element(&pattern[p..], byte),
+            _ => None,
+        };
+        match (step, retry) {
+            (Some(len), _) => {
+                p += len;
+                n += 1;
+            }
+            (None, Some((after_star, next))) if next <= name.len() => {
+                p = after_star;
+                n = next;
+                retry = Some((after_star, next + 1));
+            }
+            (None, _) => return false,
+        }
+    }
+}
+
+/// Match a byte against the element at the start of a pattern, other than `*`, and get the length of the
+/// element if it matches.
+fn element(pattern: &[u8], byte: u8) -> Option<usize> {
+    let (matched, len) = match pattern[0] {
+        b'?' => (true, 1),
+        // An unterminated set is a literal `[`.
+        b'[' => bracket(&pattern[1..], byte)
+            .map_or((byte == b'[', 1), |(matched, len)| (matched, len + 1)),
+        _ => {
+            let (expected, len) = literal(pattern, 0)?;
+            (expected == byte, len)
+        }
+    };
+    matched.then_some(len)
+}
+
+/// Match a byte against a set, given the pattern after its `[`, and get whether it matches and the length
+/// of the set up to its `]`, or nothing if it is not terminated.
+fn bracket(pattern: &[u8], byte: u8) -> Option<(bool, usize)> {
+    let negated = matches!(pattern.first(), Some(b'!' | b'^'));
+    let mut i = usize::from(negated);
+    let start = i;
+    let mut matched = false;
+    loop {
+        let &c = pattern.get(i)?;
+        // A `]` right after the `[` or its negation is part of the set.
+        if c == b']' && i > start {
+            return Some((matched != negated, i + 1));
+        }
+        if pattern[i..].starts_with(b"[:") {
+            if let Some(end) = pattern[i + 2..].windows(2).position(|pair| pair == b":]") {
+                matched |= in_class(&pattern[i + 2..i + 2 + end], byte);
+                i += end + 4;
+                continue;
+            }
+        }
+        let (low, len) = literal(pattern, i)?;
+        i += len;
+        match (pattern.get(i), pattern.get(i + 1)) {
+            (Some(b'-'), Some(&next)) if next != b']' => {
+                let (high, len) = literal(pattern, i + 1)?;
+                i += len + 1;
+                matched |= (low..=high).contains(&byte);
+            }
+            _ => matched |= low == byte,
+        }
+    }
+}
+
+/// Get the byte at a position of a pattern, with the backslash escapes, and the length of its escape.
+fn literal(pattern: &[u8], i: usize) -> Option<(u8, usize)> {
+    match pattern.get(i)? {
+        // A trailing backslash is literal.
+        b'\\' => Some(pattern.get(i + 1).map_or((b'\\', 1), |&byte| (byte, 2))),
+        &byte => Some((byte, 1)),
+    }
+}
+
+/// Check whether a byte is in a character class of the C locale, like `digit`.
+fn in_class(class: &[u8], byte: u8) -> bool {
+    match class {
+        b"alnum" => byte.is_ascii_alphanumeric(),
+        b"alpha" => byte.is_ascii_alphabetic(),
+        b"blank" => byte == b' ' || byte == b'\t',
+        b"cntrl" => byte.is_ascii_control(),
+        b"digit" => byte.is_ascii_digit(),
+        b"graph" => byte.is_ascii_graphic(),
+        b"lower" => byte.is_ascii_lowercase(),
+        b"print" => byte.is_ascii_graphic() || byte == b' ',
+        b"punct" => byte.is_ascii_punctuation(),
+        b"space" => byte.is_ascii_whitespace() || byte == b'\x0b',
+        b"upper" => byte.is_ascii_uppercase(),
+        b"xdigit" => byte.is_ascii_hexdigit(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fnmatch() {
+        for (pattern, name, expected) in [
+            ("", "", true),
+            ("", "a", false),
+            ("abc", "abc", true),
+            ("abc", "abd", false),
+            ("*", "", true),
+            ("*", "a/b", true),
+            ("a*", "a", true),
+            ("*c", "abc", true),
+            ("*c", "abcd", false),
+            ("a*b*c", "axxbyyc", true),
+            ("a*b*c", "axxbyy", false),
+            ("*.tmp", "dir/file.tmp", true),
+            ("**a", "bba", true),
+            ("?", "", false),
+            ("a?c", "abc", true),
+            ("a?c", "ac", false),
+            ("[abc]", "b", true),
+            ("[abc]", "d", false),
+            ("[a-c]x", "bx", true),
+            ("[!a-c]", "b", false),
+            ("[^a-c]", "d", true),
+            ("[]]", "]", true),
+            ("[!]]", "a", true),
+            ("[a-]", "-", true),
+            ("[[:digit:]x]", "7", true),
+            ("[[:digit:]x]", "x", true),
+            ("[[:upper:]]", "a", false),
+            ("[", "[", true),
+            ("[a", "[a", true),
+            ("\\*", "*", true),
+            ("\\*", "a", false),
+            ("[\\]]", "]", true),
+            ("a\\", "a\\", true),
+        ] {
+            assert_eq!(
+                fnmatch(pattern.as_bytes(), name.as_bytes()),
+                expected,
+                "{pattern} {name}"
+            );
+        }
+    }
+}
@@ -0,0 +1,334 @@
+//! Sizes for people, as printed by `du -h`, `df -h`, and `ls -lh`, and the `--block-size` they are counted in.
+//!
+//! Human-readable sizes have at most three digits and a unit, `K`, `M`, `G`, ... for powers of 1024, or `k`,
+//! `M`, `G`, ... for powers of 1000 with `--si`. They are rounded up, so that a size is never shown as
+//! smaller than it is:
+//!
+//! ```
+//! use rust_coreutils::common::human::{human_readable, Base, BlockSize};
+//!
+//! assert_eq!(human_readable(999, Base::Base1024), "999");
+//! assert_eq!(human_readable(1025, Base::Base1024), "1.1K");
+//! assert_eq!(human_readable(1_048_576, Base::Base1024), "1.0M");
+//! assert_eq!(human_readable(1_500_000, Base::Base1000), "1.5M");
+//!
+//! assert_eq!(BlockSize::parse("1K").unwrap().format(5000), "5");
+//! assert_eq!(BlockSize::parse("KB").unwrap().format(5000), "5kB");
+//! assert_eq!(BlockSize::parse("si").unwrap().format(5000), "5.0k");
+//! ```
+
+use crate::common::size::{parse_size_u64, SizeError, SizeErrorKind};
+use std::env;
+
+/// The base of the units of human-readable sizes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Base {
+    /// `-h`: powers of 1024.
+    Base1024,
+    /// `--si`: powers of 1000.
+    Base1000,
+}
+
+/// The units after the first power of the base, which only differ in the case of kilo.
+const UNITS_1024: [&str; 8] = ["K", "M", "G", "T", "P", "E", "Z", "Y"];
+const UNITS_1000: [&str; 8] = ["k", "M", "G", "T", "P", "E", "Z", "Y"];
+
+/// Format a size with at most three digits and a unit, rounding up like GNU.
+///
+/// Sizes below the base are shown as they are. Otherwise, the size is shown with one decimal while it is
+/// below 10 units, and as a whole number of units above.
+pub fn human_readable(bytes: u64, base: Base) -> String {
+    let (base, units) = match base {
+        Base::Base1024 => (1024, UNITS_1024),
+        Base::Base1000 => (1000, UNITS_1000),
+    };
+    let bytes = u128::from(bytes);
+    if bytes < base {
+        return bytes.to_string();
+    }
+
+    let mut exponent = 0;
+    let mut scale = 1;
+    while exponent < units.len() && scale * base <= bytes {
+        scale *= base;
+        exponent += 1;
+    }
+    let unit = units[exponent - 1];
+
+    if bytes < 10 * scale {
+        let tenths = (bytes * 10).div_ceil(scale);
+        return if tenths < 100 {
+            format!("{}.{}{unit}", tenths / 10, tenths % 10)
+        } else {
+            format!("10{unit}")
+        };
+    }
+    let amount = bytes.div_ceil(scale);
+    match units.get(exponent) {
+        // Rounding up reached the next unit.
+        Some(next) if amount == base => format!("1.0{next}"),
+        _ => format!("{amount}{unit}"),
+    }
+}
+
+/// How sizes are counted, as selected by `-h`, `--si`, `-k`, `-m`, `--block-size`, or the environment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BlockSize {
+    /// Human-readable sizes.
+    Human(Base),
+    /// Whole numbers of blocks, rounded up.
+    Blocks {
+        /// The size of a block in bytes.
+        size: u64,
+        /// The unit printed after the numbers, when the block size was given as a unit alone, like `K` or
+        /// `MB`.
+        unit: Option<String>,
+    },
+}
+
+impl BlockSize {
+    /// Count in blocks of `size` bytes, like with `-k` (1024) or `-m` (1048576).
+    pub fn blocks(size: u64) -> Self {
+        BlockSize::Blocks { size, unit: None }
+    }
+
+    /// Parse the argument of `--block-size`: `human-readable`, `si`, or a positive size like `1K`, `512`, or
+    /// `MB`.
+    ///
+    /// A leading `'` asks for the digits to be grouped by thousands, which the C locale does not do, so it
+    /// is accepted and ignored.
+    pub fn parse(spec: &str) -> Result<Self, SizeError> {
+        let invalid = || SizeError {
+            kind: SizeErrorKind::Invalid,
+            input: spec.to_string(),
+        };
+
+        let unquoted = spec.strip_prefix('\'').unwrap_or(spec);
+        match unquoted {
+            "human-readable" => return Ok(BlockSize::Human(Base::Base1024)),
+            "si" => return Ok(BlockSize::Human(Base::Base1000)),
+            _ => {}
+        }
+
+        let digits = unquoted.bytes().take_while(u8::is_ascii_digit).count();
+        let suffix = &unquoted[digits..];
+        // Unlike other sizes, block sizes cannot be counted in 512-byte blocks.
+        if suffix.starts_with('b') {
+            return Err(invalid());
+        }
+        let size = parse_size_u64(unquoted).map_err(|error| SizeError {
+            kind: error.kind,
+            input: spec.to_string(),
+        })?;
+        if size == 0 {
+            return Err(invalid());
+        }
+
+        let unit = (digits == 0).then(|| {
+            let (letter, rest) = suffix.split_at(1);
+            if rest == "B" && letter.eq_ignore_ascii_case("k") {
+                "kB".to_string()
+            } else {
+                letter.to_ascii_uppercase() + rest
+            }
+        });
+        Ok(BlockSize::Blocks { size, unit })
+    }
+
+    /// Get the block size of the environment: the first of `util_var` (like `DU_BLOCK_SIZE`), `BLOCK_SIZE`,
+    /// and `BLOCKSIZE` which is set, if it is valid.
+    pub fn from_env(util_var: &str) -> Option<Self> {
+        Self::from_vars(util_var, |name| env::var(name).ok())
+    }
+
+    fn from_vars(util_var: &str, var: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        let spec = [util_var, "BLOCK_SIZE", "BLOCKSIZE"]
+            .into_iter()
+            .find_map(var)?;
+        Self::parse(&spec).ok()
+    }
+
+    /// Get the block size to use when neither the command line nor the environment sets one: 512 bytes if
+    /// `POSIXLY_CORRECT` is set, and 1024 otherwise.
+    pub fn default_size() -> Self {
+        Self::blocks(if env::var_os("POSIXLY_CORRECT").is_some() {
+            512
+        } else {
+            1024
+        })
+    }
+
+    /// Format a number of bytes.
+    pub fn format(&self, bytes: u64) -> String {
+        match self {
+            BlockSize::Human(base) => human_readable(bytes, *base),
+            BlockSize::Blocks { size, unit } => {
+                let count = bytes.div_ceil(*size);
+                match unit {
+                    Some(unit) => format!("{count}{unit}"),
+                    None => count.to_string(),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gnu_table() {
+        // The sizes shown by GNU `ls -lh` and `ls -l --si`.
+        for (bytes, base_1024, base_1000) in [
+            (0, "0", "0"),
+            (1, "1", "1"),
+            (9, "9", "9"),
+            (10, "10", "10"),
+            (99, "99", "99"),
+            (100, "100", "100"),
+            (999, "999", "999"),
+            (1000, "1000", "1.0k"),
+            (1001, "1001", "1.1k"),
+            (1023, "1023", "1.1k"),
+            (1024, "1.0K", "1.1k"),
+            (1025, "1.1K", "1.1k"),
+            (1126, "1.1K", "1.2k"),
+            (1127, "1.2K", "1.2k"),
+            (1536, "1.5K", "1.6k"),
+            (2047, "2.0K", "2.1k"),
+            (2048, "2.0K", "2.1k"),
+            (9727, "9.5K", "9.8k"),
+            (9728, "9.5K", "9.8k"),
+            (9999, "9.8K", "10k"),
+            (10000, "9.8K", "10k"),
+            (10239, "10K", "11k"),
+            (10240, "10K", "11k"),
+            (10241, "11K", "11k"),
+            (99999, "98K", "100k"),
+            (100000, "98K", "100k"),
+            (102400, "100K", "103k"),
+            (999999, "977K", "1.0M"),
+            (1000000, "977K", "1.0M"),
+            (1047552, "1023K", "1.1M"),
+            (1047553, "1.0M", "1.1M"),
+            (1048575, "1.0M", "1.1M"),
+            (1048576, "1.0M", "1.1M"),
+            (1048577, "1.1M", "1.1M"),
+            (10485759, "10M", "11M"),
+            (10485760, "10M", "11M"),
+            (104857600, "100M", "105M"),
+            (1073741823, "1.0G", "1.1G"),
+            (1073741824, "1.0G", "1.1G"),
+            (1073741825, "1.1G", "1.1G"),
+            (1099511627776, "1.0T", "1.1T"),
+            (1125899906842624, "1.0P", "1.2P"),
+            (1152921504606846976, "1.0E", "1.2E"),
+            (u64::MAX, "16E", "19E"),
+        ] {
+            assert_eq!(human_readable(bytes, Base::Base1024), base_1024, "{bytes}");
+            assert_eq!(human_readable(bytes, Base::Base1000), base_1000, "{bytes}");
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let parse = |spec| BlockSize::parse(spec).unwrap();
+        assert_eq!(parse("human-readable"), BlockSize::Human(Base::Base1024));
+        assert_eq!(parse("si"), BlockSize::Human(Base::Base1000));
+        assert_eq!(parse("'si"), BlockSize::Human(Base::Base1000));
+        assert_eq!(parse("1"), BlockSize::blocks(1));
+        assert_eq!(parse("1K"), BlockSize::blocks(1024));
+        assert_eq!(parse("'1K"), BlockSize::blocks(1024));
+        assert_eq!(parse("2kB"), BlockSize::blocks(2000));
+        for (spec, size, unit) in [
+            ("K", 1024, "K"),
+            ("k", 1024, "K"),
+            ("KB", 1000, "kB"),
+            ("kB", 1000, "kB"),
+            ("KiB", 1024, "KiB"),
+            ("M", 1 << 20, "M"),
+            ("MB", 1_000_000, "MB"),
+            ("'G", 1 << 30, "G"),
+        ] {
+            assert_eq!(
+                parse(spec),
+                BlockSize::Blocks {
+                    size,
+                    unit: Some(unit.to_string())
+                },
+                "{spec}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for spec in ["", "'", "0", "x", "b", "2b", "-1", "1.5K", "human", "SI"] {
+            let error = BlockSize::parse(spec).unwrap_err();
+            assert_eq!(error.kind, SizeErrorKind::Invalid, "{spec:?}");
+            assert_eq!(error.input, spec);
+        }
+        assert_eq!(
+            BlockSize::parse("'16E").unwrap_err().to_string(),
+            "''16E': Value too large for defined data type"
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        let format = |spec, bytes| BlockSize::parse(spec).unwrap().format(bytes);
+        // The sizes shown by GNU `du --apparent-size -B SPEC` for a 5000-byte file.
+        assert_eq!(format("K", 5000), "5K");
+        assert_eq!(format("KB", 5000), "5kB");
+        assert_eq!(format("KiB", 5000), "5KiB");
+        assert_eq!(format("M", 5000), "1M");
+        assert_eq!(format("MB", 5000), "1MB");
+        assert_eq!(format("1K", 5000), "5");
+        assert_eq!(format("1000", 5000), "5");
+        assert_eq!(format("2K", 5000), "3");
+        assert_eq!(format("human-readable", 5000), "4.9K");
+        assert_eq!(format("si", 5000), "5.0k");
+        assert_eq!(BlockSize::blocks(1024).format(0), "0");
+        assert_eq!(BlockSize::blocks(1).format(u64::MAX), u64::MAX.to_string());
+    }
+
+    #[test]
+    fn test_env() {
+        let vars = |pairs: &'static [(&str, &str)]| {
+            move |name: &str| {
+                pairs
+                    .iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        assert_eq!(BlockSize::from_vars("DU_BLOCK_SIZE", vars(&[])), None);
+        assert_eq!(
+            BlockSize::from_vars("DU_BLOCK_SIZE", vars(&[("BLOCKSIZE", "1M")])),
+            Some(BlockSize::blocks(1 << 20))
+        );
+        assert_eq!(
+            BlockSize::from_vars(
+                "DU_BLOCK_SIZE",
+                vars(&[("BLOCK_SIZE", "si"), ("BLOCKSIZE", "1M")])
+            ),
+            Some(BlockSize::Human(Base::Base1000))
+        );
+        assert_eq!(
+            BlockSize::from_vars(
+                "DU_BLOCK_SIZE",
+                vars(&[("DU_BLOCK_SIZE", "512"), ("BLOCK_SIZE", "si")])
+            ),
+            Some(BlockSize::blocks(512))
+        );
+        // An invalid value is ignored rather than falling back to the next variable.
+        assert_eq!(
+            BlockSize::from_vars(
+                "DU_BLOCK_SIZE",
+                vars(&[("DU_BLOCK_SIZE", "x"), ("BLOCK_SIZE", "si")])
+            ),
+            None
+        );
+    }
+}
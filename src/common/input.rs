@@ -0,0 +1,328 @@
+//! Iteration over the input operands of a utility, where `-` or no operands at all mean standard input.
+//!
+//! ```no_run
+//! use rust_coreutils::common::input::InputIter;
+//! use std::io::BufRead;
+//!
+//! let mut inputs = InputIter::new(vec!["a.txt".into(), "-".into()]);
+//! while let Some(input) = inputs.next_buffered() {
+//!     match input {
+//!         Ok(input) => {
+//!             let name = input.display_name().into_owned();
+//!             println!("{name}: {} lines", input.reader.lines().count());
+//!         }
+//!         Err(error) => eprintln!("{}: {}", error.display_name(), error.error),
+//!     }
+//! }
+//! ```
+
+use std::borrow::Cow;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+/// The buffer size used for reading files.
+pub const BUFFER_SIZE: usize = 64 * 1024;
+
+/// An opened input.
+pub struct Input<R> {
+    /// The operand, or `None` if standard input is read because no operands were given.
+    pub operand: Option<OsString>,
+    /// The reader of the input.
+    pub reader: R,
+}
+
+impl<R> Input<R> {
+    /// Check whether the input is standard input, either explicitly (`-`) or implicitly.
+    pub fn is_stdin(&self) -> bool {
+        is_stdin(&self.operand)
+    }
+
+    /// Get the name to show in messages, which is `-` for standard input.
+    pub fn display_name(&self) -> Cow<'_, str> {
+        display_name(&self.operand)
+    }
+}
+
+/// An input that could not be opened.
+#[derive(Debug)]
+pub struct InputError {
+    /// The operand that could not be opened.
+    pub operand: OsString,
+    /// The reason why it could not be opened.
+    pub error: io::Error,
+}
+
+impl InputError {
+    /// Get the name to show in messages.
+    pub fn display_name(&self) -> Cow<'_, str> {
+        self.operand.to_string_lossy()
+    }
+}
+
+/// An opened input, for utilities which handle files and standard input differently, for example to seek in
+/// files.
+pub enum Source<'a> {
+    /// A file named by an operand, which is not buffered.
+    File(File),
+    /// Standard input.
+    Stdin(&'a mut dyn BufRead),
+}
+
+/// Iterates over the inputs named by the operands, opening each of them only when it is reached.
+///
+/// This is not an [`Iterator`], because the inputs borrow from the iterator: standard input can be named
+/// more than once, so each input reading it reborrows the same reader.
+pub struct InputIter<'a> {
+    operands: std::vec::IntoIter<Option<OsString>>,
+    stdin: Box<dyn BufRead + 'a>,
+}
+
+impl InputIter<'static> {
+    /// Iterate over the operands, reading standard input of the process for `-` or when there are none.
+    pub fn new(operands: Vec<OsString>) -> Self {
+        Self::with_stdin(operands, Box::new(io::stdin().lock()))
+    }
+}
+
+impl<'a> InputIter<'a> {
+    /// Iterate over the operands, reading `stdin` for `-` or when there are none.
+    pub fn with_stdin(operands: Vec<OsString>, stdin: Box<dyn BufRead + 'a>) -> Self {
+        let operands = if operands.is_empty() {
+            vec![None]
+        } else {
+            operands.into_iter().map(Some).collect()
+        };
+
+        InputIter {
+            operands: operands.into_iter(),
+            stdin,
+        }
+    }
+
+    /// Open the next input for reading lines or other buffered access.
+    ///
+    /// When an input cannot be opened, an error is returned for it and the iteration can continue with the
+    /// remaining ones.
+    pub fn next_buffered(&mut self) -> Option<Result<Input<Box<dyn BufRead + '_>>, InputError>> {
+        let operand = self.operands.next()?;
+        if is_stdin(&operand) {
+            return Some(Ok(Input {
+                operand,
+                reader: Box::new(&mut self.stdin),
+            }));
+        }
+
+        Some(open(operand).map(|(operand, file)| Input {
+            operand,
+            reader: Box::new(BufReader::with_capacity(BUFFER_SIZE, file)) as Box<dyn BufRead>,
+        }))
+    }
+
+    /// Open the next input for raw reads, for byte-oriented utilities doing their own buffering.
+    ///
+    /// When an input cannot be opened, an error is returned for it and the iteration can continue with the
+    /// remaining ones.
+    pub fn next_raw(&mut self) -> Option<Result<Input<Box<dyn Read + '_>>, InputError>> {
+        let operand = self.operands.next()?;
+        if is_stdin(&operand) {
+            return Some(Ok(Input {
+                operand,
+                reader: Box::new(&mut self.stdin),
+            }));
+        }
+
+        Some(open(operand).map(|(operand, file)| Input {
+            operand,
+            reader: Box::new(file) as Box<dyn Read>,
+        }))
+    }
+
+    /// Open the next input as a file, or standard input.
+    ///
+    /// When an input cannot be opened, an error is returned for it and the iteration can continue with the
+    /// remaining ones.
+    pub fn next_source(&mut self) -> Option<Result<Input<Source<'_>>, InputError>> {
+        let operand = self.operands.next()?;
+        if is_stdin(&operand) {
+            return Some(Ok(Input {
+                operand,
+                reader: Source::Stdin(&mut *self.stdin),
+            }));
+        }
+
+        Some(open(operand).map(|(operand, file)| Input {
+            operand,
+            reader: Source::File(file),
+        }))
+    }
+}
+
+fn is_stdin(operand: &Option<OsString>) -> bool {
+    operand.as_ref().is_none_or(|operand| operand == "-")
+}
+
+fn display_name(operand: &Option<OsString>) -> Cow<'_, str> {
+    match operand {
+        Some(operand) if operand != "-" => operand.to_string_lossy(),
+        _ => Cow::Borrowed("-"),
+    }
+}
+
+fn open(operand: Option<OsString>) -> Result<(Option<OsString>, File), InputError> {
+    let path = operand.expect("standard input is handled by the caller");
+    match File::open(Path::new(&path)) {
+        Ok(file) => Ok((Some(path), file)),
+        Err(error) => Err(InputError {
+            operand: path,
+            error,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::ErrorKind;
+
+    /// Read all inputs, returning their names and contents, or the names of the operands that failed.
+    fn read_all(inputs: &mut InputIter, raw: bool) -> Vec<Result<(String, String), String>> {
+        let mut result = Vec::new();
+        loop {
+            let mut content = String::new();
+            let input = if raw {
+                match inputs.next_raw() {
+                    None => break,
+                    Some(input) => input.map(|mut input| {
+                        input.reader.read_to_string(&mut content).unwrap();
+                        input.display_name().to_string()
+                    }),
+                }
+            } else {
+                match inputs.next_buffered() {
+                    None => break,
+                    Some(input) => input.map(|mut input| {
+                        input.reader.read_to_string(&mut content).unwrap();
+                        input.display_name().to_string()
+                    }),
+                }
+            };
+            result.push(
+                input
+                    .map(|name| (name, content))
+                    .map_err(|error| error.display_name().to_string()),
+            );
+        }
+        result
+    }
+
+    #[test]
+    fn test_no_operands() {
+        let mut inputs = InputIter::with_stdin(vec![], Box::new(&b"from stdin"[..]));
+        let input = inputs.next_buffered().unwrap().unwrap();
+        assert!(input.is_stdin());
+        assert_eq!(input.operand, None);
+        assert_eq!(input.display_name(), "-");
+        drop(input);
+        assert!(inputs.next_buffered().is_none());
+    }
+
+    #[test]
+    fn test_order_and_errors() {
+        let directory = tempfile::tempdir().unwrap();
+        let a = directory.path().join("a");
+        let b = directory.path().join("b");
+        let missing = directory.path().join("missing");
+        std::fs::write(&a, "first\n").unwrap();
+        std::fs::write(&b, "second\n").unwrap();
+
+        for raw in [false, true] {
+            let operands = vec![
+                a.clone().into(),
+                "-".into(),
+                missing.clone().into(),
+                b.clone().into(),
+                "-".into(),
+            ];
+            let mut inputs = InputIter::with_stdin(operands, Box::new(&b"stdin\n"[..]));
+            assert_eq!(
+                read_all(&mut inputs, raw),
+                [
+                    Ok((a.display().to_string(), "first\n".to_string())),
+                    Ok(("-".to_string(), "stdin\n".to_string())),
+                    Err(missing.display().to_string()),
+                    Ok((b.display().to_string(), "second\n".to_string())),
+                    // Standard input is already exhausted.
+                    Ok(("-".to_string(), String::new())),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_error_kind() {
+        let directory = tempfile::tempdir().unwrap();
+        let missing = directory.path().join("missing");
+
+        let mut inputs = InputIter::with_stdin(vec![missing.clone().into()], Box::new(io::empty()));
+        let Some(Err(error)) = inputs.next_buffered() else {
+            panic!("opening a missing file should fail");
+        };
+        assert_eq!(error.operand, missing);
+        assert_eq!(error.error.kind(), ErrorKind::NotFound);
+        assert!(inputs.next_buffered().is_none());
+    }
+
+    #[test]
+    fn test_stdin_is_shared() {
+        let mut inputs =
+            InputIter::with_stdin(vec!["-".into(), "-".into()], Box::new(&b"one\ntwo\n"[..]));
+
+        let mut line = String::new();
+        let mut input = inputs.next_buffered().unwrap().unwrap();
+        input.reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "one\n");
+        drop(input);
+
+        // The second input continues where the first one stopped.
+        line.clear();
+        let mut input = inputs.next_buffered().unwrap().unwrap();
+        input.reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "two\n");
+    }
+
+    #[test]
+    fn test_source() {
+        let directory = tempfile::tempdir().unwrap();
+        let a = directory.path().join("a");
+        std::fs::write(&a, "file\n").unwrap();
+
+        let operands = vec![
+            a.into(),
+            "-".into(),
+            directory.path().join("missing").into(),
+        ];
+        let mut inputs = InputIter::with_stdin(operands, Box::new(&b"stdin\n"[..]));
+        let mut content = String::new();
+        let Some(Ok(Input {
+            reader: Source::File(mut file),
+            ..
+        })) = inputs.next_source()
+        else {
+            panic!("a file operand should be opened as a file");
+        };
+        file.read_to_string(&mut content).unwrap();
+        let Some(Ok(Input {
+            reader: Source::Stdin(stdin),
+            ..
+        })) = inputs.next_source()
+        else {
+            panic!("- should be standard input");
+        };
+        stdin.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "file\nstdin\n");
+        assert!(matches!(inputs.next_source(), Some(Err(_))));
+        assert!(inputs.next_source().is_none());
+    }
+}
@@ -0,0 +1,109 @@
+//! Man pages for the utilities, generated from their command-line definitions.
+
+use crate::common::error::{Reporter, UError};
+use crate::common::utility::Utility;
+use clap::Parser;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+/// Write a man page for every utility into a directory.
+#[derive(Parser, Debug)]
+#[command(name = "manpages", about, long_about = None)]
+struct Args {
+    /// The directory to write the pages into, which is created if it does not exist.
+    directory: PathBuf,
+}
+
+/// Render the man page of a utility as roff.
+pub fn write_man_page(utility: &Utility, output: &mut dyn Write) -> io::Result<()> {
+    let command = (utility.command)();
+    clap_mangen::Man::new(command).render(output)
+}
+
+/// Run the manpages applet with the given command line, including the program name, and return the exit
+/// code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    let args = match Args::try_parse_from(args) {
+        Ok(args) => args,
+        Err(error) => {
+            let _ = error.print();
+            return error.exit_code();
+        }
+    };
+
+    let reporter = Reporter::new("manpages", io::stderr());
+    let result = write_man_pages(&args.directory);
+    reporter.finish(result)
+}
+
+/// Write `<utility>.1` for every utility into `directory`.
+fn write_man_pages(directory: &PathBuf) -> Result<(), UError> {
+    std::fs::create_dir_all(directory).map_err(|error| UError::io(directory.display(), &error))?;
+
+    for utility in crate::UTILITIES {
+        let path = directory.join(format!("{}.1", utility.name));
+        let mut output = File::create(&path)
+            .map(BufWriter::new)
+            .map_err(|error| UError::io(path.display(), &error))?;
+        write_man_page(utility, &mut output)
+            .and_then(|()| output.flush())
+            .map_err(|error| UError::io(path.display(), &error))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_cmd::prelude::*;
+    use std::process::Command;
+
+    fn render(name: &str) -> String {
+        let utility = Utility::find(name.as_ref()).unwrap();
+        let mut output = Vec::new();
+        write_man_page(utility, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_echo_page() {
+        let page = render("echo");
+        assert!(page.starts_with(".ie \\n(.g .ds Aq \\(aq"));
+        assert!(page.contains("\n.TH echo 1 "));
+        assert!(page.contains(r"\\0NNN byte with octal value NNN (0 to 3 digits)"));
+        assert!(page.contains(r"\-\-no\-newline"));
+    }
+
+    #[test]
+    fn test_binary_writes_every_page() {
+        let directory = tempfile::tempdir().unwrap();
+        let output = directory.path().join("man");
+
+        let mut cmd = Command::cargo_bin("coreutils").unwrap();
+        cmd.arg("manpages").arg(&output).assert().success();
+
+        for utility in crate::UTILITIES {
+            let page = std::fs::read_to_string(output.join(format!("{}.1", utility.name))).unwrap();
+            assert!(page.contains(&format!(".TH {} 1 ", utility.name)));
+        }
+    }
+
+    #[test]
+    fn test_binary_unwritable_directory() {
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("file");
+        std::fs::write(&file, "").unwrap();
+
+        let mut cmd = Command::cargo_bin("coreutils").unwrap();
+        cmd.arg("manpages")
+            .arg(file.join("man"))
+            .assert()
+            .code(1)
+            .stderr(predicates::str::starts_with("manpages: "));
+    }
+}
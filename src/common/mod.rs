@@ -0,0 +1,35 @@
+//! Building blocks shared by the utilities.
+
+pub mod backup;
+pub mod canonicalize;
+#[cfg(any(feature = "feat_chgrp", feature = "feat_chown"))]
+pub mod chown_core;
+pub mod colors;
+pub mod compare;
+#[cfg(feature = "completions")]
+pub mod completions;
+#[cfg(feature = "datetime")]
+pub mod datetime;
+pub mod encoding;
+pub mod error;
+pub mod escapes;
+pub mod format;
+pub mod glob;
+pub mod human;
+pub mod input;
+#[cfg(feature = "manpages")]
+pub mod manpages;
+pub mod mode;
+pub mod mounts;
+pub mod os;
+pub mod paths;
+pub mod pipes;
+pub mod prompt;
+pub mod records;
+pub mod size;
+#[cfg(test)]
+pub mod testing;
+#[cfg(unix)]
+pub mod users;
+pub mod utility;
+pub mod walk;
@@ -0,0 +1,606 @@
+//! File modes, as shown by `ls -l` and stat, and as changed by chmod, `mkdir -m`, and `install -m`.
+//!
+//! A mode is either octal, like `755`, or a comma-separated list of symbolic clauses, like `u+rwx,g-s,o=rX`.
+//! Each clause names who it applies to (`u`, `g`, `o`, or `a`), and then one or more operations (`+`, `-`,
+//! or `=`) with the permissions to add, remove, or set: `r`, `w`, `x`, `X` (execute if it is a directory or
+//! anyone can already execute it), `s` (setuid and setgid), and `t` (sticky), or the permissions that `u`,
+//! `g`, or `o` already have:
+//!
+//! ```
+//! use rust_coreutils::common::mode::{format_mode, parse_mode};
+//!
+//! let mode = parse_mode("u+x,g=u,o-r", 0o644, false, 0o022).unwrap();
+//! assert_eq!(mode, 0o770);
+//! assert_eq!(format_mode(0o100000 | mode), "-rwxrwx---");
+//! assert_eq!(parse_mode("a+z", 0o644, false, 0).unwrap_err().to_string(), "'a+z'");
+//! ```
+
+use std::fmt;
+#[cfg(unix)]
+use std::fs;
+use std::fs::Metadata;
+
+const S_IFMT: u32 = 0o170000;
+const S_IFSOCK: u32 = 0o140000;
+const S_IFLNK: u32 = 0o120000;
+const S_IFREG: u32 = 0o100000;
+const S_IFBLK: u32 = 0o060000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFCHR: u32 = 0o020000;
+const S_IFIFO: u32 = 0o010000;
+
+const S_ISUID: u32 = 0o4000;
+const S_ISGID: u32 = 0o2000;
+const S_ISVTX: u32 = 0o1000;
+const S_IRWXU: u32 = 0o700;
+const S_IRWXG: u32 = 0o070;
+const S_IRWXO: u32 = 0o007;
+const READ: u32 = 0o444;
+const WRITE: u32 = 0o222;
+const EXECUTE: u32 = 0o111;
+
+/// All the bits that a mode can change.
+const MODE_BITS: u32 = 0o7777;
+
+/// Get the character for the type of a file in `ls -l`, like `d` for a directory, or `?` if it is unknown.
+pub fn format_type_char(st_mode: u32) -> char {
+    match st_mode & S_IFMT {
+        S_IFREG => '-',
+        S_IFDIR => 'd',
+        S_IFLNK => 'l',
+        S_IFBLK => 'b',
+        S_IFCHR => 'c',
+        S_IFIFO => 'p',
+        S_IFSOCK => 's',
+        _ => '?',
+    }
+}
+
+/// Describe the type of a file like rm's questions and stat, like `regular empty file`.
+pub fn file_kind(metadata: &Metadata) -> &'static str {
+    let file_type = metadata.file_type();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        if file_type.is_fifo() {
+            return "fifo";
+        } else if file_type.is_socket() {
+            return "socket";
+        } else if file_type.is_block_device() {
+            return "block special file";
+        } else if file_type.is_char_device() {
+            return "character special file";
+        }
+    }
+    if file_type.is_dir() {
+        "directory"
+    } else if file_type.is_symlink() {
+        "symbolic link"
+    } else if metadata.len() == 0 {
+        "regular empty file"
+    } else {
+        "regular file"
+    }
+}
+
+/// Get the type and permissions of a file, as in `st_mode`.
+#[cfg(unix)]
+pub fn st_mode(metadata: &Metadata) -> u32 {
+    std::os::unix::fs::MetadataExt::mode(metadata)
+}
+
+/// Make up the mode of a file from what Windows tells: everyone can read and execute everything, and write
+/// what is not read-only.
+#[cfg(not(unix))]
+pub fn st_mode(metadata: &Metadata) -> u32 {
+    let file_type = if metadata.is_symlink() {
+        S_IFLNK
+    } else if metadata.is_dir() {
+        S_IFDIR
+    } else {
+        S_IFREG
+    };
+    let write = if metadata.permissions().readonly() {
+        0
+    } else {
+        WRITE
+    };
+    file_type | READ | EXECUTE | write
+}
+
+/// Format a mode like `ls -l`, like `drwxr-sr-t`: the type, then the permissions of the user, group, and
+/// others.
+///
+/// The setuid and setgid bits are shown as `s` in place of the execute permission of the user and the group,
+/// or as `S` if they cannot execute the file, and the sticky bit as `t` or `T` in place of that of others.
+pub fn format_mode(st_mode: u32) -> String {
+    let mut result = String::with_capacity(10);
+    result.push(format_type_char(st_mode));
+    for (shift, special, set, unset) in [
+        (6, S_ISUID, 's', 'S'),
+        (3, S_ISGID, 's', 'S'),
+        (0, S_ISVTX, 't', 'T'),
+    ] {
+        let bits = st_mode >> shift;
+        result.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        result.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        result.push(match (bits & 0o1 != 0, st_mode & special != 0) {
+            (true, true) => set,
+            (false, true) => unset,
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    result
+}
+
+/// A mode that could not be parsed.
+///
+/// It is displayed as the quoted mode, so that utilities can prefix it with their own message, like GNU's
+/// `invalid mode: 'a+z'`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModeError {
+    /// The mode that could not be parsed.
+    pub input: String,
+}
+
+impl fmt::Display for ModeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}'", self.input)
+    }
+}
+
+impl std::error::Error for ModeError {}
+
+/// What the permissions of an operation are.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Source {
+    /// The listed permissions.
+    Listed,
+    /// The listed permissions, and the execute permission if `X` applies.
+    ExecuteIfAny,
+    /// The permissions of the class in the value, copied to the others.
+    Copy,
+}
+
+/// A single `+`, `-`, or `=` operation of a clause.
+#[derive(Clone, Copy, Debug)]
+struct Change {
+    op: u8,
+    source: Source,
+    /// The bits of the classes the clause applies to, or 0 if none were named.
+    affected: u32,
+    value: u32,
+    /// The setuid and setgid bits which the operation mentions explicitly, which are otherwise left alone on
+    /// directories.
+    mentioned: u32,
+}
+
+/// Parse a symbolic mode, like `u+rwx,g-s,o=rX,a+X` or `g=u`, and apply it to the permissions `current`.
+/// Only the permission bits of the result are set.
+///
+/// Clauses which do not name who they apply to apply to everyone, except for the bits set in `umask`. On
+/// directories, the setuid and setgid bits are only changed if they are mentioned, so that `=rwx` keeps
+/// them but `=rwxs` or `u-s` does not.
+pub fn parse_symbolic(
+    spec: &str,
+    current: u32,
+    is_dir: bool,
+    umask: u32,
+) -> Result<u32, ModeError> {
+    Ok(apply(&compile_symbolic(spec)?, current, is_dir, umask))
+}
+
+/// Parse an octal or symbolic mode and apply it to the permissions `current`, like chmod. Only the
+/// permission bits of the result are set.
+///
+/// An octal mode sets all the permissions, but with fewer than five digits, it keeps the setuid and setgid
+/// bits of directories unless it sets them, so that only something like `00755` clears them.
+pub fn parse_mode(spec: &str, current: u32, is_dir: bool, umask: u32) -> Result<u32, ModeError> {
+    let changes = match spec.as_bytes().first() {
+        Some(b'0'..=b'7') => vec![compile_octal(spec)?],
+        _ => compile_symbolic(spec)?,
+    };
+    Ok(apply(&changes, current, is_dir, umask))
+}
+
+/// Get the file mode creation mask of the process, whose bits the kernel clears from the modes of the new
+/// files.
+///
+/// On Linux, it is read from `/proc/self/status`, because reading it otherwise means setting it, which
+/// briefly changes it for the other threads too.
+#[cfg(unix)]
+pub fn umask() -> u32 {
+    if let Some(mask) = fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| umask_from_status(&status))
+    {
+        return mask;
+    }
+    // SAFETY: umask always succeeds, and the second call restores the mask.
+    let mask = unsafe {
+        let mask = libc::umask(0);
+        libc::umask(mask);
+        mask
+    };
+    mask as u32 & 0o777
+}
+
+/// Get the file mode creation mask of the process, which does not exist on this system.
+#[cfg(not(unix))]
+pub fn umask() -> u32 {
+    0
+}
+
+/// Find the mask in the `Umask:` line of `/proc/self/status`.
+#[cfg(unix)]
+fn umask_from_status(status: &str) -> Option<u32> {
+    let line = status
+        .lines()
+        .find_map(|line| line.strip_prefix("Umask:"))?;
+    u32::from_str_radix(line.trim(), 8).ok()
+}
+
+fn invalid(spec: &str) -> ModeError {
+    ModeError {
+        input: spec.to_string(),
+    }
+}
+
+fn compile_octal(spec: &str) -> Result<Change, ModeError> {
+    if !spec.bytes().all(|byte| matches!(byte, b'0'..=b'7')) {
+        return Err(invalid(spec));
+    }
+    let value = u32::from_str_radix(spec, 8)
+        .ok()
+        .filter(|&value| value <= MODE_BITS)
+        .ok_or_else(|| invalid(spec))?;
+    let mentioned = if spec.len() < 5 {
+        value & (S_ISUID | S_ISGID)
+    } else {
+        S_ISUID | S_ISGID
+    };
+    Ok(Change {
+        op: b'=',
+        source: Source::Listed,
+        affected: MODE_BITS,
+        value,
+        mentioned,
+    })
+}
+
+fn compile_symbolic(spec: &str) -> Result<Vec<Change>, ModeError> {
+    let bytes = spec.as_bytes();
+    let mut changes = Vec::new();
+    let mut index = 0;
+    loop {
+        let mut affected = 0;
+        loop {
+            match bytes.get(index) {
+                Some(b'u') => affected |= S_ISUID | S_IRWXU,
+                Some(b'g') => affected |= S_ISGID | S_IRWXG,
+                Some(b'o') => affected |= S_ISVTX | S_IRWXO,
+                Some(b'a') => affected |= MODE_BITS,
+                Some(b'+' | b'-' | b'=') => break,
+                _ => return Err(invalid(spec)),
+            }
+            index += 1;
+        }
+
+        while let Some(&op @ (b'+' | b'-' | b'=')) = bytes.get(index) {
+            index += 1;
+            let (source, value) = match bytes.get(index) {
+                Some(b'u') => (Source::Copy, S_IRWXU),
+                Some(b'g') => (Source::Copy, S_IRWXG),
+                Some(b'o') => (Source::Copy, S_IRWXO),
+                _ => {
+                    let mut source = Source::Listed;
+                    let mut value = 0;
+                    while let Some(&permission) = bytes.get(index) {
+                        match permission {
+                            b'r' => value |= READ,
+                            b'w' => value |= WRITE,
+                            b'x' => value |= EXECUTE,
+                            b'X' => source = Source::ExecuteIfAny,
+                            b's' => value |= S_ISUID | S_ISGID,
+                            b't' => value |= S_ISVTX,
+                            _ => break,
+                        }
+                        index += 1;
+                    }
+                    (source, value)
+                }
+            };
+            if source == Source::Copy {
+                index += 1;
+            }
+            changes.push(Change {
+                op,
+                source,
+                affected,
+                value,
+                mentioned: if affected != 0 {
+                    affected & value
+                } else {
+                    value
+                },
+            });
+        }
+
+        match bytes.get(index) {
+            None => return Ok(changes),
+            Some(b',') => index += 1,
+            Some(_) => return Err(invalid(spec)),
+        }
+    }
+}
+
+/// Apply the operations in order, like GNU's `mode_adjust`.
+fn apply(changes: &[Change], current: u32, is_dir: bool, umask: u32) -> u32 {
+    let mut mode = current & MODE_BITS;
+    for change in changes {
+        let omitted = if is_dir { S_ISUID | S_ISGID } else { 0 } & !change.mentioned;
+        let mut value = change.value;
+        match change.source {
+            Source::Listed => {}
+            Source::ExecuteIfAny => {
+                if mode & EXECUTE != 0 || is_dir {
+                    value |= EXECUTE;
+                }
+            }
+            Source::Copy => {
+                value &= mode;
+                for class in [READ, WRITE, EXECUTE] {
+                    if value & class != 0 {
+                        value |= class;
+                    }
+                }
+            }
+        }
+
+        let affected = if change.affected != 0 {
+            change.affected
+        } else {
+            !umask
+        };
+        value &= affected & !omitted;
+
+        match change.op {
+            b'+' => mode |= value,
+            b'-' => mode &= !value,
+            _ => {
+                // Without a class, `=` also clears the bits that the umask protects from being set.
+                let preserved = if change.affected != 0 {
+                    !change.affected
+                } else {
+                    0
+                } | omitted;
+                mode = (mode & preserved) | value;
+            }
+        }
+    }
+    mode & MODE_BITS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbolic(spec: &str, current: u32) -> u32 {
+        parse_symbolic(spec, current, false, 0o022).unwrap()
+    }
+
+    fn directory(spec: &str, current: u32) -> u32 {
+        parse_mode(spec, current, true, 0o022).unwrap()
+    }
+
+    #[test]
+    fn test_format_mode() {
+        for (mode, expected) in [
+            (0o100644, "-rw-r--r--"),
+            (0o100755, "-rwxr-xr-x"),
+            (0o100000, "----------"),
+            (0o107777, "-rwsrwsrwt"),
+            (0o107000, "---S--S--T"),
+            (0o104755, "-rwsr-xr-x"),
+            (0o102745, "-rwxr-Sr-x"),
+            (0o041777, "drwxrwxrwt"),
+            (0o040754, "drwxr-xr--"),
+            (0o120777, "lrwxrwxrwx"),
+            (0o060660, "brw-rw----"),
+            (0o020620, "crw--w----"),
+            (0o010644, "prw-r--r--"),
+            (0o140755, "srwxr-xr-x"),
+            (0o000644, "?rw-r--r--"),
+        ] {
+            assert_eq!(format_mode(mode), expected, "{mode:o}");
+        }
+        assert_eq!(format_type_char(0o040755), 'd');
+        assert_eq!(format_type_char(0o170000), '?');
+    }
+
+    #[test]
+    fn test_add_remove() {
+        assert_eq!(symbolic("u+x", 0o644), 0o744);
+        assert_eq!(symbolic("u+rwx", 0o000), 0o700);
+        assert_eq!(symbolic("g+w", 0o644), 0o664);
+        assert_eq!(symbolic("o-r", 0o644), 0o640);
+        assert_eq!(symbolic("go-rwx", 0o777), 0o700);
+        assert_eq!(symbolic("a+x", 0o644), 0o755);
+        assert_eq!(symbolic("a-w", 0o666), 0o444);
+        assert_eq!(symbolic("ug+x", 0o644), 0o754);
+        assert_eq!(symbolic("uo-w", 0o666), 0o464);
+        assert_eq!(symbolic("u-x", 0o644), 0o644);
+        assert_eq!(symbolic("u+", 0o644), 0o644);
+    }
+
+    #[test]
+    fn test_set() {
+        assert_eq!(symbolic("u=rx", 0o644), 0o544);
+        assert_eq!(symbolic("g=", 0o674), 0o604);
+        assert_eq!(symbolic("o=rwx", 0o640), 0o647);
+        assert_eq!(symbolic("a=r", 0o777), 0o444);
+        assert_eq!(symbolic("ug=rw", 0o000), 0o660);
+        // `=` only clears the bits of the named classes.
+        assert_eq!(symbolic("u=", 0o4755), 0o055);
+        assert_eq!(symbolic("g=", 0o2775), 0o705);
+        assert_eq!(symbolic("o=", 0o1777), 0o770);
+    }
+
+    #[test]
+    fn test_without_who() {
+        // The umask protects bits from being set or cleared, except by `=`.
+        assert_eq!(symbolic("+w", 0o444), 0o644);
+        assert_eq!(symbolic("+x", 0o644), 0o755);
+        assert_eq!(symbolic("-w", 0o666), 0o466);
+        assert_eq!(symbolic("=rw", 0o777), 0o644);
+        assert_eq!(symbolic("=", 0o777), 0o000);
+        assert_eq!(parse_symbolic("+w", 0o444, false, 0).unwrap(), 0o666);
+        assert_eq!(parse_symbolic("-w", 0o666, false, 0o077).unwrap(), 0o466);
+        assert_eq!(parse_symbolic("=rwx", 0o000, false, 0o027).unwrap(), 0o750);
+    }
+
+    #[test]
+    fn test_multiple_clauses() {
+        assert_eq!(symbolic("u+rwx,g-s,o=rX", 0o2640), 0o745);
+        assert_eq!(symbolic("u=rwx,g=rx,o=", 0o000), 0o750);
+        assert_eq!(symbolic("a-rwx,u+r", 0o777), 0o400);
+        assert_eq!(symbolic("u+x,u-x", 0o644), 0o644);
+        assert_eq!(symbolic("o=,o+w", 0o777), 0o772);
+        // Operations follow each other without repeating the classes.
+        assert_eq!(symbolic("u=rw+x", 0o000), 0o700);
+        assert_eq!(symbolic("go=r-r+w", 0o777), 0o722);
+        assert_eq!(symbolic("a+r,+w,o-w", 0o000), 0o644);
+    }
+
+    #[test]
+    fn test_conditional_execute() {
+        assert_eq!(symbolic("a+X", 0o644), 0o644);
+        assert_eq!(symbolic("a+X", 0o744), 0o755);
+        assert_eq!(symbolic("a+X", 0o654), 0o755);
+        assert_eq!(symbolic("o=rX", 0o640), 0o644);
+        assert_eq!(symbolic("o=rX", 0o740), 0o745);
+        assert_eq!(parse_symbolic("a+X", 0o644, true, 0).unwrap(), 0o755);
+        assert_eq!(parse_symbolic("go=X", 0o700, false, 0).unwrap(), 0o711);
+        // `X` looks at the mode as changed by the previous operations.
+        assert_eq!(symbolic("u+x,a+X", 0o644), 0o755);
+        assert_eq!(symbolic("a-x,a+X", 0o755), 0o644);
+        assert_eq!(symbolic("a-X", 0o755), 0o644);
+    }
+
+    #[test]
+    fn test_copy() {
+        assert_eq!(symbolic("g=u", 0o740), 0o770);
+        assert_eq!(symbolic("o=g", 0o751), 0o755);
+        assert_eq!(symbolic("go=u", 0o700), 0o777);
+        assert_eq!(symbolic("u=o", 0o705), 0o505);
+        assert_eq!(symbolic("o+u", 0o641), 0o647);
+        assert_eq!(symbolic("g-o", 0o775), 0o725);
+        // Copying ignores the special bits.
+        assert_eq!(symbolic("g=u", 0o4700), 0o4770);
+        assert_eq!(symbolic("o=u", 0o4700), 0o4707);
+        // Without classes, the copy applies to everyone except the umask.
+        assert_eq!(symbolic("=u", 0o700), 0o755);
+        assert_eq!(symbolic("u=g,g=u", 0o750), 0o550);
+        assert_eq!(symbolic("g=u,u-x", 0o700), 0o670);
+    }
+
+    #[test]
+    fn test_special_bits() {
+        assert_eq!(symbolic("u+s", 0o755), 0o4755);
+        assert_eq!(symbolic("g+s", 0o755), 0o2755);
+        assert_eq!(symbolic("ug+s", 0o755), 0o6755);
+        assert_eq!(symbolic("o+s", 0o755), 0o755);
+        assert_eq!(symbolic("+s", 0o755), 0o6755);
+        assert_eq!(symbolic("a+s", 0o755), 0o6755);
+        assert_eq!(symbolic("+t", 0o755), 0o1755);
+        assert_eq!(symbolic("o+t", 0o755), 0o1755);
+        assert_eq!(symbolic("u+t", 0o755), 0o755);
+        assert_eq!(symbolic("a-st", 0o7777), 0o0777);
+        assert_eq!(symbolic("u=rwxs", 0o000), 0o4700);
+        assert_eq!(symbolic("a=", 0o7777), 0o000);
+    }
+
+    #[test]
+    fn test_directory_special_bits() {
+        // On directories, `=` keeps the setuid and setgid bits unless it mentions them.
+        assert_eq!(directory("u=rwx", 0o4755), 0o4755);
+        assert_eq!(directory("g=rx", 0o2775), 0o2755);
+        assert_eq!(directory("a=rx", 0o6777), 0o6555);
+        assert_eq!(directory("=rwx", 0o6777), 0o6755);
+        assert_eq!(directory("u=rwxs", 0o2700), 0o6700);
+        assert_eq!(directory("g=rxs", 0o4700), 0o6750);
+        assert_eq!(directory("a=", 0o6777), 0o6000);
+        assert_eq!(directory("a=s", 0o6777), 0o6000);
+        // Mentioning setuid only for the group does not make it mentioned for the user.
+        assert_eq!(directory("g=s", 0o6777), 0o6707);
+        assert_eq!(directory("u-s", 0o6755), 0o2755);
+        assert_eq!(directory("g-s", 0o6755), 0o4755);
+        // The sticky bit is not special.
+        assert_eq!(directory("o=rx", 0o1777), 0o0775);
+        assert_eq!(parse_mode("u=rwx", 0o4755, false, 0).unwrap(), 0o755);
+    }
+
+    #[test]
+    fn test_octal() {
+        assert_eq!(parse_mode("755", 0o644, false, 0o077).unwrap(), 0o755);
+        assert_eq!(parse_mode("0", 0o777, false, 0).unwrap(), 0);
+        assert_eq!(parse_mode("4755", 0o644, false, 0).unwrap(), 0o4755);
+        assert_eq!(parse_mode("7777", 0, false, 0).unwrap(), 0o7777);
+        assert_eq!(parse_mode("755", 0o6644, false, 0).unwrap(), 0o755);
+        // Fewer than five digits keep the setuid and setgid bits of directories, but not the sticky bit.
+        assert_eq!(directory("755", 0o6644), 0o6755);
+        assert_eq!(directory("0755", 0o7644), 0o6755);
+        assert_eq!(directory("2755", 0o4644), 0o6755);
+        assert_eq!(directory("00755", 0o6644), 0o0755);
+        assert_eq!(directory("02755", 0o6644), 0o2755);
+        // Symbolic modes are parsed too.
+        assert_eq!(parse_mode("u+x", 0o644, false, 0).unwrap(), 0o744);
+    }
+
+    #[test]
+    fn test_file_type_ignored() {
+        assert_eq!(symbolic("u+x", 0o100644), 0o744);
+        assert_eq!(parse_mode("a+X", 0o040600, true, 0).unwrap(), 0o711);
+    }
+
+    #[test]
+    fn test_invalid() {
+        for spec in [
+            "", ",", "u", "ug", "x", "rwx", "u+z", "u+rwz", "u+x,", ",u+x", "u+x,,g+x", "u+ux",
+            "g=uw", "u+x g+x", "U+x", "a+R", "u!x", "8", "789", "0x755", "07778", "17777", "u=+,",
+            " u+x",
+        ] {
+            assert_eq!(
+                parse_mode(spec, 0o644, false, 0),
+                Err(ModeError {
+                    input: spec.to_string()
+                }),
+                "{spec:?}"
+            );
+        }
+        assert!(parse_symbolic("755", 0o644, false, 0).is_err());
+        assert_eq!(
+            parse_mode("u+z", 0, false, 0).unwrap_err().to_string(),
+            "'u+z'"
+        );
+    }
+
+    #[test]
+    fn test_empty_operations() {
+        assert_eq!(parse_mode("+", 0o644, false, 0).unwrap(), 0o644);
+        assert_eq!(parse_mode("-", 0o644, false, 0).unwrap(), 0o644);
+        assert_eq!(parse_mode("+-", 0o644, false, 0).unwrap(), 0o644);
+        assert_eq!(parse_mode("u+-=", 0o644, false, 0).unwrap(), 0o044);
+        assert_eq!(parse_mode("a+,u-", 0o644, false, 0).unwrap(), 0o644);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_umask() {
+        let status = "Name:\tmkdir\nUmask:\t0027\nState:\tR (running)\n";
+        assert_eq!(umask_from_status(status), Some(0o027));
+        assert_eq!(umask_from_status("Name:\tmkdir\n"), None);
+        assert_eq!(umask() & !0o777, 0);
+    }
+}
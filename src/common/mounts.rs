@@ -0,0 +1,178 @@
+//! The table of the mounted file systems, for df.
+//!
+//! On Linux, it is read from `/proc/self/mountinfo`, whose lines can be parsed on their own:
+//!
+//! ```
+//! use rust_coreutils::common::mounts::parse_mountinfo;
+//! use std::path::Path;
+//!
+//! let mounts = parse_mountinfo(b"28 1 254:0 / / rw,relatime - ext4 /dev/vda rw\n");
+//! assert_eq!(mounts[0].source, "/dev/vda");
+//! assert_eq!(mounts[0].target, Path::new("/"));
+//! assert_eq!(mounts[0].fs_type, "ext4");
+//! ```
+
+use std::ffi::OsString;
+use std::io;
+use std::path::PathBuf;
+
+/// A mounted file system.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mount {
+    /// What is mounted, like `/dev/sda1` or `tmpfs`.
+    pub source: OsString,
+    /// The directory it is mounted on.
+    pub target: PathBuf,
+    /// The type of the file system, like `ext4`.
+    pub fs_type: String,
+}
+
+/// Get the mounted file systems, in the order they were mounted.
+#[cfg(target_os = "linux")]
+pub fn mounts() -> io::Result<Vec<Mount>> {
+    Ok(parse_mountinfo(&std::fs::read("/proc/self/mountinfo")?))
+}
+
+/// Get the mounted file systems, in the order they were mounted.
+#[cfg(any(
+    target_os = "macos",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "openbsd"
+))]
+pub fn mounts() -> io::Result<Vec<Mount>> {
+    use std::ffi::CStr;
+    use std::os::raw::c_char;
+
+    let name = |field: &[c_char]| {
+        // SAFETY: the names are null-terminated within their arrays.
+        let name = unsafe { CStr::from_ptr(field.as_ptr()) };
+        os_string(name.to_bytes().to_vec())
+    };
+    let mut buffer: *mut libc::statfs = std::ptr::null_mut();
+    // SAFETY: the buffer is allocated by getmntinfo, and stays valid until the next call.
+    let count = unsafe { libc::getmntinfo(&mut buffer, libc::MNT_NOWAIT) };
+    if count <= 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: getmntinfo returned `count` entries.
+    let entries = unsafe { std::slice::from_raw_parts(buffer, count as usize) };
+    Ok(entries
+        .iter()
+        .map(|entry| Mount {
+            source: name(&entry.f_mntfromname),
+            target: PathBuf::from(name(&entry.f_mntonname)),
+            fs_type: name(&entry.f_fstypename).to_string_lossy().into_owned(),
+        })
+        .collect())
+}
+
+/// Get the mounted file systems, which cannot be listed on this platform.
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "macos",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "openbsd"
+)))]
+pub fn mounts() -> io::Result<Vec<Mount>> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// Parse the contents of `/proc/self/mountinfo`, skipping the lines which are not valid.
+///
+/// Each line is made of the mount id, the id of its parent, the device number, the root of the mount in
+/// its file system, the mount point, its options, optional fields, a `-`, the type of the file system, its
+/// source, and its own options. The spaces, tabs, newlines, and backslashes in the paths are escaped in
+/// octal, like `\040`.
+pub fn parse_mountinfo(contents: &[u8]) -> Vec<Mount> {
+    contents
+        .split(|&byte| byte == b'\n')
+        .filter_map(|line| {
+            let fields: Vec<&[u8]> = line.split(|&byte| byte == b' ').collect();
+            let separator = fields.iter().skip(6).position(|&field| field == b"-")? + 6;
+            let fs_type = fields.get(separator + 1)?;
+            let source = fields.get(separator + 2)?;
+            Some(Mount {
+                source: os_string(unescape(source)),
+                target: PathBuf::from(os_string(unescape(fields[4]))),
+                fs_type: String::from_utf8_lossy(&unescape(fs_type)).into_owned(),
+            })
+        })
+        .collect()
+}
+
+/// Replace the octal escapes of a field of the mount table, like `\040`, with their bytes.
+fn unescape(field: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(field.len());
+    let mut i = 0;
+    while i < field.len() {
+        let digits = field.get(i + 1..i + 4).filter(|digits| {
+            digits.iter().all(|digit| (b'0'..=b'7').contains(digit)) && digits[0] <= b'3'
+        });
+        match (field[i], digits) {
+            (b'\\', Some(digits)) => {
+                bytes.push(
+                    digits
+                        .iter()
+                        .fold(0, |value, digit| value * 8 + (digit - b'0')),
+                );
+                i += 4;
+            }
+            (byte, _) => {
+                bytes.push(byte);
+                i += 1;
+            }
+        }
+    }
+    bytes
+}
+
+#[cfg(unix)]
+fn os_string(bytes: Vec<u8>) -> OsString {
+    std::os::unix::ffi::OsStringExt::from_vec(bytes)
+}
+
+#[cfg(not(unix))]
+fn os_string(bytes: Vec<u8>) -> OsString {
+    String::from_utf8_lossy(&bytes).into_owned().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mountinfo() {
+        let mounts = parse_mountinfo(
+            b"23 28 0:22 / /proc rw,relatime - proc proc rw\n\
+              28 1 254:0 / / rw,relatime shared:1 master:2 - ext4 /dev/vda rw,discard\n\
+              invalid line\n\
+              40 28 0:40 /sub /mnt/with\\040space\\134 rw - fuse.sshfs user@host:/a\\011b rw\n\
+              \n",
+        );
+        let mount = |source: &str, target: &str, fs_type: &str| Mount {
+            source: OsString::from(source),
+            target: PathBuf::from(target),
+            fs_type: fs_type.to_owned(),
+        };
+        assert_eq!(
+            mounts,
+            [
+                mount("proc", "/proc", "proc"),
+                mount("/dev/vda", "/", "ext4"),
+                mount("user@host:/a\tb", "/mnt/with space\\", "fuse.sshfs"),
+            ]
+        );
+        assert_eq!(unescape(b"a\\0b\\400\\101"), b"a\\0b\\400A");
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_mounts() {
+        let mounts = mounts().unwrap();
+        assert!(mounts
+            .iter()
+            .any(|mount| mount.target == std::path::Path::new("/")));
+    }
+}
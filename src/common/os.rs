@@ -0,0 +1,201 @@
+//! Portable access to the strings and streams of the operating system.
+//!
+//! On Unix, arguments are arbitrary bytes, and so is what the utilities print. On Windows, arguments are
+//! Unicode, and the console only accepts UTF-8, so writing other bytes to it fails: [`stdout`] and
+//! [`stderr`] replace them when writing to a console, and pass them through unchanged to files and pipes.
+//! Newlines are never translated to `\r\n`.
+//!
+//! ```
+//! use rust_coreutils::common::os::ConsoleWriter;
+//! use std::io::Write;
+//!
+//! let mut console = ConsoleWriter::new(Vec::new(), true);
+//! console.write_all(b"caf\xc3").unwrap();
+//! console.write_all(b"\xa9 \xff\n").unwrap();
+//! assert_eq!(console.into_inner(), "café \u{FFFD}\n".as_bytes());
+//! ```
+
+use std::borrow::Cow;
+use std::ffi::OsStr;
+use std::io::{self, IsTerminal, StderrLock, StdoutLock, Write};
+use std::mem;
+
+/// Get the bytes of an argument, or of a path. On Unix, they are the raw bytes, so that arguments which are
+/// not valid UTF-8 are printed unchanged. Elsewhere, arguments which are not valid Unicode are converted
+/// lossily.
+pub fn os_str_bytes(data: &OsStr) -> Cow<'_, [u8]> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Cow::Borrowed(data.as_bytes())
+    }
+    #[cfg(not(unix))]
+    match data.to_string_lossy() {
+        Cow::Borrowed(data) => Cow::Borrowed(data.as_bytes()),
+        Cow::Owned(data) => Cow::Owned(data.into_bytes()),
+    }
+}
+
+/// U+FFFD, which replaces what is not valid UTF-8.
+const REPLACEMENT: &[u8] = "\u{FFFD}".as_bytes();
+
+/// Writes bytes to a stream, optionally replacing what is not valid UTF-8 with U+FFFD, for consoles which
+/// only accept UTF-8.
+///
+/// A UTF-8 sequence can be split across writes: an incomplete sequence at the end of a write is kept until
+/// the next one, and replaced if the writer is dropped before it is completed.
+pub struct ConsoleWriter<W: Write> {
+    inner: Option<W>,
+    lossy: bool,
+    pending: Vec<u8>,
+}
+
+impl<W: Write> ConsoleWriter<W> {
+    /// Write to `inner`, replacing invalid UTF-8 if `lossy` is set.
+    pub fn new(inner: W, lossy: bool) -> Self {
+        ConsoleWriter {
+            inner: Some(inner),
+            lossy,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Get the underlying stream back. An incomplete UTF-8 sequence which is still pending is replaced.
+    pub fn into_inner(mut self) -> W {
+        let _ = self.finish();
+        self.inner.take().expect("the stream is only taken once")
+    }
+
+    fn inner(&mut self) -> &mut W {
+        self.inner.as_mut().expect("the stream is only taken once")
+    }
+
+    /// Replace the pending incomplete sequence, if there is one.
+    fn finish(&mut self) -> io::Result<()> {
+        if mem::take(&mut self.pending).is_empty() {
+            return Ok(());
+        }
+        self.inner().write_all(REPLACEMENT)
+    }
+
+    fn write_lossy(&mut self, buf: &[u8]) -> io::Result<()> {
+        let mut data = mem::take(&mut self.pending);
+        data.extend_from_slice(buf);
+
+        let mut chunks = data.utf8_chunks().peekable();
+        while let Some(chunk) = chunks.next() {
+            let inner = self.inner.as_mut().expect("the stream is only taken once");
+            inner.write_all(chunk.valid().as_bytes())?;
+            let invalid = chunk.invalid();
+            if invalid.is_empty() {
+                continue;
+            }
+            let incomplete = chunks.peek().is_none()
+                && std::str::from_utf8(invalid).is_err_and(|error| error.error_len().is_none());
+            if incomplete {
+                self.pending = invalid.to_vec();
+            } else {
+                inner.write_all(REPLACEMENT)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for ConsoleWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.lossy {
+            return self.inner().write(buf);
+        }
+        self.write_lossy(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner().flush()
+    }
+}
+
+impl<W: Write> Drop for ConsoleWriter<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.finish();
+        }
+    }
+}
+
+/// Get the standard output of the process, which replaces invalid UTF-8 if it is a Windows console.
+pub fn stdout() -> ConsoleWriter<StdoutLock<'static>> {
+    let stdout = io::stdout();
+    let lossy = cfg!(windows) && stdout.is_terminal();
+    ConsoleWriter::new(stdout.lock(), lossy)
+}
+
+/// Get the standard error of the process, which replaces invalid UTF-8 if it is a Windows console.
+pub fn stderr() -> ConsoleWriter<StderrLock<'static>> {
+    let stderr = io::stderr();
+    let lossy = cfg!(windows) && stderr.is_terminal();
+    ConsoleWriter::new(stderr.lock(), lossy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write the pieces to a lossy console.
+    fn console(pieces: &[&[u8]]) -> String {
+        let mut console = ConsoleWriter::new(Vec::new(), true);
+        for piece in pieces {
+            assert_eq!(console.write(piece).unwrap(), piece.len());
+        }
+        String::from_utf8(console.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn test_valid() {
+        assert_eq!(
+            console(&[b"hello\n", "wörld\r\n".as_bytes()]),
+            "hello\nwörld\r\n"
+        );
+        assert_eq!(console(&[b""]), "");
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(console(&[b"a\xffb"]), "a\u{FFFD}b");
+        assert_eq!(console(&[b"\xff\xfe"]), "\u{FFFD}\u{FFFD}");
+        assert_eq!(console(&[b"\xc3("]), "\u{FFFD}(");
+        // A truncated sequence in the middle is replaced once, like `String::from_utf8_lossy`.
+        assert_eq!(console(&[b"\xe2\x82x"]), "\u{FFFD}x");
+        assert_eq!(console(&[b"\xed\xa0\x80"]), "\u{FFFD}\u{FFFD}\u{FFFD}");
+    }
+
+    #[test]
+    fn test_split_sequence() {
+        assert_eq!(console(&[b"\xe2", b"\x82", b"\xac"]), "€");
+        assert_eq!(
+            console(&["😀".as_bytes()[..2].as_ref(), &"😀".as_bytes()[2..]]),
+            "😀"
+        );
+        assert_eq!(console(&[b"a\xe2\x82", b"x"]), "a\u{FFFD}x");
+        // A sequence which is never completed is replaced at the end.
+        assert_eq!(console(&[b"a\xe2\x82"]), "a\u{FFFD}");
+    }
+
+    #[test]
+    fn test_not_lossy() {
+        let mut writer = ConsoleWriter::new(Vec::new(), false);
+        writer.write_all(b"a\xff\xe2").unwrap();
+        assert_eq!(writer.into_inner(), b"a\xff\xe2");
+    }
+
+    #[test]
+    fn test_os_str_bytes() {
+        assert_eq!(os_str_bytes("héllo".as_ref()), "héllo".as_bytes());
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            assert_eq!(os_str_bytes(OsStr::from_bytes(b"a\xff")), &b"a\xff"[..]);
+        }
+    }
+}
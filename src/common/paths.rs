@@ -0,0 +1,141 @@
+//! Checks on the paths of the files which cp, mv, and rm operate on, shared so that they name the new files
+//! and refuse the same impossible operations alike.
+//!
+//! ```
+//! use rust_coreutils::common::paths::{base_name, is_inside};
+//! use std::path::Path;
+//!
+//! assert_eq!(base_name(Path::new("dir/file/")), "file");
+//! assert_eq!(base_name(Path::new("dir/.")), ".");
+//!
+//! let directory = tempfile::tempdir().unwrap();
+//! let tree = directory.path().join("tree");
+//! std::fs::create_dir(&tree).unwrap();
+//! assert!(is_inside(&tree, &tree.join("copy")));
+//! assert!(!is_inside(&tree, &directory.path().join("copy")));
+//! ```
+
+use std::ffi::OsStr;
+use std::fs::{self, Metadata};
+#[cfg(unix)]
+use std::io::{self, ErrorKind};
+use std::path::{is_separator, Component, Path};
+
+/// Get the last component of a path, which names the file in a destination directory.
+///
+/// Unlike [`Path::components`], a trailing `.` is kept, so `dir/.` copies the contents of `dir`.
+pub fn base_name(path: &Path) -> &OsStr {
+    let bytes = path.as_os_str().as_encoded_bytes();
+    let separator = |byte: &u8| is_separator(char::from(*byte));
+    let end = bytes.len()
+        - bytes
+            .iter()
+            .rev()
+            .take_while(|byte| separator(byte))
+            .count();
+    if bytes[..end] == *b"."
+        || (bytes[..end].ends_with(b".") && end > 1 && separator(&bytes[end - 2]))
+    {
+        return OsStr::new(".");
+    }
+    match path.components().next_back() {
+        Some(Component::Normal(name)) => name,
+        Some(component) => component.as_os_str(),
+        None => path.as_os_str(),
+    }
+}
+
+/// Check whether a new path is inside a directory, where copying or moving the directory would never end.
+///
+/// The path itself does not have to exist, but its parent does.
+pub fn is_inside(directory: &Path, path: &Path) -> bool {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    match (fs::canonicalize(directory), fs::canonicalize(parent)) {
+        (Ok(directory), Ok(parent)) => parent.join(base_name(path)).starts_with(directory),
+        _ => false,
+    }
+}
+
+/// Check whether two existing files are the same one, given their metadata and their paths.
+#[cfg(unix)]
+pub fn same_file(a: &Metadata, b: &Metadata, _a_path: &Path, _b_path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+/// Check whether two existing files are the same one, given their metadata and their paths.
+#[cfg(not(unix))]
+pub fn same_file(_a: &Metadata, _b: &Metadata, a_path: &Path, b_path: &Path) -> bool {
+    match (fs::canonicalize(a_path), fs::canonicalize(b_path)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Convert a path for a system call, which fails if it contains a NUL byte.
+#[cfg(unix)]
+pub fn c_path(path: &Path) -> io::Result<std::ffi::CString> {
+    use std::os::unix::ffi::OsStrExt;
+    std::ffi::CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| io::Error::from(ErrorKind::InvalidInput))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_name() {
+        for (path, name) in [
+            ("file", "file"),
+            ("dir/file", "file"),
+            ("dir//file//", "file"),
+            ("/", "/"),
+            (".", "."),
+            ("./", "."),
+            ("dir/.", "."),
+            ("dir/./", "."),
+            ("dir/..", ".."),
+            ("dir/.file", ".file"),
+            ("dir/file.", "file."),
+        ] {
+            assert_eq!(base_name(Path::new(path)), name, "{path}");
+        }
+    }
+
+    #[test]
+    fn test_is_inside() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        fs::create_dir(root.join("ab")).unwrap();
+
+        assert!(is_inside(&root.join("a"), &root.join("a")));
+        assert!(is_inside(&root.join("a"), &root.join("a/b/a")));
+        assert!(is_inside(&root.join("a"), &root.join("a/b/../c")));
+        assert!(!is_inside(&root.join("a/b"), &root.join("a/c")));
+        assert!(!is_inside(&root.join("a"), &root.join("ab/a")));
+        // The parent of the path has to exist.
+        assert!(!is_inside(&root.join("a"), &root.join("a/missing/a")));
+    }
+
+    #[test]
+    fn test_same_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let (a, b) = (directory.path().join("a"), directory.path().join("b"));
+        fs::write(&a, "").unwrap();
+        fs::write(&b, "").unwrap();
+        let metadata = |path| fs::metadata(path).unwrap();
+
+        assert!(same_file(
+            &metadata(&a),
+            &metadata(&a),
+            &a,
+            &directory.path().join("./a")
+        ));
+        assert!(!same_file(&metadata(&a), &metadata(&b), &a, &b));
+    }
+}
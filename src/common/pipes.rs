@@ -0,0 +1,59 @@
+//! Broken pipe handling shared by all binaries.
+//!
+//! The Rust runtime ignores `SIGPIPE`, so writing to a closed pipe fails with `EPIPE`, and `println!` panics
+//! on it. Like the GNU utilities, the binaries restore the default disposition instead, so that a pipeline
+//! like `yes | head -n 1` terminates the writer silently with the usual exit status of 141 in the shell.
+//!
+//! Every `main` calls [`init`] before doing anything else.
+
+/// Restore the default `SIGPIPE` disposition, which terminates the process on a write to a closed pipe.
+///
+/// There is no `SIGPIPE` outside of Unix, where this does nothing, and a broken pipe is reported as a write
+/// error instead.
+pub fn init() {
+    #[cfg(unix)]
+    // SAFETY: called before any other threads are spawned, and `SIG_DFL` does not run any code.
+    unsafe {
+        libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use assert_cmd::prelude::*;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::Command;
+
+    /// Run the command with the read end of its stdout closed.
+    fn assert_closed_pipe(mut cmd: Command) {
+        let (reader, writer) = std::io::pipe().unwrap();
+        drop(reader);
+
+        let output = cmd.stdout(writer).output().unwrap();
+        assert_eq!(output.status.signal(), Some(libc::SIGPIPE));
+        assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+    }
+
+    /// Arguments longer than the pipe buffer in total.
+    fn long_args() -> Vec<String> {
+        vec!["x".repeat(1000); 100]
+    }
+
+    #[test]
+    fn test_binary_closed_pipe() {
+        let mut cmd = Command::cargo_bin("echo").unwrap();
+        cmd.args(long_args());
+        assert_closed_pipe(cmd);
+    }
+
+    #[test]
+    fn test_multicall_closed_pipe() {
+        let mut cmd = Command::cargo_bin("coreutils").unwrap();
+        cmd.arg("echo").args(long_args());
+        assert_closed_pipe(cmd);
+
+        let mut cmd = Command::cargo_bin("coreutils").unwrap();
+        cmd.arg("--list");
+        assert_closed_pipe(cmd);
+    }
+}
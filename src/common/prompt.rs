@@ -0,0 +1,310 @@
+//! Interactive confirmation for rm, mv, and cp, like `rm: remove regular file 'a'? `.
+//!
+//! The question is printed to standard error, and an answer starting with `y` or `Y` is affirmative. Any
+//! other answer, an empty line, and the end of the input mean no:
+//!
+//! ```
+//! use rust_coreutils::common::prompt::Prompter;
+//! use rust_coreutils::prompt_yes;
+//!
+//! let mut answers = &b"yes\nnope\n"[..];
+//! let mut stderr = Vec::new();
+//! let mut prompter = Prompter::new("rm", &mut answers, &mut stderr);
+//! assert!(prompt_yes!(prompter, "remove regular file '{}'?", "a"));
+//! assert!(!prompt_yes!(prompter, "remove regular file '{}'?", "b"));
+//! assert!(!prompt_yes!(prompter, "remove regular file '{}'?", "c"));
+//! assert_eq!(
+//!     String::from_utf8(stderr).unwrap(),
+//!     "rm: remove regular file 'a'? rm: remove regular file 'b'? rm: remove regular file 'c'? "
+//! );
+//! ```
+
+use crate::common::error::{UError, UResult, EXIT_FAILURE};
+use clap::ValueEnum;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, ErrorKind, Read, Write};
+
+/// When to ask for confirmation, as selected by the last of `-f`, `-i`, `-I`, and `--interactive[=WHEN]`.
+///
+/// The values of `--interactive` are `never` (or `no`, `none`), `once`, and `always` (or `yes`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum PromptPolicy {
+    /// `-f`: never prompt, and ignore missing operands.
+    #[value(skip)]
+    Force,
+    /// `--interactive=never`: never prompt.
+    #[value(alias = "no", alias = "none")]
+    Never,
+    /// No option: only prompt for operations that are unlikely to be intended, like removing a
+    /// write-protected file when standard input is a terminal.
+    #[default]
+    #[value(skip)]
+    Default,
+    /// `-I`, `--interactive=once`: prompt once before removing more than three files or recursively.
+    Once,
+    /// `-i`, `--interactive=always`: prompt before every operation.
+    #[value(alias = "yes")]
+    Always,
+}
+
+impl PromptPolicy {
+    /// Get the policy of the command line, given the options which are set. The options are expected to
+    /// override each other, so that only the last one is set.
+    pub fn from_options(force: bool, once: bool, always: bool, interactive: Option<Self>) -> Self {
+        match (force, once, always, interactive) {
+            (true, ..) => PromptPolicy::Force,
+            (_, true, ..) => PromptPolicy::Once,
+            (_, _, true, _) => PromptPolicy::Always,
+            (.., Some(policy)) => policy,
+            _ => PromptPolicy::Default,
+        }
+    }
+
+    /// Check whether to prompt once before operating on `operands` files, possibly recursively.
+    pub fn prompt_once(self, operands: usize, recursive: bool) -> bool {
+        self == PromptPolicy::Once && (operands > 3 || recursive)
+    }
+
+    /// Check whether to prompt before every operation.
+    pub fn prompt_always(self) -> bool {
+        self == PromptPolicy::Always
+    }
+
+    /// Check whether to prompt before an operation on a write-protected file.
+    pub fn prompt_write_protected(self, stdin_is_terminal: bool) -> bool {
+        self == PromptPolicy::Always || (self == PromptPolicy::Default && stdin_is_terminal)
+    }
+
+    /// Check whether missing operands are silently ignored.
+    pub fn ignore_missing(self) -> bool {
+        self == PromptPolicy::Force
+    }
+
+    /// Parse the value of `--interactive`, which can be abbreviated as long as it is unambiguous.
+    pub fn parse(when: &str) -> UResult<Self> {
+        let possible: Vec<_> = Self::value_variants()
+            .iter()
+            .filter_map(|&policy| Some((policy.to_possible_value()?, policy)))
+            .collect();
+        let values: Vec<(&str, Self)> = possible
+            .iter()
+            .flat_map(|(value, policy)| value.get_name_and_aliases().map(|name| (name, *policy)))
+            .collect();
+        if let Some(&(_, policy)) = values.iter().find(|(name, _)| *name == when) {
+            return Ok(policy);
+        }
+        let mut matches = values
+            .iter()
+            .filter(|(name, _)| name.starts_with(when))
+            .map(|&(_, policy)| policy);
+        let problem = match matches.next() {
+            Some(policy) if matches.all(|other| other == policy) => return Ok(policy),
+            Some(_) => "ambiguous",
+            None => "invalid",
+        };
+        Err(UError::new(
+            EXIT_FAILURE,
+            format!(
+                "{problem} argument '{when}' for '--interactive'\n\
+                 Valid arguments are:\n  \
+                 - 'never', 'no', 'none'\n  \
+                 - 'once'\n  \
+                 - 'always', 'yes'"
+            ),
+        ))
+    }
+}
+
+/// Asks questions on one stream and reads the answers from another.
+pub struct Prompter<'a> {
+    util: &'static str,
+    input: &'a mut dyn Read,
+    output: &'a mut dyn Write,
+}
+
+impl<'a> Prompter<'a> {
+    /// Create a prompter for the given utility, reading the answers from `input` and printing the questions
+    /// to `output`, which is usually standard error.
+    pub fn new(util: &'static str, input: &'a mut dyn Read, output: &'a mut dyn Write) -> Self {
+        Prompter {
+            util,
+            input,
+            output,
+        }
+    }
+
+    /// Print the question prefixed with the utility name, and check whether the answer is affirmative.
+    /// See also [`prompt_yes!`](crate::prompt_yes).
+    ///
+    /// Exactly one line of the input is consumed, so that the next question reads the next answer.
+    pub fn prompt_yes(&mut self, question: fmt::Arguments) -> bool {
+        let _ = write!(self.output, "{}: {question} ", self.util);
+        let _ = self.output.flush();
+
+        match self.read_line() {
+            Ok(line) => matches!(line.first(), Some(b'y' | b'Y')),
+            Err(_) => false,
+        }
+    }
+
+    /// Read a line without its terminator, byte by byte so that nothing after it is consumed. An empty line
+    /// is returned at the end of the input.
+    fn read_line(&mut self) -> io::Result<Vec<u8>> {
+        let mut line = Vec::new();
+        let mut byte = [0];
+        loop {
+            match self.input.read(&mut byte) {
+                Ok(0) => return Ok(line),
+                Ok(_) if byte[0] == b'\n' => return Ok(line),
+                Ok(_) => line.push(byte[0]),
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+/// Open the terminal to read answers from, for utilities whose standard input is the data they process.
+pub fn open_tty() -> io::Result<File> {
+    File::open(if cfg!(windows) { "CONIN$" } else { "/dev/tty" })
+}
+
+/// Print a question prefixed with the utility name through a [`Prompter`], and check whether the answer is
+/// affirmative.
+#[macro_export]
+macro_rules! prompt_yes {
+    ($prompter:expr, $($arg:tt)+) => {
+        $prompter.prompt_yes(format_args!($($arg)+))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ask a question with the given answers, returning the result and what was printed.
+    fn ask(answers: &[u8]) -> (bool, String) {
+        let mut input = answers;
+        let mut output = Vec::new();
+        let answer =
+            Prompter::new("rm", &mut input, &mut output).prompt_yes(format_args!("remove?"));
+        (answer, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_yes() {
+        assert_eq!(ask(b"y\n"), (true, "rm: remove? ".to_string()));
+        assert!(ask(b"Y\n").0);
+        assert!(ask(b"yes\n").0);
+        assert!(ask(b"yolo\n").0);
+        assert!(ask(b"Yes\nno\n").0);
+    }
+
+    #[test]
+    fn test_no() {
+        assert!(!ask(b"n\n").0);
+        assert!(!ask(b"no\n").0);
+        assert!(!ask(b"\n").0);
+        assert!(!ask(b"\nyes\n").0);
+    }
+
+    #[test]
+    fn test_garbage() {
+        assert!(!ask(b" y\n").0);
+        assert!(!ask(b"oui\n").0);
+        assert!(!ask(b"\xff\xfe\n").0);
+        assert!(!ask(b"1\n").0);
+    }
+
+    #[test]
+    fn test_eof() {
+        assert!(!ask(b"").0);
+        // A last line without a terminator is still an answer.
+        assert!(ask(b"y").0);
+
+        let mut input = &b"y\n"[..];
+        let mut output = Vec::new();
+        let mut prompter = Prompter::new("rm", &mut input, &mut output);
+        assert!(prompt_yes!(prompter, "first?"));
+        for _ in 0..3 {
+            assert!(!prompt_yes!(prompter, "again?"));
+        }
+    }
+
+    #[test]
+    fn test_read_error() {
+        struct Failing;
+        impl Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("broken"))
+            }
+        }
+
+        let mut output = Vec::new();
+        assert!(!Prompter::new("rm", &mut Failing, &mut output).prompt_yes(format_args!("remove?")));
+    }
+
+    #[test]
+    fn test_policy() {
+        let policy = PromptPolicy::from_options;
+        assert_eq!(policy(false, false, false, None), PromptPolicy::Default);
+        assert_eq!(policy(true, false, false, None), PromptPolicy::Force);
+        assert_eq!(policy(false, true, false, None), PromptPolicy::Once);
+        assert_eq!(policy(false, false, true, None), PromptPolicy::Always);
+        assert_eq!(
+            policy(false, false, false, Some(PromptPolicy::Never)),
+            PromptPolicy::Never
+        );
+
+        assert!(PromptPolicy::Once.prompt_once(4, false));
+        assert!(PromptPolicy::Once.prompt_once(1, true));
+        assert!(!PromptPolicy::Once.prompt_once(3, false));
+        assert!(!PromptPolicy::Always.prompt_once(4, true));
+
+        assert!(PromptPolicy::Default.prompt_write_protected(true));
+        assert!(!PromptPolicy::Default.prompt_write_protected(false));
+        assert!(PromptPolicy::Always.prompt_write_protected(false));
+        for policy in [PromptPolicy::Force, PromptPolicy::Never, PromptPolicy::Once] {
+            assert!(!policy.prompt_write_protected(true));
+            assert!(!policy.prompt_always());
+        }
+        assert!(PromptPolicy::Force.ignore_missing());
+        assert!(!PromptPolicy::Never.ignore_missing());
+    }
+
+    #[test]
+    fn test_interactive_values() {
+        let parse = |value| PromptPolicy::from_str(value, false);
+        assert_eq!(parse("never"), Ok(PromptPolicy::Never));
+        assert_eq!(parse("no"), Ok(PromptPolicy::Never));
+        assert_eq!(parse("none"), Ok(PromptPolicy::Never));
+        assert_eq!(parse("once"), Ok(PromptPolicy::Once));
+        assert_eq!(parse("always"), Ok(PromptPolicy::Always));
+        assert_eq!(parse("yes"), Ok(PromptPolicy::Always));
+        assert!(parse("force").is_err());
+        assert!(parse("default").is_err());
+    }
+
+    #[test]
+    fn test_parse_interactive() {
+        assert_eq!(PromptPolicy::parse("no"), Ok(PromptPolicy::Never));
+        assert_eq!(PromptPolicy::parse("n"), Ok(PromptPolicy::Never));
+        assert_eq!(PromptPolicy::parse("al"), Ok(PromptPolicy::Always));
+        assert_eq!(PromptPolicy::parse("y"), Ok(PromptPolicy::Always));
+        let error = |problem: &str, when: &str| {
+            UError::new(
+                EXIT_FAILURE,
+                format!(
+                    "{problem} argument '{when}' for '--interactive'\n\
+                     Valid arguments are:\n  \
+                     - 'never', 'no', 'none'\n  \
+                     - 'once'\n  \
+                     - 'always', 'yes'"
+                ),
+            )
+        };
+        assert_eq!(PromptPolicy::parse("bogus"), Err(error("invalid", "bogus")));
+        assert_eq!(PromptPolicy::parse(""), Err(error("ambiguous", "")));
+    }
+}
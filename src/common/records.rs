@@ -0,0 +1,243 @@
+//! Records separated by a delimiter, which is a newline, or a null byte with `-z`, for the line-oriented
+//! utilities like sort, uniq, head, tail, and cut.
+//!
+//! ```
+//! use rust_coreutils::common::records::{RecordReader, RecordWriter};
+//!
+//! let mut reader = RecordReader::new(&b"a\nb\0c"[..], b'\0');
+//! let mut writer = RecordWriter::new(Vec::new(), b'\0');
+//! while let Some(record) = reader.next_record() {
+//!     writer.write_record(record.unwrap().content).unwrap();
+//! }
+//! assert_eq!(writer.into_inner(), b"a\nb\0c\0");
+//! ```
+
+use std::io::{self, BufRead, Write};
+
+/// The `-z`, `--zero-terminated` option of a command line.
+#[derive(clap::Args, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ZeroTerminatedArgs {
+    /// Separate lines with a null byte instead of a newline.
+    #[arg(short = 'z', long)]
+    pub zero_terminated: bool,
+}
+
+impl ZeroTerminatedArgs {
+    /// Get the delimiter of the records.
+    pub fn delimiter(self) -> u8 {
+        if self.zero_terminated {
+            b'\0'
+        } else {
+            b'\n'
+        }
+    }
+}
+
+/// A record read by a [`RecordReader`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Record<'a> {
+    /// The content of the record, without its delimiter.
+    pub content: &'a [u8],
+    /// Whether the record was followed by the delimiter, which only the last one of the input may not be.
+    pub terminated: bool,
+}
+
+/// Reads the records of an input one at a time, into a buffer which is reused for all of them.
+///
+/// This is not an [`Iterator`], because each record borrows from the reader.
+pub struct RecordReader<R> {
+    reader: R,
+    delimiter: u8,
+    buffer: Vec<u8>,
+}
+
+impl<R: BufRead> RecordReader<R> {
+    /// Read the records of `reader` separated by `delimiter`.
+    pub fn new(reader: R, delimiter: u8) -> Self {
+        RecordReader {
+            reader,
+            delimiter,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Read the next record, or `None` at the end of the input.
+    pub fn next_record(&mut self) -> Option<io::Result<Record<'_>>> {
+        self.buffer.clear();
+        match self.reader.read_until(self.delimiter, &mut self.buffer) {
+            Ok(0) => return None,
+            Ok(_) => {}
+            Err(error) => return Some(Err(error)),
+        }
+        Some(Ok(match self.buffer.split_last() {
+            Some((&last, content)) if last == self.delimiter => Record {
+                content,
+                terminated: true,
+            },
+            _ => Record {
+                content: &self.buffer,
+                terminated: false,
+            },
+        }))
+    }
+
+    /// Get the underlying reader, for example to copy the rest of the input as it is.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+}
+
+/// Writes records, each followed by a delimiter.
+pub struct RecordWriter<W> {
+    writer: W,
+    delimiter: u8,
+}
+
+impl<W: Write> RecordWriter<W> {
+    /// Write records to `writer` separated by `delimiter`.
+    pub fn new(writer: W, delimiter: u8) -> Self {
+        RecordWriter { writer, delimiter }
+    }
+
+    /// Write a record and its delimiter.
+    pub fn write_record(&mut self, content: &[u8]) -> io::Result<()> {
+        self.writer.write_all(content)?;
+        self.writer.write_all(&[self.delimiter])
+    }
+
+    /// Write a record as it was read, with its delimiter only if it had one.
+    pub fn write_as_read(&mut self, record: Record) -> io::Result<()> {
+        if record.terminated {
+            self.write_record(record.content)
+        } else {
+            self.writer.write_all(record.content)
+        }
+    }
+
+    /// Get the underlying writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Get the underlying writer back.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufReader, Read};
+
+    /// Read all the records, with whether they were terminated.
+    fn records(input: &[u8], delimiter: u8, capacity: usize) -> Vec<(Vec<u8>, bool)> {
+        let mut reader = RecordReader::new(BufReader::with_capacity(capacity, input), delimiter);
+        let mut records = Vec::new();
+        while let Some(record) = reader.next_record() {
+            let record = record.unwrap();
+            records.push((record.content.to_vec(), record.terminated));
+        }
+        records
+    }
+
+    #[test]
+    fn test_lines() {
+        assert_eq!(
+            records(b"a\nbc\n", b'\n', 64),
+            [(b"a".to_vec(), true), (b"bc".to_vec(), true)]
+        );
+        assert_eq!(
+            records(b"a\n\nb", b'\n', 64),
+            [
+                (b"a".to_vec(), true),
+                (vec![], true),
+                (b"b".to_vec(), false)
+            ]
+        );
+        assert_eq!(records(b"", b'\n', 64), []);
+        assert_eq!(records(b"\n", b'\n', 64), [(vec![], true)]);
+    }
+
+    #[test]
+    fn test_zero_terminated() {
+        // Newlines are part of the content of null-terminated records.
+        assert_eq!(
+            records(b"a\nb\0\nc\0d\n", b'\0', 64),
+            [
+                (b"a\nb".to_vec(), true),
+                (b"\nc".to_vec(), true),
+                (b"d\n".to_vec(), false)
+            ]
+        );
+        assert_eq!(
+            records(b"\0\0", b'\0', 64),
+            [(vec![], true), (vec![], true)]
+        );
+    }
+
+    #[test]
+    fn test_buffer_boundaries() {
+        let input: Vec<u8> = (0..1000u32)
+            .flat_map(|n| {
+                let mut record = n.to_string().repeat(n as usize % 7).into_bytes();
+                record.push(b'\0');
+                record
+            })
+            .collect();
+        for capacity in [1, 2, 3, 16, 4096] {
+            let records = records(&input, b'\0', capacity);
+            assert_eq!(records.len(), 1000);
+            for (n, (content, terminated)) in records.into_iter().enumerate() {
+                assert_eq!(content, n.to_string().repeat(n % 7).into_bytes());
+                assert!(terminated);
+            }
+        }
+    }
+
+    #[test]
+    fn test_read_error() {
+        struct Failing;
+        impl Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("broken"))
+            }
+        }
+
+        let mut reader = RecordReader::new(BufReader::new(Failing), b'\n');
+        assert_eq!(
+            reader.next_record().unwrap().unwrap_err().to_string(),
+            "broken"
+        );
+    }
+
+    #[test]
+    fn test_writer() {
+        let mut reader = RecordReader::new(&b"a\nb\0c"[..], b'\0');
+        let mut writer = RecordWriter::new(Vec::new(), b'\0');
+        while let Some(record) = reader.next_record() {
+            writer.write_as_read(record.unwrap()).unwrap();
+        }
+        assert_eq!(writer.into_inner(), b"a\nb\0c");
+
+        let mut writer = RecordWriter::new(Vec::new(), b'\n');
+        writer.write_record(b"a\0b").unwrap();
+        writer.write_record(b"").unwrap();
+        assert_eq!(writer.into_inner(), b"a\0b\n\n");
+    }
+
+    #[test]
+    fn test_args() {
+        #[derive(clap::Parser)]
+        struct Command {
+            #[command(flatten)]
+            records: ZeroTerminatedArgs,
+        }
+
+        use clap::Parser;
+        let parse = |args: &[&str]| Command::parse_from(args).records.delimiter();
+        assert_eq!(parse(&["util"]), b'\n');
+        assert_eq!(parse(&["util", "-z"]), b'\0');
+        assert_eq!(parse(&["util", "--zero-terminated"]), b'\0');
+    }
+}
@@ -0,0 +1,317 @@
+//! Byte counts with multiplier suffixes, like `head -c 2M`, `split -b 512K`, or `dd bs=4k`.
+//!
+//! A number can be followed by a suffix: `b` (512), `K` or `k` (1024), `M`, `G`, `T`, `P`, `E`, `Z`, `Y`, `R`,
+//! and `Q` are powers of 1024, or of 1000 when followed by `B` (`KB`, `MB`, ...). A trailing `iB` (`KiB`,
+//! `MiB`, ...) is the same as no `B`. A suffix without a number counts once, so `K` is 1024.
+//!
+//! [`parse_prefixed_size`] also takes the numbers of C, like od: hexadecimal after `0x`, and octal after `0`.
+//!
+//! ```
+//! use rust_coreutils::common::size::{parse_size, parse_signed_size, Sign};
+//!
+//! assert_eq!(parse_size("2M"), Ok(2 * 1024 * 1024));
+//! assert_eq!(parse_size("2MB"), Ok(2_000_000));
+//! assert_eq!(parse_signed_size("-1k").unwrap(), (Some(Sign::Minus), 1024));
+//! assert_eq!(
+//!     format!("invalid number of bytes: {}", parse_size("1KX").unwrap_err()),
+//!     "invalid number of bytes: '1KX'"
+//! );
+//! ```
+
+use std::fmt;
+
+/// The explicit sign of a size, which callers interpret, e.g. as a count from the end.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sign {
+    /// A leading `+`.
+    Plus,
+    /// A leading `-`.
+    Minus,
+}
+
+/// Why a size could not be parsed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SizeErrorKind {
+    /// The string is not a number with an optional suffix.
+    Invalid,
+    /// The size does not fit.
+    TooLarge,
+}
+
+/// A size that could not be parsed.
+///
+/// It is displayed as the quoted string, followed by the reason if it is too large, so that utilities can
+/// prefix it with their own message, like GNU's `invalid number of bytes: '1KX'`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SizeError {
+    /// Why the size could not be parsed.
+    pub kind: SizeErrorKind,
+    /// The string that could not be parsed.
+    pub input: String,
+}
+
+impl SizeError {
+    fn new(kind: SizeErrorKind, input: &str) -> Self {
+        SizeError {
+            kind,
+            input: input.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for SizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            SizeErrorKind::Invalid => write!(f, "'{}'", self.input),
+            SizeErrorKind::TooLarge => {
+                write!(f, "'{}': Value too large for defined data type", self.input)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SizeError {}
+
+/// The suffixes which are powers of 1000 or 1024, in increasing order.
+const POWERS: &[u8] = b"KMGTPEZYRQ";
+
+/// Parse a size without a sign.
+pub fn parse_size(s: &str) -> Result<u128, SizeError> {
+    parse_unsigned(s, s, false, 10)
+}
+
+/// Parse a size without a sign, whose number is hexadecimal after a leading `0x` or `0X`, or octal after a
+/// leading `0`.
+pub fn parse_prefixed_size(s: &str) -> Result<u128, SizeError> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        // The digits are required, so that the suffix of `0xK` is not taken for a number.
+        Some(hex) if hex.starts_with(|c: char| c.is_ascii_hexdigit()) => {
+            parse_unsigned(hex, s, false, 16)
+        }
+        Some(_) => Err(SizeError::new(SizeErrorKind::Invalid, s)),
+        None if s.starts_with('0') => parse_unsigned(s, s, false, 8),
+        None => parse_unsigned(s, s, false, 10),
+    }
+}
+
+/// Parse a size which fits in a `u64`.
+pub fn parse_size_u64(s: &str) -> Result<u64, SizeError> {
+    let size = parse_size(s)?;
+    u64::try_from(size).map_err(|_| SizeError::new(SizeErrorKind::TooLarge, s))
+}
+
+/// Parse a size with an optional leading `+` or `-`, which is returned separately.
+pub fn parse_signed_size(s: &str) -> Result<(Option<Sign>, u128), SizeError> {
+    let (sign, rest) = match s.as_bytes().first() {
+        Some(b'+') => (Some(Sign::Plus), &s[1..]),
+        Some(b'-') => (Some(Sign::Minus), &s[1..]),
+        _ => (None, s),
+    };
+    Ok((sign, parse_unsigned(rest, s, false, 10)?))
+}
+
+/// Parse a size like dd does: the suffixes also include `c` (1) and `w` (2), and sizes can be multiplied
+/// with `x`, like `2x512`.
+pub fn parse_dd_size(s: &str) -> Result<u128, SizeError> {
+    s.split('x').try_fold(1u128, |product, factor| {
+        let factor = parse_unsigned(factor, s, true, 10)?;
+        product
+            .checked_mul(factor)
+            .ok_or_else(|| SizeError::new(SizeErrorKind::TooLarge, s))
+    })
+}
+
+/// Parse a number in `radix` with an optional suffix, reporting errors for `input`.
+fn parse_unsigned(s: &str, input: &str, dd: bool, radix: u32) -> Result<u128, SizeError> {
+    let invalid = || SizeError::new(SizeErrorKind::Invalid, input);
+    let too_large = || SizeError::new(SizeErrorKind::TooLarge, input);
+
+    let digits = s.chars().take_while(|c| c.is_digit(radix)).count();
+    let (number, suffix) = s.split_at(digits);
+    let number = match number {
+        // A suffix alone counts once, but nothing at all is not a size.
+        "" if suffix.is_empty() => return Err(invalid()),
+        "" => 1,
+        number => u128::from_str_radix(number, radix).map_err(|_| too_large())?,
+    };
+
+    let multiplier = multiplier(suffix, dd).ok_or_else(invalid)?;
+    number.checked_mul(multiplier).ok_or_else(too_large)
+}
+
+/// Get the value of a suffix, or `None` if it is not valid.
+fn multiplier(suffix: &str, dd: bool) -> Option<u128> {
+    let Some((&first, rest)) = suffix.as_bytes().split_first() else {
+        return Some(1);
+    };
+
+    let exponent = match first {
+        b'b' if rest.is_empty() => return Some(512),
+        b'c' if dd && rest.is_empty() => return Some(1),
+        b'w' if dd && rest.is_empty() => return Some(2),
+        b'k' => 1,
+        _ => POWERS.iter().position(|&power| power == first)? as u32 + 1,
+    };
+    let base: u128 = match rest {
+        b"" | b"iB" => 1024,
+        b"B" => 1000,
+        _ => return None,
+    };
+    base.checked_pow(exponent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invalid(input: &str) -> Result<u128, SizeError> {
+        Err(SizeError::new(SizeErrorKind::Invalid, input))
+    }
+
+    fn too_large(input: &str) -> Result<u128, SizeError> {
+        Err(SizeError::new(SizeErrorKind::TooLarge, input))
+    }
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(parse_size("0"), Ok(0));
+        assert_eq!(parse_size("1"), Ok(1));
+        assert_eq!(parse_size("0012"), Ok(12));
+        assert_eq!(parse_size("18446744073709551615"), Ok(u64::MAX.into()));
+        assert_eq!(
+            parse_size("340282366920938463463374607431768211455"),
+            Ok(u128::MAX)
+        );
+    }
+
+    #[test]
+    fn test_every_suffix() {
+        assert_eq!(parse_size("3b"), Ok(3 * 512));
+        assert_eq!(parse_size("3k"), Ok(3 * 1024));
+        assert_eq!(parse_size("3kB"), Ok(3000));
+        assert_eq!(parse_size("3kiB"), Ok(3 * 1024));
+
+        for (index, &letter) in POWERS.iter().enumerate() {
+            let letter = letter as char;
+            let exponent = index as u32 + 1;
+            assert_eq!(
+                parse_size(&format!("3{letter}")),
+                Ok(3 * 1024u128.pow(exponent)),
+                "{letter}"
+            );
+            assert_eq!(
+                parse_size(&format!("3{letter}iB")),
+                Ok(3 * 1024u128.pow(exponent)),
+                "{letter}iB"
+            );
+            assert_eq!(
+                parse_size(&format!("3{letter}B")),
+                Ok(3 * 1000u128.pow(exponent)),
+                "{letter}B"
+            );
+        }
+    }
+
+    #[test]
+    fn test_suffix_without_number() {
+        assert_eq!(parse_size("K"), Ok(1024));
+        assert_eq!(parse_size("MB"), Ok(1_000_000));
+        assert_eq!(parse_size("b"), Ok(512));
+    }
+
+    #[test]
+    fn test_garbage() {
+        for input in [
+            "", "1KX", "X", "1 K", " 1", "1 ", "1.5K", "1e3", "0x10", "1Kb", "1KIB", "1kb", "1m",
+            "1g", "1bB", "1biB", "1B", "1iB", "1c", "1w", "1KK", "K1", "+", "-1", "1x2", "１",
+        ] {
+            assert_eq!(parse_size(input), invalid(input), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert_eq!(parse_size_u64("18446744073709551615"), Ok(u64::MAX));
+        assert_eq!(
+            parse_size_u64("16EiB"),
+            Err(SizeError::new(SizeErrorKind::TooLarge, "16EiB"))
+        );
+        assert_eq!(
+            parse_size_u64("18446744073709551616"),
+            Err(SizeError::new(
+                SizeErrorKind::TooLarge,
+                "18446744073709551616"
+            ))
+        );
+        assert_eq!(parse_size_u64("15EiB"), Ok(15 << 60));
+
+        assert_eq!(
+            parse_size("340282366920938463463374607431768211456"),
+            too_large("340282366920938463463374607431768211456")
+        );
+        assert_eq!(parse_size("1000000000QB"), too_large("1000000000QB"));
+        assert_eq!(parse_size("1QB"), Ok(10u128.pow(30)));
+    }
+
+    #[test]
+    fn test_signed() {
+        assert_eq!(parse_signed_size("5"), Ok((None, 5)));
+        assert_eq!(parse_signed_size("+5K"), Ok((Some(Sign::Plus), 5120)));
+        assert_eq!(parse_signed_size("-5"), Ok((Some(Sign::Minus), 5)));
+        assert_eq!(
+            parse_signed_size("-"),
+            Err(SizeError::new(SizeErrorKind::Invalid, "-"))
+        );
+        assert_eq!(
+            parse_signed_size("--5"),
+            Err(SizeError::new(SizeErrorKind::Invalid, "--5"))
+        );
+        assert_eq!(
+            parse_signed_size("+1KX"),
+            Err(SizeError::new(SizeErrorKind::Invalid, "+1KX"))
+        );
+    }
+
+    #[test]
+    fn test_prefixed() {
+        assert_eq!(parse_prefixed_size("0x10"), Ok(16));
+        assert_eq!(parse_prefixed_size("0X1fK"), Ok(31 * 1024));
+        // A b is a hexadecimal digit, not a suffix.
+        assert_eq!(parse_prefixed_size("0x1b"), Ok(27));
+        assert_eq!(parse_prefixed_size("010"), Ok(8));
+        assert_eq!(parse_prefixed_size("0"), Ok(0));
+        assert_eq!(parse_prefixed_size("0k"), Ok(0));
+        assert_eq!(parse_prefixed_size("19k"), Ok(19 * 1024));
+        assert_eq!(parse_prefixed_size("K"), Ok(1024));
+        for input in ["0x", "0xK", "08", "0x-1", "x10", "1e"] {
+            assert_eq!(parse_prefixed_size(input), invalid(input), "{input:?}");
+        }
+        assert_eq!(
+            parse_prefixed_size("0x100000000000000000000000000000000"),
+            too_large("0x100000000000000000000000000000000")
+        );
+    }
+
+    #[test]
+    fn test_dd() {
+        assert_eq!(parse_dd_size("4k"), Ok(4096));
+        assert_eq!(parse_dd_size("3c"), Ok(3));
+        assert_eq!(parse_dd_size("3w"), Ok(6));
+        assert_eq!(parse_dd_size("2x512"), Ok(1024));
+        assert_eq!(parse_dd_size("2x3x1K"), Ok(6144));
+        assert_eq!(parse_dd_size("2xw"), Ok(4));
+        assert_eq!(parse_dd_size("1x"), invalid("1x"));
+        assert_eq!(parse_dd_size("x2"), invalid("x2"));
+        assert_eq!(parse_dd_size("1cB"), invalid("1cB"));
+        assert_eq!(parse_dd_size("1QxQxQ"), too_large("1QxQxQ"));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(parse_size("1KX").unwrap_err().to_string(), "'1KX'");
+        assert_eq!(
+            parse_size_u64("16EiB").unwrap_err().to_string(),
+            "'16EiB': Value too large for defined data type"
+        );
+    }
+}
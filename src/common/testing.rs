@@ -0,0 +1,66 @@
+//! Helpers for the unit tests of the utilities, which run them in-process through their [`Run`] function.
+
+use crate::common::utility::Run;
+use std::ffi::OsString;
+use std::fs;
+
+/// Run a utility in-process on the given standard input, returning the exit code, stdout, and stderr.
+pub fn run_util_bytes(
+    run: Run,
+    command_line: &[&str],
+    stdin: impl AsRef<[u8]>,
+) -> (i32, Vec<u8>, String) {
+    let args: Vec<OsString> = command_line.iter().map(OsString::from).collect();
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    let code = run(&args, &mut stdin.as_ref(), &mut stdout, &mut stderr);
+    (code, stdout, String::from_utf8(stderr).unwrap())
+}
+
+/// Like [`run_util_bytes`], for the utilities which write text.
+pub fn run_util_stdin(
+    run: Run,
+    command_line: &[&str],
+    stdin: impl AsRef<[u8]>,
+) -> (i32, String, String) {
+    let (code, stdout, stderr) = run_util_bytes(run, command_line, stdin);
+    (code, String::from_utf8(stdout).unwrap(), stderr)
+}
+
+/// Like [`run_util_stdin`], with an empty standard input.
+pub fn run_util(run: Run, command_line: &[&str]) -> (i32, String, String) {
+    run_util_stdin(run, command_line, b"")
+}
+
+/// Like [`run_util`], returning only the exit code and stderr, for the utilities which work on files.
+pub fn run_util_stderr(run: Run, command_line: &[&str]) -> (i32, String) {
+    let (code, _, stderr) = run_util(run, command_line);
+    (code, stderr)
+}
+
+/// A directory with files, by name and content.
+pub struct Fixture(pub tempfile::TempDir);
+
+impl Fixture {
+    pub fn new(files: &[(&str, &str)]) -> Self {
+        let directory = tempfile::tempdir().unwrap();
+        for (name, content) in files {
+            fs::write(directory.path().join(name), content).unwrap();
+        }
+        Fixture(directory)
+    }
+
+    pub fn path(&self, name: &str) -> String {
+        self.0.path().join(name).to_str().unwrap().to_owned()
+    }
+
+    /// Get the names of the files in the directory.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(self.0.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        names
+    }
+}
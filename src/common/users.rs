@@ -0,0 +1,450 @@
+//! User and group names, for chown, chgrp, id, and `ls -l`.
+//!
+//! Lookups go through the reentrant `getpwnam_r` family, and their results are cached for the life of the
+//! process, so that listing a large directory asks for each owner only once. A lookup returns `Ok(None)` if
+//! there is no such user or group, and an error only if the database could not be read:
+//!
+//! ```
+//! use rust_coreutils::common::users::{name_for_uid, parse_owner_spec, uid_for_name};
+//!
+//! assert_eq!(uid_for_name("root").unwrap(), Some(0));
+//! assert_eq!(name_for_uid(0).unwrap().as_deref(), Some("root"));
+//! assert_eq!(uid_for_name("no such user").unwrap(), None);
+//! assert_eq!(parse_owner_spec("0:0").unwrap(), (Some(0), Some(0)));
+//! assert_eq!(
+//!     parse_owner_spec("no such user").unwrap_err().to_string(),
+//!     "invalid user: 'no such user'"
+//! );
+//! ```
+
+use crate::common::error::io_error_message;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::hash::Hash;
+use std::io;
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::{LazyLock, Mutex, PoisonError};
+
+/// The largest buffer given to a lookup, to give up on entries that keep claiming not to fit.
+const MAX_BUFFER: usize = 1 << 20;
+
+/// The results of the lookups so far, including the names which do not exist.
+#[derive(Default)]
+struct Cache {
+    /// The uid and login group of each user name.
+    users: HashMap<String, Option<(u32, u32)>>,
+    user_names: HashMap<u32, Option<String>>,
+    groups: HashMap<String, Option<u32>>,
+    group_names: HashMap<u32, Option<String>>,
+}
+
+static CACHE: LazyLock<Mutex<Cache>> = LazyLock::new(Mutex::default);
+
+/// Get a result from the cache, or look it up and remember it unless the lookup failed.
+fn cached<K: Hash + Eq, V: Clone>(
+    map: fn(&mut Cache) -> &mut HashMap<K, Option<V>>,
+    key: K,
+    lookup: impl FnOnce(&K) -> io::Result<Option<V>>,
+) -> io::Result<Option<V>> {
+    let mut cache = CACHE.lock().unwrap_or_else(PoisonError::into_inner);
+    let map = map(&mut cache);
+    if let Some(value) = map.get(&key) {
+        return Ok(value.clone());
+    }
+    let value = lookup(&key)?;
+    map.insert(key, value.clone());
+    Ok(value)
+}
+
+/// Call a reentrant lookup function with a buffer for the strings of the entry, growing it until the entry
+/// fits, and extract what is needed before the buffer goes away.
+fn lookup_entry<E, T>(
+    mut call: impl FnMut(*mut E, *mut libc::c_char, usize, *mut *mut E) -> libc::c_int,
+    extract: impl FnOnce(&E) -> T,
+) -> io::Result<Option<T>> {
+    let mut buffer: Vec<libc::c_char> = vec![0; 1024];
+    loop {
+        let mut entry = MaybeUninit::<E>::uninit();
+        let mut result = ptr::null_mut();
+        match call(
+            entry.as_mut_ptr(),
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        ) {
+            0 if result.is_null() => return Ok(None),
+            // SAFETY: the entry was filled in, and its strings point into the buffer, which is still alive.
+            0 => return Ok(Some(extract(unsafe { &*result }))),
+            libc::ERANGE if buffer.len() < MAX_BUFFER => buffer.resize(buffer.len() * 2, 0),
+            libc::EINTR => {}
+            // Some systems report a missing entry as an error, as allowed by POSIX.
+            libc::ENOENT | libc::ESRCH | libc::EBADF | libc::EPERM => return Ok(None),
+            code => return Err(io::Error::from_raw_os_error(code)),
+        }
+    }
+}
+
+/// Copy a string of an entry.
+///
+/// # Safety
+///
+/// The pointer must point to a string terminated by a null byte.
+unsafe fn entry_name(name: *const libc::c_char) -> String {
+    String::from_utf8_lossy(CStr::from_ptr(name).to_bytes()).into_owned()
+}
+
+/// Look up a user by name, returning its uid and login group.
+fn user(name: &str) -> io::Result<Option<(u32, u32)>> {
+    cached(
+        |cache| &mut cache.users,
+        name.to_string(),
+        |name| {
+            // A name with a null byte cannot be in the database.
+            let Ok(name) = CString::new(name.as_str()) else {
+                return Ok(None);
+            };
+            lookup_entry(
+                |entry, buffer, length, result| unsafe {
+                    libc::getpwnam_r(name.as_ptr(), entry, buffer, length, result)
+                },
+                |entry: &libc::passwd| (entry.pw_uid, entry.pw_gid),
+            )
+        },
+    )
+}
+
+/// Look up the uid of a user name.
+pub fn uid_for_name(name: &str) -> io::Result<Option<u32>> {
+    Ok(user(name)?.map(|(uid, _)| uid))
+}
+
+/// Look up the name of a uid.
+pub fn name_for_uid(uid: u32) -> io::Result<Option<String>> {
+    cached(
+        |cache| &mut cache.user_names,
+        uid,
+        |&uid| {
+            lookup_entry(
+                |entry, buffer, length, result| unsafe {
+                    libc::getpwuid_r(uid, entry, buffer, length, result)
+                },
+                |entry: &libc::passwd| unsafe { entry_name(entry.pw_name) },
+            )
+        },
+    )
+}
+
+/// Look up the gid of a group name.
+pub fn gid_for_name(name: &str) -> io::Result<Option<u32>> {
+    cached(
+        |cache| &mut cache.groups,
+        name.to_string(),
+        |name| {
+            let Ok(name) = CString::new(name.as_str()) else {
+                return Ok(None);
+            };
+            lookup_entry(
+                |entry, buffer, length, result| unsafe {
+                    libc::getgrnam_r(name.as_ptr(), entry, buffer, length, result)
+                },
+                |entry: &libc::group| entry.gr_gid,
+            )
+        },
+    )
+}
+
+/// Look up the name of a gid.
+pub fn name_for_gid(gid: u32) -> io::Result<Option<String>> {
+    cached(
+        |cache| &mut cache.group_names,
+        gid,
+        |&gid| {
+            lookup_entry(
+                |entry, buffer, length, result| unsafe {
+                    libc::getgrgid_r(gid, entry, buffer, length, result)
+                },
+                |entry: &libc::group| unsafe { entry_name(entry.gr_name) },
+            )
+        },
+    )
+}
+
+/// Why an owner spec could not be parsed.
+#[derive(Debug)]
+pub enum OwnerSpecErrorKind {
+    /// The user is neither a known name nor a number.
+    InvalidUser,
+    /// The group is neither a known name nor a number.
+    InvalidGroup,
+    /// A login group was asked for with `user:`, but the user is not in the database.
+    InvalidSpec,
+    /// The user or group database could not be read.
+    Io(io::Error),
+}
+
+/// An owner spec that could not be parsed.
+///
+/// It is displayed like GNU's `invalid user: 'alice'`, quoting the whole spec.
+#[derive(Debug)]
+pub struct OwnerSpecError {
+    /// Why the spec could not be parsed.
+    pub kind: OwnerSpecErrorKind,
+    /// The spec that could not be parsed.
+    pub spec: String,
+}
+
+impl fmt::Display for OwnerSpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            OwnerSpecErrorKind::InvalidUser => write!(f, "invalid user: '{}'", self.spec),
+            OwnerSpecErrorKind::InvalidGroup => write!(f, "invalid group: '{}'", self.spec),
+            OwnerSpecErrorKind::InvalidSpec => write!(f, "invalid spec: '{}'", self.spec),
+            OwnerSpecErrorKind::Io(error) => {
+                write!(f, "'{}': {}", self.spec, io_error_message(error))
+            }
+        }
+    }
+}
+
+impl std::error::Error for OwnerSpecError {}
+
+/// Parse a numeric id, with an optional leading `+`. The all-ones id means "unchanged" to chown(2), so it is
+/// not accepted.
+fn parse_id(s: &str) -> Option<u32> {
+    let digits = s.strip_prefix('+').unwrap_or(s);
+    if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok().filter(|&id| id != u32::MAX)
+}
+
+/// Parse the owner of chown, `USER[:GROUP]`, into a uid and a gid, either of which may be missing.
+///
+/// Each part is a name or a number; a leading `+` means a number without looking up a name. `USER:` also
+/// gets the login group of the user, and `:GROUP` changes only the group.
+///
+/// For compatibility, a `.` is also accepted as the separator if there is no `:` and the whole spec is not
+/// a user name.
+pub fn parse_owner_spec(spec: &str) -> Result<(Option<u32>, Option<u32>), OwnerSpecError> {
+    let error = |kind| OwnerSpecError {
+        kind,
+        spec: spec.to_string(),
+    };
+
+    if let Some((user, group)) = spec.split_once(':') {
+        return parse_with_separator(Some(user), Some(group)).map_err(error);
+    }
+    match parse_with_separator(Some(spec), None) {
+        Err(OwnerSpecErrorKind::InvalidUser) => match spec.split_once('.') {
+            Some((user, group)) => parse_with_separator(Some(user), Some(group))
+                .map_err(|_| error(OwnerSpecErrorKind::InvalidUser)),
+            None => Err(error(OwnerSpecErrorKind::InvalidUser)),
+        },
+        result => result.map_err(error),
+    }
+}
+
+/// Parse the parts of an owner spec around a separator: `group` is `None` without a separator, and empty
+/// parts are missing.
+fn parse_with_separator(
+    user: Option<&str>,
+    group: Option<&str>,
+) -> Result<(Option<u32>, Option<u32>), OwnerSpecErrorKind> {
+    let login_group = group == Some("");
+    let user = user.filter(|user| !user.is_empty());
+    let group = group.filter(|group| !group.is_empty());
+
+    let (uid, mut gid) = match user {
+        None => (None, None),
+        Some(user) => {
+            let entry = if user.starts_with('+') {
+                None
+            } else {
+                self::user(user).map_err(OwnerSpecErrorKind::Io)?
+            };
+            match entry {
+                Some((uid, gid)) => (Some(uid), login_group.then_some(gid)),
+                None if login_group => return Err(OwnerSpecErrorKind::InvalidSpec),
+                None => (
+                    Some(parse_id(user).ok_or(OwnerSpecErrorKind::InvalidUser)?),
+                    None,
+                ),
+            }
+        }
+    };
+
+    if let Some(group) = group {
+        gid = Some(group_id(group)?);
+    }
+    Ok((uid, gid))
+}
+
+/// Parse the group of chgrp, a name or a number, into a gid. Like in owner specs, a leading `+` means a
+/// number without looking up a name.
+pub fn parse_group(spec: &str) -> Result<u32, OwnerSpecError> {
+    group_id(spec).map_err(|kind| OwnerSpecError {
+        kind,
+        spec: spec.to_string(),
+    })
+}
+
+fn group_id(group: &str) -> Result<u32, OwnerSpecErrorKind> {
+    let entry = if group.starts_with('+') {
+        None
+    } else {
+        gid_for_name(group).map_err(OwnerSpecErrorKind::Io)?
+    };
+    match entry {
+        Some(gid) => Ok(gid),
+        None => parse_id(group).ok_or(OwnerSpecErrorKind::InvalidGroup),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids() -> (u32, u32) {
+        // SAFETY: these never fail.
+        unsafe { (libc::getuid(), libc::getgid()) }
+    }
+
+    fn kind(spec: &str) -> OwnerSpecErrorKind {
+        parse_owner_spec(spec).unwrap_err().kind
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (uid, gid) = ids();
+        // The process may run as an id without a name, like in some containers.
+        if let Some(name) = name_for_uid(uid).unwrap() {
+            assert_eq!(uid_for_name(&name).unwrap(), Some(uid));
+            assert_eq!(parse_owner_spec(&name).unwrap(), (Some(uid), None));
+        }
+        if let Some(name) = name_for_gid(gid).unwrap() {
+            assert_eq!(gid_for_name(&name).unwrap(), Some(gid));
+            assert_eq!(
+                parse_owner_spec(&format!(":{name}")).unwrap(),
+                (None, Some(gid))
+            );
+        }
+        // Cached results are the same.
+        assert_eq!(name_for_uid(uid).unwrap(), name_for_uid(uid).unwrap());
+    }
+
+    #[test]
+    fn test_missing() {
+        assert_eq!(uid_for_name("no such user").unwrap(), None);
+        assert_eq!(uid_for_name("no such user").unwrap(), None);
+        assert_eq!(gid_for_name("no such group").unwrap(), None);
+        assert_eq!(uid_for_name("nul\0byte").unwrap(), None);
+        assert_eq!(name_for_uid(u32::MAX - 1).unwrap(), None);
+        assert_eq!(name_for_gid(u32::MAX - 1).unwrap(), None);
+    }
+
+    #[test]
+    fn test_numeric_spec() {
+        let (uid, gid) = ids();
+        assert_eq!(
+            parse_owner_spec(&format!("{uid}:{gid}")).unwrap(),
+            (Some(uid), Some(gid))
+        );
+        assert_eq!(
+            parse_owner_spec(&format!("+{uid}:+{gid}")).unwrap(),
+            (Some(uid), Some(gid))
+        );
+        assert_eq!(
+            parse_owner_spec(&format!("{uid}.{gid}")).unwrap(),
+            (Some(uid), Some(gid))
+        );
+        assert_eq!(
+            parse_owner_spec(&format!(":{gid}")).unwrap(),
+            (None, Some(gid))
+        );
+        assert_eq!(
+            parse_owner_spec("123456789").unwrap(),
+            (Some(123456789), None)
+        );
+        assert_eq!(parse_owner_spec(":").unwrap(), (None, None));
+        assert_eq!(parse_owner_spec("").unwrap(), (None, None));
+    }
+
+    #[test]
+    fn test_login_group() {
+        let (uid, _) = ids();
+        if let Some(name) = name_for_uid(uid).unwrap() {
+            let (_, login_group) = user(&name).unwrap().unwrap();
+            assert_eq!(
+                parse_owner_spec(&format!("{name}:")).unwrap(),
+                (Some(uid), Some(login_group))
+            );
+        }
+        assert!(matches!(
+            kind("no such user:"),
+            OwnerSpecErrorKind::InvalidSpec
+        ));
+    }
+
+    #[test]
+    fn test_invalid() {
+        for spec in [
+            "no such user",
+            "-1",
+            "1x",
+            "4294967295",
+            "4294967296",
+            "+",
+            "+root",
+            "0.x",
+        ] {
+            assert!(
+                matches!(kind(spec), OwnerSpecErrorKind::InvalidUser),
+                "{spec:?}"
+            );
+        }
+        for spec in [":no such group", "0:-1", "0:4294967295", "0:0:0"] {
+            assert!(
+                matches!(kind(spec), OwnerSpecErrorKind::InvalidGroup),
+                "{spec:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_group() {
+        let (_, gid) = ids();
+        if let Some(name) = name_for_gid(gid).unwrap() {
+            assert_eq!(parse_group(&name).unwrap(), gid);
+        }
+        assert_eq!(parse_group(&gid.to_string()).unwrap(), gid);
+        assert_eq!(parse_group("+123456789").unwrap(), 123456789);
+        for spec in ["", "no such group", "4294967295", "0:0", "+root"] {
+            assert_eq!(
+                parse_group(spec).unwrap_err().to_string(),
+                format!("invalid group: '{spec}'")
+            );
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            parse_owner_spec("0:no such group").unwrap_err().to_string(),
+            "invalid group: '0:no such group'"
+        );
+        assert_eq!(
+            parse_owner_spec("x:").unwrap_err().to_string(),
+            "invalid spec: 'x:'"
+        );
+        assert_eq!(
+            OwnerSpecError {
+                kind: OwnerSpecErrorKind::Io(io::Error::from_raw_os_error(libc::EIO)),
+                spec: "alice".to_string(),
+            }
+            .to_string(),
+            "'alice': Input/output error"
+        );
+    }
+}
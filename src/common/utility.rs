@@ -0,0 +1,109 @@
+//! The shared description of a utility: its name, its entry points, and its command-line definition.
+//!
+//! Every utility has a [`Run`] function, which takes its streams as arguments instead of using the ones of
+//! the process, so that it can be embedded and tested without spawning a process:
+//!
+//! ```
+//! use rust_coreutils::common::utility::Utility;
+//!
+//! let echo = Utility::find("echo".as_ref()).unwrap();
+//! let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+//! let code = (echo.run)(&["echo".into(), "hi".into()], &mut &b""[..], &mut stdout, &mut stderr);
+//! assert_eq!((code, &stdout[..], &stderr[..]), (0, &b"hi\n"[..], &b""[..]));
+//! ```
+
+use crate::common::os;
+use clap::{ArgMatches, Command, CommandFactory, Parser};
+use std::ffi::{OsStr, OsString};
+use std::io::{self, Read, Write};
+
+/// The entry point of a utility, which takes the command line including the program name, standard input,
+/// standard output, and standard error, and returns the exit code.
+pub type Run = fn(&[OsString], &mut dyn Read, &mut dyn Write, &mut dyn Write) -> i32;
+
+/// A utility whose command line is defined with clap, which gives access to the definition for generating
+/// shell completions and documentation.
+pub trait UtilCommand: CommandFactory {
+    /// The name of the utility.
+    const NAME: &'static str;
+
+    /// Get the definition of the command line, named after the utility.
+    fn util_command() -> Command {
+        Self::command().name(Self::NAME).bin_name(Self::NAME)
+    }
+}
+
+/// A utility that can be run by the multicall binary.
+#[derive(Clone, Copy, Debug)]
+pub struct Utility {
+    /// The name of the utility.
+    pub name: &'static str,
+    /// The entry point, which takes the command line including the program name and returns the exit code.
+    pub uumain: fn(Vec<OsString>) -> i32,
+    /// The entry point on the given streams.
+    pub run: Run,
+    /// Get the definition of the command line.
+    pub command: fn() -> Command,
+}
+
+impl Utility {
+    /// Find the utility with the given name among [`UTILITIES`](crate::UTILITIES).
+    pub fn find(name: &OsStr) -> Option<&'static Utility> {
+        crate::UTILITIES.iter().find(|utility| name == utility.name)
+    }
+}
+
+/// Run a utility on the standard streams of the process, replacing invalid UTF-8 on Windows consoles.
+pub fn uumain<I>(run: Run, args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    let args: Vec<OsString> = args.into_iter().collect();
+    run(
+        &args,
+        &mut io::stdin().lock(),
+        &mut os::stdout(),
+        &mut os::stderr(),
+    )
+}
+
+/// Parse a command line, or print the error to `stderr` (or the help and version to `stdout`) and return the
+/// exit code.
+///
+/// `--` ends the options, so that operands can start with `-`, and is not an operand itself. Utilities whose
+/// operands are files read standard input for a lone `-`, which clap leaves as an operand.
+pub fn parse_args<T, I>(args: I, stdout: &mut dyn Write, stderr: &mut dyn Write) -> Result<T, i32>
+where
+    T: Parser,
+    I: IntoIterator<Item = OsString>,
+{
+    T::try_parse_from(args).map_err(|error| report(error, stdout, stderr))
+}
+
+/// Parse a command line like [`parse_args`], and also get the matches, which tell where every argument is,
+/// for the utilities in which the order of different options matters.
+pub fn parse_matches<T, I>(
+    args: I,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> Result<(T, ArgMatches), i32>
+where
+    T: Parser,
+    I: IntoIterator<Item = OsString>,
+{
+    T::command()
+        .try_get_matches_from(args)
+        .and_then(|matches| Ok((T::from_arg_matches(&matches)?, matches)))
+        .map_err(|error| report(error, stdout, stderr))
+}
+
+fn report(error: clap::Error, stdout: &mut dyn Write, stderr: &mut dyn Write) -> i32 {
+    let output: &mut dyn Write = if error.use_stderr() {
+        &mut *stderr
+    } else {
+        &mut *stdout
+    };
+    let _ = write!(output, "{}", error.render());
+    let _ = output.flush();
+    error.exit_code()
+}
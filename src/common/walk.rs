@@ -0,0 +1,499 @@
+//! Depth-first traversal of directory trees, for the recursive modes of du, rm, cp, chmod, and chown.
+//!
+//! The entries below each directory are visited in the order of their names. An error on one entry is
+//! reported and the traversal continues with the next one:
+//!
+//! ```
+//! use rust_coreutils::common::walk::{Walk, WalkOptions};
+//!
+//! let directory = tempfile::tempdir().unwrap();
+//! std::fs::create_dir(directory.path().join("sub")).unwrap();
+//! std::fs::write(directory.path().join("sub/file"), "").unwrap();
+//!
+//! let options = WalkOptions {
+//!     post_order: true,
+//!     ..WalkOptions::default()
+//! };
+//! let depths: Vec<usize> = Walk::new(vec![directory.path().into()], options)
+//!     .map(|entry| entry.unwrap().depth)
+//!     .collect();
+//! assert_eq!(depths, [2, 1, 0]);
+//! ```
+
+use std::ffi::OsString;
+use std::fs::{self, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Which symbolic links are followed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Follow {
+    /// Never follow symbolic links (`-P`).
+    #[default]
+    Never,
+    /// Follow the symbolic links given as roots only (`-H`).
+    Roots,
+    /// Follow all symbolic links (`-L`).
+    Always,
+}
+
+/// The options of a traversal.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WalkOptions {
+    /// Which symbolic links are followed.
+    pub follow: Follow,
+    /// Do not descend into directories on another file system than their root (`-x`). The directories
+    /// themselves are still visited.
+    pub one_file_system: bool,
+    /// Visit directories after their contents instead of before.
+    pub post_order: bool,
+}
+
+/// A visited file.
+#[derive(Clone, Debug)]
+pub struct Entry {
+    /// The path of the file, which is the root joined with the names of the entries leading to it.
+    pub path: PathBuf,
+    /// The metadata of the file, or of its target if the symbolic link was followed.
+    pub metadata: Metadata,
+    /// The number of directories between the root and the file, which is 0 for the root itself.
+    pub depth: usize,
+}
+
+impl Entry {
+    /// Check whether the file is a directory, or a symbolic link to one which was followed.
+    pub fn is_dir(&self) -> bool {
+        self.metadata.is_dir()
+    }
+
+    /// Get the device of the file system containing the file.
+    #[cfg(unix)]
+    pub fn dev(&self) -> u64 {
+        std::os::unix::fs::MetadataExt::dev(&self.metadata)
+    }
+
+    /// Get the inode of the file.
+    #[cfg(unix)]
+    pub fn ino(&self) -> u64 {
+        std::os::unix::fs::MetadataExt::ino(&self.metadata)
+    }
+}
+
+/// An error encountered during a traversal, which does not stop it.
+#[derive(Debug)]
+pub enum WalkError {
+    /// The metadata or the contents of a file could not be read.
+    Io {
+        /// The file that could not be read.
+        path: PathBuf,
+        /// The reason why it could not be read.
+        error: io::Error,
+    },
+    /// A directory is the same as one of the directories containing it, which happens when following
+    /// symbolic links. It is not descended into.
+    Loop {
+        /// The path of the directory.
+        path: PathBuf,
+        /// The path of the directory containing it which it is the same as.
+        ancestor: PathBuf,
+    },
+}
+
+impl WalkError {
+    /// Get the path of the file the error is about.
+    pub fn path(&self) -> &Path {
+        match self {
+            WalkError::Io { path, .. } | WalkError::Loop { path, .. } => path,
+        }
+    }
+}
+
+/// A directory being traversed.
+struct Frame {
+    entry: Entry,
+    /// The names of the remaining entries, in reverse order, once the directory was read.
+    names: Option<Vec<OsString>>,
+    /// Whether none of the entries has been visited yet.
+    fresh: bool,
+}
+
+/// Iterates over the files below the roots, depth-first.
+///
+/// The contents of a directory are read at once when the traversal descends into it, so that the number
+/// of open file descriptors does not depend on the depth of the tree.
+pub struct Walk {
+    options: WalkOptions,
+    roots: std::vec::IntoIter<PathBuf>,
+    stack: Vec<Frame>,
+    /// The entries and errors to return before continuing the traversal.
+    queue: Vec<Result<Entry, WalkError>>,
+    /// The device of the current root.
+    root_dev: Option<u64>,
+}
+
+impl Walk {
+    /// Traverse the trees below each of the roots, in order.
+    pub fn new(roots: Vec<PathBuf>, options: WalkOptions) -> Self {
+        Walk {
+            options,
+            roots: roots.into_iter(),
+            stack: Vec::new(),
+            queue: Vec::new(),
+            root_dev: None,
+        }
+    }
+
+    /// Do not descend into the directory that was just returned, in pre-order mode.
+    ///
+    /// This does nothing if the last entry was not a directory which was going to be descended into.
+    pub fn skip_current_dir(&mut self) {
+        if !self.options.post_order
+            && self.queue.is_empty()
+            && self.stack.last().is_some_and(|frame| frame.fresh)
+        {
+            self.stack.pop();
+        }
+    }
+
+    /// Get the metadata of a file, following symbolic links as requested.
+    fn metadata(&self, path: &Path, depth: usize) -> io::Result<Metadata> {
+        let follow = match self.options.follow {
+            Follow::Never => false,
+            Follow::Roots => depth == 0,
+            Follow::Always => true,
+        };
+        if !follow {
+            return fs::symlink_metadata(path);
+        }
+
+        // A dangling symbolic link is visited as it is.
+        fs::metadata(path).or_else(|error| match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => Ok(metadata),
+            _ => Err(error),
+        })
+    }
+
+    /// Queue the entry for a path, and start traversing it if it is a directory.
+    fn visit(&mut self, path: PathBuf, depth: usize) {
+        let metadata = match self.metadata(&path, depth) {
+            Ok(metadata) => metadata,
+            Err(error) => {
+                self.queue.push(Err(WalkError::Io { path, error }));
+                return;
+            }
+        };
+        let entry = Entry {
+            path,
+            metadata,
+            depth,
+        };
+        if !entry.is_dir() {
+            self.queue.push(Ok(entry));
+            return;
+        }
+
+        let id = file_id(&entry.metadata);
+        if depth == 0 {
+            self.root_dev = id.map(|(dev, _)| dev);
+        }
+        if let Some(ancestor) = self
+            .stack
+            .iter()
+            .find(|frame| id.is_some() && file_id(&frame.entry.metadata) == id)
+        {
+            self.queue.push(Err(WalkError::Loop {
+                path: entry.path,
+                ancestor: ancestor.entry.path.clone(),
+            }));
+            return;
+        }
+        if self.options.one_file_system && id.map(|(dev, _)| dev) != self.root_dev {
+            self.queue.push(Ok(entry));
+            return;
+        }
+
+        // The directory is only read once it is descended into, so that it can be skipped, or made readable
+        // in pre-order mode.
+        if !self.options.post_order {
+            self.queue.push(Ok(entry.clone()));
+        }
+        self.stack.push(Frame {
+            entry,
+            names: None,
+            fresh: true,
+        });
+    }
+}
+
+impl Iterator for Walk {
+    type Item = Result<Entry, WalkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.queue.pop() {
+                return Some(item);
+            }
+
+            if let Some(frame) = self.stack.last_mut() {
+                frame.fresh = false;
+                let names = match &mut frame.names {
+                    Some(names) => names,
+                    None => {
+                        let names = frame.names.insert(Vec::new());
+                        match read_names(&frame.entry.path) {
+                            Ok(read) => *names = read,
+                            Err(error) => {
+                                return Some(Err(WalkError::Io {
+                                    path: frame.entry.path.clone(),
+                                    error,
+                                }));
+                            }
+                        }
+                        names
+                    }
+                };
+                match names.pop() {
+                    Some(name) => {
+                        let path = frame.entry.path.join(name);
+                        let depth = frame.entry.depth + 1;
+                        self.visit(path, depth);
+                    }
+                    None => {
+                        let frame = self.stack.pop().unwrap();
+                        if self.options.post_order {
+                            return Some(Ok(frame.entry));
+                        }
+                    }
+                }
+                continue;
+            }
+
+            let root = self.roots.next()?;
+            self.visit(root, 0);
+        }
+    }
+}
+
+/// Read the names of the entries of a directory, in reverse order, so that they can be popped in order.
+fn read_names(path: &Path) -> io::Result<Vec<OsString>> {
+    let mut names = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.file_name()))
+        .collect::<io::Result<Vec<_>>>()?;
+    names.sort_unstable_by(|a, b| b.cmp(a));
+    Ok(names)
+}
+
+/// Get the device and inode identifying a file, where they are available.
+#[cfg(unix)]
+pub(crate) fn file_id(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+/// Get the device and inode identifying a file, where they are available.
+#[cfg(not(unix))]
+pub(crate) fn file_id(_metadata: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::{symlink, MetadataExt, PermissionsExt};
+
+    /// Build a tree with a file, a symbolic link to the root, and a dangling symbolic link.
+    fn tree() -> tempfile::TempDir {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        fs::write(root.join("a/b/file"), "data").unwrap();
+        fs::write(root.join("c"), "").unwrap();
+        symlink(root, root.join("a/loop")).unwrap();
+        symlink("missing", root.join("dangling")).unwrap();
+        directory
+    }
+
+    /// Walk the tree, returning the paths relative to its root, with errors as `!path`.
+    fn walk(root: &Path, options: WalkOptions) -> Vec<String> {
+        Walk::new(vec![root.to_path_buf()], options)
+            .map(|item| {
+                let (prefix, path) = match &item {
+                    Ok(entry) => ("", entry.path.as_path()),
+                    Err(error) => ("!", error.path()),
+                };
+                let path = path.strip_prefix(root).unwrap().to_string_lossy();
+                format!("{prefix}{path}")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_pre_order() {
+        let directory = tree();
+        assert_eq!(
+            walk(directory.path(), WalkOptions::default()),
+            ["", "a", "a/b", "a/b/file", "a/loop", "c", "dangling"]
+        );
+
+        let depths: Vec<usize> = Walk::new(vec![directory.path().into()], WalkOptions::default())
+            .map(|entry| entry.unwrap().depth)
+            .collect();
+        assert_eq!(depths, [0, 1, 2, 3, 2, 1, 1]);
+    }
+
+    #[test]
+    fn test_post_order() {
+        let directory = tree();
+        let options = WalkOptions {
+            post_order: true,
+            ..WalkOptions::default()
+        };
+        assert_eq!(
+            walk(directory.path(), options),
+            ["a/b/file", "a/b", "a/loop", "a", "c", "dangling", ""]
+        );
+    }
+
+    #[test]
+    fn test_follow_symlink_loop() {
+        let directory = tree();
+        let options = WalkOptions {
+            follow: Follow::Always,
+            ..WalkOptions::default()
+        };
+        assert_eq!(
+            walk(directory.path(), options),
+            ["", "a", "a/b", "a/b/file", "!a/loop", "c", "dangling"]
+        );
+
+        let error = Walk::new(vec![directory.path().join("a/loop")], options)
+            .find_map(Result::err)
+            .unwrap();
+        match error {
+            WalkError::Loop { path, ancestor } => {
+                assert_eq!(path, directory.path().join("a/loop/a/loop"));
+                assert_eq!(ancestor, directory.path().join("a/loop"));
+            }
+            error => panic!("unexpected error: {error:?}"),
+        }
+
+        // The dangling link is visited as it is.
+        let entry = Walk::new(vec![directory.path().join("dangling")], options)
+            .next()
+            .unwrap()
+            .unwrap();
+        assert!(entry.metadata.file_type().is_symlink());
+    }
+
+    #[test]
+    fn test_follow_roots() {
+        let directory = tree();
+        let root = directory.path().join("a/loop");
+
+        let options = WalkOptions {
+            follow: Follow::Roots,
+            ..WalkOptions::default()
+        };
+        let paths = walk(&root, options);
+        assert_eq!(paths[..3], ["", "a", "a/b"]);
+        assert!(paths.contains(&"a/loop".to_string()));
+        assert!(!paths.contains(&"a/loop/a".to_string()));
+
+        assert_eq!(walk(&root, WalkOptions::default()), [""]);
+    }
+
+    #[test]
+    fn test_errors_do_not_stop_the_walk() {
+        let directory = tree();
+        let missing = directory.path().join("missing");
+        let items: Vec<_> = Walk::new(
+            vec![missing.clone(), directory.path().join("c")],
+            WalkOptions::default(),
+        )
+        .collect();
+        assert!(matches!(&items[0], Err(WalkError::Io { path, error })
+            if *path == missing && error.kind() == io::ErrorKind::NotFound));
+        assert_eq!(items[1].as_ref().unwrap().path, directory.path().join("c"));
+        assert_eq!(items.len(), 2);
+    }
+
+    #[test]
+    fn test_unreadable_directory() {
+        let directory = tree();
+        let unreadable = directory.path().join("a/b");
+        fs::set_permissions(&unreadable, fs::Permissions::from_mode(0o000)).unwrap();
+        let readable = fs::read_dir(&unreadable).is_ok();
+
+        for post_order in [false, true] {
+            let options = WalkOptions {
+                post_order,
+                ..WalkOptions::default()
+            };
+            let paths = walk(directory.path(), options);
+            // The superuser can read the directory anyway.
+            if readable {
+                assert!(paths.contains(&"a/b/file".to_string()));
+            } else if post_order {
+                assert_eq!(paths, ["!a/b", "a/b", "a/loop", "a", "c", "dangling", ""]);
+            } else {
+                assert_eq!(paths, ["", "a", "a/b", "!a/b", "a/loop", "c", "dangling"]);
+            }
+        }
+
+        fs::set_permissions(&unreadable, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    fn test_read_on_descent() {
+        // In pre-order mode, a directory is only read after it is returned, like chmod -R needs.
+        let directory = tree();
+        let unreadable = directory.path().join("a/b");
+        fs::set_permissions(&unreadable, fs::Permissions::from_mode(0o000)).unwrap();
+        let mut depths = Vec::new();
+        for item in Walk::new(vec![directory.path().into()], WalkOptions::default()) {
+            let entry = item.unwrap();
+            if entry.path == unreadable {
+                fs::set_permissions(&unreadable, fs::Permissions::from_mode(0o755)).unwrap();
+            }
+            depths.push(entry.depth);
+        }
+        assert_eq!(depths, [0, 1, 2, 3, 2, 1, 1]);
+    }
+
+    #[test]
+    fn test_one_file_system() {
+        let directory = tree();
+        let options = WalkOptions {
+            one_file_system: true,
+            ..WalkOptions::default()
+        };
+        let entries: Vec<Entry> = Walk::new(vec![directory.path().into()], options)
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(entries.len(), 7);
+
+        let dev = entries[0].dev();
+        assert!(entries.iter().all(|entry| entry.dev() == dev));
+        assert_eq!(
+            entries[0].ino(),
+            fs::metadata(directory.path()).unwrap().ino()
+        );
+    }
+
+    #[test]
+    fn test_skip_current_dir() {
+        let directory = tree();
+        let mut walk = Walk::new(vec![directory.path().into()], WalkOptions::default());
+        let mut paths = Vec::new();
+        while let Some(entry) = walk.next() {
+            let entry = entry.unwrap();
+            if entry.path.ends_with("a") {
+                walk.skip_current_dir();
+            }
+            paths.push(entry.path);
+        }
+        assert_eq!(paths.len(), 4);
+        assert!(paths[1].ends_with("a"));
+        assert!(paths[2].ends_with("c"));
+    }
+}
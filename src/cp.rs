@@ -0,0 +1,1910 @@
+//! Copy files and directories.
+//!
+//! A single file is copied with [`copy_file`], which refuses to copy a file onto itself, and a directory
+//! tree with a [`Copier`], which also keeps the attributes of the files as requested:
+//!
+//! ```
+//! use rust_coreutils::common::error::Reporter;
+//! use rust_coreutils::cp::{copy_file, Copier, CpOptions, Preserve};
+//!
+//! let directory = tempfile::tempdir().unwrap();
+//! let source = directory.path().join("source");
+//! let destination = directory.path().join("destination");
+//! std::fs::write(&source, "data").unwrap();
+//!
+//! copy_file(&source, &destination).unwrap();
+//! assert_eq!(std::fs::read(&destination).unwrap(), b"data");
+//! assert!(copy_file(&source, &source).is_err());
+//!
+//! let tree = directory.path().join("tree");
+//! std::fs::create_dir(&tree).unwrap();
+//! copy_file(&source, &tree.join("file")).unwrap();
+//! let options = CpOptions {
+//!     recursive: true,
+//!     preserve: Preserve::ALL,
+//!     verbose: true,
+//!     ..CpOptions::default()
+//! };
+//! let mut reporter = Reporter::new("cp", Vec::new());
+//! let (mut stdin, mut stdout) = (std::io::empty(), Vec::new());
+//! let mut copier = Copier::new(options, &mut reporter, &mut stdin, &mut stdout);
+//! copier.copy(&tree, &directory.path().join("copy"));
+//! assert!(!reporter.failed());
+//! assert_eq!(std::fs::read(directory.path().join("copy/file")).unwrap(), b"data");
+//! assert_eq!(stdout.split(|&byte| byte == b'\n').count(), 3);
+//! ```
+
+use crate::common::backup::{self, BackupArgs, BackupMode, BackupOptions};
+use crate::common::error::{io_error_message, Reporter, UError, UResult, EXIT_FAILURE};
+use crate::common::input::BUFFER_SIZE;
+use crate::common::os::os_str_bytes;
+#[cfg(unix)]
+use crate::common::paths::c_path;
+use crate::common::paths::{base_name, is_inside, same_file};
+use crate::common::prompt::Prompter;
+use crate::common::utility::{self, parse_matches, UtilCommand, Utility};
+use crate::common::walk::{file_id, Entry, Follow, Walk, WalkError, WalkOptions};
+use crate::prompt_yes;
+use clap::parser::ValueSource;
+use clap::{ArgAction, ArgMatches, Parser};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fmt::Display;
+use std::fs::{self, DirBuilder, File, Metadata, OpenOptions};
+use std::io::{self, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "cp",
+    author,
+    version,
+    about = "Copy SOURCE to DEST, or the SOURCEs into the directory DEST.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Same as -dR --preserve=all.
+    #[arg(short, long, default_value_t = false)]
+    archive: bool,
+
+    /// Same as --no-dereference --preserve=links.
+    #[arg(short = 'd', default_value_t = false)]
+    no_dereference_preserve_links: bool,
+
+    /// Remove an existing destination file which cannot be opened, and try again.
+    #[arg(short, long, default_value_t = false)]
+    force: bool,
+
+    /// Ask before overwriting a file.
+    #[arg(short, long, default_value_t = false, overrides_with = "no_clobber")]
+    interactive: bool,
+
+    /// Never overwrite a file.
+    #[arg(short, long, default_value_t = false, overrides_with = "interactive")]
+    no_clobber: bool,
+
+    /// Follow all symbolic links in the SOURCEs.
+    #[arg(short = 'L', long, default_value_t = false)]
+    dereference: bool,
+
+    /// Copy symbolic links as links rather than the files they point to, which is the default with -R.
+    #[arg(short = 'P', long, default_value_t = false)]
+    no_dereference: bool,
+
+    /// Follow the symbolic links given as SOURCEs only.
+    #[arg(short = 'H', default_value_t = false)]
+    dereference_command_line: bool,
+
+    /// Same as --preserve=mode,ownership,timestamps.
+    #[arg(short = 'p', default_value_t = false)]
+    preserve_default: bool,
+
+    /// Preserve the attributes in the comma-separated ATTR_LIST: mode, ownership, timestamps, links, or
+    /// all. Without ATTR_LIST, preserve the mode, ownership, and timestamps.
+    #[arg(
+        long,
+        value_name = "ATTR_LIST",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "mode,ownership,timestamps",
+        action = ArgAction::Append
+    )]
+    preserve: Vec<String>,
+
+    /// Copy directories and their contents recursively.
+    #[arg(short = 'R', visible_short_alias = 'r', long, default_value_t = false)]
+    recursive: bool,
+
+    /// Print what is being done.
+    #[arg(short, long, default_value_t = false)]
+    verbose: bool,
+
+    #[command(flatten)]
+    backup: BackupArgs,
+
+    /// The files to copy, followed by the destination.
+    #[arg(value_name = "SOURCE... DEST")]
+    files: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "cp";
+}
+
+/// The description of cp for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run cp with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run cp with the given command line, including the program name, on the given streams, and return the
+/// exit code.
+pub fn run(
+    args: &[OsString],
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let (args, matches): (Args, _) = match parse_matches(args.iter().cloned(), stdout, stderr) {
+        Ok(parsed) => parsed,
+        Err(code) => return code,
+    };
+
+    let mut reporter = Reporter::new("cp", stderr);
+    let (options, backup) = match options(&args, &matches) {
+        Ok(options) => options,
+        Err(error) => return reporter.finish(Err(error)),
+    };
+    let result = cp(
+        &args.files,
+        &mut Copier::new(options, &mut reporter, stdin, stdout).backups(backup),
+    );
+    reporter.finish(result)
+}
+
+/// The options which select which symbolic links are followed, where the last one wins.
+const FOLLOW_OPTIONS: [(&str, Follow); 5] = [
+    ("dereference", Follow::Always),
+    ("no_dereference", Follow::Never),
+    ("dereference_command_line", Follow::Roots),
+    ("archive", Follow::Never),
+    ("no_dereference_preserve_links", Follow::Never),
+];
+
+fn options(args: &Args, matches: &ArgMatches) -> UResult<(CpOptions, BackupOptions)> {
+    if args.backup.given() && args.no_clobber {
+        return Err(UError::usage(
+            EXIT_FAILURE,
+            "options --backup and --no-clobber are mutually exclusive",
+        ));
+    }
+
+    let recursive = args.recursive || args.archive;
+    let follow = FOLLOW_OPTIONS
+        .iter()
+        .filter_map(|&(id, follow)| Some((last_index(matches, id)?, follow)))
+        .max_by_key(|&(index, _)| index)
+        .map(|(_, follow)| follow)
+        .unwrap_or(match recursive {
+            true => Follow::Never,
+            false => Follow::Always,
+        });
+
+    let mut preserve = Preserve::default();
+    if args.archive {
+        preserve = Preserve::ALL;
+    }
+    if args.no_dereference_preserve_links {
+        preserve.links = true;
+    }
+    if args.preserve_default {
+        preserve = preserve.union(Preserve::BASIC);
+    }
+    for list in &args.preserve {
+        preserve = preserve.union(Preserve::parse(list)?);
+    }
+
+    let overwrite = match (args.interactive, args.no_clobber) {
+        (true, _) => Overwrite::Prompt,
+        (_, true) => Overwrite::Never,
+        _ => Overwrite::Replace,
+    };
+    let options = CpOptions {
+        recursive,
+        follow,
+        preserve,
+        overwrite,
+        force: args.force,
+        verbose: args.verbose,
+    };
+    Ok((options, args.backup.options()?))
+}
+
+fn last_index(matches: &ArgMatches, id: &str) -> Option<usize> {
+    // The flags which are not given have a default value, at the end.
+    if matches.value_source(id) != Some(ValueSource::CommandLine) {
+        return None;
+    }
+    matches.indices_of(id)?.max()
+}
+
+/// The options of a copy.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CpOptions {
+    /// Copy directories with their contents, recursively (`-R`), rather than refusing to copy them. Special
+    /// files, like fifos, are created rather than read.
+    pub recursive: bool,
+    /// Which symbolic links are followed. The others are copied as links.
+    pub follow: Follow,
+    /// The attributes of the sources which the copies get.
+    pub preserve: Preserve,
+    /// What to do with the files which exist at the destination.
+    pub overwrite: Overwrite,
+    /// Remove an existing destination file which cannot be opened for writing, and try again (`-f`).
+    pub force: bool,
+    /// Print each copy, like `'a' -> 'b'` (`-v`).
+    pub verbose: bool,
+}
+
+/// What to do with a file which exists where a file which is not a directory is copied.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Overwrite {
+    /// Replace it.
+    #[default]
+    Replace,
+    /// Ask whether to replace it (`-i`).
+    Prompt,
+    /// Keep it, and skip the copy (`-n`).
+    Never,
+}
+
+/// The attributes of the sources which the copies get, rather than those of new files.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Preserve {
+    /// The permission bits, including the setuid, setgid, and sticky bits.
+    pub mode: bool,
+    /// The owner and the group, which are kept where permitted.
+    pub ownership: bool,
+    /// The access and modification times.
+    pub timestamps: bool,
+    /// The hard links between the sources, so that their copies are hard links to each other too.
+    pub links: bool,
+}
+
+/// The accepted values in `ATTR_LIST`, which can be abbreviated as long as they are unambiguous.
+const ATTRIBUTES: &[(&str, Preserve)] = &[
+    (
+        "mode",
+        Preserve {
+            mode: true,
+            ..Preserve::NONE
+        },
+    ),
+    (
+        "timestamps",
+        Preserve {
+            timestamps: true,
+            ..Preserve::NONE
+        },
+    ),
+    (
+        "ownership",
+        Preserve {
+            ownership: true,
+            ..Preserve::NONE
+        },
+    ),
+    (
+        "links",
+        Preserve {
+            links: true,
+            ..Preserve::NONE
+        },
+    ),
+    ("all", Preserve::ALL),
+];
+
+impl Preserve {
+    /// No attributes, which is the default.
+    pub const NONE: Preserve = Preserve {
+        mode: false,
+        ownership: false,
+        timestamps: false,
+        links: false,
+    };
+
+    /// The attributes of `-p`, and of `--preserve` without a list.
+    pub const BASIC: Preserve = Preserve {
+        mode: true,
+        ownership: true,
+        timestamps: true,
+        links: false,
+    };
+
+    /// All the attributes, for `-a`.
+    pub const ALL: Preserve = Preserve {
+        mode: true,
+        ownership: true,
+        timestamps: true,
+        links: true,
+    };
+
+    /// Parse a comma-separated `ATTR_LIST`.
+    pub fn parse(list: &str) -> UResult<Self> {
+        list.split(',').try_fold(Preserve::NONE, |preserve, name| {
+            Ok(preserve.union(Preserve::parse_attribute(name)?))
+        })
+    }
+
+    fn parse_attribute(name: &str) -> UResult<Self> {
+        if let Some(&(_, preserve)) = ATTRIBUTES.iter().find(|(attribute, _)| *attribute == name) {
+            return Ok(preserve);
+        }
+
+        let mut matches = ATTRIBUTES
+            .iter()
+            .filter(|(attribute, _)| !name.is_empty() && attribute.starts_with(name))
+            .map(|&(_, preserve)| preserve);
+        let problem = match matches.next() {
+            Some(preserve) if matches.all(|other| other == preserve) => return Ok(preserve),
+            Some(_) => "ambiguous",
+            // An empty name is a prefix of every attribute.
+            None if name.is_empty() => "ambiguous",
+            None => "invalid",
+        };
+        Err(UError::new(
+            EXIT_FAILURE,
+            format!(
+                "{problem} argument '{name}' for '--preserve'\n\
+                 Valid arguments are:\n  \
+                 - 'mode'\n  \
+                 - 'timestamps'\n  \
+                 - 'ownership'\n  \
+                 - 'links'\n  \
+                 - 'all'"
+            ),
+        ))
+    }
+
+    /// Get the attributes which are preserved by either.
+    pub fn union(self, other: Preserve) -> Preserve {
+        Preserve {
+            mode: self.mode || other.mode,
+            ownership: self.ownership || other.ownership,
+            timestamps: self.timestamps || other.timestamps,
+            links: self.links || other.links,
+        }
+    }
+}
+
+/// Copy the operands but the last one to the last one: into it if it is a directory, or onto it if there
+/// is a single source.
+///
+/// A source which fails to be copied is reported, and the copy continues with the next one.
+pub fn cp<W: Write>(operands: &[OsString], copier: &mut Copier<W>) -> UResult<()> {
+    let (sources, destination) = match operands {
+        [] => return Err(UError::usage(EXIT_FAILURE, "missing file operand")),
+        [source] => {
+            let message = format!(
+                "missing destination file operand after '{}'",
+                source.to_string_lossy()
+            );
+            return Err(UError::usage(EXIT_FAILURE, message));
+        }
+        [sources @ .., destination] => (sources, Path::new(destination)),
+    };
+
+    let into_directory = match fs::metadata(destination) {
+        Ok(metadata) => metadata.is_dir(),
+        Err(error) if sources.len() > 1 => {
+            return Err(failure(
+                format_args!("target '{}'", destination.display()),
+                &error,
+            ))
+        }
+        Err(_) => false,
+    };
+    if sources.len() > 1 && !into_directory {
+        let message = format!("target '{}': Not a directory", destination.display());
+        return Err(UError::new(EXIT_FAILURE, message));
+    }
+
+    for source in sources {
+        let source = Path::new(source);
+        match into_directory {
+            true => copier.copy(source, &destination.join(base_name(source))),
+            false => copier.copy(source, destination),
+        }
+    }
+    Ok(())
+}
+
+/// Copy a file to the given path, replacing the contents of the file there if there is one.
+///
+/// A new file gets the permission bits of the source, minus the umask, while an existing one keeps its
+/// own. Directories are not copied.
+pub fn copy_file(source: &Path, destination: &Path) -> UResult<()> {
+    let metadata = fs::metadata(source)
+        .map_err(|error| failure(format_args!("cannot stat '{}'", source.display()), &error))?;
+    if metadata.is_dir() {
+        return Err(omitting_directory(source));
+    }
+    check_destination(source, &metadata, destination)?;
+    copy_contents(source, &metadata, destination).map_err(UError::from)
+}
+
+/// A copied directory which gets its final attributes once its contents are copied, so that their copies
+/// do not change its times, and because its permissions may not allow its owner to write the contents.
+struct PendingDirectory {
+    /// The depth of the source directory in the walk.
+    depth: usize,
+    /// The metadata of the source directory.
+    metadata: Metadata,
+    /// The path of the copy.
+    path: PathBuf,
+    /// The permission bits of the copy, if they are not preserved and have to be set.
+    #[cfg_attr(not(unix), allow(dead_code))]
+    mode: Option<u32>,
+}
+
+/// Copies files, asking questions and printing messages on the given streams.
+pub struct Copier<'a, W: Write> {
+    options: CpOptions,
+    reporter: &'a mut Reporter<W>,
+    /// Where the answers to the questions of `-i` are read.
+    answers: &'a mut dyn Read,
+    /// Where `-v` prints the copies.
+    stdout: &'a mut dyn Write,
+    /// The copies of the sources with several hard links, by device and inode, with `--preserve=links`.
+    copies: HashMap<(u64, u64), PathBuf>,
+    /// Whether the copies are printed like mv prints the files it moves by copying them.
+    moving: bool,
+    /// How to back up the files which are replaced.
+    backup: BackupOptions,
+}
+
+impl<'a, W: Write> Copier<'a, W> {
+    /// Create a copier with the given options, reporting the failures to `reporter`, reading the answers to
+    /// the questions from `answers`, and printing the copies to `stdout`.
+    pub fn new(
+        options: CpOptions,
+        reporter: &'a mut Reporter<W>,
+        answers: &'a mut dyn Read,
+        stdout: &'a mut dyn Write,
+    ) -> Self {
+        Copier {
+            options,
+            reporter,
+            answers,
+            stdout,
+            copies: HashMap::new(),
+            moving: false,
+            backup: BackupOptions::default(),
+        }
+    }
+
+    /// Print the copies like mv, which copies the files it cannot rename: `copied 'a' -> 'b'`, and
+    /// `created directory 'b'` for the directories.
+    pub fn moving(mut self) -> Self {
+        self.moving = true;
+        self
+    }
+
+    /// Back up the files which are replaced, rather than removing or overwriting them.
+    pub fn backups(mut self, backup: BackupOptions) -> Self {
+        self.backup = backup;
+        self
+    }
+
+    /// Copy a file, or a directory and its contents with `options.recursive`, to the given path.
+    ///
+    /// A directory is merged into an existing one at the destination. The failures are reported, and the
+    /// copy continues with the next file.
+    pub fn copy(&mut self, source: &Path, destination: &Path) {
+        if !self.options.recursive {
+            let metadata = match self.options.follow {
+                Follow::Never => fs::symlink_metadata(source),
+                Follow::Roots | Follow::Always => fs::metadata(source),
+            };
+            let result = metadata
+                .map_err(|error| {
+                    failure(format_args!("cannot stat '{}'", source.display()), &error)
+                })
+                .and_then(|metadata| match metadata.is_dir() {
+                    true => Err(omitting_directory(source)),
+                    false => self.copy_entry(source, &metadata, destination),
+                });
+            if let Err(error) = result {
+                self.reporter.error(format_args!("{error}"));
+            }
+            return;
+        }
+
+        let walk_options = WalkOptions {
+            follow: self.options.follow,
+            ..WalkOptions::default()
+        };
+        let mut walk = Walk::new(vec![source.to_path_buf()], walk_options);
+        // The copies of the directories containing the current entry, by depth.
+        let mut targets: Vec<PathBuf> = Vec::new();
+        let mut pending: Vec<PendingDirectory> = Vec::new();
+        while let Some(entry) = walk.next() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(error) => {
+                    self.reporter
+                        .error(format_args!("{}", walk_failure(&error)));
+                    continue;
+                }
+            };
+            self.finish_directories(&mut pending, entry.depth);
+            targets.truncate(entry.depth);
+            let target = match targets.last() {
+                Some(parent) => parent.join(base_name(&entry.path)),
+                None => destination.to_path_buf(),
+            };
+
+            let follows = match self.options.follow {
+                Follow::Never => false,
+                Follow::Roots => entry.depth == 0,
+                Follow::Always => true,
+            };
+            let result = if follows && entry.metadata.file_type().is_symlink() {
+                // The walk returns the links it fails to follow as they are.
+                let error = fs::metadata(&entry.path)
+                    .err()
+                    .unwrap_or(ErrorKind::NotFound.into());
+                Err(failure(
+                    format_args!("cannot stat '{}'", entry.path.display()),
+                    &error,
+                ))
+            } else if entry.is_dir() {
+                self.copy_directory(&entry, &target).map(|directory| {
+                    pending.extend(directory);
+                    targets.push(target);
+                })
+            } else {
+                self.copy_entry(&entry.path, &entry.metadata, &target)
+            };
+            if let Err(error) = result {
+                self.reporter.error(format_args!("{error}"));
+                walk.skip_current_dir();
+            }
+        }
+        self.finish_directories(&mut pending, 0);
+    }
+
+    /// Copy a file which is not a directory, given its metadata, or that of its target if it is a symbolic
+    /// link to follow.
+    fn copy_entry(
+        &mut self,
+        source: &Path,
+        metadata: &Metadata,
+        destination: &Path,
+    ) -> UResult<()> {
+        let file_type = metadata.file_type();
+        let id = file_id(metadata).filter(|_| self.options.preserve.links && has_links(metadata));
+        if let Some(first) = id.and_then(|id| self.copies.get(&id)).cloned() {
+            return self.copy_hard_link(source, metadata, &first, destination);
+        }
+
+        if file_type.is_symlink() {
+            self.copy_symlink(source, metadata, destination)?;
+        } else if cfg!(unix) && self.options.recursive && !file_type.is_file() {
+            self.copy_special(source, metadata, destination)?;
+        } else {
+            self.copy_regular(source, metadata, destination)?;
+        }
+        if let Some(id) = id {
+            self.copies.insert(id, destination.to_path_buf());
+        }
+        Ok(())
+    }
+
+    /// Copy the contents of a file, unless the destination is kept.
+    fn copy_regular(
+        &mut self,
+        source: &Path,
+        metadata: &Metadata,
+        destination: &Path,
+    ) -> UResult<()> {
+        if self.options.overwrite == Overwrite::Never && fs::metadata(destination).is_ok() {
+            return Ok(());
+        }
+        check_destination(source, metadata, destination)?;
+        let backup = match fs::symlink_metadata(destination) {
+            Ok(_) if !self.confirm_overwrite(destination) => return Ok(()),
+            Ok(_) => self.back_up(source, destination)?,
+            Err(_) => None,
+        };
+        self.print_copy(source, destination, backup.as_deref());
+
+        match copy_contents(source, metadata, destination) {
+            Err(CopyError::Open(_)) if self.options.force => {
+                fs::remove_file(destination).map_err(|error| {
+                    failure(
+                        format_args!("cannot remove '{}'", destination.display()),
+                        &error,
+                    )
+                })?;
+                if self.options.verbose {
+                    let _ = writeln!(self.stdout, "removed '{}'", destination.display());
+                }
+                copy_contents(source, metadata, destination).map_err(UError::from)?;
+            }
+            result => result.map_err(UError::from)?,
+        }
+        self.preserve(metadata, destination, true);
+        Ok(())
+    }
+
+    /// Copy a symbolic link as a link with the same target.
+    fn copy_symlink(
+        &mut self,
+        source: &Path,
+        metadata: &Metadata,
+        destination: &Path,
+    ) -> UResult<()> {
+        let target = fs::read_link(source).map_err(|error| {
+            failure(
+                format_args!("cannot read symbolic link '{}'", source.display()),
+                &error,
+            )
+        })?;
+        let Some(backup) = self.replace_non_directory(source, metadata, destination)? else {
+            return Ok(());
+        };
+        self.print_copy(source, destination, backup.as_deref());
+        #[cfg(unix)]
+        let result = std::os::unix::fs::symlink(target, destination);
+        #[cfg(not(unix))]
+        let result = {
+            let _ = target;
+            Err(io::Error::new(
+                ErrorKind::Unsupported,
+                "symbolic links are not supported",
+            ))
+        };
+        result.map_err(|error| {
+            failure(
+                format_args!("cannot create symbolic link '{}'", destination.display()),
+                &error,
+            )
+        })?;
+        self.preserve(metadata, destination, false);
+        Ok(())
+    }
+
+    /// Create a special file, like a fifo or a device, of the same type as the source.
+    fn copy_special(
+        &mut self,
+        source: &Path,
+        metadata: &Metadata,
+        destination: &Path,
+    ) -> UResult<()> {
+        let Some(backup) = self.replace_non_directory(source, metadata, destination)? else {
+            return Ok(());
+        };
+        self.print_copy(source, destination, backup.as_deref());
+        let kind = match is_fifo(metadata) {
+            true => "fifo",
+            false => "special file",
+        };
+        make_node(destination, metadata).map_err(|error| {
+            failure(
+                format_args!("cannot create {kind} '{}'", destination.display()),
+                &error,
+            )
+        })?;
+        self.preserve(metadata, destination, true);
+        Ok(())
+    }
+
+    /// Make the destination a hard link to the copy of another link to the same source.
+    fn copy_hard_link(
+        &mut self,
+        source: &Path,
+        metadata: &Metadata,
+        first: &Path,
+        destination: &Path,
+    ) -> UResult<()> {
+        let Some(backup) = self.replace_non_directory(source, metadata, destination)? else {
+            return Ok(());
+        };
+        self.print_copy(source, destination, backup.as_deref());
+        fs::hard_link(first, destination).map_err(|error| {
+            failure(
+                format_args!(
+                    "cannot create hard link '{}' to '{}'",
+                    destination.display(),
+                    first.display()
+                ),
+                &error,
+            )
+        })
+    }
+
+    /// Create the copy of a directory, or check that there is a directory to merge it into, and get the
+    /// attributes to give a new copy once its contents are copied.
+    ///
+    /// For the directory given as an operand, check that it is not copied onto or into itself.
+    fn copy_directory(
+        &mut self,
+        entry: &Entry,
+        destination: &Path,
+    ) -> UResult<Option<PendingDirectory>> {
+        let (source, metadata) = (entry.path.as_path(), &entry.metadata);
+        if entry.depth == 0 {
+            if let Ok(existing) = fs::metadata(destination) {
+                if same_file(metadata, &existing, source, destination) {
+                    return Err(same_file_error(source, destination));
+                }
+            }
+            if is_inside(source, destination) {
+                let message = format!(
+                    "cannot copy a directory, '{}', into itself, '{}'",
+                    source.display(),
+                    destination.display()
+                );
+                return Err(UError::new(EXIT_FAILURE, message));
+            }
+        }
+
+        #[cfg_attr(not(unix), allow(unused_mut))]
+        let mut builder = DirBuilder::new();
+        #[cfg(unix)]
+        let mode = {
+            use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+            let mode = metadata.permissions().mode() & 0o777;
+            // The owner needs to write the contents, and the final bits are set at the end.
+            builder.mode(mode | 0o700);
+            mode
+        };
+        match builder.create(destination) {
+            Ok(()) => {
+                match self.moving {
+                    true if self.options.verbose => {
+                        let _ =
+                            writeln!(self.stdout, "created directory '{}'", destination.display());
+                    }
+                    _ => self.print_copy(source, destination, None),
+                }
+                #[cfg(unix)]
+                let mode = {
+                    use std::os::unix::fs::PermissionsExt;
+                    // The kernel applied the umask to the new directory.
+                    let created = fs::metadata(destination)
+                        .map(|metadata| metadata.permissions().mode() & 0o777)
+                        .unwrap_or(mode);
+                    (created & mode != created).then_some(created & mode)
+                };
+                #[cfg(not(unix))]
+                let mode = None;
+                let preserve = self.options.preserve;
+                let pending = PendingDirectory {
+                    depth: entry.depth,
+                    metadata: metadata.clone(),
+                    path: destination.to_path_buf(),
+                    mode,
+                };
+                Ok(
+                    (mode.is_some() || preserve.mode || preserve.ownership || preserve.timestamps)
+                        .then_some(pending),
+                )
+            }
+            Err(error) if error.kind() == ErrorKind::AlreadyExists => {
+                match fs::metadata(destination) {
+                    Ok(existing) if existing.is_dir() => Ok(None),
+                    _ => {
+                        let message = format!(
+                            "cannot overwrite non-directory '{}' with directory '{}'",
+                            destination.display(),
+                            source.display()
+                        );
+                        Err(UError::new(EXIT_FAILURE, message))
+                    }
+                }
+            }
+            Err(error) => Err(failure(
+                format_args!("cannot create directory '{}'", destination.display()),
+                &error,
+            )),
+        }
+    }
+
+    /// Give the pending directories which are at the given depth or deeper their final attributes, since
+    /// their contents are all copied.
+    fn finish_directories(&mut self, pending: &mut Vec<PendingDirectory>, depth: usize) {
+        while pending
+            .last()
+            .is_some_and(|directory| directory.depth >= depth)
+        {
+            let directory = pending.pop().unwrap();
+            #[cfg(unix)]
+            if let Some(mode) = directory.mode.filter(|_| !self.options.preserve.mode) {
+                use std::os::unix::fs::PermissionsExt;
+                let permissions = fs::Permissions::from_mode(mode);
+                if let Err(error) = fs::set_permissions(&directory.path, permissions) {
+                    let operation =
+                        format_args!("setting permissions for '{}'", directory.path.display());
+                    self.reporter
+                        .error(format_args!("{}", failure(operation, &error)));
+                }
+            }
+            self.preserve(&directory.metadata, &directory.path, true);
+        }
+    }
+
+    /// Remove the file at the destination, if any, or back it up, so that a link or a special file can be
+    /// created there, and check whether to go on with the copy. Returns `None` if the copy is skipped, and
+    /// the backup, if any, otherwise.
+    fn replace_non_directory(
+        &mut self,
+        source: &Path,
+        metadata: &Metadata,
+        destination: &Path,
+    ) -> UResult<Option<Option<PathBuf>>> {
+        let existing = match fs::symlink_metadata(destination) {
+            Ok(existing) => existing,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Some(None)),
+            Err(error) => {
+                return Err(failure(
+                    format_args!("cannot stat '{}'", destination.display()),
+                    &error,
+                ))
+            }
+        };
+        if self.options.overwrite == Overwrite::Never {
+            return Ok(None);
+        }
+        if same_file(metadata, &existing, source, destination) {
+            return Err(same_file_error(source, destination));
+        }
+        if existing.is_dir() {
+            return Err(overwriting_directory(destination));
+        }
+        if !self.confirm_overwrite(destination) {
+            return Ok(None);
+        }
+        if let Some(backup) = self.back_up(source, destination)? {
+            return Ok(Some(Some(backup)));
+        }
+        fs::remove_file(destination).map_err(|error| {
+            failure(
+                format_args!("cannot remove '{}'", destination.display()),
+                &error,
+            )
+        })?;
+        Ok(Some(None))
+    }
+
+    /// Back up the file at the destination, unless backups are not made, and get the backup.
+    fn back_up(&mut self, source: &Path, destination: &Path) -> UResult<Option<PathBuf>> {
+        if self.backup.mode == BackupMode::None {
+            return Ok(None);
+        }
+        backup::check_source(source, destination, &self.backup, "copied")?;
+        backup::back_up(destination, &self.backup)
+    }
+
+    /// Ask whether to overwrite an existing file with `-i`.
+    fn confirm_overwrite(&mut self, destination: &Path) -> bool {
+        if self.options.overwrite != Overwrite::Prompt {
+            return true;
+        }
+        let mut prompter = Prompter::new("cp", &mut *self.answers, self.reporter.stderr());
+        prompt_yes!(prompter, "overwrite '{}'?", destination.display())
+    }
+
+    /// Print a copy with `-v`, and the backup of the file it replaced, if any.
+    fn print_copy(&mut self, source: &Path, destination: &Path, backup: Option<&Path>) {
+        if self.options.verbose {
+            let _ = write!(
+                self.stdout,
+                "{}'{}' -> '{}'",
+                if self.moving { "copied " } else { "" },
+                source.display(),
+                destination.display()
+            );
+            let _ = match backup {
+                Some(backup) => writeln!(self.stdout, " (backup: '{}')", backup.display()),
+                None => writeln!(self.stdout),
+            };
+        }
+    }
+
+    /// Give a copy the attributes of the source which are preserved, and report the failures. The
+    /// permission bits of links are always those of new links.
+    fn preserve(&mut self, metadata: &Metadata, destination: &Path, follow: bool) {
+        let preserve = self.options.preserve;
+        if preserve.timestamps {
+            if let Err(error) = set_times(destination, metadata, follow) {
+                let operation = format_args!("preserving times for '{}'", destination.display());
+                self.reporter
+                    .error(format_args!("{}", failure(operation, &error)));
+            }
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            // Only the owner and the superuser may set the setuid and setgid bits.
+            let mut mode = metadata.permissions().mode() & 0o7777;
+            if preserve.ownership {
+                match set_owner(destination, metadata, follow) {
+                    Ok(true) => {}
+                    Ok(false) => mode &= !0o6000,
+                    Err(error) => {
+                        let operation = format_args!(
+                            "failed to preserve ownership for '{}'",
+                            destination.display()
+                        );
+                        self.reporter
+                            .error(format_args!("{}", failure(operation, &error)));
+                    }
+                }
+            }
+            if preserve.mode && follow {
+                let permissions = fs::Permissions::from_mode(mode);
+                if let Err(error) = fs::set_permissions(destination, permissions) {
+                    let operation =
+                        format_args!("preserving permissions for '{}'", destination.display());
+                    self.reporter
+                        .error(format_args!("{}", failure(operation, &error)));
+                }
+            }
+        }
+    }
+}
+
+/// Check that the file at the destination, if any, can be replaced by a copy of the source.
+fn check_destination(source: &Path, metadata: &Metadata, destination: &Path) -> UResult<()> {
+    let cannot_stat = |error: &io::Error| {
+        failure(
+            format_args!("cannot stat '{}'", destination.display()),
+            error,
+        )
+    };
+    let link = match fs::symlink_metadata(destination) {
+        Ok(link) => link,
+        // A missing file is created, unless the path can only name a directory.
+        Err(error) if error.kind() == ErrorKind::NotFound => {
+            return match os_str_bytes(destination.as_os_str()).ends_with(b"/") {
+                true => Err(UError::new(
+                    EXIT_FAILURE,
+                    format!(
+                        "cannot create regular file '{}': Not a directory",
+                        destination.display()
+                    ),
+                )),
+                false => Ok(()),
+            };
+        }
+        Err(error) => return Err(cannot_stat(&error)),
+    };
+
+    let existing = match fs::metadata(destination) {
+        Ok(existing) => existing,
+        Err(error) if link.file_type().is_symlink() && error.kind() == ErrorKind::NotFound => {
+            let message = format!(
+                "not writing through dangling symlink '{}'",
+                destination.display()
+            );
+            return Err(UError::new(EXIT_FAILURE, message));
+        }
+        Err(error) => return Err(cannot_stat(&error)),
+    };
+    if same_file(metadata, &existing, source, destination) {
+        return Err(same_file_error(source, destination));
+    }
+    if existing.is_dir() {
+        return Err(overwriting_directory(destination));
+    }
+    Ok(())
+}
+
+/// A failure to copy the contents of a file.
+enum CopyError {
+    /// The existing destination could not be opened, which `-f` recovers from.
+    Open(UError),
+    /// Any other failure.
+    Other(UError),
+}
+
+impl From<CopyError> for UError {
+    fn from(error: CopyError) -> Self {
+        match error {
+            CopyError::Open(error) | CopyError::Other(error) => error,
+        }
+    }
+}
+
+/// Copy the contents of the source to the destination, creating it if needed.
+fn copy_contents(source: &Path, metadata: &Metadata, destination: &Path) -> Result<(), CopyError> {
+    let mut input = File::open(source).map_err(|error| {
+        CopyError::Other(failure(
+            format_args!("cannot open '{}' for reading", source.display()),
+            &error,
+        ))
+    })?;
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        // The kernel applies the umask to the mode of a new file, like GNU without --preserve.
+        options.mode(metadata.permissions().mode() & 0o777);
+    }
+    #[cfg(not(unix))]
+    let _ = metadata;
+    let mut output = options.open(destination).map_err(|error| {
+        let error = failure(
+            format_args!("cannot create regular file '{}'", destination.display()),
+            &error,
+        );
+        match fs::symlink_metadata(destination).is_ok() {
+            true => CopyError::Open(error),
+            false => CopyError::Other(error),
+        }
+    })?;
+
+    // The file is read until its end rather than for its size, which is 0 for the files in /proc.
+    let mut buffer = vec![0; BUFFER_SIZE];
+    loop {
+        let count = match input.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(count) => count,
+            Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+            Err(error) => {
+                return Err(CopyError::Other(failure(
+                    format_args!("error reading '{}'", source.display()),
+                    &error,
+                )))
+            }
+        };
+        output.write_all(&buffer[..count]).map_err(|error| {
+            CopyError::Other(failure(
+                format_args!("error writing '{}'", destination.display()),
+                &error,
+            ))
+        })?;
+    }
+}
+
+/// Check whether a file has other hard links than the one being copied.
+#[cfg(unix)]
+fn has_links(metadata: &Metadata) -> bool {
+    std::os::unix::fs::MetadataExt::nlink(metadata) > 1
+}
+
+/// Check whether a file has other hard links than the one being copied.
+#[cfg(not(unix))]
+fn has_links(_metadata: &Metadata) -> bool {
+    false
+}
+
+/// Check whether a file is a fifo.
+#[cfg(unix)]
+fn is_fifo(metadata: &Metadata) -> bool {
+    std::os::unix::fs::FileTypeExt::is_fifo(&metadata.file_type())
+}
+
+/// Check whether a file is a fifo.
+#[cfg(not(unix))]
+fn is_fifo(_metadata: &Metadata) -> bool {
+    false
+}
+
+/// Create a special file of the same type and with the same permission bits as the source, minus the
+/// umask.
+#[cfg(unix)]
+fn make_node(path: &Path, metadata: &Metadata) -> io::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+    let path = c_path(path)?;
+    // The file type and the permission bits.
+    let mode = metadata.mode() & 0o170777;
+    // SAFETY: the path is a valid C string.
+    match unsafe {
+        libc::mknod(
+            path.as_ptr(),
+            mode as libc::mode_t,
+            metadata.rdev() as libc::dev_t,
+        )
+    } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// Create a special file of the same type and with the same permission bits as the source, minus the
+/// umask.
+#[cfg(not(unix))]
+fn make_node(_path: &Path, _metadata: &Metadata) -> io::Result<()> {
+    Err(ErrorKind::Unsupported.into())
+}
+
+/// Give a file the access and modification times of the source, or to the link itself unless `follow`.
+#[cfg(unix)]
+fn set_times(path: &Path, metadata: &Metadata, follow: bool) -> io::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+    let path = c_path(path)?;
+    let times = [
+        libc::timespec {
+            tv_sec: metadata.atime() as libc::time_t,
+            tv_nsec: metadata.atime_nsec() as libc::c_long,
+        },
+        libc::timespec {
+            tv_sec: metadata.mtime() as libc::time_t,
+            tv_nsec: metadata.mtime_nsec() as libc::c_long,
+        },
+    ];
+    let flags = match follow {
+        true => 0,
+        false => libc::AT_SYMLINK_NOFOLLOW,
+    };
+    // SAFETY: the path is a valid C string, and there are two times.
+    match unsafe { libc::utimensat(libc::AT_FDCWD, path.as_ptr(), times.as_ptr(), flags) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// Give a file the access and modification times of the source.
+#[cfg(not(unix))]
+fn set_times(path: &Path, metadata: &Metadata, _follow: bool) -> io::Result<()> {
+    let times = fs::FileTimes::new()
+        .set_accessed(metadata.accessed()?)
+        .set_modified(metadata.modified()?);
+    File::options().write(true).open(path)?.set_times(times)
+}
+
+/// Give a file the owner and the group of the source, or to the link itself unless `follow`, and check
+/// whether both were set. Like in GNU, lacking the permission to do so is not an error, and the group is
+/// still set if possible.
+#[cfg(unix)]
+fn set_owner(path: &Path, metadata: &Metadata, follow: bool) -> io::Result<bool> {
+    use std::os::unix::fs::{chown, lchown, MetadataExt};
+    let change = |uid, gid| match follow {
+        true => chown(path, uid, gid),
+        false => lchown(path, uid, gid),
+    };
+    let denied =
+        |error: &io::Error| matches!(error.raw_os_error(), Some(libc::EPERM | libc::EINVAL));
+    match change(Some(metadata.uid()), Some(metadata.gid())) {
+        Ok(()) => Ok(true),
+        Err(error) if denied(&error) => match change(None, Some(metadata.gid())) {
+            Err(error) if !denied(&error) => Err(error),
+            _ => Ok(false),
+        },
+        Err(error) => Err(error),
+    }
+}
+
+/// Describe an error of the traversal of a source directory.
+fn walk_failure(error: &WalkError) -> String {
+    match error {
+        WalkError::Io { path, error } => {
+            // The entries of a directory which exists could not be read.
+            let operation = match fs::symlink_metadata(path) {
+                Ok(metadata) if metadata.is_dir() => "cannot access",
+                _ => "cannot stat",
+            };
+            failure(format_args!("{operation} '{}'", path.display()), error).message
+        }
+        WalkError::Loop { path, .. } => {
+            format!("cannot copy cyclic symbolic link '{}'", path.display())
+        }
+    }
+}
+
+/// Create the error for a directory given without -R.
+fn omitting_directory(source: &Path) -> UError {
+    let message = format!(
+        "-r not specified; omitting directory '{}'",
+        source.display()
+    );
+    UError::new(EXIT_FAILURE, message)
+}
+
+/// Create the error for a copy onto the file itself.
+fn same_file_error(source: &Path, destination: &Path) -> UError {
+    let message = format!(
+        "'{}' and '{}' are the same file",
+        source.display(),
+        destination.display()
+    );
+    UError::new(EXIT_FAILURE, message)
+}
+
+/// Create the error for a copy of a file which is not a directory onto a directory.
+fn overwriting_directory(destination: &Path) -> UError {
+    let message = format!(
+        "cannot overwrite directory '{}' with non-directory",
+        destination.display()
+    );
+    UError::new(EXIT_FAILURE, message)
+}
+
+/// Create an error about an operation on a file, like `cannot stat 'file': No such file or directory`.
+fn failure(operation: impl Display, error: &io::Error) -> UError {
+    UError::new(
+        EXIT_FAILURE,
+        format!("{operation}: {}", io_error_message(error)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::{run_util_stderr, run_util_stdin};
+
+    /// Copy the operands with the given options, returning the result and stderr.
+    fn copy_operands(operands: &[OsString], options: CpOptions) -> (UResult<()>, String) {
+        let mut stderr = Vec::new();
+        let mut reporter = Reporter::new("cp", &mut stderr);
+        let result = cp(
+            operands,
+            &mut Copier::new(options, &mut reporter, &mut &b""[..], &mut Vec::new()),
+        );
+        (result, String::from_utf8(stderr).unwrap())
+    }
+
+    #[test]
+    fn test_into_directory() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::write(root.join("a"), "first").unwrap();
+        fs::write(root.join("b"), "second").unwrap();
+        fs::create_dir(root.join("d")).unwrap();
+
+        let path = |name| root.join(name).to_str().unwrap().to_owned();
+        assert_eq!(
+            run_util_stderr(run, &["cp", &path("a"), &path("d")]),
+            (0, String::new())
+        );
+        assert_eq!(fs::read_to_string(root.join("d/a")).unwrap(), "first");
+
+        assert_eq!(
+            run_util_stderr(run, &["cp", &path("a"), &path("b"), &path("d/")]),
+            (0, String::new())
+        );
+        assert_eq!(fs::read_to_string(root.join("d/b")).unwrap(), "second");
+    }
+
+    #[test]
+    fn test_overwrite() {
+        let directory = tempfile::tempdir().unwrap();
+        let (source, destination) = (directory.path().join("a"), directory.path().join("b"));
+        fs::write(&source, "new").unwrap();
+        fs::write(&destination, "much older contents").unwrap();
+
+        copy_file(&source, &destination).unwrap();
+        assert_eq!(fs::read_to_string(&destination).unwrap(), "new");
+    }
+
+    #[test]
+    fn test_same_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("f");
+        fs::write(&file, "data").unwrap();
+
+        let path = file.to_str().unwrap();
+        assert_eq!(
+            run_util_stderr(run, &["cp", path, path]),
+            (
+                EXIT_FAILURE,
+                format!("cp: '{path}' and '{path}' are the same file\n")
+            )
+        );
+        assert_eq!(fs::read_to_string(&file).unwrap(), "data");
+
+        // The same file through another path into a directory.
+        let dotdot = directory.path().join("sub/..");
+        fs::create_dir(directory.path().join("sub")).unwrap();
+        assert!(copy_file(&dotdot.join("f"), &file).is_err());
+        assert_eq!(fs::read_to_string(&file).unwrap(), "data");
+    }
+
+    #[test]
+    fn test_errors() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir(root.join("d")).unwrap();
+        fs::create_dir_all(root.join("out/f")).unwrap();
+        fs::write(root.join("f"), "data").unwrap();
+        fs::write(root.join("g"), "").unwrap();
+
+        let operands =
+            ["d", "missing", "f", "g", "out"].map(|name| root.join(name).into_os_string());
+        let (result, stderr) = copy_operands(&operands, CpOptions::default());
+        result.unwrap();
+        let path = |name| root.join(name).display().to_string();
+        assert_eq!(
+            stderr,
+            format!(
+                "cp: -r not specified; omitting directory '{}'\n\
+                 cp: cannot stat '{}': No such file or directory\n\
+                 cp: cannot overwrite directory '{}' with non-directory\n",
+                path("d"),
+                path("missing"),
+                path("out/f"),
+            )
+        );
+        // The operands after a failed one are still copied.
+        assert!(root.join("out/g").exists());
+
+        let operands = ["f", "g", "missing"].map(|name| root.join(name).into_os_string());
+        let error = copy_operands(&operands, CpOptions::default())
+            .0
+            .unwrap_err();
+        let message = format!("target '{}': No such file or directory", path("missing"));
+        assert_eq!(error, UError::new(EXIT_FAILURE, message));
+        let operands = ["f", "f", "g"].map(|name| root.join(name).into_os_string());
+        let error = copy_operands(&operands, CpOptions::default())
+            .0
+            .unwrap_err();
+        let message = format!("target '{}': Not a directory", path("g"));
+        assert_eq!(error, UError::new(EXIT_FAILURE, message));
+
+        assert_eq!(
+            run_util_stderr(run, &["cp"]),
+            (
+                EXIT_FAILURE,
+                "cp: missing file operand\nTry 'cp --help' for more information.\n".to_owned()
+            )
+        );
+        assert_eq!(
+            run_util_stderr(run, &["cp", "f"]),
+            (
+                EXIT_FAILURE,
+                "cp: missing destination file operand after 'f'\n\
+                 Try 'cp --help' for more information.\n"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_destination_symlink() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::write(root.join("f"), "data").unwrap();
+        fs::write(root.join("g"), "").unwrap();
+        std::os::unix::fs::symlink("g", root.join("link")).unwrap();
+        std::os::unix::fs::symlink("missing", root.join("dangling")).unwrap();
+
+        copy_file(&root.join("f"), &root.join("link")).unwrap();
+        assert_eq!(fs::read_to_string(root.join("g")).unwrap(), "data");
+
+        let error = copy_file(&root.join("f"), &root.join("dangling")).unwrap_err();
+        let message = format!(
+            "not writing through dangling symlink '{}'",
+            root.join("dangling").display()
+        );
+        assert_eq!(error, UError::new(EXIT_FAILURE, message));
+        assert!(!root.join("missing").exists());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_permissions() {
+        use assert_cmd::cargo::CommandCargoExt;
+        use std::os::unix::fs::PermissionsExt;
+        use std::process::Command;
+
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::write(root.join("exe"), "").unwrap();
+        fs::set_permissions(root.join("exe"), fs::Permissions::from_mode(0o4775)).unwrap();
+        fs::write(root.join("existing"), "").unwrap();
+        fs::set_permissions(root.join("existing"), fs::Permissions::from_mode(0o600)).unwrap();
+
+        // Run through a shell, which sets the umask of cp only.
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg("umask 027 && \"$0\" exe copy && \"$0\" exe existing")
+            .arg(Command::cargo_bin("cp").unwrap().get_program())
+            .current_dir(root)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let mode = |name| fs::metadata(root.join(name)).unwrap().permissions().mode() & 0o7777;
+        // The setuid bit is not copied, and the umask applies.
+        assert_eq!(mode("copy"), 0o750);
+        assert_eq!(mode("existing"), 0o600);
+    }
+
+    /// Build a tree with a directory, a symbolic link to it, a dangling symbolic link, and a fifo, and a
+    /// symbolic link to the tree next to it.
+    #[cfg(unix)]
+    fn tree() -> tempfile::TempDir {
+        use std::os::unix::fs::symlink;
+
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        fs::write(root.join("a/b/f"), "data").unwrap();
+        symlink("b", root.join("a/lb")).unwrap();
+        symlink("missing", root.join("a/dangling")).unwrap();
+        let fifo =
+            std::ffi::CString::new(root.join("a/fifo").into_os_string().into_encoded_bytes());
+        assert_eq!(unsafe { libc::mkfifo(fifo.unwrap().as_ptr(), 0o644) }, 0);
+        symlink("a", root.join("la")).unwrap();
+        directory
+    }
+
+    /// Copy recursively, following the given links, and get the stderr.
+    #[cfg(unix)]
+    fn copy_tree(source: &Path, destination: &Path, follow: Follow) -> String {
+        let options = CpOptions {
+            recursive: true,
+            follow,
+            ..CpOptions::default()
+        };
+        let mut stderr = Vec::new();
+        let mut reporter = Reporter::new("cp", &mut stderr);
+        Copier::new(options, &mut reporter, &mut &b""[..], &mut Vec::new())
+            .copy(source, destination);
+        String::from_utf8(stderr).unwrap()
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_recursive_symlinks() {
+        use std::os::unix::fs::FileTypeExt;
+
+        let directory = tree();
+        let root = directory.path();
+        let file_type = |path: &str| fs::symlink_metadata(root.join(path)).unwrap().file_type();
+
+        // -P copies the links as links, and recreates the fifo.
+        assert_eq!(
+            copy_tree(&root.join("la"), &root.join("P"), Follow::Never),
+            ""
+        );
+        assert_eq!(fs::read_link(root.join("P")).unwrap(), Path::new("a"));
+        assert_eq!(
+            copy_tree(&root.join("a"), &root.join("P2"), Follow::Never),
+            ""
+        );
+        assert_eq!(fs::read_to_string(root.join("P2/b/f")).unwrap(), "data");
+        assert_eq!(fs::read_link(root.join("P2/lb")).unwrap(), Path::new("b"));
+        assert_eq!(
+            fs::read_link(root.join("P2/dangling")).unwrap(),
+            Path::new("missing")
+        );
+        assert!(file_type("P2/fifo").is_fifo());
+
+        // -H follows the operand only.
+        assert_eq!(
+            copy_tree(&root.join("la"), &root.join("H"), Follow::Roots),
+            ""
+        );
+        assert!(file_type("H").is_dir());
+        assert!(file_type("H/lb").is_symlink());
+
+        // -L follows every link, and fails on the dangling one.
+        let stderr = copy_tree(&root.join("la"), &root.join("L"), Follow::Always);
+        let message = format!(
+            "cp: cannot stat '{}': No such file or directory\n",
+            root.join("la/dangling").display()
+        );
+        assert_eq!(stderr, message);
+        assert!(file_type("L/lb").is_dir());
+        assert_eq!(fs::read_to_string(root.join("L/lb/f")).unwrap(), "data");
+        assert!(!root.join("L/dangling").exists());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_recursive_naming() {
+        let directory = tree();
+        let root = directory.path();
+        let path = |name| root.join(name).to_str().unwrap().to_owned();
+
+        // A missing destination is the copy, and an existing directory gets the copy inside.
+        assert_eq!(
+            run_util_stderr(run, &["cp", "-r", &path("a"), &path("c")]),
+            (0, String::new())
+        );
+        assert!(root.join("c/b/f").exists());
+        assert_eq!(
+            run_util_stderr(run, &["cp", "-r", &path("a"), &path("c")]),
+            (0, String::new())
+        );
+        assert!(root.join("c/a/b/f").exists());
+        assert!(!root.join("c/a/a").exists());
+
+        // A directory is merged into an existing one.
+        fs::remove_file(root.join("c/b/f")).unwrap();
+        fs::write(root.join("c/b/g"), "").unwrap();
+        assert_eq!(
+            run_util_stderr(run, &["cp", "-R", &path("a/b"), &path("c")]),
+            (0, String::new())
+        );
+        assert!(root.join("c/b/f").exists() && root.join("c/b/g").exists());
+
+        fs::write(root.join("file"), "").unwrap();
+        assert_eq!(
+            run_util_stderr(run, &["cp", "-r", &path("a"), &path("file")]),
+            (
+                EXIT_FAILURE,
+                format!(
+                    "cp: cannot overwrite non-directory '{}' with directory '{}'\n",
+                    path("file"),
+                    path("a")
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn test_into_itself() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        let path = |name| root.join(name).to_str().unwrap().to_owned();
+
+        assert_eq!(
+            run_util_stderr(run, &["cp", "-r", &path("a"), &path("a/b")]),
+            (
+                EXIT_FAILURE,
+                format!(
+                    "cp: cannot copy a directory, '{}', into itself, '{}'\n",
+                    path("a"),
+                    path("a/b/a")
+                )
+            )
+        );
+        assert!(!root.join("a/b/a").exists());
+
+        let (code, stderr) = run_util_stderr(run, &["cp", "-r", &path("a"), &path("a/b/new")]);
+        assert_eq!(code, EXIT_FAILURE);
+        assert!(stderr.contains("into itself"));
+        assert!(!root.join("a/b/new").exists());
+
+        let (code, stderr) = run_util_stderr(run, &["cp", "-r", &path("a"), &path("a/b/..")]);
+        assert_eq!(code, EXIT_FAILURE);
+        assert!(stderr.contains("into itself"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_recursive_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = tree();
+        let root = directory.path();
+        fs::set_permissions(root.join("a/b"), fs::Permissions::from_mode(0o555)).unwrap();
+        assert_eq!(
+            copy_tree(&root.join("a"), &root.join("c"), Follow::Never),
+            ""
+        );
+        let mode = fs::metadata(root.join("c/b")).unwrap().permissions().mode() & 0o777;
+        // The contents were copied before the directory lost the write permission, minus the umask.
+        assert_eq!(mode & 0o222, 0);
+        assert_eq!(mode & !0o555, 0);
+        assert_eq!(fs::read_to_string(root.join("c/b/f")).unwrap(), "data");
+    }
+
+    #[test]
+    fn test_preserve_parse() {
+        let preserve = Preserve {
+            mode: true,
+            links: true,
+            ..Preserve::NONE
+        };
+        assert_eq!(Preserve::parse("mode,links"), Ok(preserve));
+        assert_eq!(Preserve::parse("al"), Ok(Preserve::ALL));
+        assert_eq!(Preserve::parse("t,o,m"), Ok(Preserve::BASIC));
+        let error = Preserve::parse("mode,xattr").unwrap_err();
+        assert!(error.message.starts_with(
+            "invalid argument 'xattr' for '--preserve'\nValid arguments are:\n  - 'mode'\n"
+        ));
+        let error = Preserve::parse("").unwrap_err();
+        assert!(error
+            .message
+            .starts_with("ambiguous argument '' for '--preserve'"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_preserve() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+        use std::time::{Duration, SystemTime};
+
+        let directory = tree();
+        let root = directory.path();
+        let old = SystemTime::UNIX_EPOCH + Duration::new(1_000_000_000, 123_456_789);
+        let set_modified = |path: &str| {
+            File::options()
+                .write(true)
+                .open(root.join(path))
+                .unwrap()
+                .set_modified(old)
+                .unwrap()
+        };
+        fs::set_permissions(root.join("a/b/f"), fs::Permissions::from_mode(0o640)).unwrap();
+        fs::hard_link(root.join("a/b/f"), root.join("a/b/g")).unwrap();
+        set_modified("a/b/f");
+        File::open(root.join("a/b"))
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+
+        let path = |name| root.join(name).to_str().unwrap().to_owned();
+        assert_eq!(
+            run_util_stderr(run, &["cp", "-p", &path("a/b/f"), &path("f")]),
+            (0, String::new())
+        );
+        let metadata = fs::metadata(root.join("f")).unwrap();
+        assert_eq!(metadata.modified().unwrap(), old);
+        assert_eq!(metadata.mode() & 0o777, 0o640);
+
+        // The times of the directories are set once their contents are copied.
+        assert_eq!(
+            run_util_stderr(run, &["cp", "-a", &path("a"), &path("c")]),
+            (0, String::new())
+        );
+        assert_eq!(
+            fs::metadata(root.join("c/b")).unwrap().modified().unwrap(),
+            old
+        );
+        assert_eq!(
+            fs::metadata(root.join("c/b/f"))
+                .unwrap()
+                .modified()
+                .unwrap(),
+            old
+        );
+        let (f, g) = (root.join("c/b/f"), root.join("c/b/g"));
+        assert_eq!(
+            fs::metadata(f).unwrap().ino(),
+            fs::metadata(g).unwrap().ino()
+        );
+        assert!(fs::symlink_metadata(root.join("c/lb"))
+            .unwrap()
+            .file_type()
+            .is_symlink());
+
+        // Without -p, the copies are new files.
+        assert_eq!(
+            run_util_stderr(run, &["cp", "-R", &path("a"), &path("d")]),
+            (0, String::new())
+        );
+        assert_ne!(
+            fs::metadata(root.join("d/b/f"))
+                .unwrap()
+                .modified()
+                .unwrap(),
+            old
+        );
+        let (f, g) = (root.join("d/b/f"), root.join("d/b/g"));
+        assert_ne!(
+            fs::metadata(f).unwrap().ino(),
+            fs::metadata(g).unwrap().ino()
+        );
+    }
+
+    #[test]
+    fn test_interactive() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::write(root.join("f"), "new").unwrap();
+        fs::write(root.join("g"), "old").unwrap();
+        let (f, g) = (root.join("f"), root.join("g"));
+        let (f, g) = (f.to_str().unwrap(), g.to_str().unwrap());
+        let contents = || fs::read_to_string(root.join("g")).unwrap();
+        let question = format!("cp: overwrite '{g}'? ");
+
+        assert_eq!(
+            run_util_stdin(run, &["cp", "-i", f, g], "n\n"),
+            (0, String::new(), question.clone())
+        );
+        assert_eq!(contents(), "old");
+
+        // The last of -i and -n wins.
+        assert_eq!(
+            run_util_stdin(run, &["cp", "-in", f, g], "y\n"),
+            (0, String::new(), String::new())
+        );
+        assert_eq!(contents(), "old");
+        assert_eq!(
+            run_util_stdin(run, &["cp", "-ni", f, g], "y\n"),
+            (0, String::new(), question)
+        );
+        assert_eq!(contents(), "new");
+
+        // With -n, even copying a file onto itself is skipped.
+        assert_eq!(
+            run_util_stdin(run, &["cp", "-n", f, f], ""),
+            (0, String::new(), String::new())
+        );
+    }
+
+    #[test]
+    fn test_verbose() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir_all(root.join("d/e")).unwrap();
+        fs::write(root.join("d/e/f"), "").unwrap();
+        let path = |name| root.join(name).display().to_string();
+
+        let (code, stdout, stderr) =
+            run_util_stdin(run, &["cp", "-rv", &path("d"), &path("c")], "");
+        assert_eq!((code, stderr.as_str()), (0, ""));
+        assert_eq!(
+            stdout,
+            format!(
+                "'{}' -> '{}'\n'{}' -> '{}'\n'{}' -> '{}'\n",
+                path("d"),
+                path("c"),
+                path("d/e"),
+                path("c/e"),
+                path("d/e/f"),
+                path("c/e/f")
+            )
+        );
+
+        // The existing directories are merged silently, and -n skips the existing files silently.
+        fs::write(root.join("d/g"), "").unwrap();
+        let (code, stdout, _) = run_util_stdin(run, &["cp", "-rnv", &path("d/."), &path("c")], "");
+        assert_eq!(code, 0);
+        assert_eq!(
+            stdout,
+            format!("'{}' -> '{}'\n", path("d/./g"), path("c/./g"))
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_force() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::write(root.join("f"), "data").unwrap();
+        // Nobody can open a socket, not even the superuser.
+        let _listener = std::os::unix::net::UnixListener::bind(root.join("socket")).unwrap();
+        let (f, socket) = (root.join("f"), root.join("socket"));
+        let (f, socket) = (f.to_str().unwrap(), socket.to_str().unwrap());
+
+        let (code, stderr) = run_util_stderr(run, &["cp", f, socket]);
+        assert_eq!(code, EXIT_FAILURE);
+        assert_eq!(
+            stderr,
+            format!("cp: cannot create regular file '{socket}': No such device or address\n")
+        );
+
+        assert_eq!(
+            run_util_stdin(run, &["cp", "-fv", f, socket], ""),
+            (
+                0,
+                format!("'{f}' -> '{socket}'\nremoved '{socket}'\n"),
+                String::new()
+            )
+        );
+        assert_eq!(fs::read_to_string(socket).unwrap(), "data");
+
+        // A read-only file is replaced too.
+        let g = root.join("g");
+        fs::write(&g, "").unwrap();
+        fs::set_permissions(&g, fs::Permissions::from_mode(0o444)).unwrap();
+        assert_eq!(
+            run_util_stderr(run, &["cp", "-f", f, g.to_str().unwrap()]),
+            (0, String::new())
+        );
+        assert_eq!(fs::read_to_string(g).unwrap(), "data");
+    }
+
+    #[test]
+    fn test_backup() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::write(root.join("f"), "new").unwrap();
+        fs::write(root.join("g"), "old").unwrap();
+        let path = |name| root.join(name).display().to_string();
+        let contents = |name| fs::read_to_string(root.join(name)).unwrap();
+
+        let (code, stdout, stderr) = run_util_stdin(
+            run,
+            &[
+                "cp",
+                "-v",
+                "--backup=simple",
+                "-S",
+                ".orig",
+                &path("f"),
+                &path("g"),
+            ],
+            "",
+        );
+        assert_eq!((code, stderr.as_str()), (0, ""));
+        assert_eq!(
+            stdout,
+            format!(
+                "'{}' -> '{}' (backup: '{}')\n",
+                path("f"),
+                path("g"),
+                path("g.orig")
+            )
+        );
+        assert_eq!(
+            (contents("g"), contents("g.orig")),
+            ("new".into(), "old".into())
+        );
+
+        for backup in ["g.~1~", "g.~2~"] {
+            assert_eq!(
+                run_util_stderr(run, &["cp", "--backup=numbered", &path("f"), &path("g")]),
+                (0, String::new())
+            );
+            assert_eq!(contents(backup), "new");
+        }
+
+        // The backup would replace the source before it is copied.
+        assert_eq!(
+            run_util_stderr(
+                run,
+                &[
+                    "cp",
+                    "--backup=simple",
+                    "-S",
+                    ".orig",
+                    &path("g.orig"),
+                    &path("g")
+                ]
+            ),
+            (
+                EXIT_FAILURE,
+                format!(
+                    "cp: backing up '{}' might destroy source;  '{}' not copied\n",
+                    path("g"),
+                    path("g.orig")
+                )
+            )
+        );
+        assert_eq!(contents("g.orig"), "old");
+
+        assert_eq!(
+            run_util_stderr(run, &["cp", "-bn", &path("f"), &path("g")]),
+            (
+                EXIT_FAILURE,
+                "cp: options --backup and --no-clobber are mutually exclusive\n\
+                 Try 'cp --help' for more information.\n"
+                    .into()
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_backup_links() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::write(root.join("f"), "new").unwrap();
+        fs::write(root.join("g"), "old").unwrap();
+        std::os::unix::fs::symlink("f", root.join("link")).unwrap();
+        let path = |name| root.join(name).display().to_string();
+
+        // A link replaces the file, which is backed up rather than removed.
+        assert_eq!(
+            run_util_stderr(run, &["cp", "-P", "--backup=t", &path("link"), &path("g")]),
+            (0, String::new())
+        );
+        assert_eq!(fs::read_link(root.join("g")).unwrap(), Path::new("f"));
+        assert_eq!(fs::read_to_string(root.join("g.~1~")).unwrap(), "old");
+    }
+}
@@ -0,0 +1,861 @@
+//! Print selected parts of the lines of files.
+//!
+//! The inputs are printed with [`cut`], from a selection parsed with [`List::parse`]:
+//!
+//! ```
+//! use rust_coreutils::common::error::Reporter;
+//! use rust_coreutils::common::input::InputIter;
+//! use rust_coreutils::cut::{cut, CutOptions, List, Mode, Unit};
+//!
+//! let mut inputs = InputIter::with_stdin(vec![], Box::new(&b"a:b:c\nxy\n"[..]));
+//! let options = CutOptions {
+//!     mode: Mode::Fields(List::parse("3,1", Unit::Fields).unwrap()),
+//!     field_delimiter: b':',
+//!     ..CutOptions::default()
+//! };
+//! let mut stdout = Vec::new();
+//! let mut reporter = Reporter::new("cut", Vec::new());
+//! cut(&mut inputs, &options, &mut stdout, &mut reporter).unwrap();
+//! assert_eq!(stdout, b"a:c\nxy\n");
+//! ```
+
+use crate::common::error::{Reporter, UError, UResult, EXIT_FAILURE};
+use crate::common::input::{InputIter, BUFFER_SIZE};
+use crate::common::os::os_str_bytes;
+use crate::common::records::{RecordReader, ZeroTerminatedArgs};
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use clap::Parser;
+use std::ffi::OsString;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::ops::Range;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "cut",
+    author,
+    version,
+    about = "Print the selected parts of the lines of each FILE.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Select only these bytes. LIST is made of ranges N, N-, -M, or N-M, counted from 1 and separated by
+    /// commas or blanks.
+    #[arg(short, long, value_name = "LIST", allow_hyphen_values = true)]
+    bytes: Option<String>,
+
+    /// Select only these characters, which are bytes, like in the C locale.
+    #[arg(short, long, value_name = "LIST", allow_hyphen_values = true)]
+    characters: Option<String>,
+
+    /// Select only these fields, and print the lines without a delimiter whole, unless -s is given.
+    #[arg(short, long, value_name = "LIST", allow_hyphen_values = true)]
+    fields: Option<String>,
+
+    /// Separate fields with DELIM instead of a tab. An empty DELIM is a null byte.
+    #[arg(short, long, value_name = "DELIM", allow_hyphen_values = true)]
+    delimiter: Option<OsString>,
+
+    /// Do not print the lines without a delimiter.
+    #[arg(short = 's', long, default_value_t = false)]
+    only_delimited: bool,
+
+    /// Select everything but the bytes, characters, or fields of the list.
+    #[arg(long, default_value_t = false)]
+    complement: bool,
+
+    /// Join the selected fields with STRING instead of the delimiter, and separate the ranges of bytes or
+    /// characters with it.
+    #[arg(long, value_name = "STRING", allow_hyphen_values = true)]
+    output_delimiter: Option<OsString>,
+
+    /// Ignored, for compatibility.
+    #[arg(short = 'n', default_value_t = false)]
+    no_split: bool,
+
+    #[command(flatten)]
+    records: ZeroTerminatedArgs,
+
+    /// The files to read. With no FILE, or when FILE is -, standard input is read.
+    #[arg(value_name = "FILE")]
+    files: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "cut";
+}
+
+/// The description of cut for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run cut with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run cut with the given command line, including the program name, on the given streams, and return the
+/// exit code.
+pub fn run(
+    args: &[OsString],
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(args.iter().cloned(), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let mut reporter = Reporter::new("cut", stderr);
+    let options = match options(&args) {
+        Ok(options) => options,
+        Err(message) => return reporter.finish(Err(UError::usage(EXIT_FAILURE, message))),
+    };
+    let mut inputs = InputIter::with_stdin(
+        args.files,
+        Box::new(BufReader::with_capacity(BUFFER_SIZE, stdin)),
+    );
+    let result = cut(&mut inputs, &options, stdout, &mut reporter);
+    reporter.finish(result)
+}
+
+/// Get the options of the command line, checked in the order GNU does.
+fn options(args: &Args) -> Result<CutOptions, String> {
+    let lists = [&args.bytes, &args.characters, &args.fields];
+    if lists.iter().filter(|list| list.is_some()).count() > 1 {
+        return Err("only one list may be specified".to_owned());
+    }
+    let field_delimiter = match args.delimiter.as_deref().map(os_str_bytes).as_deref() {
+        None => b'\t',
+        // Like GNU, an empty delimiter is the null byte.
+        Some([]) => b'\0',
+        Some(&[byte]) => byte,
+        Some(_) => return Err("the delimiter must be a single character".to_owned()),
+    };
+
+    let (list, unit) = match lists {
+        [Some(list), None, None] | [None, Some(list), None] => (list, Unit::Positions),
+        [None, None, Some(list)] => (list, Unit::Fields),
+        _ => return Err("you must specify a list of bytes, characters, or fields".to_owned()),
+    };
+    if unit == Unit::Positions && args.delimiter.is_some() {
+        return Err("an input delimiter may be specified only when operating on fields".to_owned());
+    }
+    if unit == Unit::Positions && args.only_delimited {
+        return Err(
+            "suppressing non-delimited lines makes sense\n\tonly when operating on fields"
+                .to_owned(),
+        );
+    }
+
+    let mut list = List::parse(list, unit)?;
+    if args.complement {
+        list = list.complement();
+    }
+    Ok(CutOptions {
+        // Characters are bytes until multibyte locales are supported.
+        mode: match unit {
+            Unit::Positions => Mode::Bytes(list),
+            Unit::Fields => Mode::Fields(list),
+        },
+        field_delimiter,
+        only_delimited: args.only_delimited,
+        output_delimiter: args
+            .output_delimiter
+            .as_deref()
+            .map(|delimiter| os_str_bytes(delimiter).into_owned()),
+        delimiter: args.records.delimiter(),
+    })
+}
+
+/// What the numbers of a [`List`] count, which only changes the messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit {
+    /// Bytes or characters.
+    Positions,
+    /// Fields.
+    Fields,
+}
+
+impl Unit {
+    fn numbered_from_one(self) -> String {
+        match self {
+            Unit::Positions => "byte/character positions are numbered from 1".to_owned(),
+            Unit::Fields => "fields are numbered from 1".to_owned(),
+        }
+    }
+
+    fn invalid_range(self) -> String {
+        match self {
+            Unit::Positions => "invalid byte or character range".to_owned(),
+            Unit::Fields => "invalid field range".to_owned(),
+        }
+    }
+
+    fn invalid_value(self, value: &str) -> String {
+        match self {
+            Unit::Positions => format!("invalid byte/character position '{value}'"),
+            Unit::Fields => format!("invalid field value '{value}'"),
+        }
+    }
+
+    fn too_large(self, number: &str) -> String {
+        match self {
+            Unit::Positions => format!("byte/character offset '{number}' is too large"),
+            Unit::Fields => format!("field number '{number}' is too large"),
+        }
+    }
+}
+
+/// A selection of positions, like `1,3-5,7-`, as sorted ranges which do not overlap.
+///
+/// Ranges which only touch are kept apart, like GNU does, so that `--output-delimiter` separates them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct List {
+    ranges: Vec<Range<usize>>,
+}
+
+impl List {
+    /// Parse a list of ranges `N`, `N-`, `-M`, or `N-M`, separated by commas or blanks, with the GNU message
+    /// of the first error. The ranges can overlap and be in any order.
+    pub fn parse(list: &str, unit: Unit) -> Result<List, String> {
+        let mut ranges = Vec::new();
+        let mut offset = 0;
+        for item in list.split([',', ' ', '\t']) {
+            ranges.push(parse_range(item, &list[offset..], unit)?);
+            offset += item.len() + 1;
+        }
+
+        ranges.sort_by_key(|range| range.start);
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start < last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        Ok(List { ranges: merged })
+    }
+
+    /// Get the positions which are not selected, which may be none.
+    pub fn complement(&self) -> List {
+        let mut ranges = Vec::new();
+        let mut start = 0;
+        for range in &self.ranges {
+            if start < range.start {
+                ranges.push(start..range.start);
+            }
+            start = range.end;
+        }
+        if start < usize::MAX {
+            ranges.push(start..usize::MAX);
+        }
+        List { ranges }
+    }
+
+    /// Get the ranges of positions counted from 0, in order. The end of an open range is `usize::MAX`.
+    pub fn ranges(&self) -> &[Range<usize>] {
+        &self.ranges
+    }
+}
+
+/// Parse one range of a list, where `rest` is the list from the start of the range, for messages.
+fn parse_range(item: &str, rest: &str, unit: Unit) -> Result<Range<usize>, String> {
+    let invalid_value = |at: &str| unit.invalid_value(&rest[item.len() - at.len()..]);
+    let (start, after_start) = parse_position(item, unit)?;
+    let Some(after_dash) = after_start.strip_prefix('-') else {
+        if !after_start.is_empty() {
+            return Err(invalid_value(after_start));
+        }
+        return match start {
+            Some(start) if start > 0 => Ok(start - 1..start),
+            _ => Err(unit.numbered_from_one()),
+        };
+    };
+
+    if start == Some(0) {
+        return Err(unit.numbered_from_one());
+    }
+    let (end, after_end) = parse_position(after_dash, unit)?;
+    if after_end.starts_with('-') {
+        return Err(unit.invalid_range());
+    }
+    if !after_end.is_empty() {
+        return Err(invalid_value(after_end));
+    }
+    let first = start.unwrap_or(1);
+    match (start, end) {
+        (None, None) => Err("invalid range with no endpoint: -".to_owned()),
+        (_, None) => Ok(first - 1..usize::MAX),
+        (_, Some(end)) if end < first => Err("invalid decreasing range".to_owned()),
+        (_, Some(end)) => Ok(first - 1..end),
+    }
+}
+
+/// Parse the digits at the start of `text`, if any, and get the text after them.
+fn parse_position(text: &str, unit: Unit) -> Result<(Option<usize>, &str), String> {
+    let digits = text.bytes().take_while(u8::is_ascii_digit).count();
+    if digits == 0 {
+        return Ok((None, text));
+    }
+    let (number, rest) = text.split_at(digits);
+    // The largest value stands for the end of the line.
+    match number.parse::<usize>() {
+        Ok(position) if position < usize::MAX => Ok((Some(position), rest)),
+        _ => Err(unit.too_large(number)),
+    }
+}
+
+/// What is selected from the lines.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// These bytes of each line.
+    Bytes(List),
+    /// These fields of each line, separated by the field delimiter.
+    Fields(List),
+}
+
+/// The behaviour of cut.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CutOptions {
+    /// What is selected from the lines.
+    pub mode: Mode,
+    /// The byte which separates fields.
+    pub field_delimiter: u8,
+    /// Leave out the lines without a field delimiter, instead of printing them whole.
+    pub only_delimited: bool,
+    /// What joins the selected fields, instead of the field delimiter, or separates the ranges of bytes,
+    /// instead of nothing.
+    pub output_delimiter: Option<Vec<u8>>,
+    /// The byte which ends lines.
+    pub delimiter: u8,
+}
+
+impl Default for CutOptions {
+    /// Select whole lines, which is what `cut -b1-` does, and fields separated by tabs.
+    fn default() -> Self {
+        CutOptions {
+            mode: Mode::Bytes(List {
+                ranges: vec![Range {
+                    start: 0,
+                    end: usize::MAX,
+                }],
+            }),
+            field_delimiter: b'\t',
+            only_delimited: false,
+            output_delimiter: None,
+            delimiter: b'\n',
+        }
+    }
+}
+
+impl CutOptions {
+    /// Write the selected parts of a line and the delimiter, or nothing when the line is left out.
+    fn write_line(&self, writer: &mut dyn Write, line: &[u8]) -> io::Result<()> {
+        match &self.mode {
+            Mode::Bytes(list) => self.write_bytes(writer, line, list)?,
+            Mode::Fields(_) if !line.contains(&self.field_delimiter) => {
+                if self.only_delimited {
+                    return Ok(());
+                }
+                writer.write_all(line)?;
+            }
+            Mode::Fields(list) => self.write_fields(writer, line, list)?,
+        }
+        writer.write_all(&[self.delimiter])
+    }
+
+    /// Write the selected bytes of a line, which are whatever falls inside the ranges.
+    fn write_bytes(&self, writer: &mut dyn Write, line: &[u8], list: &List) -> io::Result<()> {
+        for (index, range) in list.ranges().iter().enumerate() {
+            if range.start >= line.len() {
+                break;
+            }
+            if let (true, Some(separator)) = (index > 0, &self.output_delimiter) {
+                writer.write_all(separator)?;
+            }
+            writer.write_all(&line[range.start..range.end.min(line.len())])?;
+        }
+        Ok(())
+    }
+
+    /// Write the selected fields of a line, joined by the output delimiter. The fields after the last one of
+    /// the line are empty, and only printed once.
+    fn write_fields(&self, writer: &mut dyn Write, line: &[u8], list: &List) -> io::Result<()> {
+        let separator = match &self.output_delimiter {
+            Some(separator) => separator.as_slice(),
+            None => std::slice::from_ref(&self.field_delimiter),
+        };
+        let mut ranges = list.ranges().iter().peekable();
+        let mut first = true;
+        for (index, field) in line.split(|&byte| byte == self.field_delimiter).enumerate() {
+            while ranges.next_if(|range| range.end <= index).is_some() {}
+            let Some(range) = ranges.peek() else {
+                break;
+            };
+            if range.start <= index {
+                if !first {
+                    writer.write_all(separator)?;
+                }
+                writer.write_all(field)?;
+                first = false;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Print the selected parts of the lines of the inputs, one after the other. A last line without a delimiter
+/// is printed with one.
+///
+/// Inputs which cannot be read are reported, and the next ones are still printed. A closed pipe stops without
+/// an error.
+pub fn cut<W: Write>(
+    inputs: &mut InputIter,
+    options: &CutOptions,
+    stdout: &mut dyn Write,
+    reporter: &mut Reporter<W>,
+) -> UResult<()> {
+    let mut writer = BufWriter::with_capacity(BUFFER_SIZE, stdout);
+    while let Some(input) = inputs.next_buffered() {
+        let mut input = match input {
+            Ok(input) => input,
+            Err(error) => {
+                reporter.io_error(error.display_name(), &error.error);
+                continue;
+            }
+        };
+        match cut_lines(&mut input.reader, options, &mut writer) {
+            Ok(()) => {}
+            Err(Failure::Read(error)) => reporter.io_error(input.display_name(), &error),
+            Err(Failure::Write(error)) => return UError::write(error),
+        }
+    }
+    writer.flush().or_else(UError::write)
+}
+
+/// Why printing an input stopped.
+enum Failure {
+    Read(io::Error),
+    Write(io::Error),
+}
+
+/// Print the selected parts of the lines of one input.
+fn cut_lines(
+    reader: &mut dyn BufRead,
+    options: &CutOptions,
+    writer: &mut dyn Write,
+) -> Result<(), Failure> {
+    let mut records = RecordReader::new(reader, options.delimiter);
+    while let Some(record) = records.next_record() {
+        let line = record.map_err(Failure::Read)?.content;
+        options.write_line(writer, line).map_err(Failure::Write)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::{run_util_stdin, Fixture};
+
+    /// Run cut on some text, returning the output.
+    fn cut_text(args: &[&str], input: &str) -> String {
+        let command_line: Vec<&str> = std::iter::once("cut").chain(args.iter().copied()).collect();
+        let (code, stdout, stderr) = run_util_stdin(run, &command_line, input.as_bytes());
+        assert_eq!((code, stderr.as_str()), (0, ""), "{args:?}");
+        stdout
+    }
+
+    #[test]
+    fn test_parse_list() {
+        let ranges = |list| -> Vec<(usize, usize)> {
+            let list = List::parse(list, Unit::Positions).unwrap();
+            list.ranges()
+                .iter()
+                .map(|range| (range.start, range.end))
+                .collect()
+        };
+        assert_eq!(ranges("3"), [(2, 3)]);
+        assert_eq!(ranges("2-4"), [(1, 4)]);
+        assert_eq!(ranges("-3"), [(0, 3)]);
+        assert_eq!(ranges("4-"), [(3, usize::MAX)]);
+        assert_eq!(ranges("5-5"), [(4, 5)]);
+        // Out of order and overlapping ranges are sorted and merged.
+        assert_eq!(ranges("7,2-4,3-6"), [(1, 6), (6, 7)]);
+        assert_eq!(ranges("3-,1,5"), [(0, 1), (2, usize::MAX)]);
+        assert_eq!(ranges("1 3\t5"), [(0, 1), (2, 3), (4, 5)]);
+        // Ranges which only touch are kept apart.
+        assert_eq!(ranges("1,2"), [(0, 1), (1, 2)]);
+        assert_eq!(ranges("2,2"), [(1, 2)]);
+    }
+
+    #[test]
+    fn test_invalid_lists() {
+        for (list, message) in [
+            ("0", "byte/character positions are numbered from 1"),
+            ("", "byte/character positions are numbered from 1"),
+            ("1,,2", "byte/character positions are numbered from 1"),
+            ("1  2", "byte/character positions are numbered from 1"),
+            ("1,", "byte/character positions are numbered from 1"),
+            ("0-2", "byte/character positions are numbered from 1"),
+            ("3-2", "invalid decreasing range"),
+            ("2-0", "invalid decreasing range"),
+            ("-0", "invalid decreasing range"),
+            ("x", "invalid byte/character position 'x'"),
+            ("1-x", "invalid byte/character position 'x'"),
+            ("1,2x,3", "invalid byte/character position 'x,3'"),
+            ("-", "invalid range with no endpoint: -"),
+            ("1-2-3", "invalid byte or character range"),
+            ("--", "invalid byte or character range"),
+            (
+                "99999999999999999999",
+                "byte/character offset '99999999999999999999' is too large",
+            ),
+            (
+                "1-18446744073709551615",
+                "byte/character offset '18446744073709551615' is too large",
+            ),
+        ] {
+            assert_eq!(
+                List::parse(list, Unit::Positions),
+                Err(message.to_owned()),
+                "{list:?}"
+            );
+            assert_eq!(
+                run_util_stdin(run, &["cut", "-b", list], b"abc\n"),
+                (
+                    1,
+                    String::new(),
+                    format!("cut: {message}\nTry 'cut --help' for more information.\n")
+                ),
+                "{list:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_bytes() {
+        assert_eq!(cut_text(&["-b2-4,3-6"], "abcdefgh\n"), "bcdef\n");
+        assert_eq!(cut_text(&["-b", "5-,-2"], "abcdefgh\n"), "abefgh\n");
+        assert_eq!(cut_text(&["--bytes=3-"], "abcdef\nxy\n"), "cdef\n\n");
+        assert_eq!(cut_text(&["-b", "-3"], "abcdef\nxy\n"), "abc\nxy\n");
+        // The bytes are printed in the order of the line, each at most once.
+        assert_eq!(cut_text(&["-b", "4,1,2-3,1"], "abcdef\n"), "abcd\n");
+        assert_eq!(cut_text(&["-b", "1 3"], "abcdef\n"), "ac\n");
+    }
+
+    #[test]
+    fn test_short_lines() {
+        assert_eq!(cut_text(&["-b2-4"], "a\nab\nabc\n\n"), "\nb\nbc\n\n");
+        assert_eq!(cut_text(&["-b", "9"], "abc\n"), "\n");
+    }
+
+    #[test]
+    fn test_characters() {
+        assert_eq!(cut_text(&["-c2-3"], "abcd\n"), "bc\n");
+        assert_eq!(cut_text(&["--characters", "-1", "-n"], "xyz\n"), "x\n");
+    }
+
+    #[test]
+    fn test_last_line_without_newline() {
+        assert_eq!(cut_text(&["-b2-"], "abc"), "bc\n");
+        assert_eq!(cut_text(&["-b1"], ""), "");
+    }
+
+    #[test]
+    fn test_zero_terminated() {
+        assert_eq!(cut_text(&["-z", "-b1"], "ab\0c\nd\0"), "a\0c\0");
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        for (args, message) in [
+            (
+                &[][..],
+                "you must specify a list of bytes, characters, or fields",
+            ),
+            (
+                &["-s"],
+                "you must specify a list of bytes, characters, or fields",
+            ),
+            (&["-b1", "-c1"], "only one list may be specified"),
+            (&["-f1", "-b1"], "only one list may be specified"),
+            (
+                &["-f1", "-d", "ab"],
+                "the delimiter must be a single character",
+            ),
+            (
+                &["-f1", "-d", "é"],
+                "the delimiter must be a single character",
+            ),
+            (
+                &["-b1", "-d:"],
+                "an input delimiter may be specified only when operating on fields",
+            ),
+            (
+                &["-c1", "-s"],
+                "suppressing non-delimited lines makes sense\n\tonly when operating on fields",
+            ),
+            (&["-f", "0"], "fields are numbered from 1"),
+        ] {
+            let command_line: Vec<&str> =
+                std::iter::once("cut").chain(args.iter().copied()).collect();
+            assert_eq!(
+                run_util_stdin(run, &command_line, b"a\n"),
+                (
+                    1,
+                    String::new(),
+                    format!("cut: {message}\nTry 'cut --help' for more information.\n")
+                ),
+                "{args:?}"
+            );
+        }
+        // The last list of an option is used.
+        assert_eq!(cut_text(&["-b1", "-b2"], "ab\n"), "b\n");
+    }
+
+    #[test]
+    fn test_invalid_field_lists() {
+        for (list, message) in [
+            ("0", "fields are numbered from 1"),
+            ("1,,2", "fields are numbered from 1"),
+            ("3-2", "invalid decreasing range"),
+            ("x", "invalid field value 'x'"),
+            ("2-x,3", "invalid field value 'x,3'"),
+            ("-", "invalid range with no endpoint: -"),
+            ("1-2-3", "invalid field range"),
+            (
+                "99999999999999999999",
+                "field number '99999999999999999999' is too large",
+            ),
+        ] {
+            assert_eq!(
+                List::parse(list, Unit::Fields),
+                Err(message.to_owned()),
+                "{list:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_complement_list() {
+        let complement = |list| -> Vec<(usize, usize)> {
+            let list = List::parse(list, Unit::Positions).unwrap().complement();
+            list.ranges()
+                .iter()
+                .map(|range| (range.start, range.end))
+                .collect()
+        };
+        assert_eq!(complement("2,4-5"), [(0, 1), (2, 3), (5, usize::MAX)]);
+        assert_eq!(complement("3-"), [(0, 2)]);
+        assert_eq!(complement("-2"), [(2, usize::MAX)]);
+        assert_eq!(complement("1,2"), [(2, usize::MAX)]);
+        assert_eq!(complement("1-"), []);
+    }
+
+    #[test]
+    fn test_fields() {
+        let passwd = "root:x:0:0:root:/root:/bin/bash\n\
+                      daemon:x:1:1:daemon:/usr/sbin:/usr/sbin/nologin\n";
+        assert_eq!(cut_text(&["-d:", "-f1"], passwd), "root\ndaemon\n");
+        assert_eq!(
+            cut_text(&["-d", ":", "-f", "7,1,3-4"], passwd),
+            "root:0:0:/bin/bash\ndaemon:1:1:/usr/sbin/nologin\n"
+        );
+        assert_eq!(
+            cut_text(&["--delimiter=:", "--fields=6-"], passwd),
+            "/root:/bin/bash\n/usr/sbin:/usr/sbin/nologin\n"
+        );
+        // Fields are separated by tabs by default.
+        assert_eq!(cut_text(&["-f2"], "a\tb c\td\n"), "b c\n");
+        // Empty fields are kept.
+        assert_eq!(cut_text(&["-d:", "-f2-"], "a:b:c\n::\n:x\n"), "b:c\n:\nx\n");
+        assert_eq!(cut_text(&["-d:", "-f1,2"], "a:\n"), "a:\n");
+    }
+
+    #[test]
+    fn test_fields_beyond_the_line() {
+        assert_eq!(cut_text(&["-d:", "-f5"], "a:b:c\n"), "\n");
+        assert_eq!(cut_text(&["-d:", "-f2,5"], "a:b:c\n"), "b\n");
+        assert_eq!(cut_text(&["-d:", "-f3-9"], "a:b:c\n"), "c\n");
+    }
+
+    #[test]
+    fn test_lines_without_delimiter() {
+        let input = "a:b\nnodelim\n\n";
+        assert_eq!(cut_text(&["-d:", "-f2"], input), "b\nnodelim\n\n");
+        assert_eq!(cut_text(&["-d:", "-f2", "-s"], input), "b\n");
+        assert_eq!(cut_text(&["-d:", "-f2", "--only-delimited"], "x"), "");
+    }
+
+    #[test]
+    fn test_delimiter() {
+        assert_eq!(cut_text(&["-d", "-", "-f2"], "a-b\n"), "b\n");
+        assert_eq!(cut_text(&["-d", " ", "-f1,3"], "a b c\n"), "a c\n");
+        // Like GNU, an empty delimiter is a null byte.
+        assert_eq!(cut_text(&["-d", "", "-f2"], "a\0b\n"), "b\n");
+        // Fields can contain newlines with -z.
+        assert_eq!(cut_text(&["-z", "-d:", "-f2"], "a:b\nc\0"), "b\nc\0");
+    }
+
+    #[test]
+    fn test_complement() {
+        assert_eq!(
+            cut_text(&["-d:", "--complement", "-f2-"], "a:b:c\nx\n"),
+            "a\nx\n"
+        );
+        assert_eq!(cut_text(&["-d:", "--complement", "-f1-"], "a:b\n"), "\n");
+        assert_eq!(
+            cut_text(&["-d:", "--complement", "-f", "-2"], "a:b:c:d\n"),
+            "c:d\n"
+        );
+        assert_eq!(
+            cut_text(&["--complement", "-b3-"], "abcdef\nx\n"),
+            "ab\nx\n"
+        );
+        assert_eq!(cut_text(&["--complement", "-c", "2"], "a:b:c\n"), "ab:c\n");
+    }
+
+    #[test]
+    fn test_output_delimiter() {
+        assert_eq!(
+            cut_text(
+                &["-d:", "-f1,3-", "--output-delimiter", " -> "],
+                "a:b:c:d\n"
+            ),
+            "a -> c -> d\n"
+        );
+        assert_eq!(
+            cut_text(&["-d:", "-f1,2", "--output-delimiter="], "a:b\n"),
+            "ab\n"
+        );
+        // Only what is printed is joined.
+        assert_eq!(
+            cut_text(&["-d:", "-f2,5", "--output-delimiter=::"], "a:b:c\n"),
+            "b\n"
+        );
+        // Ranges of bytes are separated unless they overlap, even when they touch.
+        assert_eq!(
+            cut_text(&["-b1-2,4,5-", "--output-delimiter=||"], "abcdef\nab\n"),
+            "ab||d||ef\nab\n"
+        );
+        assert_eq!(
+            cut_text(&["-b2-3,3-4", "--output-delimiter=:"], "abcd\n"),
+            "bcd\n"
+        );
+        assert_eq!(
+            cut_text(
+                &["-b2,4", "--complement", "--output-delimiter=-"],
+                "abcdef\n"
+            ),
+            "a-c-ef\n"
+        );
+    }
+
+    #[test]
+    fn test_files() {
+        let fixture = Fixture::new(&[("a", "abc\ndef\n"), ("b", "ghi")]);
+        let (a, b) = (fixture.path("a"), fixture.path("b"));
+        assert_eq!(cut_text(&["-b2", &a, &b], ""), "b\ne\nh\n");
+        assert_eq!(cut_text(&["-b1", &a, "-", &a], "xyz\n"), "a\nd\nx\na\nd\n");
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let fixture = Fixture::new(&[("a", "abc\n")]);
+        let (missing, a) = (fixture.path("missing"), fixture.path("a"));
+        assert_eq!(
+            run_util_stdin(run, &["cut", "-b1", &missing, &a], b""),
+            (
+                1,
+                "a\n".to_owned(),
+                format!("cut: {missing}: No such file or directory\n")
+            )
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_directory() {
+        let fixture = Fixture::new(&[("a", "abc\n")]);
+        let directory = fixture.path("");
+        let directory = directory.trim_end_matches('/');
+        assert_eq!(
+            run_util_stdin(run, &["cut", "-b1", directory, &fixture.path("a")], b""),
+            (
+                1,
+                "a\n".to_owned(),
+                format!("cut: {directory}: Is a directory\n")
+            )
+        );
+    }
+
+    #[test]
+    fn test_run_write_error() {
+        let args: Vec<OsString> = vec!["cut".into(), "-b1-".into()];
+        let mut stdout = io::Cursor::new([0; 4]);
+        let mut stderr = Vec::new();
+        let code = run(
+            &args,
+            &mut &b"line 1\nline 2\n"[..],
+            &mut stdout,
+            &mut stderr,
+        );
+        assert_eq!(code, 1);
+        assert_eq!(
+            stderr,
+            b"cut: write error: failed to write the buffered data\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_broken_pipe() {
+        use assert_cmd::cargo::CommandCargoExt;
+        use std::os::unix::process::ExitStatusExt;
+        use std::process::Stdio;
+
+        let content: String = (0..200_000).map(|n| format!("{n}\n")).collect();
+        let fixture = Fixture::new(&[("a", &content)]);
+        let mut child = std::process::Command::cargo_bin("cut")
+            .unwrap()
+            .args(["-b1-", &fixture.path("a")])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        drop(child.stdout.take());
+        let output = child.wait_with_output().unwrap();
+        assert_eq!(output.status.signal(), Some(13));
+        assert!(output.stderr.is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_full_disk() {
+        use assert_cmd::prelude::*;
+        use std::fs::File;
+
+        let fixture = Fixture::new(&[("a", "abc\n")]);
+        let output = std::process::Command::cargo_bin("cut")
+            .unwrap()
+            .args(["-b1", &fixture.path("a")])
+            .stdout(File::create("/dev/full").unwrap())
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(
+            String::from_utf8(output.stderr).unwrap(),
+            "cut: write error: No space left on device\n"
+        );
+    }
+}
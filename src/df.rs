@@ -0,0 +1,621 @@
+//! Print the space used and available on the mounted file systems.
+//!
+//! The rows are made of the mount table and of the usage of each file system, so that they can be built
+//! from any values:
+//!
+//! ```
+//! use rust_coreutils::common::mounts::Mount;
+//! use rust_coreutils::df::{header, row, DfOptions, Usage};
+//! use std::path::PathBuf;
+//!
+//! let mount = Mount {
+//!     source: "/dev/sda1".into(),
+//!     target: PathBuf::from("/"),
+//!     fs_type: "ext4".to_owned(),
+//! };
+//! let usage = Usage {
+//!     fragment_size: 4096,
+//!     blocks: 1000,
+//!     free: 300,
+//!     available: 250,
+//!     files: 100,
+//!     files_free: 40,
+//! };
+//! let options = DfOptions::default();
+//! assert_eq!(
+//!     header(&options),
+//!     ["Filesystem", "1K-blocks", "Used", "Available", "Use%", "Mounted on"]
+//! );
+//! // 700 blocks are used out of the 950 which are not reserved.
+//! assert_eq!(
+//!     row(&mount, &usage, &options),
+//!     ["/dev/sda1", "4000", "2800", "1000", "74%", "/"].map(|cell| cell.as_bytes().to_vec())
+//! );
+//! ```
+
+use crate::common::error::{Reporter, UError, UResult, EXIT_FAILURE};
+use crate::common::human::{human_readable, Base, BlockSize};
+use crate::common::mounts::{mounts, Mount};
+use crate::common::os::os_str_bytes;
+use crate::common::utility::{parse_args, uumain as run_uumain, UtilCommand, Utility};
+use clap::Parser;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, ErrorKind, Read, Write};
+use std::path::Path;
+use unicode_width::UnicodeWidthStr;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "df",
+    author,
+    version,
+    about = "Print the space used and available on the file systems containing each FILE, or on all of them.",
+    long_about = None,
+    args_override_self = true,
+    disable_help_flag = true
+)]
+struct Args {
+    /// Print help.
+    #[arg(long, action = clap::ArgAction::Help)]
+    help: Option<bool>,
+
+    /// Also print the file systems without any blocks, and the ones mounted several times.
+    #[arg(short = 'a', long, default_value_t = false)]
+    all: bool,
+
+    /// Print the sizes in powers of 1024, like 1.5K and 234M.
+    #[arg(short = 'h', long, default_value_t = false, overrides_with = "si")]
+    human_readable: bool,
+
+    /// Print the sizes in powers of 1000, like 1.5k and 234M.
+    #[arg(
+        short = 'H',
+        long,
+        default_value_t = false,
+        overrides_with = "human_readable"
+    )]
+    si: bool,
+
+    /// Print the inodes instead of the blocks.
+    #[arg(short = 'i', long, default_value_t = false)]
+    inodes: bool,
+
+    /// Files on the file systems to print, instead of all of them.
+    #[arg(value_name = "FILE")]
+    files: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "df";
+}
+
+/// The description of df for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run df with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    run_uumain(run, args)
+}
+
+/// Run df with the given command line, including the program name, on the given streams, and return the
+/// exit code.
+pub fn run(
+    args: &[OsString],
+    _stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(args.iter().cloned(), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let block_size = if args.human_readable {
+        BlockSize::Human(Base::Base1024)
+    } else if args.si {
+        BlockSize::Human(Base::Base1000)
+    } else {
+        BlockSize::from_env("DF_BLOCK_SIZE").unwrap_or_else(BlockSize::default_size)
+    };
+    let options = DfOptions {
+        all: args.all,
+        block_size,
+        inodes: args.inodes,
+    };
+    let mut reporter = Reporter::new("df", stderr);
+    let result = df(&args.files, &options, stdout, &mut reporter);
+    reporter.finish(result)
+}
+
+/// The options of df.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DfOptions {
+    /// Also print the file systems without blocks, and the ones hidden by others (`-a`).
+    pub all: bool,
+    /// How to print the sizes (`-h`, `-H`), in 1K blocks by default.
+    pub block_size: BlockSize,
+    /// Print the inodes instead of the blocks (`-i`).
+    pub inodes: bool,
+}
+
+impl Default for DfOptions {
+    fn default() -> Self {
+        DfOptions {
+            all: false,
+            block_size: BlockSize::blocks(1024),
+            inodes: false,
+        }
+    }
+}
+
+/// The usage of a file system, as told by `statvfs`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// The size of the blocks counted by the other fields.
+    pub fragment_size: u64,
+    /// The number of blocks.
+    pub blocks: u64,
+    /// The number of free blocks, including the ones reserved for root.
+    pub free: u64,
+    /// The number of free blocks available to the other users.
+    pub available: u64,
+    /// The number of inodes.
+    pub files: u64,
+    /// The number of free inodes.
+    pub files_free: u64,
+}
+
+/// Get the usage of the file system containing a file.
+#[cfg(unix)]
+// The types of the fields depend on the platform.
+#[allow(clippy::unnecessary_cast)]
+pub fn usage(path: &Path) -> io::Result<Usage> {
+    use std::ffi::CString;
+    use std::mem::MaybeUninit;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| io::Error::from_raw_os_error(libc::ENOENT))?;
+    let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: the path is null-terminated, and statvfs fills the structure when it succeeds.
+    if unsafe { libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: statvfs succeeded.
+    let stat = unsafe { stat.assume_init() };
+    Ok(Usage {
+        fragment_size: stat.f_frsize as u64,
+        blocks: stat.f_blocks as u64,
+        free: stat.f_bfree as u64,
+        available: stat.f_bavail as u64,
+        files: stat.f_files as u64,
+        files_free: stat.f_ffree as u64,
+    })
+}
+
+/// Get the usage of the file system containing a file, which is not supported on this platform.
+#[cfg(not(unix))]
+pub fn usage(_path: &Path) -> io::Result<Usage> {
+    Err(ErrorKind::Unsupported.into())
+}
+
+/// Print the usage of the file systems containing the operands, or of all the mounted file systems.
+///
+/// Without operands nor `all`, the file systems without blocks are skipped, like `proc`, and so are the
+/// ones mounted several times but once, like GNU.
+pub fn df<W: Write>(
+    operands: &[OsString],
+    options: &DfOptions,
+    stdout: &mut dyn Write,
+    reporter: &mut Reporter<W>,
+) -> UResult<()> {
+    let table = mounts()
+        .map_err(|error| UError::io("cannot read table of mounted file systems", &error))?;
+
+    let mut rows = Vec::new();
+    if operands.is_empty() {
+        for mount in visible_mounts(&table, options.all) {
+            match usage(&mount.target) {
+                Ok(usage) if options.all || usage.blocks > 0 => {
+                    rows.push(row(mount, &usage, options));
+                }
+                Ok(_) => {}
+                // The mount points which cannot be reached are not interesting.
+                Err(error)
+                    if matches!(
+                        error.kind(),
+                        ErrorKind::NotFound | ErrorKind::PermissionDenied
+                    ) => {}
+                Err(error) => reporter.io_error(mount.target.display(), &error),
+            }
+        }
+    }
+    for operand in operands {
+        let path = Path::new(operand);
+        let found = fs::canonicalize(path).and_then(|canonical| {
+            let mount = containing_mount(&table, &canonical)
+                .ok_or_else(|| io::Error::from(ErrorKind::NotFound))?;
+            Ok((mount, usage(path)?))
+        });
+        match found {
+            Ok((mount, usage)) => rows.push(row(mount, &usage, options)),
+            Err(error) => reporter.io_error(path.display(), &error),
+        }
+    }
+
+    if rows.is_empty() {
+        if reporter.failed() {
+            return Ok(());
+        }
+        return Err(UError::new(EXIT_FAILURE, "no file systems processed"));
+    }
+    rows.insert(0, header(options).map(String::into_bytes));
+    write_table(&rows, stdout).or_else(UError::write)
+}
+
+/// Get the mounts to print without operands: all of them with `all`, or only one for each file system,
+/// preferring the ones whose source is a path, then the ones with the shortest mount points, and the
+/// ones mounted last over the same mount point, which hide the others.
+fn visible_mounts(table: &[Mount], all: bool) -> Vec<&Mount> {
+    if all {
+        return table.iter().collect();
+    }
+    // The device of each mount point, which is the one mounted last over it.
+    let mut devices: Vec<(Option<u64>, &Mount)> = Vec::new();
+    for mount in table {
+        let device = device(&mount.target);
+        let Some(kept) = devices
+            .iter_mut()
+            .find(|(other, _)| device.is_some() && *other == device)
+        else {
+            devices.push((device, mount));
+            continue;
+        };
+        let is_path = |mount: &Mount| os_str_bytes(&mount.source).contains(&b'/');
+        let length = |mount: &Mount| mount.target.as_os_str().len();
+        if (is_path(mount) && !is_path(kept.1))
+            || length(kept.1) > length(mount)
+            || kept.1.target == mount.target
+        {
+            kept.1 = mount;
+        }
+    }
+    devices.into_iter().map(|(_, mount)| mount).collect()
+}
+
+#[cfg(unix)]
+fn device(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).ok().map(|metadata| metadata.dev())
+}
+
+#[cfg(not(unix))]
+fn device(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// Get the mount containing a canonical path: the one with the longest mount point which contains it,
+/// and the last one mounted if there are several.
+pub fn containing_mount<'a>(table: &'a [Mount], path: &Path) -> Option<&'a Mount> {
+    // The maximum which is returned is the last one.
+    table
+        .iter()
+        .filter(|mount| path.starts_with(&mount.target))
+        .max_by_key(|mount| mount.target.components().count())
+}
+
+/// Get the header of the table.
+pub fn header(options: &DfOptions) -> [String; 6] {
+    let columns = match (options.inodes, &options.block_size) {
+        (true, _) => ["Inodes", "IUsed", "IFree", "IUse%"].map(String::from),
+        (false, BlockSize::Human(_)) => ["Size", "Used", "Avail", "Use%"].map(String::from),
+        (false, BlockSize::Blocks { size, unit }) => {
+            let size = match unit {
+                Some(unit) => format!("1{unit}"),
+                None => block_size_name(*size),
+            };
+            [
+                format!("{size}-blocks"),
+                "Used".into(),
+                "Available".into(),
+                "Use%".into(),
+            ]
+        }
+    };
+    let [total, used, available, percent] = columns;
+    [
+        "Filesystem".into(),
+        total,
+        used,
+        available,
+        percent,
+        "Mounted on".into(),
+    ]
+}
+
+/// Get the name of a block size in the header, like `1K` or `512B`, like GNU: in powers of 1024 if it is
+/// a multiple of 1024, and in powers of 1000 with a `B` otherwise.
+fn block_size_name(size: u64) -> String {
+    let name = match size % 1024 {
+        0 => human_readable(size, Base::Base1024),
+        _ => human_readable(size, Base::Base1000) + "B",
+    };
+    name.replacen(".0", "", 1)
+}
+
+/// Get the cells of the row of a file system.
+pub fn row(mount: &Mount, usage: &Usage, options: &DfOptions) -> [Vec<u8>; 6] {
+    let (total, used, available) = match options.inodes {
+        true => (
+            usage.files,
+            usage.files.saturating_sub(usage.files_free),
+            usage.files_free,
+        ),
+        false => (
+            usage.blocks,
+            usage.blocks.saturating_sub(usage.free),
+            usage.available,
+        ),
+    };
+    let size = |count: u64| match (options.inodes, &options.block_size) {
+        (true, BlockSize::Human(base)) => human_readable(count, *base),
+        (true, _) => count.to_string(),
+        (false, block_size) => block_size.format(count.saturating_mul(usage.fragment_size)),
+    };
+    [
+        os_str_bytes(&mount.source).into_owned(),
+        size(total).into_bytes(),
+        size(used).into_bytes(),
+        size(available).into_bytes(),
+        percent(used, available).into_bytes(),
+        os_str_bytes(mount.target.as_os_str()).into_owned(),
+    ]
+}
+
+/// Get the percentage of the space used out of the space available to the users, rounded up, or `-`
+/// without space.
+fn percent(used: u64, available: u64) -> String {
+    let total = u128::from(used) + u128::from(available);
+    match total {
+        0 => "-".to_owned(),
+        _ => format!("{}%", (u128::from(used) * 100).div_ceil(total)),
+    }
+}
+
+/// Write the rows with their columns aligned: the file systems to the left, in at least 14 columns, the
+/// numbers to the right, in at least 5 columns or 4 for the percentages, and the mount points last,
+/// without padding.
+fn write_table(rows: &[[Vec<u8>; 6]], stdout: &mut dyn Write) -> io::Result<()> {
+    let width = |cell: &[u8]| String::from_utf8_lossy(cell).width();
+    let columns = 6;
+    let widths: Vec<usize> = (0..columns)
+        .map(|column| {
+            let minimum = match column {
+                0 => 14,
+                4 => 4,
+                _ => 5,
+            };
+            rows.iter()
+                .map(|row| width(&row[column]))
+                .fold(minimum, usize::max)
+        })
+        .collect();
+    for row in rows {
+        let mut line = Vec::new();
+        for (column, cell) in row.iter().enumerate() {
+            let padding = b" ".repeat(widths[column] - width(cell));
+            if column > 0 {
+                line.push(b' ');
+            }
+            match column {
+                0 => line.extend_from_slice(&[&cell[..], &padding].concat()),
+                _ if column + 1 == columns => line.extend_from_slice(cell),
+                _ => line.extend_from_slice(&[&padding, &cell[..]].concat()),
+            }
+        }
+        line.push(b'\n');
+        stdout.write_all(&line)?;
+    }
+    stdout.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::mounts::parse_mountinfo;
+    #[cfg(target_os = "linux")]
+    use crate::common::testing::run_util;
+
+    const MOUNTINFO: &[u8] = b"\
+        23 28 0:22 / /proc rw,relatime - proc proc rw\n\
+        28 1 254:0 / / rw,relatime - ext4 /dev/vda rw\n\
+        29 28 254:16 / /home rw,relatime - ext4 /dev/vdb rw\n\
+        30 29 0:40 / /home/user/mnt\\040point rw - tmpfs tmpfs rw\n\
+        31 29 0:41 / /home rw,relatime - xfs /dev/vdc rw\n";
+
+    #[test]
+    fn test_header() {
+        let header = |block_size, inodes| {
+            header(&DfOptions {
+                block_size,
+                inodes,
+                ..DfOptions::default()
+            })
+            .join(" ")
+        };
+        assert_eq!(
+            header(BlockSize::blocks(1024), false),
+            "Filesystem 1K-blocks Used Available Use% Mounted on"
+        );
+        assert_eq!(
+            header(BlockSize::Human(Base::Base1000), false),
+            "Filesystem Size Used Avail Use% Mounted on"
+        );
+        assert_eq!(
+            header(BlockSize::Human(Base::Base1024), true),
+            "Filesystem Inodes IUsed IFree IUse% Mounted on"
+        );
+        // The block sizes are named like GNU.
+        for (size, name) in [
+            ("512", "512B"),
+            ("1000", "1kB"),
+            ("1536", "1.6kB"),
+            ("2048", "2K"),
+            ("1M", "1M"),
+            ("MB", "1MB"),
+            ("1", "1B"),
+        ] {
+            assert_eq!(
+                header(BlockSize::parse(size).unwrap(), false)
+                    .split(' ')
+                    .nth(1),
+                Some(format!("{name}-blocks").as_str()),
+                "{size}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_row() {
+        let mounts = parse_mountinfo(MOUNTINFO);
+        let usage = Usage {
+            fragment_size: 4096,
+            blocks: 65_899_201,
+            free: 44_177_500,
+            available: 40_000_000,
+            files: 16_777_216,
+            files_free: 16_217_906,
+        };
+        let row = |mount: &Mount, usage: &Usage, block_size, inodes| {
+            let options = DfOptions {
+                block_size,
+                inodes,
+                ..DfOptions::default()
+            };
+            row(mount, usage, &options)
+                .map(|cell| String::from_utf8(cell).unwrap())
+                .join(" ")
+        };
+        // The percentage is rounded up, out of the space which is not reserved.
+        assert_eq!(
+            row(&mounts[1], &usage, BlockSize::blocks(1024), false),
+            "/dev/vda 263596804 86886804 160000000 36% /"
+        );
+        assert_eq!(
+            row(&mounts[1], &usage, BlockSize::Human(Base::Base1024), false),
+            "/dev/vda 252G 83G 153G 36% /"
+        );
+        assert_eq!(
+            row(&mounts[1], &usage, BlockSize::Human(Base::Base1000), false),
+            "/dev/vda 270G 89G 164G 36% /"
+        );
+        assert_eq!(
+            row(&mounts[3], &usage, BlockSize::blocks(1024), true),
+            "tmpfs 16777216 559310 16217906 4% /home/user/mnt point"
+        );
+        assert_eq!(
+            row(&mounts[3], &usage, BlockSize::Human(Base::Base1024), true),
+            "tmpfs 16M 547K 16M 4% /home/user/mnt point"
+        );
+        // The file systems without any blocks have no percentage.
+        assert_eq!(
+            row(
+                &mounts[0],
+                &Usage::default(),
+                BlockSize::blocks(1024),
+                false
+            ),
+            "proc 0 0 0 - /proc"
+        );
+    }
+
+    #[test]
+    fn test_containing_mount() {
+        let mounts = parse_mountinfo(MOUNTINFO);
+        let target = |path: &str| {
+            containing_mount(&mounts, Path::new(path)).map(|mount| mount.source.clone())
+        };
+        assert_eq!(target("/etc/passwd"), Some("/dev/vda".into()));
+        assert_eq!(target("/proc"), Some("proc".into()));
+        assert_eq!(target("/homework"), Some("/dev/vda".into()));
+        // The last file system mounted on a directory hides the others.
+        assert_eq!(target("/home/user"), Some("/dev/vdc".into()));
+        assert_eq!(target("/home/user/mnt point/file"), Some("tmpfs".into()));
+        assert_eq!(target("relative"), None);
+    }
+
+    #[test]
+    fn test_write_table() {
+        let rows = [
+            [
+                "Filesystem",
+                "1K-blocks",
+                "Used",
+                "Available",
+                "Use%",
+                "Mounted on",
+            ],
+            ["/dev/a-long-device-name", "100", "1", "99", "1%", "/"],
+            ["tmpfs", "0", "0", "0", "-", "/mnt"],
+        ]
+        .map(|row| row.map(|cell| cell.as_bytes().to_vec()));
+        let mut stdout = Vec::new();
+        write_table(&rows, &mut stdout).unwrap();
+        assert_eq!(
+            String::from_utf8(stdout).unwrap(),
+            "Filesystem              1K-blocks  Used Available Use% Mounted on\n\
+             /dev/a-long-device-name       100     1        99   1% /\n\
+             tmpfs                           0     0         0    - /mnt\n"
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_df() {
+        let (code, stdout, stderr) = run_util(run, &["df"]);
+        assert_eq!((code, stderr.as_str()), (0, ""));
+        let mut lines = stdout.lines();
+        let header: Vec<&str> = lines.next().unwrap().split_whitespace().collect();
+        assert_eq!(
+            header,
+            [
+                "Filesystem",
+                "1K-blocks",
+                "Used",
+                "Available",
+                "Use%",
+                "Mounted",
+                "on"
+            ]
+        );
+        assert!(lines.any(|line| line.ends_with(" /")), "{stdout}");
+
+        let (code, stdout, stderr) = run_util(run, &["df", "-i", "/", "/missing"]);
+        assert_eq!(code, EXIT_FAILURE);
+        assert_eq!(stderr, "df: /missing: No such file or directory\n");
+        let header: Vec<&str> = stdout.lines().next().unwrap().split_whitespace().collect();
+        assert_eq!(
+            header,
+            [
+                "Filesystem",
+                "Inodes",
+                "IUsed",
+                "IFree",
+                "IUse%",
+                "Mounted",
+                "on"
+            ]
+        );
+        assert!(stdout.lines().nth(1).unwrap().ends_with(" /"), "{stdout}");
+        assert_eq!(stdout.lines().count(), 2);
+    }
+}
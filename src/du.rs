@@ -0,0 +1,853 @@
+//! Print the disk usage of files and directory trees.
+//!
+//! The usage of a directory includes that of everything below it, and the directories are printed after
+//! their contents:
+//!
+//! ```
+//! use rust_coreutils::common::error::Reporter;
+//! use rust_coreutils::common::human::BlockSize;
+//! use rust_coreutils::du::{du, DuOptions};
+//! use std::ffi::OsString;
+//!
+//! let directory = tempfile::tempdir().unwrap();
+//! let root = directory.path().join("root");
+//! std::fs::create_dir_all(root.join("sub")).unwrap();
+//! std::fs::write(root.join("sub/file"), vec![0; 3000]).unwrap();
+//! let options = DuOptions {
+//!     all: true,
+//!     apparent_size: true,
+//!     block_size: BlockSize::blocks(1),
+//!     ..DuOptions::default()
+//! };
+//!
+//! let mut stdout = Vec::new();
+//! let mut stderr = Vec::new();
+//! let mut reporter = Reporter::new("du", &mut stderr);
+//! du(&[OsString::from(&root)], &options, &mut stdout, &mut reporter).unwrap();
+//! let sizes: Vec<u64> = String::from_utf8(stdout)
+//!     .unwrap()
+//!     .lines()
+//!     .map(|line| line.split('\t').next().unwrap().parse().unwrap())
+//!     .collect();
+//! // The file, then the directories, which also count their own size.
+//! assert_eq!(sizes[0], 3000);
+//! assert!(sizes[0] < sizes[1] && sizes[1] < sizes[2]);
+//! ```
+
+use crate::common::error::{io_error_message, Reporter, UError, UResult, EXIT_FAILURE};
+use crate::common::glob::fnmatch;
+use crate::common::human::{Base, BlockSize};
+use crate::common::os::os_str_bytes;
+use crate::common::size::SizeErrorKind;
+use crate::common::utility::{parse_matches, uumain as run_uumain, UtilCommand, Utility};
+#[cfg(unix)]
+use crate::common::walk::Entry;
+use crate::common::walk::{Walk, WalkError, WalkOptions};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Parser};
+#[cfg(unix)]
+use std::collections::HashSet;
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, Metadata};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "du",
+    author,
+    version,
+    about = "Print the disk usage of each FILE, and of the directories below it, in 1K blocks by default.",
+    long_about = None,
+    args_override_self = true,
+    disable_help_flag = true
+)]
+struct Args {
+    /// Print help.
+    #[arg(long, action = clap::ArgAction::Help)]
+    help: Option<bool>,
+
+    /// Print the usage of the files too, not only of the directories.
+    #[arg(short = 'a', long, default_value_t = false)]
+    all: bool,
+
+    /// Print the apparent sizes, rather than the disk usage.
+    #[arg(long, default_value_t = false)]
+    apparent_size: bool,
+
+    /// Count the sizes in blocks of SIZE bytes, like 1M, or `human-readable` or `si`.
+    #[arg(short = 'B', long, value_name = "SIZE")]
+    block_size: Option<String>,
+
+    /// Print the apparent sizes in bytes, like `--apparent-size --block-size=1`.
+    #[arg(short = 'b', long, default_value_t = false)]
+    bytes: bool,
+
+    /// Print the total of all the FILEs last.
+    #[arg(short = 'c', long, default_value_t = false)]
+    total: bool,
+
+    /// Print the sizes in powers of 1024, like 1.5K and 234M.
+    #[arg(short = 'h', long, default_value_t = false)]
+    human_readable: bool,
+
+    /// Only print the directories, or the files with -a, which are at most N levels below the FILEs.
+    #[arg(short = 'd', long, value_name = "N")]
+    max_depth: Option<String>,
+
+    /// Only print the total of each FILE, like --max-depth=0.
+    #[arg(short = 's', long, default_value_t = false)]
+    summarize: bool,
+
+    /// Skip the files on other file systems than their FILE.
+    #[arg(short = 'x', long, default_value_t = false)]
+    one_file_system: bool,
+
+    /// Skip the files whose names, or the ends of whose paths, match the shell pattern PATTERN.
+    #[arg(long, value_name = "PATTERN")]
+    exclude: Vec<OsString>,
+
+    /// Skip the files matching any pattern of FILE, one per line, or of standard input for `-`.
+    #[arg(short = 'X', long, value_name = "FILE")]
+    exclude_from: Vec<OsString>,
+
+    /// End each line with a null byte instead of a newline.
+    #[arg(short = '0', long, default_value_t = false)]
+    null: bool,
+
+    /// The files and directories to measure, `.` by default.
+    #[arg(value_name = "FILE")]
+    files: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "du";
+}
+
+/// The description of du for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run du with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    run_uumain(run, args)
+}
+
+/// Run du with the given command line, including the program name, on the given streams, and return the
+/// exit code.
+pub fn run(
+    args: &[OsString],
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let (args, matches): (Args, _) = match parse_matches(args.iter().cloned(), stdout, stderr) {
+        Ok(parsed) => parsed,
+        Err(code) => return code,
+    };
+
+    let mut reporter = Reporter::new("du", stderr);
+    let result = options(&args, &matches, stdin, &mut reporter)
+        .and_then(|options| du(&args.files, &options, stdout, &mut reporter));
+    reporter.finish(result)
+}
+
+fn options<W: Write>(
+    args: &Args,
+    matches: &ArgMatches,
+    stdin: &mut dyn Read,
+    reporter: &mut Reporter<W>,
+) -> UResult<DuOptions> {
+    if args.all && args.summarize {
+        return Err(UError::usage(
+            EXIT_FAILURE,
+            "cannot both summarize and show all entries",
+        ));
+    }
+    let mut max_depth = match &args.max_depth {
+        Some(depth) => Some(depth.parse().map_err(|_| {
+            UError::usage(EXIT_FAILURE, format!("invalid maximum depth '{depth}'"))
+        })?),
+        None => None,
+    };
+    if args.summarize {
+        match max_depth {
+            Some(0) => reporter.warning(format_args!(
+                "warning: summarizing is the same as using --max-depth=0"
+            )),
+            Some(depth) => {
+                return Err(UError::usage(
+                    EXIT_FAILURE,
+                    format!("warning: summarizing conflicts with --max-depth={depth}"),
+                ))
+            }
+            None => max_depth = Some(0),
+        }
+    }
+
+    // The last of the options which say how to count the sizes is the one that counts.
+    let last = ["block_size", "bytes", "human_readable"]
+        .into_iter()
+        .filter_map(|id| Some((last_index(matches, id)?, id)))
+        .max()
+        .map(|(_, id)| id);
+    let block_size = match (last, &args.block_size) {
+        (Some("block_size"), Some(size)) => {
+            BlockSize::parse(size).map_err(|error| match error.kind {
+                SizeErrorKind::TooLarge => {
+                    UError::new(EXIT_FAILURE, format!("-B argument '{size}' too large"))
+                }
+                SizeErrorKind::Invalid => {
+                    UError::new(EXIT_FAILURE, format!("invalid -B argument {error}"))
+                }
+            })?
+        }
+        (Some("bytes"), _) => BlockSize::blocks(1),
+        (Some("human_readable"), _) => BlockSize::Human(Base::Base1024),
+        _ => BlockSize::from_env("DU_BLOCK_SIZE").unwrap_or_else(BlockSize::default_size),
+    };
+
+    let mut exclude: Vec<Vec<u8>> = args
+        .exclude
+        .iter()
+        .map(|pattern| os_str_bytes(pattern).into_owned())
+        .collect();
+    for file in &args.exclude_from {
+        let contents = match file.as_os_str() == "-" {
+            true => {
+                let mut contents = Vec::new();
+                stdin.read_to_end(&mut contents).map(|_| contents)
+            }
+            false => fs::read(file),
+        };
+        // Like in GNU, this is a usage error.
+        let contents = contents.map_err(|error| UError {
+            usage: true,
+            ..UError::io(Path::new(file).display(), &error)
+        })?;
+        exclude.extend(patterns(&contents));
+    }
+
+    Ok(DuOptions {
+        all: args.all,
+        max_depth,
+        apparent_size: args.apparent_size || args.bytes,
+        block_size,
+        null: args.null,
+        one_file_system: args.one_file_system,
+        total: args.total,
+        exclude,
+    })
+}
+
+fn last_index(matches: &ArgMatches, id: &str) -> Option<usize> {
+    // The flags which are not given have a default value, at the end.
+    if matches.value_source(id) != Some(ValueSource::CommandLine) {
+        return None;
+    }
+    matches.indices_of(id)?.max()
+}
+
+/// Get the patterns of a file given to `--exclude-from`, one per line, without the trailing whitespace or
+/// the empty lines, like GNU.
+fn patterns(contents: &[u8]) -> impl Iterator<Item = Vec<u8>> + '_ {
+    contents
+        .split(|&byte| byte == b'\n')
+        .map(|line| line.trim_ascii_end())
+        .filter(|pattern| !pattern.is_empty())
+        .map(<[u8]>::to_vec)
+}
+
+/// The options of du.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DuOptions {
+    /// Print the files below the operands too (`-a`), not only the directories.
+    pub all: bool,
+    /// Only print the entries which are at most this deep below the operands (`--max-depth`, or 0 with
+    /// `-s`). The deeper ones are still counted.
+    pub max_depth: Option<usize>,
+    /// Count the sizes of the files (`--apparent-size`, `-b`), rather than the blocks allocated to them.
+    pub apparent_size: bool,
+    /// How to print the sizes (`-B`, `-h`, `-b`), in 1K blocks by default.
+    pub block_size: BlockSize,
+    /// End the lines with a null byte (`-0`), rather than a newline.
+    pub null: bool,
+    /// Skip the files on other file systems than their operand (`-x`).
+    pub one_file_system: bool,
+    /// Print the total of all the operands last (`-c`).
+    pub total: bool,
+    /// The shell patterns of the files to skip (`--exclude`, `--exclude-from`), matched against the whole
+    /// paths and against their ends after each slash.
+    pub exclude: Vec<Vec<u8>>,
+}
+
+impl Default for DuOptions {
+    fn default() -> Self {
+        DuOptions {
+            all: false,
+            max_depth: None,
+            apparent_size: false,
+            block_size: BlockSize::blocks(1024),
+            null: false,
+            one_file_system: false,
+            total: false,
+            exclude: Vec::new(),
+        }
+    }
+}
+
+/// Print the disk usage of each operand, or of `.` without operands, continuing after the failures.
+///
+/// A file with several hard links, or a directory given twice, is only counted the first time it is seen.
+pub fn du<W: Write>(
+    operands: &[OsString],
+    options: &DuOptions,
+    stdout: &mut dyn Write,
+    reporter: &mut Reporter<W>,
+) -> UResult<()> {
+    let current = [OsString::from(".")];
+    let operands = if operands.is_empty() {
+        &current[..]
+    } else {
+        operands
+    };
+    let mut counter = Counter {
+        options,
+        reporter,
+        stdout,
+        #[cfg(unix)]
+        seen: HashSet::new(),
+    };
+    let mut total = 0;
+    for operand in operands {
+        match counter.count_tree(Path::new(operand)) {
+            Ok(usage) => total += usage,
+            Err(error) => return UError::write(error),
+        }
+    }
+    if options.total {
+        if let Err(error) = counter.print(total, OsStr::new("total")) {
+            return UError::write(error);
+        }
+    }
+    counter.stdout.flush().or_else(UError::write)
+}
+
+/// Counts the disk usage of the trees, remembering the files which were seen.
+struct Counter<'a, W: Write> {
+    options: &'a DuOptions,
+    reporter: &'a mut Reporter<W>,
+    stdout: &'a mut dyn Write,
+    /// The device and inode of the directories and of the files with several links.
+    #[cfg(unix)]
+    seen: HashSet<(u64, u64)>,
+}
+
+/// A directory whose contents are being counted.
+struct PendingDirectory {
+    path: PathBuf,
+    /// The usage of the directory itself and of its contents so far.
+    usage: u64,
+}
+
+impl<W: Write> Counter<'_, W> {
+    /// Print the usage of the directories below `root`, deepest first, and of the other files with `-a`,
+    /// and get the usage of the whole tree.
+    fn count_tree(&mut self, root: &Path) -> io::Result<u64> {
+        let walk_options = WalkOptions {
+            one_file_system: self.options.one_file_system,
+            ..WalkOptions::default()
+        };
+        let mut walk = Walk::new(vec![root.to_path_buf()], walk_options);
+        // The directories containing the current entry, by depth.
+        let mut pending: Vec<PendingDirectory> = Vec::new();
+        let mut total = 0;
+        #[cfg(unix)]
+        let mut root_dev = 0;
+        // The last entry which was skipped, whose contents may not be readable, which is not an error.
+        let mut skipped = None;
+        while let Some(entry) = walk.next() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(WalkError::Io { path, error }) => {
+                    if skipped.as_ref() == Some(&path) {
+                        continue;
+                    }
+                    let depth = pending
+                        .iter()
+                        .take_while(|directory| path.starts_with(&directory.path))
+                        .count();
+                    total += self.finish_directories(&mut pending, depth)?;
+                    // A directory whose contents cannot be read comes right before its error, and it is still
+                    // counted with what could be read.
+                    let action = match pending.last() {
+                        Some(directory) if directory.path == path => "cannot read directory",
+                        _ => "cannot access",
+                    };
+                    self.reporter.error(format_args!(
+                        "{action} '{}': {}",
+                        path.display(),
+                        io_error_message(&error)
+                    ));
+                    continue;
+                }
+                // The symbolic links are not followed, so there are no loops.
+                Err(WalkError::Loop { .. }) => continue,
+            };
+            total += self.finish_directories(&mut pending, entry.depth)?;
+            #[cfg(unix)]
+            if entry.depth == 0 {
+                root_dev = entry.dev();
+            }
+            #[cfg(unix)]
+            let other_device = self.options.one_file_system && on_other_device(&entry, root_dev);
+            #[cfg(not(unix))]
+            let other_device = false;
+            // The skipped directories are not even traversed.
+            if other_device || self.excluded(&entry.path) || !self.first_time(&entry.metadata) {
+                walk.skip_current_dir();
+                skipped = Some(entry.path);
+                continue;
+            }
+            skipped = None;
+
+            let usage = usage(&entry.metadata, self.options.apparent_size);
+            if entry.is_dir() {
+                pending.push(PendingDirectory {
+                    path: entry.path,
+                    usage,
+                });
+                continue;
+            }
+            if entry.depth == 0 || (self.options.all && self.shown(entry.depth)) {
+                self.print(usage, entry.path.as_os_str())?;
+            }
+            match pending.last_mut() {
+                Some(directory) => directory.usage += usage,
+                None => total += usage,
+            }
+        }
+        Ok(total + self.finish_directories(&mut pending, 0)?)
+    }
+
+    /// Print the pending directories which are at the given depth or deeper, since all their contents were
+    /// counted, adding their usage to the directories containing them, and get the usage of the root if it
+    /// is one of them.
+    fn finish_directories(
+        &mut self,
+        pending: &mut Vec<PendingDirectory>,
+        depth: usize,
+    ) -> io::Result<u64> {
+        while pending.len() > depth {
+            let directory = pending.pop().unwrap();
+            if self.shown(pending.len()) {
+                self.print(directory.usage, directory.path.as_os_str())?;
+            }
+            match pending.last_mut() {
+                Some(parent) => parent.usage += directory.usage,
+                None => return Ok(directory.usage),
+            }
+        }
+        Ok(0)
+    }
+
+    /// Check whether the entries at a depth are printed.
+    fn shown(&self, depth: usize) -> bool {
+        self.options
+            .max_depth
+            .is_none_or(|max_depth| depth <= max_depth)
+    }
+
+    /// Check whether a path, or its end after one of its slashes, matches one of the patterns to exclude.
+    fn excluded(&self, path: &Path) -> bool {
+        let path = os_str_bytes(path.as_os_str());
+        let ends = path
+            .iter()
+            .enumerate()
+            .filter(|&(_, &byte)| byte == b'/')
+            .map(|(i, _)| &path[i + 1..]);
+        let mut names = std::iter::once(&path[..]).chain(ends);
+        names.any(|name| {
+            self.options
+                .exclude
+                .iter()
+                .any(|pattern| fnmatch(pattern, name))
+        })
+    }
+
+    /// Check whether a file is seen for the first time, remembering it if it can be seen again.
+    #[cfg(unix)]
+    fn first_time(&mut self, metadata: &Metadata) -> bool {
+        use std::os::unix::fs::MetadataExt;
+        if !metadata.is_dir() && metadata.nlink() < 2 {
+            return true;
+        }
+        self.seen.insert((metadata.dev(), metadata.ino()))
+    }
+
+    #[cfg(not(unix))]
+    fn first_time(&mut self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn print(&mut self, usage: u64, name: &OsStr) -> io::Result<()> {
+        let mut line = self.options.block_size.format(usage).into_bytes();
+        line.push(b'\t');
+        line.extend_from_slice(&os_str_bytes(name));
+        line.push(if self.options.null { b'\0' } else { b'\n' });
+        self.stdout.write_all(&line)
+    }
+}
+
+/// Check whether an entry below an operand is on another file system than the operand, on the device
+/// `root_dev`, to skip it with `-x`.
+#[cfg(unix)]
+fn on_other_device(entry: &Entry, root_dev: u64) -> bool {
+    entry.depth > 0 && entry.dev() != root_dev
+}
+
+/// Get the number of bytes allocated to a file, or its size with `apparent_size`.
+#[cfg(unix)]
+fn usage(metadata: &Metadata, apparent_size: bool) -> u64 {
+    match apparent_size {
+        true => metadata.len(),
+        false => std::os::unix::fs::MetadataExt::blocks(metadata) * 512,
+    }
+}
+
+#[cfg(not(unix))]
+fn usage(metadata: &Metadata, apparent_size: bool) -> u64 {
+    match apparent_size {
+        true => metadata.len(),
+        false => metadata.len().div_ceil(512) * 512,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::{run_util, run_util_stdin};
+    use std::fs;
+
+    /// Create a tree with files of known sizes, and get its root.
+    fn make_tree(directory: &Path) -> PathBuf {
+        let root = directory.join("root");
+        fs::create_dir_all(root.join("sub/deeper")).unwrap();
+        fs::write(root.join("a"), vec![0; 1000]).unwrap();
+        fs::write(root.join("sub/b"), vec![0; 3000]).unwrap();
+        fs::write(root.join("sub/deeper/c"), vec![0; 5000]).unwrap();
+        root
+    }
+
+    #[test]
+    fn test_post_order() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = make_tree(directory.path());
+        let path = |name: &str| root.join(name).display().to_string();
+        // The sizes of the directories themselves depend on the file system.
+        let size = |name: &str| fs::metadata(root.join(name)).unwrap().len();
+        let deeper = size("sub/deeper") + 5000;
+        let sub = size("sub") + 3000 + deeper;
+        let total = size("") + 1000 + sub;
+
+        let (code, stdout, stderr) = run_util(run, &["du", "-b", &root.display().to_string()]);
+        assert_eq!((code, stderr.as_str()), (0, ""));
+        assert_eq!(
+            stdout,
+            format!(
+                "{deeper}\t{}\n{sub}\t{}\n{total}\t{}\n",
+                path("sub/deeper"),
+                path("sub"),
+                root.display()
+            )
+        );
+
+        let (_, stdout, _) = run_util(run, &["du", "-ab", &root.display().to_string()]);
+        assert_eq!(
+            stdout,
+            format!(
+                "1000\t{}\n3000\t{}\n5000\t{}\n{deeper}\t{}\n{sub}\t{}\n{total}\t{}\n",
+                path("a"),
+                path("sub/b"),
+                path("sub/deeper/c"),
+                path("sub/deeper"),
+                path("sub"),
+                root.display()
+            )
+        );
+
+        // The files given as operands are always printed, and the lines can end with a null byte.
+        let (_, stdout, _) = run_util(run, &["du", "-sb0", &path("sub"), &path("a")]);
+        assert_eq!(
+            stdout,
+            format!("{sub}\t{}\x001000\t{}\0", path("sub"), path("a"))
+        );
+        assert_eq!(
+            run_util(run, &["du", "-as", &root.display().to_string()]),
+            (
+                EXIT_FAILURE,
+                String::new(),
+                "du: cannot both summarize and show all entries\n\
+                 Try 'du --help' for more information.\n"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = make_tree(directory.path());
+        let root = root.display().to_string();
+        let (_, full, _) = run_util(run, &["du", "-ab", &root]);
+
+        // The deeper entries are still counted, but only the shallow ones are printed.
+        let (code, stdout, stderr) = run_util(run, &["du", "-ab", "--max-depth=1", &root]);
+        assert_eq!((code, stderr.as_str()), (0, ""));
+        let shallow: Vec<&str> = full
+            .lines()
+            .filter(|line| {
+                let path = line.split('\t').nth(1).unwrap();
+                path[root.len()..].matches('/').count() <= 1
+            })
+            .collect();
+        assert_eq!(stdout.lines().collect::<Vec<_>>(), shallow);
+        assert_eq!(stdout.lines().last(), full.lines().last());
+        assert_eq!(
+            run_util(run, &["du", "-b", "-d0", &root]).1,
+            run_util(run, &["du", "-bs", &root]).1
+        );
+
+        assert_eq!(
+            run_util(run, &["du", "-s", "-d0", &root]).2,
+            "du: warning: summarizing is the same as using --max-depth=0\n"
+        );
+        assert_eq!(
+            run_util(run, &["du", "-s", "-d1", &root]),
+            (
+                EXIT_FAILURE,
+                String::new(),
+                "du: warning: summarizing conflicts with --max-depth=1\n\
+                 Try 'du --help' for more information.\n"
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            run_util(run, &["du", "--max-depth=-1", &root]).2,
+            "du: invalid maximum depth '-1'\nTry 'du --help' for more information.\n"
+        );
+    }
+
+    #[test]
+    fn test_total() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = make_tree(directory.path());
+        let path = |name: &str| root.join(name).display().to_string();
+        let size = |name: &str| fs::metadata(root.join(name)).unwrap().len();
+        let sub = size("sub") + size("sub/deeper") + 8000;
+
+        let (code, stdout, _) = run_util(run, &["du", "-cb", &path("sub"), &path("a")]);
+        assert_eq!(code, 0);
+        assert_eq!(
+            stdout.lines().last().unwrap(),
+            format!("{}\ttotal", sub + 1000)
+        );
+        // The files seen again are not counted again.
+        let (_, stdout, _) = run_util(
+            run,
+            &["du", "-csb", &root.display().to_string(), &path("sub")],
+        );
+        assert_eq!(
+            stdout,
+            format!(
+                "{}\t{}\n{}\ttotal\n",
+                size("") + 1000 + sub,
+                root.display(),
+                size("") + 1000 + sub
+            )
+        );
+    }
+
+    #[test]
+    fn test_exclude() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = make_tree(directory.path());
+        let path = |name: &str| root.join(name).display().to_string();
+        let size = |name: &str| fs::metadata(root.join(name)).unwrap().len();
+        let sub = size("sub") + 3000;
+        let expected = format!(
+            "1000\t{}\n3000\t{}\n{sub}\t{}\n{}\t{}\n",
+            path("a"),
+            path("sub/b"),
+            path("sub"),
+            size("") + 1000 + sub,
+            root.display()
+        );
+
+        // The excluded tree is removed from both the output and the sums, whether the pattern matches its
+        // name or the end of its path.
+        let root = root.display().to_string();
+        for pattern in ["deeper", "d*r", "sub/deep?r", "*/sub/[d]eeper"] {
+            let exclude = format!("--exclude={pattern}");
+            assert_eq!(
+                run_util(run, &["du", "-ab", &exclude, &root]),
+                (0, expected.clone(), String::new()),
+                "{pattern}"
+            );
+        }
+        assert_eq!(
+            run_util_stdin(
+                run,
+                &["du", "-ab", "-X", "-", &root],
+                b"nothing\n\nub/deeper \n"
+            )
+            .1,
+            run_util(run, &["du", "-ab", &root]).1
+        );
+        assert_eq!(
+            run_util_stdin(
+                run,
+                &["du", "-ab", "--exclude-from=-", &root],
+                b"x\ndeeper \n"
+            )
+            .1,
+            expected
+        );
+        assert_eq!(
+            run_util(run, &["du", "--exclude=*", &root]),
+            (0, String::new(), String::new())
+        );
+    }
+
+    #[test]
+    fn test_human_readable() {
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("file");
+        fs::write(&file, vec![1; 1536 * 1024]).unwrap();
+        let file = file.display().to_string();
+
+        // The last of -b and -h says how to print the sizes, while -b also counts the apparent sizes.
+        assert_eq!(
+            run_util(run, &["du", "-bh", &file]).1,
+            format!("1.5M\t{file}\n")
+        );
+        assert_eq!(
+            run_util(run, &["du", "-hb", &file]).1,
+            format!("1572864\t{file}\n")
+        );
+        assert_eq!(
+            run_util(run, &["du", "--apparent-size", "-B1K", &file]).1,
+            format!("1536\t{file}\n")
+        );
+        assert_eq!(
+            run_util(run, &["du", "--apparent-size", "--block-size=MB", &file]).1,
+            format!("2MB\t{file}\n")
+        );
+        assert_eq!(
+            run_util(run, &["du", "-B", "0", &file]).2,
+            "du: invalid -B argument '0'\n"
+        );
+        // A file written without holes takes at least its size on disk.
+        let (_, stdout, _) = run_util(run, &["du", &file]);
+        let blocks: u64 = stdout.split('\t').next().unwrap().parse().unwrap();
+        assert!(blocks >= 1536, "{stdout}");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_hard_links() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = make_tree(directory.path());
+        fs::hard_link(root.join("a"), root.join("sub/link")).unwrap();
+        let path = |name: &str| root.join(name).display().to_string();
+
+        // A file is only counted once, and so is a directory given twice.
+        let (_, stdout, _) = run_util(run, &["du", "-ab", &root.display().to_string()]);
+        assert!(
+            stdout.contains(&format!("1000\t{}\n", path("a"))),
+            "{stdout}"
+        );
+        assert!(!stdout.contains(&path("sub/link")), "{stdout}");
+        let (_, stdout, _) = run_util(run, &["du", "-sb", &path("sub"), &path("sub")]);
+        assert_eq!(stdout.lines().count(), 1, "{stdout}");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_other_device() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = make_tree(directory.path());
+        let walk = Walk::new(vec![root], WalkOptions::default());
+        let entries: Vec<Entry> = walk.map(Result::unwrap).collect();
+        let dev = entries[0].dev();
+        assert!(entries.iter().all(|entry| !on_other_device(entry, dev)));
+        // The operand itself is never skipped.
+        let other: Vec<usize> = entries
+            .iter()
+            .filter(|entry| on_other_device(entry, dev + 1))
+            .map(|entry| entry.depth)
+            .collect();
+        assert_eq!(other.len(), entries.len() - 1);
+        assert!(!other.contains(&0));
+
+        let root = entries[0].path.display().to_string();
+        assert_eq!(
+            run_util(run, &["du", "-x", &root]),
+            run_util(run, &["du", &root])
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_errors() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = tempfile::tempdir().unwrap();
+        let root = make_tree(directory.path());
+        let path = |name: &str| root.join(name).display().to_string();
+        let missing = path("missing");
+        let (code, _, stderr) = run_util(run, &["du", &missing]);
+        assert_eq!(
+            (code, stderr),
+            (
+                EXIT_FAILURE,
+                format!("du: cannot access '{missing}': No such file or directory\n")
+            )
+        );
+
+        fs::set_permissions(root.join("sub"), fs::Permissions::from_mode(0o300)).unwrap();
+        // Root can read the directory anyway.
+        if fs::read_dir(root.join("sub")).is_ok() {
+            return;
+        }
+        // The sums continue with what could be read.
+        let size = |name: &str| fs::metadata(root.join(name)).unwrap().len();
+        let (code, stdout, stderr) = run_util(run, &["du", "-b", &root.display().to_string()]);
+        fs::set_permissions(root.join("sub"), fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(code, EXIT_FAILURE);
+        assert_eq!(
+            stderr,
+            format!(
+                "du: cannot read directory '{}': Permission denied\n",
+                path("sub")
+            )
+        );
+        assert_eq!(
+            stdout,
+            format!(
+                "{}\t{}\n{}\t{}\n",
+                size("sub"),
+                path("sub"),
+                size("") + 1000 + size("sub"),
+                root.display()
+            )
+        );
+    }
+}
@@ -1,20 +1,45 @@
-// Implement the fish-shell version of echo.
-
-use clap::Parser;
+//! The fish-shell version of echo.
+//!
+//! The arguments are rendered into bytes with [`render`], so that the output can be written anywhere:
+//!
+//! ```
+//! use rust_coreutils::echo::{render, EchoOptions};
+//! use std::ffi::OsString;
+//!
+//! let args = [OsString::from("hello"), OsString::from(r"world\t!")];
+//! let options = EchoOptions {
+//!     escapes: true,
+//!     ..EchoOptions::default()
+//! };
+//! assert_eq!(render(&args, &options), b"hello world\t!\n");
+//! ```
+
+use crate::common::error::{Reporter, UError};
+use crate::common::escapes::{expand_escapes_into, EscapeFlavor};
+use crate::common::os::os_str_bytes;
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use clap::{Parser, ValueEnum};
+use std::ffi::{OsStr, OsString};
+use std::io::{Read, Write};
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(
+    name = "echo",
+    author,
+    version,
+    about = "Display a line of text.",
+    long_about = None,
+    args_override_self = true
+)]
 struct Args {
     /// Do not output a newline.
-    #[arg(short, default_value_t = false)]
+    #[arg(short, long = "no-newline", default_value_t = false)]
     n: bool,
 
     /// Do not separate arguments with spaces.
-    #[arg(short, default_value_t = false)]
+    #[arg(short, long = "no-space", default_value_t = false)]
     s: bool,
 
-    // Ignore the -E option, as it is the default behaviour.
-    // Also, clap does not support multiple short options for a single argument.
     /// Enable interpretation of backslash escapes.
     /// The following sequences are recognized:
     ///   • \ backslash
@@ -27,65 +52,274 @@ struct Args {
     ///   • \r carriage return
     ///   • \t horizontal tab
     ///   • \v vertical tab
-    ///   • \0NNN byte with octal value NNN (1 to 3 digits)
+    ///   • \0NNN byte with octal value NNN (0 to 3 digits)
     ///   • \xHH byte with hexadecimal value HH (1 to 2 digits)
-    #[arg(short, default_value_t = false, verbatim_doc_comment)]
+    ///   • \uHHHH Unicode character with hexadecimal value HHHH (1 to 4 digits)
+    ///   • \UHHHHHHHH Unicode character with hexadecimal value HHHHHHHH (1 to 8 digits)
+    #[arg(
+        short,
+        long = "escapes",
+        default_value_t = false,
+        overrides_with = "no_escapes",
+        verbatim_doc_comment
+    )]
     e: bool,
 
-    data: Vec<String>,
+    /// Disable interpretation of backslash escapes (default).
+    /// If both -e and -E are given, the last one wins.
+    #[arg(
+        short = 'E',
+        long = "no-escapes",
+        default_value_t = false,
+        overrides_with = "e"
+    )]
+    no_escapes: bool,
+
+    /// Imitate the echo of another implementation: fish (default), GNU, or POSIX.
+    /// The POSIX mode is also enabled by the POSIXLY_CORRECT environment variable.
+    #[arg(long, value_enum, value_name = "MODE")]
+    compat: Option<Compat>,
+
+    data: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "echo";
 }
 
-pub fn main() {
-    let args = Args::parse();
+/// The description of echo for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run echo with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run echo with the given command line, including the program name, on the given streams, and return the
+/// exit code. Standard input is not read.
+pub fn run(
+    args: &[OsString],
+    _stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let default_compat = Compat::from_env();
+    let mut args: Args =
+        match parse_args(split_options(args.to_vec(), default_compat), stdout, stderr) {
+            Ok(args) => args,
+            Err(code) => return code,
+        };
+    args.compat.get_or_insert(default_compat);
 
     let data = echo(args);
 
-    print!("{data}");
+    let reporter = Reporter::new("echo", stderr);
+    let result = stdout
+        .write_all(&data)
+        .and_then(|()| stdout.flush())
+        .or_else(UError::write);
+    reporter.finish(result)
+}
+
+/// The flavour of echo to imitate.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+enum Compat {
+    /// fish: -n, -s, -e, and -E are recognized, and escapes are disabled by default.
+    #[default]
+    Fish,
+    /// GNU coreutils: -n, -e, and -E are recognized, and escapes are disabled by default.
+    Gnu,
+    /// POSIX: only a first -n is recognized, and escapes are always interpreted.
+    Posix,
+}
+
+impl Compat {
+    /// Get the mode used when `--compat` is not given, which is `posix` if `POSIXLY_CORRECT` is set.
+    fn from_env() -> Self {
+        if std::env::var_os("POSIXLY_CORRECT").is_some() {
+            Compat::Posix
+        } else {
+            Compat::Fish
+        }
+    }
+
+    /// Check whether an argument consists only of the short flags supported in this mode, like `-n` or
+    /// `-nsE`.
+    fn is_flag_cluster(self, argument: &OsStr) -> bool {
+        let supported: &[u8] = match self {
+            Compat::Fish => b"nseE",
+            Compat::Gnu => b"neE",
+            Compat::Posix => b"n",
+        };
+
+        // The flags are ASCII, so the platform-specific encoding of the rest of the argument does not matter.
+        match argument.as_encoded_bytes().split_first() {
+            Some((b'-', flags)) => {
+                !flags.is_empty() && flags.iter().all(|flag| supported.contains(flag))
+            }
+            _ => false,
+        }
+    }
+
+    /// Check whether an argument is one of the long options supported in this mode, like `--no-newline`.
+    fn is_long_option(self, argument: &OsStr) -> bool {
+        let supported: &[&str] = match self {
+            Compat::Fish => &["--no-newline", "--no-space", "--escapes", "--no-escapes"],
+            Compat::Gnu => &["--no-newline", "--escapes", "--no-escapes"],
+            Compat::Posix => &[],
+        };
+
+        supported.iter().any(|option| argument == *option)
+    }
+}
+
+impl Args {
+    /// Resolve the command-line flags and the compatibility mode into the behaviour of echo.
+    fn options(&self) -> EchoOptions {
+        let compat = self.compat.unwrap_or_default();
+        EchoOptions {
+            newline: !self.n,
+            separator: if self.s && compat == Compat::Fish {
+                b"".to_vec()
+            } else {
+                b" ".to_vec()
+            },
+            escapes: match compat {
+                Compat::Fish | Compat::Gnu => self.e,
+                Compat::Posix => true,
+            },
+        }
+    }
+}
+
+/// Split the command line into the options and the operands.
+///
+/// Only a leading run of flag clusters and long options valid in the compatibility mode (like `-nse` and
+/// `--no-newline` for fish) is treated as options. In the POSIX mode, only a single `-n` is recognized.
+/// Everything after the options, including arguments that merely look like options and `--`, is an operand
+/// that is printed verbatim.
+/// The result can be passed to clap, as the operands are separated from the options with `--`.
+///
+/// The leading run can also select the compatibility mode with `--compat=MODE`, which overrides `default`.
+/// `--help` and `--version` are recognized only when they are the sole argument.
+fn split_options<I>(args: I, default: Compat) -> Vec<OsString>
+where
+    I: IntoIterator<Item = OsString>,
+{
+    let mut args: Vec<OsString> = args.into_iter().collect();
+
+    if let [_, argument] = args.as_slice() {
+        if argument == "--help" || argument == "--version" {
+            return args;
+        }
+    }
+
+    let mut compat = default;
+    let mut flag_clusters = 0;
+    let mut operands_start = 1;
+    while let Some(argument) = args.get(operands_start) {
+        if let Some(mode) = argument
+            .to_str()
+            .and_then(|argument| argument.strip_prefix("--compat="))
+        {
+            // Invalid modes are left for clap to report.
+            compat = Compat::from_str(mode, false).unwrap_or(compat);
+        } else if compat.is_flag_cluster(argument)
+            && (compat != Compat::Posix || flag_clusters == 0)
+        {
+            flag_clusters += 1;
+        } else if compat.is_long_option(argument) {
+            // Nothing to do, the long option is parsed by clap.
+        } else {
+            break;
+        }
+        operands_start += 1;
+    }
+
+    args.insert(operands_start.min(args.len()), OsString::from("--"));
+    args
+}
+
+/// Render the parsed command line.
+fn echo(args: Args) -> Vec<u8> {
+    render(&args.data, &args.options())
 }
 
-fn echo(args: Args) -> String {
-    let line_ending = if args.n { "" } else { "\n" };
-    let arguments_join = if args.s { "" } else { " " };
-
-    let mut data = args.data.join(arguments_join) + line_ending;
-    if args.e {
-        data = data.replace("\\\\", "\\");
-        data = data.replace("\\a", "\x07");
-        data = data.replace("\\b", "\x08");
-
-        let truncate_offset = data.find("\\c").unwrap_or(data.len());
-        data.truncate(truncate_offset);
-
-        data = data.replace("\\e", "\x1b");
-        data = data.replace("\\f", "\x0c");
-        data = data.replace("\\n", "\n");
-        data = data.replace("\\r", "\r");
-        data = data.replace("\\t", "\t");
-        data = data.replace("\\v", "\x0b");
-
-        // Use regex to find \0NNN and \xHH.
-        let octals = regex::Regex::new(r"\\0[0-7]{1,3}").unwrap();
-        data = octals
-            .replace_all(&data, |caps: &regex::Captures| {
-                let octal = caps.get(0).unwrap().as_str();
-                let octal = &octal[2..];
-                let octal = u8::from_str_radix(octal, 8).unwrap();
-                let octal = std::char::from_u32(octal as u32).unwrap();
-                octal.to_string()
-            })
-            .to_string();
-        let hexadecimals = regex::Regex::new(r"\\x[0-9a-fA-F]{1,2}").unwrap();
-        data = hexadecimals
-            .replace_all(&data, |caps: &regex::Captures| {
-                let hexadecimal = caps.get(0).unwrap().as_str();
-                let hexadecimal = &hexadecimal[2..];
-                let hexadecimal = u8::from_str_radix(hexadecimal, 16).unwrap();
-                let hexadecimal = std::char::from_u32(hexadecimal as u32).unwrap();
-                hexadecimal.to_string()
-            })
-            .to_string();
+/// The behaviour of echo.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EchoOptions {
+    /// Output a newline after the arguments.
+    pub newline: bool,
+    /// The bytes printed between the arguments.
+    pub separator: Vec<u8>,
+    /// Interpret backslash escapes in the arguments.
+    pub escapes: bool,
+}
+
+impl Default for EchoOptions {
+    /// The default behaviour of echo: arguments separated with spaces, followed by a newline, without
+    /// escapes.
+    fn default() -> Self {
+        EchoOptions {
+            newline: true,
+            separator: b" ".to_vec(),
+            escapes: false,
+        }
     }
+}
 
+/// Render the arguments into the bytes that echo prints.
+///
+/// When escapes are enabled, `\c` stops the output, including the newline:
+///
+/// ```
+/// use rust_coreutils::echo::{render, EchoOptions};
+/// use std::ffi::OsString;
+///
+/// let args = [OsString::from(r"a\cb"), OsString::from("c")];
+/// let options = EchoOptions {
+///     escapes: true,
+///     ..EchoOptions::default()
+/// };
+/// assert_eq!(render(&args, &options), b"a");
+/// ```
+pub fn render(args: &[OsString], options: &EchoOptions) -> Vec<u8> {
+    let line_ending: &[u8] = if options.newline { b"\n" } else { b"" };
+
+    // Escape sequences are never shorter than what they expand to, so the output fits without reallocations.
+    let capacity = args.iter().map(|argument| argument.len()).sum::<usize>()
+        + options.separator.len() * args.len().saturating_sub(1)
+        + line_ending.len();
+    let mut data = Vec::with_capacity(capacity);
+    for (index, argument) in args.iter().enumerate() {
+        if index > 0 {
+            data.extend_from_slice(&options.separator);
+        }
+
+        let argument = os_str_bytes(argument);
+        if !options.escapes {
+            data.extend_from_slice(&argument);
+            continue;
+        }
+
+        // Escapes are expanded per argument, so that a backslash at the end of one argument
+        // cannot form an escape sequence with the beginning of the next one.
+        let terminated = expand_escapes_into(&argument, EscapeFlavor::Echo, &mut data);
+        if terminated {
+            // `\c` suppresses all further output, including the trailing newline.
+            return data;
+        }
+    }
+
+    data.extend_from_slice(line_ending);
     data
 }
 
@@ -95,6 +329,94 @@ mod tests {
     use assert_cmd::prelude::*;
     use std::process::Command;
 
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    /// Run echo in-process, returning the exit code, stdout, and stderr.
+    fn run_echo(command_line: &[&str], stdout: &mut dyn Write) -> (i32, Vec<u8>) {
+        let mut stderr = Vec::new();
+        let code = run(&args(command_line), &mut &b""[..], stdout, &mut stderr);
+        (code, stderr)
+    }
+
+    #[test]
+    fn test_run() {
+        let mut stdout = Vec::new();
+        let (code, stderr) = run_echo(&["echo", "-ne", r"a	b", "c"], &mut stdout);
+        assert_eq!(
+            (code, &stdout[..], &stderr[..]),
+            (0, &b"a\tb c"[..], &b""[..])
+        );
+
+        let mut stdout = Vec::new();
+        let (code, stderr) = run_echo(&["echo"], &mut stdout);
+        assert_eq!((code, &stdout[..], &stderr[..]), (0, &b"\n"[..], &b""[..]));
+    }
+
+    #[test]
+    fn test_run_help_and_usage_error() {
+        let mut stdout = Vec::new();
+        let (code, stderr) = run_echo(&["echo", "--help"], &mut stdout);
+        assert_eq!(code, 0);
+        assert!(String::from_utf8(stdout).unwrap().contains("Usage: echo"));
+        assert_eq!(stderr, b"");
+
+        let mut stdout = Vec::new();
+        let (code, stderr) = run_echo(&["echo", "--compat=nope"], &mut stdout);
+        assert_eq!(code, 2);
+        assert_eq!(stdout, b"");
+        assert!(String::from_utf8(stderr)
+            .unwrap()
+            .contains("invalid value 'nope'"));
+    }
+
+    #[test]
+    fn test_run_write_error() {
+        let mut buffer = [0; 2];
+        let (code, stderr) = run_echo(&["echo", "data"], &mut &mut buffer[..]);
+        assert_eq!(code, 1);
+        assert_eq!(stderr, b"echo: write error: failed to write whole buffer\n");
+        assert_eq!(&buffer, b"da");
+    }
+
+    #[test]
+    fn test_render_default() {
+        let options = EchoOptions::default();
+        assert_eq!(render(&[], &options), b"\n");
+        assert_eq!(render(&args(&["data"]), &options), b"data\n");
+        assert_eq!(
+            render(&args(&["data", "more data"]), &options),
+            b"data more data\n"
+        );
+        assert_eq!(render(&args(&[r"data\n"]), &options), b"data\\n\n");
+    }
+
+    #[test]
+    fn test_render_newline_and_separator() {
+        let options = EchoOptions {
+            newline: false,
+            separator: b", ".to_vec(),
+            ..EchoOptions::default()
+        };
+        assert_eq!(render(&[], &options), b"");
+        assert_eq!(render(&args(&["a", "b", "c"]), &options), b"a, b, c");
+    }
+
+    #[test]
+    fn test_render_escapes() {
+        let options = EchoOptions {
+            escapes: true,
+            ..EchoOptions::default()
+        };
+        assert_eq!(
+            render(&args(&[r"a\tb", r"\x41\0102"]), &options),
+            b"a\tb AB\n"
+        );
+        assert_eq!(render(&args(&[r"\\n", r"\xff"]), &options), b"\\n \xff\n");
+        assert_eq!(render(&args(&["a", r"b\c", "c"]), &options), b"a b");
+    }
+
     #[test]
     fn test_binary() {
         let mut cmd = Command::cargo_bin("echo").unwrap();
@@ -107,109 +429,517 @@ mod tests {
             .assert()
             .success()
             .stdout("datamore \ndata");
+
+        let mut cmd = Command::cargo_bin("echo").unwrap();
+        cmd.arg("-ne")
+            .arg(r"\x00\xff")
+            .assert()
+            .success()
+            .stdout(&[0x00, 0xff][..]);
+    }
+
+    #[test]
+    fn test_binary_literal_options() {
+        let mut cmd = Command::cargo_bin("echo").unwrap();
+        cmd.arg("-x").assert().success().stdout("-x\n");
+
+        let mut cmd = Command::cargo_bin("echo").unwrap();
+        cmd.arg("-n").arg("-x").assert().success().stdout("-x");
+
+        let mut cmd = Command::cargo_bin("echo").unwrap();
+        cmd.arg("--").arg("hi").assert().success().stdout("-- hi\n");
+
+        let mut cmd = Command::cargo_bin("echo").unwrap();
+        cmd.arg("-n")
+            .arg("data")
+            .arg("-e")
+            .arg(r"\t")
+            .assert()
+            .success()
+            .stdout(r"data -e \t");
+
+        let mut cmd = Command::cargo_bin("echo").unwrap();
+        cmd.arg("--help-me")
+            .arg("-")
+            .assert()
+            .success()
+            .stdout("--help-me -\n");
+
+        let mut cmd = Command::cargo_bin("echo").unwrap();
+        cmd.arg("--help")
+            .arg("me")
+            .assert()
+            .success()
+            .stdout("--help me\n");
+    }
+
+    #[test]
+    fn test_split_options() {
+        let split = |args: &[&str]| split_options(args.iter().map(OsString::from), Compat::Fish);
+
+        assert_eq!(split(&["echo"]), ["echo", "--"]);
+        assert_eq!(
+            split(&["echo", "-n", "-se", "-x", "-n"]),
+            ["echo", "-n", "-se", "--", "-x", "-n"]
+        );
+        assert_eq!(split(&["echo", "-nx", "-n"]), ["echo", "--", "-nx", "-n"]);
+        assert_eq!(split(&["echo", "-", "--"]), ["echo", "--", "-", "--"]);
+        assert_eq!(split(&["echo", "--help"]), ["echo", "--help"]);
+        assert_eq!(
+            split(&["echo", "-n", "--version"]),
+            ["echo", "-n", "--", "--version"]
+        );
+
+        let args = Args::parse_from(split(&["echo", "-n", "-n", "-x"]));
+        assert_eq!(echo(args), b"-x");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_binary_broken_pipe() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let (reader, writer) = std::io::pipe().unwrap();
+        drop(reader);
+
+        let mut cmd = Command::cargo_bin("echo").unwrap();
+        let output = cmd.arg("data").stdout(writer).output().unwrap();
+        assert_eq!(output.status.signal(), Some(libc::SIGPIPE));
+        assert!(output.stderr.is_empty());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_binary_write_error() {
+        let full = std::fs::File::create("/dev/full").unwrap();
+
+        let mut cmd = Command::cargo_bin("echo").unwrap();
+        cmd.arg("data")
+            .stdout(full)
+            .assert()
+            .code(1)
+            .stderr("echo: write error: No space left on device\n");
+    }
+
+    #[test]
+    fn test_binary_posixly_correct() {
+        let mut cmd = Command::cargo_bin("echo").unwrap();
+        cmd.env("POSIXLY_CORRECT", "1")
+            .arg("-n")
+            .arg("-e")
+            .arg(r"a\tb")
+            .assert()
+            .success()
+            .stdout("-e a\tb");
+
+        let mut cmd = Command::cargo_bin("echo").unwrap();
+        cmd.env("POSIXLY_CORRECT", "1")
+            .arg("--compat=fish")
+            .arg("-s")
+            .arg(r"a\tb")
+            .arg("c")
+            .assert()
+            .success()
+            .stdout("a\\tbc\n");
+    }
+
+    #[test]
+    fn test_binary_usage_error() {
+        let mut cmd = Command::cargo_bin("echo").unwrap();
+        cmd.arg("--compat=nope")
+            .arg("data")
+            .assert()
+            .code(2)
+            .stdout("")
+            .stderr(predicates::str::contains("invalid value 'nope'"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_binary_non_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let mut cmd = Command::cargo_bin("echo").unwrap();
+        cmd.arg(OsStr::from_bytes(b"a\xff\xfeb"))
+            .arg(OsStr::from_bytes(b"\xc3"))
+            .assert()
+            .success()
+            .stdout(&b"a\xff\xfeb \xc3\n"[..]);
+
+        let mut cmd = Command::cargo_bin("echo").unwrap();
+        cmd.arg("-e")
+            .arg(OsStr::from_bytes(b"\xff\\t\xfe"))
+            .assert()
+            .success()
+            .stdout(&b"\xff\t\xfe\n"[..]);
     }
 
     #[test]
     fn test_no_args() {
-        let args = Args::parse_from(&["echo"]);
-        assert_eq!(echo(args), "\n");
+        let args = Args::parse_from(["echo"]);
+        assert_eq!(echo(args), b"\n");
 
-        let args = Args::parse_from(&["echo", "data"]);
-        assert_eq!(echo(args), "data\n");
+        let args = Args::parse_from(["echo", "data"]);
+        assert_eq!(echo(args), b"data\n");
 
-        let args = Args::parse_from(&["echo", "data", "more data"]);
-        assert_eq!(echo(args), "data more data\n");
+        let args = Args::parse_from(["echo", "data", "more data"]);
+        assert_eq!(echo(args), b"data more data\n");
 
-        let args = Args::parse_from(&["echo", "data", "more data\\n"]);
-        assert_eq!(echo(args), "data more data\\n\n");
+        let args = Args::parse_from(["echo", "data", "more data\\n"]);
+        assert_eq!(echo(args), b"data more data\\n\n");
     }
 
     #[test]
     fn test_n() {
-        let args = Args::parse_from(&["echo", "-n"]);
-        assert_eq!(echo(args), "");
+        let args = Args::parse_from(["echo", "-n"]);
+        assert_eq!(echo(args), b"");
 
-        let args = Args::parse_from(&["echo", "-n", "data"]);
-        assert_eq!(echo(args), "data");
+        let args = Args::parse_from(["echo", "-n", "data"]);
+        assert_eq!(echo(args), b"data");
 
-        let args = Args::parse_from(&["echo", "-n", "data", "more data"]);
-        assert_eq!(echo(args), "data more data");
+        let args = Args::parse_from(["echo", "-n", "data", "more data"]);
+        assert_eq!(echo(args), b"data more data");
 
-        let args = Args::parse_from(&["echo", "-n", "data\\n"]);
-        assert_eq!(echo(args), "data\\n");
+        let args = Args::parse_from(["echo", "-n", "data\\n"]);
+        assert_eq!(echo(args), b"data\\n");
     }
 
     #[test]
     fn test_s() {
-        let args = Args::parse_from(&["echo", "-s"]);
-        assert_eq!(echo(args), "\n");
+        let args = Args::parse_from(["echo", "-s"]);
+        assert_eq!(echo(args), b"\n");
 
-        let args = Args::parse_from(&["echo", "-s", "data"]);
-        assert_eq!(echo(args), "data\n");
+        let args = Args::parse_from(["echo", "-s", "data"]);
+        assert_eq!(echo(args), b"data\n");
 
-        let args = Args::parse_from(&["echo", "-s", "data", "more data"]);
-        assert_eq!(echo(args), "datamore data\n");
+        let args = Args::parse_from(["echo", "-s", "data", "more data"]);
+        assert_eq!(echo(args), b"datamore data\n");
 
-        let args = Args::parse_from(&["echo", "-s", "data\\n"]);
-        assert_eq!(echo(args), "data\\n\n");
+        let args = Args::parse_from(["echo", "-s", "data\\n"]);
+        assert_eq!(echo(args), b"data\\n\n");
     }
 
     #[test]
     fn test_e() {
-        let args = Args::parse_from(&["echo", "-e"]);
-        assert_eq!(echo(args), "\n");
+        let args = Args::parse_from(["echo", "-e"]);
+        assert_eq!(echo(args), b"\n");
+
+        let args = Args::parse_from(["echo", "-e", "data"]);
+        assert_eq!(echo(args), b"data\n");
+
+        let args = Args::parse_from(["echo", "-e", r"data\\", "more data"]);
+        assert_eq!(echo(args), b"data\\ more data\n");
+
+        let args = Args::parse_from(["echo", "-e", r"data\a", "more data"]);
+        assert_eq!(echo(args), b"data\x07 more data\n");
+
+        let args = Args::parse_from(["echo", "-e", r"data\b", "more data"]);
+        assert_eq!(echo(args), b"data\x08 more data\n");
+
+        let args = Args::parse_from(["echo", "-e", r"data \c more data"]);
+        assert_eq!(echo(args), b"data ");
+
+        let args = Args::parse_from(["echo", "-e", r"data\e more data"]);
+        assert_eq!(echo(args), b"data\x1b more data\n");
+
+        let args = Args::parse_from(["echo", "-e", r"data \f more data"]);
+        assert_eq!(echo(args), b"data \x0c more data\n");
+
+        let args = Args::parse_from(["echo", "-e", r"data \n more data"]);
+        assert_eq!(echo(args), b"data \n more data\n");
+
+        let args = Args::parse_from(["echo", "-e", r"data\n"]);
+        assert_eq!(echo(args), b"data\n\n");
+
+        let args = Args::parse_from(["echo", "-e", r"data \r more data"]);
+        assert_eq!(echo(args), b"data \r more data\n");
+
+        let args = Args::parse_from(["echo", "-e", r"data \t more data"]);
+        assert_eq!(echo(args), b"data \t more data\n");
+
+        let args = Args::parse_from(["echo", "-e", r"data \v more data"]);
+        assert_eq!(echo(args), b"data \x0b more data\n");
+
+        let args = Args::parse_from(["echo", "-e", r"data \0153 more data"]);
+        assert_eq!(echo(args), b"data k more data\n");
+
+        let args = Args::parse_from(["echo", "-e", r"data \x75 more data"]);
+        assert_eq!(echo(args), b"data u more data\n");
+    }
+
+    #[test]
+    fn test_e_escaped_backslash() {
+        let args = Args::parse_from(["echo", "-e", r"\\n"]);
+        assert_eq!(echo(args), b"\\n\n");
+
+        let args = Args::parse_from(["echo", "-e", r"\\\n"]);
+        assert_eq!(echo(args), b"\\\n\n");
+
+        let args = Args::parse_from(["echo", "-e", r"\\\\n"]);
+        assert_eq!(echo(args), b"\\\\n\n");
+
+        let args = Args::parse_from(["echo", "-e", r"\\t \\x41"]);
+        assert_eq!(echo(args), b"\\t \\x41\n");
+    }
+
+    #[test]
+    fn test_e_hexadecimal_without_digits() {
+        let args = Args::parse_from(["echo", "-e", r"\xg"]);
+        assert_eq!(echo(args), b"\\xg\n");
+
+        let args = Args::parse_from(["echo", "-e", r"\x"]);
+        assert_eq!(echo(args), b"\\x\n");
+
+        let args = Args::parse_from(["echo", "-e", r"\x41g"]);
+        assert_eq!(echo(args), b"Ag\n");
+    }
+
+    #[test]
+    fn test_e_trailing_backslash() {
+        let args = Args::parse_from(["echo", "-e", "data\\"]);
+        assert_eq!(echo(args), b"data\\\n");
+
+        let args = Args::parse_from(["echo", "-ne", "\\"]);
+        assert_eq!(echo(args), b"\\");
+    }
+
+    #[test]
+    fn test_e_unknown_escape() {
+        let args = Args::parse_from(["echo", "-e", r"\q"]);
+        assert_eq!(echo(args), b"\\q\n");
+    }
+
+    #[test]
+    fn test_e_raw_bytes() {
+        let args = Args::parse_from(["echo", "-ne", r"\xff"]);
+        assert_eq!(echo(args), vec![0xff]);
+
+        let args = Args::parse_from(["echo", "-ne", r"\x00\x80\x7f"]);
+        assert_eq!(echo(args), vec![0x00, 0x80, 0x7f]);
 
-        let args = Args::parse_from(&["echo", "-e", "data"]);
-        assert_eq!(echo(args), "data\n");
+        let args = Args::parse_from(["echo", "-ne", r"\0377\0200\00"]);
+        assert_eq!(echo(args), vec![0xff, 0x80, 0x00]);
 
-        let args = Args::parse_from(&["echo", "-e", r"data\\", "more data"]);
-        assert_eq!(echo(args), "data\\ more data\n");
+        let args = Args::parse_from(["echo", "-ne", r"é\xe9"]);
+        assert_eq!(echo(args), vec![0xc3, 0xa9, 0xe9]);
+    }
+
+    #[test]
+    fn test_e_octal_without_digits() {
+        let args = Args::parse_from(["echo", "-ne", r"\0"]);
+        assert_eq!(echo(args), b"\0");
+
+        let args = Args::parse_from(["echo", "-ne", r"a\0b"]);
+        assert_eq!(echo(args), b"a\0b");
+
+        let args = Args::parse_from(["echo", "-ne", r"\08"]);
+        assert_eq!(echo(args), b"\08");
+    }
+
+    #[test]
+    fn test_e_octal_digit_limit() {
+        let args = Args::parse_from(["echo", "-ne", r"\01234"]);
+        assert_eq!(echo(args), b"\x534");
+
+        let args = Args::parse_from(["echo", "-ne", r"\0101\07"]);
+        assert_eq!(echo(args), b"A\x07");
+
+        let args = Args::parse_from(["echo", "-ne", r"\0777"]);
+        assert_eq!(echo(args), b"\xff");
+
+        let args = Args::parse_from(["echo", "-ne", r"\0400"]);
+        assert_eq!(echo(args), b"\0");
+    }
+
+    #[test]
+    fn test_e_hexadecimal_digit_limit() {
+        let args = Args::parse_from(["echo", "-ne", r"\x414"]);
+        assert_eq!(echo(args), b"A4");
+
+        let args = Args::parse_from(["echo", "-ne", r"\xA"]);
+        assert_eq!(echo(args), b"\n");
+
+        let args = Args::parse_from(["echo", "-ne", r"\xfG"]);
+        assert_eq!(echo(args), b"\x0fG");
+    }
+
+    #[test]
+    fn test_e_unicode() {
+        let args = Args::parse_from(["echo", "-ne", r"\u00e9"]);
+        assert_eq!(echo(args), "é".as_bytes());
+
+        let args = Args::parse_from(["echo", "-ne", r"\U0001F600"]);
+        assert_eq!(echo(args), "😀".as_bytes());
+
+        let args = Args::parse_from(["echo", "-ne", r"\u41\U42"]);
+        assert_eq!(echo(args), b"AB");
+
+        let args = Args::parse_from(["echo", "-ne", r"\u00e9ff"]);
+        assert_eq!(echo(args), "éff".as_bytes());
+
+        let args = Args::parse_from(["echo", "-ne", r"\u \U"]);
+        assert_eq!(echo(args), br"\u \U");
+
+        let args = Args::parse_from(["echo", "-ne", r"\U00110000 \uD800"]);
+        assert_eq!(echo(args), br"\U00110000 \uD800");
+    }
+
+    #[test]
+    fn test_e_c() {
+        let args = Args::parse_from(["echo", "-e", r"\cdata", "more data"]);
+        assert_eq!(echo(args), b"");
+
+        let args = Args::parse_from(["echo", "-e", "data", r"more\c data"]);
+        assert_eq!(echo(args), b"data more");
+
+        let args = Args::parse_from(["echo", "-e", r"da\cta", "more data"]);
+        assert_eq!(echo(args), b"da");
+
+        let args = Args::parse_from(["echo", "-e", r"a\\cb"]);
+        assert_eq!(echo(args), b"a\\cb\n");
+
+        let args = Args::parse_from(["echo", "-e", r"a\\\cb"]);
+        assert_eq!(echo(args), b"a\\");
+    }
+
+    #[test]
+    fn test_e_escapes_do_not_span_arguments() {
+        let args = Args::parse_from(["echo", "-se", "data\\", "n"]);
+        assert_eq!(echo(args), b"data\\n\n");
+    }
+
+    #[test]
+    fn test_e_and_big_e() {
+        let args = Args::parse_from(["echo", "-E", r"a\tb"]);
+        assert_eq!(echo(args), b"a\\tb\n");
+
+        let args = Args::parse_from(["echo", "-e", "-E", r"a\tb"]);
+        assert_eq!(echo(args), b"a\\tb\n");
+
+        let args = Args::parse_from(["echo", "-E", "-e", r"a\tb"]);
+        assert_eq!(echo(args), b"a\tb\n");
 
-        let args = Args::parse_from(&["echo", "-e", r"data\a", "more data"]);
-        assert_eq!(echo(args), "data\x07 more data\n");
+        let args = Args::parse_from(["echo", "-eE", r"a\tb"]);
+        assert_eq!(echo(args), b"a\\tb\n");
 
-        let args = Args::parse_from(&["echo", "-e", r"data\b", "more data"]);
-        assert_eq!(echo(args), "data\x08 more data\n");
+        let args = Args::parse_from(["echo", "-Ee", r"a\tb"]);
+        assert_eq!(echo(args), b"a\tb\n");
 
-        let args = Args::parse_from(&["echo", "-e", r"data \c more data"]);
-        assert_eq!(echo(args), "data ");
+        let args = Args::parse_from(["echo", "-e", "-E", "-e", r"a\tb"]);
+        assert_eq!(echo(args), b"a\tb\n");
+    }
 
-        let args = Args::parse_from(&["echo", "-e", r"data\e more data"]);
-        assert_eq!(echo(args), "data\x1b more data\n");
+    #[test]
+    fn test_long_options() {
+        let args = Args::parse_from(["echo", "--no-newline", "hi"]);
+        let short = Args::parse_from(["echo", "-n", "hi"]);
+        assert_eq!(args.options(), short.options());
+        assert_eq!(echo(args), b"hi");
 
-        let args = Args::parse_from(&["echo", "-e", r"data \f more data"]);
-        assert_eq!(echo(args), "data \x0c more data\n");
+        let args = Args::parse_from(["echo", "--no-space", "a", "b"]);
+        assert_eq!(echo(args), b"ab\n");
 
-        let args = Args::parse_from(&["echo", "-e", r"data \n more data"]);
-        assert_eq!(echo(args), "data \n more data\n");
+        let args = Args::parse_from(["echo", "--escapes", r"a\tb"]);
+        assert_eq!(echo(args), b"a\tb\n");
 
-        let args = Args::parse_from(&["echo", "-e", r"data\n"]);
-        assert_eq!(echo(args), "data\n\n");
+        let args = Args::parse_from(["echo", "--escapes", "--no-escapes", r"a\tb"]);
+        assert_eq!(echo(args), b"a\\tb\n");
 
-        let args = Args::parse_from(&["echo", "-e", r"data \r more data"]);
-        assert_eq!(echo(args), "data \r more data\n");
+        let args = Args::parse_from(["echo", "-E", "--escapes", r"a\tb"]);
+        assert_eq!(echo(args), b"a\tb\n");
 
-        let args = Args::parse_from(&["echo", "-e", r"data \t more data"]);
-        assert_eq!(echo(args), "data \t more data\n");
+        let split = |args: &[&str]| split_options(args.iter().map(OsString::from), Compat::Fish);
+        let args = Args::parse_from(split(&["echo", "--no-newline", "--no-spaces", "-n"]));
+        assert_eq!(echo(args), b"--no-spaces -n");
 
-        let args = Args::parse_from(&["echo", "-e", r"data \v more data"]);
-        assert_eq!(echo(args), "data \x0b more data\n");
+        let args = Args::parse_from(split(&["echo", "--compat=gnu", "--no-space", "a"]));
+        assert_eq!(echo(args), b"--no-space a\n");
+    }
 
-        let args = Args::parse_from(&["echo", "-e", r"data \0153 more data"]);
-        assert_eq!(echo(args), "data k more data\n");
+    #[test]
+    fn test_binary_help() {
+        let mut cmd = Command::cargo_bin("echo").unwrap();
+        let output = cmd
+            .arg("--help")
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        let help = String::from_utf8(output).unwrap();
+        assert!(help.contains("-n, --no-newline"));
+        assert!(help.contains("-s, --no-space"));
+        assert!(help.contains("-e, --escapes"));
+        assert!(help.contains("-E, --no-escapes"));
+    }
+
+    #[test]
+    fn test_compat() {
+        // Each case lists the arguments and the expected output in the fish, GNU, and POSIX modes.
+        let cases: &[(&[&str], [&[u8]; 3])] = &[
+            (&["a", "b"], [b"a b\n", b"a b\n", b"a b\n"]),
+            (&[r"a\tb"], [b"a\\tb\n", b"a\\tb\n", b"a\tb\n"]),
+            (&["-s", "a", "b"], [b"ab\n", b"-s a b\n", b"-s a b\n"]),
+            (&["-e", r"a\tb"], [b"a\tb\n", b"a\tb\n", b"-e a\tb\n"]),
+            (&["-E", r"a\tb"], [b"a\\tb\n", b"a\\tb\n", b"-E a\tb\n"]),
+            (&["-n", "a"], [b"a", b"a", b"a"]),
+            (&["-n", "-n", "a"], [b"a", b"a", b"-n a"]),
+            (&["-ne", r"a\c", "b"], [b"a", b"a", b"-ne a"]),
+            (&["-ns", "a", "b"], [b"ab", b"-ns a b\n", b"-ns a b\n"]),
+        ];
+
+        for (input, expected) in cases {
+            for (compat, expected) in ["fish", "gnu", "posix"].into_iter().zip(expected) {
+                let compat = format!("--compat={compat}");
+                let argv: Vec<&str> = ["echo", &compat]
+                    .into_iter()
+                    .chain(input.iter().copied())
+                    .collect();
+                let args = Args::parse_from(split_options(
+                    argv.into_iter().map(OsString::from),
+                    Compat::Fish,
+                ));
+                assert_eq!(echo(args), *expected, "{compat} {input:?}");
+            }
+        }
+    }
 
-        let args = Args::parse_from(&["echo", "-e", r"data \x75 more data"]);
-        assert_eq!(echo(args), "data u more data\n");
+    #[test]
+    fn test_compat_options() {
+        let args = Args::parse_from(split_options(
+            ["echo", "-nse"].map(OsString::from),
+            Compat::Fish,
+        ));
+        let expected = EchoOptions {
+            newline: false,
+            separator: b"".to_vec(),
+            escapes: true,
+        };
+        assert_eq!(args.options(), expected);
+
+        let mut args = Args::parse_from(split_options(
+            ["echo", "-n", "-E"].map(OsString::from),
+            Compat::Posix,
+        ));
+        args.compat.get_or_insert(Compat::Posix);
+        let expected = EchoOptions {
+            newline: false,
+            separator: b" ".to_vec(),
+            escapes: true,
+        };
+        assert_eq!(args.options(), expected);
+        assert_eq!(args.data, ["-E"]);
     }
+
     #[test]
     fn test_nse() {
-        let args = Args::parse_from(&["echo", "-n", "-s", "-e"]);
-        assert_eq!(echo(args), "");
+        let args = Args::parse_from(["echo", "-n", "-s", "-e"]);
+        assert_eq!(echo(args), b"");
 
-        let args = Args::parse_from(&["echo", "-nse", "data"]);
-        assert_eq!(echo(args), "data");
+        let args = Args::parse_from(["echo", "-nse", "data"]);
+        assert_eq!(echo(args), b"data");
 
-        let args = Args::parse_from(&["echo", "-nse", "data", r"more \ndata"]);
-        assert_eq!(echo(args), "datamore \ndata");
+        let args = Args::parse_from(["echo", "-nse", "data", r"more \ndata"]);
+        assert_eq!(echo(args), b"datamore \ndata");
     }
 }
@@ -0,0 +1,897 @@
+//! Print the first lines of files.
+//!
+//! The inputs are printed with [`head`], so that they can be read from anywhere and written anywhere:
+//!
+//! ```
+//! use rust_coreutils::common::error::Reporter;
+//! use rust_coreutils::common::input::InputIter;
+//! use rust_coreutils::head::{head, Count, HeadOptions};
+//!
+//! let mut inputs = InputIter::with_stdin(vec![], Box::new(&b"1\n2\n3\n"[..]));
+//! let options = HeadOptions {
+//!     count: Count::Lines(2),
+//!     ..HeadOptions::default()
+//! };
+//! let mut stdout = Vec::new();
+//! let mut reporter = Reporter::new("head", Vec::new());
+//! head(&mut inputs, &options, &mut stdout, &mut reporter).unwrap();
+//! assert_eq!(stdout, b"1\n2\n");
+//! ```
+
+use crate::common::error::{io_error_message, Reporter, UError, UResult, EXIT_FAILURE};
+use crate::common::input::{InputIter, BUFFER_SIZE};
+use crate::common::os::os_str_bytes;
+use crate::common::records::{Record, RecordReader, RecordWriter, ZeroTerminatedArgs};
+use crate::common::size::{parse_signed_size, Sign, SizeError, SizeErrorKind};
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use clap::Parser;
+use std::collections::VecDeque;
+use std::ffi::OsString;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "head",
+    author,
+    version,
+    about = "Print the first 10 lines of each FILE. With more than one FILE, each is preceded by a header \
+             with its name.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Print the first NUM bytes. With a leading -, print all but the last NUM bytes. NUM may have a
+    /// multiplier suffix, like K or M.
+    #[arg(
+        short = 'c',
+        long,
+        value_name = "[-]NUM",
+        allow_hyphen_values = true,
+        overrides_with = "lines"
+    )]
+    bytes: Option<String>,
+
+    /// Print the first NUM lines instead of the first 10. With a leading -, print all but the last NUM
+    /// lines. NUM may have a multiplier suffix, like K or M.
+    #[arg(
+        short = 'n',
+        long,
+        value_name = "[-]NUM",
+        allow_hyphen_values = true,
+        overrides_with = "bytes"
+    )]
+    lines: Option<String>,
+
+    /// Never print headers.
+    #[arg(
+        short,
+        long,
+        visible_alias = "silent",
+        default_value_t = false,
+        overrides_with = "verbose"
+    )]
+    quiet: bool,
+
+    /// Always print headers.
+    #[arg(short, long, default_value_t = false, overrides_with = "quiet")]
+    verbose: bool,
+
+    #[command(flatten)]
+    records: ZeroTerminatedArgs,
+
+    /// The files to print. With no FILE, or when FILE is -, standard input is read.
+    #[arg(value_name = "FILE")]
+    files: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "head";
+}
+
+/// The description of head for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run head with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run_on_stdin, args)
+}
+
+/// Run head with the given command line, including the program name, on the given streams, and return the
+/// exit code.
+pub fn run(
+    args: &[OsString],
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let mut stdin = BufReader::with_capacity(BUFFER_SIZE, stdin);
+    run_buffered(args, &mut stdin, stdout, stderr)
+}
+
+/// Run head like [`run`] on the standard input of the process, which is left just after what was printed
+/// when it is a file, like GNU's, so that the next command reads the rest, as in `(head -n 1; cat) < file`.
+fn run_on_stdin(
+    args: &[OsString],
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let mut stdin = BufReader::with_capacity(BUFFER_SIZE, stdin);
+    let code = run_buffered(args, &mut stdin, stdout, stderr);
+    #[cfg(unix)]
+    rewind_stdin(stdin.buffer().len());
+    code
+}
+
+/// Give back the bytes which were read from standard input but not printed, if it is a file.
+///
+/// Only the buffer of head has to be given back: the one of the standard input of Rust is bypassed by
+/// reads which are larger than it.
+#[cfg(unix)]
+fn rewind_stdin(unread: usize) {
+    use std::io::{Seek, SeekFrom};
+    use std::os::fd::AsFd;
+
+    if unread == 0 {
+        return;
+    }
+    // The duplicate shares the offset of standard input.
+    let Ok(stdin) = io::stdin().as_fd().try_clone_to_owned() else {
+        return;
+    };
+    let mut stdin = std::fs::File::from(stdin);
+    if stdin.metadata().is_ok_and(|metadata| metadata.is_file()) {
+        // There is nothing to do about a failure, after which the next command reads less.
+        let _ = stdin.seek(SeekFrom::Current(-(unread as i64)));
+    }
+}
+
+fn run_buffered(
+    args: &[OsString],
+    stdin: &mut dyn BufRead,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(expand_obsolete_count(args.to_vec()), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let mut reporter = Reporter::new("head", stderr);
+    let count = match (&args.bytes, &args.lines) {
+        (Some(bytes), _) => parse_count(bytes, Count::Bytes, Count::AllButLastBytes)
+            .map_err(|error| format!("invalid number of bytes: {error}")),
+        (None, Some(lines)) => parse_count(lines, Count::Lines, Count::AllButLastLines)
+            .map_err(|error| format!("invalid number of lines: {error}")),
+        (None, None) => Ok(Count::Lines(10)),
+    };
+    let count = match count {
+        Ok(count) => count,
+        Err(message) => return reporter.finish(Err(UError::new(EXIT_FAILURE, message))),
+    };
+    let options = HeadOptions {
+        count,
+        headers: !args.quiet && (args.verbose || args.files.len() > 1),
+        delimiter: args.records.delimiter(),
+    };
+
+    let mut inputs = InputIter::with_stdin(args.files, Box::new(stdin));
+    let result = head(&mut inputs, &options, stdout, &mut reporter);
+    reporter.finish(result)
+}
+
+/// Rewrite the obsolete form of the count as a first argument, like `-5` or `-2kc`, into options: the
+/// digits are the count, optionally followed by a multiplier (`b`, `k`, or `m`), a unit (`c` for bytes or `l`
+/// for lines), and the flags `q`, `v`, and `z`.
+fn expand_obsolete_count(mut args: Vec<OsString>) -> Vec<OsString> {
+    let Some(first) = args.get(1).and_then(|arg| arg.to_str()) else {
+        return args;
+    };
+    let Some(spec) = first.strip_prefix('-') else {
+        return args;
+    };
+    let digits = spec.bytes().take_while(u8::is_ascii_digit).count();
+    if digits == 0 {
+        return args;
+    }
+
+    let (number, mut rest) = spec.split_at(digits);
+    let mut count = number.to_owned();
+    if let Some(multiplier) = rest.chars().next().and_then(|c| match c {
+        'b' => Some('b'),
+        'k' => Some('K'),
+        'm' => Some('M'),
+        _ => None,
+    }) {
+        count.push(multiplier);
+        rest = &rest[1..];
+    }
+    let option = match rest.chars().next() {
+        Some('c') => {
+            rest = &rest[1..];
+            "-c"
+        }
+        Some('l') => {
+            rest = &rest[1..];
+            "-n"
+        }
+        _ => "-n",
+    };
+    if !rest.bytes().all(|flag| b"qvz".contains(&flag)) {
+        // Not the obsolete form, leave it for clap to report.
+        return args;
+    }
+
+    let mut expanded = vec![OsString::from(option), OsString::from(count)];
+    expanded.extend(rest.chars().map(|flag| OsString::from(format!("-{flag}"))));
+    args.splice(1..2, expanded);
+    args
+}
+
+/// Parse the argument of `-c` or `-n`, where a leading `-` means all but the last ones.
+fn parse_count(
+    arg: &str,
+    first: fn(u64) -> Count,
+    all_but_last: fn(u64) -> Count,
+) -> Result<Count, SizeError> {
+    let (sign, value) = parse_signed_size(arg)?;
+    let value = u64::try_from(value).map_err(|_| SizeError {
+        kind: SizeErrorKind::TooLarge,
+        input: arg.to_owned(),
+    })?;
+    Ok(match sign {
+        Some(Sign::Minus) => all_but_last(value),
+        _ => first(value),
+    })
+}
+
+/// What to print of each input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Count {
+    /// The first lines.
+    Lines(u64),
+    /// The first bytes.
+    Bytes(u64),
+    /// All but the last lines, which have to be held back until the end of the input is reached.
+    AllButLastLines(u64),
+    /// All but the last bytes, which have to be held back until the end of the input is reached.
+    AllButLastBytes(u64),
+}
+
+/// The behaviour of head.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HeadOptions {
+    /// What to print of each input.
+    pub count: Count,
+    /// Print a header with the name of each input before it.
+    pub headers: bool,
+    /// The byte which ends lines.
+    pub delimiter: u8,
+}
+
+impl Default for HeadOptions {
+    /// The default behaviour of head: the first 10 lines of an input, without a header.
+    fn default() -> Self {
+        HeadOptions {
+            count: Count::Lines(10),
+            headers: false,
+            delimiter: b'\n',
+        }
+    }
+}
+
+/// Print the first lines or bytes of each input, reading no more of them than needed, or all but the last
+/// ones.
+///
+/// An input which cannot be opened or read is reported, and the remaining ones are still printed. A failure
+/// to write is returned, as there is no point in going on. A closed pipe stops without an error.
+pub fn head<W: Write>(
+    inputs: &mut InputIter,
+    options: &HeadOptions,
+    stdout: &mut dyn Write,
+    reporter: &mut Reporter<W>,
+) -> UResult<()> {
+    let mut first = true;
+    while let Some(input) = inputs.next_buffered() {
+        let mut input = match input {
+            Ok(input) => input,
+            Err(error) => {
+                reporter.error(format_args!(
+                    "cannot open '{}' for reading: {}",
+                    error.display_name(),
+                    io_error_message(&error.error)
+                ));
+                continue;
+            }
+        };
+
+        if options.headers {
+            let mut header = if first { Vec::new() } else { b"\n".to_vec() };
+            header.extend_from_slice(b"==> ");
+            match &input.operand {
+                Some(operand) if !input.is_stdin() => {
+                    header.extend_from_slice(&os_str_bytes(operand))
+                }
+                _ => header.extend_from_slice(b"standard input"),
+            }
+            header.extend_from_slice(b" <==\n");
+            if let Err(error) = stdout.write_all(&header) {
+                return UError::write(error);
+            }
+            first = false;
+        }
+
+        let result = match options.count {
+            Count::Lines(lines) => copy_lines(&mut input.reader, lines, options.delimiter, stdout),
+            Count::Bytes(bytes) => copy_bytes(&mut input.reader, bytes, stdout),
+            Count::AllButLastLines(lines) => {
+                copy_all_but_last_lines(&mut input.reader, lines, options.delimiter, stdout)
+            }
+            Count::AllButLastBytes(bytes) => {
+                copy_all_but_last_bytes(&mut input.reader, bytes, stdout)
+            }
+        };
+        match result {
+            Ok(()) => {}
+            Err(Failure::Read(error)) => reporter.error(format_args!(
+                "error reading '{}': {}",
+                input.display_name(),
+                io_error_message(&error)
+            )),
+            Err(Failure::Write(error)) => return UError::write(error),
+        }
+    }
+    stdout.flush().or_else(UError::write)
+}
+
+/// Why printing an input stopped.
+enum Failure {
+    Read(io::Error),
+    Write(io::Error),
+}
+
+/// Copy the first lines of an input, stopping as soon as they are written, so that the rest of the input
+/// is not read beyond the buffer.
+fn copy_lines(
+    reader: &mut dyn BufRead,
+    lines: u64,
+    delimiter: u8,
+    stdout: &mut dyn Write,
+) -> Result<(), Failure> {
+    let mut remaining = lines;
+    while remaining > 0 {
+        let data = fill_buf(reader)?;
+        if data.is_empty() {
+            break;
+        }
+
+        let mut end = data.len();
+        for (position, _) in data
+            .iter()
+            .enumerate()
+            .filter(|&(_, &byte)| byte == delimiter)
+        {
+            remaining -= 1;
+            if remaining == 0 {
+                end = position + 1;
+                break;
+            }
+        }
+        stdout.write_all(&data[..end]).map_err(Failure::Write)?;
+        reader.consume(end);
+    }
+    Ok(())
+}
+
+/// Copy the first bytes of an input, without reading it beyond the buffer.
+fn copy_bytes(reader: &mut dyn BufRead, bytes: u64, stdout: &mut dyn Write) -> Result<(), Failure> {
+    let mut remaining = bytes;
+    while remaining > 0 {
+        let data = fill_buf(reader)?;
+        if data.is_empty() {
+            break;
+        }
+        let end = data
+            .len()
+            .min(usize::try_from(remaining).unwrap_or(usize::MAX));
+        stdout.write_all(&data[..end]).map_err(Failure::Write)?;
+        reader.consume(end);
+        remaining -= end as u64;
+    }
+    Ok(())
+}
+
+/// Copy all but the last lines of an input, keeping a window of the lines which could still be the last
+/// ones. A final line without a delimiter counts as a line.
+fn copy_all_but_last_lines(
+    reader: &mut dyn BufRead,
+    lines: u64,
+    delimiter: u8,
+    stdout: &mut dyn Write,
+) -> Result<(), Failure> {
+    let mut records = RecordReader::new(reader, delimiter);
+    let mut writer = RecordWriter::new(stdout, delimiter);
+    let mut window: VecDeque<(Vec<u8>, bool)> = VecDeque::new();
+    while let Some(record) = records.next_record() {
+        let record = record.map_err(Failure::Read)?;
+        if lines == 0 {
+            writer.write_as_read(record).map_err(Failure::Write)?;
+            continue;
+        }
+
+        // Reuse the allocation of the line which leaves the window.
+        let mut content = if window.len() as u64 == lines {
+            let (content, terminated) = window.pop_front().expect("the window is not empty");
+            writer
+                .write_as_read(Record {
+                    content: &content,
+                    terminated,
+                })
+                .map_err(Failure::Write)?;
+            content
+        } else {
+            Vec::new()
+        };
+        content.clear();
+        content.extend_from_slice(record.content);
+        window.push_back((content, record.terminated));
+    }
+    Ok(())
+}
+
+/// Copy all but the last bytes of an input, keeping a window of the bytes which could still be the last
+/// ones.
+fn copy_all_but_last_bytes(
+    reader: &mut dyn BufRead,
+    bytes: u64,
+    stdout: &mut dyn Write,
+) -> Result<(), Failure> {
+    let bytes = usize::try_from(bytes).unwrap_or(usize::MAX);
+    let mut window = Vec::new();
+    loop {
+        let data = fill_buf(reader)?;
+        if data.is_empty() {
+            return Ok(());
+        }
+        window.extend_from_slice(data);
+        let length = data.len();
+        reader.consume(length);
+
+        if window.len() > bytes {
+            let end = window.len() - bytes;
+            stdout.write_all(&window[..end]).map_err(Failure::Write)?;
+            window.drain(..end);
+        }
+    }
+}
+
+/// Fill the buffer of a reader, retrying when interrupted.
+fn fill_buf(reader: &mut dyn BufRead) -> Result<&[u8], Failure> {
+    loop {
+        match reader.fill_buf() {
+            // Returning the buffer from inside the loop is rejected by the borrow checker, so it is filled
+            // again outside of it, which returns the same buffer without reading.
+            Ok(_) => break,
+            Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+            Err(error) => return Err(Failure::Read(error)),
+        }
+    }
+    reader.fill_buf().map_err(Failure::Read)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::{run_util_stdin, Fixture};
+    use assert_cmd::Command;
+
+    /// Get `count` numbered lines.
+    fn lines(count: usize) -> String {
+        (1..=count).map(|n| format!("{n}\n")).collect()
+    }
+
+    #[test]
+    fn test_default() {
+        assert_eq!(
+            run_util_stdin(run, &["head"], lines(20).as_bytes()),
+            (0, lines(10), String::new())
+        );
+        assert_eq!(run_util_stdin(run, &["head", "-"], b"a\nb").1, "a\nb");
+        assert_eq!(run_util_stdin(run, &["head"], b"").1, "");
+    }
+
+    #[test]
+    fn test_lines() {
+        // Fewer lines than requested.
+        assert_eq!(
+            run_util_stdin(run, &["head", "-n", "5"], b"a\nb\n").1,
+            "a\nb\n"
+        );
+        // Exactly the requested count, with and without a final newline.
+        assert_eq!(
+            run_util_stdin(run, &["head", "-n", "2"], b"a\nb\n").1,
+            "a\nb\n"
+        );
+        assert_eq!(run_util_stdin(run, &["head", "-n", "2"], b"a\nb").1, "a\nb");
+        assert_eq!(
+            run_util_stdin(run, &["head", "-n", "2"], b"a\nb\nc").1,
+            "a\nb\n"
+        );
+        assert_eq!(run_util_stdin(run, &["head", "-n", "0"], b"a\nb\n").1, "");
+        assert_eq!(run_util_stdin(run, &["head", "--lines=1"], b"\n\n").1, "\n");
+        assert_eq!(
+            run_util_stdin(run, &["head", "-n", "1K"], lines(2000).as_bytes()).1,
+            lines(1024)
+        );
+    }
+
+    #[test]
+    fn test_long_lines() {
+        let line = "x".repeat(3 * BUFFER_SIZE);
+        let input = format!("{line}\n{line}\n{line}\n");
+        assert_eq!(
+            run_util_stdin(run, &["head", "-n", "2"], input.as_bytes()).1,
+            format!("{line}\n{line}\n")
+        );
+    }
+
+    #[test]
+    fn test_zero_terminated() {
+        assert_eq!(
+            run_util_stdin(run, &["head", "-zn", "2"], b"a\nb\0c\0d\0").1,
+            "a\nb\0c\0"
+        );
+    }
+
+    #[test]
+    fn test_invalid_lines() {
+        assert_eq!(
+            run_util_stdin(run, &["head", "-n", "1x"], b""),
+            (
+                1,
+                String::new(),
+                "head: invalid number of lines: '1x'\n".to_owned()
+            )
+        );
+        assert_eq!(
+            run_util_stdin(run, &["head", "-n", ""], b"").2,
+            "head: invalid number of lines: ''\n"
+        );
+    }
+
+    #[test]
+    fn test_bytes() {
+        assert_eq!(run_util_stdin(run, &["head", "-c", "2"], b"abc\n").1, "ab");
+        // More bytes than the input has.
+        assert_eq!(
+            run_util_stdin(run, &["head", "-c", "10"], b"abc\n").1,
+            "abc\n"
+        );
+        assert_eq!(run_util_stdin(run, &["head", "-c", "0"], b"abc\n").1, "");
+        assert_eq!(
+            run_util_stdin(run, &["head", "--bytes=5"], b"a\nb\nc\n").1,
+            "a\nb\nc"
+        );
+        let input = "x".repeat(3 * BUFFER_SIZE);
+        assert_eq!(
+            run_util_stdin(run, &["head", "-c", "1K"], input.as_bytes())
+                .1
+                .len(),
+            1024
+        );
+        assert_eq!(
+            run_util_stdin(run, &["head", "-c", "1kB"], input.as_bytes())
+                .1
+                .len(),
+            1000
+        );
+        assert_eq!(
+            run_util_stdin(run, &["head", "-c", "2b"], input.as_bytes())
+                .1
+                .len(),
+            1024
+        );
+        assert_eq!(
+            run_util_stdin(run, &["head", "-c", "65537"], input.as_bytes())
+                .1
+                .len(),
+            BUFFER_SIZE + 1
+        );
+    }
+
+    #[test]
+    fn test_last_of_bytes_and_lines_wins() {
+        assert_eq!(
+            run_util_stdin(run, &["head", "-c", "2", "-n", "1"], b"abc").1,
+            "abc"
+        );
+        assert_eq!(
+            run_util_stdin(run, &["head", "-n", "1", "-c", "2"], b"abc\nd\n").1,
+            "ab"
+        );
+    }
+
+    #[test]
+    fn test_all_but_last_lines() {
+        assert_eq!(
+            run_util_stdin(run, &["head", "-n", "-1"], b"a\nb\nc\n").1,
+            "a\nb\n"
+        );
+        // A final line without a newline counts as a line.
+        assert_eq!(
+            run_util_stdin(run, &["head", "-n", "-1"], b"a\nb\nc").1,
+            "a\nb\n"
+        );
+        // All but the last zero lines are all the lines.
+        assert_eq!(
+            run_util_stdin(run, &["head", "-n", "-0"], b"a\nb\nc").1,
+            "a\nb\nc"
+        );
+        assert_eq!(run_util_stdin(run, &["head", "-n", "-5"], b"a\nb\n").1, "");
+        assert_eq!(
+            run_util_stdin(run, &["head", "--lines=-2"], b"\n\n\n").1,
+            "\n"
+        );
+        assert_eq!(
+            run_util_stdin(run, &["head", "-zn", "-1"], b"a\0b\0").1,
+            "a\0"
+        );
+        assert_eq!(
+            run_util_stdin(run, &["head", "-n", "-1K"], lines(3000).as_bytes()).1,
+            lines(3000 - 1024)
+        );
+    }
+
+    #[test]
+    fn test_all_but_last_bytes() {
+        assert_eq!(
+            run_util_stdin(run, &["head", "-c", "-2"], b"abcdef").1,
+            "abcd"
+        );
+        assert_eq!(
+            run_util_stdin(run, &["head", "-c", "-0"], b"abcdef").1,
+            "abcdef"
+        );
+        assert_eq!(run_util_stdin(run, &["head", "-c", "-10"], b"abcdef").1, "");
+        let input = lines(100_000);
+        assert_eq!(
+            run_util_stdin(run, &["head", "-c", "-100"], input.as_bytes()).1,
+            input[..input.len() - 100]
+        );
+        assert_eq!(
+            run_util_stdin(run, &["head", "-c", "-100K"], input.as_bytes()).1,
+            input[..input.len() - 100 * 1024]
+        );
+    }
+
+    #[test]
+    fn test_all_but_last_from_pipe() {
+        let input = lines(50_000);
+        for (count, expected) in [
+            ("-n-10", &input[..input.len() - "49991\n".len() * 10]),
+            ("-c-70000", &input[..input.len() - 70000]),
+        ] {
+            let (mut reader, mut writer) = io::pipe().unwrap();
+            let writer = {
+                let input = input.clone();
+                std::thread::spawn(move || writer.write_all(input.as_bytes()))
+            };
+            let mut stdout = Vec::new();
+            let args = [OsString::from("head"), OsString::from(count)];
+            assert_eq!(run(&args, &mut reader, &mut stdout, &mut io::sink()), 0);
+            writer.join().unwrap().unwrap();
+            assert!(stdout == expected.as_bytes(), "{count}");
+        }
+    }
+
+    #[test]
+    fn test_obsolete_count() {
+        assert_eq!(
+            run_util_stdin(run, &["head", "-2"], lines(5).as_bytes()).1,
+            lines(2)
+        );
+        assert_eq!(
+            run_util_stdin(run, &["head", "-2l"], lines(5).as_bytes()).1,
+            lines(2)
+        );
+        assert_eq!(run_util_stdin(run, &["head", "-3c"], b"abcdef").1, "abc");
+        let input = "x".repeat(3000);
+        assert_eq!(
+            run_util_stdin(run, &["head", "-2kc"], input.as_bytes())
+                .1
+                .len(),
+            2048
+        );
+        assert_eq!(
+            run_util_stdin(run, &["head", "-1b"], lines(1000).as_bytes()).1,
+            lines(512)
+        );
+        assert_eq!(
+            run_util_stdin(run, &["head", "-1v"], b"a\nb\n").1,
+            "==> standard input <==\na\n"
+        );
+        assert_eq!(run_util_stdin(run, &["head", "-1z"], b"a\0b\0").1, "a\0");
+        assert_eq!(
+            run_util_stdin(run, &["head", "-1", "-"], b"a\nb\n").1,
+            "a\n"
+        );
+        // Only as the first argument.
+        assert_eq!(run_util_stdin(run, &["head", "-v", "-1"], b"").0, 2);
+        assert_eq!(run_util_stdin(run, &["head", "-1x"], b"").0, 2);
+    }
+
+    #[test]
+    fn test_invalid_bytes() {
+        assert_eq!(
+            run_util_stdin(run, &["head", "-c", "1KX"], b"abc"),
+            (
+                1,
+                String::new(),
+                "head: invalid number of bytes: '1KX'\n".to_owned()
+            )
+        );
+        assert_eq!(
+            run_util_stdin(run, &["head", "-c", "-x"], b"abc").2,
+            "head: invalid number of bytes: '-x'\n"
+        );
+        assert_eq!(
+            run_util_stdin(run, &["head", "-n", "-99999999999999999999999"], b"abc").2,
+            "head: invalid number of lines: '-99999999999999999999999': Value too large for defined \
+             data type\n"
+        );
+    }
+
+    #[test]
+    fn test_headers() {
+        let fixture = Fixture::new(&[("a", "a1\na2\n"), ("b", "b1")]);
+        let (a, b) = (&fixture.path("a"), &fixture.path("b"));
+        assert_eq!(
+            run_util_stdin(run, &["head", a, b], b""),
+            (
+                0,
+                format!("==> {a} <==\na1\na2\n\n==> {b} <==\nb1"),
+                String::new()
+            )
+        );
+        assert_eq!(
+            run_util_stdin(run, &["head", "-n", "1", a, "-"], b"stdin\n").1,
+            format!("==> {a} <==\na1\n\n==> standard input <==\nstdin\n")
+        );
+        assert_eq!(
+            run_util_stdin(run, &["head", "-q", a, b], b"").1,
+            "a1\na2\nb1"
+        );
+        assert_eq!(
+            run_util_stdin(run, &["head", "--silent", a, b], b"").1,
+            "a1\na2\nb1"
+        );
+        assert_eq!(
+            run_util_stdin(run, &["head", "-v", b], b"").1,
+            format!("==> {b} <==\nb1")
+        );
+        assert_eq!(
+            run_util_stdin(run, &["head", "-v"], b"x\n").1,
+            "==> standard input <==\nx\n"
+        );
+        // The last of -q and -v wins.
+        assert_eq!(run_util_stdin(run, &["head", "-vq", b], b"").1, "b1");
+        assert_eq!(
+            run_util_stdin(run, &["head", "-qv", b], b"").1,
+            format!("==> {b} <==\nb1")
+        );
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let fixture = Fixture::new(&[("a", "a1\n")]);
+        let (a, missing) = (&fixture.path("a"), &fixture.path("missing"));
+        assert_eq!(
+            run_util_stdin(run, &["head", missing, a, missing, a], b""),
+            (
+                1,
+                format!("==> {a} <==\na1\n\n==> {a} <==\na1\n"),
+                format!(
+                    "head: cannot open '{missing}' for reading: No such file or directory\n\
+                     head: cannot open '{missing}' for reading: No such file or directory\n"
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn test_directory() {
+        let fixture = Fixture::new(&[]);
+        let directory = &fixture.path("");
+        let (code, stdout, stderr) = run_util_stdin(run, &["head", "-v", directory], b"");
+        assert_eq!(code, 1);
+        assert_eq!(stdout, format!("==> {directory} <==\n"));
+        assert!(stderr.starts_with(&format!("head: error reading '{directory}': ")));
+    }
+
+    #[test]
+    fn test_pipe_is_not_read_to_the_end() {
+        let input = lines(200_000);
+        let (mut reader, mut writer) = io::pipe().unwrap();
+        let writer = {
+            let input = input.clone();
+            std::thread::spawn(move || writer.write_all(input.as_bytes()))
+        };
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let args = [OsString::from("head"), OsString::from("-n3")];
+        assert_eq!(run(&args, &mut reader, &mut stdout, &mut stderr), 0);
+        assert_eq!(stdout, b"1\n2\n3\n");
+
+        // At most a buffer was read, and the rest is still in the pipe, unchanged.
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        writer.join().unwrap().unwrap();
+        assert!(input.len() - rest.len() <= BUFFER_SIZE);
+        assert!(input.ends_with(&rest));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_file_is_left_after_the_output() {
+        use assert_cmd::cargo::CommandCargoExt;
+        use std::fs;
+        use std::io::Seek;
+
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("input");
+        fs::write(&path, lines(200_000)).unwrap();
+        for (args, stdout) in [(&["-n3"][..], "1\n2\n3\n"), (&["-c5"], "1\n2\n3")] {
+            // The binary shares the offset of the file, which it leaves just after what it printed.
+            let mut file = fs::File::open(&path).unwrap();
+            let output = std::process::Command::cargo_bin("head")
+                .unwrap()
+                .args(args)
+                .stdin(file.try_clone().unwrap())
+                .output()
+                .unwrap();
+            assert_eq!(output.stdout, stdout.as_bytes());
+            assert_eq!(file.stream_position().unwrap(), stdout.len() as u64);
+        }
+    }
+
+    #[test]
+    fn test_run_write_error() {
+        let mut buffer = [0; 4];
+        let mut stderr = Vec::new();
+        let code = run(
+            &[OsString::from("head")],
+            &mut lines(10).as_bytes(),
+            &mut &mut buffer[..],
+            &mut stderr,
+        );
+        assert_eq!(code, 1);
+        assert_eq!(stderr, b"head: write error: failed to write whole buffer\n");
+    }
+
+    #[test]
+    fn test_binary() {
+        let mut cmd = Command::cargo_bin("head").unwrap();
+        cmd.args(["-n", "2"])
+            .write_stdin("1\n2\n3\n")
+            .assert()
+            .success()
+            .stdout("1\n2\n");
+
+        let mut cmd = Command::cargo_bin("head").unwrap();
+        cmd.args(["missing", "-"])
+            .write_stdin("stdin\n")
+            .assert()
+            .code(1)
+            .stdout("==> standard input <==\nstdin\n")
+            .stderr("head: cannot open 'missing' for reading: No such file or directory\n");
+    }
+}
@@ -0,0 +1,153 @@
+//! A simple coreutils clone written in Rust.
+//!
+//! Besides the binaries, the core of each utility is available as a library, so that it can be embedded
+//! without spawning a process. Every utility module has a `run` function, which takes the command line and
+//! the streams to use, and the binaries are wrappers which pass the streams of the process.
+
+#[cfg(feature = "feat_base32")]
+pub mod base32;
+#[cfg(feature = "feat_base64")]
+pub mod base64;
+#[cfg(feature = "feat_basenc")]
+pub mod basenc;
+#[cfg(feature = "feat_cat")]
+pub mod cat;
+#[cfg(feature = "feat_chgrp")]
+pub mod chgrp;
+#[cfg(feature = "feat_chmod")]
+pub mod chmod;
+#[cfg(feature = "feat_chown")]
+pub mod chown;
+pub mod common;
+#[cfg(feature = "feat_cp")]
+pub mod cp;
+#[cfg(feature = "feat_cut")]
+pub mod cut;
+#[cfg(feature = "feat_df")]
+pub mod df;
+#[cfg(feature = "feat_du")]
+pub mod du;
+#[cfg(feature = "feat_echo")]
+pub mod echo;
+#[cfg(feature = "feat_head")]
+pub mod head;
+#[cfg(feature = "feat_ln")]
+pub mod ln;
+#[cfg(feature = "feat_ls")]
+pub mod ls;
+#[cfg(feature = "feat_mkdir")]
+pub mod mkdir;
+#[cfg(feature = "feat_mktemp")]
+pub mod mktemp;
+#[cfg(feature = "feat_mv")]
+pub mod mv;
+#[cfg(feature = "feat_od")]
+pub mod od;
+#[cfg(feature = "feat_printf")]
+pub mod printf;
+#[cfg(feature = "feat_readlink")]
+pub mod readlink;
+#[cfg(feature = "feat_realpath")]
+pub mod realpath;
+#[cfg(feature = "feat_rm")]
+pub mod rm;
+#[cfg(feature = "feat_rmdir")]
+pub mod rmdir;
+#[cfg(feature = "feat_seq")]
+pub mod seq;
+#[cfg(feature = "feat_shred")]
+pub mod shred;
+#[cfg(feature = "feat_sort")]
+pub mod sort;
+#[cfg(feature = "feat_split")]
+pub mod split;
+#[cfg(feature = "feat_stat")]
+pub mod stat;
+#[cfg(feature = "feat_tail")]
+pub mod tail;
+#[cfg(feature = "feat_touch")]
+pub mod touch;
+#[cfg(feature = "feat_tr")]
+pub mod tr;
+#[cfg(feature = "feat_truncate")]
+pub mod truncate;
+#[cfg(feature = "feat_uniq")]
+pub mod uniq;
+#[cfg(feature = "feat_wc")]
+pub mod wc;
+
+use common::utility::Utility;
+
+/// All utilities enabled at build time, sorted by name.
+pub const UTILITIES: &[Utility] = &[
+    #[cfg(feature = "feat_base32")]
+    base32::UTILITY,
+    #[cfg(feature = "feat_base64")]
+    base64::UTILITY,
+    #[cfg(feature = "feat_basenc")]
+    basenc::UTILITY,
+    #[cfg(feature = "feat_cat")]
+    cat::UTILITY,
+    #[cfg(feature = "feat_chgrp")]
+    chgrp::UTILITY,
+    #[cfg(feature = "feat_chmod")]
+    chmod::UTILITY,
+    #[cfg(feature = "feat_chown")]
+    chown::UTILITY,
+    #[cfg(feature = "feat_cp")]
+    cp::UTILITY,
+    #[cfg(feature = "feat_cut")]
+    cut::UTILITY,
+    #[cfg(feature = "feat_df")]
+    df::UTILITY,
+    #[cfg(feature = "feat_du")]
+    du::UTILITY,
+    #[cfg(feature = "feat_echo")]
+    echo::UTILITY,
+    #[cfg(feature = "feat_head")]
+    head::UTILITY,
+    #[cfg(feature = "feat_ln")]
+    ln::UTILITY,
+    #[cfg(feature = "feat_ls")]
+    ls::UTILITY,
+    #[cfg(feature = "feat_mkdir")]
+    mkdir::UTILITY,
+    #[cfg(feature = "feat_mktemp")]
+    mktemp::UTILITY,
+    #[cfg(feature = "feat_mv")]
+    mv::UTILITY,
+    #[cfg(feature = "feat_od")]
+    od::UTILITY,
+    #[cfg(feature = "feat_printf")]
+    printf::UTILITY,
+    #[cfg(feature = "feat_readlink")]
+    readlink::UTILITY,
+    #[cfg(feature = "feat_realpath")]
+    realpath::UTILITY,
+    #[cfg(feature = "feat_rm")]
+    rm::UTILITY,
+    #[cfg(feature = "feat_rmdir")]
+    rmdir::UTILITY,
+    #[cfg(feature = "feat_seq")]
+    seq::UTILITY,
+    #[cfg(feature = "feat_shred")]
+    shred::UTILITY,
+    #[cfg(feature = "feat_sort")]
+    sort::UTILITY,
+    #[cfg(feature = "feat_split")]
+    split::UTILITY,
+    #[cfg(feature = "feat_stat")]
+    stat::UTILITY,
+    #[cfg(feature = "feat_tail")]
+    tail::UTILITY,
+    #[cfg(feature = "feat_touch")]
+    touch::UTILITY,
+    #[cfg(feature = "feat_tr")]
+    tr::UTILITY,
+    #[cfg(feature = "feat_truncate")]
+    truncate::UTILITY,
+    #[cfg(feature = "feat_uniq")]
+    uniq::UTILITY,
+    #[cfg(feature = "feat_wc")]
+    wc::UTILITY,
+];
@@ -0,0 +1,1034 @@
+//! Make links between files, hard links by default and symbolic links with `-s`.
+//!
+//! Links are made with a [`Linker`], which refuses to replace the files which exist where the links are
+//! made, unless asked to with `-f` or `-i`:
+//!
+//! ```
+//! use rust_coreutils::common::error::Reporter;
+//! use rust_coreutils::ln::{Linker, LnOptions};
+//!
+//! let directory = tempfile::tempdir().unwrap();
+//! let target = directory.path().join("target");
+//! let link = directory.path().join("link");
+//! std::fs::write(&target, "contents").unwrap();
+//!
+//! let options = LnOptions {
+//!     verbose: true,
+//!     ..LnOptions::default()
+//! };
+//! let mut reporter = Reporter::new("ln", Vec::new());
+//! let (mut stdin, mut stdout) = (std::io::empty(), Vec::new());
+//! let mut linker = Linker::new(options, &mut reporter, &mut stdin, &mut stdout);
+//! linker.link(&target, &link);
+//! assert_eq!(std::fs::read(&link).unwrap(), b"contents");
+//! linker.link(&target, &link);
+//! assert!(reporter.failed());
+//! assert_eq!(stdout, format!("'{}' => '{}'\n", link.display(), target.display()).as_bytes());
+//! ```
+
+use crate::common::backup::{self, BackupArgs, BackupMode, BackupOptions};
+use crate::common::canonicalize::{canonicalize, relative_path, Mode};
+use crate::common::error::{io_error_message, Reporter, UError, UResult, EXIT_FAILURE};
+use crate::common::paths::base_name;
+use crate::common::prompt::Prompter;
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use crate::prompt_yes;
+use clap::{ArgAction, Parser};
+use std::ffi::OsString;
+use std::fmt::Display;
+use std::fs;
+use std::io::{self, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "ln",
+    author,
+    version,
+    about = "Make a link to TARGET named LINK_NAME, or links to the TARGETs in DIRECTORY, or a link to \
+             TARGET in the current directory.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Remove the existing files where the links are made.
+    #[arg(short, long, default_value_t = false, overrides_with = "interactive")]
+    force: bool,
+
+    /// Ask before removing the existing files where the links are made.
+    #[arg(short, long, default_value_t = false, overrides_with = "force")]
+    interactive: bool,
+
+    /// Replace a link named LINK_NAME to a directory rather than making the link in the directory.
+    #[arg(short = 'n', long, default_value_t = false)]
+    no_dereference: bool,
+
+    /// Make the symbolic links relative to their directory.
+    #[arg(short, long, default_value_t = false)]
+    relative: bool,
+
+    /// Make symbolic links instead of hard links.
+    #[arg(short, long, default_value_t = false)]
+    symbolic: bool,
+
+    /// Make all the links in DIRECTORY.
+    #[arg(short, long, value_name = "DIRECTORY", action = ArgAction::Append)]
+    target_directory: Vec<PathBuf>,
+
+    /// Treat LINK_NAME as a normal file, even if it is a directory.
+    #[arg(short = 'T', long, default_value_t = false)]
+    no_target_directory: bool,
+
+    /// Print the name of each link.
+    #[arg(short, long, default_value_t = false)]
+    verbose: bool,
+
+    #[command(flatten)]
+    backup: BackupArgs,
+
+    /// The targets of the links, followed by the link name or the directory of the links unless -t is given.
+    #[arg(value_name = "TARGET... [LINK_NAME|DIRECTORY]")]
+    files: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "ln";
+}
+
+/// The description of ln for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run ln with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run ln with the given command line, including the program name, on the given streams, and return the
+/// exit code.
+pub fn run(
+    args: &[OsString],
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(args.iter().cloned(), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let mut reporter = Reporter::new("ln", stderr);
+    let (operands, options) = match options(args) {
+        Ok(parsed) => parsed,
+        Err(error) => return reporter.finish(Err(error)),
+    };
+    let result = ln(
+        &operands,
+        &mut Linker::new(options, &mut reporter, stdin, stdout),
+    );
+    reporter.finish(result)
+}
+
+fn options(mut args: Args) -> UResult<(Vec<OsString>, LnOptions)> {
+    if args.relative && !args.symbolic {
+        return Err(UError::new(
+            EXIT_FAILURE,
+            "cannot do --relative without --symbolic",
+        ));
+    }
+    if args.target_directory.len() > 1 {
+        return Err(UError::new(
+            EXIT_FAILURE,
+            "multiple target directories specified",
+        ));
+    }
+    let existing = match (args.force, args.interactive) {
+        (true, _) => Existing::Remove,
+        (_, true) => Existing::Prompt,
+        _ => Existing::Fail,
+    };
+    let options = LnOptions {
+        symbolic: args.symbolic,
+        relative: args.relative,
+        existing,
+        target_directory: args.target_directory.pop(),
+        no_target_directory: args.no_target_directory,
+        no_dereference: args.no_dereference,
+        verbose: args.verbose,
+        backup: args.backup.options()?,
+    };
+    Ok((args.files, options))
+}
+
+/// What to do with the files which exist where the links are made.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Existing {
+    /// Keep them, and fail to make the links.
+    #[default]
+    Fail,
+    /// Remove them (`-f`).
+    Remove,
+    /// Ask whether to remove them (`-i`).
+    Prompt,
+}
+
+/// The options of ln.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LnOptions {
+    /// Make symbolic links, whose contents are the targets as given (`-s`), rather than hard links.
+    pub symbolic: bool,
+    /// Make the targets of the symbolic links relative to the directories of the links (`-r`), rather than
+    /// keeping them as they are given, relative to the current directory.
+    pub relative: bool,
+    /// What to do with the files which exist where the links are made.
+    pub existing: Existing,
+    /// The directory to make all the links in (`-t`), rather than the last operand.
+    pub target_directory: Option<PathBuf>,
+    /// Make the link named by the second operand even if it is a directory (`-T`), rather than making the
+    /// link in it.
+    pub no_target_directory: bool,
+    /// Make the link named by the last operand if it is a symbolic link to a directory (`-n`), rather than
+    /// making the link in the directory.
+    pub no_dereference: bool,
+    /// Print each link, like `'link' => 'target'` for a hard link and `'link' -> 'target'` for a symbolic
+    /// one (`-v`).
+    pub verbose: bool,
+    /// How to back up the files which are replaced, which ln does even without `-f`.
+    pub backup: BackupOptions,
+}
+
+/// Make links to the operands in the directory given by the options: in the last operand if it is a
+/// directory, with a single target named by it otherwise, or in the current directory if there is a single
+/// operand.
+///
+/// A link which fails to be made is reported, and ln continues with the next one.
+pub fn ln<W: Write>(operands: &[OsString], linker: &mut Linker<W>) -> UResult<()> {
+    let options = &linker.options;
+    let (targets, directory) = match (&options.target_directory, operands) {
+        (_, []) => return Err(UError::usage(EXIT_FAILURE, "missing file operand")),
+        (Some(_), _) if options.no_target_directory => {
+            return Err(UError::new(
+                EXIT_FAILURE,
+                "cannot combine --target-directory and --no-target-directory",
+            ))
+        }
+        (Some(directory), targets) => {
+            match fs::metadata(directory) {
+                Ok(metadata) if metadata.is_dir() => {}
+                Ok(_) => {
+                    let message = format!("target '{}' is not a directory", directory.display());
+                    return Err(UError::new(EXIT_FAILURE, message));
+                }
+                Err(error) => {
+                    return Err(failure(
+                        format_args!("failed to access '{}'", directory.display()),
+                        &error,
+                    ))
+                }
+            }
+            (targets, directory.clone())
+        }
+        (None, [target]) if options.no_target_directory => {
+            let message = format!(
+                "missing destination file operand after '{}'",
+                target.to_string_lossy()
+            );
+            return Err(UError::usage(EXIT_FAILURE, message));
+        }
+        (None, [target, link]) if options.no_target_directory => {
+            linker.link(Path::new(target), Path::new(link));
+            return Ok(());
+        }
+        (None, [_, _, extra, ..]) if options.no_target_directory => {
+            let message = format!("extra operand '{}'", extra.to_string_lossy());
+            return Err(UError::usage(EXIT_FAILURE, message));
+        }
+        (None, [_]) => (operands, PathBuf::from(".")),
+        (None, [targets @ .., destination]) => {
+            let destination = Path::new(destination);
+            let metadata = match options.no_dereference {
+                true => fs::symlink_metadata(destination),
+                false => fs::metadata(destination),
+            };
+            match metadata {
+                Ok(metadata) if metadata.is_dir() => (targets, destination.to_path_buf()),
+                Ok(_) if targets.len() > 1 => {
+                    let message = format!("target '{}': Not a directory", destination.display());
+                    return Err(UError::new(EXIT_FAILURE, message));
+                }
+                Err(error) if targets.len() > 1 => {
+                    return Err(failure(
+                        format_args!("target '{}'", destination.display()),
+                        &error,
+                    ))
+                }
+                _ => {
+                    linker.link(Path::new(&targets[0]), destination);
+                    return Ok(());
+                }
+            }
+        }
+    };
+
+    for target in targets {
+        let target = Path::new(target);
+        linker.link(target, &directory.join(base_name(target)));
+    }
+    Ok(())
+}
+
+/// Makes links, asking questions and printing messages on the given streams.
+pub struct Linker<'a, W: Write> {
+    options: LnOptions,
+    reporter: &'a mut Reporter<W>,
+    /// Where the answers to the questions of `-i` are read.
+    answers: &'a mut dyn Read,
+    /// Where `-v` prints the links.
+    stdout: &'a mut dyn Write,
+}
+
+impl<'a, W: Write> Linker<'a, W> {
+    /// Create a linker with the given options, reporting the failures to `reporter`, reading the answers to
+    /// the questions from `answers`, and printing the links to `stdout`.
+    pub fn new(
+        options: LnOptions,
+        reporter: &'a mut Reporter<W>,
+        answers: &'a mut dyn Read,
+        stdout: &'a mut dyn Write,
+    ) -> Self {
+        Linker {
+            options,
+            reporter,
+            answers,
+            stdout,
+        }
+    }
+
+    /// Make a link to `target` named `link`, and report the failures.
+    ///
+    /// The target of a symbolic link is not checked, so it can be missing, and a relative one is relative to
+    /// the directory of the link. A hard link to a symbolic link is another link to the symbolic link.
+    pub fn link(&mut self, target: &Path, link: &Path) {
+        if let Err(error) = self.try_link(target, link) {
+            self.reporter.error(format_args!("{error}"));
+        }
+    }
+
+    fn try_link(&mut self, target: &Path, link: &Path) -> UResult<()> {
+        let symbolic = self.options.symbolic;
+        // What the symbolic link contains.
+        let contents = match self.options.relative {
+            true => &relative_target(target, link),
+            false => target,
+        };
+        if !symbolic {
+            let metadata = fs::symlink_metadata(target).map_err(|error| {
+                failure(
+                    format_args!("failed to access '{}'", target.display()),
+                    &error,
+                )
+            })?;
+            if metadata.is_dir() {
+                let message = format!("{}: hard link not allowed for directory", target.display());
+                return Err(UError::new(EXIT_FAILURE, message));
+            }
+        }
+        let replace =
+            self.options.existing != Existing::Fail || self.options.backup.mode != BackupMode::None;
+        let backup = match replace {
+            true => match self.remove_existing(target, link)? {
+                Some(backup) => backup,
+                None => return Ok(()),
+            },
+            false => None,
+        };
+
+        let result = match symbolic {
+            true => symlink(contents, link),
+            false => fs::hard_link(target, link),
+        };
+        result.map_err(|error| link_failure(symbolic, contents, link, &error))?;
+        if self.options.verbose {
+            let arrow = if symbolic { "->" } else { "=>" };
+            if let Some(backup) = backup {
+                let _ = write!(self.stdout, "'{}' ~ ", backup.display());
+            }
+            let _ = writeln!(
+                self.stdout,
+                "'{}' {arrow} '{}'",
+                link.display(),
+                contents.display()
+            );
+        }
+        Ok(())
+    }
+
+    /// Remove the file where a link is made with `-f` or `-i`, if there is one, or back it up, and check
+    /// whether to make the link. Returns `None` if the link is not made, and the backup, if any, otherwise.
+    fn remove_existing(&mut self, target: &Path, link: &Path) -> UResult<Option<Option<PathBuf>>> {
+        let existing = match fs::symlink_metadata(link) {
+            Ok(existing) => existing,
+            // Making the link fails with a better message.
+            Err(_) => return Ok(Some(None)),
+        };
+        if is_same_entry(target, link) {
+            let message = format!(
+                "'{}' and '{}' are the same file",
+                target.display(),
+                link.display()
+            );
+            return Err(UError::new(EXIT_FAILURE, message));
+        }
+        if self.options.existing == Existing::Prompt {
+            let mut prompter = Prompter::new("ln", &mut *self.answers, self.reporter.stderr());
+            if !prompt_yes!(prompter, "replace '{}'?", link.display()) {
+                return Ok(None);
+            }
+        }
+        if existing.is_dir() {
+            let message = format!("{}: cannot overwrite directory", link.display());
+            return Err(UError::new(EXIT_FAILURE, message));
+        }
+        if let Some(backup) = backup::back_up(link, &self.options.backup)? {
+            return Ok(Some(Some(backup)));
+        }
+        fs::remove_file(link)
+            .map_err(|error| failure(format_args!("cannot remove '{}'", link.display()), &error))?;
+        Ok(Some(None))
+    }
+}
+
+/// Check whether two paths name the same entry of the same directory, so that replacing one with a link to
+/// the other would remove the target.
+fn is_same_entry(a: &Path, b: &Path) -> bool {
+    let parent = |path: &Path| match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => fs::canonicalize(parent),
+        _ => fs::canonicalize("."),
+    };
+    base_name(a) == base_name(b)
+        && fs::symlink_metadata(a).is_ok()
+        && matches!((parent(a), parent(b)), (Ok(a), Ok(b)) if a == b)
+}
+
+/// Get the target of a symbolic link named `link` to the file named `target` in the current directory,
+/// relative to the directory of the link, like `../target`.
+///
+/// The link is resolved except for its last component, which can be a link to replace, and so is the
+/// target. The target is kept as it is when it cannot be resolved.
+fn relative_target(target: &Path, link: &Path) -> PathBuf {
+    let directory = match link.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    match (
+        canonicalize(target, Mode::Missing),
+        canonicalize(directory, Mode::Missing),
+    ) {
+        (Ok(target), Ok(directory)) => relative_path(&target, &directory),
+        _ => target.to_path_buf(),
+    }
+}
+
+/// Make a symbolic link, whose target is relative to the directory of the link.
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+/// Make a symbolic link, which is not supported.
+#[cfg(not(unix))]
+fn symlink(_target: &Path, _link: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        ErrorKind::Unsupported,
+        "symbolic links are not supported",
+    ))
+}
+
+/// Describe the failure to make a link, naming the target only when it can be the cause, like GNU.
+fn link_failure(symbolic: bool, target: &Path, link: &Path, error: &io::Error) -> UError {
+    let empty = target.as_os_str().is_empty();
+    let (link, target) = (link.display(), target.display());
+    match (symbolic, error.kind()) {
+        (true, kind) if kind == ErrorKind::InvalidFilename || empty => failure(
+            format_args!("failed to create symbolic link '{link}' -> '{target}'"),
+            error,
+        ),
+        (true, _) => failure(
+            format_args!("failed to create symbolic link '{link}'"),
+            error,
+        ),
+        (false, ErrorKind::TooManyLinks) => failure(
+            format_args!("failed to create hard link to '{target}'"),
+            error,
+        ),
+        (
+            false,
+            ErrorKind::AlreadyExists
+            | ErrorKind::QuotaExceeded
+            | ErrorKind::StorageFull
+            | ErrorKind::ReadOnlyFilesystem,
+        ) => failure(format_args!("failed to create hard link '{link}'"), error),
+        (false, _) => failure(
+            format_args!("failed to create hard link '{link}' => '{target}'"),
+            error,
+        ),
+    }
+}
+
+fn failure(operation: impl Display, error: &io::Error) -> UError {
+    UError::new(
+        EXIT_FAILURE,
+        format!("{operation}: {}", io_error_message(error)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::{run_util_stderr, run_util_stdin};
+
+    #[test]
+    #[cfg(unix)]
+    fn test_hard_link() {
+        use std::os::unix::fs::MetadataExt;
+
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::write(root.join("file"), "contents").unwrap();
+        let path = |name| root.join(name).display().to_string();
+
+        assert_eq!(
+            run_util_stdin(run, &["ln", "-v", &path("file"), &path("link")], ""),
+            (
+                0,
+                format!("'{}' => '{}'\n", path("link"), path("file")),
+                String::new()
+            )
+        );
+        let inode = |name| fs::metadata(root.join(name)).unwrap().ino();
+        assert_eq!(inode("link"), inode("file"));
+        assert_eq!(fs::metadata(root.join("file")).unwrap().nlink(), 2);
+
+        fs::create_dir(root.join("d")).unwrap();
+        assert_eq!(
+            run_util_stderr(run, &["ln", &path("d"), &path("e")]),
+            (
+                EXIT_FAILURE,
+                format!("ln: {}: hard link not allowed for directory\n", path("d"))
+            )
+        );
+        assert!(!root.join("e").exists());
+
+        assert_eq!(
+            run_util_stderr(run, &["ln", &path("missing"), &path("new")]),
+            (
+                EXIT_FAILURE,
+                format!(
+                    "ln: failed to access '{}': No such file or directory\n",
+                    path("missing")
+                )
+            )
+        );
+        assert_eq!(
+            run_util_stderr(run, &["ln", &path("file"), &path("missing/link")]),
+            (
+                EXIT_FAILURE,
+                format!(
+                    "ln: failed to create hard link '{}' => '{}': No such file or directory\n",
+                    path("missing/link"),
+                    path("file")
+                )
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_symbolic() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir(root.join("d")).unwrap();
+        fs::write(root.join("file"), "contents").unwrap();
+        let path = |name| root.join(name).display().to_string();
+
+        // The targets are kept as they are, even when they are missing.
+        assert_eq!(
+            run_util_stdin(run, &["ln", "-sv", "nowhere", &path("dangling")], ""),
+            (
+                0,
+                format!("'{}' -> 'nowhere'\n", path("dangling")),
+                String::new()
+            )
+        );
+        assert_eq!(
+            fs::read_link(root.join("dangling")).unwrap(),
+            Path::new("nowhere")
+        );
+        assert!(!root.join("dangling").exists());
+
+        assert_eq!(
+            run_util_stderr(run, &["ln", "--symbolic", "../file", &path("d")]),
+            (0, String::new())
+        );
+        assert_eq!(
+            fs::read_link(root.join("d/file")).unwrap(),
+            Path::new("../file")
+        );
+        assert_eq!(fs::read_to_string(root.join("d/file")).unwrap(), "contents");
+
+        // A symbolic link to a directory is fine.
+        assert_eq!(
+            run_util_stderr(run, &["ln", "-s", "d", &path("l")]),
+            (0, String::new())
+        );
+        assert!(root.join("l").is_dir());
+
+        assert_eq!(
+            run_util_stderr(run, &["ln", "-s", "", &path("empty")]),
+            (
+                EXIT_FAILURE,
+                format!(
+                    "ln: failed to create symbolic link '{}' -> '': No such file or directory\n",
+                    path("empty")
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn test_existing() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::write(root.join("file"), "new").unwrap();
+        fs::write(root.join("old"), "old").unwrap();
+        fs::create_dir_all(root.join("d/file")).unwrap();
+        let path = |name| root.join(name).display().to_string();
+
+        assert_eq!(
+            run_util_stderr(run, &["ln", &path("file"), &path("old")]),
+            (
+                EXIT_FAILURE,
+                format!(
+                    "ln: failed to create hard link '{}': File exists\n",
+                    path("old")
+                )
+            )
+        );
+        assert_eq!(fs::read_to_string(root.join("old")).unwrap(), "old");
+
+        assert_eq!(
+            run_util_stderr(run, &["ln", "-f", &path("file"), &path("old")]),
+            (0, String::new())
+        );
+        assert_eq!(fs::read_to_string(root.join("old")).unwrap(), "new");
+
+        // Replacing a file with a link to itself would remove it.
+        for args in [&["-f"][..], &["-sf"], &["-i"]] {
+            let mut command_line = vec!["ln"];
+            command_line.extend(args);
+            let (file, other) = (path("file"), path("d/../file"));
+            command_line.extend([file.as_str(), other.as_str()]);
+            assert_eq!(
+                run_util_stderr(run, &command_line),
+                (
+                    EXIT_FAILURE,
+                    format!("ln: '{file}' and '{other}' are the same file\n")
+                )
+            );
+            assert_eq!(fs::read_to_string(root.join("file")).unwrap(), "new");
+        }
+
+        assert_eq!(
+            run_util_stderr(run, &["ln", "-f", &path("file"), &path("d")]),
+            (
+                EXIT_FAILURE,
+                format!(
+                    "ln: {}: cannot overwrite directory\n",
+                    root.join("d").join("file").display()
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn test_interactive() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::write(root.join("file"), "new").unwrap();
+        fs::write(root.join("old"), "old").unwrap();
+        let path = |name| root.join(name).display().to_string();
+        let question = format!("ln: replace '{}'? ", path("old"));
+
+        assert_eq!(
+            run_util_stdin(run, &["ln", "-i", &path("file"), &path("old")], "n\n"),
+            (0, String::new(), question.clone())
+        );
+        assert_eq!(fs::read_to_string(root.join("old")).unwrap(), "old");
+
+        // No question without an existing file, and the last of -f and -i wins.
+        assert_eq!(
+            run_util_stdin(run, &["ln", "-fi", &path("file"), &path("new")], ""),
+            (0, String::new(), String::new())
+        );
+        assert_eq!(
+            run_util_stdin(run, &["ln", "-if", &path("file"), &path("new")], ""),
+            (0, String::new(), String::new())
+        );
+
+        assert_eq!(
+            run_util_stdin(run, &["ln", "-iv", &path("file"), &path("old")], "yes\n"),
+            (
+                0,
+                format!("'{}' => '{}'\n", path("old"), path("file")),
+                question
+            )
+        );
+        assert_eq!(fs::read_to_string(root.join("old")).unwrap(), "new");
+    }
+
+    #[test]
+    fn test_into_directory() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir(root.join("d")).unwrap();
+        fs::create_dir(root.join("sub")).unwrap();
+        for name in ["a", "b", "sub/c"] {
+            fs::write(root.join(name), name).unwrap();
+        }
+        let path = |name| root.join(name).display().to_string();
+
+        let command_line = ["ln", &path("a"), &path("b"), &path("sub/c"), &path("d/")];
+        assert_eq!(run_util_stderr(run, &command_line), (0, String::new()));
+        for (name, contents) in [("a", "a"), ("b", "b"), ("c", "sub/c")] {
+            assert_eq!(
+                fs::read_to_string(root.join("d").join(name)).unwrap(),
+                contents
+            );
+        }
+
+        // The links which can be made are made.
+        fs::write(root.join("e"), "e").unwrap();
+        let (code, stderr) = run_util_stderr(run, &["ln", &path("a"), &path("e"), &path("d")]);
+        assert_eq!(
+            (code, stderr),
+            (
+                EXIT_FAILURE,
+                format!(
+                    "ln: failed to create hard link '{}': File exists\n",
+                    root.join("d").join("a").display()
+                )
+            )
+        );
+        assert!(root.join("d/e").exists());
+
+        assert_eq!(
+            run_util_stderr(run, &["ln", &path("a"), &path("b"), &path("e")]),
+            (
+                EXIT_FAILURE,
+                format!("ln: target '{}': Not a directory\n", path("e"))
+            )
+        );
+        assert_eq!(
+            run_util_stderr(run, &["ln", &path("a"), &path("b"), &path("missing")]),
+            (
+                EXIT_FAILURE,
+                format!(
+                    "ln: target '{}': No such file or directory\n",
+                    path("missing")
+                )
+            )
+        );
+        assert_eq!(
+            run_util_stderr(run, &["ln"]),
+            (
+                EXIT_FAILURE,
+                "ln: missing file operand\nTry 'ln --help' for more information.\n".to_owned()
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_relative() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(directory.path()).unwrap();
+        fs::create_dir_all(root.join("dir")).unwrap();
+        fs::create_dir_all(root.join("other")).unwrap();
+        fs::write(root.join("other/file"), "contents").unwrap();
+        let path = |name| root.join(name).display().to_string();
+
+        assert_eq!(
+            run_util_stdin(
+                run,
+                &["ln", "-srv", &path("other/file"), &path("dir/link")],
+                ""
+            ),
+            (
+                0,
+                format!("'{}' -> '../other/file'\n", path("dir/link")),
+                String::new()
+            )
+        );
+        assert_eq!(
+            fs::read_link(root.join("dir/link")).unwrap(),
+            Path::new("../other/file")
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("dir/link")).unwrap(),
+            "contents"
+        );
+
+        // The link name is relative to the current directory, and the links in it are resolved.
+        std::os::unix::fs::symlink("dir", root.join("alias")).unwrap();
+        let up = "../".repeat(std::env::current_dir().unwrap().components().count() - 1);
+        let relative = format!("{up}{}", path("alias/missing").trim_start_matches('/'));
+        assert_eq!(
+            run_util_stderr(
+                run,
+                &[
+                    "ln",
+                    "--symbolic",
+                    "--relative",
+                    &path("other/missing"),
+                    &relative
+                ]
+            ),
+            (0, String::new())
+        );
+        assert_eq!(
+            fs::read_link(root.join("dir/missing")).unwrap(),
+            Path::new("../other/missing")
+        );
+
+        assert_eq!(
+            run_util_stderr(run, &["ln", "-sr", &path("dir"), &path("dir/self")]),
+            (0, String::new())
+        );
+        assert_eq!(
+            fs::read_link(root.join("dir/self")).unwrap(),
+            Path::new(".")
+        );
+
+        assert_eq!(
+            run_util_stderr(run, &["ln", "-r", &path("other/file"), &path("dir/hard")]),
+            (
+                EXIT_FAILURE,
+                "ln: cannot do --relative without --symbolic\n".to_owned()
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_no_dereference() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir(root.join("d")).unwrap();
+        fs::write(root.join("file"), "").unwrap();
+        std::os::unix::fs::symlink("d", root.join("link")).unwrap();
+        let path = |name| root.join(name).display().to_string();
+
+        // The link is made in the directory, unless -n is given.
+        assert_eq!(
+            run_util_stderr(run, &["ln", "-sf", "file", &path("link")]),
+            (0, String::new())
+        );
+        assert_eq!(
+            fs::read_link(root.join("d/file")).unwrap(),
+            Path::new("file")
+        );
+        assert_eq!(
+            run_util_stderr(run, &["ln", "-sn", "file", &path("link")]),
+            (
+                EXIT_FAILURE,
+                format!(
+                    "ln: failed to create symbolic link '{}': File exists\n",
+                    path("link")
+                )
+            )
+        );
+        assert_eq!(
+            run_util_stderr(run, &["ln", "-snf", "file", &path("link")]),
+            (0, String::new())
+        );
+        assert_eq!(fs::read_link(root.join("link")).unwrap(), Path::new("file"));
+        assert!(root.join("d").is_dir());
+
+        std::os::unix::fs::symlink("d", root.join("again")).unwrap();
+        assert_eq!(
+            run_util_stderr(
+                run,
+                &[
+                    "ln",
+                    "--no-dereference",
+                    &path("file"),
+                    &path("d/file"),
+                    &path("again")
+                ]
+            ),
+            (
+                EXIT_FAILURE,
+                format!("ln: target '{}': Not a directory\n", path("again"))
+            )
+        );
+    }
+
+    #[test]
+    fn test_target_directory() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir(root.join("d")).unwrap();
+        for name in ["a", "b"] {
+            fs::write(root.join(name), name).unwrap();
+        }
+        let path = |name| root.join(name).display().to_string();
+
+        assert_eq!(
+            run_util_stderr(run, &["ln", "-t", &path("d"), &path("a"), &path("b")]),
+            (0, String::new())
+        );
+        for name in ["a", "b"] {
+            assert_eq!(fs::read_to_string(root.join("d").join(name)).unwrap(), name);
+        }
+
+        // With -T, the directory is the link to replace.
+        assert_eq!(
+            run_util_stderr(run, &["ln", "-T", &path("a"), &path("d")]),
+            (
+                EXIT_FAILURE,
+                format!(
+                    "ln: failed to create hard link '{}': File exists\n",
+                    path("d")
+                )
+            )
+        );
+        assert_eq!(
+            run_util_stderr(run, &["ln", "-fT", &path("a"), &path("d")]),
+            (
+                EXIT_FAILURE,
+                format!("ln: {}: cannot overwrite directory\n", path("d"))
+            )
+        );
+        assert_eq!(
+            run_util_stderr(run, &["ln", "-T", &path("a"), &path("c")]),
+            (0, String::new())
+        );
+        assert_eq!(fs::read_to_string(root.join("c")).unwrap(), "a");
+
+        assert_eq!(
+            run_util_stderr(run, &["ln", "-t", &path("a"), &path("b")]),
+            (
+                EXIT_FAILURE,
+                format!("ln: target '{}' is not a directory\n", path("a"))
+            )
+        );
+        assert_eq!(
+            run_util_stderr(run, &["ln", "-t", &path("missing"), &path("b")]),
+            (
+                EXIT_FAILURE,
+                format!(
+                    "ln: failed to access '{}': No such file or directory\n",
+                    path("missing")
+                )
+            )
+        );
+        for (args, message) in [
+            (
+                &["-t", "d", "-t", "e", "a"][..],
+                "multiple target directories specified",
+            ),
+            (
+                &["-t", "d", "-T", "a"],
+                "cannot combine --target-directory and --no-target-directory",
+            ),
+        ] {
+            let mut command_line = vec!["ln"];
+            command_line.extend(args);
+            assert_eq!(
+                run_util_stderr(run, &command_line),
+                (EXIT_FAILURE, format!("ln: {message}\n")),
+                "{args:?}"
+            );
+        }
+        // The misuses of the operands are followed by a hint.
+        for (args, message) in [
+            (&["-t", "d"][..], "missing file operand"),
+            (&["-T", "a"], "missing destination file operand after 'a'"),
+            (&["-T", "a", "b", "c"], "extra operand 'c'"),
+        ] {
+            let mut command_line = vec!["ln"];
+            command_line.extend(args);
+            assert_eq!(
+                run_util_stderr(run, &command_line),
+                (
+                    EXIT_FAILURE,
+                    format!("ln: {message}\nTry 'ln --help' for more information.\n")
+                ),
+                "{args:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_backup() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::write(root.join("file"), "new").unwrap();
+        fs::write(root.join("old"), "old").unwrap();
+        let path = |name| root.join(name).display().to_string();
+
+        // The existing file is backed up even without -f.
+        let (code, stdout, stderr) = run_util_stdin(
+            run,
+            &["ln", "-v", "--backup=numbered", &path("file"), &path("old")],
+            "",
+        );
+        assert_eq!((code, stderr.as_str()), (0, ""));
+        assert_eq!(
+            stdout,
+            format!(
+                "'{}' ~ '{}' => '{}'\n",
+                path("old.~1~"),
+                path("old"),
+                path("file")
+            )
+        );
+        assert_eq!(fs::read_to_string(root.join("old")).unwrap(), "new");
+        assert_eq!(fs::read_to_string(root.join("old.~1~")).unwrap(), "old");
+
+        // The checks of -f still apply.
+        fs::create_dir(root.join("d")).unwrap();
+        assert_eq!(
+            run_util_stderr(run, &["ln", "-T", "--backup=t", &path("file"), &path("d")]),
+            (
+                EXIT_FAILURE,
+                format!("ln: {}: cannot overwrite directory\n", path("d"))
+            )
+        );
+        assert_eq!(
+            run_util_stderr(run, &["ln", "--backup=t", &path("file"), &path("file")]),
+            (
+                EXIT_FAILURE,
+                format!(
+                    "ln: '{}' and '{}' are the same file\n",
+                    path("file"),
+                    path("file")
+                )
+            )
+        );
+        assert!(!root.join("file.~1~").exists());
+
+        let (code, stderr) =
+            run_util_stderr(run, &["ln", "--backup=nope", &path("file"), &path("old")]);
+        assert_eq!(code, EXIT_FAILURE);
+        assert!(stderr.starts_with("ln: invalid argument 'nope' for 'backup type'\n"));
+        assert!(stderr.ends_with("\nTry 'ln --help' for more information.\n"));
+    }
+}
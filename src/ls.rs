@@ -0,0 +1,1927 @@
+//! List the contents of directories.
+//!
+//! The entries of a directory are read and sorted by [`read_directory`], and printed by [`ls`]:
+//!
+//! ```
+//! use rust_coreutils::ls::{read_directory, LsOptions};
+//!
+//! let directory = tempfile::tempdir().unwrap();
+//! std::fs::write(directory.path().join("b"), "").unwrap();
+//! std::fs::write(directory.path().join("a"), "").unwrap();
+//! std::fs::write(directory.path().join(".hidden"), "").unwrap();
+//!
+//! let entries = read_directory(directory.path(), &LsOptions::default()).unwrap();
+//! let names: Vec<_> = entries.iter().map(|entry| entry.name.to_str().unwrap()).collect();
+//! assert_eq!(names, ["a", "b"]);
+//! ```
+
+use crate::common::colors::{parse_ls_colors, Indicator, LsColors};
+use crate::common::error::{io_error_message, Reporter, UError, UResult, EXIT_FAILURE};
+use crate::common::human::{human_readable, Base, BlockSize};
+use crate::common::input::BUFFER_SIZE;
+use crate::common::mode::{format_mode, format_type_char, st_mode};
+use crate::common::os::{self, os_str_bytes};
+#[cfg(unix)]
+use crate::common::users;
+use crate::common::utility::{parse_matches, UtilCommand, Utility};
+use chrono::{DateTime, Local};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Parser};
+use std::cmp::Ordering;
+use std::ffi::OsString;
+use std::fs::{self, Metadata};
+use std::io::{self, BufWriter, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use unicode_width::UnicodeWidthStr;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "ls",
+    author,
+    version,
+    about = "List the contents of each FILE which is a directory, and the name of every other FILE.",
+    long_about = None,
+    args_override_self = true,
+    disable_help_flag = true
+)]
+struct Args {
+    /// Print help.
+    #[arg(long, action = clap::ArgAction::Help)]
+    help: Option<bool>,
+
+    /// Do not ignore the entries whose names start with `.`, and list `.` and `..` as well.
+    #[arg(short, long, default_value_t = false, overrides_with = "almost_all")]
+    all: bool,
+
+    /// Do not ignore the entries whose names start with `.`, except `.` and `..`.
+    #[arg(short = 'A', long, default_value_t = false, overrides_with = "all")]
+    almost_all: bool,
+
+    /// List the entries in columns, from top to bottom. This is the default when standard output is a
+    /// terminal.
+    #[arg(short = 'C', default_value_t = false)]
+    columns: bool,
+
+    /// List one entry per line. This is the default when standard output is not a terminal.
+    #[arg(short = '1', default_value_t = false)]
+    one_per_line: bool,
+
+    /// List the type, permissions, number of links, owner, group, size, and modification time of each entry
+    /// before its name, and the target of symbolic links after it.
+    #[arg(short = 'l', default_value_t = false)]
+    long: bool,
+
+    /// Like -l, but without the owner.
+    #[arg(short = 'g', default_value_t = false)]
+    no_owner: bool,
+
+    /// Like -l, but without the group.
+    #[arg(short = 'o', default_value_t = false)]
+    no_group: bool,
+
+    /// With -l, print the sizes in powers of 1024, like 1.5K and 234M.
+    #[arg(short = 'h', long, default_value_t = false, overrides_with = "si")]
+    human_readable: bool,
+
+    /// Like -h, but in powers of 1000, like 1.5k and 234M.
+    #[arg(long, default_value_t = false, overrides_with = "human_readable")]
+    si: bool,
+
+    /// Sort by modification time, newest first.
+    #[arg(short = 't', default_value_t = false)]
+    sort_time: bool,
+
+    /// Sort by size, largest first.
+    #[arg(short = 'S', default_value_t = false)]
+    sort_size: bool,
+
+    /// Sort by extension, which is what follows the last `.` of the name: the names without one come
+    /// first.
+    #[arg(short = 'X', default_value_t = false)]
+    sort_extension: bool,
+
+    /// Do not sort, and list the entries in the order of the directory.
+    #[arg(short = 'U', default_value_t = false)]
+    unsorted: bool,
+
+    /// Reverse the order of the sort.
+    #[arg(short, long, default_value_t = false)]
+    reverse: bool,
+
+    /// List the subdirectories recursively, after the directory containing them.
+    #[arg(short = 'R', long, default_value_t = false)]
+    recursive: bool,
+
+    /// Color the names by the type of file, with the colors of LS_COLORS. WHEN is always (the default),
+    /// never, or auto, which colors them when standard output is a terminal.
+    #[arg(
+        long,
+        value_name = "WHEN",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "always"
+    )]
+    color: Option<String>,
+
+    /// Fit the columns in COLS characters, or in an unlimited width with 0. By default, the width of the
+    /// terminal is used, or the COLUMNS environment variable if it is set.
+    #[arg(short, long, value_name = "COLS", allow_hyphen_values = true)]
+    width: Option<String>,
+
+    /// The files to list. With no FILE, the current directory is listed.
+    #[arg(value_name = "FILE")]
+    files: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "ls";
+}
+
+/// When to color the names, with `--color`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ColorWhen {
+    Always,
+    Never,
+    /// When standard output is a terminal.
+    Auto,
+}
+
+/// The accepted values of `--color`, which can be abbreviated as long as they are unambiguous.
+const COLOR_WHENS: &[(&str, ColorWhen)] = &[
+    ("always", ColorWhen::Always),
+    ("yes", ColorWhen::Always),
+    ("force", ColorWhen::Always),
+    ("never", ColorWhen::Never),
+    ("no", ColorWhen::Never),
+    ("none", ColorWhen::Never),
+    ("auto", ColorWhen::Auto),
+    ("tty", ColorWhen::Auto),
+    ("if-tty", ColorWhen::Auto),
+];
+
+impl ColorWhen {
+    fn parse(when: &str) -> UResult<Self> {
+        if let Some(&(_, color)) = COLOR_WHENS.iter().find(|(name, _)| *name == when) {
+            return Ok(color);
+        }
+        let mut matches = COLOR_WHENS
+            .iter()
+            .filter(|(name, _)| name.starts_with(when))
+            .map(|&(_, color)| color);
+        let problem = match matches.next() {
+            Some(color) if matches.all(|other| other == color) => return Ok(color),
+            Some(_) => "ambiguous",
+            None => "invalid",
+        };
+        Err(UError::new(
+            EXIT_FAILURE,
+            format!(
+                "{problem} argument '{when}' for '--color'\n\
+                 Valid arguments are:\n  \
+                 - 'always', 'yes', 'force'\n  \
+                 - 'never', 'no', 'none'\n  \
+                 - 'auto', 'tty', 'if-tty'"
+            ),
+        ))
+    }
+}
+
+/// The description of ls for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// The exit code of a run where a FILE operand could not be accessed, which GNU considers more serious
+/// than the failures below the operands.
+pub const EXIT_TROUBLE: i32 = 2;
+
+/// The options which select the format, of which the last one wins, except for -1 after -l.
+const FORMAT_OPTIONS: [&str; 5] = ["columns", "one_per_line", "long", "no_owner", "no_group"];
+
+/// The options which select the order of the entries, of which the last one wins.
+const SORT_OPTIONS: [(&str, Sort); 4] = [
+    ("sort_time", Sort::Time),
+    ("sort_size", Sort::Size),
+    ("sort_extension", Sort::Extension),
+    ("unsorted", Sort::None),
+];
+
+/// The width of the columns when neither the terminal nor COLUMNS tells it.
+const DEFAULT_WIDTH: usize = 80;
+
+/// The narrowest column, which holds a name of one character and the two blanks after it.
+const MIN_COLUMN_WIDTH: usize = 3;
+
+/// The distance between tab stops, with which the columns are aligned.
+const TAB_SIZE: usize = 8;
+
+/// Run ls with the given command line, including the program name, and return the exit code.
+///
+/// Unlike [`run`], the defaults depend on whether standard output is a terminal.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    let args: Vec<OsString> = args.into_iter().collect();
+    let terminal = io::stdout().is_terminal();
+    run_on(&args, terminal, &mut os::stdout(), &mut os::stderr())
+}
+
+/// Run ls with the given command line, including the program name, on the given streams, and return the
+/// exit code. Standard output is treated as a file, never as a terminal.
+pub fn run(
+    args: &[OsString],
+    _stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    run_on(args, false, stdout, stderr)
+}
+
+fn run_on(
+    args: &[OsString],
+    terminal: bool,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let (args, matches): (Args, _) = match parse_matches(args.iter().cloned(), stdout, stderr) {
+        Ok(parsed) => parsed,
+        Err(code) => return code,
+    };
+
+    let mut reporter = Reporter::new("ls", stderr);
+    let options = match options(&args, &matches, terminal, &mut reporter) {
+        Ok(options) => options,
+        Err(error) => return reporter.finish(Err(error)),
+    };
+    let operands = if args.files.is_empty() {
+        vec![OsString::from(".")]
+    } else {
+        args.files
+    };
+    match ls(&operands, &options, stdout, &mut reporter) {
+        Ok(true) => {
+            reporter.finish(Ok(()));
+            EXIT_TROUBLE
+        }
+        result => reporter.finish(result.map(drop)),
+    }
+}
+
+fn options<W: Write>(
+    args: &Args,
+    matches: &ArgMatches,
+    terminal: bool,
+    reporter: &mut Reporter<W>,
+) -> UResult<LsOptions> {
+    let mut formats: Vec<(usize, &str)> = FORMAT_OPTIONS
+        .into_iter()
+        .filter_map(|id| Some((last_index(matches, id)?, id)))
+        .collect();
+    formats.sort_unstable();
+    let mut format = if terminal {
+        Format::Columns
+    } else {
+        Format::OneLine
+    };
+    for (_, id) in formats {
+        format = match id {
+            "columns" => Format::Columns,
+            // Like in GNU, -1 does not override -l, but only -C.
+            "one_per_line" if format == Format::Long => Format::Long,
+            "one_per_line" => Format::OneLine,
+            _ => Format::Long,
+        };
+    }
+    let colored = match args.color.as_deref().map(ColorWhen::parse).transpose()? {
+        Some(ColorWhen::Always) => true,
+        Some(ColorWhen::Auto) => terminal,
+        Some(ColorWhen::Never) | None => false,
+    };
+    let colors = match std::env::var_os("LS_COLORS") {
+        _ if !colored => None,
+        Some(value) if !value.is_empty() => match parse_ls_colors(&os_str_bytes(&value)) {
+            Ok(colors) => Some(colors),
+            Err(error) => {
+                // Like in GNU, the listing goes on without colors.
+                for line in error.to_string().lines() {
+                    reporter.warning(format_args!("{line}"));
+                }
+                None
+            }
+        },
+        _ => Some(LsColors::default()),
+    };
+    let width = match &args.width {
+        Some(width) => parse_width(width)
+            .ok_or_else(|| UError::new(EXIT_TROUBLE, format!("invalid line width: '{width}'")))?,
+        // The width only matters to the columns and the colors, and COLUMNS is not even checked otherwise.
+        None if format != Format::Columns && colors.is_none() => DEFAULT_WIDTH,
+        None => match std::env::var("COLUMNS") {
+            Ok(columns) if !columns.is_empty() => parse_width(&columns).unwrap_or_else(|| {
+                reporter.warning(format_args!(
+                    "ignoring invalid width in environment variable COLUMNS: '{columns}'"
+                ));
+                terminal_width(terminal)
+            }),
+            _ => terminal_width(terminal),
+        },
+    };
+    Ok(LsOptions {
+        hidden: if args.all {
+            Hidden::All
+        } else if args.almost_all {
+            Hidden::AlmostAll
+        } else {
+            Hidden::Ignore
+        },
+        format,
+        width,
+        owner: !args.no_owner,
+        group: !args.no_group,
+        human: if args.human_readable {
+            Some(Base::Base1024)
+        } else if args.si {
+            Some(Base::Base1000)
+        } else {
+            None
+        },
+        sort: SORT_OPTIONS
+            .into_iter()
+            .filter_map(|(id, sort)| Some((last_index(matches, id)?, sort)))
+            .max_by_key(|&(index, _)| index)
+            .map_or(Sort::Name, |(_, sort)| sort),
+        reverse: args.reverse,
+        recursive: args.recursive,
+        colors,
+    })
+}
+
+/// Get the position of the last occurrence of a flag on the command line.
+fn last_index(matches: &ArgMatches, id: &str) -> Option<usize> {
+    // The flags which are not given have a default value, at the end.
+    if matches.value_source(id) != Some(ValueSource::CommandLine) {
+        return None;
+    }
+    matches.indices_of(id)?.max()
+}
+
+fn parse_width(width: &str) -> Option<usize> {
+    width.parse().ok()
+}
+
+/// Get the width of standard output if it is a terminal which tells it.
+fn terminal_width(terminal: bool) -> usize {
+    #[cfg(unix)]
+    if terminal {
+        let mut size = libc::winsize {
+            ws_row: 0,
+            ws_col: 0,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+        // SAFETY: TIOCGWINSZ only writes a winsize to the pointer it is given.
+        let result = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+        if result == 0 && size.ws_col > 0 {
+            return size.ws_col.into();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = terminal;
+    DEFAULT_WIDTH
+}
+
+/// Which entries whose names start with `.` are listed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Hidden {
+    /// None of them.
+    #[default]
+    Ignore,
+    /// All of them except `.` and `..` (`-A`).
+    AlmostAll,
+    /// All of them, and `.` and `..` as well, even if the directory does not contain them (`-a`).
+    All,
+}
+
+/// How the entries are laid out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    /// One entry per line (`-1`).
+    #[default]
+    OneLine,
+    /// In as many columns as fit in the width, filled from top to bottom (`-C`).
+    Columns,
+    /// One entry per line, with its metadata (`-l`).
+    Long,
+}
+
+/// The order of the entries.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Sort {
+    /// By name, byte by byte like sort does.
+    #[default]
+    Name,
+    /// By modification time, newest first, and then by name (`-t`).
+    Time,
+    /// By size, largest first, and then by name (`-S`).
+    Size,
+    /// By extension, and then by name (`-X`).
+    Extension,
+    /// In the order of the directory (`-U`), which is not reversed by `-r` either.
+    None,
+}
+
+/// The behaviour of ls.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LsOptions {
+    /// Which entries whose names start with `.` are listed.
+    pub hidden: Hidden,
+    /// How the entries are laid out.
+    pub format: Format,
+    /// The width that the columns must fit in, or 0 for an unlimited width, in which case the columns are
+    /// aligned with blanks only.
+    pub width: usize,
+    /// Show the owner in the long format.
+    pub owner: bool,
+    /// Show the group in the long format.
+    pub group: bool,
+    /// Print the sizes in the long format in the powers of this base, instead of in bytes.
+    pub human: Option<Base>,
+    /// The order of the entries.
+    pub sort: Sort,
+    /// Reverse the order of the entries, unless they are not sorted.
+    pub reverse: bool,
+    /// List the subdirectories of the directories recursively.
+    pub recursive: bool,
+    /// The colors of the names, if they are colored.
+    pub colors: Option<LsColors>,
+}
+
+impl Default for LsOptions {
+    /// The default behaviour of ls when standard output is not a terminal.
+    fn default() -> Self {
+        LsOptions {
+            hidden: Hidden::Ignore,
+            format: Format::OneLine,
+            width: DEFAULT_WIDTH,
+            owner: true,
+            group: true,
+            human: None,
+            sort: Sort::Name,
+            reverse: false,
+            recursive: false,
+            colors: None,
+        }
+    }
+}
+
+impl LsOptions {
+    /// Check whether the metadata of every entry is needed.
+    fn needs_metadata(&self) -> bool {
+        self.format == Format::Long
+            || self.recursive
+            || matches!(self.sort, Sort::Time | Sort::Size)
+            || self.colors.is_some()
+    }
+}
+
+/// A file to list.
+#[derive(Clone, Debug)]
+pub struct Entry {
+    /// The name to print, which is the name of the entry in its directory, or the operand as it was given.
+    pub name: OsString,
+    /// The path through which the file is accessed.
+    pub path: PathBuf,
+    /// The metadata of the file, if it was needed and could be read. It is that of the symbolic link itself,
+    /// except for the operands which point to directories outside of the long format.
+    pub metadata: Option<Metadata>,
+}
+
+impl Entry {
+    /// Create an entry, reading its metadata if `options` needs it.
+    fn new(name: OsString, path: PathBuf, options: &LsOptions) -> Self {
+        let metadata = match options.needs_metadata() {
+            true => fs::symlink_metadata(&path).ok(),
+            false => None,
+        };
+        Entry {
+            name,
+            path,
+            metadata,
+        }
+    }
+}
+
+/// Read the entries of the directory at `path` which `options` selects, in the order in which they are
+/// listed.
+pub fn read_directory(path: &Path, options: &LsOptions) -> io::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    if options.hidden == Hidden::All {
+        for name in [".", ".."] {
+            entries.push(Entry::new(name.into(), path.join(name), options));
+        }
+    }
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if options.hidden == Hidden::Ignore && os_str_bytes(&name).starts_with(b".") {
+            continue;
+        }
+        entries.push(Entry::new(name, entry.path(), options));
+    }
+    sort_entries(&mut entries, options);
+    Ok(entries)
+}
+
+/// Sort entries in the order that `options` selects.
+///
+/// The times and sizes are those of the metadata of the entries, and the entries without metadata count as
+/// the oldest and smallest.
+pub fn sort_entries(entries: &mut [Entry], options: &LsOptions) {
+    fn by_name(a: &Entry, b: &Entry) -> Ordering {
+        os_str_bytes(&a.name).cmp(&os_str_bytes(&b.name))
+    }
+    let modified = |entry: &Entry| {
+        entry
+            .metadata
+            .as_ref()
+            .and_then(|metadata| metadata.modified().ok())
+    };
+    let size = |entry: &Entry| entry.metadata.as_ref().map_or(0, Metadata::len);
+    let extension = |entry: &Entry| {
+        let name = os_str_bytes(&entry.name).into_owned();
+        match name.iter().rposition(|&byte| byte == b'.') {
+            Some(dot) => name[dot..].to_vec(),
+            None => Vec::new(),
+        }
+    };
+    match options.sort {
+        Sort::None => return,
+        Sort::Name => entries.sort_by(by_name),
+        Sort::Time => {
+            entries.sort_by(|a, b| modified(b).cmp(&modified(a)).then_with(|| by_name(a, b)))
+        }
+        Sort::Size => entries.sort_by(|a, b| size(b).cmp(&size(a)).then_with(|| by_name(a, b))),
+        Sort::Extension => entries
+            .sort_by_cached_key(|entry| (extension(entry), os_str_bytes(&entry.name).into_owned())),
+    }
+    if options.reverse {
+        entries.reverse();
+    }
+}
+
+/// List the files given as operands, then the contents of the directories given as operands, each after a
+/// header with its name if there are several operands, and followed by their subdirectories if the listing
+/// is recursive.
+///
+/// The operands which cannot be accessed are reported, and listing continues with the next one. Returns
+/// whether an operand could not be accessed or read, for which GNU exits with [`EXIT_TROUBLE`].
+pub fn ls<W: Write>(
+    operands: &[OsString],
+    options: &LsOptions,
+    stdout: &mut dyn Write,
+    reporter: &mut Reporter<W>,
+) -> UResult<bool> {
+    let mut trouble = false;
+    let mut files = Vec::new();
+    let mut directories = Vec::new();
+    for operand in operands {
+        let path = PathBuf::from(operand);
+        // The symbolic links to directories are listed as directories, except in the long format, which shows
+        // the links themselves. The other symbolic links are listed as links, even when they dangle.
+        let metadata = match options.format {
+            Format::Long => fs::symlink_metadata(&path),
+            _ => match fs::metadata(&path) {
+                Ok(metadata) if metadata.is_dir() => Ok(metadata),
+                Ok(_) => fs::symlink_metadata(&path),
+                Err(error) => fs::symlink_metadata(&path).map_err(|_| error),
+            },
+        };
+        match metadata {
+            Ok(metadata) => {
+                let list = match metadata.is_dir() {
+                    true => &mut directories,
+                    false => &mut files,
+                };
+                list.push(Entry {
+                    name: operand.clone(),
+                    path,
+                    metadata: options.needs_metadata().then_some(metadata),
+                });
+            }
+            Err(error) => {
+                reporter.error(format_args!(
+                    "cannot access '{}': {}",
+                    operand.to_string_lossy(),
+                    io_error_message(&error)
+                ));
+                trouble = true;
+            }
+        }
+    }
+    sort_entries(&mut files, options);
+    sort_entries(&mut directories, options);
+
+    let mut writer = BufWriter::with_capacity(BUFFER_SIZE, stdout);
+    let mut painter = Painter::new(options);
+    let headers = operands.len() > 1 || options.recursive;
+    let mut first = files.is_empty();
+    let result = match options.format {
+        // Like GNU, the fields of the files are aligned with those of the directories as well.
+        Format::Long => write_long(&files, &directories, options, &mut painter, &mut writer),
+        _ => write_entries(&files, options, &mut painter, &mut writer),
+    };
+    // The directories left to list, in reverse order, and whether they are operands.
+    let mut pending: Vec<(Entry, bool)> = directories
+        .into_iter()
+        .rev()
+        .map(|directory| (directory, true))
+        .collect();
+    let result = result.and_then(|()| {
+        while let Some((directory, operand)) = pending.pop() {
+            let entries = match read_directory(&directory.path, options) {
+                Ok(entries) => entries,
+                Err(error) => {
+                    // Print what was listed so far before the error.
+                    writer.flush()?;
+                    reporter.error(format_args!(
+                        "cannot open directory '{}': {}",
+                        directory.name.to_string_lossy(),
+                        io_error_message(&error)
+                    ));
+                    trouble |= operand;
+                    continue;
+                }
+            };
+            if !first {
+                writer.write_all(b"\n")?;
+            }
+            first = false;
+            if headers {
+                writer.write_all(&os_str_bytes(&directory.name))?;
+                writer.write_all(b":\n")?;
+            }
+            if options.format == Format::Long {
+                writeln!(writer, "total {}", total_size(&entries, options.human))?;
+            }
+            write_entries(&entries, options, &mut painter, &mut writer)?;
+            if options.recursive {
+                // The subdirectories are listed next, in order, before the rest of the pending directories.
+                pending.extend(
+                    subdirectories(&directory, entries)
+                        .rev()
+                        .map(|subdirectory| (subdirectory, false)),
+                );
+            }
+        }
+        painter.finish(&mut writer)?;
+        writer.flush()
+    });
+    match result {
+        Ok(()) => Ok(trouble),
+        Err(error) => UError::write(error).map(|()| trouble),
+    }
+}
+
+/// Get the entries of a directory which are directories to recurse into, named after their path from the
+/// directory. The symbolic links to directories are skipped, so that they cannot make a loop.
+fn subdirectories(
+    directory: &Entry,
+    entries: Vec<Entry>,
+) -> impl DoubleEndedIterator<Item = Entry> {
+    let parent = Path::new(&directory.name).to_path_buf();
+    entries
+        .into_iter()
+        .filter(|entry| {
+            entry.metadata.as_ref().is_some_and(Metadata::is_dir)
+                && entry.name != "."
+                && entry.name != ".."
+        })
+        .map(move |entry| Entry {
+            name: parent.join(&entry.name).into_os_string(),
+            ..entry
+        })
+}
+
+fn write_entries(
+    entries: &[Entry],
+    options: &LsOptions,
+    painter: &mut Painter,
+    output: &mut dyn Write,
+) -> io::Result<()> {
+    match options.format {
+        Format::OneLine => {
+            for entry in entries {
+                painter.normal(output)?;
+                painter.entry(entry, 0, output)?;
+                output.write_all(b"\n")?;
+            }
+            Ok(())
+        }
+        Format::Columns => write_columns(entries, options.width, painter, output),
+        Format::Long => write_long(entries, &[], options, painter, output),
+    }
+}
+
+/// Write the entries in as many columns as fit in `width`, filled from top to bottom, and separated by at
+/// least two blanks. The columns are aligned with tabs where they can be, like GNU does, unless the names are
+/// colored.
+fn write_columns(
+    entries: &[Entry],
+    width: usize,
+    painter: &mut Painter,
+    output: &mut dyn Write,
+) -> io::Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let widths: Vec<usize> = entries
+        .iter()
+        .map(|entry| entry.name.to_string_lossy().width())
+        .collect();
+    let (column_widths, tab_size) = match width {
+        0 => (layout(&widths, usize::MAX), 0),
+        // Some terminals mix up the tabs and the colors.
+        width if painter.colors.is_some() => (layout(&widths, width), 0),
+        width => (layout(&widths, width), TAB_SIZE),
+    };
+    let rows = entries.len().div_ceil(column_widths.len());
+    for row in 0..rows {
+        let mut position = 0;
+        for (column, &column_width) in column_widths.iter().enumerate() {
+            let index = column * rows + row;
+            painter.normal(output)?;
+            painter.entry(&entries[index], position, output)?;
+            if index + rows >= entries.len() {
+                break;
+            }
+            indent(
+                position + widths[index],
+                position + column_width,
+                tab_size,
+                output,
+            )?;
+            position += column_width;
+        }
+        output.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Choose the greatest number of columns whose lines fit in `width`, and get the width of each column,
+/// including the two blanks after it except for the last one.
+///
+/// Like GNU, every column is at least [`MIN_COLUMN_WIDTH`] wide, and the lines are only checked against
+/// `width` when a column grows, so that a few short names may be laid out in slightly more than `width`.
+fn layout(widths: &[usize], width: usize) -> Vec<usize> {
+    let max_columns = width.div_ceil(MIN_COLUMN_WIDTH).clamp(1, widths.len());
+    // The width of each column, the width of the lines, and whether they fit, with every number of columns.
+    let mut layouts: Vec<(Vec<usize>, usize, bool)> = (1..=max_columns)
+        .map(|columns| {
+            (
+                vec![MIN_COLUMN_WIDTH; columns],
+                columns * MIN_COLUMN_WIDTH,
+                true,
+            )
+        })
+        .collect();
+    for (index, &name_width) in widths.iter().enumerate() {
+        for (column_widths, line_width, fits) in &mut layouts {
+            let columns = column_widths.len();
+            if !*fits {
+                continue;
+            }
+            let column = index / widths.len().div_ceil(columns);
+            let real_width = name_width + if column == columns - 1 { 0 } else { 2 };
+            if column_widths[column] < real_width {
+                *line_width += real_width - column_widths[column];
+                column_widths[column] = real_width;
+                *fits = *line_width < width;
+            }
+        }
+    }
+    let (column_widths, ..) = layouts
+        .into_iter()
+        .rev()
+        .find(|(column_widths, _, fits)| *fits || column_widths.len() == 1)
+        .expect("there is always one column");
+    column_widths
+}
+
+/// Move from column `from` to column `to` with tabs, or blanks where a tab would go too far or when
+/// `tab_size` is 0.
+fn indent(mut from: usize, to: usize, tab_size: usize, output: &mut dyn Write) -> io::Result<()> {
+    while from < to {
+        if tab_size != 0 && to / tab_size > (from + 1) / tab_size {
+            output.write_all(b"\t")?;
+            from += tab_size - from % tab_size;
+        } else {
+            output.write_all(b" ")?;
+            from += 1;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the names in their colors, if they are colored.
+///
+/// Like GNU, the first escape sequence is preceded by a reset, and the colors are restored at the end if the
+/// sequences are not the usual ones.
+struct Painter<'a> {
+    colors: Option<&'a LsColors>,
+    /// The width of the lines, after which the names which may wrap clear the end of their line, or 0.
+    width: usize,
+    /// Whether the symbolic links are followed to get the color of their targets.
+    follow_links: bool,
+    /// Whether an escape sequence was written.
+    used: bool,
+}
+
+impl<'a> Painter<'a> {
+    fn new(options: &'a LsOptions) -> Self {
+        let colors = options.colors.as_ref();
+        let follow_links = colors.is_some_and(|colors| {
+            colors.is_colored(Indicator::Orphan)
+                || (colors.is_colored(Indicator::Executable) && colors.link_as_target())
+                || (colors.is_colored(Indicator::Missing) && options.format == Format::Long)
+        });
+        Painter {
+            colors,
+            width: options.width,
+            follow_links,
+            used: false,
+        }
+    }
+
+    /// Write the name of an entry which starts at the column `start`, and return its length.
+    fn entry(&mut self, entry: &Entry, start: usize, output: &mut dyn Write) -> io::Result<usize> {
+        let name = os_str_bytes(&entry.name);
+        let color = self.colors.and_then(|colors| {
+            let target = self.target_metadata(entry);
+            color(colors, entry, &name, target.as_ref(), false)
+        });
+        self.name(&name, color, start, output)
+    }
+
+    /// Write the target of the symbolic link of an entry, which starts at the column `start`.
+    fn target(
+        &mut self,
+        entry: &Entry,
+        target: &Path,
+        start: usize,
+        output: &mut dyn Write,
+    ) -> io::Result<()> {
+        let name = os_str_bytes(target.as_os_str());
+        let color = self.colors.and_then(|colors| {
+            let metadata = self.target_metadata(entry);
+            color(colors, entry, &name, metadata.as_ref(), true)
+        });
+        self.name(&name, color, start, output).map(drop)
+    }
+
+    /// Get the metadata of the file which the symbolic link of an entry points to, if it is needed and it
+    /// exists.
+    fn target_metadata(&self, entry: &Entry) -> Option<Metadata> {
+        let link = entry.metadata.as_ref().is_some_and(Metadata::is_symlink);
+        (link && self.follow_links).then(|| fs::metadata(&entry.path).ok())?
+    }
+
+    /// Write a name in a color, or in the normal color, and return its length.
+    fn name(
+        &mut self,
+        name: &[u8],
+        color: Option<&[u8]>,
+        start: usize,
+        output: &mut dyn Write,
+    ) -> io::Result<usize> {
+        let Some(colors) = self.colors else {
+            output.write_all(name)?;
+            return Ok(name.len());
+        };
+        let normal = colors.is_colored(Indicator::Normal);
+        if let Some(color) = color {
+            if normal {
+                self.sequence(colors, &[], output)?;
+            }
+            self.sequence(colors, color, output)?;
+        }
+        output.write_all(name)?;
+        if color.is_some() || normal {
+            self.reset(colors, output)?;
+            let last = start + name.len().max(1) - 1;
+            if self.width != 0 && start / self.width != last / self.width {
+                self.put(get(colors, Indicator::ClearToEol), output)?;
+            }
+        }
+        Ok(name.len())
+    }
+
+    /// Switch to the normal color, before the name of an entry or its line in the long format.
+    fn normal(&mut self, output: &mut dyn Write) -> io::Result<()> {
+        match self.colors {
+            Some(colors) if colors.is_colored(Indicator::Normal) => {
+                self.sequence(colors, get(colors, Indicator::Normal), output)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Restore the colors of the terminal at the end of the output.
+    fn finish(&mut self, output: &mut dyn Write) -> io::Result<()> {
+        match self.colors {
+            Some(colors)
+                if self.used
+                    && (get(colors, Indicator::Left), get(colors, Indicator::Right))
+                        != (b"\x1b[", b"m") =>
+            {
+                self.sequence(colors, &[], output)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Write the escape sequence with the given parameters.
+    fn sequence(
+        &mut self,
+        colors: &LsColors,
+        parameters: &[u8],
+        output: &mut dyn Write,
+    ) -> io::Result<()> {
+        self.put(get(colors, Indicator::Left), output)?;
+        self.put(parameters, output)?;
+        self.put(get(colors, Indicator::Right), output)
+    }
+
+    /// Write the sequence after a colored name.
+    fn reset(&mut self, colors: &LsColors, output: &mut dyn Write) -> io::Result<()> {
+        match colors.get(Indicator::End) {
+            Some(end) => self.put(end, output),
+            None => self.sequence(colors, get(colors, Indicator::Reset), output),
+        }
+    }
+
+    /// Write a part of an escape sequence, after a reset if it is the first one.
+    fn put(&mut self, bytes: &[u8], output: &mut dyn Write) -> io::Result<()> {
+        if !self.used {
+            self.used = true;
+            if let Some(colors) = self.colors {
+                self.reset(colors, output)?;
+            }
+        }
+        output.write_all(bytes)
+    }
+}
+
+/// Get the sequence of an indicator, which is empty if it has none.
+fn get(colors: &LsColors, indicator: Indicator) -> &[u8] {
+    colors.get(indicator).unwrap_or_default()
+}
+
+/// Get the color of an entry named `name`, or with `target`, that of the target of its symbolic link, also
+/// named `name`. The metadata of the target is only given if it exists and the symbolic links are followed.
+///
+/// Like GNU, the regular files are colored by their suffix if they have no other color, and the symbolic
+/// links whose target does not exist are only colored as such if they are followed. The capabilities of the
+/// files are not read, so that `ca` is never used.
+fn color<'a>(
+    colors: &'a LsColors,
+    entry: &Entry,
+    name: &[u8],
+    target_metadata: Option<&Metadata>,
+    target: bool,
+) -> Option<&'a [u8]> {
+    let metadata = entry.metadata.as_ref();
+    let mode = match target_metadata {
+        Some(target_metadata) if target || colors.link_as_target() => st_mode(target_metadata),
+        _ if target => 0,
+        _ => metadata.map_or(0, st_mode),
+    };
+    let colored = |indicator| colors.is_colored(indicator);
+    let mut indicator = if target && target_metadata.is_none() && colored(Indicator::Missing) {
+        Indicator::Missing
+    } else if metadata.is_none() {
+        Indicator::Orphan
+    } else {
+        match format_type_char(mode) {
+            '-' if mode & 0o4000 != 0 && colored(Indicator::Setuid) => Indicator::Setuid,
+            '-' if mode & 0o2000 != 0 && colored(Indicator::Setgid) => Indicator::Setgid,
+            '-' if mode & 0o111 != 0 && colored(Indicator::Executable) => Indicator::Executable,
+            '-' if metadata.is_some_and(|metadata| link_count(metadata) > 1)
+                && colored(Indicator::MultiHardlink) =>
+            {
+                Indicator::MultiHardlink
+            }
+            '-' => Indicator::File,
+            'd' if mode & 0o1002 == 0o1002 && colored(Indicator::StickyOtherWritable) => {
+                Indicator::StickyOtherWritable
+            }
+            'd' if mode & 0o002 != 0 && colored(Indicator::OtherWritable) => {
+                Indicator::OtherWritable
+            }
+            'd' if mode & 0o1000 != 0 && colored(Indicator::Sticky) => Indicator::Sticky,
+            'd' => Indicator::Directory,
+            'l' => Indicator::Link,
+            'p' => Indicator::Fifo,
+            's' => Indicator::Socket,
+            'b' => Indicator::BlockDevice,
+            'c' => Indicator::CharDevice,
+            _ => Indicator::Orphan,
+        }
+    };
+    if indicator == Indicator::File {
+        if let Some(color) = colors.extension(name) {
+            return Some(color);
+        }
+    }
+    if indicator == Indicator::Link
+        && target_metadata.is_none()
+        && (colors.link_as_target() || colored(Indicator::Orphan))
+    {
+        indicator = Indicator::Orphan;
+    }
+    colors.get(indicator)
+}
+
+/// The age from which modification times are shown with the year instead of the time of day: half of an
+/// average Gregorian year, like GNU.
+const SIX_MONTHS: Duration = Duration::from_secs(31_556_952 / 2);
+
+/// The fields of the long format of an entry before its name.
+struct LongFields {
+    mode: String,
+    links: String,
+    owner: Owner,
+    group: Owner,
+    size: Size,
+    time: String,
+}
+
+/// The owner or the group of a file.
+enum Owner {
+    /// The name, aligned to the left.
+    #[cfg_attr(not(unix), allow(dead_code))]
+    Name(String),
+    /// The id, aligned to the right, when it has no name.
+    Id(String),
+}
+
+impl Owner {
+    #[cfg(unix)]
+    fn new(id: u32, name: io::Result<Option<String>>) -> Self {
+        match name {
+            Ok(Some(name)) => Owner::Name(name),
+            _ => Owner::Id(id.to_string()),
+        }
+    }
+
+    fn width(&self) -> usize {
+        match self {
+            Owner::Name(name) => name.width(),
+            Owner::Id(id) => id.len(),
+        }
+    }
+
+    fn write(&self, width: usize, output: &mut dyn Write) -> io::Result<()> {
+        let padding = " ".repeat(width - self.width());
+        match self {
+            Owner::Name(name) => write!(output, "{name}{padding} "),
+            Owner::Id(id) => write!(output, "{padding}{id} "),
+        }
+    }
+}
+
+/// The size of a file, or the major and minor numbers of a device.
+enum Size {
+    Bytes(String),
+    Device(String, String),
+}
+
+impl LongFields {
+    fn new(metadata: Option<&Metadata>, human: Option<Base>, now: SystemTime) -> Self {
+        let Some(metadata) = metadata else {
+            let unknown = || Owner::Id("?".to_owned());
+            return LongFields {
+                mode: "?".repeat(10),
+                links: "?".to_owned(),
+                owner: unknown(),
+                group: unknown(),
+                size: Size::Bytes("?".to_owned()),
+                time: "?".to_owned(),
+            };
+        };
+        #[cfg(unix)]
+        let (owner, group) = {
+            use std::os::unix::fs::MetadataExt;
+            (
+                Owner::new(metadata.uid(), users::name_for_uid(metadata.uid())),
+                Owner::new(metadata.gid(), users::name_for_gid(metadata.gid())),
+            )
+        };
+        #[cfg(not(unix))]
+        let (owner, group) = (Owner::Id("?".to_owned()), Owner::Id("?".to_owned()));
+        let size = match device_numbers(metadata) {
+            Some((major, minor)) => Size::Device(major.to_string(), minor.to_string()),
+            None => Size::Bytes(match human {
+                Some(base) => human_readable(metadata.len(), base),
+                None => metadata.len().to_string(),
+            }),
+        };
+        LongFields {
+            mode: format_mode(st_mode(metadata)),
+            links: link_count(metadata).to_string(),
+            owner,
+            group,
+            size,
+            time: metadata
+                .modified()
+                .map_or_else(|_| "?".to_owned(), |time| format_time(time, now)),
+        }
+    }
+}
+
+/// Write the entries in the long format, with the fields of every entry aligned with those of the others,
+/// and of the entries of `aligned`, which are not written.
+fn write_long(
+    entries: &[Entry],
+    aligned: &[Entry],
+    options: &LsOptions,
+    painter: &mut Painter,
+    output: &mut dyn Write,
+) -> io::Result<()> {
+    let now = SystemTime::now();
+    let lines: Vec<LongFields> = entries
+        .iter()
+        .chain(aligned)
+        .map(|entry| LongFields::new(entry.metadata.as_ref(), options.human, now))
+        .collect();
+    let max = |width: fn(&LongFields) -> usize| lines.iter().map(width).max().unwrap_or(0);
+    let links_width = max(|line| line.links.len());
+    let owner_width = max(|line| line.owner.width());
+    let group_width = max(|line| line.group.width());
+    let major_width = max(|line| match &line.size {
+        Size::Device(major, _) => major.len(),
+        Size::Bytes(_) => 0,
+    });
+    let minor_width = max(|line| match &line.size {
+        Size::Device(_, minor) => minor.len(),
+        Size::Bytes(_) => 0,
+    });
+    let mut size_width = max(|line| match &line.size {
+        Size::Bytes(size) => size.len(),
+        Size::Device(..) => 0,
+    });
+    if major_width > 0 {
+        size_width = size_width.max(major_width + 2 + minor_width);
+    }
+
+    for (entry, line) in entries.iter().zip(&lines) {
+        // The fields are gathered to know in which column the name starts, which GNU counts from the size on
+        // when the owner or the group is shown.
+        let mut fields = Vec::new();
+        write!(fields, "{} {:>links_width$} ", line.mode, line.links)?;
+        if options.owner {
+            line.owner.write(owner_width, &mut fields)?;
+        }
+        if options.group {
+            line.group.write(group_width, &mut fields)?;
+        }
+        let size_start = match options.owner || options.group {
+            true => fields.len(),
+            false => 0,
+        };
+        match &line.size {
+            Size::Bytes(size) => write!(fields, "{size:>size_width$} ")?,
+            Size::Device(major, minor) => write!(
+                fields,
+                "{major:>0$}, {minor:>minor_width$} ",
+                size_width - minor_width - 2
+            )?,
+        }
+        write!(fields, "{} ", line.time)?;
+        painter.normal(output)?;
+        output.write_all(&fields)?;
+        let start = fields.len() - size_start;
+        let end = start + painter.entry(entry, start, output)?;
+        if entry
+            .metadata
+            .as_ref()
+            .is_some_and(|metadata| metadata.is_symlink())
+        {
+            if let Ok(target) = fs::read_link(&entry.path) {
+                output.write_all(b" -> ")?;
+                painter.target(entry, &target, end + 4, output)?;
+            }
+        }
+        output.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Get the space used by the entries in 1024-byte blocks, or human-readable with `-h` or `--si`, for the
+/// `total` line of the long format.
+fn total_size(entries: &[Entry], human: Option<Base>) -> String {
+    let blocks: u64 = entries
+        .iter()
+        .filter_map(|entry| entry.metadata.as_ref())
+        .map(allocated_blocks)
+        .sum();
+    human
+        .map_or(BlockSize::blocks(1024), BlockSize::Human)
+        .format(blocks * 512)
+}
+
+/// Format a modification time like GNU does in the C locale: with the time of day if it is in the six
+/// months before `now`, and with the year otherwise.
+fn format_time(time: SystemTime, now: SystemTime) -> String {
+    // The file may have been modified since the listing started.
+    let now = if time > now { SystemTime::now() } else { now };
+    let recent = now.duration_since(time).is_ok_and(|age| age < SIX_MONTHS);
+    let time: DateTime<Local> = time.into();
+    let format = if recent { "%b %e %H:%M" } else { "%b %e  %Y" };
+    time.format(format).to_string()
+}
+
+#[cfg(unix)]
+fn link_count(metadata: &Metadata) -> u64 {
+    std::os::unix::fs::MetadataExt::nlink(metadata)
+}
+
+#[cfg(not(unix))]
+fn link_count(_metadata: &Metadata) -> u64 {
+    1
+}
+
+/// Get the number of 512-byte blocks allocated to a file.
+#[cfg(unix)]
+fn allocated_blocks(metadata: &Metadata) -> u64 {
+    std::os::unix::fs::MetadataExt::blocks(metadata)
+}
+
+#[cfg(not(unix))]
+fn allocated_blocks(metadata: &Metadata) -> u64 {
+    metadata.len().div_ceil(512)
+}
+
+/// Get the major and minor numbers of a block or character device.
+#[cfg(unix)]
+fn device_numbers(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::{FileTypeExt, MetadataExt};
+    let file_type = metadata.file_type();
+    if !(file_type.is_block_device() || file_type.is_char_device()) {
+        return None;
+    }
+    let device = metadata.rdev() as libc::dev_t;
+    Some((libc::major(device).into(), libc::minor(device).into()))
+}
+
+#[cfg(not(unix))]
+fn device_numbers(_metadata: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::run_util;
+
+    /// Create a tree with hidden files, a file, and two directories.
+    fn tree() -> tempfile::TempDir {
+        let directory = tempfile::tempdir().unwrap();
+        for name in ["file", "B", "a", ".hidden", "d1/x", "d1/.y", "d2/z"] {
+            let path = directory.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        directory
+    }
+
+    #[test]
+    fn test_hidden() {
+        let directory = tree();
+        let path = directory.path().to_str().unwrap();
+        assert_eq!(
+            run_util(run, &["ls", path]),
+            (0, "B\na\nd1\nd2\nfile\n".to_owned(), String::new())
+        );
+        assert_eq!(
+            run_util(run, &["ls", "-A", path]).1,
+            ".hidden\nB\na\nd1\nd2\nfile\n"
+        );
+        assert_eq!(
+            run_util(run, &["ls", "-a", path]).1,
+            ".\n..\n.hidden\nB\na\nd1\nd2\nfile\n"
+        );
+        // The last of -a and -A wins.
+        assert_eq!(
+            run_util(run, &["ls", "-aA", path]).1,
+            ".hidden\nB\na\nd1\nd2\nfile\n"
+        );
+        assert_eq!(
+            run_util(run, &["ls", "-Aa", path]).1,
+            ".\n..\n.hidden\nB\na\nd1\nd2\nfile\n"
+        );
+
+        let entries = read_directory(
+            &directory.path().join("d1"),
+            &LsOptions {
+                hidden: Hidden::All,
+                ..LsOptions::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            entries.iter().map(|entry| &entry.path).collect::<Vec<_>>(),
+            [".", "..", ".y", "x"]
+                .map(|name| directory.path().join("d1").join(name))
+                .iter()
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_operands() {
+        let directory = tree();
+        let path = |name: &str| directory.path().join(name).to_str().unwrap().to_owned();
+        let (d1, d2, file, a) = (path("d1"), path("d2"), path("file"), path("a"));
+        // The files come first, then the directories, each in order.
+        assert_eq!(
+            run_util(run, &["ls", &d2, &file, &d1, &a]).1,
+            format!("{a}\n{file}\n\n{d1}:\nx\n\n{d2}:\nz\n")
+        );
+        assert_eq!(run_util(run, &["ls", &d1]).1, "x\n");
+        assert_eq!(run_util(run, &["ls", &file]).1, format!("{file}\n"));
+        assert_eq!(run_util(run, &["ls", "-a", &file]).1, format!("{file}\n"));
+    }
+
+    #[test]
+    fn test_missing_operand() {
+        let directory = tree();
+        let d1 = directory.path().join("d1");
+        let d1 = d1.to_str().unwrap();
+        // The listing continues, and the header is still printed since there are two operands.
+        assert_eq!(
+            run_util(run, &["ls", "missing", d1]),
+            (
+                2,
+                format!("{d1}:\nx\n"),
+                "ls: cannot access 'missing': No such file or directory\n".to_owned()
+            )
+        );
+        assert_eq!(run_util(run, &["ls", "missing"]).0, 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dangling_symlink() {
+        let directory = tempfile::tempdir().unwrap();
+        let link = directory.path().join("link");
+        std::os::unix::fs::symlink("missing", &link).unwrap();
+        let link = link.to_str().unwrap();
+        assert_eq!(
+            run_util(run, &["ls", link]),
+            (0, format!("{link}\n"), String::new())
+        );
+    }
+
+    #[test]
+    fn test_columns() {
+        let directory = tempfile::tempdir().unwrap();
+        for name in [
+            "a",
+            "bb",
+            "cccccccccc",
+            "dddd",
+            "eeeeeeeeeeee",
+            "f",
+            "gg",
+            "hhhhhhhhh",
+            "iii",
+            "k",
+        ] {
+            fs::write(directory.path().join(name), "").unwrap();
+        }
+        let path = directory.path().to_str().unwrap();
+        // The expected outputs are GNU's.
+        assert_eq!(
+            run_util(run, &["ls", "-C", "-w", "40", path]).1,
+            "a\t    eeeeeeeeeeee  iii\nbb\t    f\t\t  k\ncccccccccc  gg\ndddd\t    hhhhhhhhh\n"
+        );
+        assert_eq!(
+            run_util(run, &["ls", "-C", "-w", "0", path]).1,
+            "a  bb  cccccccccc  dddd  eeeeeeeeeeee  f  gg  hhhhhhhhh  iii  k\n"
+        );
+        assert_eq!(
+            run_util(run, &["ls", "-C", "-w", "1", path]).1,
+            run_util(run, &["ls", path]).1
+        );
+        assert_eq!(
+            run_util(run, &["ls", "-w", "40", path]).1,
+            run_util(run, &["ls", path]).1
+        );
+        assert_eq!(
+            run_util(run, &["ls", "-C1", path]).1,
+            run_util(run, &["ls", path]).1
+        );
+        assert_eq!(
+            run_util(run, &["ls", "-w", "x"]),
+            (2, String::new(), "ls: invalid line width: 'x'\n".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_layout() {
+        assert_eq!(layout(&[1, 1, 1], 80), [3, 3, 3]);
+        assert_eq!(layout(&[2, 1, 1], 80), [4, 3, 3]);
+        assert_eq!(layout(&[2, 1, 1], 10), [4, 3]);
+        // The columns did not grow, so the width was never checked.
+        assert_eq!(layout(&[1, 1, 1], 7), [3, 3, 3]);
+        assert_eq!(layout(&[1, 1, 1], 6), [3, 3]);
+        assert_eq!(layout(&[100], 80), [100]);
+    }
+
+    #[test]
+    fn test_indent() {
+        let indent = |from, to, tab_size| {
+            let mut output = Vec::new();
+            indent(from, to, tab_size, &mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        assert_eq!(indent(6, 8, TAB_SIZE), "\t");
+        assert_eq!(indent(6, 8, 0), "  ");
+        assert_eq!(indent(1, 19, TAB_SIZE), "\t\t   ");
+        // A tab which would only replace one blank is not used.
+        assert_eq!(indent(6, 7, TAB_SIZE), " ");
+        assert_eq!(indent(5, 5, TAB_SIZE), "");
+    }
+
+    /// 2020-01-02 12:00:00 UTC, which is January 2 in every time zone but the furthest ones.
+    const OLD: u64 = 1_577_966_400;
+
+    /// Create a tree with files of different sizes, one of them modified long ago, and a symbolic link.
+    #[cfg(unix)]
+    fn long_tree() -> tempfile::TempDir {
+        use std::os::unix::fs::PermissionsExt;
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path();
+        for (name, size) in [("small", 1), ("large", 12345), ("old", 0)] {
+            fs::write(path.join(name), vec![b'x'; size]).unwrap();
+            fs::set_permissions(path.join(name), fs::Permissions::from_mode(0o644)).unwrap();
+        }
+        let old = fs::File::options()
+            .write(true)
+            .open(path.join("old"))
+            .unwrap();
+        old.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(OLD))
+            .unwrap();
+        std::os::unix::fs::symlink("small", path.join("link")).unwrap();
+        directory
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_long() {
+        use std::os::unix::fs::MetadataExt;
+        let directory = long_tree();
+        let path = directory.path();
+        let metadata = fs::symlink_metadata(path.join("large")).unwrap();
+        let user = users::name_for_uid(metadata.uid())
+            .unwrap()
+            .unwrap_or(metadata.uid().to_string());
+        let group = users::name_for_gid(metadata.gid())
+            .unwrap()
+            .unwrap_or(metadata.gid().to_string());
+        let time = |name: &str| {
+            let modified = fs::symlink_metadata(path.join(name))
+                .unwrap()
+                .modified()
+                .unwrap();
+            format_time(modified, SystemTime::now())
+        };
+        let blocks: u64 = ["large", "link", "old", "small"]
+            .iter()
+            .map(|name| fs::symlink_metadata(path.join(name)).unwrap().blocks())
+            .sum();
+
+        let (code, stdout, stderr) = run_util(run, &["ls", "-l", path.to_str().unwrap()]);
+        assert_eq!((code, stderr.as_str()), (0, ""));
+        // The sizes are aligned to the right, and the year replaces the time of day of the old file.
+        assert_eq!(
+            stdout,
+            format!(
+                "total {}\n\
+                 -rw-r--r-- 1 {user} {group} 12345 {} large\n\
+                 lrwxrwxrwx 1 {user} {group}     5 {} link -> small\n\
+                 -rw-r--r-- 1 {user} {group}     0 Jan  2  2020 old\n\
+                 -rw-r--r-- 1 {user} {group}     1 {} small\n",
+                blocks.div_ceil(2),
+                time("large"),
+                time("link"),
+                time("small"),
+            )
+        );
+
+        // Get the line of the old file, up to its date.
+        let old_line = |option: &str| {
+            let stdout = run_util(run, &["ls", option, path.to_str().unwrap()]).1;
+            let line = stdout
+                .lines()
+                .find(|line| line.ends_with(" old"))
+                .unwrap()
+                .to_owned();
+            line[..line.find(" Jan").unwrap()].to_owned()
+        };
+        assert_eq!(old_line("-o"), format!("-rw-r--r-- 1 {user}     0"));
+        assert_eq!(old_line("-g"), format!("-rw-r--r-- 1 {group}     0"));
+        assert_eq!(old_line("-go"), "-rw-r--r-- 1     0");
+        // -1 does not override -l, unlike -C.
+        assert_eq!(
+            run_util(run, &["ls", "-l1", path.to_str().unwrap()]).1,
+            stdout
+        );
+        assert_eq!(
+            run_util(run, &["ls", "-lC1", path.to_str().unwrap()]).1,
+            "large\nlink\nold\nsmall\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_long_operands() {
+        let directory = long_tree();
+        let path = |name: &str| directory.path().join(name).to_str().unwrap().to_owned();
+        let (code, stdout, _) = run_util(run, &["ls", "-og", &path("link"), &path("small")]);
+        assert_eq!(code, 0);
+        // The symbolic link is not followed, and there is no total.
+        let lines: Vec<&str> = stdout.lines().collect();
+        assert!(lines[0].starts_with("lrwxrwxrwx 1 5 "), "{stdout}");
+        assert!(
+            lines[0].ends_with(&format!(" {} -> small", path("link"))),
+            "{stdout}"
+        );
+        assert!(lines[1].starts_with("-rw-r--r-- 1 1 "), "{stdout}");
+
+        // The sizes of the files are also aligned with those of the directories, like in GNU.
+        let (_, stdout, _) = run_util(
+            run,
+            &[
+                "ls",
+                "-og",
+                &path("small"),
+                directory.path().to_str().unwrap(),
+            ],
+        );
+        let size_width = fs::metadata(directory.path())
+            .unwrap()
+            .len()
+            .to_string()
+            .len();
+        assert!(
+            stdout.starts_with(&format!("-rw-r--r-- 1 {:>size_width$} ", 1)),
+            "{stdout}"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_long_human() {
+        use std::os::unix::fs::MetadataExt;
+        let directory = long_tree();
+        let path = |name: &str| directory.path().join(name).to_str().unwrap().to_owned();
+        let sizes = |options: &[&str]| {
+            let mut command_line = vec!["ls", "-og"];
+            command_line.extend_from_slice(options);
+            let (large, small) = (path("large"), path("small"));
+            command_line.extend_from_slice(&[&large, &small]);
+            let (code, stdout, stderr) = run_util(run, &command_line);
+            assert_eq!((code, stderr.as_str()), (0, ""));
+            stdout
+                .lines()
+                .map(|line| line["-rw-r--r-- 1 ".len()..][..3].to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(sizes(&["-h"]), ["13K", "  1"]);
+        assert_eq!(sizes(&["--human-readable"]), ["13K", "  1"]);
+        assert_eq!(sizes(&["--si"]), ["13k", "  1"]);
+        assert_eq!(sizes(&["-h", "--si"]), ["13k", "  1"]);
+        assert_eq!(sizes(&["--si", "-h"]), ["13K", "  1"]);
+
+        // The total is human-readable as well.
+        let blocks: u64 = ["large", "link", "old", "small"]
+            .iter()
+            .map(|name| fs::symlink_metadata(path(name)).unwrap().blocks())
+            .sum();
+        let (_, stdout, _) = run_util(run, &["ls", "-lh", directory.path().to_str().unwrap()]);
+        let total = format!("total {}\n", human_readable(blocks * 512, Base::Base1024));
+        assert!(stdout.starts_with(&total), "{stdout}");
+        // -h is not the help, which is only --help.
+        assert_eq!(
+            run_util(run, &["ls", "-h", &path("small")]).1,
+            path("small") + "\n"
+        );
+        let (code, stdout, _) = run_util(run, &["ls", "--help"]);
+        assert_eq!(code, 0);
+        assert!(stdout.contains("Usage: ls"), "{stdout}");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_long_device() {
+        let (code, stdout, _) = run_util(run, &["ls", "-og", "/dev/null"]);
+        assert_eq!(code, 0);
+        assert!(stdout.starts_with("crw-rw-rw- 1 1, 3 "), "{stdout}");
+    }
+
+    #[test]
+    fn test_owner() {
+        let write = |owner: Owner, width| {
+            let mut output = Vec::new();
+            owner.write(width, &mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        // Names are aligned to the left, and ids to the right.
+        assert_eq!(write(Owner::Name("ab".to_owned()), 4), "ab   ");
+        assert_eq!(write(Owner::Id("12".to_owned()), 4), "  12 ");
+        assert_eq!(write(Owner::Name("é".to_owned()), 2), "é  ");
+    }
+
+    #[test]
+    fn test_format_time() {
+        let old = SystemTime::UNIX_EPOCH + Duration::from_secs(OLD);
+        let time_of_day = DateTime::<Local>::from(old)
+            .format("Jan  2 %H:%M")
+            .to_string();
+        assert_eq!(format_time(old, old + Duration::from_secs(60)), time_of_day);
+        assert_eq!(
+            format_time(old, old + SIX_MONTHS - Duration::from_secs(1)),
+            time_of_day
+        );
+        // Older than six months, or in the future.
+        assert_eq!(format_time(old, old + SIX_MONTHS), "Jan  2  2020");
+        let future = format_time(SystemTime::now() + SIX_MONTHS, SystemTime::now());
+        assert!(!future.contains(':'), "{future}");
+    }
+
+    /// Set the modification time of a file to some seconds after [`OLD`].
+    fn set_modified(path: &Path, seconds: u64) {
+        let file = fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(OLD + seconds))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_sort() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path();
+        for (name, size, seconds) in [
+            ("b.txt", 10, 0),
+            ("a.rs", 10, 20),
+            ("c", 5, 20),
+            ("d.rs", 30, 10),
+            (".e", 20, 0),
+        ] {
+            fs::write(path.join(name), vec![b'x'; size]).unwrap();
+            set_modified(&path.join(name), seconds);
+        }
+        let path = path.to_str().unwrap();
+        let list = |flags: &str| run_util(run, &["ls", "-A", flags, path]).1;
+        // The ties are sorted by name.
+        assert_eq!(list("-t"), "a.rs\nc\nd.rs\n.e\nb.txt\n");
+        assert_eq!(list("-S"), "d.rs\n.e\na.rs\nb.txt\nc\n");
+        assert_eq!(list("-X"), "c\n.e\na.rs\nd.rs\nb.txt\n");
+        // The whole order is reversed, including the ties.
+        assert_eq!(list("-tr"), "b.txt\n.e\nd.rs\nc\na.rs\n");
+        assert_eq!(list("-Sr"), "c\nb.txt\na.rs\n.e\nd.rs\n");
+        assert_eq!(list("-r"), "d.rs\nc\nb.txt\na.rs\n.e\n");
+        // The last sort option wins, and -r does not reverse the order of the directory.
+        assert_eq!(list("-tS"), list("-S"));
+        assert_eq!(list("-Ut"), list("-t"));
+        assert_eq!(list("-tU"), list("-Ur"));
+        let mut unsorted: Vec<String> = list("-U").lines().map(str::to_owned).collect();
+        unsorted.sort_unstable();
+        assert_eq!(unsorted, [".e", "a.rs", "b.txt", "c", "d.rs"]);
+        // The operands are sorted as well.
+        let operand = |name: &str| format!("{path}/{name}");
+        assert_eq!(
+            run_util(run, &["ls", "-S", &operand("c"), &operand("d.rs")]).1,
+            format!("{}\n{}\n", operand("d.rs"), operand("c"))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_recursive() {
+        let directory = tempfile::tempdir().unwrap();
+        for name in ["a/b/c/file", "a/b/x", "a/y", "d/z", "file"] {
+            let path = directory.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        // The symbolic links to directories are not followed, not even when they make a loop.
+        std::os::unix::fs::symlink("..", directory.path().join("a/loop")).unwrap();
+        let path = directory.path().to_str().unwrap();
+        assert_eq!(
+            run_util(run, &["ls", "-R", path]),
+            (
+                0,
+                format!(
+                    "{path}:\na\nd\nfile\n\n{path}/a:\nb\nloop\ny\n\n{path}/a/b:\nc\nx\n\n\
+                     {path}/a/b/c:\nfile\n\n{path}/d:\nz\n"
+                ),
+                String::new()
+            )
+        );
+        // The subdirectories are sorted like the rest of the entries, and named after the operand.
+        assert_eq!(
+            run_util(run, &["ls", "-Rr", &format!("{path}/a/")]).1,
+            format!("{path}/a/:\ny\nloop\nb\n\n{path}/a/b:\nx\nc\n\n{path}/a/b/c:\nfile\n")
+        );
+        // The . and .. entries are not listed again.
+        assert_eq!(
+            run_util(run, &["ls", "-Ra", &format!("{path}/d")]).1,
+            format!("{path}/d:\n.\n..\nz\n")
+        );
+        // The file operands come first, without headers.
+        let file = format!("{path}/file");
+        assert_eq!(
+            run_util(run, &["ls", "-R", &file, &format!("{path}/d")]).1,
+            format!("{file}\n\n{path}/d:\nz\n")
+        );
+        assert_eq!(run_util(run, &["ls", "-R", &file]).1, format!("{file}\n"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_recursive_unreadable() {
+        use std::os::unix::fs::PermissionsExt;
+        let directory = tempfile::tempdir().unwrap();
+        for name in ["a/secret/file", "b/file"] {
+            let path = directory.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        let secret = directory.path().join("a/secret");
+        fs::set_permissions(&secret, fs::Permissions::from_mode(0o000)).unwrap();
+        let readable = fs::read_dir(&secret).is_ok();
+        let (a, b) = (directory.path().join("a"), directory.path().join("b"));
+        let (a, b) = (a.to_str().unwrap(), b.to_str().unwrap());
+        let result = run_util(run, &["ls", "-R", a, b]);
+        fs::set_permissions(&secret, fs::Permissions::from_mode(0o755)).unwrap();
+        if readable {
+            // The permissions do not apply to root.
+            return;
+        }
+        // The listing continues after the error, which is not a serious trouble.
+        assert_eq!(
+            result,
+            (
+                1,
+                format!("{a}:\nsecret\n\n{b}:\nfile\n"),
+                format!("ls: cannot open directory '{a}/secret': Permission denied\n")
+            )
+        );
+    }
+
+    #[test]
+    fn test_color_when() {
+        let directory = tree();
+        let d1 = directory.path().join("d1");
+        let d1 = d1.to_str().unwrap();
+        // The output of run is never a terminal.
+        assert_eq!(run_util(run, &["ls", "--color=auto", d1]).1, "x\n");
+        assert_eq!(
+            run_util(run, &["ls", "--color=always", "--color=n", d1]).1,
+            "x\n"
+        );
+        // A value must be attached to the option.
+        assert_eq!(
+            run_util(run, &["ls", "--color", "never"]).2,
+            "ls: cannot access 'never': No such file or directory\n"
+        );
+        let (code, stdout, stderr) = run_util(run, &["ls", "--color=a", d1]);
+        assert_eq!((code, stdout.as_str()), (1, ""));
+        assert!(
+            stderr.starts_with("ls: ambiguous argument 'a' for '--color'\nValid arguments are:\n")
+        );
+        assert!(run_util(run, &["ls", "--color=x", d1])
+            .2
+            .starts_with("ls: invalid argument 'x' for '--color'\n"));
+    }
+
+    /// Create a directory with a file of every type that is colored, and a command to list it in color.
+    #[cfg(unix)]
+    fn color_tree() -> (tempfile::TempDir, assert_cmd::Command) {
+        use assert_cmd::cargo::CommandCargoExt;
+        use std::os::unix::fs::PermissionsExt;
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path();
+        fs::create_dir(path.join("dir")).unwrap();
+        for name in ["exe", "file.tar", "plain"] {
+            fs::write(path.join(name), "").unwrap();
+            fs::set_permissions(path.join(name), fs::Permissions::from_mode(0o644)).unwrap();
+        }
+        fs::set_permissions(path.join("exe"), fs::Permissions::from_mode(0o755)).unwrap();
+        std::os::unix::fs::symlink("plain", path.join("link")).unwrap();
+        std::os::unix::fs::symlink("missing", path.join("broken")).unwrap();
+        std::os::unix::net::UnixListener::bind(path.join("sock")).unwrap();
+        let mut command = std::process::Command::cargo_bin("ls").unwrap();
+        command.current_dir(path).env_remove("LS_COLORS");
+        (directory, command.into())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_color() {
+        let (_directory, mut command) = color_tree();
+        // The default colors, which reset the colors before the first one.
+        command.arg("--color").assert().success().stdout(
+            "\x1b[0m\x1b[01;36mbroken\x1b[0m\n\x1b[01;34mdir\x1b[0m\n\x1b[01;32mexe\x1b[0m\nfile.tar\n\
+             \x1b[01;36mlink\x1b[0m\nplain\n\x1b[01;35msock\x1b[0m\n",
+        );
+
+        let (_directory, mut command) = color_tree();
+        command
+            .env("LS_COLORS", "di=01;31:*.TAR=35:or=31:ex=00:fi=4")
+            .args(["--color=always", "-w", "0", "-C"])
+            .assert()
+            .success()
+            .stdout(
+                "\x1b[0m\x1b[31mbroken\x1b[0m  \x1b[01;31mdir\x1b[0m  \x1b[4mexe\x1b[0m  \
+                 \x1b[35mfile.tar\x1b[0m  \x1b[01;36mlink\x1b[0m  \x1b[4mplain\x1b[0m  \
+                 \x1b[01;35msock\x1b[0m\n",
+            );
+
+        // The other sequences of the escapes, which are restored at the end.
+        let (_directory, mut command) = color_tree();
+        command
+            .env("LS_COLORS", "lc=<:rc=>:ec=|:no=N")
+            .args(["--color=always", "dir", "exe", "plain"])
+            .assert()
+            .success()
+            .stdout("|<N><><01;32>exe|\n<N>plain|\n\ndir:\n<>");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_color_long() {
+        let (_directory, mut command) = color_tree();
+        // The targets are only colored when the symbolic links are followed, here because of `or`.
+        let output = command
+            .env("LS_COLORS", "or=31:mi=05")
+            .args(["--color=always", "-l", "broken", "dir", "link"])
+            .output()
+            .unwrap();
+        let lines: Vec<&str> = std::str::from_utf8(&output.stdout)
+            .unwrap()
+            .lines()
+            .collect();
+        assert_eq!(lines.len(), 5, "{lines:?}");
+        assert!(lines[0].ends_with(" \x1b[0m\x1b[31mbroken\x1b[0m -> \x1b[05mmissing\x1b[0m"));
+        assert!(lines[1].ends_with(" \x1b[01;36mlink\x1b[0m -> plain"));
+        assert_eq!(lines[3], "dir:");
+
+        let (_directory, mut command) = color_tree();
+        let output = command
+            .args(["--color=always", "-l", "link"])
+            .output()
+            .unwrap();
+        assert!(output
+            .stdout
+            .ends_with(b" \x1b[0m\x1b[01;36mlink\x1b[0m -> plain\n"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_color_invalid() {
+        let (_directory, mut command) = color_tree();
+        command
+            .env("LS_COLORS", "di=1:zz=2")
+            .args(["--color=always", "dir", "exe"])
+            .assert()
+            .success()
+            .stdout("exe\n\ndir:\n")
+            .stderr(
+                "ls: unrecognized prefix: 'zz'\n\
+                 ls: unparsable value for LS_COLORS environment variable\n",
+            );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_name() {
+        use std::os::unix::ffi::OsStrExt;
+        let directory = tempfile::tempdir().unwrap();
+        fs::write(
+            directory.path().join(std::ffi::OsStr::from_bytes(b"\xff")),
+            "",
+        )
+        .unwrap();
+        let args: Vec<OsString> = vec!["ls".into(), directory.path().into()];
+        let mut stdout = Vec::new();
+        assert_eq!(run(&args, &mut &b""[..], &mut stdout, &mut Vec::new()), 0);
+        assert_eq!(stdout, b"\xff\n");
+    }
+}
@@ -1,6 +1,129 @@
-mod echo;
+// A busybox-style multicall binary, which runs the applet named by argv[0] or by the first argument.
+
+#[cfg(feature = "completions")]
+use rust_coreutils::common::completions;
+#[cfg(feature = "manpages")]
+use rust_coreutils::common::manpages;
+use rust_coreutils::common::pipes;
+use rust_coreutils::common::utility::Utility;
+use rust_coreutils::UTILITIES;
+use std::ffi::{OsStr, OsString};
+use std::path::Path;
+
+/// The entry point of an applet, which takes the command line including the applet name.
+type Applet = fn(Vec<OsString>) -> i32;
+
+/// The applets that are not utilities themselves.
+const HELPER_APPLETS: &[(&str, Applet)] = &[
+    #[cfg(feature = "completions")]
+    ("completions", completions::uumain),
+    #[cfg(feature = "manpages")]
+    ("manpages", manpages::uumain),
+];
+
+fn find_applet(name: &OsStr) -> Option<Applet> {
+    // Compare the file stem, so that `echo.exe` and `/usr/bin/echo` run echo as well.
+    let name = Path::new(name).file_stem()?;
+    if let Some(utility) = Utility::find(name) {
+        return Some(utility.uumain);
+    }
+    HELPER_APPLETS
+        .iter()
+        .find(|(applet, _)| name == *applet)
+        .map(|&(_, applet)| applet)
+}
+
+fn print_applets(output: &mut dyn std::io::Write) {
+    let mut names: Vec<&str> = UTILITIES.iter().map(|utility| utility.name).collect();
+    names.extend(HELPER_APPLETS.iter().map(|(name, _)| name));
+    names.sort_unstable();
+    for name in names {
+        let _ = writeln!(output, "{name}");
+    }
+}
 
 fn main() {
-    println!("Hello, world!");
-    echo::main();
+    pipes::init();
+    let args: Vec<OsString> = std::env::args_os().collect();
+
+    // Invoked through a symlink named after the applet.
+    if let Some(applet) = args.first().and_then(|name| find_applet(name)) {
+        std::process::exit(applet(args));
+    }
+
+    let Some(name) = args.get(1) else {
+        eprintln!("Usage: coreutils <applet> [arguments]...\n\nAvailable applets:");
+        print_applets(&mut std::io::stderr());
+        std::process::exit(1);
+    };
+
+    if name == "--list" {
+        print_applets(&mut std::io::stdout());
+        return;
+    }
+
+    match find_applet(name) {
+        Some(applet) => std::process::exit(applet(args[1..].to_vec())),
+        None => {
+            eprintln!(
+                "coreutils: unknown applet '{}'\n\nAvailable applets:",
+                name.to_string_lossy()
+            );
+            print_applets(&mut std::io::stderr());
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_cmd::prelude::*;
+    use std::process::Command;
+
+    #[test]
+    fn test_applet_argument() {
+        let mut cmd = Command::cargo_bin("coreutils").unwrap();
+        cmd.arg("echo")
+            .arg("-n")
+            .arg("hi")
+            .assert()
+            .success()
+            .stdout("hi");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_applet_symlink() {
+        let directory = tempfile::tempdir().unwrap();
+        let link = directory.path().join("echo");
+        std::os::unix::fs::symlink(
+            Command::cargo_bin("coreutils").unwrap().get_program(),
+            &link,
+        )
+        .unwrap();
+
+        let mut cmd = Command::new(&link);
+        cmd.arg("-n").arg("hi").assert().success().stdout("hi");
+    }
+
+    #[test]
+    fn test_list() {
+        let mut cmd = Command::cargo_bin("coreutils").unwrap();
+        cmd.arg("--list").assert().success().stdout(
+            "base32\nbase64\nbasenc\ncat\nchgrp\nchmod\nchown\ncompletions\ncp\ncut\ndf\ndu\necho\nhead\nln\nls\nmanpages\nmkdir\nmktemp\nmv\nod\nprintf\nreadlink\nrealpath\nrm\nrmdir\nseq\nshred\nsort\nsplit\nstat\ntail\ntouch\ntr\ntruncate\nuniq\nwc\n",
+        );
+    }
+
+    #[test]
+    fn test_unknown_applet() {
+        let mut cmd = Command::cargo_bin("coreutils").unwrap();
+        cmd.arg("nope").assert().code(1).stdout("").stderr(
+            "coreutils: unknown applet 'nope'\n\nAvailable applets:\nbase32\nbase64\nbasenc\ncat\nchgrp\nchmod\nchown\ncompletions\ncp\ncut\ndf\ndu\necho\nhead\nln\nls\nmanpages\nmkdir\nmktemp\nmv\nod\nprintf\nreadlink\nrealpath\nrm\nrmdir\nseq\nshred\nsort\nsplit\nstat\ntail\ntouch\ntr\ntruncate\nuniq\nwc\n",
+        );
+
+        let mut cmd = Command::cargo_bin("coreutils").unwrap();
+        cmd.assert()
+            .code(1)
+            .stderr(predicates::str::contains("Usage: coreutils"));
+    }
 }
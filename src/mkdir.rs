@@ -0,0 +1,414 @@
+//! Create directories.
+//!
+//! With `parents`, the missing directories containing a new one are created too, and a directory which
+//! already exists is not an error:
+//!
+//! ```
+//! use rust_coreutils::mkdir::{create_directory, MkdirOptions};
+//!
+//! let directory = tempfile::tempdir().unwrap();
+//! let nested = directory.path().join("a/b/c");
+//! let options = MkdirOptions {
+//!     parents: true,
+//!     verbose: true,
+//!     ..MkdirOptions::default()
+//! };
+//! let mut stdout = Vec::new();
+//! create_directory(&nested, &options, &mut stdout).unwrap();
+//! create_directory(&nested, &options, &mut stdout).unwrap();
+//! assert!(nested.is_dir());
+//! assert_eq!(String::from_utf8(stdout).unwrap().lines().count(), 3);
+//!
+//! let error = create_directory(&nested, &MkdirOptions::default(), &mut Vec::new()).unwrap_err();
+//! assert!(error.to_string().ends_with("File exists"));
+//! ```
+
+use crate::common::error::{io_error_message, Reporter, UError, UResult, EXIT_FAILURE};
+use crate::common::mode::{parse_mode, umask};
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use clap::Parser;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "mkdir",
+    author,
+    version,
+    about = "Create the DIRECTORY(ies), if they do not already exist.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Set the mode of the new directories, like chmod, rather than a=rwx minus the umask.
+    #[arg(short, long, value_name = "MODE", allow_hyphen_values = true)]
+    mode: Option<String>,
+
+    /// Create the missing parent directories too, and do not fail if a directory exists.
+    #[arg(short, long, default_value_t = false)]
+    parents: bool,
+
+    /// Print a message for each created directory.
+    #[arg(short, long, default_value_t = false)]
+    verbose: bool,
+
+    /// The directories to create.
+    #[arg(value_name = "DIRECTORY")]
+    directories: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "mkdir";
+}
+
+/// The description of mkdir for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run mkdir with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run mkdir with the given command line, including the program name, on the given streams, and return
+/// the exit code.
+pub fn run(
+    args: &[OsString],
+    _stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(args.iter().cloned(), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let mut reporter = Reporter::new("mkdir", stderr);
+    let result = options(&args)
+        .and_then(|options| mkdir(&args.directories, &options, stdout, &mut reporter));
+    reporter.finish(result)
+}
+
+fn options(args: &Args) -> UResult<MkdirOptions> {
+    let mode = match &args.mode {
+        // Like chmod on a directory with all the permissions, so that the clauses without a class are
+        // limited by the umask.
+        Some(mode) => Some(
+            parse_mode(mode, 0o777, true, umask())
+                .map_err(|error| UError::new(EXIT_FAILURE, format!("invalid mode {error}")))?,
+        ),
+        None => None,
+    };
+    Ok(MkdirOptions {
+        parents: args.parents,
+        mode,
+        verbose: args.verbose,
+    })
+}
+
+/// The options of mkdir.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MkdirOptions {
+    /// Create the missing parent directories, and accept the directories which exist (`-p`).
+    pub parents: bool,
+    /// The mode of the new directories (`-m`), rather than all the permissions minus the umask. The
+    /// parent directories created with `parents` get the default mode.
+    pub mode: Option<u32>,
+    /// Print the created directories (`-v`).
+    pub verbose: bool,
+}
+
+/// Create the directories, reporting the failures and continuing with the next one.
+pub fn mkdir<W: Write>(
+    operands: &[OsString],
+    options: &MkdirOptions,
+    stdout: &mut dyn Write,
+    reporter: &mut Reporter<W>,
+) -> UResult<()> {
+    if operands.is_empty() {
+        return Err(UError::usage(EXIT_FAILURE, "missing operand"));
+    }
+    for operand in operands {
+        if let Err(error) = create_directory(Path::new(operand), options, stdout) {
+            reporter.error(format_args!("{error}"));
+        }
+    }
+    Ok(())
+}
+
+/// Create a directory, and print it to `stdout` with `options.verbose`.
+///
+/// With `options.parents`, the missing directories containing it are created and printed first, with
+/// the write and execute permissions of the owner added to the default mode, so that the next ones can
+/// be created in them.
+pub fn create_directory(
+    path: &Path,
+    options: &MkdirOptions,
+    stdout: &mut dyn Write,
+) -> UResult<()> {
+    if options.parents {
+        create_parents(path, options, stdout)?;
+    }
+
+    let mode = options.mode.unwrap_or(0o777);
+    if let Err(error) = make_dir(path, mode) {
+        if options.parents && fs::metadata(path).is_ok_and(|metadata| metadata.is_dir()) {
+            return Ok(());
+        }
+        return Err(cannot_create(path, &io_error_message(&error)));
+    }
+    announce(path, options, stdout);
+    // The umask cleared some of the bits, or the kernel ignored the special ones.
+    if options.mode.is_some() && mode & (umask() | 0o7000) != 0 {
+        set_mode(path, mode)?;
+    }
+    Ok(())
+}
+
+/// Create the missing directories containing a path, from the outermost one.
+fn create_parents(path: &Path, options: &MkdirOptions, stdout: &mut dyn Write) -> UResult<()> {
+    let mut parents: Vec<&Path> = path
+        .ancestors()
+        .skip(1)
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .collect();
+    parents.reverse();
+    for parent in parents {
+        match make_dir(parent, 0o777) {
+            Ok(()) => {
+                announce(parent, options, stdout);
+                let mask = umask();
+                if mask & 0o300 != 0 {
+                    set_mode(parent, (0o777 & !mask) | 0o300)?;
+                }
+            }
+            Err(error) => match fs::metadata(parent) {
+                Ok(metadata) if metadata.is_dir() => {}
+                Ok(_) => return Err(cannot_create(parent, "Not a directory")),
+                Err(_) => return Err(cannot_create(parent, &io_error_message(&error))),
+            },
+        }
+    }
+    Ok(())
+}
+
+/// Print a created directory with `options.verbose`.
+fn announce(path: &Path, options: &MkdirOptions, stdout: &mut dyn Write) {
+    if options.verbose {
+        let _ = writeln!(stdout, "mkdir: created directory '{}'", path.display());
+    }
+}
+
+fn cannot_create(path: &Path, problem: &str) -> UError {
+    let message = format!("cannot create directory '{}': {problem}", path.display());
+    UError::new(EXIT_FAILURE, message)
+}
+
+/// Create a directory with the given permissions, minus the umask.
+#[cfg(unix)]
+fn make_dir(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+    fs::DirBuilder::new().mode(mode & 0o777).create(path)
+}
+
+/// Create a directory. The permissions do not apply on this system.
+#[cfg(not(unix))]
+fn make_dir(path: &Path, _mode: u32) -> io::Result<()> {
+    fs::create_dir(path)
+}
+
+/// Set the mode of a created directory, which the umask does not apply to.
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> UResult<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode)).map_err(|error| {
+        let message = format!(
+            "cannot change permissions of '{}': {}",
+            path.display(),
+            io_error_message(&error)
+        );
+        UError::new(EXIT_FAILURE, message)
+    })
+}
+
+/// Set the mode of a created directory, which does not apply on this system.
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> UResult<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::run_util;
+
+    /// Get the permission bits of a file.
+    #[cfg(unix)]
+    fn mode(path: &Path) -> u32 {
+        use std::os::unix::fs::PermissionsExt;
+        fs::metadata(path).unwrap().permissions().mode() & 0o7777
+    }
+
+    #[test]
+    fn test_parents() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        let path = |name| root.join(name).display().to_string();
+
+        assert_eq!(
+            run_util(run, &["mkdir", &path("a/b/c")]),
+            (
+                EXIT_FAILURE,
+                String::new(),
+                format!(
+                    "mkdir: cannot create directory '{}': No such file or directory\n",
+                    path("a/b/c")
+                )
+            )
+        );
+        assert_eq!(
+            run_util(run, &["mkdir", "-p", &path("a/b/c"), &path("a//d/")]),
+            (0, String::new(), String::new())
+        );
+        assert!(root.join("a/b/c").is_dir() && root.join("a/d").is_dir());
+
+        // An existing directory is only an error without -p.
+        assert_eq!(
+            run_util(run, &["mkdir", "-p", &path("a/b")]),
+            (0, String::new(), String::new())
+        );
+        assert_eq!(
+            run_util(run, &["mkdir", &path("a/b"), &path("e")]),
+            (
+                EXIT_FAILURE,
+                String::new(),
+                format!(
+                    "mkdir: cannot create directory '{}': File exists\n",
+                    path("a/b")
+                )
+            )
+        );
+        assert!(root.join("e").is_dir());
+        assert_eq!(
+            run_util(run, &["mkdir"]),
+            (
+                EXIT_FAILURE,
+                String::new(),
+                "mkdir: missing operand\nTry 'mkdir --help' for more information.\n".to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn test_file_component() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::write(root.join("f"), "").unwrap();
+        let path = |name| root.join(name).display().to_string();
+
+        for (args, problem) in [
+            (
+                &["mkdir", "-p", &path("f/x/y")][..],
+                (path("f"), "Not a directory"),
+            ),
+            (&["mkdir", &path("f/x")], (path("f/x"), "Not a directory")),
+            (&["mkdir", "-p", &path("f")], (path("f"), "File exists")),
+        ] {
+            let (name, problem) = problem;
+            assert_eq!(
+                run_util(run, args),
+                (
+                    EXIT_FAILURE,
+                    String::new(),
+                    format!("mkdir: cannot create directory '{name}': {problem}\n")
+                ),
+                "{args:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_verbose() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir(root.join("a")).unwrap();
+        let path = |name| root.join(name).display().to_string();
+
+        // The existing directories are not printed.
+        let (code, stdout, stderr) = run_util(run, &["mkdir", "-pv", &path("a/b/c"), &path("a/b")]);
+        assert_eq!((code, stderr.as_str()), (0, ""));
+        assert_eq!(
+            stdout,
+            format!(
+                "mkdir: created directory '{}'\nmkdir: created directory '{}'\n",
+                path("a/b"),
+                path("a/b/c")
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_mode() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        let path = |name| root.join(name).display().to_string();
+
+        for (spec, expected) in [
+            ("750", 0o750),
+            ("u=rwx,go=", 0o700),
+            // The umask does not apply to the modes given in full.
+            ("a=rwx", 0o777),
+            ("1777", 0o1777),
+            // A clause without a class is limited by the umask.
+            ("=rwx", 0o777 & !umask()),
+        ] {
+            let name = path(spec);
+            assert_eq!(run_util(run, &["mkdir", "-m", spec, &name]).0, 0, "{spec}");
+            assert_eq!(mode(Path::new(&name)), expected, "{spec}");
+        }
+        assert_eq!(
+            run_util(run, &["mkdir", "-m", "a+z", &path("z")]),
+            (
+                EXIT_FAILURE,
+                String::new(),
+                "mkdir: invalid mode 'a+z'\n".to_owned()
+            )
+        );
+        assert!(!root.join("z").exists());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_parent_modes() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        let path = |name| root.join(name).display().to_string();
+
+        // Only the last directory gets the mode of -m, and the parents can always be written by the owner.
+        assert_eq!(
+            run_util(run, &["mkdir", "-p", "-m", "500", &path("p/q/r")]).0,
+            0
+        );
+        let parent_mode = (0o777 & !umask()) | 0o300;
+        assert_eq!(mode(&root.join("p")), parent_mode);
+        assert_eq!(mode(&root.join("p/q")), parent_mode);
+        assert_eq!(mode(&root.join("p/q/r")), 0o500);
+
+        // The mode of an existing directory is left alone.
+        assert_eq!(
+            run_util(run, &["mkdir", "-p", "-m", "700", &path("p")]).0,
+            0
+        );
+        assert_eq!(mode(&root.join("p")), parent_mode);
+    }
+}
@@ -0,0 +1,535 @@
+//! Create temporary files and directories with names nobody else has, and print them.
+//!
+//! The `X`s of the template are replaced with random letters and digits until the name is one that does
+//! not exist yet. Every name is created with `O_CREAT | O_EXCL`, or `mkdir`, so that a file made by someone
+//! else in the meantime is never taken, only retried:
+//!
+//! ```
+//! use rust_coreutils::mktemp::{mktemp, Create, Template};
+//! use std::ffi::OsStr;
+//!
+//! let directory = tempfile::tempdir().unwrap();
+//! let template = Template::new(OsStr::new("log.XXXX"), Some(OsStr::new(".txt")), Some(directory.path()));
+//! let path = mktemp(&template.unwrap(), Create::File).unwrap();
+//! let name = path.file_name().unwrap().to_str().unwrap();
+//! assert!(name.starts_with("log.") && name.ends_with(".txt") && name.len() == 12);
+//! assert!(path.starts_with(directory.path()) && path.is_file());
+//! ```
+
+use crate::common::error::{io_error_message, Reporter, UError, UResult, EXIT_FAILURE};
+use crate::common::os::os_str_bytes;
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use clap::Parser;
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fmt::{self, Display};
+use std::fs;
+use std::io::{self, ErrorKind, Read, Write};
+use std::ops::Range;
+use std::path::{self, Path, PathBuf};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "mktemp",
+    author,
+    version,
+    about = "Create a temporary file or directory from TEMPLATE, or tmp.XXXXXXXXXX in the temporary \
+        directory, and print its name.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Create a directory, with the mode u=rwx, rather than a file, with the mode u=rw.
+    #[arg(short, long, default_value_t = false)]
+    directory: bool,
+
+    /// Only print a name which does not exist, without creating anything, which is unsafe.
+    #[arg(short = 'u', long, default_value_t = false)]
+    dry_run: bool,
+
+    /// Do not print the errors about creating the file or directory.
+    #[arg(short, long, default_value_t = false)]
+    quiet: bool,
+
+    /// Append SUFF to TEMPLATE, which must then end with the X's. SUFF must not contain a slash.
+    #[arg(long, value_name = "SUFF")]
+    suffix: Option<OsString>,
+
+    /// Interpret TEMPLATE relative to DIR.
+    #[arg(short = 'p', value_name = "DIR", overrides_with = "tmpdir")]
+    parent: Option<OsString>,
+
+    /// Interpret TEMPLATE relative to DIR, or to $TMPDIR, or to /tmp. TEMPLATE must not be absolute.
+    #[arg(
+        long,
+        value_name = "DIR",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "",
+        overrides_with = "parent"
+    )]
+    tmpdir: Option<OsString>,
+
+    /// The name to create: at least three consecutive X's, the last ones unless there is a --suffix, are
+    /// replaced.
+    #[arg(value_name = "TEMPLATE")]
+    templates: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "mktemp";
+}
+
+/// The description of mktemp for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run mktemp with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run mktemp with the given command line, including the program name, on the given streams, and return
+/// the exit code.
+pub fn run(
+    args: &[OsString],
+    _stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(args.iter().cloned(), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let mut reporter = Reporter::new("mktemp", stderr);
+    let result = template(&args).and_then(|template| {
+        let create = match (args.dry_run, args.directory) {
+            (true, _) => Create::Nothing,
+            (false, true) => Create::Directory,
+            (false, false) => Create::File,
+        };
+        match mktemp(&template, create) {
+            Ok(path) => print(&path, create, stdout),
+            Err(_) if args.quiet => {
+                reporter.set_failed();
+                Ok(())
+            }
+            Err(error) => Err(UError::new(
+                EXIT_FAILURE,
+                format!(
+                    "failed to create {} via template '{template}': {}",
+                    match create {
+                        Create::Directory => "directory",
+                        Create::File | Create::Nothing => "file",
+                    },
+                    io_error_message(&error)
+                ),
+            )),
+        }
+    });
+    reporter.finish(result)
+}
+
+fn template(args: &Args) -> UResult<Template> {
+    let template = match &args.templates[..] {
+        [] => OsStr::new(DEFAULT_TEMPLATE),
+        [template] => template,
+        _ => return Err(UError::usage(EXIT_FAILURE, "too many templates")),
+    };
+    // Without a template, the default one is in the temporary directory.
+    let tmpdir = match args.parent.as_ref().or(args.tmpdir.as_ref()) {
+        Some(directory) => Some(temporary_directory(Some(directory), env::var_os("TMPDIR"))),
+        None if args.templates.is_empty() => Some(temporary_directory(None, env::var_os("TMPDIR"))),
+        None => None,
+    };
+    Template::new(template, args.suffix.as_deref(), tmpdir.as_deref())
+}
+
+/// Get the directory of the templates given with `-p` and `--tmpdir`: `directory` unless it is empty, then
+/// `tmpdir_var`, the value of `TMPDIR`, unless it is empty too, then the temporary directory of the system.
+fn temporary_directory(directory: Option<&OsString>, tmpdir_var: Option<OsString>) -> PathBuf {
+    match (directory, tmpdir_var) {
+        (Some(directory), _) if !directory.is_empty() => PathBuf::from(directory),
+        (_, Some(tmpdir)) if !tmpdir.is_empty() => PathBuf::from(tmpdir),
+        // Where `env::temp_dir` would find the empty `TMPDIR`.
+        _ if cfg!(unix) => PathBuf::from("/tmp"),
+        _ => env::temp_dir(),
+    }
+}
+
+/// Print the new name, removing what was created if it cannot be printed, since nobody would know about it.
+fn print(path: &Path, create: Create, stdout: &mut dyn Write) -> UResult<()> {
+    let bytes = os_str_bytes(path.as_os_str());
+    let Err(error) = stdout
+        .write_all(&bytes)
+        .and_then(|()| stdout.write_all(b"\n"))
+        .and_then(|()| stdout.flush())
+    else {
+        return Ok(());
+    };
+    let _ = match create {
+        Create::File => fs::remove_file(path),
+        Create::Directory => fs::remove_dir(path),
+        Create::Nothing => Ok(()),
+    };
+    UError::write(error)
+}
+
+/// The template used without an operand, in the temporary directory.
+pub const DEFAULT_TEMPLATE: &str = "tmp.XXXXXXXXXX";
+
+/// The characters which replace the X's: the letters and digits of ASCII, like GNU mktemp.
+const CHARACTERS: &[u8; 62] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+/// How many names to try before giving up, when they all exist: at least as many as there are names with
+/// three X's.
+const ATTEMPTS: usize = 62 * 62 * 62;
+
+/// What to create with the name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Create {
+    /// A file, with the mode u=rw.
+    File,
+    /// A directory, with the mode u=rwx.
+    Directory,
+    /// Nothing, only find a name which does not exist yet (`-u`).
+    Nothing,
+}
+
+/// A checked template: the name, whose X's are in `xs`, with its directory and suffix.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+    name: OsString,
+    xs: Range<usize>,
+}
+
+impl Template {
+    /// Check a template and append `suffix` to it, or take what follows the last X as the suffix. With a
+    /// `tmpdir`, the template is relative to it and must not be absolute.
+    pub fn new(
+        template: &OsStr,
+        suffix: Option<&OsStr>,
+        tmpdir: Option<&Path>,
+    ) -> UResult<Template> {
+        let invalid = |message: String| Err(UError::new(EXIT_FAILURE, message));
+        let bytes = template.as_encoded_bytes();
+        let mut name = template.to_os_string();
+        let end = match suffix {
+            Some(_) if bytes.last() != Some(&b'X') => {
+                return invalid(format!(
+                    "with --suffix, template '{}' must end in X",
+                    Path::new(template).display()
+                ))
+            }
+            Some(suffix) => {
+                name.push(suffix);
+                bytes.len()
+            }
+            None => bytes
+                .iter()
+                .rposition(|&byte| byte == b'X')
+                .map_or(bytes.len(), |x| x + 1),
+        };
+
+        let bytes = name.as_encoded_bytes();
+        if bytes[end..]
+            .iter()
+            .any(|&byte| path::is_separator(byte.into()))
+        {
+            // The suffix is not valid UTF-8 only where the template is not, so it is split at an ASCII X.
+            let suffix = String::from_utf8_lossy(&bytes[end..]);
+            return invalid(format!(
+                "invalid suffix '{suffix}', contains directory separator"
+            ));
+        }
+        let start = end
+            - bytes[..end]
+                .iter()
+                .rev()
+                .take_while(|&&byte| byte == b'X')
+                .count();
+        if end - start < 3 {
+            return invalid(format!(
+                "too few X's in template '{}'",
+                Path::new(&name).display()
+            ));
+        }
+
+        let Some(tmpdir) = tmpdir else {
+            return Ok(Template {
+                name,
+                xs: start..end,
+            });
+        };
+        if Path::new(template).has_root() {
+            return invalid(format!(
+                "invalid template, '{}'; with --tmpdir, it may not be absolute",
+                Path::new(template).display()
+            ));
+        }
+        // Ending the directory with a separator keeps the template as it is after it.
+        let mut path = tmpdir.join("").into_os_string();
+        let offset = path.len();
+        path.push(name);
+        Ok(Template {
+            name: path,
+            xs: start + offset..end + offset,
+        })
+    }
+
+    /// Get a name with random characters in place of the X's.
+    fn generate(&self) -> io::Result<PathBuf> {
+        let mut bytes = self.name.as_encoded_bytes().to_vec();
+        let mut xs = self.xs.clone().peekable();
+        let mut random = [0; 32];
+        while xs.peek().is_some() {
+            getrandom::fill(&mut random)?;
+            // The bytes from 248 are dropped, so that every character is as likely.
+            for byte in random.iter().filter(|&&byte| byte < 248) {
+                let Some(x) = xs.next() else { break };
+                bytes[x] = CHARACTERS[usize::from(byte % 62)];
+            }
+        }
+        // SAFETY: the bytes are those of an `OsString`, where ASCII characters replaced ASCII X's.
+        Ok(PathBuf::from(unsafe {
+            OsString::from_encoded_bytes_unchecked(bytes)
+        }))
+    }
+}
+
+impl Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Path::new(&self.name).display().fmt(f)
+    }
+}
+
+/// Create a file or directory with a new name from the template, or find a name which does not exist with
+/// [`Create::Nothing`], trying other names while they exist.
+pub fn mktemp(template: &Template, create: Create) -> io::Result<PathBuf> {
+    for _ in 0..ATTEMPTS {
+        let path = template.generate()?;
+        let result = match create {
+            Create::File => create_file(&path),
+            Create::Directory => create_directory(&path),
+            Create::Nothing => match fs::symlink_metadata(&path) {
+                Ok(_) => Err(ErrorKind::AlreadyExists.into()),
+                Err(error) if error.kind() == ErrorKind::NotFound => Ok(()),
+                Err(error) => Err(error),
+            },
+        };
+        match result {
+            Ok(()) => return Ok(path),
+            Err(error) if error.kind() == ErrorKind::AlreadyExists => continue,
+            Err(error) => return Err(error),
+        }
+    }
+    Err(ErrorKind::AlreadyExists.into())
+}
+
+/// Create a file only if it does not exist, with `O_CREAT | O_EXCL`.
+fn create_file(path: &Path) -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path).map(drop)
+}
+
+fn create_directory(path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    return std::os::unix::fs::DirBuilderExt::mode(&mut fs::DirBuilder::new(), 0o700).create(path);
+    #[cfg(not(unix))]
+    fs::create_dir(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::run_util;
+    use std::collections::HashSet;
+
+    fn template(template: &str, suffix: Option<&str>, tmpdir: Option<&str>) -> UResult<Template> {
+        Template::new(
+            OsStr::new(template),
+            suffix.map(OsStr::new),
+            tmpdir.map(Path::new),
+        )
+    }
+
+    /// Check that a name is the template with its X's replaced.
+    fn assert_generated(path: &Path, template: &str) {
+        let name = path.to_str().unwrap();
+        assert_eq!(name.len(), template.len(), "{name}");
+        for (character, expected) in name.bytes().zip(template.bytes()) {
+            match expected {
+                b'X' => assert!(CHARACTERS.contains(&character), "{name}"),
+                _ => assert_eq!(character, expected, "{name}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_template() {
+        let error = |message: &str| Err(UError::new(EXIT_FAILURE, message));
+        assert_eq!(
+            template("fooXX", None, None),
+            error("too few X's in template 'fooXX'")
+        );
+        assert_eq!(
+            template("XaXXbXX", None, None),
+            error("too few X's in template 'XaXXbXX'")
+        );
+        assert_eq!(
+            template("fooXX", Some(".txt"), None),
+            error("too few X's in template 'fooXX.txt'")
+        );
+        assert_eq!(
+            template("dir", None, None),
+            error("too few X's in template 'dir'")
+        );
+        assert_eq!(
+            template("fooXXXb", Some(".txt"), None),
+            error("with --suffix, template 'fooXXXb' must end in X")
+        );
+        assert_eq!(
+            template("fooXXX", Some("/a"), None),
+            error("invalid suffix '/a', contains directory separator")
+        );
+        assert_eq!(
+            template("XXX/b", None, None),
+            error("invalid suffix '/b', contains directory separator")
+        );
+        assert_eq!(
+            template("/abs.XXX", None, Some("/tmp")),
+            error("invalid template, '/abs.XXX'; with --tmpdir, it may not be absolute")
+        );
+
+        // The X's are the last ones, and the suffix follows them.
+        for (xs, suffix, expected) in [
+            ("XXXXXX", None, "XXXXXX"),
+            ("aXXXb.csv", None, "aXXXb.csv"),
+            ("aXXXX", Some(".txt"), "aXXXX.txt"),
+            ("aXXX", Some(""), "aXXX"),
+        ] {
+            let path = template(xs, suffix, None).unwrap().generate().unwrap();
+            assert_generated(&path, expected);
+        }
+        let path = template("XXXaXXX", None, None).unwrap().generate().unwrap();
+        assert!(path.to_str().unwrap().starts_with("XXXa"), "{path:?}");
+    }
+
+    #[test]
+    #[cfg(unix)] // The separators differ elsewhere.
+    fn test_tmpdir() {
+        for (tmpdir, template_, expected) in [
+            ("/tmp", "aXXX", "/tmp/aXXX"),
+            ("/tmp/", "aXXX", "/tmp/aXXX"),
+            ("dir", "sub/aXXX", "dir/sub/aXXX"),
+        ] {
+            let template = template(template_, None, Some(tmpdir)).unwrap();
+            assert_eq!(template.to_string(), expected);
+            assert_generated(&template.generate().unwrap(), expected);
+        }
+
+        let var = |tmpdir: &str| Some(OsString::from(tmpdir));
+        let directory = OsString::from("dir");
+        let empty = OsString::new();
+        assert_eq!(
+            temporary_directory(Some(&directory), var("var")),
+            Path::new("dir")
+        );
+        assert_eq!(
+            temporary_directory(Some(&empty), var("var")),
+            Path::new("var")
+        );
+        assert_eq!(temporary_directory(None, var("var")), Path::new("var"));
+        assert_eq!(temporary_directory(None, var("")), Path::new("/tmp"));
+        assert_eq!(temporary_directory(None, None), Path::new("/tmp"));
+    }
+
+    #[test]
+    fn test_create() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path().to_str().unwrap();
+
+        let (code, stdout, stderr) = run_util(run, &["mktemp", "-p", root]);
+        assert_eq!((code, stderr.as_str()), (0, ""));
+        let file = PathBuf::from(stdout.strip_suffix('\n').unwrap());
+        let expected = directory.path().join("tmp.XXXXXXXXXX");
+        assert_generated(&file, expected.to_str().unwrap());
+        assert!(fs::metadata(&file).unwrap().is_file());
+        let (code, stdout, stderr) = run_util(run, &["mktemp", "-d", &format!("{root}/dirXXX")]);
+        assert_eq!((code, stderr.as_str()), (0, ""));
+        let dir = PathBuf::from(stdout.strip_suffix('\n').unwrap());
+        assert!(fs::metadata(&dir).unwrap().is_dir());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o7777;
+            assert_eq!(mode(&file), 0o600);
+            assert_eq!(mode(&dir), 0o700);
+        }
+
+        let (code, stdout, stderr) = run_util(run, &["mktemp", "-u", "--tmpdir=missing", "aXXX"]);
+        assert_eq!((code, stderr.as_str()), (0, ""));
+        let name = Path::new(stdout.strip_suffix('\n').unwrap());
+        assert_generated(name, Path::new("missing").join("aXXX").to_str().unwrap());
+        assert!(!name.exists());
+
+        let missing = format!("{root}/missing/aXXX");
+        for (args, expected) in [
+            (
+                &[][..],
+                format!("mktemp: failed to create file via template '{missing}': "),
+            ),
+            (
+                &["-d"],
+                format!("mktemp: failed to create directory via template '{missing}': "),
+            ),
+            (&["-q"], String::new()),
+        ] {
+            let (code, stdout, stderr) = run_util(run, &[&["mktemp"], args, &[&missing]].concat());
+            assert_eq!((code, stdout.as_str()), (EXIT_FAILURE, ""));
+            assert!(stderr.starts_with(&expected), "{stderr}");
+        }
+        assert_eq!(
+            run_util(run, &["mktemp", "aXXX", "bXXX"]),
+            (
+                EXIT_FAILURE,
+                String::new(),
+                "mktemp: too many templates\nTry 'mktemp --help' for more information.\n"
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            run_util(run, &["mktemp", "-q", "aXX"]),
+            (
+                EXIT_FAILURE,
+                String::new(),
+                "mktemp: too few X's in template 'aXX'\n".to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn test_no_collisions() {
+        // With three X's, there are 238328 names, so that collisions are tried again fairly often.
+        let directory = tempfile::tempdir().unwrap();
+        let template = template("XXX", None, directory.path().to_str()).unwrap();
+        let mut paths = HashSet::new();
+        for _ in 0..2000 {
+            for create in [Create::File, Create::Directory] {
+                assert!(paths.insert(mktemp(&template, create).unwrap()));
+            }
+        }
+        assert_eq!(fs::read_dir(directory.path()).unwrap().count(), paths.len());
+    }
+}
@@ -0,0 +1,1096 @@
+//! Move or rename files and directories.
+//!
+//! Files are moved with a [`Mover`], which renames them, and copies them to the destination and removes
+//! them when it is on another file system. It refuses to move a file onto itself or a directory into
+//! itself:
+//!
+//! ```
+//! use rust_coreutils::common::error::Reporter;
+//! use rust_coreutils::mv::{Mover, MvOptions};
+//!
+//! let directory = tempfile::tempdir().unwrap();
+//! let source = directory.path().join("source");
+//! let destination = directory.path().join("destination");
+//! std::fs::create_dir(&source).unwrap();
+//!
+//! let options = MvOptions {
+//!     verbose: true,
+//!     ..MvOptions::default()
+//! };
+//! let mut reporter = Reporter::new("mv", Vec::new());
+//! let (mut stdin, mut stdout) = (std::io::empty(), Vec::new());
+//! let mut mover = Mover::new(options, &mut reporter, &mut stdin, &mut stdout);
+//! mover.move_file(&source, &destination);
+//! assert!(destination.is_dir() && !source.exists());
+//! mover.move_file(&destination, &destination.join("inside"));
+//! assert!(reporter.failed());
+//! assert_eq!(stdout, format!("renamed '{}' -> '{}'\n", source.display(), destination.display()).as_bytes());
+//! ```
+
+use crate::common::backup::{self, BackupArgs, BackupMode, BackupOptions};
+use crate::common::error::{io_error_message, Reporter, UError, UResult, EXIT_FAILURE};
+use crate::common::paths::{base_name, is_inside, same_file};
+use crate::common::prompt::Prompter;
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use crate::common::walk::{Follow, Walk, WalkError, WalkOptions};
+use crate::cp::{Copier, CpOptions, Overwrite, Preserve};
+use crate::prompt_yes;
+use clap::{ArgAction, Parser};
+use std::ffi::OsString;
+use std::fmt::{self, Display};
+use std::fs::{self, Metadata};
+use std::io::{self, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "mv",
+    author,
+    version,
+    about = "Rename SOURCE to DEST, or move the SOURCEs into the directory DEST.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Never ask before overwriting a file, which is the default.
+    #[arg(short, long, default_value_t = false, overrides_with_all = ["interactive", "no_clobber"])]
+    force: bool,
+
+    /// Ask before overwriting a file.
+    #[arg(short, long, default_value_t = false, overrides_with_all = ["force", "no_clobber"])]
+    interactive: bool,
+
+    /// Never overwrite a file.
+    #[arg(short, long, default_value_t = false, overrides_with_all = ["force", "interactive"])]
+    no_clobber: bool,
+
+    /// Move all the SOURCEs into DIRECTORY.
+    #[arg(short, long, value_name = "DIRECTORY", action = ArgAction::Append)]
+    target_directory: Vec<PathBuf>,
+
+    /// Treat DEST as a normal file, even if it is a directory.
+    #[arg(short = 'T', long, default_value_t = false)]
+    no_target_directory: bool,
+
+    /// Only replace the files which are older than their SOURCE.
+    #[arg(short, long, default_value_t = false)]
+    update: bool,
+
+    /// Print what is being done.
+    #[arg(short, long, default_value_t = false)]
+    verbose: bool,
+
+    #[command(flatten)]
+    backup: BackupArgs,
+
+    /// The files to move, followed by the destination unless -t is given.
+    #[arg(value_name = "SOURCE... DEST")]
+    files: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "mv";
+}
+
+/// The description of mv for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run mv with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run mv with the given command line, including the program name, on the given streams, and return the
+/// exit code.
+pub fn run(
+    args: &[OsString],
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(args.iter().cloned(), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let mut reporter = Reporter::new("mv", stderr);
+    let (operands, options) = match options(args) {
+        Ok(parsed) => parsed,
+        Err(error) => return reporter.finish(Err(error)),
+    };
+    let result = mv(
+        &operands,
+        &mut Mover::new(options, &mut reporter, stdin, stdout),
+    );
+    reporter.finish(result)
+}
+
+fn options(mut args: Args) -> UResult<(Vec<OsString>, MvOptions)> {
+    if args.target_directory.len() > 1 {
+        return Err(UError::new(
+            EXIT_FAILURE,
+            "multiple target directories specified",
+        ));
+    }
+    if args.backup.given() && args.no_clobber {
+        return Err(UError::usage(
+            EXIT_FAILURE,
+            "options --backup and --no-clobber are mutually exclusive",
+        ));
+    }
+    let overwrite = match (args.interactive, args.no_clobber) {
+        (true, _) => Overwrite::Prompt,
+        (_, true) => Overwrite::Never,
+        _ => Overwrite::Replace,
+    };
+    let options = MvOptions {
+        target_directory: args.target_directory.pop(),
+        no_target_directory: args.no_target_directory,
+        overwrite,
+        update: args.update,
+        verbose: args.verbose,
+        backup: args.backup.options()?,
+    };
+    Ok((args.files, options))
+}
+
+/// The options of mv.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MvOptions {
+    /// The directory to move all the operands into (`-t`), rather than the last operand.
+    pub target_directory: Option<PathBuf>,
+    /// Rename the first operand to the second one even if the second one is a directory (`-T`), rather
+    /// than moving it into the directory.
+    pub no_target_directory: bool,
+    /// What to do with the files which exist at the destination.
+    pub overwrite: Overwrite,
+    /// Keep the files at the destination which are as recent as the source or more, and skip the move
+    /// (`-u`).
+    pub update: bool,
+    /// Print each move, like `renamed 'a' -> 'b'` (`-v`).
+    pub verbose: bool,
+    /// How to back up the files which are replaced.
+    pub backup: BackupOptions,
+}
+
+/// Move the operands to the destination given by the options: into the last operand if it is a directory,
+/// or onto it if there is a single source.
+///
+/// A source which fails to be moved is reported, and the move continues with the next one.
+pub fn mv<W: Write>(operands: &[OsString], mover: &mut Mover<W>) -> UResult<()> {
+    let options = &mover.options;
+    let (sources, directory) = match (&options.target_directory, operands) {
+        (_, []) => return Err(UError::usage(EXIT_FAILURE, "missing file operand")),
+        (Some(_), _) if options.no_target_directory => {
+            return Err(UError::new(
+                EXIT_FAILURE,
+                "cannot combine --target-directory (-t) and --no-target-directory (-T)",
+            ))
+        }
+        (Some(directory), sources) => {
+            match fs::metadata(directory) {
+                Ok(metadata) if metadata.is_dir() => {}
+                Ok(_) => {
+                    let message = format!(
+                        "target directory '{}': Not a directory",
+                        directory.display()
+                    );
+                    return Err(UError::new(EXIT_FAILURE, message));
+                }
+                Err(error) => {
+                    return Err(failure(
+                        format_args!("target directory '{}'", directory.display()),
+                        &error,
+                    ))
+                }
+            }
+            (sources, directory.clone())
+        }
+        (None, [source]) => {
+            let message = format!(
+                "missing destination file operand after '{}'",
+                source.to_string_lossy()
+            );
+            return Err(UError::usage(EXIT_FAILURE, message));
+        }
+        (None, [source, destination]) if options.no_target_directory => {
+            mover.move_file(Path::new(source), Path::new(destination));
+            return Ok(());
+        }
+        (None, [_, _, extra, ..]) if options.no_target_directory => {
+            let message = format!("extra operand '{}'", extra.to_string_lossy());
+            return Err(UError::usage(EXIT_FAILURE, message));
+        }
+        (None, [sources @ .., destination]) => {
+            let destination = Path::new(destination);
+            match fs::metadata(destination) {
+                Ok(metadata) if metadata.is_dir() => (sources, destination.to_path_buf()),
+                Ok(_) if sources.len() > 1 => {
+                    let message = format!("target '{}': Not a directory", destination.display());
+                    return Err(UError::new(EXIT_FAILURE, message));
+                }
+                Err(error) if sources.len() > 1 => {
+                    return Err(failure(
+                        format_args!("target '{}'", destination.display()),
+                        &error,
+                    ))
+                }
+                _ => {
+                    mover.move_file(Path::new(&sources[0]), destination);
+                    return Ok(());
+                }
+            }
+        }
+    };
+
+    for source in sources {
+        let source = Path::new(source);
+        mover.move_file(source, &directory.join(base_name(source)));
+    }
+    Ok(())
+}
+
+/// The options of the copies of the files which cannot be renamed, which keep all their attributes.
+const COPY_OPTIONS: CpOptions = CpOptions {
+    recursive: true,
+    follow: Follow::Never,
+    preserve: Preserve::ALL,
+    overwrite: Overwrite::Replace,
+    force: false,
+    verbose: false,
+};
+
+/// Moves files, asking questions and printing messages on the given streams.
+pub struct Mover<'a, W: Write> {
+    options: MvOptions,
+    reporter: &'a mut Reporter<W>,
+    /// Where the answers to the questions of `-i` are read.
+    answers: &'a mut dyn Read,
+    /// Where `-v` prints the moves.
+    stdout: &'a mut dyn Write,
+    /// Renames a file, which the tests replace to move files as if they were on different file systems.
+    rename: fn(&Path, &Path) -> io::Result<()>,
+}
+
+impl<'a, W: Write> Mover<'a, W> {
+    /// Create a mover with the given options, reporting the failures to `reporter`, reading the answers to
+    /// the questions from `answers`, and printing the moves to `stdout`.
+    pub fn new(
+        options: MvOptions,
+        reporter: &'a mut Reporter<W>,
+        answers: &'a mut dyn Read,
+        stdout: &'a mut dyn Write,
+    ) -> Self {
+        Mover {
+            options,
+            reporter,
+            answers,
+            stdout,
+            rename: |source, destination| fs::rename(source, destination),
+        }
+    }
+
+    /// Move a file or a directory to the given path, replacing the file or the empty directory there, if
+    /// there is one.
+    ///
+    /// A directory only replaces a directory, and a file which is not a directory replaces a file which is
+    /// not one either. The failures are reported.
+    pub fn move_file(&mut self, source: &Path, destination: &Path) {
+        if let Err(error) = self.try_move(source, destination) {
+            self.reporter.error(format_args!("{error}"));
+        }
+    }
+
+    fn try_move(&mut self, source: &Path, destination: &Path) -> UResult<()> {
+        let metadata = fs::symlink_metadata(source)
+            .map_err(|error| failure(format_args!("cannot stat '{}'", source.display()), &error))?;
+        if self.options.overwrite == Overwrite::Never && fs::symlink_metadata(destination).is_ok() {
+            return Ok(());
+        }
+        let mut existing = check_destination(source, &metadata, destination)?;
+        if let Some(existing) = &existing {
+            if self.options.update && !is_older(existing, &metadata) {
+                return Ok(());
+            }
+            if self.options.overwrite == Overwrite::Prompt {
+                let mut prompter = Prompter::new("mv", &mut *self.answers, self.reporter.stderr());
+                if !prompt_yes!(prompter, "overwrite '{}'?", destination.display()) {
+                    return Ok(());
+                }
+            }
+        }
+
+        let mut backup = None;
+        if existing.is_some() && self.options.backup.mode != BackupMode::None {
+            backup::check_source(source, destination, &self.options.backup, "moved")?;
+            backup = backup::back_up(destination, &self.options.backup)?;
+            existing = None;
+        }
+
+        let error = match (self.rename)(source, destination) {
+            Ok(()) => {
+                let backup = match backup {
+                    Some(backup) => format!(" (backup: '{}')", backup.display()),
+                    None => String::new(),
+                };
+                self.print(format_args!(
+                    "renamed '{}' -> '{}'{backup}",
+                    source.display(),
+                    destination.display()
+                ));
+                return Ok(());
+            }
+            Err(error) => error,
+        };
+        if error.kind() == ErrorKind::CrossesDevices {
+            return self.move_by_copy(source, destination, existing.as_ref());
+        }
+        // The system refuses to move a directory into itself with EINVAL, which says less.
+        if error.kind() == ErrorKind::InvalidInput
+            && metadata.is_dir()
+            && is_inside(source, destination)
+        {
+            let message = format!(
+                "cannot move '{}' to a subdirectory of itself, '{}'",
+                source.display(),
+                destination.display()
+            );
+            return Err(UError::new(EXIT_FAILURE, message));
+        }
+        Err(failure(
+            format_args!(
+                "cannot move '{}' to '{}'",
+                source.display(),
+                destination.display()
+            ),
+            &error,
+        ))
+    }
+
+    /// Move a file to another file system by copying it with its attributes and removing it.
+    ///
+    /// The file at the destination is removed first. If the copy fails, what was copied is removed, and the
+    /// source is kept.
+    fn move_by_copy(
+        &mut self,
+        source: &Path,
+        destination: &Path,
+        existing: Option<&Metadata>,
+    ) -> UResult<()> {
+        if let Some(existing) = existing {
+            let removed = match existing.is_dir() {
+                true => fs::remove_dir(destination),
+                false => fs::remove_file(destination),
+            };
+            removed.map_err(|error| {
+                failure(
+                    format_args!(
+                        "inter-device move failed: '{}' to '{}'; unable to remove target",
+                        source.display(),
+                        destination.display()
+                    ),
+                    &error,
+                )
+            })?;
+        }
+
+        let options = CpOptions {
+            verbose: self.options.verbose,
+            ..COPY_OPTIONS
+        };
+        // The failures of the copy are printed like the other ones, but counted apart.
+        let mut reporter = Reporter::new("mv", self.reporter.stderr());
+        Copier::new(options, &mut reporter, &mut io::empty(), &mut *self.stdout)
+            .moving()
+            .copy(source, destination);
+        if reporter.failed() {
+            // Anything at the destination was removed, so it is the incomplete copy.
+            let _ = match fs::symlink_metadata(destination) {
+                Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(destination),
+                Ok(_) => fs::remove_file(destination),
+                Err(error) => Err(error),
+            };
+            self.reporter.set_failed();
+            return Ok(());
+        }
+        self.remove(source);
+        Ok(())
+    }
+
+    /// Remove a file, or a directory and its contents, once they are copied, and report the failures.
+    fn remove(&mut self, path: &Path) {
+        let walk_options = WalkOptions {
+            post_order: true,
+            ..WalkOptions::default()
+        };
+        for entry in Walk::new(vec![path.to_path_buf()], walk_options) {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(WalkError::Io { path, error }) => {
+                    self.reporter.error(format_args!(
+                        "cannot remove '{}': {}",
+                        path.display(),
+                        io_error_message(&error)
+                    ));
+                    continue;
+                }
+                // The symbolic links are not followed, so there are no loops.
+                Err(WalkError::Loop { .. }) => continue,
+            };
+            let (result, removed) = match entry.is_dir() {
+                true => (fs::remove_dir(&entry.path), "removed directory"),
+                false => (fs::remove_file(&entry.path), "removed"),
+            };
+            match result {
+                Ok(()) => self.print(format_args!("{removed} '{}'", entry.path.display())),
+                Err(error) => self.reporter.error(format_args!(
+                    "cannot remove '{}': {}",
+                    entry.path.display(),
+                    io_error_message(&error)
+                )),
+            }
+        }
+    }
+
+    /// Print a message with `-v`.
+    fn print(&mut self, message: fmt::Arguments) {
+        if self.options.verbose {
+            let _ = writeln!(self.stdout, "{message}");
+        }
+    }
+}
+
+/// Check that a file can be moved to the given path, and get the metadata of the file there, if there is
+/// one.
+fn check_destination(
+    source: &Path,
+    metadata: &Metadata,
+    destination: &Path,
+) -> UResult<Option<Metadata>> {
+    let existing = match fs::symlink_metadata(destination) {
+        Ok(existing) => existing,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(error) => {
+            return Err(failure(
+                format_args!("cannot stat '{}'", destination.display()),
+                &error,
+            ))
+        }
+    };
+    // A symbolic link to the destination would replace the only name of the file it points to.
+    let same = same_file(metadata, &existing, source, destination)
+        || (metadata.file_type().is_symlink()
+            && fs::metadata(source)
+                .is_ok_and(|target| same_file(&target, &existing, source, destination)));
+    if same {
+        let message = format!(
+            "'{}' and '{}' are the same file",
+            source.display(),
+            destination.display()
+        );
+        return Err(UError::new(EXIT_FAILURE, message));
+    }
+    match (metadata.is_dir(), existing.is_dir()) {
+        (false, true) => {
+            let message = format!(
+                "cannot overwrite directory '{}' with non-directory",
+                destination.display()
+            );
+            Err(UError::new(EXIT_FAILURE, message))
+        }
+        (true, false) => {
+            let message = format!(
+                "cannot overwrite non-directory '{}' with directory '{}'",
+                destination.display(),
+                source.display()
+            );
+            Err(UError::new(EXIT_FAILURE, message))
+        }
+        _ => Ok(Some(existing)),
+    }
+}
+
+/// Check whether a file was last modified before another one, for `-u`.
+fn is_older(metadata: &Metadata, other: &Metadata) -> bool {
+    match (metadata.modified(), other.modified()) {
+        (Ok(modified), Ok(other)) => modified < other,
+        _ => true,
+    }
+}
+
+fn failure(operation: impl Display, error: &io::Error) -> UError {
+    UError::new(
+        EXIT_FAILURE,
+        format!("{operation}: {}", io_error_message(error)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::{run_util_stderr, run_util_stdin};
+    use std::fs::File;
+
+    /// Rename files as if they were on different file systems.
+    #[cfg(unix)]
+    fn cross_device(_source: &Path, _destination: &Path) -> io::Result<()> {
+        Err(ErrorKind::CrossesDevices.into())
+    }
+
+    /// Move a file with the given options, renaming it with `rename`, and return whether it failed, stdout,
+    /// and stderr.
+    fn move_with(
+        options: MvOptions,
+        rename: fn(&Path, &Path) -> io::Result<()>,
+        source: &Path,
+        destination: &Path,
+    ) -> (bool, String, String) {
+        let mut stderr = Vec::new();
+        let mut reporter = Reporter::new("mv", &mut stderr);
+        let mut stdout = Vec::new();
+        let mut answers = io::empty();
+        let mut mover = Mover::new(options, &mut reporter, &mut answers, &mut stdout);
+        mover.rename = rename;
+        mover.move_file(source, destination);
+        (
+            reporter.failed(),
+            String::from_utf8(stdout).unwrap(),
+            String::from_utf8(stderr).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_rename() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::write(root.join("a"), "data").unwrap();
+        let path = |name| root.join(name).display().to_string();
+
+        assert_eq!(
+            run_util_stderr(run, &["mv", &path("a"), &path("b")]),
+            (0, String::new())
+        );
+        assert!(!root.join("a").exists());
+        assert_eq!(fs::read_to_string(root.join("b")).unwrap(), "data");
+
+        fs::create_dir_all(root.join("d/e")).unwrap();
+        assert_eq!(
+            run_util_stderr(run, &["mv", &path("d"), &path("c")]),
+            (0, String::new())
+        );
+        assert!(root.join("c/e").is_dir() && !root.join("d").exists());
+    }
+
+    #[test]
+    fn test_into_directory() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir_all(root.join("d/sub")).unwrap();
+        for name in ["a", "b", "d/sub/c"] {
+            fs::write(root.join(name), name).unwrap();
+        }
+        let path = |name| root.join(name).display().to_string();
+
+        let (code, stderr) = run_util_stderr(
+            run,
+            &["mv", &path("a"), &path("b"), &path("d/sub/c"), &path("d/")],
+        );
+        assert_eq!((code, stderr.as_str()), (0, ""));
+        for name in ["a", "b", "c"] {
+            assert!(root.join("d").join(name).is_file());
+        }
+
+        let (code, stderr) = run_util_stderr(
+            run,
+            &["mv", "-t", &path("d/sub"), &path("d/a"), &path("d/b")],
+        );
+        assert_eq!((code, stderr.as_str()), (0, ""));
+        assert!(root.join("d/sub/a").exists() && root.join("d/sub/b").exists());
+    }
+
+    #[test]
+    fn test_replace() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::write(root.join("a"), "new").unwrap();
+        fs::write(root.join("b"), "old").unwrap();
+        fs::create_dir_all(root.join("d/x")).unwrap();
+        fs::create_dir(root.join("empty")).unwrap();
+        fs::create_dir_all(root.join("full/x")).unwrap();
+
+        let move_file = |source, destination| {
+            move_with(
+                MvOptions::default(),
+                |source, destination| fs::rename(source, destination),
+                &root.join(source),
+                &root.join(destination),
+            )
+        };
+        assert_eq!(move_file("a", "b"), (false, String::new(), String::new()));
+        assert_eq!(fs::read_to_string(root.join("b")).unwrap(), "new");
+
+        assert_eq!(
+            move_file("d", "empty"),
+            (false, String::new(), String::new())
+        );
+        assert!(root.join("empty/x").is_dir());
+
+        let message = format!(
+            "mv: cannot move '{}' to '{}': Directory not empty\n",
+            root.join("empty").display(),
+            root.join("full").display()
+        );
+        assert_eq!(move_file("empty", "full"), (true, String::new(), message));
+        assert!(root.join("empty/x").is_dir());
+    }
+
+    #[test]
+    fn test_no_target_directory() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir_all(root.join("d/x")).unwrap();
+        fs::create_dir(root.join("e")).unwrap();
+        fs::write(root.join("f"), "").unwrap();
+        let path = |name| root.join(name).display().to_string();
+
+        // Without -T, d would be moved to e/d.
+        assert_eq!(
+            run_util_stderr(run, &["mv", "-T", &path("d"), &path("e")]),
+            (0, String::new())
+        );
+        assert!(root.join("e/x").is_dir() && !root.join("e/d").exists());
+
+        assert_eq!(
+            run_util_stderr(run, &["mv", "-T", &path("f"), &path("e")]),
+            (
+                EXIT_FAILURE,
+                format!(
+                    "mv: cannot overwrite directory '{}' with non-directory\n",
+                    path("e")
+                )
+            )
+        );
+        assert_eq!(
+            run_util_stderr(run, &["mv", "-T", &path("f"), &path("e"), &path("g")]),
+            (
+                EXIT_FAILURE,
+                format!(
+                    "mv: extra operand '{}'\nTry 'mv --help' for more information.\n",
+                    path("g")
+                )
+            )
+        );
+        assert_eq!(
+            run_util_stderr(run, &["mv", "-T", "-t", &path("e"), &path("f")]),
+            (
+                EXIT_FAILURE,
+                "mv: cannot combine --target-directory (-t) and --no-target-directory (-T)\n"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir_all(root.join("d/x")).unwrap();
+        fs::write(root.join("f"), "").unwrap();
+        fs::write(root.join("g"), "").unwrap();
+        let path = |name| root.join(name).display().to_string();
+
+        assert_eq!(
+            run_util_stderr(run, &["mv", &path("f"), &path("missing/f")]),
+            (
+                EXIT_FAILURE,
+                format!(
+                    "mv: cannot move '{}' to '{}': No such file or directory\n",
+                    path("f"),
+                    path("missing/f")
+                )
+            )
+        );
+        assert_eq!(
+            run_util_stderr(run, &["mv", &path("f"), &path("g"), &path("missing")]),
+            (
+                EXIT_FAILURE,
+                format!(
+                    "mv: target '{}': No such file or directory\n",
+                    path("missing")
+                )
+            )
+        );
+        assert_eq!(
+            run_util_stderr(run, &["mv", "-t", &path("g"), &path("f")]),
+            (
+                EXIT_FAILURE,
+                format!("mv: target directory '{}': Not a directory\n", path("g"))
+            )
+        );
+
+        // The sources after a failed one are still moved.
+        let (code, stderr) = run_util_stderr(
+            run,
+            &["mv", &path("missing"), &path("d"), &path("f"), &path("d/x")],
+        );
+        assert_eq!(code, EXIT_FAILURE);
+        assert_eq!(
+            stderr,
+            format!(
+                "mv: cannot stat '{}': No such file or directory\n\
+                 mv: cannot move '{}' to a subdirectory of itself, '{}'\n",
+                path("missing"),
+                path("d"),
+                path("d/x/d")
+            )
+        );
+        assert!(root.join("d/x/f").exists());
+
+        assert_eq!(
+            run_util_stderr(run, &["mv", &path("g"), &path("./g")]),
+            (
+                EXIT_FAILURE,
+                format!(
+                    "mv: '{}' and '{}' are the same file\n",
+                    path("g"),
+                    path("./g")
+                )
+            )
+        );
+        assert!(root.join("g").exists());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_cross_device() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+        use std::time::{Duration, SystemTime};
+
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir_all(root.join("d/e")).unwrap();
+        fs::write(root.join("d/e/f"), "data").unwrap();
+        fs::hard_link(root.join("d/e/f"), root.join("d/g")).unwrap();
+        std::os::unix::fs::symlink("f", root.join("d/e/l")).unwrap();
+        fs::set_permissions(root.join("d/e"), fs::Permissions::from_mode(0o751)).unwrap();
+        let old = SystemTime::UNIX_EPOCH + Duration::new(1_000_000_000, 123_456_789);
+        File::open(root.join("d/e"))
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        fs::create_dir(root.join("c")).unwrap();
+
+        let options = MvOptions {
+            verbose: true,
+            ..MvOptions::default()
+        };
+        let (failed, stdout, stderr) =
+            move_with(options, cross_device, &root.join("d"), &root.join("c"));
+        assert_eq!((failed, stderr.as_str()), (false, ""));
+        let path = |name| root.join(name).display().to_string();
+        assert_eq!(
+            stdout,
+            format!(
+                "created directory '{}'\n\
+                 created directory '{}'\n\
+                 copied '{}' -> '{}'\n\
+                 copied '{}' -> '{}'\n\
+                 copied '{}' -> '{}'\n\
+                 removed '{}'\n\
+                 removed '{}'\n\
+                 removed directory '{}'\n\
+                 removed '{}'\n\
+                 removed directory '{}'\n",
+                path("c"),
+                path("c/e"),
+                path("d/e/f"),
+                path("c/e/f"),
+                path("d/e/l"),
+                path("c/e/l"),
+                path("d/g"),
+                path("c/g"),
+                path("d/e/f"),
+                path("d/e/l"),
+                path("d/e"),
+                path("d/g"),
+                path("d"),
+            )
+        );
+        assert!(!root.join("d").exists());
+
+        // The copies keep the attributes and the hard links, like with cp -a.
+        let e = fs::metadata(root.join("c/e")).unwrap();
+        assert_eq!((e.mode() & 0o7777, e.modified().unwrap()), (0o751, old));
+        assert_eq!(fs::read_link(root.join("c/e/l")).unwrap(), Path::new("f"));
+        let (f, g) = (root.join("c/e/f"), root.join("c/g"));
+        assert_eq!(
+            fs::metadata(f).unwrap().ino(),
+            fs::metadata(g).unwrap().ino()
+        );
+
+        // A file replaces the one at the destination rather than writing into it.
+        fs::write(root.join("new"), "new").unwrap();
+        fs::hard_link(root.join("c/g"), root.join("link")).unwrap();
+        let (failed, _, _) = move_with(
+            MvOptions::default(),
+            cross_device,
+            &root.join("new"),
+            &root.join("c/g"),
+        );
+        assert!(!failed);
+        assert_eq!(fs::read_to_string(root.join("c/g")).unwrap(), "new");
+        assert_eq!(fs::read_to_string(root.join("link")).unwrap(), "data");
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_cross_device_failure() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        // The deepest directory can be created below the source, but not below the destination, whose path
+        // is longer than the system allows.
+        let mut deep = root.join("d");
+        fs::create_dir(&deep).unwrap();
+        fs::write(deep.join("a"), "").unwrap();
+        while deep.as_os_str().len() < 3880 {
+            deep.push("x".repeat(200));
+            fs::create_dir(&deep).unwrap();
+        }
+        let destination = root.join("c".repeat(250));
+
+        let (failed, _, stderr) = move_with(
+            MvOptions::default(),
+            cross_device,
+            &root.join("d"),
+            &destination,
+        );
+        assert!(failed);
+        assert!(stderr.contains("File name too long"), "{stderr}");
+        // What was copied is removed, and the source is kept.
+        assert!(!destination.exists());
+        assert!(root.join("d/a").exists() && deep.exists());
+
+        // The destination is only replaced once it can be removed.
+        fs::create_dir_all(root.join("full/x")).unwrap();
+        fs::write(root.join("f"), "").unwrap();
+        let (failed, _, stderr) = move_with(
+            MvOptions::default(),
+            cross_device,
+            &root.join("d/a"),
+            &root.join("full"),
+        );
+        assert!(failed);
+        assert_eq!(
+            stderr,
+            format!(
+                "mv: cannot overwrite directory '{}' with non-directory\n",
+                root.join("full").display()
+            )
+        );
+        let (failed, _, stderr) = move_with(
+            MvOptions::default(),
+            cross_device,
+            &root.join("d"),
+            &root.join("full"),
+        );
+        assert!(failed);
+        assert_eq!(
+            stderr,
+            format!(
+                "mv: inter-device move failed: '{}' to '{}'; unable to remove target: Directory not empty\n",
+                root.join("d").display(),
+                root.join("full").display()
+            )
+        );
+    }
+
+    #[test]
+    fn test_interactive() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        let path = |name| root.join(name).display().to_string();
+        let reset = || {
+            fs::write(root.join("f"), "new").unwrap();
+            fs::write(root.join("g"), "old").unwrap();
+        };
+
+        reset();
+        let (code, stdout, stderr) =
+            run_util_stdin(run, &["mv", "-i", &path("f"), &path("g")], "n\n");
+        assert_eq!((code, stdout.as_str()), (0, ""));
+        assert_eq!(stderr, format!("mv: overwrite '{}'? ", path("g")));
+        assert_eq!(fs::read_to_string(root.join("g")).unwrap(), "old");
+
+        let (code, _, _) = run_util_stdin(run, &["mv", "-i", &path("f"), &path("g")], "y\n");
+        assert_eq!(code, 0);
+        assert_eq!(fs::read_to_string(root.join("g")).unwrap(), "new");
+
+        // The last of -f, -i, and -n wins, and there is no question for a new file.
+        for (args, replaced) in [
+            (&["-i", "-n"][..], false),
+            (&["-n", "-f"], true),
+            (&["-f", "-i"], false),
+            (&["-n", "-i"], false),
+        ] {
+            reset();
+            let mut command_line = vec!["mv"];
+            command_line.extend(args);
+            let (f, g) = (path("f"), path("g"));
+            command_line.extend([f.as_str(), g.as_str()]);
+            let (code, _, _) = run_util_stdin(run, &command_line, "");
+            assert_eq!(code, 0);
+            let contents = fs::read_to_string(root.join("g")).unwrap();
+            assert_eq!(contents == "new", replaced, "{args:?}");
+        }
+        let (code, _, stderr) = run_util_stdin(run, &["mv", "-i", &path("f"), &path("h")], "");
+        assert_eq!((code, stderr.as_str()), (0, ""));
+        assert!(root.join("h").exists());
+    }
+
+    #[test]
+    fn test_update() {
+        use std::time::{Duration, SystemTime};
+
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        let path = |name| root.join(name).display().to_string();
+        let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        let set = |name, contents, modified| {
+            fs::write(root.join(name), contents).unwrap();
+            File::options()
+                .write(true)
+                .open(root.join(name))
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+        };
+
+        // The destination is as recent as the source, or more.
+        for offset in [0, 1] {
+            set("f", "new", old);
+            set("g", "old", old + Duration::from_secs(offset));
+            let (code, stdout, _) = run_util_stdin(run, &["mv", "-uv", &path("f"), &path("g")], "");
+            assert_eq!((code, stdout.as_str()), (0, ""));
+            assert_eq!(fs::read_to_string(root.join("g")).unwrap(), "old");
+        }
+
+        set("g", "old", old - Duration::from_secs(1));
+        let (code, stdout, _) = run_util_stdin(run, &["mv", "-uv", &path("f"), &path("g")], "");
+        assert_eq!(code, 0);
+        assert_eq!(
+            stdout,
+            format!("renamed '{}' -> '{}'\n", path("f"), path("g"))
+        );
+        assert_eq!(fs::read_to_string(root.join("g")).unwrap(), "new");
+
+        // A missing destination is always created, without questions.
+        let (code, _, stderr) = run_util_stdin(run, &["mv", "-ui", &path("g"), &path("h")], "");
+        assert_eq!((code, stderr.as_str()), (0, ""));
+        assert!(root.join("h").exists());
+    }
+
+    #[test]
+    fn test_backup() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        let path = |name| root.join(name).display().to_string();
+        let contents = |name| fs::read_to_string(root.join(name)).unwrap();
+        fs::write(root.join("g"), "old").unwrap();
+
+        fs::write(root.join("f"), "new").unwrap();
+        let (code, stdout, stderr) = run_util_stdin(
+            run,
+            &[
+                "mv",
+                "-v",
+                "--backup=simple",
+                "-S",
+                "~",
+                &path("f"),
+                &path("g"),
+            ],
+            "",
+        );
+        assert_eq!((code, stderr.as_str()), (0, ""));
+        assert_eq!(
+            stdout,
+            format!(
+                "renamed '{}' -> '{}' (backup: '{}')\n",
+                path("f"),
+                path("g"),
+                path("g~")
+            )
+        );
+        assert_eq!(
+            (contents("g"), contents("g~")),
+            ("new".into(), "old".into())
+        );
+
+        fs::write(root.join("f"), "newer").unwrap();
+        assert_eq!(
+            run_util_stderr(run, &["mv", "--backup=numbered", &path("f"), &path("g")]),
+            (0, String::new())
+        );
+        assert_eq!(
+            (contents("g"), contents("g.~1~")),
+            ("newer".into(), "new".into())
+        );
+
+        // The backup would replace the source before it is moved.
+        assert_eq!(
+            run_util_stderr(
+                run,
+                &["mv", "--backup=simple", "-S", "~", &path("g~"), &path("g")]
+            ),
+            (
+                EXIT_FAILURE,
+                format!(
+                    "mv: backing up '{}' might destroy source;  '{}' not moved\n",
+                    path("g"),
+                    path("g~")
+                )
+            )
+        );
+        assert_eq!(contents("g~"), "old");
+
+        assert_eq!(
+            run_util_stderr(run, &["mv", "-n", "--backup=none", &path("g"), &path("h")]),
+            (
+                EXIT_FAILURE,
+                "mv: options --backup and --no-clobber are mutually exclusive\n\
+                 Try 'mv --help' for more information.\n"
+                    .into()
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_cross_device_backup() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir_all(root.join("d/sub")).unwrap();
+        fs::create_dir(root.join("e")).unwrap();
+
+        // The directory at the destination is backed up rather than removed.
+        let options = MvOptions {
+            backup: BackupOptions {
+                mode: BackupMode::Simple,
+                suffix: "~".into(),
+            },
+            ..MvOptions::default()
+        };
+        let (failed, stdout, stderr) =
+            move_with(options, cross_device, &root.join("d"), &root.join("e"));
+        assert_eq!((failed, stdout.as_str(), stderr.as_str()), (false, "", ""));
+        assert!(root.join("e/sub").is_dir() && root.join("e~").is_dir());
+    }
+}
@@ -0,0 +1,1545 @@
+//! Dump files in octal and other formats.
+//!
+//! The inputs form a single stream, which is dumped with [`od`] in lines of 16 bytes, each starting with the
+//! offset of its first byte:
+//!
+//! ```
+//! use rust_coreutils::common::error::Reporter;
+//! use rust_coreutils::common::input::InputIter;
+//! use rust_coreutils::od::{od, Kind, OdOptions, OutputType};
+//!
+//! let mut inputs = InputIter::with_stdin(Vec::new(), Box::new(&b"od\n"[..]));
+//! let options = OdOptions {
+//!     types: vec![OutputType::new(Kind::Character, 1), OutputType::new(Kind::Hex, 1)],
+//!     ..OdOptions::default()
+//! };
+//! let mut stdout = Vec::new();
+//! let mut reporter = Reporter::new("od", Vec::new());
+//! od(&mut inputs, &options, &mut stdout, &mut reporter).unwrap();
+//! assert_eq!(stdout, b"0000000   o   d  \\n\n         6f  64  0a\n0000003\n");
+//! ```
+//!
+//! The types are parsed from the `-t` syntax of GNU by [`OutputType::parse`], so that `x1z` is hexadecimal
+//! bytes followed by the printable characters.
+
+use crate::common::error::{Reporter, UError, UResult, EXIT_FAILURE};
+use crate::common::input::{InputIter, Source, BUFFER_SIZE};
+use crate::common::size::{parse_prefixed_size, SizeError, SizeErrorKind};
+use crate::common::utility::{self, parse_matches, UtilCommand, Utility};
+use clap::{ArgAction, ArgMatches, Parser};
+use std::cmp::Ordering;
+use std::ffi::OsString;
+use std::fmt::LowerExp;
+use std::io::{self, BufRead, BufReader, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::str::FromStr;
+
+/// The number of bytes of a line.
+const LINE_BYTES: usize = 16;
+
+/// The size of a long double: the 80 bit extended precision of x87, padded to 16 bytes, on x86-64, and a
+/// double where Rust does not know better.
+pub const LONG_DOUBLE_SIZE: usize = if cfg!(all(target_arch = "x86_64", not(target_env = "msvc"))) {
+    16
+} else {
+    8
+};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "od",
+    author,
+    version,
+    about = "Write the bytes of the FILEs, as octal shorts by default, to standard output.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Print the offsets in RADIX: d for decimal, o for octal, x for hexadecimal, or n for none.
+    #[arg(short = 'A', long, value_name = "RADIX")]
+    address_radix: Option<String>,
+
+    /// Skip the first BYTES of the input, which can be hexadecimal with 0x or octal with 0, and have a
+    /// suffix like K.
+    #[arg(short = 'j', long, value_name = "BYTES", allow_hyphen_values = true)]
+    skip_bytes: Option<String>,
+
+    /// Dump at most BYTES of the input.
+    #[arg(short = 'N', long, value_name = "BYTES", allow_hyphen_values = true)]
+    read_bytes: Option<String>,
+
+    /// Print the fields in the TYPEs: a for named characters, c for characters, d, o, u, or x for signed,
+    /// octal, unsigned, or hexadecimal numbers of a size in bytes or C, S, I, or L, or f for floats of a size
+    /// or F, D, or L. A trailing z adds the printable characters.
+    #[arg(short = 't', long, value_name = "TYPE", action = ArgAction::Append)]
+    format: Vec<String>,
+
+    /// Print octal bytes, like -t o1.
+    #[arg(short = 'b', action = ArgAction::Append, num_args = 0, default_missing_value = "o1")]
+    octal_bytes: Vec<String>,
+
+    /// Print the printable characters, the escapes of C like \n, and the other bytes in octal, like -t c.
+    #[arg(short = 'c', action = ArgAction::Append, num_args = 0, default_missing_value = "c")]
+    characters: Vec<String>,
+
+    /// Print octal shorts, which is the default, like -t o2.
+    #[arg(short = 'o', action = ArgAction::Append, num_args = 0, default_missing_value = "o2")]
+    octal_shorts: Vec<String>,
+
+    /// Print hexadecimal shorts, like -t x2.
+    #[arg(short = 'x', action = ArgAction::Append, num_args = 0, default_missing_value = "x2")]
+    hex_shorts: Vec<String>,
+
+    /// Print every line, instead of a * for the lines which are the same as the previous one.
+    #[arg(short = 'v', long, default_value_t = false)]
+    output_duplicates: bool,
+
+    /// The files to dump, as a single stream. With no FILE, or when FILE is -, standard input is read.
+    #[arg(value_name = "FILE")]
+    files: Vec<OsString>,
+}
+
+/// The options which select output types, as `-t` arguments, whose lines are printed in the order of the
+/// options.
+const TYPE_OPTIONS: [&str; 5] = [
+    "format",
+    "octal_bytes",
+    "characters",
+    "octal_shorts",
+    "hex_shorts",
+];
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "od";
+}
+
+/// The description of od for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run od with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run od with the given command line, including the program name, on the given streams, and return the
+/// exit code.
+pub fn run(
+    args: &[OsString],
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let (args, matches): (Args, _) = match parse_matches(args.iter().cloned(), stdout, stderr) {
+        Ok(parsed) => parsed,
+        Err(code) => return code,
+    };
+
+    let mut reporter = Reporter::new("od", stderr);
+    let options = match options(&args, &matches) {
+        Ok(options) => options,
+        Err(error) => return reporter.finish(Err(error)),
+    };
+    let mut inputs = InputIter::with_stdin(
+        args.files,
+        Box::new(BufReader::with_capacity(BUFFER_SIZE, stdin)),
+    );
+    let result = od(&mut inputs, &options, stdout, &mut reporter);
+    reporter.finish(result)
+}
+
+fn options(args: &Args, matches: &ArgMatches) -> UResult<OdOptions> {
+    let mut types = output_types(matches)?;
+    if types.is_empty() {
+        types.push(OutputType::new(Kind::Octal, 2));
+    }
+    let address_radix = match args.address_radix.as_deref() {
+        None | Some("o") => Some(Radix::Octal),
+        Some("d") => Some(Radix::Decimal),
+        Some("x") => Some(Radix::Hex),
+        Some("n") => None,
+        Some(radix) => {
+            return Err(UError::new(
+                EXIT_FAILURE,
+                format!(
+                    "invalid output address radix '{radix}'; it must be one character from [doxn]"
+                ),
+            ))
+        }
+    };
+    let skip_bytes = args
+        .skip_bytes
+        .as_deref()
+        .map(|bytes| parse_bytes('j', bytes));
+    let read_bytes = args
+        .read_bytes
+        .as_deref()
+        .map(|bytes| parse_bytes('N', bytes));
+    Ok(OdOptions {
+        types,
+        output_duplicates: args.output_duplicates,
+        address_radix,
+        skip_bytes: skip_bytes.transpose()?.unwrap_or(0),
+        read_bytes: read_bytes.transpose()?,
+    })
+}
+
+/// Get the output types of the options, in the order of the command line.
+fn output_types(matches: &ArgMatches) -> UResult<Vec<OutputType>> {
+    let mut specs: Vec<(usize, &str)> = Vec::new();
+    for id in TYPE_OPTIONS {
+        let (Some(indices), Some(values)) =
+            (matches.indices_of(id), matches.get_many::<String>(id))
+        else {
+            continue;
+        };
+        specs.extend(indices.zip(values.map(String::as_str)));
+    }
+    specs.sort_unstable();
+    let mut types = Vec::new();
+    for (_, spec) in specs {
+        types
+            .extend(OutputType::parse(spec).map_err(|message| UError::new(EXIT_FAILURE, message))?);
+    }
+    Ok(types)
+}
+
+/// Parse the argument of `-j` or `-N`, with the messages of GNU.
+fn parse_bytes(option: char, bytes: &str) -> UResult<u64> {
+    let size = parse_prefixed_size(bytes).and_then(|size| {
+        u64::try_from(size).map_err(|_| SizeError {
+            kind: SizeErrorKind::TooLarge,
+            input: bytes.to_owned(),
+        })
+    });
+    size.map_err(|error| {
+        let message = match error.kind {
+            SizeErrorKind::TooLarge => format!("-{option} argument '{bytes}' too large"),
+            SizeErrorKind::Invalid if bytes.starts_with(|c: char| c.is_ascii_digit()) => {
+                format!("invalid suffix in -{option} argument '{bytes}'")
+            }
+            SizeErrorKind::Invalid => format!("invalid -{option} argument '{bytes}'"),
+        };
+        UError::new(EXIT_FAILURE, message)
+    })
+}
+
+/// How the bytes of a field are printed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// A number in octal, with leading zeros.
+    Octal,
+    /// A number in hexadecimal, with leading zeros.
+    Hex,
+    /// A signed number in decimal.
+    Signed,
+    /// An unsigned number in decimal.
+    Unsigned,
+    /// A float, with the fewest digits which read back as the same value.
+    Float,
+    /// A printable character, the name of a control character like `nl`, or `sp`, ignoring the high bit.
+    Named,
+    /// A printable character, an escape like `\n`, or a byte in octal.
+    Character,
+}
+
+/// The names of the control characters and of the space, for [`Kind::Named`].
+const NAMES: [&str; 33] = [
+    "nul", "soh", "stx", "etx", "eot", "enq", "ack", "bel", "bs", "ht", "nl", "vt", "ff", "cr",
+    "so", "si", "dle", "dc1", "dc2", "dc3", "dc4", "nak", "syn", "etb", "can", "em", "sub", "esc",
+    "fs", "gs", "rs", "us", "sp",
+];
+
+/// A format of the fields of a line, each made of `size` bytes in the byte order of the machine.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutputType {
+    pub kind: Kind,
+    pub size: usize,
+    /// Follow the line with the printable characters of its bytes, and dots for the other ones.
+    pub printable: bool,
+}
+
+impl OutputType {
+    /// Get the type of the fields of `size` bytes, which is 1, 2, 4, or 8, 4, 8, or [`LONG_DOUBLE_SIZE`] for
+    /// floats, or 1 for characters.
+    pub fn new(kind: Kind, size: usize) -> Self {
+        OutputType {
+            kind,
+            size,
+            printable: false,
+        }
+    }
+
+    /// Parse the types of an argument of `-t`, like `x1z` or `d4fD`, or get the message of GNU if it is
+    /// invalid.
+    pub fn parse(spec: &str) -> Result<Vec<OutputType>, String> {
+        let mut types = Vec::new();
+        let mut chars = spec.chars().peekable();
+        while let Some(c) = chars.next() {
+            let kind = match c {
+                'a' => Kind::Named,
+                'c' => Kind::Character,
+                'd' => Kind::Signed,
+                'f' => Kind::Float,
+                'o' => Kind::Octal,
+                'u' => Kind::Unsigned,
+                'x' => Kind::Hex,
+                c => return Err(format!("invalid character '{c}' in type string '{spec}'")),
+            };
+            let (letters, default, name): (&[(char, usize)], _, _) = match kind {
+                Kind::Named | Kind::Character => (&[], 1, ""),
+                Kind::Float => (
+                    &[('F', 4), ('D', 8), ('L', LONG_DOUBLE_SIZE)],
+                    8,
+                    "floating point",
+                ),
+                _ => (&[('C', 1), ('S', 2), ('I', 4), ('L', 8)], 4, "integral"),
+            };
+            let mut size = default;
+            if let Some(&(_, letter_size)) = chars
+                .peek()
+                .and_then(|c| letters.iter().find(|(letter, _)| letter == c))
+            {
+                chars.next();
+                size = letter_size;
+            } else if !letters.is_empty() {
+                let mut digits = String::new();
+                while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                    digits.push(digit);
+                }
+                if !digits.is_empty() {
+                    size = digits.parse().unwrap_or(usize::MAX);
+                    let sizes: &[usize] = match kind {
+                        Kind::Float => &[4, 8, LONG_DOUBLE_SIZE],
+                        _ => &[1, 2, 4, 8],
+                    };
+                    if !sizes.contains(&size) {
+                        return Err(format!(
+                            "invalid type string '{spec}';\n\
+                             this system doesn't provide a {size}-byte {name} type"
+                        ));
+                    }
+                }
+            }
+            let printable = chars.next_if_eq(&'z').is_some();
+            types.push(OutputType {
+                kind,
+                size,
+                printable,
+            });
+        }
+        Ok(types)
+    }
+
+    /// The number of characters of a field, without the space before it, which fits any value.
+    fn width(&self) -> usize {
+        match (self.kind, self.size) {
+            (Kind::Octal, size) => (size * 8).div_ceil(3),
+            (Kind::Hex, size) => size * 2,
+            (Kind::Signed, 1) => 4,
+            (Kind::Signed, 2) => 6,
+            (Kind::Signed, 4) => 11,
+            (Kind::Unsigned, 1) => 3,
+            (Kind::Unsigned, 2) => 5,
+            (Kind::Unsigned, 4) => 10,
+            (Kind::Signed | Kind::Unsigned, _) => 20,
+            (Kind::Float, 4) => 15,
+            (Kind::Float, 8) => 24,
+            (Kind::Float, _) => 29,
+            (Kind::Named | Kind::Character, _) => 3,
+        }
+    }
+
+    /// Write the text of the field of `bytes`.
+    fn write_field(&self, bytes: &[u8], text: &mut Vec<u8>) {
+        let width = self.width();
+        match self.kind {
+            Kind::Octal => text.extend(format!("{:0width$o}", value(bytes)).bytes()),
+            Kind::Hex => text.extend(format!("{:0width$x}", value(bytes)).bytes()),
+            Kind::Signed => {
+                let shift = 64 - 8 * bytes.len() as u32;
+                let value = ((value(bytes) << shift) as i64) >> shift;
+                text.extend(value.to_string().bytes());
+            }
+            Kind::Unsigned => text.extend(value(bytes).to_string().bytes()),
+            Kind::Float => write_float(bytes, text),
+            Kind::Named => match bytes[0] & 0x7f {
+                byte @ 0..=b' ' => text.extend_from_slice(NAMES[usize::from(byte)].as_bytes()),
+                0x7f => text.extend_from_slice(b"del"),
+                byte => text.push(byte),
+            },
+            Kind::Character => {
+                let escape = match bytes[0] {
+                    b'\0' => b'0',
+                    0x07 => b'a',
+                    0x08 => b'b',
+                    b'\t' => b't',
+                    b'\n' => b'n',
+                    0x0b => b'v',
+                    0x0c => b'f',
+                    b'\r' => b'r',
+                    _ => 0,
+                };
+                match bytes[0] {
+                    _ if escape != 0 => text.extend_from_slice(&[b'\\', escape]),
+                    byte @ b' '..=b'~' => text.push(byte),
+                    byte => text.extend(format!("{byte:03o}").bytes()),
+                }
+            }
+        }
+    }
+}
+
+/// Get the number of a field, in the byte order of the machine.
+fn value(bytes: &[u8]) -> u64 {
+    let fold = |value: u64, &byte: &u8| value << 8 | u64::from(byte);
+    match cfg!(target_endian = "little") {
+        true => bytes.iter().rev().fold(0, fold),
+        false => bytes.iter().fold(0, fold),
+    }
+}
+
+/// A float as `%g` prints it.
+#[derive(Debug, PartialEq, Eq)]
+enum Decimal {
+    Infinite,
+    Nan,
+    Finite {
+        /// The significant digits, without trailing zeros unless the value is 0.
+        digits: Vec<u8>,
+        /// The power of ten of the first digit.
+        exponent: i32,
+        /// The number of significant digits of `%g`, which is at least the number of digits.
+        precision: usize,
+    },
+}
+
+/// Write the float of `bytes` with the fewest digits which read back as the same value, like GNU.
+///
+/// Like `ftoastr` of gnulib, the number of significant digits starts from the precision of the type, or 1
+/// below the normal numbers, and grows until the value is read back.
+fn write_float(bytes: &[u8], text: &mut Vec<u8>) {
+    let (negative, decimal) = match bytes.len() {
+        4 => {
+            let value = f32::from_bits(value(bytes) as u32);
+            let decimal = shortest(value.abs(), value.abs() < f32::MIN_POSITIVE, 6);
+            (value.is_sign_negative(), decimal)
+        }
+        8 => {
+            let value = f64::from_bits(value(bytes));
+            let decimal = shortest(value.abs(), value.abs() < f64::MIN_POSITIVE, 15);
+            (value.is_sign_negative(), decimal)
+        }
+        _ => {
+            let mantissa = u64::from_le_bytes(bytes[..8].try_into().unwrap());
+            let exponent = u16::from_le_bytes([bytes[8], bytes[9]]);
+            (exponent >> 15 == 1, extended(mantissa, exponent & 0x7fff))
+        }
+    };
+    if negative {
+        text.push(b'-');
+    }
+    match decimal {
+        Decimal::Infinite => text.extend_from_slice(b"inf"),
+        Decimal::Nan => text.extend_from_slice(b"nan"),
+        Decimal::Finite {
+            digits,
+            exponent,
+            precision,
+        } if exponent < -4 || exponent >= precision as i32 => {
+            text.push(digits[0]);
+            if digits.len() > 1 {
+                text.push(b'.');
+                text.extend_from_slice(&digits[1..]);
+            }
+            let sign = if exponent < 0 { '-' } else { '+' };
+            text.extend(format!("e{sign}{:02}", exponent.unsigned_abs()).bytes());
+        }
+        Decimal::Finite {
+            digits, exponent, ..
+        } if exponent < 0 => {
+            text.extend_from_slice(b"0.");
+            text.resize(text.len() + exponent.unsigned_abs() as usize - 1, b'0');
+            text.extend_from_slice(&digits);
+        }
+        Decimal::Finite {
+            digits, exponent, ..
+        } => {
+            let integer = exponent as usize + 1;
+            if digits.len() <= integer {
+                text.extend_from_slice(&digits);
+                text.resize(text.len() + integer - digits.len(), b'0');
+            } else {
+                text.extend_from_slice(&digits[..integer]);
+                text.push(b'.');
+                text.extend_from_slice(&digits[integer..]);
+            }
+        }
+    }
+}
+
+/// Get the decimal of the absolute `value`, with the fewest significant digits from `precision`, or 1 if it
+/// is `subnormal`, which read back as the value.
+///
+/// The digits are rounded from the exact value, to the nearest or even like printf, so that they are the ones
+/// of GNU even when more digits than the fewest of Rust are needed.
+fn shortest<T>(value: T, subnormal: bool, precision: usize) -> Decimal
+where
+    T: LowerExp + FromStr + PartialEq,
+{
+    let scientific = format!("{value:e}");
+    let fewest = match scientific.split_once('e') {
+        Some((mantissa, _)) => mantissa.bytes().filter(u8::is_ascii_digit).count(),
+        None if scientific == "inf" => return Decimal::Infinite,
+        None => return Decimal::Nan,
+    };
+    let start = if subnormal { 1 } else { precision };
+    for precision in fewest.max(start).. {
+        let rounded = format!("{value:.*e}", precision - 1);
+        if rounded.parse::<T>().is_ok_and(|parsed| parsed == value) {
+            let (mantissa, exponent) = rounded.split_once('e').unwrap();
+            let mut digits: Vec<u8> = mantissa.bytes().filter(|&byte| byte != b'.').collect();
+            trim_zeros(&mut digits);
+            return Decimal::Finite {
+                digits,
+                exponent: exponent.parse().unwrap(),
+                precision,
+            };
+        }
+    }
+    unreachable!("the fewest digits read back")
+}
+
+/// Get the decimal of the x87 extended precision float with `mantissa` and the biased `exponent`, whose
+/// integer bit is explicit, trying more and more digits from 18, the precision of a long double.
+///
+/// Like in GNU, there are at most `MAX_PRECISION` digits. There is no such float in Rust, so its quotient by
+/// a power of ten is rounded with big numbers, and the rounded digits are compared with the halfway points
+/// to the neighbours of the float to check that they read back.
+fn extended(mantissa: u64, exponent: u16) -> Decimal {
+    const MAX_PRECISION: usize = 21;
+    if exponent == 0x7fff {
+        return match mantissa == 1 << 63 {
+            true => Decimal::Infinite,
+            false => Decimal::Nan,
+        };
+    }
+    // Without the integer bit, the encodings of normal numbers are invalid, and printed as not a number.
+    if exponent != 0 && mantissa >> 63 == 0 {
+        return Decimal::Nan;
+    }
+    let start = if mantissa >> 63 == 0 { 1 } else { 18 };
+    // Like in glibc, the integer bit of the subnormal numbers is ignored, unless nothing else is set. With
+    // it, they never read back, so that GNU prints them with the most digits it tries.
+    let pseudo_subnormal = exponent == 0 && mantissa > 1 << 63;
+    let mantissa = mantissa & !(u64::from(pseudo_subnormal) << 63);
+    if mantissa == 0 {
+        return Decimal::Finite {
+            digits: vec![b'0'],
+            exponent: 0,
+            precision: 1,
+        };
+    }
+    // The float is the mantissa times 2 to this power.
+    let power = i32::from(exponent.max(1)) - 16383 - 63;
+
+    // In units of 2 to the power - 2, the float is 4 times the mantissa, and the values which read back as it
+    // are between the halfway points to its neighbours. The one below is closer for the smallest mantissa of
+    // an exponent, and the halfway points themselves read back as the float with the even mantissa.
+    let mantissa = u128::from(mantissa);
+    let low = match mantissa == 1 << 63 && exponent > 1 {
+        true => 4 * mantissa - 1,
+        false => 4 * mantissa - 2,
+    };
+    let high = 4 * mantissa + 2;
+    let inclusive = mantissa.is_multiple_of(2);
+
+    // The power of ten of the first digit, which is estimated from the power of two, and corrected below.
+    let log2 = 127 - mantissa.leading_zeros() as i32 + power;
+    let mut first = (f64::from(log2) * std::f64::consts::LOG10_2).floor() as i32;
+    let mut precision = start;
+    // A number of units n times ten to the power `last` is n times `scale` divided by `divisor`.
+    let mut last = first - precision as i32 + 1;
+    let (mut scale, mut divisor) = (Big::new(1), Big::new(1));
+    match power - 2 {
+        two @ 0.. => scale.shift_left(two.unsigned_abs()),
+        two => divisor.shift_left(two.unsigned_abs()),
+    }
+    match last {
+        0.. => divisor.multiply_pow10(last.unsigned_abs()),
+        _ => scale.multiply_pow10(last.unsigned_abs()),
+    }
+    loop {
+        let (quotient, remainder) = Big::new(4 * mantissa).times(&scale).divide_big(&divisor);
+        if quotient >= 10u128.pow(precision as u32) {
+            first += 1;
+            step(&mut last, 1, &mut scale, &mut divisor);
+            continue;
+        }
+        if quotient < 10u128.pow(precision as u32 - 1) {
+            first -= 1;
+            step(&mut last, -1, &mut scale, &mut divisor);
+            continue;
+        }
+        let mut twice = remainder;
+        twice.shift_left(1);
+        let rounded = match twice.cmp(&divisor) {
+            Ordering::Greater => quotient + 1,
+            Ordering::Equal if quotient % 2 == 1 => quotient + 1,
+            _ => quotient,
+        };
+        let value = Big::new(rounded).times(&divisor);
+        let (above, below) = (
+            value.cmp(&Big::new(low).times(&scale)),
+            value.cmp(&Big::new(high).times(&scale)),
+        );
+        let reads_back = (above == Ordering::Greater || inclusive && above == Ordering::Equal)
+            && (below == Ordering::Less || inclusive && below == Ordering::Equal);
+        if reads_back && !pseudo_subnormal || precision == MAX_PRECISION {
+            // Rounding up can carry to one more digit.
+            let mut digits = rounded.to_string().into_bytes();
+            let exponent = first + (digits.len() > precision) as i32;
+            trim_zeros(&mut digits);
+            return Decimal::Finite {
+                digits,
+                exponent,
+                precision,
+            };
+        }
+        precision += 1;
+        step(&mut last, -1, &mut scale, &mut divisor);
+    }
+}
+
+/// Move the power of ten `last` of the last digit of [`extended`] by one.
+fn step(last: &mut i32, by: i32, scale: &mut Big, divisor: &mut Big) {
+    // The power of ten is in the divisor when the last digit is at or after the units, and in the scale
+    // otherwise.
+    match (by > 0, *last) {
+        (true, 0..) => divisor.multiply(10),
+        (true, _) => scale.divide(10),
+        (false, 1..) => divisor.divide(10),
+        (false, _) => scale.multiply(10),
+    }
+    *last += by;
+}
+
+/// A natural number, in limbs of 32 bits from the least significant one, without leading zeros.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Big(Vec<u32>);
+
+impl Big {
+    fn new(mut value: u128) -> Self {
+        let mut limbs = Vec::new();
+        while value > 0 {
+            limbs.push(value as u32);
+            value >>= 32;
+        }
+        Big(limbs)
+    }
+
+    fn bits(&self) -> u32 {
+        match self.0.last() {
+            Some(limb) => self.0.len() as u32 * 32 - limb.leading_zeros(),
+            None => 0,
+        }
+    }
+
+    fn multiply(&mut self, factor: u32) {
+        let mut carry = 0;
+        for limb in &mut self.0 {
+            let product = u64::from(*limb) * u64::from(factor) + carry;
+            *limb = product as u32;
+            carry = product >> 32;
+        }
+        if carry > 0 {
+            self.0.push(carry as u32);
+        }
+    }
+
+    fn multiply_pow10(&mut self, mut exponent: u32) {
+        while exponent > 0 {
+            let power = exponent.min(9);
+            self.multiply(10u32.pow(power));
+            exponent -= power;
+        }
+    }
+
+    /// Divide the number by `divisor`, dropping the remainder.
+    fn divide(&mut self, divisor: u32) {
+        let mut remainder = 0;
+        for limb in self.0.iter_mut().rev() {
+            let dividend = remainder << 32 | u64::from(*limb);
+            *limb = (dividend / u64::from(divisor)) as u32;
+            remainder = dividend % u64::from(divisor);
+        }
+        self.trim();
+    }
+
+    fn times(&self, other: &Big) -> Big {
+        let mut product = vec![0; self.0.len() + other.0.len()];
+        for (i, &left) in self.0.iter().enumerate() {
+            let mut carry = 0;
+            for (j, &right) in other.0.iter().enumerate() {
+                let sum = u64::from(left) * u64::from(right) + u64::from(product[i + j]) + carry;
+                product[i + j] = sum as u32;
+                carry = sum >> 32;
+            }
+            product[i + other.0.len()] = carry as u32;
+        }
+        let mut product = Big(product);
+        product.trim();
+        product
+    }
+
+    /// Divide the number by `divisor`, getting the quotient, which must fit in a u128, and the remainder.
+    fn divide_big(mut self, divisor: &Big) -> (u128, Big) {
+        let mut quotient = 0;
+        let shift = self.bits().saturating_sub(divisor.bits());
+        let mut shifted = divisor.clone();
+        shifted.shift_left(shift);
+        for _ in 0..=shift {
+            quotient <<= 1;
+            if self >= shifted {
+                self.subtract(&shifted);
+                quotient |= 1;
+            }
+            shifted.shift_right_one();
+        }
+        (quotient, self)
+    }
+
+    /// Subtract `other`, which is not greater than the number.
+    fn subtract(&mut self, other: &Big) {
+        let mut borrow = false;
+        for (index, limb) in self.0.iter_mut().enumerate() {
+            let right = other.0.get(index).copied().unwrap_or(0);
+            let (difference, overflow) = limb.overflowing_sub(right);
+            let (difference, underflow) = difference.overflowing_sub(u32::from(borrow));
+            *limb = difference;
+            borrow = overflow || underflow;
+        }
+        self.trim();
+    }
+
+    fn shift_left(&mut self, bits: u32) {
+        if self.0.is_empty() {
+            return;
+        }
+        let (limbs, bits) = ((bits / 32) as usize, bits % 32);
+        if bits > 0 {
+            let mut carry = 0;
+            for limb in &mut self.0 {
+                let shifted = *limb << bits | carry;
+                carry = *limb >> (32 - bits);
+                *limb = shifted;
+            }
+            if carry > 0 {
+                self.0.push(carry);
+            }
+        }
+        self.0.splice(0..0, std::iter::repeat_n(0, limbs));
+    }
+
+    fn shift_right_one(&mut self) {
+        let mut carry = 0;
+        for limb in self.0.iter_mut().rev() {
+            let shifted = *limb >> 1 | carry << 31;
+            carry = *limb & 1;
+            *limb = shifted;
+        }
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.0.last() == Some(&0) {
+            self.0.pop();
+        }
+    }
+}
+
+impl Ord for Big {
+    fn cmp(&self, other: &Big) -> Ordering {
+        self.0
+            .len()
+            .cmp(&other.0.len())
+            .then_with(|| self.0.iter().rev().cmp(other.0.iter().rev()))
+    }
+}
+
+impl PartialOrd for Big {
+    fn partial_cmp(&self, other: &Big) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Remove the trailing zeros of the significant `digits`, except the one of 0.
+fn trim_zeros(digits: &mut Vec<u8>) {
+    while digits.len() > 1 && digits.last() == Some(&b'0') {
+        digits.pop();
+    }
+}
+
+/// The base of the offsets at the start of the lines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Radix {
+    Decimal,
+    Octal,
+    Hex,
+}
+
+/// The behaviour of od.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OdOptions {
+    /// The formats of the lines printed for every 16 bytes, in order.
+    pub types: Vec<OutputType>,
+    /// Print every line, instead of a `*` for the lines which are the same as the previous one.
+    pub output_duplicates: bool,
+    /// The base of the offsets, or `None` to print no offsets.
+    pub address_radix: Option<Radix>,
+    /// The number of bytes to skip at the start of the stream, which must not be longer than it.
+    pub skip_bytes: u64,
+    /// The maximum number of bytes to dump after the skipped ones, if any.
+    pub read_bytes: Option<u64>,
+}
+
+impl Default for OdOptions {
+    /// Print octal shorts, with octal offsets.
+    fn default() -> Self {
+        OdOptions {
+            types: vec![OutputType::new(Kind::Octal, 2)],
+            output_duplicates: false,
+            address_radix: Some(Radix::Octal),
+            skip_bytes: 0,
+            read_bytes: None,
+        }
+    }
+}
+
+/// Dump the inputs to `stdout` as a single stream, followed by the offset of its end.
+///
+/// The fields of the different types are aligned under each other, so that they show the same bytes. An
+/// input which cannot be opened or read is reported, and the remaining ones are still dumped. Skipping past
+/// the end of the stream is an error, before anything is written. A failure to write is returned, as there is
+/// no point in going on. A closed pipe stops the dump without an error.
+pub fn od<W: Write>(
+    inputs: &mut InputIter,
+    options: &OdOptions,
+    stdout: &mut dyn Write,
+    reporter: &mut Reporter<W>,
+) -> UResult<()> {
+    let mut writer = BufWriter::with_capacity(BUFFER_SIZE, stdout);
+    let mut dumper = Dumper::new(options);
+    let mut skip = options.skip_bytes;
+    let mut left = options.read_bytes.unwrap_or(u64::MAX);
+    let mut block = [0; LINE_BYTES];
+    let mut length = 0;
+    // The inputs are skipped even when nothing is read from them, so that skipping past the end is an error.
+    while left > 0 || skip > 0 {
+        let Some(input) = inputs.next_source() else {
+            break;
+        };
+        let input = match input {
+            Ok(input) => input,
+            Err(error) => {
+                reporter.io_error(error.display_name(), &error.error);
+                continue;
+            }
+        };
+        let name = input.display_name().into_owned();
+        let mut source = input.reader;
+        if skip > 0 {
+            match skip_input(&mut source, skip) {
+                Ok(skipped) => skip -= skipped,
+                Err(error) => {
+                    reporter.io_error(&name, &error);
+                    continue;
+                }
+            }
+            if skip > 0 {
+                continue;
+            }
+        }
+        let mut reader: Box<dyn BufRead> = match source {
+            Source::File(file) => Box::new(BufReader::with_capacity(BUFFER_SIZE, file)),
+            Source::Stdin(stdin) => Box::new(stdin),
+        };
+        while left > 0 {
+            let end = length + left.min((LINE_BYTES - length) as u64) as usize;
+            match reader.read(&mut block[length..end]) {
+                Ok(0) => break,
+                Ok(n) => {
+                    length += n;
+                    left -= n as u64;
+                }
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => {
+                    reporter.io_error(&name, &error);
+                    break;
+                }
+            }
+            if length == LINE_BYTES {
+                dumper
+                    .write_block(&block, length, &mut writer)
+                    .or_else(UError::write)?;
+                length = 0;
+            }
+        }
+    }
+    if skip > 0 {
+        return Err(UError::new(
+            EXIT_FAILURE,
+            "cannot skip past end of combined input",
+        ));
+    }
+    let mut result = match length {
+        0 => Ok(()),
+        _ => {
+            block[length..].fill(0);
+            dumper.write_block(&block, length, &mut writer)
+        }
+    };
+    if options.address_radix.is_some() {
+        dumper.line.clear();
+        dumper.write_offset(dumper.offset);
+        dumper.line.push(b'\n');
+        result = result.and_then(|()| writer.write_all(&dumper.line));
+    }
+    result.and_then(|()| writer.flush()).or_else(UError::write)
+}
+
+/// Skip up to `skip` bytes at the start of the input, getting how many were skipped. A regular file is seeked
+/// into instead of read.
+fn skip_input(source: &mut Source, skip: u64) -> io::Result<u64> {
+    match source {
+        Source::File(file) => {
+            let metadata = file.metadata()?;
+            // Some special files, like the ones of /proc, have no size, and only reading finds their end.
+            if metadata.is_file() && metadata.len() > 0 {
+                return file.seek(SeekFrom::Start(skip.min(metadata.len())));
+            }
+            io::copy(&mut Read::take(file, skip), &mut io::sink())
+        }
+        Source::Stdin(stdin) => io::copy(&mut Read::take(stdin, skip), &mut io::sink()),
+    }
+}
+
+/// The state of the dump between the lines.
+struct Dumper<'a> {
+    options: &'a OdOptions,
+    /// The offset of the next line.
+    offset: u64,
+    /// The previous line, if it was a whole one.
+    previous: Option<[u8; LINE_BYTES]>,
+    /// Whether the previous line was replaced by `*`.
+    duplicate: bool,
+    /// The number of characters of the fields of a line of any type.
+    line_width: usize,
+    line: Vec<u8>,
+}
+
+impl<'a> Dumper<'a> {
+    fn new(options: &'a OdOptions) -> Self {
+        let line_width = options
+            .types
+            .iter()
+            .map(|output_type| LINE_BYTES / output_type.size * (output_type.width() + 1))
+            .max()
+            .unwrap_or(0);
+        Dumper {
+            options,
+            offset: options.skip_bytes,
+            previous: None,
+            duplicate: false,
+            line_width,
+            line: Vec::new(),
+        }
+    }
+
+    /// Write the lines of the first `length` bytes of `block`, which is padded with zeros.
+    fn write_block(
+        &mut self,
+        block: &[u8; LINE_BYTES],
+        length: usize,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        let offset = self.offset;
+        self.offset += length as u64;
+        if !self.options.output_duplicates && length == LINE_BYTES {
+            if self.previous == Some(*block) {
+                if !self.duplicate {
+                    writer.write_all(b"*\n")?;
+                }
+                self.duplicate = true;
+                return Ok(());
+            }
+            self.previous = Some(*block);
+        }
+        self.duplicate = false;
+        for (index, output_type) in self.options.types.iter().enumerate() {
+            self.line.clear();
+            match index {
+                0 => self.write_offset(offset),
+                _ => {
+                    let width = match self.options.address_radix {
+                        Some(Radix::Decimal | Radix::Octal) => 7,
+                        Some(Radix::Hex) => 6,
+                        None => 0,
+                    };
+                    self.line.resize(width, b' ');
+                }
+            }
+            self.write_fields(output_type, block, length);
+            self.line.push(b'\n');
+            writer.write_all(&self.line)?;
+        }
+        Ok(())
+    }
+
+    fn write_offset(&mut self, offset: u64) {
+        let offset = match self.options.address_radix {
+            Some(Radix::Decimal) => format!("{offset:07}"),
+            Some(Radix::Octal) => format!("{offset:07o}"),
+            Some(Radix::Hex) => format!("{offset:06x}"),
+            None => return,
+        };
+        self.line.extend(offset.bytes());
+    }
+
+    /// Write the fields which hold any of the first `length` bytes of `block`. The spaces which make the
+    /// fields as wide as the ones of the widest type are spread between them, like in GNU.
+    fn write_fields(&mut self, output_type: &OutputType, block: &[u8], length: usize) {
+        let fields = LINE_BYTES / output_type.size;
+        let width = output_type.width();
+        let padding = self.line_width - fields * (width + 1);
+        let mut field = Vec::new();
+        let mut remaining = padding;
+        let mut written = 0;
+        for (index, bytes) in block.chunks_exact(output_type.size).enumerate() {
+            if index * output_type.size >= length {
+                break;
+            }
+            let next = padding * (fields - index - 1) / fields;
+            field.clear();
+            output_type.write_field(bytes, &mut field);
+            let field_width = remaining - next + width;
+            self.line.push(b' ');
+            self.line.resize(
+                self.line.len() + field_width.saturating_sub(field.len()),
+                b' ',
+            );
+            self.line.extend_from_slice(&field);
+            remaining = next;
+            written += 1;
+        }
+        if output_type.printable {
+            // The missing fields are blank, so that the characters are aligned with the ones of whole lines.
+            let blank = (fields - written) * (width + 1) + remaining;
+            self.line.resize(self.line.len() + blank, b' ');
+            self.line.extend_from_slice(b"  >");
+            self.line
+                .extend(block[..length].iter().map(|&byte| match byte {
+                    b' '..=b'~' => byte,
+                    _ => b'.',
+                }));
+            self.line.push(b'<');
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::run_util_stdin;
+    use std::io;
+
+    fn od_text(args: &[&str], stdin: &[u8]) -> String {
+        let command_line: Vec<&str> = ["od"].iter().chain(args).copied().collect();
+        let (code, stdout, stderr) = run_util_stdin(run, &command_line, stdin);
+        assert_eq!((code, &stderr[..]), (0, ""), "{args:?}");
+        stdout
+    }
+
+    fn all_bytes() -> Vec<u8> {
+        (0..=255).collect()
+    }
+
+    #[test]
+    fn test_characters() {
+        // The output of GNU od -c.
+        let expected = concat!(
+            "0000000  \\0 001 002 003 004 005 006  \\a  \\b  \\t  \\n  \\v  \\f  \\r 016 017\n",
+            "0000020 020 021 022 023 024 025 026 027 030 031 032 033 034 035 036 037\n",
+            "0000040       !   \"   #   $   %   &   '   (   )   *   +   ,   -   .   /\n",
+            "0000060   0   1   2   3   4   5   6   7   8   9   :   ;   <   =   >   ?\n",
+            "0000100   @   A   B   C   D   E   F   G   H   I   J   K   L   M   N   O\n",
+            "0000120   P   Q   R   S   T   U   V   W   X   Y   Z   [   \\   ]   ^   _\n",
+            "0000140   `   a   b   c   d   e   f   g   h   i   j   k   l   m   n   o\n",
+            "0000160   p   q   r   s   t   u   v   w   x   y   z   {   |   }   ~ 177\n",
+            "0000200 200 201 202 203 204 205 206 207 210 211 212 213 214 215 216 217\n",
+            "0000220 220 221 222 223 224 225 226 227 230 231 232 233 234 235 236 237\n",
+            "0000240 240 241 242 243 244 245 246 247 250 251 252 253 254 255 256 257\n",
+            "0000260 260 261 262 263 264 265 266 267 270 271 272 273 274 275 276 277\n",
+            "0000300 300 301 302 303 304 305 306 307 310 311 312 313 314 315 316 317\n",
+            "0000320 320 321 322 323 324 325 326 327 330 331 332 333 334 335 336 337\n",
+            "0000340 340 341 342 343 344 345 346 347 350 351 352 353 354 355 356 357\n",
+            "0000360 360 361 362 363 364 365 366 367 370 371 372 373 374 375 376 377\n",
+            "0000400\n",
+        );
+        assert_eq!(od_text(&["-c"], &all_bytes()), expected);
+    }
+
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn test_hex_shorts() {
+        // The output of GNU od -x.
+        let expected = concat!(
+            "0000000 0100 0302 0504 0706 0908 0b0a 0d0c 0f0e\n",
+            "0000020 1110 1312 1514 1716 1918 1b1a 1d1c 1f1e\n",
+            "0000040 2120 2322 2524 2726 2928 2b2a 2d2c 2f2e\n",
+            "0000060 3130 3332 3534 3736 3938 3b3a 3d3c 3f3e\n",
+            "0000100 4140 4342 4544 4746 4948 4b4a 4d4c 4f4e\n",
+            "0000120 5150 5352 5554 5756 5958 5b5a 5d5c 5f5e\n",
+            "0000140 6160 6362 6564 6766 6968 6b6a 6d6c 6f6e\n",
+            "0000160 7170 7372 7574 7776 7978 7b7a 7d7c 7f7e\n",
+            "0000200 8180 8382 8584 8786 8988 8b8a 8d8c 8f8e\n",
+            "0000220 9190 9392 9594 9796 9998 9b9a 9d9c 9f9e\n",
+            "0000240 a1a0 a3a2 a5a4 a7a6 a9a8 abaa adac afae\n",
+            "0000260 b1b0 b3b2 b5b4 b7b6 b9b8 bbba bdbc bfbe\n",
+            "0000300 c1c0 c3c2 c5c4 c7c6 c9c8 cbca cdcc cfce\n",
+            "0000320 d1d0 d3d2 d5d4 d7d6 d9d8 dbda dddc dfde\n",
+            "0000340 e1e0 e3e2 e5e4 e7e6 e9e8 ebea edec efee\n",
+            "0000360 f1f0 f3f2 f5f4 f7f6 f9f8 fbfa fdfc fffe\n",
+            "0000400\n",
+        );
+        assert_eq!(od_text(&["-x"], &all_bytes()), expected);
+    }
+
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn test_default() {
+        assert_eq!(
+            od_text(&[], b"hello world\n"),
+            "0000000 062550 066154 020157 067567 066162 005144\n0000014\n"
+        );
+        assert_eq!(od_text(&["-o"], b"abc"), "0000000 061141 000143\n0000003\n");
+        assert_eq!(
+            od_text(&["-b"], b"ab\xff"),
+            "0000000 141 142 377\n0000003\n"
+        );
+        assert_eq!(od_text(&[], b""), "0000000\n");
+    }
+
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn test_multiple_types() {
+        // The output of GNU od -x -b -c, where the lines are in the order of the options.
+        let expected = concat!(
+            "0000000    6568    6c6c    206f    6f77    6c72    0a64    0100    61ff\n",
+            "        150 145 154 154 157 040 167 157 162 154 144 012 000 001 377 141\n",
+            "          h   e   l   l   o       w   o   r   l   d  \\n  \\0 001 377   a\n",
+            "0000020    6362\n",
+            "        142 143\n",
+            "          b   c\n",
+            "0000022\n",
+        );
+        assert_eq!(
+            od_text(&["-x", "-b", "-c"], b"hello world\n\0\x01\xffabc"),
+            expected
+        );
+        assert_eq!(
+            od_text(&["-c", "-x"], b"abc"),
+            "0000000   a   b   c\n           6261    0063\n0000003\n"
+        );
+        assert_eq!(
+            od_text(&["-x", "-c", "-x"], b"a"),
+            "0000000    0061\n          a\n           0061\n0000001\n"
+        );
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let data = [&[b'a'; 64][..], &[b'b'; 16], &[b'a'; 16], &[b'a'; 3]].concat();
+        assert_eq!(
+            od_text(&["-c"], &data),
+            concat!(
+                "0000000   a   a   a   a   a   a   a   a   a   a   a   a   a   a   a   a\n",
+                "*\n",
+                "0000100   b   b   b   b   b   b   b   b   b   b   b   b   b   b   b   b\n",
+                "0000120   a   a   a   a   a   a   a   a   a   a   a   a   a   a   a   a\n",
+                "0000140   a   a   a\n",
+                "0000143\n",
+            )
+        );
+        // The last line is printed after a duplicate, even when it is one too.
+        assert_eq!(
+            od_text(&["-b"], &[0; 48]),
+            "0000000 000 000 000 000 000 000 000 000 000 000 000 000 000 000 000 000\n*\n0000060\n"
+        );
+        let lines = od_text(&["-v", "-c"], &data);
+        assert_eq!(lines.lines().count(), 8);
+        assert!(!lines.contains('*'));
+    }
+
+    #[test]
+    fn test_files() {
+        let directory = tempfile::tempdir().unwrap();
+        let (a, b) = (directory.path().join("a"), directory.path().join("b"));
+        std::fs::write(&a, "0123456789").unwrap();
+        std::fs::write(&b, "abcdefghij").unwrap();
+        let (a, b) = (a.to_str().unwrap(), b.to_str().unwrap());
+        // The files form a single stream.
+        assert_eq!(
+            od_text(&["-c", a, "-", b], b"\n"),
+            concat!(
+                "0000000   0   1   2   3   4   5   6   7   8   9  \\n   a   b   c   d   e\n",
+                "0000020   f   g   h   i   j\n",
+                "0000025\n",
+            )
+        );
+        assert_eq!(
+            run_util_stdin(run, &["od", "-c", "missing", a], b""),
+            (
+                1,
+                "0000000   0   1   2   3   4   5   6   7   8   9\n0000012\n".to_owned(),
+                "od: missing: No such file or directory\n".to_owned()
+            )
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_error() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().to_str().unwrap();
+        assert_eq!(
+            run_util_stdin(run, &["od", "-b", path, "-"], b"a"),
+            (
+                1,
+                "0000000 141\n0000001\n".to_owned(),
+                format!("od: {path}: Is a directory\n")
+            )
+        );
+    }
+
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn test_decimal() {
+        let data = b"hello world\n\0\x01\xffabc\x80\x7f ";
+        assert_eq!(
+            od_text(&["-t", "d4"], data),
+            concat!(
+                "0000000  1819043176  1870078063   174353522  1644101888\n",
+                "0000020  2139120482          32\n",
+                "0000025\n",
+            )
+        );
+        // The fields of the types are aligned, so the ones of d4 are spread over the ones of u1.
+        assert_eq!(
+            od_text(&["-t", "d4", "-t", "u1", "-N", "8"], data),
+            concat!(
+                "0000000      1819043176      1870078063\n",
+                "        104 101 108 108 111  32 119 111\n",
+                "0000010\n",
+            )
+        );
+        assert_eq!(
+            od_text(&["-t", "d1", "-t", "dS", "-t", "uSd8"], b"\xff\xff"),
+            concat!(
+                "0000000   -1   -1\n",
+                "               -1\n",
+                "            65535\n",
+                "                                          65535\n",
+                "0000002\n",
+            )
+        );
+        assert_eq!(
+            od_text(&["-t", "d8", "-t", "u8"], &[0x80; 8]),
+            "0000000 -9187201950435737472\n         9259542123273814144\n0000010\n"
+        );
+    }
+
+    #[test]
+    fn test_printable() {
+        let data = b"hello world\n\0\x01\xffabc\x80\x7f ";
+        assert_eq!(
+            od_text(&["-t", "x1z"], data),
+            concat!(
+                "0000000 68 65 6c 6c 6f 20 77 6f 72 6c 64 0a 00 01 ff 61  >hello world....a<\n",
+                "0000020 62 63 80 7f 20                                   >bc.. <\n",
+                "0000025\n",
+            )
+        );
+        // The characters follow the line of their type only, after the widest fields.
+        assert_eq!(
+            od_text(&["-t", "x1z", "-t", "c", "-N", "3"], data),
+            concat!(
+                "0000000  68  65  6c                                                      >hel<\n",
+                "          h   e   l\n",
+                "0000003\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_named_characters() {
+        // The high bit is ignored.
+        assert_eq!(
+            od_text(&["-t", "a"], b"\0\x01 ~\x7f\x80\xc1\n"),
+            "0000000 nul soh  sp   ~ del nul   A  nl\n0000010\n"
+        );
+    }
+
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn test_floats() {
+        let floats: Vec<u8> = [
+            1.0,
+            -2.5,
+            0.1,
+            1e-40,
+            f32::INFINITY,
+            f32::NAN,
+            1_515_019.0 + 0.25,
+            0.0,
+        ]
+        .iter()
+        .flat_map(|float: &f32| float.to_le_bytes())
+        .collect();
+        // The output of GNU, where 1515019.25 needs 8 digits and is rounded to the even one.
+        assert_eq!(
+            od_text(&["-t", "fF"], &floats),
+            concat!(
+                "0000000               1            -2.5             0.1           1e-40\n",
+                "0000020             inf             nan       1515019.2               0\n",
+                "0000040\n",
+            )
+        );
+        let doubles: Vec<u8> = [
+            1.0 / 3.0,
+            -1e300,
+            1.51430396499485e-309,
+            123456789.0,
+            -0.0,
+            1e-5,
+        ]
+        .iter()
+        .flat_map(|float: &f64| float.to_le_bytes())
+        .collect();
+        assert_eq!(
+            od_text(&["-t", "f"], &doubles),
+            concat!(
+                "0000000       0.3333333333333333                  -1e+300\n",
+                "0000020    1.51430396499485e-309                123456789\n",
+                "0000040                       -0                    1e-05\n",
+                "0000060\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_extended() {
+        let decimal = |mantissa, exponent| {
+            let mut bytes = [0; 16];
+            bytes[..8].copy_from_slice(&u64::to_le_bytes(mantissa));
+            bytes[8..10].copy_from_slice(&u16::to_le_bytes(exponent));
+            let mut text = Vec::new();
+            write_float(&bytes, &mut text);
+            String::from_utf8(text).unwrap()
+        };
+        // The outputs of GNU od -t fL.
+        assert_eq!(decimal(1 << 63, 0x3fff), "1");
+        assert_eq!(
+            decimal(0xaaaa_aaaa_aaaa_aaab, 0x3ffd),
+            "0.33333333333333333334"
+        );
+        assert_eq!(
+            decimal(0xc90f_daa2_2168_c235, 0x4000),
+            "3.1415926535897932385"
+        );
+        assert_eq!(decimal(0xcccc_cccc_cccc_cccd, 0x3ffb), "0.1");
+        assert_eq!(decimal(1 << 63, 0xbfff + 3), "-8");
+        assert_eq!(decimal(0, 0x8000), "-0");
+        assert_eq!(decimal(1 << 63, 1), "3.3621031431120935063e-4932");
+        assert_eq!(decimal(1, 0), "4e-4951");
+        assert_eq!(decimal(u64::MAX, 0x7ffe), "1.189731495357231765e+4932");
+        assert_eq!(decimal(1 << 63, 0x7fff), "inf");
+        assert_eq!(decimal(1 << 63, 0xffff), "-inf");
+        assert_eq!(decimal(3 << 62, 0x7fff), "nan");
+        // Invalid encodings: without the integer bit, and the subnormals with it.
+        assert_eq!(decimal(1, 0x3fff), "nan");
+        assert_eq!(decimal(0, 0x7fff), "nan");
+        assert_eq!(decimal(u64::MAX, 0), "3.3621031431120935059e-4932");
+        assert_eq!(decimal(1 << 63, 0), "3.3621031431120935063e-4932");
+    }
+
+    #[test]
+    fn test_type_errors() {
+        for (spec, message) in [
+            ("q", "invalid character 'q' in type string 'q'"),
+            ("x1zz", "invalid character 'z' in type string 'x1zz'"),
+            ("zx", "invalid character 'z' in type string 'zx'"),
+            ("fS", "invalid character 'S' in type string 'fS'"),
+            ("a1", "invalid character '1' in type string 'a1'"),
+            ("xC2", "invalid character '2' in type string 'xC2'"),
+            ("d 4", "invalid character ' ' in type string 'd 4'"),
+            (
+                "d3",
+                "invalid type string 'd3';\nthis system doesn't provide a 3-byte integral type",
+            ),
+            (
+                "x16",
+                "invalid type string 'x16';\nthis system doesn't provide a 16-byte integral type",
+            ),
+            (
+                "f2",
+                "invalid type string 'f2';\nthis system doesn't provide a 2-byte floating point type",
+            ),
+        ] {
+            assert_eq!(
+                run_util_stdin(run, &["od", "-t", spec], b""),
+                (1, String::new(), format!("od: {message}\n")),
+                "{spec:?}"
+            );
+        }
+        assert_eq!(
+            OutputType::parse("x1zcfDdL").unwrap(),
+            [
+                OutputType {
+                    printable: true,
+                    ..OutputType::new(Kind::Hex, 1)
+                },
+                OutputType::new(Kind::Character, 1),
+                OutputType::new(Kind::Float, 8),
+                OutputType::new(Kind::Signed, 8),
+            ]
+        );
+        assert_eq!(
+            OutputType::parse("o").unwrap(),
+            [OutputType::new(Kind::Octal, 4)]
+        );
+        // An empty type string is no type, which is the default one.
+        assert_eq!(od_text(&["-t", ""], b"a"), od_text(&[], b"a"));
+    }
+
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn test_address_radix() {
+        let data: Vec<u8> = (0..64).collect();
+        assert_eq!(
+            od_text(&["-A", "x", "-j", "16", "-N", "32", "-t", "x1"], &data),
+            concat!(
+                "000010 10 11 12 13 14 15 16 17 18 19 1a 1b 1c 1d 1e 1f\n",
+                "000020 20 21 22 23 24 25 26 27 28 29 2a 2b 2c 2d 2e 2f\n",
+                "000030\n",
+            )
+        );
+        assert_eq!(
+            od_text(&["-A", "d", "-j", "60", "-t", "u1"], &data),
+            "0000060  60  61  62  63\n0000064\n"
+        );
+        // Without offsets, the lines of other types are not indented, and the end is not printed.
+        assert_eq!(
+            od_text(&["-An", "-t", "x2", "-c", "-N3"], b"012"),
+            "    3130    0032\n   0   1   2\n"
+        );
+        assert_eq!(
+            od_text(&["-A", "x", "-x", "-c"], b"ab"),
+            "000000    6261\n         a   b\n000002\n"
+        );
+        assert_eq!(
+            run_util_stdin(run, &["od", "-A", "q"], b""),
+            (
+                1,
+                String::new(),
+                "od: invalid output address radix 'q'; it must be one character from [doxn]\n"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn test_skip_and_limit() {
+        let directory = tempfile::tempdir().unwrap();
+        let (a, b) = (directory.path().join("a"), directory.path().join("b"));
+        std::fs::write(&a, "abcdefgh").unwrap();
+        std::fs::write(&b, "ijklmnop").unwrap();
+        let (a, b) = (a.to_str().unwrap(), b.to_str().unwrap());
+        // The stream is skipped across the files, whether they are seeked or read like standard input.
+        assert_eq!(
+            od_text(&["-j", "5", "-N", "7", "-c", a, b], b""),
+            "0000005   f   g   h   i   j   k   l\n0000014\n"
+        );
+        assert_eq!(
+            od_text(&["-j", "8", "-c", a, b], b""),
+            "0000010   i   j   k   l   m   n   o   p\n0000020\n"
+        );
+        assert_eq!(
+            od_text(&["-j", "0xa", "-c", "-", b], b"0123456789"),
+            "0000012   i   j   k   l   m   n   o   p\n0000022\n"
+        );
+        assert_eq!(od_text(&["-j", "16", "-c", a, b], b""), "0000020\n");
+        assert_eq!(od_text(&["-N", "0", "-c", a], b""), "0000000\n");
+        assert_eq!(od_text(&["-j", "8", "-N", "0", a], b""), "0000010\n");
+        assert_eq!(
+            od_text(&["--skip-bytes=010", "--read-bytes=1", "-c"], b"0123456789"),
+            "0000010   8\n0000011\n"
+        );
+        let large = vec![b'x'; 2000];
+        assert_eq!(
+            od_text(&["-j", "1K", "-N", "1b", "-c"], &large),
+            "0002000   x   x   x   x   x   x   x   x   x   x   x   x   x   x   x   x\n*\n0003000\n"
+        );
+
+        // Skipping past the end is an error, which stops od before it prints anything.
+        for args in [
+            &["-j", "17", a, b][..],
+            &["-j", "1", "missing"],
+            &["-j", "11"],
+        ] {
+            let command_line: Vec<&str> = ["od"].iter().chain(args).copied().collect();
+            let (code, stdout, stderr) = run_util_stdin(run, &command_line, b"0123456789");
+            assert_eq!((code, &stdout[..]), (1, ""), "{args:?}");
+            assert!(
+                stderr.ends_with("od: cannot skip past end of combined input\n"),
+                "{stderr:?}"
+            );
+        }
+
+        for (args, message) in [
+            (["-j", "x"], "invalid -j argument 'x'"),
+            (["-N", "-1"], "invalid -N argument '-1'"),
+            (["-j", "1e"], "invalid suffix in -j argument '1e'"),
+            (
+                ["-N", "99999999999999999999"],
+                "-N argument '99999999999999999999' too large",
+            ),
+        ] {
+            let command_line: Vec<&str> = ["od"].iter().chain(&args).copied().collect();
+            assert_eq!(
+                run_util_stdin(run, &command_line, b""),
+                (1, String::new(), format!("od: {message}\n")),
+                "{args:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_run_write_error() {
+        let args: Vec<OsString> = vec!["od".into()];
+        let mut stdout = io::Cursor::new([0; 2]);
+        let mut stderr = Vec::new();
+        let code = run(&args, &mut &b"data"[..], &mut stdout, &mut stderr);
+        assert_eq!(code, 1);
+        assert_eq!(
+            stderr,
+            b"od: write error: failed to write the buffered data\n"
+        );
+    }
+}
@@ -0,0 +1,980 @@
+//! Format and print arguments, like the printf function of C.
+//!
+//! The format is rendered with [`render`], so that the output can be written anywhere:
+//!
+//! ```
+//! use rust_coreutils::common::error::Reporter;
+//! use rust_coreutils::printf::render;
+//! use std::ffi::OsString;
+//!
+//! let args = [OsString::from("a"), OsString::from("0x10"), OsString::from("b")];
+//! let mut reporter = Reporter::new("printf", Vec::new());
+//! let mut output = Vec::new();
+//! render(b"%s=%d\\n", &args, &mut output, &mut reporter).unwrap();
+//! assert_eq!(output, b"a=16\nb=0\n");
+//! ```
+
+use crate::common::error::{Reporter, UError, EXIT_FAILURE, EXIT_SUCCESS};
+use crate::common::escapes::{expand_escape, expand_escapes_into, EscapeFlavor};
+use crate::common::format::{parse_float, Count, Field, Spec};
+use crate::common::input::BUFFER_SIZE;
+use crate::common::os::os_str_bytes;
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use clap::Parser;
+use std::borrow::Cow;
+use std::ffi::OsString;
+use std::io::{self, BufWriter, Read, Write};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "printf",
+    author,
+    version,
+    about = "Print the ARGUMENTs according to FORMAT, reusing FORMAT until all of them are printed.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// The bytes to print, with backslash escapes like \n and \NNN in octal, and conversions:
+    ///   • %% a percent sign
+    ///   • %b the argument with its backslash escapes expanded, where the octal ones are \0NNN
+    ///   • %c the first byte of the argument
+    ///   • %d, %i the argument as a signed decimal integer
+    ///   • %o, %u, %x, %X the argument as an unsigned octal, decimal, or hexadecimal integer
+    ///   • %f, %F, %e, %E, %g, %G the argument as a floating point number, with 6 digits by default
+    ///   • %s the argument as it is
+    /// Between the % and the conversion there may be flags: - to justify to the left, + or a space for
+    /// the sign of positive numbers, # for the alternate form, and 0 to pad with zeros; then a field
+    /// width, and a precision after a period, either of which may be * to take it from the arguments.
+    /// The integers may be given in hexadecimal with 0x, in octal with 0, or as the value of the byte
+    /// after a quote, like 'A. The floating point numbers are doubles, so that they may differ from GNU
+    /// in the last digits of a large precision.
+    #[arg(value_name = "FORMAT", verbatim_doc_comment)]
+    format: Option<OsString>,
+
+    /// The arguments of the conversions. The missing ones are empty, which is 0 for the integers.
+    #[arg(value_name = "ARGUMENT")]
+    arguments: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "printf";
+}
+
+/// The description of printf for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run printf with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run printf with the given command line, including the program name, on the given streams, and return
+/// the exit code. Standard input is not read.
+pub fn run(
+    args: &[OsString],
+    _stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(split_options(args), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let mut reporter = Reporter::new("printf", stderr);
+    let Some(format) = &args.format else {
+        return reporter.finish(Err(UError::usage(EXIT_FAILURE, "missing operand")));
+    };
+    let mut output = BufWriter::with_capacity(BUFFER_SIZE, stdout);
+    let rendered = render(
+        &os_str_bytes(format),
+        &args.arguments,
+        &mut output,
+        &mut reporter,
+    );
+    let flushed = output.flush().map_err(RenderError::Write);
+    match rendered.and_then(|flow| flushed.map(|()| flow)) {
+        // Like GNU, `\c` exits successfully, even after an invalid argument.
+        Ok(Flow::Stop) => EXIT_SUCCESS,
+        Ok(Flow::Continue) => reporter.finish(Ok(())),
+        Err(RenderError::Format(message)) => {
+            reporter.finish(Err(UError::new(EXIT_FAILURE, message)))
+        }
+        Err(RenderError::Write(error)) => reporter.finish(UError::write(error)),
+    }
+}
+
+/// Separate the operands from the options with `--`, so that a format like `-x` is printed.
+///
+/// Like in GNU, `--help` and `--version` are only recognized when they are the sole argument, and a
+/// leading `--` is skipped.
+fn split_options(args: &[OsString]) -> Vec<OsString> {
+    let mut args = args.to_vec();
+    match args.get(1) {
+        Some(argument) if args.len() == 2 && (argument == "--help" || argument == "--version") => {}
+        Some(argument) if argument == "--" => {}
+        _ => args.insert(1.min(args.len()), OsString::from("--")),
+    }
+    args
+}
+
+/// How printing the format ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Flow {
+    /// The whole format was printed.
+    Continue,
+    /// `\c` was found, which means that nothing else is printed.
+    Stop,
+}
+
+/// Why printing the format stopped before its end.
+#[derive(Debug)]
+pub enum RenderError {
+    /// An invalid conversion, with its GNU message.
+    Format(String),
+    /// An error writing the output.
+    Write(io::Error),
+}
+
+impl From<io::Error> for RenderError {
+    fn from(error: io::Error) -> Self {
+        RenderError::Write(error)
+    }
+}
+
+/// Print `format` with `args` into `output`, reusing the format until all the arguments are consumed.
+///
+/// The arguments which are not valid for a conversion are reported to `reporter`, and replaced with what
+/// could be parsed, like in GNU. An invalid conversion stops the output, and so does an error writing it.
+pub fn render<W: Write>(
+    format: &[u8],
+    args: &[OsString],
+    output: &mut dyn Write,
+    reporter: &mut Reporter<W>,
+) -> Result<Flow, RenderError> {
+    let mut arguments = Arguments { args, next: 0 };
+    loop {
+        let start = arguments.next;
+        if render_once(format, &mut arguments, output, reporter)? == Flow::Stop {
+            return Ok(Flow::Stop);
+        }
+        if arguments.next == start || arguments.next == args.len() {
+            break;
+        }
+    }
+    if let Some(excess) = args.get(arguments.next) {
+        reporter.warning(format_args!(
+            "warning: ignoring excess arguments, starting with '{}'",
+            excess.to_string_lossy()
+        ));
+    }
+    Ok(Flow::Continue)
+}
+
+/// The arguments of the conversions, which are consumed in order.
+struct Arguments<'a> {
+    args: &'a [OsString],
+    next: usize,
+}
+
+impl<'a> Arguments<'a> {
+    /// Get the next argument, which is empty once they are all consumed.
+    fn next(&mut self) -> Cow<'a, [u8]> {
+        match self.args.get(self.next) {
+            Some(argument) => {
+                self.next += 1;
+                os_str_bytes(argument)
+            }
+            None => Cow::Borrowed(b""),
+        }
+    }
+}
+
+/// Print the format once, consuming the arguments of its conversions.
+fn render_once<W: Write>(
+    format: &[u8],
+    arguments: &mut Arguments,
+    output: &mut dyn Write,
+    reporter: &mut Reporter<W>,
+) -> Result<Flow, RenderError> {
+    let mut index = 0;
+    let mut escaped = Vec::new();
+    while let Some(offset) = format[index..]
+        .iter()
+        .position(|&byte| byte == b'%' || byte == b'\\')
+    {
+        output.write_all(&format[index..index + offset])?;
+        index += offset;
+        if format[index] == b'\\' {
+            escaped.clear();
+            let expanded = expand_escape(&format[index..], EscapeFlavor::Printf, &mut escaped);
+            output.write_all(&escaped)?;
+            match expanded {
+                // Like GNU, an unknown escape is printed with the byte after the backslash.
+                Some(1) if index + 1 < format.len() => {
+                    output.write_all(&format[index + 1..index + 2])?;
+                    index += 2;
+                }
+                Some(length) => index += length,
+                None => return Ok(Flow::Stop),
+            }
+            continue;
+        }
+        if format.get(index + 1) == Some(&b'%') {
+            output.write_all(b"%")?;
+            index += 2;
+            continue;
+        }
+        let (spec, length) = Spec::parse(&format[index..]).map_err(RenderError::Format)?;
+        if convert(spec, arguments, output, reporter)? == Flow::Stop {
+            return Ok(Flow::Stop);
+        }
+        index += length;
+    }
+    output.write_all(&format[index..])?;
+    Ok(Flow::Continue)
+}
+
+/// Print the next arguments with the specification, first the width and the precision if they are
+/// `*`, then the value, with the GNU message of an invalid width or precision.
+fn convert<W: Write>(
+    mut spec: Spec,
+    arguments: &mut Arguments,
+    output: &mut dyn Write,
+    reporter: &mut Reporter<W>,
+) -> Result<Flow, RenderError> {
+    let width = match spec.width {
+        Some(Count::Argument) => {
+            let argument = arguments.next();
+            let width = i32::try_from(signed(&argument, reporter)).map_err(|_| {
+                RenderError::Format(format!(
+                    "invalid field width: '{}'",
+                    String::from_utf8_lossy(&argument)
+                ))
+            })?;
+            // A negative width justifies to the left.
+            spec.left |= width < 0;
+            width.unsigned_abs() as usize
+        }
+        Some(Count::Fixed(width)) => width,
+        None => 0,
+    };
+    let precision = match spec.precision {
+        Some(Count::Argument) => {
+            let argument = arguments.next();
+            let precision = i32::try_from(signed(&argument, reporter)).map_err(|_| {
+                RenderError::Format(format!(
+                    "invalid precision: '{}'",
+                    String::from_utf8_lossy(&argument)
+                ))
+            })?;
+            // A negative precision is the same as none.
+            usize::try_from(precision).ok()
+        }
+        Some(Count::Fixed(precision)) => Some(precision),
+        None => None,
+    };
+
+    let argument = arguments.next();
+    let field = match spec.conversion {
+        b'b' => {
+            let mut expanded = Vec::new();
+            let terminated =
+                expand_escapes_into(&argument, EscapeFlavor::PrintfArgument, &mut expanded);
+            output.write_all(&expanded)?;
+            return Ok(if terminated {
+                Flow::Stop
+            } else {
+                Flow::Continue
+            });
+        }
+        b'c' => Field::text(&[argument.first().copied().unwrap_or(0)]),
+        b's' => Field::text(&argument[..argument.len().min(precision.unwrap_or(usize::MAX))]),
+        b'd' | b'i' => spec.signed(signed(&argument, reporter), precision),
+        b'o' | b'u' | b'x' | b'X' => spec.unsigned(unsigned(&argument, reporter), precision),
+        _ => spec.float(float(&argument, reporter), precision),
+    };
+    field.write(spec.left, width, output)?;
+    Ok(Flow::Continue)
+}
+
+/// An integer argument, as parsed by `strtoimax` or `strtoumax`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Integer {
+    negative: bool,
+    /// The absolute value, or `None` if it does not fit.
+    magnitude: Option<u64>,
+    /// The message if the argument is not only an integer.
+    trailing: Option<&'static str>,
+}
+
+/// Parse an integer argument, which may start with spaces and a sign, and be in hexadecimal with `0x` or
+/// in octal with `0`. An empty argument is 0.
+fn parse_integer(argument: &[u8]) -> Integer {
+    let start = argument
+        .iter()
+        .position(|byte| !b" \t\n\x0b\x0c\r".contains(byte))
+        .unwrap_or(argument.len());
+    let (negative, rest) = match &argument[start..] {
+        [b'-', rest @ ..] => (true, rest),
+        [b'+', rest @ ..] => (false, rest),
+        rest => (false, rest),
+    };
+    let (radix, digits) = match rest {
+        [b'0', b'x' | b'X', digit, ..] if digit.is_ascii_hexdigit() => (16, &rest[2..]),
+        [b'0', ..] => (8, rest),
+        _ => (10, rest),
+    };
+    let length = digits
+        .iter()
+        .take_while(|&&byte| char::from(byte).is_digit(radix))
+        .count();
+    if length == 0 {
+        return Integer {
+            negative: false,
+            magnitude: Some(0),
+            trailing: (!argument.is_empty()).then_some("expected a numeric value"),
+        };
+    }
+    let magnitude = digits[..length].iter().try_fold(0u64, |value, &byte| {
+        let digit = char::from(byte).to_digit(radix).expect("a digit");
+        value.checked_mul(radix.into())?.checked_add(digit.into())
+    });
+    Integer {
+        negative,
+        magnitude,
+        trailing: (length < digits.len()).then_some("value not completely converted"),
+    }
+}
+
+/// Get the value of the byte after a quote, for an argument like `'A`, warning about the bytes after it.
+fn character_constant<W: Write>(argument: &[u8], reporter: &mut Reporter<W>) -> Option<u8> {
+    let [b'\'' | b'"', byte, rest @ ..] = argument else {
+        return None;
+    };
+    if !rest.is_empty() {
+        reporter.warning(format_args!(
+            "warning: {}: character(s) following character constant have been ignored",
+            String::from_utf8_lossy(rest)
+        ));
+    }
+    Some(*byte)
+}
+
+/// Parse an integer argument, or a quote followed by a byte for its value. The caller is responsible for
+/// reporting the problems.
+fn integer<W: Write>(argument: &[u8], reporter: &mut Reporter<W>) -> Integer {
+    match character_constant(argument, reporter) {
+        Some(byte) => Integer {
+            negative: false,
+            magnitude: Some(byte.into()),
+            trailing: None,
+        },
+        None => parse_integer(argument),
+    }
+}
+
+/// Report an argument which is not valid, where `range` is set when its value does not fit. Like GNU, only
+/// the range is reported if both are wrong.
+fn report<W: Write>(
+    argument: &[u8],
+    trailing: Option<&str>,
+    range: bool,
+    reporter: &mut Reporter<W>,
+) {
+    let message = if range {
+        "Numerical result out of range"
+    } else {
+        match trailing {
+            Some(message) => message,
+            None => return,
+        }
+    };
+    reporter.error(format_args!(
+        "'{}': {message}",
+        String::from_utf8_lossy(argument)
+    ));
+}
+
+/// Get the value of an argument of `%d` and `%i`, which is clamped when it does not fit.
+fn signed<W: Write>(argument: &[u8], reporter: &mut Reporter<W>) -> i64 {
+    let integer = integer(argument, reporter);
+    let value = match integer.magnitude {
+        Some(magnitude) if integer.negative => 0i64.checked_sub_unsigned(magnitude),
+        Some(magnitude) => i64::try_from(magnitude).ok(),
+        None => None,
+    };
+    report(argument, integer.trailing, value.is_none(), reporter);
+    value.unwrap_or(if integer.negative { i64::MIN } else { i64::MAX })
+}
+
+/// Get the value of an argument of the unsigned conversions, where a negative value wraps around like in C,
+/// and which is clamped when it does not fit.
+fn unsigned<W: Write>(argument: &[u8], reporter: &mut Reporter<W>) -> u64 {
+    let integer = integer(argument, reporter);
+    report(
+        argument,
+        integer.trailing,
+        integer.magnitude.is_none(),
+        reporter,
+    );
+    match integer.magnitude {
+        Some(magnitude) if integer.negative => magnitude.wrapping_neg(),
+        Some(magnitude) => magnitude,
+        None => u64::MAX,
+    }
+}
+
+/// Get the value of an argument of the floating point conversions, or of the byte after a quote, reporting
+/// the problems.
+fn float<W: Write>(argument: &[u8], reporter: &mut Reporter<W>) -> f64 {
+    if let Some(byte) = character_constant(argument, reporter) {
+        return byte.into();
+    }
+    let (value, trailing, range) = parse_float(argument);
+    report(argument, trailing, range, reporter);
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::run_util;
+    use std::io;
+
+    /// Run printf, expecting it to succeed without messages, and return the output.
+    fn printf_text(args: &[&str]) -> String {
+        let command_line: Vec<&str> = std::iter::once("printf")
+            .chain(args.iter().copied())
+            .collect();
+        let (code, stdout, stderr) = run_util(run, &command_line);
+        assert_eq!((code, stderr.as_str()), (0, ""), "{args:?}");
+        stdout
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(
+            printf_text(&["%s, %s!\n", "Hello", "world"]),
+            "Hello, world!\n"
+        );
+        assert_eq!(printf_text(&["%d %i\n", "-12", "+3"]), "-12 3\n");
+        assert_eq!(
+            printf_text(&["%u %o %x %X\n", "10", "8", "255", "255"]),
+            "10 10 ff FF\n"
+        );
+        assert_eq!(printf_text(&["100%%\n"]), "100%\n");
+        assert_eq!(printf_text(&["%ld %hhd %zu\n", "1", "2", "3"]), "1 2 3\n");
+        assert_eq!(printf_text(&["no conversions"]), "no conversions");
+    }
+
+    #[test]
+    fn test_large_precision() {
+        // The digits after those which write the value exactly are zeros.
+        let zeros = "0".repeat(70_000);
+        assert_eq!(printf_text(&["%.70000f", "1"]), format!("1.{zeros}"));
+        assert_eq!(printf_text(&["%.70000e", "1"]), format!("1.{zeros}e+00"));
+        let fixed = printf_text(&["%.70000f", "4.9e-324"]);
+        assert!(fixed.starts_with("0.000"), "{fixed}");
+        assert_eq!(fixed.trim_end_matches('0').len(), 2 + 1074);
+    }
+
+    #[test]
+    fn test_large_counts() {
+        // The padding and the zeros are written without being kept in memory.
+        struct Counter(usize, Option<u8>);
+        impl Write for Counter {
+            fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+                self.0 += buffer.len();
+                self.1 = buffer.last().copied().or(self.1);
+                Ok(buffer.len())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        for (format, length, last) in [
+            ("%100000000d", 100_000_000, b'1'),
+            ("%-100000000d", 100_000_000, b' '),
+            ("%.100000000d", 100_000_000, b'1'),
+            ("%.100000000f", 100_000_002, b'0'),
+            ("%#.100000000e", 100_000_006, b'0'),
+        ] {
+            let mut output = Counter(0, None);
+            let mut reporter = Reporter::new("printf", Vec::new());
+            let args = [OsString::from("1")];
+            let flow = render(format.as_bytes(), &args, &mut output, &mut reporter).unwrap();
+            assert_eq!(
+                (flow, output.0, output.1),
+                (Flow::Continue, length, Some(last)),
+                "{format}"
+            );
+        }
+
+        // Like GNU, the counts are at most the largest `int` of C.
+        let count = (1u64 << 31).to_string();
+        for (format, message) in [
+            (
+                format!("[%{count}d]"),
+                format!("invalid field width: '{count}'"),
+            ),
+            (
+                format!("[%.{count}f]"),
+                format!("invalid precision: '{count}'"),
+            ),
+            (
+                "[%99999999999999999999s]".to_owned(),
+                "invalid field width: '99999999999999999999'".to_owned(),
+            ),
+            (
+                "[%5.99999999999999999999d]".to_owned(),
+                "invalid precision: '99999999999999999999'".to_owned(),
+            ),
+        ] {
+            assert_eq!(
+                run_util(run, &["printf", &format, "1"]),
+                (1, "[".to_owned(), format!("printf: {message}\n")),
+                "{format}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_golden() {
+        // The outputs of GNU printf 9.1.
+        let cases: &[(&str, &[&str], &str)] = &[
+            (
+                "[%5d|%-5d|%05d]",
+                &["42", "42", "42"],
+                "[   42|42   |00042]",
+            ),
+            (
+                "[%+d|% d|%+d|% d]",
+                &["42", "42", "-42", "-42"],
+                "[+42| 42|-42|-42]",
+            ),
+            (
+                "[%.3d|%.0d|%5.3d|%-+6.2d]",
+                &["7", "0", "-7", "3"],
+                "[007|| -007|+03   ]",
+            ),
+            ("[%05.3d|%-05d]", &["7", "7"], "[  007|7    ]"),
+            (
+                "[%#o|%#x|%#X|%#o|%#x]",
+                &["8", "255", "255", "0", "0"],
+                "[010|0xff|0XFF|0|0]",
+            ),
+            (
+                "[%#.3o|%08.3x|%-#8x]",
+                &["8", "255", "255"],
+                "[010|     0ff|0xff    ]",
+            ),
+            ("[%i|%5i|%#5o]", &["010", "0x10", "010"], "[8|   16|  010]"),
+            ("[%u|%.2u|%+u|% u]", &["3", "3", "3", "3"], "[3|03|3|3]"),
+            (
+                "[%x|%+x|% X]",
+                &["-1", "10", "10"],
+                "[ffffffffffffffff|a|A]",
+            ),
+            ("[%-s|%- 5d|%+ d]", &["a", "1", "1"], "[a| 1   |+1]"),
+            (
+                "[%hd|%lf|%Lf|%jd|%zx]",
+                &["70000", "1.5", "1.5", "5", "255"],
+                "[70000|1.500000|1.500000|5|ff]",
+            ),
+            ("[%'d|%'f]", &["1234567", "1234.5"], "[1234567|1234.500000]"),
+            ("%-+0 #5.2f|%I d|", &["1", "2"], "+1.00| 2|"),
+            (
+                "[%10s|%-10s|%.2s|%5.1s]",
+                &["abc", "abc", "abc", "abc"],
+                "[       abc|abc       |ab|    a]",
+            ),
+            ("[%3c|%-3c]", &["x", "y"], "[  x|y  ]"),
+            ("[%c|%5c]", &["", ""], "[\0|    \0]"),
+            (
+                "[%*d|%-*d|%*d]",
+                &["5", "1", "5", "2", "-4", "3"],
+                "[    1|2    |3   ]",
+            ),
+            (
+                "[%.*d|%.*s|%.*d]",
+                &["3", "4", "2", "abcdef", "-1", "5"],
+                "[004|ab|5]",
+            ),
+            ("[%*s|%-*s]", &["3", "a", "3", "b"], "[  a|b  ]"),
+            ("[%0*d]", &["5", "3"], "[00003]"),
+            ("[%*.*f]", &["10", "2", "3.14159"], "[      3.14]"),
+            ("[%.*f]", &["-5", "1.5"], "[1.500000]"),
+            (
+                "[%f|%e|%E|%g|%G]",
+                &["3.14159"; 5],
+                "[3.141590|3.141590e+00|3.141590E+00|3.14159|3.14159]",
+            ),
+            ("[%f|%e|%g]", &["0", "0", "0"], "[0.000000|0.000000e+00|0]"),
+            (
+                "[%.0f|%.0e|%#.0f|%#.0e]",
+                &["2.5"; 4],
+                "[2|2e+00|2.|2.e+00]",
+            ),
+            ("[%.e|%.f|%.g]", &["1.5"; 3], "[2e+00|2|2]"),
+            ("[%.2f|%.2f]", &["0.125", "0.375"], "[0.12|0.38]"),
+            (
+                "[%g|%g|%g|%g|%g]",
+                &["100000", "1000000", "0.0001", "0.00001", "123456789"],
+                "[100000|1e+06|0.0001|1e-05|1.23457e+08]",
+            ),
+            (
+                "[%g|%g|%g]",
+                &["1e-5", "123456", "1234567"],
+                "[1e-05|123456|1.23457e+06]",
+            ),
+            (
+                "[%#g|%#g|%.3g|%#.3g]",
+                &["1", "0.0001", "1234.5", "1"],
+                "[1.00000|0.000100000|1.23e+03|1.00]",
+            ),
+            ("[%.10g|%.0g|%g]", &["0.1", "25", "-0.0"], "[0.1|2e+01|-0]"),
+            (
+                "[%10.3f|%-10.3e|%+.2f|% .2f|%010.2f|%-+010.1f]",
+                &["3.14159", "314.159", "2", "2", "-3.14159", "2"],
+                "[     3.142|3.142e+02 |+2.00| 2.00|-000003.14|+2.0      ]",
+            ),
+            (
+                "[%e|%e|%E]",
+                &["1e100", "1e-100", "-12345.678"],
+                "[1.000000e+100|1.000000e-100|-1.234568E+04]",
+            ),
+            (
+                "[%f|%e|%g|%F|%E|%G]",
+                &["inf", "-inf", "nan", "inf", "nan", "-inf"],
+                "[inf|-inf|nan|INF|NAN|-INF]",
+            ),
+            (
+                "[%05f|%-6f|%+f]",
+                &["inf", "nan", "inf"],
+                "[  inf|nan   |+inf]",
+            ),
+            (
+                "[%f|%f|%f|%f]",
+                &["0x10", "0x1.8p1", "1e3", "'A"],
+                "[16.000000|3.000000|1000.000000|65.000000]",
+            ),
+            (
+                "[%f|%g|%f|%f]",
+                &[" 1.5", "+.5", "Infinity", "nan(x1)"],
+                "[1.500000|0.5|inf|nan]",
+            ),
+            (
+                "%b|",
+                &[r"a\tb\n", r"\0101\101", r"\x41\q"],
+                "a\tb\n|AA|A\\q|",
+            ),
+            ("%s %b|", &[r"\n", r"\n"], "\\n \n|"),
+            ("[%b]", &[], "[]"),
+        ];
+        for (format, args, expected) in cases {
+            let args: Vec<&str> = std::iter::once(*format)
+                .chain(args.iter().copied())
+                .collect();
+            assert_eq!(printf_text(&args), *expected, "{format:?}");
+        }
+    }
+
+    #[test]
+    fn test_reuse() {
+        // The format is printed again from the start until all the arguments are consumed.
+        assert_eq!(
+            printf_text(&["%s=%d\n", "a", "1", "b", "2", "c", "3"]),
+            "a=1\nb=2\nc=3\n"
+        );
+        // The missing arguments are empty, or 0.
+        assert_eq!(
+            printf_text(&["%s=%d %c.\n", "a", "1", "b", "b", "2"]),
+            "a=1 b.\nb=2 \0.\n"
+        );
+        assert_eq!(printf_text(&["[%s]\n"]), "[]\n");
+        assert_eq!(printf_text(&["%%%d\n", "1", "2"]), "%1\n%2\n");
+    }
+
+    #[test]
+    fn test_excess_arguments() {
+        assert_eq!(
+            run_util(run, &["printf", "x\n", "a", "b"]),
+            (
+                0,
+                "x\n".to_owned(),
+                "printf: warning: ignoring excess arguments, starting with 'a'\n".to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn test_characters() {
+        // Only the first byte of the argument is printed.
+        assert_eq!(printf_text(&["%c%c\n", "abc", "xyz"]), "ax\n");
+        assert_eq!(printf_text(&["%c|", ""]), "\0|");
+        // Even when it is part of a character.
+        let args: Vec<OsString> = ["printf", "%c\n", "é"].iter().map(OsString::from).collect();
+        let mut stdout = Vec::new();
+        let code = run(&args, &mut &b""[..], &mut stdout, &mut Vec::new());
+        assert_eq!((code, stdout), (0, vec![0xc3, b'\n']));
+    }
+
+    #[test]
+    fn test_escapes() {
+        // The escapes of the format are expanded, but not the ones of the arguments.
+        assert_eq!(printf_text(&[r"a\tb\n"]), "a\tb\n");
+        assert_eq!(printf_text(&["%s\n", r"a\tb\n"]), "a\\tb\\n\n");
+        assert_eq!(printf_text(&[r"\101\x42\q%s\n", r"\n"]), "AB\\q\\n\n");
+        // Like GNU, an unknown escape is printed with the byte after it.
+        assert_eq!(printf_text(&[r"\%s%%"]), "\\%s%");
+    }
+
+    #[test]
+    fn test_stop() {
+        assert_eq!(printf_text(&[r"%s\c%s", "a", "b"]), "a");
+        assert_eq!(printf_text(&[r"a\c", "x", "y"]), "a");
+        // Like GNU, `\c` exits successfully after an invalid argument.
+        assert_eq!(
+            run_util(run, &["printf", r"%d\c", "x"]),
+            (
+                0,
+                "0".to_owned(),
+                "printf: 'x': expected a numeric value\n".to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn test_integers() {
+        assert_eq!(
+            printf_text(&["%d ", "0x1f", "0X1F", "010", "-0x10", " 12", "\t+5", "", "0"]),
+            "31 31 8 -16 12 5 0 0 "
+        );
+        assert_eq!(printf_text(&["%d ", "'A", "\"B", "''"]), "65 66 39 ");
+        assert_eq!(
+            printf_text(&["%d %u\n", "9223372036854775807", "18446744073709551615"]),
+            "9223372036854775807 18446744073709551615\n"
+        );
+        assert_eq!(
+            printf_text(&["%d\n", "-9223372036854775808"]),
+            "-9223372036854775808\n"
+        );
+        // Negative values wrap around for the unsigned conversions.
+        assert_eq!(
+            printf_text(&["%u %x\n", "-1", "-1"]),
+            "18446744073709551615 ffffffffffffffff\n"
+        );
+    }
+
+    #[test]
+    fn test_invalid_integers() {
+        for (argument, stdout, message) in [
+            ("abc", "0", "expected a numeric value"),
+            (" ", "0", "expected a numeric value"),
+            ("-", "0", "expected a numeric value"),
+            ("- 5", "0", "expected a numeric value"),
+            ("'", "0", "expected a numeric value"),
+            ("12abc", "12", "value not completely converted"),
+            ("0x", "0", "value not completely converted"),
+            ("08", "0", "value not completely converted"),
+            ("1e3", "1", "value not completely converted"),
+            (
+                "99999999999999999999",
+                "9223372036854775807",
+                "Numerical result out of range",
+            ),
+            (
+                "-99999999999999999999x",
+                "-9223372036854775808",
+                "Numerical result out of range",
+            ),
+        ] {
+            // The output and the other arguments go on, but the exit status is a failure.
+            assert_eq!(
+                run_util(run, &["printf", "%d %s\n", argument, "next"]),
+                (
+                    1,
+                    format!("{stdout} next\n"),
+                    format!("printf: '{argument}': {message}\n")
+                ),
+                "{argument:?}"
+            );
+        }
+        assert_eq!(
+            run_util(run, &["printf", "%u\n", "18446744073709551616"]),
+            (
+                1,
+                "18446744073709551615\n".to_owned(),
+                "printf: '18446744073709551616': Numerical result out of range\n".to_owned()
+            )
+        );
+        // The bytes after a character constant are ignored with a warning only.
+        assert_eq!(
+            run_util(run, &["printf", "%d\n", "'AB"]),
+            (
+                0,
+                "65\n".to_owned(),
+                "printf: warning: B: character(s) following character constant have been ignored\n"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn test_invalid_conversions() {
+        for (format, stdout, message) in [
+            ("%y", "", "%y"),
+            ("a%", "a", "%"),
+            ("ab%l", "ab", "%l"),
+            ("%d%l%\n", "1", "%l%"),
+            ("[%#d]", "[", "%#d"),
+            ("[%0s]", "[", "%0s"),
+            ("[%#c]", "[", "%#c"),
+            ("[%'x]", "[", "%'x"),
+            ("[%5b]", "[", "%5b"),
+            ("[%5%]", "[", "%5%"),
+            ("[%.3%]", "[", "%.3%"),
+            ("[%a]", "[", "%a"),
+        ] {
+            // The output stops at the invalid conversion.
+            assert_eq!(
+                run_util(run, &["printf", format, "1"]),
+                (
+                    1,
+                    stdout.to_owned(),
+                    format!("printf: {message}: invalid conversion specification\n")
+                ),
+                "{format:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_argument_escapes() {
+        // `\c` in the argument of `%b` stops all the output.
+        assert_eq!(printf_text(&["%b|", r"x\cy", "z"]), "x");
+        // `%s` precision counts bytes, even in the middle of a character.
+        let args: Vec<OsString> = ["printf", "%.3s|%.1s", "éx", "é"]
+            .iter()
+            .map(OsString::from)
+            .collect();
+        let mut stdout = Vec::new();
+        let code = run(&args, &mut &b""[..], &mut stdout, &mut Vec::new());
+        assert_eq!((code, stdout), (0, b"\xc3\xa9x|\xc3".to_vec()));
+    }
+
+    #[test]
+    fn test_invalid_floats() {
+        for (argument, stdout, message) in [
+            ("abc", "0.000000", "expected a numeric value"),
+            (".", "0.000000", "expected a numeric value"),
+            ("1.5x", "1.500000", "value not completely converted"),
+            ("1e", "1.000000", "value not completely converted"),
+            ("0x", "0.000000", "value not completely converted"),
+        ] {
+            assert_eq!(
+                run_util(run, &["printf", "%f", argument]),
+                (
+                    1,
+                    stdout.to_owned(),
+                    format!("printf: '{argument}': {message}\n")
+                ),
+                "{argument:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_stars() {
+        // An invalid width is reported, and taken as what could be parsed.
+        assert_eq!(
+            run_util(run, &["printf", "[%*d]", "x", "1"]),
+            (
+                1,
+                "[1]".to_owned(),
+                "printf: 'x': expected a numeric value\n".to_owned()
+            )
+        );
+        // But one which does not fit stops the output.
+        assert_eq!(
+            run_util(run, &["printf", "[%*d]", "99999999999"]),
+            (
+                1,
+                "[".to_owned(),
+                "printf: invalid field width: '99999999999'\n".to_owned()
+            )
+        );
+        assert_eq!(
+            run_util(run, &["printf", "[%.*d]", "99999999999"]),
+            (
+                1,
+                "[".to_owned(),
+                "printf: invalid precision: '99999999999'\n".to_owned()
+            )
+        );
+        // The missing ones are 0.
+        assert_eq!(printf_text(&["[%*d|%.*f]"]), "[0|0]");
+    }
+
+    #[test]
+    fn test_operands() {
+        assert_eq!(
+            run_util(run, &["printf"]),
+            (
+                1,
+                String::new(),
+                "printf: missing operand\nTry 'printf --help' for more information.\n".to_owned()
+            )
+        );
+        // The format may look like an option.
+        assert_eq!(printf_text(&["-x"]), "-x");
+        assert_eq!(printf_text(&["--", "-%s", "n"]), "-n");
+        assert_eq!(printf_text(&["--", "--"]), "--");
+        assert_eq!(printf_text(&["--help%s", "x"]), "--helpx");
+        let (code, stdout, _) = run_util(run, &["printf", "--help"]);
+        assert_eq!(code, 0);
+        assert!(stdout.contains("Usage: printf"));
+    }
+
+    #[test]
+    fn test_run_write_error() {
+        let args: Vec<OsString> = vec!["printf".into(), "%s\n".into(), "data".into()];
+        let mut stdout = io::Cursor::new([0; 2]);
+        let mut stderr = Vec::new();
+        let code = run(&args, &mut &b""[..], &mut stdout, &mut stderr);
+        assert_eq!(code, 1);
+        assert_eq!(
+            stderr,
+            b"printf: write error: failed to write the buffered data\n"
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_full_disk() {
+        use assert_cmd::prelude::*;
+        use std::fs::File;
+
+        let output = std::process::Command::cargo_bin("printf")
+            .unwrap()
+            .args(["%s\n", "data"])
+            .stdout(File::create("/dev/full").unwrap())
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(
+            String::from_utf8(output.stderr).unwrap(),
+            "printf: write error: No space left on device\n"
+        );
+    }
+}
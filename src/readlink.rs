@@ -0,0 +1,385 @@
+//! Print the targets of symbolic links, or canonical file names.
+//!
+//! ```
+//! use rust_coreutils::common::canonicalize::Mode;
+//! use rust_coreutils::readlink::read_path;
+//! use std::path::Path;
+//!
+//! let directory = tempfile::tempdir().unwrap();
+//! let root = std::fs::canonicalize(directory.path()).unwrap();
+//! # #[cfg(unix)]
+//! # {
+//! std::os::unix::fs::symlink("target", root.join("link")).unwrap();
+//! assert_eq!(read_path(&root.join("link"), None).unwrap(), Path::new("target"));
+//! assert_eq!(
+//!     read_path(&root.join("link"), Some(Mode::AllButLast)).unwrap(),
+//!     root.join("target")
+//! );
+//! assert!(read_path(&root.join("link"), Some(Mode::Existing)).is_err());
+//! # }
+//! ```
+
+use crate::common::canonicalize::{canonicalize, Mode};
+use crate::common::error::{Reporter, UError, UResult, EXIT_FAILURE};
+use crate::common::os::os_str_bytes;
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use clap::Parser;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "readlink",
+    author,
+    version,
+    about = "Print the target of each symbolic link FILE, or its canonical name.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Print the canonical name, with all the symbolic links resolved, where all the components but the
+    /// last one exist.
+    #[arg(
+        short = 'f',
+        long,
+        default_value_t = false,
+        overrides_with_all = ["canonicalize_existing", "canonicalize_missing"]
+    )]
+    canonicalize: bool,
+
+    /// Print the canonical name, where all the components exist.
+    #[arg(
+        short = 'e',
+        long,
+        default_value_t = false,
+        overrides_with_all = ["canonicalize", "canonicalize_missing"]
+    )]
+    canonicalize_existing: bool,
+
+    /// Print the canonical name, where the components can be missing.
+    #[arg(
+        short = 'm',
+        long,
+        default_value_t = false,
+        overrides_with_all = ["canonicalize", "canonicalize_existing"]
+    )]
+    canonicalize_missing: bool,
+
+    /// Do not print the delimiter after a single name.
+    #[arg(short = 'n', long, default_value_t = false)]
+    no_newline: bool,
+
+    /// Do not report the failures, which is the default.
+    #[arg(short = 'q', long, default_value_t = false, overrides_with = "verbose")]
+    quiet: bool,
+
+    /// Do not report the failures, which is the default.
+    #[arg(short = 's', long, default_value_t = false, overrides_with = "verbose")]
+    silent: bool,
+
+    /// Report the failures.
+    #[arg(short = 'v', long, default_value_t = false, overrides_with_all = ["quiet", "silent"])]
+    verbose: bool,
+
+    /// End each name with a null byte instead of a newline.
+    #[arg(short = 'z', long, default_value_t = false)]
+    zero: bool,
+
+    /// The files to read.
+    #[arg(value_name = "FILE")]
+    files: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "readlink";
+}
+
+/// The description of readlink for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run readlink with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run readlink with the given command line, including the program name, on the given streams, and return
+/// the exit code.
+pub fn run(
+    args: &[OsString],
+    _stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(args.iter().cloned(), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let mut reporter = Reporter::new("readlink", stderr);
+    let mode = match (
+        args.canonicalize,
+        args.canonicalize_existing,
+        args.canonicalize_missing,
+    ) {
+        (true, ..) => Some(Mode::AllButLast),
+        (_, true, _) => Some(Mode::Existing),
+        (.., true) => Some(Mode::Missing),
+        _ => None,
+    };
+    let mut no_newline = args.no_newline;
+    if no_newline && args.files.len() > 1 {
+        reporter.warning(format_args!(
+            "ignoring --no-newline with multiple arguments"
+        ));
+        no_newline = false;
+    }
+    let options = ReadlinkOptions {
+        mode,
+        no_newline,
+        zero: args.zero,
+        verbose: args.verbose,
+    };
+    let result = readlink(&args.files, &options, stdout, &mut reporter);
+    reporter.finish(result)
+}
+
+/// The options of readlink.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReadlinkOptions {
+    /// Print the canonical names, with the components required to exist by the mode (`-f`, `-e`, `-m`),
+    /// rather than the targets of the symbolic links.
+    pub mode: Option<Mode>,
+    /// Do not print the delimiter after the names (`-n`).
+    pub no_newline: bool,
+    /// End the names with a null byte (`-z`), rather than a newline.
+    pub zero: bool,
+    /// Report the failures (`-v`), rather than only remembering them for the exit code.
+    pub verbose: bool,
+}
+
+/// Print the target or the canonical name of each operand, continuing after the failures.
+pub fn readlink<W: Write>(
+    operands: &[OsString],
+    options: &ReadlinkOptions,
+    stdout: &mut dyn Write,
+    reporter: &mut Reporter<W>,
+) -> UResult<()> {
+    if operands.is_empty() {
+        return Err(UError::usage(EXIT_FAILURE, "missing operand"));
+    }
+    for operand in operands {
+        let path = match read_path(Path::new(operand), options.mode) {
+            Ok(path) => path,
+            Err(error) if options.verbose => {
+                reporter.io_error(Path::new(operand).display(), &error);
+                continue;
+            }
+            Err(_) => {
+                reporter.set_failed();
+                continue;
+            }
+        };
+        let mut name = os_str_bytes(path.as_os_str()).into_owned();
+        if !options.no_newline {
+            name.push(if options.zero { b'\0' } else { b'\n' });
+        }
+        if let Err(error) = stdout.write_all(&name) {
+            return UError::write(error);
+        }
+    }
+    stdout.flush().or_else(UError::write)
+}
+
+/// Get the target of a symbolic link, or the canonical name of a file with a mode.
+///
+/// Reading a file which is not a symbolic link fails with `EINVAL`.
+pub fn read_path(path: &Path, mode: Option<Mode>) -> io::Result<PathBuf> {
+    match mode {
+        Some(mode) => canonicalize(path, mode),
+        None => fs::read_link(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::run_util;
+
+    #[test]
+    #[cfg(unix)]
+    fn test_contents() {
+        use std::os::unix::fs::symlink;
+
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::write(root.join("file"), "").unwrap();
+        symlink(root.join("file"), root.join("absolute")).unwrap();
+        symlink("../missing", root.join("relative")).unwrap();
+        let path = |name| root.join(name).display().to_string();
+
+        assert_eq!(
+            run_util(run, &["readlink", &path("absolute"), &path("relative")]),
+            (0, format!("{}\n../missing\n", path("file")), String::new())
+        );
+        assert_eq!(
+            run_util(run, &["readlink", "-n", &path("relative")]),
+            (0, "../missing".to_owned(), String::new())
+        );
+        assert_eq!(
+            run_util(
+                run,
+                &["readlink", "-z", &path("relative"), &path("relative")]
+            ),
+            (0, "../missing\0../missing\0".to_owned(), String::new())
+        );
+        assert_eq!(
+            run_util(
+                run,
+                &["readlink", "-n", &path("relative"), &path("relative")]
+            ),
+            (
+                0,
+                "../missing\n../missing\n".to_owned(),
+                "readlink: ignoring --no-newline with multiple arguments\n".to_owned()
+            )
+        );
+
+        // The failures are only reported with -v, and the other operands are still printed.
+        let command_line = ["readlink", &path("file"), &path("relative")];
+        assert_eq!(
+            run_util(run, &command_line),
+            (EXIT_FAILURE, "../missing\n".to_owned(), String::new())
+        );
+        let command_line = ["readlink", "-v", &path("file"), &path("missing")];
+        assert_eq!(
+            run_util(run, &command_line),
+            (
+                EXIT_FAILURE,
+                String::new(),
+                format!(
+                    "readlink: {}: Invalid argument\nreadlink: {}: No such file or directory\n",
+                    path("file"),
+                    path("missing")
+                )
+            )
+        );
+        assert_eq!(
+            run_util(run, &["readlink", "-vq", &path("file")]),
+            (EXIT_FAILURE, String::new(), String::new())
+        );
+    }
+
+    #[test]
+    fn test_missing_operand() {
+        assert_eq!(
+            run_util(run, &["readlink", "-f"]),
+            (
+                EXIT_FAILURE,
+                String::new(),
+                "readlink: missing operand\nTry 'readlink --help' for more information.\n"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_canonicalize() {
+        use std::os::unix::fs::symlink;
+
+        let directory = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(directory.path()).unwrap();
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        fs::create_dir(root.join("c")).unwrap();
+        fs::write(root.join("a/b/file"), "").unwrap();
+        // A chain of relative links across directories, whose `..` apply to their targets.
+        symlink("../a/b", root.join("c/to_b")).unwrap();
+        symlink("../c/to_b/../b/file", root.join("a/chain")).unwrap();
+        symlink("c/../a/chain", root.join("start")).unwrap();
+        symlink("a/b/missing", root.join("dangling")).unwrap();
+        let path = |name| root.join(name).display().to_string();
+        let file = path("a/b/file");
+
+        for option in ["-f", "-e", "-m"] {
+            assert_eq!(
+                run_util(run, &["readlink", option, &path("start")]),
+                (0, format!("{file}\n"), String::new()),
+                "{option}"
+            );
+        }
+
+        // Only -e requires the last component to exist.
+        let missing = path("a/b/missing");
+        for (option, result) in [
+            ("-f", (0, format!("{missing}\n"), String::new())),
+            ("--canonicalize", (0, format!("{missing}\n"), String::new())),
+            ("-m", (0, format!("{missing}\n"), String::new())),
+            (
+                "-e",
+                (
+                    EXIT_FAILURE,
+                    String::new(),
+                    format!(
+                        "readlink: {}: No such file or directory\n",
+                        path("dangling")
+                    ),
+                ),
+            ),
+        ] {
+            assert_eq!(
+                run_util(run, &["readlink", "-v", option, &path("dangling")]),
+                result,
+                "{option}"
+            );
+        }
+        // The last of -f, -e, and -m wins.
+        assert_eq!(
+            run_util(run, &["readlink", "-e", "-f", &path("dangling")]),
+            (0, format!("{missing}\n"), String::new())
+        );
+
+        // Only -m allows the other components to be missing.
+        let nested = path("dangling/file");
+        assert_eq!(
+            run_util(run, &["readlink", "-m", &nested]),
+            (0, format!("{missing}/file\n"), String::new())
+        );
+        assert_eq!(
+            run_util(run, &["readlink", "-fv", &nested]),
+            (
+                EXIT_FAILURE,
+                String::new(),
+                format!("readlink: {nested}: No such file or directory\n")
+            )
+        );
+
+        symlink("loop", root.join("loop")).unwrap();
+        for option in ["-f", "-e"] {
+            assert_eq!(
+                run_util(run, &["readlink", "-v", option, &path("loop")]),
+                (
+                    EXIT_FAILURE,
+                    String::new(),
+                    format!(
+                        "readlink: {}: Too many levels of symbolic links\n",
+                        path("loop")
+                    )
+                )
+            );
+        }
+        assert_eq!(
+            run_util(run, &["readlink", "-m", &path("loop")]),
+            (0, format!("{}\n", path("loop")), String::new())
+        );
+    }
+}
@@ -0,0 +1,454 @@
+//! Print the canonical absolute names of files, optionally relative to a directory.
+//!
+//! ```
+//! use rust_coreutils::realpath::{real_path, RealpathOptions};
+//! use std::path::Path;
+//!
+//! let directory = tempfile::tempdir().unwrap();
+//! let root = std::fs::canonicalize(directory.path()).unwrap();
+//! std::fs::create_dir_all(root.join("a/b")).unwrap();
+//! let options = RealpathOptions {
+//!     relative_to: Some(root.join("a/b")),
+//!     ..RealpathOptions::default()
+//! };
+//! assert_eq!(real_path(&root.join("a/c"), &options).unwrap(), Path::new("../c"));
+//! ```
+
+use crate::common::canonicalize::{canonicalize, normalize, relative_path, Mode};
+use crate::common::error::{Reporter, UError, UResult, EXIT_FAILURE};
+use crate::common::os::os_str_bytes;
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use clap::Parser;
+use std::ffi::OsString;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "realpath",
+    author,
+    version,
+    about = "Print the canonical absolute name of each FILE, where all the components but the last exist.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Require all the components to exist.
+    #[arg(
+        short = 'e',
+        long,
+        default_value_t = false,
+        overrides_with = "canonicalize_missing"
+    )]
+    canonicalize_existing: bool,
+
+    /// Allow the components to be missing.
+    #[arg(
+        short = 'm',
+        long,
+        default_value_t = false,
+        overrides_with = "canonicalize_existing"
+    )]
+    canonicalize_missing: bool,
+
+    /// Only resolve `.` and `..`, without following the symbolic links or looking at the directories.
+    #[arg(
+        short = 's',
+        long,
+        visible_alias = "no-symlinks",
+        default_value_t = false
+    )]
+    strip: bool,
+
+    /// Do not report the failures.
+    #[arg(short = 'q', long, default_value_t = false)]
+    quiet: bool,
+
+    /// End each name with a null byte instead of a newline.
+    #[arg(short = 'z', long, default_value_t = false)]
+    zero: bool,
+
+    /// Print the names relative to DIR.
+    #[arg(long, value_name = "DIR")]
+    relative_to: Option<OsString>,
+
+    /// Print the names relative to the other directory only under DIR, and the other ones absolute.
+    #[arg(long, value_name = "DIR")]
+    relative_base: Option<OsString>,
+
+    /// The files to resolve.
+    #[arg(value_name = "FILE")]
+    files: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "realpath";
+}
+
+/// The description of realpath for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run realpath with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run realpath with the given command line, including the program name, on the given streams, and return
+/// the exit code.
+pub fn run(
+    args: &[OsString],
+    _stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(args.iter().cloned(), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let mut reporter = Reporter::new("realpath", stderr);
+    let result =
+        options(&args).and_then(|options| realpath(&args.files, &options, stdout, &mut reporter));
+    reporter.finish(result)
+}
+
+/// Resolve the directories of the options with the same mode as the operands.
+fn options(args: &Args) -> UResult<RealpathOptions> {
+    let mut options = RealpathOptions {
+        mode: match (args.canonicalize_existing, args.canonicalize_missing) {
+            (true, _) => Mode::Existing,
+            (_, true) => Mode::Missing,
+            _ => Mode::AllButLast,
+        },
+        strip: args.strip,
+        quiet: args.quiet,
+        zero: args.zero,
+        ..RealpathOptions::default()
+    };
+    let directory = |name: &OsString| {
+        let mut path = PathBuf::from(name);
+        // All the components, including the last one, have to be directories with -e, which a trailing
+        // slash requires.
+        if options.mode == Mode::Existing {
+            path.push("");
+        }
+        resolve(&path, &options).map_err(|error| UError::io(Path::new(name).display(), &error))
+    };
+    let relative_to = args.relative_to.as_ref().map(directory).transpose()?;
+    let relative_base = args.relative_base.as_ref().map(directory).transpose()?;
+    (options.relative_to, options.relative_base) = match (relative_to, relative_base) {
+        // Only the base is the directory to print the names relative to.
+        (None, Some(base)) => (Some(base.clone()), Some(base)),
+        // No name can be both under the base and relative to a directory outside of it.
+        (Some(to), Some(base)) if !to.starts_with(&base) => (None, None),
+        directories => directories,
+    };
+    Ok(options)
+}
+
+/// The options of realpath.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RealpathOptions {
+    /// The components required to exist (`-e`, `-m`), all but the last one by default.
+    pub mode: Mode,
+    /// Only resolve `.` and `..` (`-s`), without following the symbolic links or looking at the directories.
+    pub strip: bool,
+    /// Do not report the failures (`-q`), only remembering them for the exit code.
+    pub quiet: bool,
+    /// End the names with a null byte (`-z`), rather than a newline.
+    pub zero: bool,
+    /// The canonical directory to print the names relative to (`--relative-to`).
+    pub relative_to: Option<PathBuf>,
+    /// The canonical directory the names have to be under to be printed relative to `relative_to`
+    /// (`--relative-base`), rather than absolute.
+    pub relative_base: Option<PathBuf>,
+}
+
+impl Default for RealpathOptions {
+    fn default() -> Self {
+        RealpathOptions {
+            mode: Mode::AllButLast,
+            strip: false,
+            quiet: false,
+            zero: false,
+            relative_to: None,
+            relative_base: None,
+        }
+    }
+}
+
+/// Print the canonical name of each operand, continuing after the failures.
+pub fn realpath<W: Write>(
+    operands: &[OsString],
+    options: &RealpathOptions,
+    stdout: &mut dyn Write,
+    reporter: &mut Reporter<W>,
+) -> UResult<()> {
+    if operands.is_empty() {
+        return Err(UError::usage(EXIT_FAILURE, "missing operand"));
+    }
+    for operand in operands {
+        let path = match real_path(Path::new(operand), options) {
+            Ok(path) => path,
+            Err(_) if options.quiet => {
+                reporter.set_failed();
+                continue;
+            }
+            Err(error) => {
+                reporter.io_error(Path::new(operand).display(), &error);
+                continue;
+            }
+        };
+        let mut name = os_str_bytes(path.as_os_str()).into_owned();
+        name.push(if options.zero { b'\0' } else { b'\n' });
+        if let Err(error) = stdout.write_all(&name) {
+            return UError::write(error);
+        }
+    }
+    stdout.flush().or_else(UError::write)
+}
+
+/// Get the canonical name of a file, relative to the directory of the options when it is under their base.
+pub fn real_path(path: &Path, options: &RealpathOptions) -> io::Result<PathBuf> {
+    let path = resolve(path, options)?;
+    match (&options.relative_to, &options.relative_base) {
+        (Some(to), None) => Ok(relative_path(&path, to)),
+        (Some(to), Some(base)) if path.starts_with(base) => Ok(relative_path(&path, to)),
+        _ => Ok(path),
+    }
+}
+
+fn resolve(path: &Path, options: &RealpathOptions) -> io::Result<PathBuf> {
+    match options.strip {
+        true => normalize(path, options.mode),
+        false => canonicalize(path, options.mode),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::run_util;
+
+    #[test]
+    fn test_missing_operand() {
+        assert_eq!(
+            run_util(run, &["realpath", "-e"]),
+            (
+                EXIT_FAILURE,
+                String::new(),
+                "realpath: missing operand\nTry 'realpath --help' for more information.\n"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_symlinks() {
+        use std::fs;
+        use std::os::unix::fs::symlink;
+
+        let directory = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(directory.path()).unwrap();
+        fs::create_dir_all(root.join("d/sub")).unwrap();
+        fs::write(root.join("d/file"), "").unwrap();
+        symlink("d/sub", root.join("link")).unwrap();
+        let path = |name| root.join(name).display().to_string();
+
+        assert_eq!(
+            run_util(
+                run,
+                &["realpath", &path("link/../file"), &path("link/missing")]
+            ),
+            (
+                0,
+                format!("{}\n{}\n", path("d/file"), path("d/sub/missing")),
+                String::new()
+            )
+        );
+        // The links are kept with -s, and `..` goes to the directory of the link.
+        assert_eq!(
+            run_util(
+                run,
+                &["realpath", "-s", &path("link/./"), &path("link/../d/file")]
+            ),
+            (
+                0,
+                format!("{}\n{}\n", path("link"), path("d/file")),
+                String::new()
+            )
+        );
+        // Nor are the missing components looked at.
+        assert_eq!(
+            run_util(run, &["realpath", "-s", &path("d/missing/x")]),
+            (0, format!("{}\n", path("d/missing/x")), String::new())
+        );
+        assert_eq!(
+            run_util(run, &["realpath", "-s", "-e", &path("d/missing/../file")]),
+            (0, format!("{}\n", path("d/file")), String::new())
+        );
+        assert_eq!(
+            run_util(run, &["realpath", "-z", "--no-symlinks", &path("link")]),
+            (0, format!("{}\0", path("link")), String::new())
+        );
+
+        // Only -e requires the last component to exist, and -m allows the other ones to be missing.
+        assert_eq!(
+            run_util(
+                run,
+                &["realpath", "-e", &path("link/missing"), &path("link")]
+            ),
+            (
+                EXIT_FAILURE,
+                format!("{}\n", path("d/sub")),
+                format!(
+                    "realpath: {}: No such file or directory\n",
+                    path("link/missing")
+                )
+            )
+        );
+        assert_eq!(
+            run_util(run, &["realpath", "-qe", &path("link/missing")]),
+            (EXIT_FAILURE, String::new(), String::new())
+        );
+        assert_eq!(
+            run_util(run, &["realpath", &path("missing/file")]),
+            (
+                EXIT_FAILURE,
+                String::new(),
+                format!(
+                    "realpath: {}: No such file or directory\n",
+                    path("missing/file")
+                )
+            )
+        );
+        assert_eq!(
+            run_util(run, &["realpath", "-m", &path("missing/../link/x")]),
+            (0, format!("{}\n", path("d/sub/x")), String::new())
+        );
+        assert_eq!(
+            run_util(run, &["realpath", "-se", &path("d/file/")]),
+            (
+                EXIT_FAILURE,
+                String::new(),
+                format!("realpath: {}: Not a directory\n", path("d/file/"))
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_relative() {
+        use std::fs;
+
+        let directory = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(directory.path()).unwrap();
+        fs::create_dir_all(root.join("d/sub")).unwrap();
+        fs::create_dir(root.join("sibling")).unwrap();
+        let path = |name| root.join(name).display().to_string();
+        let relative_to = format!("--relative-to={}", path("d"));
+        let relative_base = format!("--relative-base={}", path("d"));
+
+        let command_line = [
+            "realpath",
+            &relative_to,
+            &path("sibling/file"),
+            &path("d/f"),
+            &path("d"),
+        ];
+        assert_eq!(
+            run_util(run, &command_line),
+            (0, "../sibling/file\nf\n.\n".to_owned(), String::new())
+        );
+        // Only the root is shared with the directory.
+        let parents = vec![".."; root.join("d").components().count() - 1];
+        assert_eq!(
+            run_util(run, &["realpath", &relative_to, "/"]),
+            (0, format!("{}\n", parents.join("/")), String::new())
+        );
+        assert_eq!(
+            run_util(run, &["realpath", "--relative-to=/", "/", &path("d")]),
+            (0, format!(".\n{}\n", &path("d")[1..]), String::new())
+        );
+
+        // The names outside of the base are absolute.
+        let command_line = [
+            "realpath",
+            &relative_base,
+            &path("sibling/file"),
+            &path("d/f"),
+        ];
+        assert_eq!(
+            run_util(run, &command_line),
+            (0, format!("{}\nf\n", path("sibling/file")), String::new())
+        );
+        let relative_to_sub = format!("--relative-to={}", path("d/sub"));
+        let command_line = [
+            "realpath",
+            &relative_base,
+            &relative_to_sub,
+            &path("d/f"),
+            &path("sibling/file"),
+        ];
+        assert_eq!(
+            run_util(run, &command_line),
+            (
+                0,
+                format!("../f\n{}\n", path("sibling/file")),
+                String::new()
+            )
+        );
+        // A directory outside of the base leaves all the names absolute.
+        let relative_base_sub = format!("--relative-base={}", path("d/sub"));
+        assert_eq!(
+            run_util(
+                run,
+                &[
+                    "realpath",
+                    &relative_base_sub,
+                    &relative_to,
+                    &path("d/sub/f")
+                ]
+            ),
+            (0, format!("{}\n", path("d/sub/f")), String::new())
+        );
+
+        // The directories are resolved with the mode of the operands.
+        let missing = format!("--relative-to={}", path("missing/x"));
+        assert_eq!(
+            run_util(run, &["realpath", &missing, &path("d")]),
+            (
+                EXIT_FAILURE,
+                String::new(),
+                format!(
+                    "realpath: {}: No such file or directory\n",
+                    path("missing/x")
+                )
+            )
+        );
+        let file = format!("--relative-to={}", path("d/f"));
+        fs::write(root.join("d/f"), "").unwrap();
+        assert_eq!(
+            run_util(run, &["realpath", "-e", &file, &path("d")]),
+            (
+                EXIT_FAILURE,
+                String::new(),
+                format!("realpath: {}: Not a directory\n", path("d/f"))
+            )
+        );
+        let missing = format!("--relative-to={}", path("missing"));
+        assert_eq!(
+            run_util(run, &["realpath", &missing, &path("d")]),
+            (0, "../d\n".to_owned(), String::new())
+        );
+    }
+}
@@ -0,0 +1,958 @@
+//! Remove files and directories.
+//!
+//! Files are removed with a [`Remover`], which removes directories with their contents when asked to,
+//! without following symbolic links, and asks before the removals as the options say:
+//!
+//! ```
+//! use rust_coreutils::common::error::Reporter;
+//! use rust_coreutils::common::prompt::PromptPolicy;
+//! use rust_coreutils::rm::{Remover, RmOptions};
+//!
+//! let directory = tempfile::tempdir().unwrap();
+//! let tree = directory.path().join("tree");
+//! std::fs::create_dir_all(tree.join("sub")).unwrap();
+//! std::fs::write(tree.join("sub/file"), "").unwrap();
+//!
+//! let mut reporter = Reporter::new("rm", Vec::new());
+//! let (mut answers, mut stdout) = (std::io::empty(), Vec::new());
+//! let mut remover = Remover::new(RmOptions::default(), &mut reporter, &mut answers, &mut stdout);
+//! remover.remove(&tree);
+//! assert!(tree.exists() && reporter.failed());
+//!
+//! let options = RmOptions {
+//!     prompt: PromptPolicy::Always,
+//!     recursive: true,
+//!     ..RmOptions::default()
+//! };
+//! let mut reporter = Reporter::new("rm", Vec::new());
+//! let mut answers = &b"yes\nno\n"[..];
+//! Remover::new(options, &mut reporter, &mut answers, &mut stdout).remove(&tree);
+//! assert!(tree.join("sub/file").exists() && !reporter.failed());
+//! assert_eq!(
+//!     String::from_utf8_lossy(reporter.stderr()),
+//!     format!(
+//!         "rm: descend into directory '{}'? rm: descend into directory '{}'? ",
+//!         tree.display(),
+//!         tree.join("sub").display()
+//!     )
+//! );
+//! ```
+
+use crate::common::error::{io_error_message, Reporter, UError, UResult, EXIT_FAILURE};
+use crate::common::mode::file_kind;
+use crate::common::os;
+#[cfg(unix)]
+use crate::common::paths::c_path;
+use crate::common::paths::{base_name, same_file};
+use crate::common::prompt::{PromptPolicy, Prompter};
+use crate::common::utility::{parse_matches, UtilCommand, Utility};
+use crate::common::walk::{Walk, WalkError, WalkOptions};
+use crate::{prompt_yes, show_error};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Parser};
+use std::ffi::OsString;
+use std::fs::{self, Metadata};
+use std::io::{self, ErrorKind, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "rm",
+    author,
+    version,
+    about = "Remove the FILEs.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Ignore the files which do not exist, and never ask.
+    #[arg(short, long, default_value_t = false)]
+    force: bool,
+
+    /// Ask before every removal.
+    #[arg(short = 'i', default_value_t = false)]
+    always: bool,
+
+    /// Ask once before removing more than three files, or removing recursively.
+    #[arg(short = 'I', default_value_t = false)]
+    once: bool,
+
+    /// Ask never, once (like -I), or always (like -i). Without WHEN, always ask.
+    #[arg(
+        long,
+        value_name = "WHEN",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "always"
+    )]
+    interactive: Option<String>,
+
+    /// Remove directories and their contents recursively.
+    #[arg(short = 'r', visible_short_alias = 'R', long, default_value_t = false)]
+    recursive: bool,
+
+    /// Remove empty directories.
+    #[arg(short, long, default_value_t = false)]
+    dir: bool,
+
+    /// Print what is being done.
+    #[arg(short, long, default_value_t = false)]
+    verbose: bool,
+
+    /// The files to remove.
+    #[arg(value_name = "FILE")]
+    files: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "rm";
+}
+
+/// The description of rm for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run rm with the given command line, including the program name, and return the exit code.
+///
+/// Unlike [`run`], removing a write-protected file is confirmed when standard input is a terminal.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    let args: Vec<OsString> = args.into_iter().collect();
+    let terminal = io::stdin().is_terminal();
+    run_on(
+        &args,
+        terminal,
+        &mut io::stdin().lock(),
+        &mut os::stdout(),
+        &mut os::stderr(),
+    )
+}
+
+/// Run rm with the given command line, including the program name, on the given streams, and return the
+/// exit code. Standard input is treated as a file, never as a terminal.
+pub fn run(
+    args: &[OsString],
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    run_on(args, false, stdin, stdout, stderr)
+}
+
+fn run_on(
+    args: &[OsString],
+    terminal: bool,
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let (args, matches): (Args, _) = match parse_matches(args.iter().cloned(), stdout, stderr) {
+        Ok(parsed) => parsed,
+        Err(code) => return code,
+    };
+
+    let mut reporter = Reporter::new("rm", stderr);
+    let options = match options(&args, &matches, terminal) {
+        Ok(options) => options,
+        Err(error) => return reporter.finish(Err(error)),
+    };
+    let result = rm(
+        &args.files,
+        &mut Remover::new(options, &mut reporter, stdin, stdout),
+    );
+    reporter.finish(result)
+}
+
+fn options(args: &Args, matches: &ArgMatches, terminal: bool) -> UResult<RmOptions> {
+    let interactive = args
+        .interactive
+        .as_deref()
+        .map(PromptPolicy::parse)
+        .transpose()?;
+    // The last of the options which say when to ask is the one that counts.
+    let last = ["force", "once", "always", "interactive"]
+        .into_iter()
+        .filter_map(|id| Some((last_index(matches, id)?, id)))
+        .max()
+        .map(|(_, id)| id);
+    let prompt = PromptPolicy::from_options(
+        last == Some("force"),
+        last == Some("once"),
+        last == Some("always"),
+        interactive.filter(|_| last == Some("interactive")),
+    );
+    Ok(RmOptions {
+        force: args.force,
+        prompt,
+        stdin_is_terminal: terminal,
+        recursive: args.recursive,
+        dir: args.dir,
+        verbose: args.verbose,
+    })
+}
+
+fn last_index(matches: &ArgMatches, id: &str) -> Option<usize> {
+    // The flags which are not given have a default value, at the end.
+    if matches.value_source(id) != Some(ValueSource::CommandLine) {
+        return None;
+    }
+    matches.indices_of(id)?.max()
+}
+
+/// The options of rm.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RmOptions {
+    /// Ignore the files which do not exist (`-f`), even when a later option asks before the removals.
+    pub force: bool,
+    /// When to ask before removing files, as selected by the last of `-f`, `-i`, `-I`, and `--interactive`.
+    pub prompt: PromptPolicy,
+    /// Whether the answers come from a terminal, so that removing a write-protected file is confirmed with
+    /// the default `prompt`.
+    pub stdin_is_terminal: bool,
+    /// Remove directories with their contents (`-r`), rather than refusing to remove them.
+    pub recursive: bool,
+    /// Remove empty directories (`-d`).
+    pub dir: bool,
+    /// Print the removed files (`-v`).
+    pub verbose: bool,
+}
+
+/// Remove the operands, after asking once with `-I`.
+///
+/// An operand which fails to be removed is reported, and the removal continues with the next one.
+pub fn rm<W: Write>(operands: &[OsString], remover: &mut Remover<W>) -> UResult<()> {
+    let options = remover.options;
+    if operands.is_empty() {
+        return match options.force {
+            true => Ok(()),
+            false => Err(UError::usage(EXIT_FAILURE, "missing operand")),
+        };
+    }
+    if options
+        .prompt
+        .prompt_once(operands.len(), options.recursive)
+    {
+        let plural = if operands.len() == 1 { "" } else { "s" };
+        let recursively = if options.recursive {
+            " recursively"
+        } else {
+            ""
+        };
+        let mut prompter = Prompter::new("rm", &mut *remover.answers, remover.reporter.stderr());
+        if !prompt_yes!(
+            prompter,
+            "remove {} argument{plural}{recursively}?",
+            operands.len()
+        ) {
+            return Ok(());
+        }
+    }
+    for operand in operands {
+        remover.remove(Path::new(operand));
+    }
+    Ok(())
+}
+
+/// Removes files, asking questions and printing messages on the given streams.
+pub struct Remover<'a, W: Write> {
+    options: RmOptions,
+    reporter: &'a mut Reporter<W>,
+    /// Where the answers to the questions are read.
+    answers: &'a mut dyn Read,
+    /// Where `-v` prints the removals.
+    stdout: &'a mut dyn Write,
+}
+
+/// A directory whose contents are being removed, which is removed after them.
+struct PendingDirectory {
+    path: PathBuf,
+    metadata: Metadata,
+    /// Whether its removal is already confirmed, because it was empty when it was asked about.
+    confirmed: bool,
+    /// Whether a file inside it is kept, so that it is kept too.
+    keeps: bool,
+}
+
+impl<'a, W: Write> Remover<'a, W> {
+    /// Create a remover with the given options, reporting the failures to `reporter`, reading the answers
+    /// to the questions from `answers`, and printing the removals to `stdout`.
+    pub fn new(
+        options: RmOptions,
+        reporter: &'a mut Reporter<W>,
+        answers: &'a mut dyn Read,
+        stdout: &'a mut dyn Write,
+    ) -> Self {
+        Remover {
+            options,
+            reporter,
+            answers,
+            stdout,
+        }
+    }
+
+    /// Remove a file, an empty directory with `options.dir`, or a directory and its contents with
+    /// `options.recursive`.
+    ///
+    /// A symbolic link is removed rather than the file it points to. With `options.recursive`, the root
+    /// directory, and the paths ending with `.` or `..`, are refused. The failures are reported, and the
+    /// removal continues with the next file. A file which is not confirmed is kept, with the directories
+    /// containing it, and the removal continues with the other files.
+    pub fn remove(&mut self, path: &Path) {
+        if self.options.recursive {
+            if let Err(error) = check_root(path) {
+                self.reporter.error(format_args!("{error}"));
+                return;
+            }
+            self.remove_tree(path);
+            return;
+        }
+
+        let metadata = match fs::symlink_metadata(path) {
+            Ok(metadata) => metadata,
+            Err(error) => return self.cannot_remove(path, &error),
+        };
+        // A directory which cannot be removed is not asked about.
+        let problem = match (metadata.is_dir(), self.options.dir) {
+            (true, false) => Some("Is a directory"),
+            (true, true) if !is_empty_dir(path) => Some("Directory not empty"),
+            _ => None,
+        };
+        match problem {
+            Some(problem) => show_error!(
+                self.reporter,
+                "cannot remove '{}': {problem}",
+                path.display()
+            ),
+            None => {
+                self.remove_entry(path, &metadata, false);
+            }
+        }
+    }
+
+    /// Remove a directory and its contents, asking whether to descend into it first if needed.
+    ///
+    /// The directories containing a file which is kept are kept without another error or question.
+    fn remove_tree(&mut self, path: &Path) {
+        let mut walk = Walk::new(vec![path.to_path_buf()], WalkOptions::default());
+        // The directories containing the current entry, by depth.
+        let mut pending: Vec<PendingDirectory> = Vec::new();
+        while let Some(entry) = walk.next() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(WalkError::Io { path, error }) => {
+                    let depth = pending
+                        .iter()
+                        .take_while(|directory| path.starts_with(&directory.path))
+                        .count();
+                    self.finish_directories(&mut pending, depth);
+                    self.cannot_remove(&path, &error);
+                    // A directory whose contents cannot be read comes right before its error.
+                    if pending
+                        .last()
+                        .is_some_and(|directory| directory.path == path)
+                    {
+                        keep(&mut pending);
+                    }
+                    continue;
+                }
+                // The symbolic links are not followed, so there are no loops.
+                Err(WalkError::Loop { .. }) => continue,
+            };
+            self.finish_directories(&mut pending, entry.depth);
+
+            if !entry.is_dir() {
+                if self.remove_entry(&entry.path, &entry.metadata, false) {
+                    keep(&mut pending);
+                }
+                continue;
+            }
+            let mut confirmed = false;
+            if let Some(protection) = self.protection(&entry.path, &entry.metadata) {
+                // An empty directory is removed right away, rather than descended into.
+                confirmed = is_empty_dir(&entry.path);
+                let action = if confirmed { "remove" } else { "descend into" };
+                let mut prompter = Prompter::new("rm", &mut *self.answers, self.reporter.stderr());
+                if !prompt_yes!(
+                    prompter,
+                    "{action} {protection}directory '{}'?",
+                    entry.path.display()
+                ) {
+                    walk.skip_current_dir();
+                    keep(&mut pending);
+                    continue;
+                }
+            }
+            pending.push(PendingDirectory {
+                path: entry.path,
+                metadata: entry.metadata,
+                confirmed,
+                keeps: false,
+            });
+        }
+        self.finish_directories(&mut pending, 0);
+    }
+
+    /// Remove the pending directories which are at the given depth or deeper, since their contents are
+    /// all removed, unless they keep a file.
+    fn finish_directories(&mut self, pending: &mut Vec<PendingDirectory>, depth: usize) {
+        while pending.len() > depth {
+            let directory = pending.pop().unwrap();
+            if !directory.keeps
+                && self.remove_entry(&directory.path, &directory.metadata, directory.confirmed)
+            {
+                keep(pending);
+            }
+        }
+    }
+
+    /// Remove a file or an empty directory, asking first if needed and not `confirmed` yet, and return
+    /// whether it is kept.
+    fn remove_entry(&mut self, path: &Path, metadata: &Metadata, confirmed: bool) -> bool {
+        if let Some(protection) = self.protection(path, metadata).filter(|_| !confirmed) {
+            let mut prompter = Prompter::new("rm", &mut *self.answers, self.reporter.stderr());
+            if !prompt_yes!(
+                prompter,
+                "remove {protection}{} '{}'?",
+                file_kind(metadata),
+                path.display()
+            ) {
+                return true;
+            }
+        }
+
+        let result = match metadata.is_dir() {
+            true => fs::remove_dir(path),
+            false => fs::remove_file(path),
+        };
+        match result {
+            Ok(()) => {
+                if self.options.verbose {
+                    let kind = if metadata.is_dir() { "directory " } else { "" };
+                    let _ = writeln!(self.stdout, "removed {kind}'{}'", path.display());
+                }
+                false
+            }
+            Err(error) => {
+                self.cannot_remove(path, &error);
+                !is_missing(&error)
+            }
+        }
+    }
+
+    /// Check whether to ask before removing a file, and how to describe it in the question: as
+    /// `write-protected ` or not.
+    fn protection(&self, path: &Path, metadata: &Metadata) -> Option<&'static str> {
+        let prompt = self.options.prompt;
+        let write_protected = !self.options.force
+            && prompt.prompt_write_protected(self.options.stdin_is_terminal)
+            && !metadata.is_symlink()
+            && is_write_protected(path, metadata);
+        match (write_protected, prompt.prompt_always()) {
+            (true, _) => Some("write-protected "),
+            (false, true) => Some(""),
+            (false, false) => None,
+        }
+    }
+
+    /// Report the failure to remove a file, unless it does not exist with `-f`.
+    fn cannot_remove(&mut self, path: &Path, error: &io::Error) {
+        if self.options.force && is_missing(error) {
+            return;
+        }
+        self.reporter.error(format_args!(
+            "cannot remove '{}': {}",
+            path.display(),
+            io_error_message(error)
+        ));
+    }
+}
+
+/// Mark the pending directories as keeping a file, since one of their contents is kept.
+fn keep(pending: &mut [PendingDirectory]) {
+    for directory in pending {
+        directory.keeps = true;
+    }
+}
+
+/// Check whether a directory is empty. A directory which cannot be read is not.
+fn is_empty_dir(path: &Path) -> bool {
+    fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_none())
+}
+
+/// Check whether the user cannot write to a file, which is then confirmed before being removed.
+#[cfg(unix)]
+fn is_write_protected(path: &Path, _metadata: &Metadata) -> bool {
+    let Ok(path) = c_path(path) else {
+        return false;
+    };
+    // SAFETY: the path is a valid C string.
+    let result =
+        unsafe { libc::faccessat(libc::AT_FDCWD, path.as_ptr(), libc::W_OK, libc::AT_EACCESS) };
+    result != 0 && io::Error::last_os_error().raw_os_error() == Some(libc::EACCES)
+}
+
+/// Check whether the user cannot write to a file, which is then confirmed before being removed.
+#[cfg(not(unix))]
+fn is_write_protected(_path: &Path, metadata: &Metadata) -> bool {
+    metadata.permissions().readonly()
+}
+
+/// Check whether an error means that a file does not exist, including when a directory in its path is a
+/// file.
+fn is_missing(error: &io::Error) -> bool {
+    matches!(error.kind(), ErrorKind::NotFound | ErrorKind::NotADirectory)
+}
+
+/// Check that a directory can be removed recursively: it is neither `.`, `..`, nor the root directory.
+fn check_root(path: &Path) -> UResult<()> {
+    if matches!(base_name(path).to_str(), Some("." | "..")) {
+        let message = format!(
+            "refusing to remove '.' or '..' directory: skipping '{}'",
+            path.display()
+        );
+        return Err(UError::new(EXIT_FAILURE, message));
+    }
+
+    let root = Path::new("/");
+    if let (Ok(metadata), Ok(root_metadata)) = (fs::metadata(path), fs::metadata(root)) {
+        if same_file(&metadata, &root_metadata, path, root) {
+            let same = match path == root {
+                true => String::new(),
+                false => " (same as '/')".to_owned(),
+            };
+            let message = format!(
+                "it is dangerous to operate recursively on '{}'{same}",
+                path.display()
+            );
+            return Err(UError::new(EXIT_FAILURE, message));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::{run_util_stderr, run_util_stdin};
+
+    #[test]
+    fn test_missing() {
+        let directory = tempfile::tempdir().unwrap();
+        let missing = directory.path().join("missing");
+        let path = missing.to_str().unwrap();
+
+        assert_eq!(
+            run_util_stderr(run, &["rm", path]),
+            (
+                EXIT_FAILURE,
+                format!("rm: cannot remove '{path}': No such file or directory\n")
+            )
+        );
+        assert_eq!(
+            run_util_stderr(run, &["rm", "-f", path]),
+            (0, String::new())
+        );
+        assert_eq!(
+            run_util_stderr(run, &["rm", "-rf", path]),
+            (0, String::new())
+        );
+        assert_eq!(
+            run_util_stderr(run, &["rm", "-f", &format!("{path}/file")]),
+            (0, String::new())
+        );
+
+        assert_eq!(run_util_stderr(run, &["rm", "-f"]), (0, String::new()));
+        assert_eq!(
+            run_util_stderr(run, &["rm"]),
+            (
+                EXIT_FAILURE,
+                "rm: missing operand\nTry 'rm --help' for more information.\n".to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn test_directory() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir_all(root.join("d/e")).unwrap();
+        fs::write(root.join("d/e/f"), "").unwrap();
+        fs::write(root.join("g"), "").unwrap();
+        let path = |name| root.join(name).display().to_string();
+
+        // The other operands are still removed.
+        assert_eq!(
+            run_util_stderr(run, &["rm", &path("d"), &path("g")]),
+            (
+                EXIT_FAILURE,
+                format!("rm: cannot remove '{}': Is a directory\n", path("d"))
+            )
+        );
+        assert!(root.join("d/e/f").exists() && !root.join("g").exists());
+
+        assert_eq!(
+            run_util_stderr(run, &["rm", "-R", &path("d")]),
+            (0, String::new())
+        );
+        assert!(!root.join("d").exists());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_read_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir_all(root.join("d/a")).unwrap();
+        fs::create_dir_all(root.join("d/ro")).unwrap();
+        fs::create_dir_all(root.join("d/z")).unwrap();
+        for name in ["d/a/f", "d/ro/f", "d/z/f", "d/read-only"] {
+            fs::write(root.join(name), "").unwrap();
+        }
+        fs::set_permissions(root.join("d/read-only"), fs::Permissions::from_mode(0o444)).unwrap();
+        fs::set_permissions(root.join("d/ro"), fs::Permissions::from_mode(0o555)).unwrap();
+        let path = |name| root.join(name).display().to_string();
+
+        let (code, stderr) = run_util_stderr(run, &["rm", "-r", &path("d")]);
+        // The superuser can remove the entries of the read-only directory.
+        if unsafe { libc::geteuid() } == 0 {
+            assert_eq!((code, stderr.as_str()), (0, ""));
+            assert!(!root.join("d").exists());
+            return;
+        }
+
+        // The read-only file is removed, and the directories containing the kept file are kept quietly.
+        assert_eq!(code, EXIT_FAILURE);
+        assert_eq!(
+            stderr,
+            format!(
+                "rm: cannot remove '{}': Permission denied\n",
+                path("d/ro/f")
+            )
+        );
+        assert!(root.join("d/ro/f").exists());
+        for name in ["d/a", "d/read-only", "d/z"] {
+            assert!(!root.join(name).exists(), "{name}");
+        }
+        fs::set_permissions(root.join("d/ro"), fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_symlink_to_directory() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir(root.join("d")).unwrap();
+        fs::write(root.join("d/f"), "").unwrap();
+        std::os::unix::fs::symlink("d", root.join("l")).unwrap();
+        std::os::unix::fs::symlink("d", root.join("m")).unwrap();
+        let path = |name| root.join(name).display().to_string();
+
+        for (args, link) in [(&["rm"][..], "l"), (&["rm", "-r"], "m")] {
+            let mut command_line = args.to_vec();
+            let link_path = path(link);
+            command_line.push(&link_path);
+            assert_eq!(run_util_stderr(run, &command_line), (0, String::new()));
+            assert!(fs::symlink_metadata(root.join(link)).is_err());
+            assert!(root.join("d/f").exists());
+        }
+    }
+
+    #[test]
+    fn test_refusals() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir(root.join("d")).unwrap();
+        let path = |name| root.join(name).display().to_string();
+
+        for name in ["d/.", "d/..", "d/./"] {
+            assert_eq!(
+                run_util_stderr(run, &["rm", "-r", &path(name)]),
+                (
+                    EXIT_FAILURE,
+                    format!(
+                        "rm: refusing to remove '.' or '..' directory: skipping '{}'\n",
+                        path(name)
+                    )
+                )
+            );
+        }
+        assert!(root.join("d").exists());
+        assert_eq!(
+            run_util_stderr(run, &["rm", "-rf", "."]),
+            (
+                EXIT_FAILURE,
+                "rm: refusing to remove '.' or '..' directory: skipping '.'\n".to_owned()
+            )
+        );
+
+        // The root directory is checked without running rm on it, in case the check fails.
+        let error = check_root(Path::new("/")).unwrap_err();
+        let message = "it is dangerous to operate recursively on '/'";
+        assert_eq!(error, UError::new(EXIT_FAILURE, message));
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink("/", root.join("root")).unwrap();
+            let error = check_root(&root.join("root/")).unwrap_err();
+            let message = format!(
+                "it is dangerous to operate recursively on '{}' (same as '/')",
+                root.join("root/").display()
+            );
+            assert_eq!(error, UError::new(EXIT_FAILURE, message));
+        }
+        check_root(&root.join("d")).unwrap();
+    }
+
+    #[test]
+    fn test_interactive() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir_all(root.join("t/d")).unwrap();
+        fs::create_dir(root.join("t/n")).unwrap();
+        fs::write(root.join("t/a"), "a").unwrap();
+        fs::write(root.join("t/d/e"), "").unwrap();
+        let path = |name| root.join(name).display().to_string();
+
+        // Declining to descend into t/d keeps it, and t, without another question.
+        let (code, stdout, stderr) =
+            run_util_stdin(run, &["rm", "-ri", &path("t")], "y\ny\nn\ny\n");
+        assert_eq!((code, stdout.as_str()), (0, ""));
+        assert_eq!(
+            stderr,
+            format!(
+                "rm: descend into directory '{}'? rm: remove regular file '{}'? \
+                 rm: descend into directory '{}'? rm: remove directory '{}'? ",
+                path("t"),
+                path("t/a"),
+                path("t/d"),
+                path("t/n")
+            )
+        );
+        assert!(root.join("t/d/e").exists());
+        assert!(!root.join("t/a").exists() && !root.join("t/n").exists());
+
+        // The end of the answers declines the last question.
+        let (code, _, stderr) = run_util_stdin(
+            run,
+            &["rm", "--interactive", "-r", &path("t")],
+            "y\ny\ny\ny\n",
+        );
+        assert_eq!(code, 0);
+        assert_eq!(
+            stderr,
+            format!(
+                "rm: descend into directory '{}'? rm: descend into directory '{}'? \
+                 rm: remove regular empty file '{}'? rm: remove directory '{}'? \
+                 rm: remove directory '{}'? ",
+                path("t"),
+                path("t/d"),
+                path("t/d/e"),
+                path("t/d"),
+                path("t")
+            )
+        );
+        assert!(root.join("t").exists() && !root.join("t/d").exists());
+        assert_eq!(run_util_stdin(run, &["rm", "-di", &path("t")], "y\n").0, 0);
+        assert!(!root.join("t").exists());
+    }
+
+    #[test]
+    fn test_interactive_options() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        let path = |name| root.join(name).display().to_string();
+
+        // The last of -f, -i, -I, and --interactive wins, but -f still ignores the missing files.
+        for (args, asked) in [
+            (&["-i", "-f"][..], false),
+            (&["-f", "-i"], true),
+            (&["-i", "--interactive=never"], false),
+            (&["--interactive=no", "--interactive"], true),
+            (&["-I", "--interactive=al"], true),
+            (&["-i", "-I"], false),
+        ] {
+            fs::write(root.join("f"), "").unwrap();
+            let mut command_line = vec!["rm"];
+            command_line.extend(args);
+            let (f, missing) = (path("f"), path("missing"));
+            command_line.extend([f.as_str(), missing.as_str()]);
+            let (code, _, stderr) = run_util_stdin(run, &command_line, "y\n");
+            let missing_error =
+                format!("rm: cannot remove '{missing}': No such file or directory\n");
+            let question = format!("rm: remove regular empty file '{f}'? ");
+            let ignores_missing = args.contains(&"-f");
+            let expected = match (asked, ignores_missing) {
+                (true, true) => question,
+                (true, false) => question + &missing_error,
+                (false, true) => String::new(),
+                (false, false) => missing_error,
+            };
+            assert_eq!(stderr, expected, "{args:?}");
+            assert_eq!(code, if ignores_missing { 0 } else { EXIT_FAILURE });
+            assert!(!root.join("f").exists());
+        }
+
+        assert_eq!(
+            run_util_stderr(run, &["rm", "--interactive=sometimes", &path("f")]),
+            (
+                EXIT_FAILURE,
+                "rm: invalid argument 'sometimes' for '--interactive'\n\
+                 Valid arguments are:\n  \
+                 - 'never', 'no', 'none'\n  \
+                 - 'once'\n  \
+                 - 'always', 'yes'\n"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn test_once() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        let path = |name| root.join(name).display().to_string();
+        let names = ["1", "2", "3", "4"];
+        for name in names {
+            fs::write(root.join(name), "").unwrap();
+        }
+        let mut command_line = vec!["rm".to_owned(), "-I".to_owned()];
+        command_line.extend(names.map(path));
+        let command_line: Vec<&str> = command_line.iter().map(String::as_str).collect();
+
+        // Declining keeps all of the files, without an error.
+        assert_eq!(
+            run_util_stdin(run, &command_line, "n\n"),
+            (0, String::new(), "rm: remove 4 arguments? ".to_owned())
+        );
+        assert!(names.iter().all(|name| root.join(name).exists()));
+
+        // Three files are removed without a question.
+        assert_eq!(
+            run_util_stdin(run, &command_line[..5], ""),
+            (0, String::new(), String::new())
+        );
+        assert!(!root.join("3").exists() && root.join("4").exists());
+
+        // A recursive removal is always asked about.
+        assert_eq!(
+            run_util_stdin(run, &["rm", "-rI", &path("4")], "y\n"),
+            (
+                0,
+                String::new(),
+                "rm: remove 1 argument recursively? ".to_owned()
+            )
+        );
+        assert!(!root.join("4").exists());
+    }
+
+    #[test]
+    fn test_empty_directories() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir_all(root.join("n/e")).unwrap();
+        fs::create_dir(root.join("e")).unwrap();
+        let path = |name| root.join(name).display().to_string();
+
+        // The empty directory is removed, but not the other one, which is not asked about either.
+        let (code, stdout, stderr) =
+            run_util_stdin(run, &["rm", "-div", &path("e"), &path("n")], "y\n");
+        assert_eq!(code, EXIT_FAILURE);
+        assert_eq!(stdout, format!("removed directory '{}'\n", path("e")));
+        assert_eq!(
+            stderr,
+            format!(
+                "rm: remove directory '{}'? rm: cannot remove '{}': Directory not empty\n",
+                path("e"),
+                path("n")
+            )
+        );
+        assert!(!root.join("e").exists() && root.join("n/e").exists());
+    }
+
+    #[test]
+    fn test_verbose() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir_all(root.join("t/d")).unwrap();
+        fs::write(root.join("t/a"), "").unwrap();
+        fs::write(root.join("t/d/b"), "").unwrap();
+        fs::write(root.join("t/z"), "").unwrap();
+        let path = |name| root.join(name).display().to_string();
+
+        // Each directory is removed after its contents.
+        let (code, stdout, stderr) = run_util_stdin(run, &["rm", "-rv", &path("t")], "");
+        assert_eq!((code, stderr.as_str()), (0, ""));
+        let expected: String = [
+            ("", "t/a"),
+            ("", "t/d/b"),
+            ("directory ", "t/d"),
+            ("", "t/z"),
+            ("directory ", "t"),
+        ]
+        .into_iter()
+        .map(|(kind, name)| format!("removed {kind}'{}'\n", path(name)))
+        .collect();
+        assert_eq!(stdout, expected);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_write_protected() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        let file = root.join("f");
+        let remove = |options: RmOptions| {
+            let _ = fs::remove_file(&file);
+            fs::write(&file, "").unwrap();
+            fs::set_permissions(&file, fs::Permissions::from_mode(0o444)).unwrap();
+            let mut stderr = Vec::new();
+            let mut reporter = Reporter::new("rm", &mut stderr);
+            let (mut answers, mut stdout) = (&b"n\n"[..], Vec::new());
+            Remover::new(options, &mut reporter, &mut answers, &mut stdout).remove(&file);
+            String::from_utf8(stderr).unwrap()
+        };
+        let terminal = RmOptions {
+            stdin_is_terminal: true,
+            ..RmOptions::default()
+        };
+
+        // The superuser can write to any file, which is not asked about.
+        if unsafe { libc::geteuid() } == 0 {
+            assert_eq!(remove(terminal), "");
+            assert!(!file.exists());
+            return;
+        }
+
+        let question = format!(
+            "rm: remove write-protected regular empty file '{}'? ",
+            file.display()
+        );
+        assert_eq!(remove(terminal), question);
+        assert!(file.exists());
+        let always = RmOptions {
+            prompt: PromptPolicy::Always,
+            ..RmOptions::default()
+        };
+        assert_eq!(remove(always), question);
+        for options in [
+            RmOptions::default(),
+            RmOptions {
+                force: true,
+                prompt: PromptPolicy::Force,
+                ..terminal
+            },
+        ] {
+            assert_eq!(remove(options), "");
+            assert!(!file.exists());
+        }
+    }
+}
@@ -0,0 +1,376 @@
+//! Remove empty directories.
+//!
+//! With `parents`, the directories which an operand names as its ancestors are removed after it, until
+//! one of them cannot be:
+//!
+//! ```
+//! use rust_coreutils::rmdir::{remove_directory, RmdirOptions};
+//!
+//! let directory = tempfile::tempdir().unwrap();
+//! let a = directory.path().join("a");
+//! std::fs::create_dir_all(a.join("b/c")).unwrap();
+//! std::fs::create_dir(a.join("kept")).unwrap();
+//!
+//! let options = RmdirOptions {
+//!     parents: true,
+//!     ..RmdirOptions::default()
+//! };
+//! let error = remove_directory(&a.join("b/c"), &options, &mut Vec::new()).unwrap_err();
+//! let message = format!("failed to remove directory '{}': Directory not empty", a.display());
+//! assert_eq!(error.to_string(), message);
+//! assert!(!a.join("b").exists());
+//! ```
+
+use crate::common::error::{io_error_message, Reporter, UError, UResult, EXIT_FAILURE};
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use clap::Parser;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, ErrorKind, Read, Write};
+use std::path::Path;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "rmdir",
+    author,
+    version,
+    about = "Remove the DIRECTORY(ies), if they are empty.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Do not fail on the directories which are not empty.
+    #[arg(long, default_value_t = false)]
+    ignore_fail_on_non_empty: bool,
+
+    /// Remove the DIRECTORY and then its ancestors, so that `rmdir -p a/b/c` is like `rmdir a/b/c a/b a`.
+    #[arg(short, long, default_value_t = false)]
+    parents: bool,
+
+    /// Print a message for each directory to remove.
+    #[arg(short, long, default_value_t = false)]
+    verbose: bool,
+
+    /// The directories to remove.
+    #[arg(value_name = "DIRECTORY")]
+    directories: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "rmdir";
+}
+
+/// The description of rmdir for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run rmdir with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run rmdir with the given command line, including the program name, on the given streams, and return
+/// the exit code.
+pub fn run(
+    args: &[OsString],
+    _stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(args.iter().cloned(), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let mut reporter = Reporter::new("rmdir", stderr);
+    let options = RmdirOptions {
+        ignore_fail_on_non_empty: args.ignore_fail_on_non_empty,
+        parents: args.parents,
+        verbose: args.verbose,
+    };
+    let result = rmdir(&args.directories, &options, stdout, &mut reporter);
+    reporter.finish(result)
+}
+
+/// The options of rmdir.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RmdirOptions {
+    /// Ignore the failures to remove the directories which are not empty (`--ignore-fail-on-non-empty`).
+    pub ignore_fail_on_non_empty: bool,
+    /// Remove the ancestors named in the operands too (`-p`).
+    pub parents: bool,
+    /// Print each directory before removing it (`-v`).
+    pub verbose: bool,
+}
+
+/// Remove the directories, reporting the failures and continuing with the next one.
+pub fn rmdir<W: Write>(
+    operands: &[OsString],
+    options: &RmdirOptions,
+    stdout: &mut dyn Write,
+    reporter: &mut Reporter<W>,
+) -> UResult<()> {
+    if operands.is_empty() {
+        return Err(UError::usage(EXIT_FAILURE, "missing operand"));
+    }
+    for operand in operands {
+        if let Err(error) = remove_directory(Path::new(operand), options, stdout) {
+            reporter.error(format_args!("{error}"));
+        }
+    }
+    Ok(())
+}
+
+/// Remove an empty directory, and then its ancestors in the path with `options.parents`, printing each
+/// one to `stdout` first with `options.verbose`.
+///
+/// A symbolic link is never followed, even with a trailing slash. The ancestors are removed until one
+/// fails, which is an error unless it is not empty with `options.ignore_fail_on_non_empty`.
+pub fn remove_directory(
+    path: &Path,
+    options: &RmdirOptions,
+    stdout: &mut dyn Write,
+) -> UResult<()> {
+    if let Err(error) = remove(path, options, stdout) {
+        if ignorable(&error, path, options) {
+            return Ok(());
+        }
+        let problem = match is_symlink_to_directory(&error, path) {
+            true => "Symbolic link not followed".to_owned(),
+            false => io_error_message(&error),
+        };
+        let message = format!("failed to remove '{}': {problem}", path.display());
+        return Err(UError::new(EXIT_FAILURE, message));
+    }
+    if !options.parents {
+        return Ok(());
+    }
+
+    for parent in path
+        .ancestors()
+        .skip(1)
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        if let Err(error) = remove(parent, options, stdout) {
+            if ignorable(&error, parent, options) {
+                return Ok(());
+            }
+            let message = format!(
+                "failed to remove directory '{}': {}",
+                parent.display(),
+                io_error_message(&error)
+            );
+            return Err(UError::new(EXIT_FAILURE, message));
+        }
+    }
+    Ok(())
+}
+
+/// Print a directory with `options.verbose`, and remove it.
+fn remove(path: &Path, options: &RmdirOptions, stdout: &mut dyn Write) -> io::Result<()> {
+    if options.verbose {
+        let _ = writeln!(stdout, "rmdir: removing directory, '{}'", path.display());
+    }
+    fs::remove_dir(path)
+}
+
+/// Check whether a failure to remove a directory is ignored, because it is not empty with
+/// `options.ignore_fail_on_non_empty`.
+///
+/// Some systems check the permissions before the contents, so when the directory cannot be removed, it is
+/// read to find out whether it is empty.
+fn ignorable(error: &io::Error, path: &Path, options: &RmdirOptions) -> bool {
+    options.ignore_fail_on_non_empty
+        && match error.kind() {
+            ErrorKind::DirectoryNotEmpty | ErrorKind::AlreadyExists => true,
+            ErrorKind::PermissionDenied
+            | ErrorKind::ReadOnlyFilesystem
+            | ErrorKind::ResourceBusy => {
+                fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_some())
+            }
+            _ => false,
+        }
+}
+
+/// Check whether a directory could not be removed because it is a symbolic link to a directory, with a
+/// trailing slash which would otherwise resolve it.
+fn is_symlink_to_directory(error: &io::Error, path: &Path) -> bool {
+    let has_trailing_slash = path
+        .as_os_str()
+        .as_encoded_bytes()
+        .last()
+        .is_some_and(|&byte| std::path::is_separator(char::from(byte)));
+    if error.kind() != ErrorKind::NotADirectory || !has_trailing_slash {
+        return false;
+    }
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return false;
+    };
+    fs::metadata(path).is_ok_and(|metadata| metadata.is_dir())
+        && fs::symlink_metadata(parent.join(name)).is_ok_and(|metadata| metadata.is_symlink())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::run_util;
+
+    #[test]
+    fn test_errors() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir_all(root.join("full/child")).unwrap();
+        fs::create_dir(root.join("empty")).unwrap();
+        fs::write(root.join("file"), "").unwrap();
+        let path = |name| root.join(name).display().to_string();
+
+        // The other operands are still removed.
+        let (code, stdout, stderr) = run_util(
+            run,
+            &[
+                "rmdir",
+                &path("full"),
+                &path("file"),
+                &path("missing"),
+                &path("empty"),
+            ],
+        );
+        assert_eq!((code, stdout.as_str()), (EXIT_FAILURE, ""));
+        assert_eq!(
+            stderr,
+            format!(
+                "rmdir: failed to remove '{}': Directory not empty\n\
+                 rmdir: failed to remove '{}': Not a directory\n\
+                 rmdir: failed to remove '{}': No such file or directory\n",
+                path("full"),
+                path("file"),
+                path("missing")
+            )
+        );
+        assert!(root.join("full/child").is_dir() && !root.join("empty").exists());
+
+        // Only the directories which are not empty are ignored.
+        let (code, _, stderr) = run_util(
+            run,
+            &[
+                "rmdir",
+                "--ignore-fail-on-non-empty",
+                &path("full"),
+                &path("missing"),
+            ],
+        );
+        assert_eq!(code, EXIT_FAILURE);
+        assert_eq!(
+            stderr,
+            format!(
+                "rmdir: failed to remove '{}': No such file or directory\n",
+                path("missing")
+            )
+        );
+        assert_eq!(
+            run_util(run, &["rmdir"]),
+            (
+                EXIT_FAILURE,
+                String::new(),
+                "rmdir: missing operand\nTry 'rmdir --help' for more information.\n".to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn test_parents() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir_all(root.join("a/b/c")).unwrap();
+        // The removal of the ancestors stops at the temporary directory, which is not empty.
+        fs::write(root.join("kept"), "").unwrap();
+        let path = |name| root.join(name).display().to_string();
+
+        let (code, stdout, stderr) = run_util(run, &["rmdir", "-pv", &path("a/b/c")]);
+        assert_eq!(code, EXIT_FAILURE);
+        assert_eq!(
+            stdout,
+            format!(
+                "rmdir: removing directory, '{}'\n\
+                 rmdir: removing directory, '{}'\n\
+                 rmdir: removing directory, '{}'\n\
+                 rmdir: removing directory, '{}'\n",
+                path("a/b/c"),
+                path("a/b"),
+                path("a"),
+                root.display()
+            )
+        );
+        assert_eq!(
+            stderr,
+            format!(
+                "rmdir: failed to remove directory '{}': Directory not empty\n",
+                root.display()
+            )
+        );
+        assert!(!root.join("a").exists());
+
+        // A directory with another child stops the removal.
+        for name in ["x/y/z", "x/w"] {
+            fs::create_dir_all(root.join(name)).unwrap();
+        }
+        assert_eq!(
+            run_util(run, &["rmdir", "-p", &format!("{}//y/z/", path("x"))]),
+            (
+                EXIT_FAILURE,
+                String::new(),
+                format!(
+                    "rmdir: failed to remove directory '{}': Directory not empty\n",
+                    path("x")
+                )
+            )
+        );
+        assert!(!root.join("x/y").exists() && root.join("x/w").exists());
+
+        // The failure to remove an ancestor which is not empty is ignored, but it stops the removal.
+        fs::create_dir(root.join("x/y")).unwrap();
+        assert_eq!(
+            run_util(
+                run,
+                &[
+                    "rmdir",
+                    "-p",
+                    "--ignore-fail-on-non-empty",
+                    &path("x/y"),
+                    &path("x/w")
+                ]
+            ),
+            (0, String::new(), String::new())
+        );
+        assert!(!root.join("x").exists() && root.join("kept").exists());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_symlink_to_directory() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir(root.join("d")).unwrap();
+        std::os::unix::fs::symlink("d", root.join("l")).unwrap();
+        let path = |name| root.join(name).display().to_string();
+
+        let (code, _, stderr) = run_util(run, &["rmdir", &path("l"), &format!("{}/", path("l"))]);
+        assert_eq!(code, EXIT_FAILURE);
+        assert_eq!(
+            stderr,
+            format!(
+                "rmdir: failed to remove '{0}': Not a directory\n\
+                 rmdir: failed to remove '{0}/': Symbolic link not followed\n",
+                path("l")
+            )
+        );
+        assert!(root.join("d").is_dir() && root.join("l").is_symlink());
+    }
+}
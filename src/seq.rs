@@ -0,0 +1,991 @@
+//! Print a sequence of numbers.
+//!
+//! The operands are parsed with [`Sequence::parse`], and the sequence is printed with [`seq`]:
+//!
+//! ```
+//! use rust_coreutils::seq::{seq, SeqOptions, Sequence};
+//!
+//! let sequence = Sequence::parse(&[b"10", b"-3", b"1"]).unwrap();
+//! let options = SeqOptions {
+//!     separator: b", ".to_vec(),
+//!     ..SeqOptions::default()
+//! };
+//! let mut stdout = Vec::new();
+//! seq(&sequence, &options, &mut stdout).unwrap();
+//! assert_eq!(stdout, b"10, 7, 4, 1\n");
+//! ```
+
+use crate::common::error::{Reporter, UError, UResult, EXIT_FAILURE};
+use crate::common::format::{parse_float, Count, Spec};
+use crate::common::input::BUFFER_SIZE;
+use crate::common::os::os_str_bytes;
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use clap::Parser;
+use std::ffi::OsString;
+use std::io::{self, BufWriter, Read, Write};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "seq",
+    author,
+    version,
+    about = "Print the numbers from FIRST to LAST, counting by INCREMENT.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Print the numbers with FORMAT, which has a single floating point conversion of printf, like %.2f.
+    #[arg(short, long, value_name = "FORMAT", allow_hyphen_values = true)]
+    format: Option<OsString>,
+
+    /// Separate the numbers with STRING instead of a newline. The output still ends with a newline.
+    #[arg(short, long, value_name = "STRING", allow_hyphen_values = true)]
+    separator: Option<OsString>,
+
+    /// Pad the numbers with leading zeros to the same width.
+    #[arg(short = 'w', long, default_value_t = false)]
+    equal_width: bool,
+
+    /// FIRST and INCREMENT are 1 when they are omitted, and the sequence is empty if LAST is before FIRST.
+    /// The numbers may be integers, decimals with an exponent like 1.5e3, or hexadecimal with 0x. Without
+    /// FORMAT, they are printed with as many decimals as FIRST or INCREMENT.
+    #[arg(value_name = "[FIRST [INCREMENT]] LAST")]
+    operands: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "seq";
+}
+
+/// The description of seq for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run seq with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run seq with the given command line, including the program name, on the given streams, and return the
+/// exit code. Standard input is not read.
+pub fn run(
+    args: &[OsString],
+    _stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(split_options(args), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let reporter = Reporter::new("seq", stderr);
+    let (sequence, options) = match parse(&args) {
+        Ok(parsed) => parsed,
+        Err(error) => return reporter.finish(Err(error)),
+    };
+    let result = seq(&sequence, &options, stdout);
+    reporter.finish(result)
+}
+
+/// Separate the operands from the options with `--`, like GNU: the options end at the first operand, and an
+/// argument like `-1` or `-.5` is a negative number rather than options.
+fn split_options(args: &[OsString]) -> Vec<OsString> {
+    let mut args = args.to_vec();
+    let mut index = 1;
+    while let Some(argument) = args.get(index) {
+        match &os_str_bytes(argument)[..] {
+            b"--" => return args,
+            [b'-', b'-', long @ ..] => {
+                // The value of a long option may be the next argument.
+                if long == b"format" || long == b"separator" {
+                    index += 1;
+                }
+            }
+            [b'-', cluster @ ..]
+                if cluster
+                    .first()
+                    .is_some_and(|&byte| byte != b'.' && !byte.is_ascii_digit()) =>
+            {
+                // The value of -f or -s is the rest of the cluster, or the next argument.
+                if let Some(position) = cluster
+                    .iter()
+                    .position(|&byte| byte == b'f' || byte == b's')
+                {
+                    if position + 1 == cluster.len() {
+                        index += 1;
+                    }
+                }
+            }
+            _ => break,
+        }
+        index += 1;
+    }
+    args.insert(index.min(args.len()), OsString::from("--"));
+    args
+}
+
+/// Get the sequence and the options of the command line, checked in the order GNU does.
+fn parse(args: &Args) -> UResult<(Sequence, SeqOptions)> {
+    let operands: Vec<_> = args
+        .operands
+        .iter()
+        .map(|operand| os_str_bytes(operand))
+        .collect();
+    let operands: Vec<&[u8]> = operands.iter().map(|operand| &operand[..]).collect();
+    if operands.is_empty() {
+        return Err(UError::usage(EXIT_FAILURE, "missing operand"));
+    }
+    let format = match &args.format {
+        Some(format) => Some(
+            Format::parse(&os_str_bytes(format))
+                .map_err(|message| UError::new(EXIT_FAILURE, message))?,
+        ),
+        None => None,
+    };
+    if format.is_some() && args.equal_width {
+        return Err(UError::usage(
+            EXIT_FAILURE,
+            "format string may not be specified when printing equal width strings",
+        ));
+    }
+    let options = SeqOptions {
+        format,
+        separator: args.separator.as_deref().map_or_else(
+            || b"\n".to_vec(),
+            |separator| os_str_bytes(separator).into_owned(),
+        ),
+        equal_width: args.equal_width,
+    };
+    let sequence =
+        Sequence::parse(&operands).map_err(|message| UError::usage(EXIT_FAILURE, message))?;
+    Ok((sequence, options))
+}
+
+/// A number of the operands.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Number {
+    /// An integer, which is counted exactly.
+    Integer(i128),
+    /// Any other number, like `1.5`, `1e3`, or an integer which is too large.
+    Float(f64),
+}
+
+impl Number {
+    /// Parse an operand, which may start with spaces, or get the GNU message if it is not a number.
+    pub fn parse(operand: &[u8]) -> Result<Number, String> {
+        let invalid = |reason: &str| {
+            Err(format!(
+                "invalid {reason} argument: '{}'",
+                String::from_utf8_lossy(operand)
+            ))
+        };
+        let start = operand
+            .iter()
+            .position(|byte| !b" \t\n\x0b\x0c\r".contains(byte))
+            .unwrap_or(operand.len());
+        let digits = match &operand[start..] {
+            [b'+' | b'-', digits @ ..] => digits,
+            digits => digits,
+        };
+        if !digits.is_empty() && digits.iter().all(u8::is_ascii_digit) {
+            let integer = std::str::from_utf8(&operand[start..]).expect("ASCII digits");
+            if let Ok(integer) = integer.parse() {
+                return Ok(Number::Integer(integer));
+            }
+        }
+        match parse_float(operand) {
+            _ if start == operand.len() => invalid("floating point"),
+            (value, None, _) if value.is_nan() => invalid("'not-a-number'"),
+            (value, None, _) => Ok(Number::Float(value)),
+            (_, Some(_), _) => invalid("floating point"),
+        }
+    }
+
+    fn is_zero(self) -> bool {
+        match self {
+            Number::Integer(value) => value == 0,
+            Number::Float(value) => value == 0.0,
+        }
+    }
+
+    fn float(self) -> f64 {
+        match self {
+            Number::Integer(value) => value as f64,
+            Number::Float(value) => value,
+        }
+    }
+}
+
+/// An operand, with how it is written, from which the default format of the numbers is inferred.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Operand {
+    pub number: Number,
+    /// The number of digits after the decimal point, or `None` if it is not known, like for `0x1.8`.
+    pub precision: Option<usize>,
+    /// The number of bytes of the number written without an exponent, or 0 if it is not known.
+    pub width: usize,
+}
+
+impl Operand {
+    /// The value of FIRST and INCREMENT when they are omitted.
+    const ONE: Operand = Operand {
+        number: Number::Integer(1),
+        precision: Some(0),
+        width: 1,
+    };
+
+    /// Parse an operand, or get the GNU message if it is not a number.
+    ///
+    /// Like in GNU, the precision and the width count the digits of the operand as it is written, so that
+    /// `1.50` has a precision of 2, and `1.5e1` is written `15` with a precision of 0.
+    pub fn parse(operand: &[u8]) -> Result<Operand, String> {
+        let number = Number::parse(operand)?;
+        // The spaces and a plus sign are not printed.
+        let start = operand
+            .iter()
+            .position(|byte| !b" \t\n\x0b\x0c\r+".contains(byte))
+            .unwrap_or(operand.len());
+        let text = &operand[start..];
+        let point = text.iter().position(|&byte| byte == b'.');
+        let mut precision = (point.is_none() && !text.contains(&b'p')).then_some(0);
+        let hexadecimal = text.iter().any(|byte| byte.eq_ignore_ascii_case(&b'x'));
+        if hexadecimal || !number.float().is_finite() {
+            return Ok(Operand {
+                number,
+                precision,
+                width: 0,
+            });
+        }
+
+        let exponent = text
+            .iter()
+            .position(|byte| byte.eq_ignore_ascii_case(&b'e'));
+        let mut width = text.len() as isize;
+        let mut fraction = 0;
+        if let Some(point) = point {
+            fraction = text[point + 1..exponent.unwrap_or(text.len())].len();
+            precision = Some(fraction);
+            width += match fraction {
+                // `1.` is printed `1`.
+                0 => -1,
+                // `.5` and `-.5` are printed `0.5` and `-0.5`.
+                _ if point == 0 || !text[point - 1].is_ascii_digit() => 1,
+                _ => 0,
+            };
+        }
+        if let Some(exponent_index) = exponent {
+            let value = exponent_value(&text[exponent_index + 1..]);
+            let digits = precision.unwrap_or(0);
+            let shifted = if value < 0 {
+                digits.checked_add(value.unsigned_abs() as usize)
+            } else {
+                Some(digits - digits.min(value as usize))
+            };
+            // Like in GNU, neither the precision nor the width are known if they do not fit in an `int`.
+            let Some(shifted) = shifted.filter(|&shifted| shifted <= i32::MAX as usize) else {
+                return Ok(Operand {
+                    number,
+                    precision: None,
+                    width: 0,
+                });
+            };
+            precision = Some(shifted);
+            // The exponent is not printed, but the digits it adds are.
+            width -= (text.len() - exponent_index) as isize;
+            let shift = if value < 0 {
+                match point {
+                    // The point was not counted for `1.e-1`.
+                    Some(point) if exponent_index == point + 1 => width += 1,
+                    Some(_) => {}
+                    None => width += 1,
+                }
+                value.unsigned_abs() as usize
+            } else {
+                if point.is_some() && precision == Some(0) && width > 0 {
+                    width -= 1;
+                }
+                value as usize - fraction.min(value as usize)
+            };
+            width = width.saturating_add_unsigned(shift);
+        }
+        Ok(Operand {
+            number,
+            precision,
+            width: if width > i32::MAX as isize {
+                0
+            } else {
+                width.max(0) as usize
+            },
+        })
+    }
+}
+
+/// Get the value of the exponent after `e` like `strtol`, which is 0 without digits.
+fn exponent_value(text: &[u8]) -> i64 {
+    let (negative, digits) = match text {
+        [b'-', digits @ ..] => (true, digits),
+        [b'+', digits @ ..] => (false, digits),
+        digits => (false, digits),
+    };
+    let value =
+        digits
+            .iter()
+            .take_while(|byte| byte.is_ascii_digit())
+            .fold(0i64, |value, &digit| {
+                value
+                    .saturating_mul(10)
+                    .saturating_add(i64::from(digit - b'0'))
+            });
+    if negative {
+        -value
+    } else {
+        value
+    }
+}
+
+/// The numbers to print.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sequence {
+    pub first: Operand,
+    /// The difference between the numbers, which is not zero, and negative to count down.
+    pub increment: Operand,
+    pub last: Operand,
+}
+
+impl Sequence {
+    /// Get the sequence of the operands, `[FIRST [INCREMENT]] LAST`, checked in the order GNU does.
+    pub fn parse(operands: &[&[u8]]) -> Result<Sequence, String> {
+        let (first, increment, last) = match operands {
+            [] => return Err("missing operand".to_owned()),
+            [last] => (None, None, last),
+            [first, last] => (Some(first), None, last),
+            [first, increment, last] => (Some(first), Some(increment), last),
+            [_, _, _, extra, ..] => {
+                return Err(format!(
+                    "extra operand '{}'",
+                    String::from_utf8_lossy(extra)
+                ))
+            }
+        };
+        let first = first.map_or(Ok(Operand::ONE), |first| Operand::parse(first))?;
+        let increment = match increment {
+            Some(operand) => {
+                let increment = Operand::parse(operand)?;
+                if increment.number.is_zero() {
+                    return Err(format!(
+                        "invalid Zero increment value: '{}'",
+                        String::from_utf8_lossy(operand)
+                    ));
+                }
+                increment
+            }
+            None => Operand::ONE,
+        };
+        let last = Operand::parse(last)?;
+        Ok(Sequence {
+            first,
+            increment,
+            last,
+        })
+    }
+
+    /// Get the format of the numbers without `-f`, like GNU: as many decimals as FIRST or INCREMENT, and with
+    /// `equal_width`, padded with zeros to the width of the widest of FIRST and LAST with those decimals. If
+    /// the decimals are not known, the numbers are printed like `%g`.
+    fn default_format(&self, equal_width: bool) -> Format {
+        let float = |spec| Format {
+            before: Vec::new(),
+            spec,
+            after: Vec::new(),
+        };
+        let (Some(first), Some(increment), Some(last)) = (
+            self.first.precision,
+            self.increment.precision,
+            self.last.precision,
+        ) else {
+            return float(Spec {
+                conversion: b'g',
+                ..Spec::default()
+            });
+        };
+        let precision = first.max(increment);
+        let spec = Spec {
+            precision: Some(Count::Fixed(precision)),
+            conversion: b'f',
+            ..Spec::default()
+        };
+        if !equal_width {
+            return float(spec);
+        }
+        // The widths of FIRST and LAST with the decimals of the numbers, where a point is added or removed.
+        let point = |digits: usize| usize::from(digits == 0 && precision > 0);
+        let first_width = self.first.width + (precision - first) + point(first);
+        let mut last_width = (self.last.width + precision).saturating_sub(last) + point(last);
+        if last > 0 && precision == 0 {
+            last_width = last_width.saturating_sub(1);
+        }
+        float(Spec {
+            zero: true,
+            width: Some(Count::Fixed(first_width.max(last_width))),
+            ..spec
+        })
+    }
+}
+
+/// The format of the numbers given with `-f`, with a single floating point conversion.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Format {
+    /// The text before the conversion.
+    before: Vec<u8>,
+    spec: Spec,
+    /// The text after the conversion.
+    after: Vec<u8>,
+}
+
+impl Format {
+    /// Parse a format like `a%.2fb`, where `%%` is a percent sign, or get the GNU message if it does not
+    /// have a single `%e`, `%f`, or `%g` conversion, in either case. Unlike in GNU, `%a` is not supported.
+    pub fn parse(format: &[u8]) -> Result<Format, String> {
+        let invalid = |problem: &str| {
+            Err(format!(
+                "format '{}' {problem}",
+                String::from_utf8_lossy(format)
+            ))
+        };
+        let mut before = Vec::new();
+        let Some(start) = copy_text(format, &mut before) else {
+            return invalid("has no % directive");
+        };
+        let skip = |index: usize, bytes: &[u8]| {
+            index
+                + format[index..]
+                    .iter()
+                    .take_while(|byte| bytes.contains(byte))
+                    .count()
+        };
+        let mut index = skip(start + 1, b"-+#0 '");
+        index = skip(index, b"0123456789");
+        if format.get(index) == Some(&b'.') {
+            index = skip(index + 1, b"0123456789");
+        }
+        if format.get(index) == Some(&b'L') {
+            index += 1;
+        }
+        match format.get(index) {
+            None => return invalid("ends in %"),
+            Some(conversion) if !b"efgEFG".contains(conversion) => {
+                return invalid(&format!(
+                    "has unknown %{} directive",
+                    char::from(*conversion)
+                ));
+            }
+            Some(_) => {}
+        }
+        // There is no grouping of the digits in the C locale.
+        let directive: Vec<u8> = format[start..=index]
+            .iter()
+            .copied()
+            .filter(|&byte| byte != b'\'')
+            .collect();
+        let (spec, _) = Spec::parse(&directive)?;
+        let mut after = Vec::new();
+        if copy_text(&format[index + 1..], &mut after).is_some() {
+            return invalid("has too many % directives");
+        }
+        Ok(Format {
+            before,
+            spec,
+            after,
+        })
+    }
+
+    /// Print a number with the format into `output`.
+    fn write<W: Write>(&self, value: f64, output: &mut W) -> io::Result<()> {
+        let fixed = |count| match count {
+            Some(Count::Fixed(count)) => Some(count),
+            _ => None,
+        };
+        output.write_all(&self.before)?;
+        self.spec.float(value, fixed(self.spec.precision)).write(
+            self.spec.left,
+            fixed(self.spec.width).unwrap_or(0),
+            output,
+        )?;
+        output.write_all(&self.after)
+    }
+}
+
+/// Copy the text of a format up to its first conversion, where `%%` is a percent sign, and get the index of
+/// the conversion, if there is one.
+fn copy_text(format: &[u8], output: &mut Vec<u8>) -> Option<usize> {
+    let mut index = 0;
+    while index < format.len() {
+        match (format[index], format.get(index + 1)) {
+            (b'%', Some(b'%')) => {
+                output.push(b'%');
+                index += 2;
+            }
+            (b'%', _) => return Some(index),
+            (byte, _) => {
+                output.push(byte);
+                index += 1;
+            }
+        }
+    }
+    None
+}
+
+/// How to print the numbers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SeqOptions {
+    /// The format of the numbers, instead of the one inferred from the operands.
+    pub format: Option<Format>,
+    /// What goes between the numbers, while the output ends with a newline.
+    pub separator: Vec<u8>,
+    /// Pad the numbers with zeros to the same width, which cannot be combined with a format.
+    pub equal_width: bool,
+}
+
+impl Default for SeqOptions {
+    fn default() -> Self {
+        SeqOptions {
+            format: None,
+            separator: b"\n".to_vec(),
+            equal_width: false,
+        }
+    }
+}
+
+/// Print the numbers of the sequence, separated by the separator and followed by a newline, or nothing if
+/// the sequence is empty.
+///
+/// The integers are counted exactly, while the other numbers are computed with floating point, from the
+/// first one and the number of increments so that the errors do not add up. A closed pipe stops without an
+/// error.
+pub fn seq(sequence: &Sequence, options: &SeqOptions, stdout: &mut dyn Write) -> UResult<()> {
+    let mut writer = BufWriter::with_capacity(BUFFER_SIZE, stdout);
+    let result = match (
+        sequence.first.number,
+        sequence.increment.number,
+        sequence.last.number,
+    ) {
+        (Number::Integer(first), Number::Integer(increment), Number::Integer(last))
+            if options.format.is_none() =>
+        {
+            // The integers are printed without decimals, so only the width is needed.
+            let width = match sequence.default_format(options.equal_width).spec.width {
+                Some(Count::Fixed(width)) => width,
+                _ => 0,
+            };
+            print_integers(
+                [first, increment, last],
+                width,
+                &options.separator,
+                &mut writer,
+            )
+        }
+        (first, increment, last) => {
+            let default;
+            let format = match &options.format {
+                Some(format) => format,
+                None => {
+                    default = sequence.default_format(options.equal_width);
+                    &default
+                }
+            };
+            let values = [first.float(), increment.float(), last.float()];
+            print_floats(values, format, &options.separator, &mut writer)
+        }
+    };
+    result.and_then(|()| writer.flush()).or_else(UError::write)
+}
+
+/// Print a sequence of integers, without going through floating point, padded with zeros to `width`.
+fn print_integers<W: Write>(
+    [first, increment, last]: [i128; 3],
+    width: usize,
+    separator: &[u8],
+    writer: &mut W,
+) -> io::Result<()> {
+    let mut buffer = [0; 41];
+    let mut value = first;
+    let mut started = false;
+    while if increment > 0 {
+        value <= last
+    } else {
+        value >= last
+    } {
+        if started {
+            writer.write_all(separator)?;
+        }
+        started = true;
+        let start = format_integer(value, &mut buffer);
+        if buffer.len() - start < width {
+            let (sign, digits) = buffer[start..].split_at(usize::from(value < 0));
+            writer.write_all(sign)?;
+            let zeros = width - (buffer.len() - start);
+            writer.write_all(&b"0".repeat(zeros))?;
+            writer.write_all(digits)?;
+        } else {
+            writer.write_all(&buffer[start..])?;
+        }
+        match value.checked_add(increment) {
+            Some(next) => value = next,
+            None => break,
+        }
+    }
+    if started {
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Format an integer at the end of `buffer`, and get the index of its first byte.
+///
+/// This is more than twice as fast as `write!`, which matters for the long sequences.
+fn format_integer(value: i128, buffer: &mut [u8; 41]) -> usize {
+    let mut index = buffer.len();
+    let mut magnitude = value.unsigned_abs();
+    // The division of 128-bit integers is slow, so only the first digits of the large ones use it.
+    while magnitude > u128::from(u64::MAX) {
+        index -= 1;
+        buffer[index] = b'0' + (magnitude % 10) as u8;
+        magnitude /= 10;
+    }
+    let mut magnitude = magnitude as u64;
+    loop {
+        index -= 1;
+        buffer[index] = b'0' + (magnitude % 10) as u8;
+        magnitude /= 10;
+        if magnitude == 0 {
+            break;
+        }
+    }
+    if value < 0 {
+        index -= 1;
+        buffer[index] = b'-';
+    }
+    index
+}
+
+/// Print a sequence of floating point numbers with a format.
+///
+/// Like GNU, the number after LAST is still printed if it is formatted like LAST and differently from the
+/// previous number, which makes up for the rounding errors: in `seq 0 0.1 0.3`, the fourth number is a bit
+/// more than 0.3, but it is printed as `0.3`.
+fn print_floats<W: Write>(
+    [first, increment, last]: [f64; 3],
+    format: &Format,
+    separator: &[u8],
+    writer: &mut W,
+) -> io::Result<()> {
+    let after_last = |value: f64| {
+        if increment < 0.0 {
+            value < last
+        } else {
+            value > last
+        }
+    };
+    if after_last(first) {
+        return Ok(());
+    }
+    let mut previous = Vec::new();
+    let mut current = Vec::new();
+    format.write(first, &mut current)?;
+    for index in 1u64.. {
+        writer.write_all(&current)?;
+        let value = first + index as f64 * increment;
+        std::mem::swap(&mut previous, &mut current);
+        current.clear();
+        format.write(value, &mut current)?;
+        if after_last(value) || value.is_nan() {
+            let (printed, trailing, _) = parse_float(&current);
+            if trailing.is_some() || printed != last || previous == current {
+                break;
+            }
+            writer.write_all(separator)?;
+            writer.write_all(&current)?;
+            break;
+        }
+        writer.write_all(separator)?;
+    }
+    writer.write_all(b"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::run_util;
+
+    /// Run seq, expecting it to succeed without messages, and return the output.
+    fn seq_text(args: &[&str]) -> String {
+        let command_line: Vec<&str> = std::iter::once("seq").chain(args.iter().copied()).collect();
+        let (code, stdout, stderr) = run_util(run, &command_line);
+        assert_eq!((code, stderr.as_str()), (0, ""), "{args:?}");
+        stdout
+    }
+
+    #[test]
+    fn test_operands() {
+        assert_eq!(seq_text(&["3"]), "1\n2\n3\n");
+        assert_eq!(seq_text(&["-2", "1"]), "-2\n-1\n0\n1\n");
+        assert_eq!(seq_text(&["1", "3", "10"]), "1\n4\n7\n10\n");
+        assert_eq!(seq_text(&["1", "3", "9"]), "1\n4\n7\n");
+        assert_eq!(seq_text(&["--", "-2", "-1"]), "-2\n-1\n");
+        assert_eq!(seq_text(&[" +2", "0x3"]), "2\n3\n");
+    }
+
+    #[test]
+    fn test_descending() {
+        assert_eq!(seq_text(&["5", "-2", "0"]), "5\n3\n1\n");
+        assert_eq!(seq_text(&["1", "-1", "-2"]), "1\n0\n-1\n-2\n");
+        assert_eq!(seq_text(&["-1", "-0.5", "-2"]), "-1.0\n-1.5\n-2.0\n");
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(seq_text(&["0"]), "");
+        assert_eq!(seq_text(&["5", "1"]), "");
+        assert_eq!(seq_text(&["1", "-1", "5"]), "");
+    }
+
+    #[test]
+    fn test_first_is_last() {
+        assert_eq!(seq_text(&["3", "3"]), "3\n");
+        assert_eq!(seq_text(&["3", "-7", "3"]), "3\n");
+        assert_eq!(seq_text(&["1", "2", "1"]), "1\n");
+        assert_eq!(seq_text(&["1e3", "1e3"]), "1000\n");
+    }
+
+    #[test]
+    fn test_large_integers() {
+        // The integers which do not fit in 64 bits are still exact.
+        assert_eq!(
+            seq_text(&["9223372036854775806", "9223372036854775808"]),
+            "9223372036854775806\n9223372036854775807\n9223372036854775808\n"
+        );
+        assert_eq!(
+            seq_text(&["18446744073709551614", "18446744073709551616"]),
+            "18446744073709551614\n18446744073709551615\n18446744073709551616\n"
+        );
+        assert_eq!(
+            seq_text(&["-9223372036854775809", "-9223372036854775807"]),
+            "-9223372036854775809\n-9223372036854775808\n-9223372036854775807\n"
+        );
+        assert_eq!(
+            seq_text(&["999999999", "1000000000"]),
+            "999999999\n1000000000\n"
+        );
+        // Counting stops instead of overflowing.
+        let max = i128::MAX.to_string();
+        assert_eq!(seq_text(&[&max, &max]), format!("{max}\n"));
+    }
+
+    #[test]
+    fn test_decimals() {
+        // The numbers have as many decimals as FIRST or INCREMENT, and no rounding errors.
+        assert_eq!(
+            seq_text(&["0", "0.1", "0.5"]),
+            "0.0\n0.1\n0.2\n0.3\n0.4\n0.5\n"
+        );
+        assert_eq!(seq_text(&["0.5", "2"]), "0.5\n1.5\n");
+        assert_eq!(seq_text(&["1", "0.25", "1.5"]), "1.00\n1.25\n1.50\n");
+        assert_eq!(seq_text(&["1.25", "0.1", "1.5"]), "1.25\n1.35\n1.45\n");
+        assert_eq!(seq_text(&["0.1", "0.3"]), "0.1\n");
+        // The number after LAST is printed if it only differs from it by a rounding error.
+        assert_eq!(seq_text(&["0", "0.000001", "0.000003"]).lines().count(), 4);
+        assert_eq!(seq_text(&["0", "0.1", "1"]).lines().last(), Some("1.0"));
+        // The exponents change the decimals, and the digits of hexadecimal numbers are not known.
+        assert_eq!(seq_text(&["0.1", "1e-1", "0.3"]), "0.1\n0.2\n0.3\n");
+        assert_eq!(seq_text(&["1.5e1", "17"]), "15\n16\n17\n");
+        assert_eq!(seq_text(&["0x1.8", "3"]), "1.5\n2.5\n");
+        assert_eq!(seq_text(&["1", "inf", "1e10"]), "1\n");
+        // Like in GNU, the decimals are not known when there are too many of them.
+        assert_eq!(seq_text(&["1e-3000000000", "1"]), "0\n1\n");
+        assert_eq!(seq_text(&["-w", "0e99999999999999999999", "1"]), "0\n1\n");
+    }
+
+    #[test]
+    fn test_equal_width() {
+        assert_eq!(seq_text(&["-w", "8", "10"]), "08\n09\n10\n");
+        assert_eq!(seq_text(&["-w", "-1", "1"]), "-1\n00\n01\n");
+        assert_eq!(seq_text(&["-w", "001", "3"]), "001\n002\n003\n");
+        assert_eq!(seq_text(&["-w", "0.5", "3"]), "0.5\n1.5\n2.5\n");
+        assert_eq!(seq_text(&["-w", "0.5", "10"]).lines().next(), Some("00.5"));
+        assert_eq!(seq_text(&["-w", "0", "-0.5", "-1"]), "00.0\n-0.5\n-1.0\n");
+        assert_eq!(seq_text(&["-w", "1", "1.50", "3"]), "1.00\n2.50\n");
+        assert_eq!(seq_text(&["-w", "1e1", "12"]), "10\n11\n12\n");
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(
+            seq_text(&["-f", "%.2f", "1", ".5", "3"]),
+            "1.00\n1.50\n2.00\n2.50\n3.00\n"
+        );
+        assert_eq!(seq_text(&["-f", "a%%b%gc%%", "1", "2"]), "a%b1c%\na%b2c%\n");
+        assert_eq!(
+            seq_text(&["-f", "%5.1e|", "1", "2"]),
+            "1.0e+00|\n2.0e+00|\n"
+        );
+        assert_eq!(seq_text(&["-f", "%-4Lg|", "1", "2"]), "1   |\n2   |\n");
+        assert_eq!(seq_text(&["-f", "%'.1f", "1"]), "1.0\n");
+        assert_eq!(
+            seq_text(&["--format=%g", "1", "0.1", "1.2"]),
+            "1\n1.1\n1.2\n"
+        );
+    }
+
+    #[test]
+    fn test_invalid_format() {
+        for (format, message) in [
+            ("x", "format 'x' has no % directive"),
+            ("%%", "format '%%' has no % directive"),
+            ("%", "format '%' ends in %"),
+            ("%.", "format '%.' ends in %"),
+            ("%d", "format '%d' has unknown %d directive"),
+            ("%*f", "format '%*f' has unknown %* directive"),
+            ("%lg", "format '%lg' has unknown %l directive"),
+            ("%f%f", "format '%f%f' has too many % directives"),
+            ("%f%", "format '%f%' has too many % directives"),
+        ] {
+            assert_eq!(
+                run_util(run, &["seq", "-f", format, "1"]),
+                (1, String::new(), format!("seq: {message}\n")),
+                "{format:?}"
+            );
+        }
+        // Like GNU, the format is checked before it is found to be combined with -w.
+        assert_eq!(
+            run_util(run, &["seq", "-w", "-f", "%g", "1"]).2,
+            "seq: format string may not be specified when printing equal width strings\n\
+             Try 'seq --help' for more information.\n"
+        );
+        assert_eq!(
+            run_util(run, &["seq", "-w", "-f", "x", "1"]).2,
+            "seq: format 'x' has no % directive\n"
+        );
+    }
+
+    #[test]
+    fn test_large_counts() {
+        let text = seq_text(&["-f", "%-100000gx", "1", "2"]);
+        assert_eq!(text.len(), 2 * 100_002);
+        assert!(text.starts_with("1 ") && text.ends_with(" x\n"));
+        let text = seq_text(&["-f", "%.100000f", "1", "2"]);
+        assert_eq!(text.lines().count(), 2);
+        assert!(text.starts_with("1.000") && text.contains("0\n2.000"));
+        assert_eq!(text.len(), 2 * 100_003);
+
+        // Like GNU, the counts are at most the largest `int` of C.
+        for (format, message) in [
+            (
+                "%99999999999999999999g",
+                "invalid field width: '99999999999999999999'",
+            ),
+            (
+                "%.99999999999999999999f",
+                "invalid precision: '99999999999999999999'",
+            ),
+            ("%.2147483648e", "invalid precision: '2147483648'"),
+        ] {
+            assert_eq!(
+                run_util(run, &["seq", "-f", format, "1"]),
+                (1, String::new(), format!("seq: {message}\n")),
+                "{format:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_separator() {
+        assert_eq!(seq_text(&["-s", ", ", "1", "3"]), "1, 2, 3\n");
+        assert_eq!(seq_text(&["-s", "", "1", "3"]), "123\n");
+        assert_eq!(seq_text(&["-s", r"\n", "1", "2"]), "1\\n2\n");
+        assert_eq!(seq_text(&["-ws:", "9", "11"]), "09:10:11\n");
+        assert_eq!(seq_text(&["-s", "-", "0", "0.5", "1"]), "0.0-0.5-1.0\n");
+        // The output is empty, without a newline, for an empty sequence.
+        assert_eq!(seq_text(&["-s", ",", "2", "1"]), "");
+    }
+
+    #[test]
+    fn test_options_and_operands() {
+        // The negative numbers are operands, and so is everything after the first operand.
+        assert_eq!(seq_text(&["-.5", "1"]), "-0.5\n0.5\n");
+        assert_eq!(seq_text(&["-s", "-1", "1", "2"]), "1-12\n");
+        assert_eq!(seq_text(&["-f%g", "-1", "0"]), "-1\n0\n");
+        assert_eq!(
+            run_util(run, &["seq", "1", "-w", "3"]).2,
+            "seq: invalid floating point argument: '-w'\nTry 'seq --help' for more information.\n"
+        );
+    }
+
+    #[test]
+    fn test_zero_increment() {
+        assert_eq!(
+            run_util(run, &["seq", "1", "0", "5"]),
+            (
+                1,
+                String::new(),
+                "seq: invalid Zero increment value: '0'\nTry 'seq --help' for more information.\n"
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            run_util(run, &["seq", "1", "-0.0", "5"]).2,
+            "seq: invalid Zero increment value: '-0.0'\nTry 'seq --help' for more information.\n"
+        );
+        // Like GNU, it is reported before an invalid LAST.
+        assert_eq!(
+            run_util(run, &["seq", "1", "0", "x"]).2,
+            "seq: invalid Zero increment value: '0'\nTry 'seq --help' for more information.\n"
+        );
+    }
+
+    #[test]
+    fn test_invalid_operands() {
+        for (command_line, message) in [
+            (&["seq"][..], "missing operand"),
+            (&["seq", "1", "2", "3", "4"], "extra operand '4'"),
+            (&["seq", "x"], "invalid floating point argument: 'x'"),
+            (&["seq", "1", "2x"], "invalid floating point argument: '2x'"),
+            (&["seq", "2 "], "invalid floating point argument: '2 '"),
+            (&["seq", ""], "invalid floating point argument: ''"),
+            (
+                &["seq", "1", "nan"],
+                "invalid 'not-a-number' argument: 'nan'",
+            ),
+        ] {
+            assert_eq!(
+                run_util(run, command_line),
+                (
+                    1,
+                    String::new(),
+                    format!("seq: {message}\nTry 'seq --help' for more information.\n")
+                ),
+                "{command_line:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_run_write_error() {
+        let args: Vec<OsString> = vec!["seq".into(), "1000".into()];
+        let mut stdout = io::Cursor::new([0; 2]);
+        let mut stderr = Vec::new();
+        let code = run(&args, &mut &b""[..], &mut stdout, &mut stderr);
+        assert_eq!(code, 1);
+        assert_eq!(
+            stderr,
+            b"seq: write error: failed to write the buffered data\n"
+        );
+    }
+}
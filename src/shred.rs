@@ -0,0 +1,622 @@
+//! Overwrite files with random data, so that what they contained is hard to recover, and optionally remove
+//! them.
+//!
+//! Every pass writes the whole file, rounded up to a multiple of its block size unless `exact`, in chunks of
+//! the block size, and syncs the data before the next one. The passes are all random, unlike GNU's, which
+//! mixes in fixed patterns from four passes. A file named `-` is a file, not standard output, and GNU's
+//! `--force` and `--random-source` are not supported.
+//!
+//! ```
+//! use rust_coreutils::common::error::Reporter;
+//! use rust_coreutils::shred::{shred, ShredOptions};
+//!
+//! let directory = tempfile::tempdir().unwrap();
+//! let file = directory.path().join("file");
+//! std::fs::write(&file, "secret").unwrap();
+//! let options = ShredOptions {
+//!     iterations: 0,
+//!     zero: true,
+//!     exact: true,
+//!     ..ShredOptions::default()
+//! };
+//! shred(&file, &options, &mut Reporter::new("shred", Vec::new())).unwrap();
+//! assert_eq!(std::fs::read(&file).unwrap(), [0; 6]);
+//! ```
+
+use crate::common::error::{io_error_message, Reporter, UError, UResult, EXIT_FAILURE};
+use crate::common::size::parse_size_u64;
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use clap::Parser;
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "shred",
+    author,
+    version,
+    about = "Overwrite the FILEs repeatedly, so that their contents are harder to recover, and optionally \
+        remove them.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Overwrite N times.
+    #[arg(
+        short = 'n',
+        long,
+        value_name = "N",
+        default_value = "3",
+        allow_hyphen_values = true
+    )]
+    iterations: String,
+
+    /// Overwrite only the first SIZE bytes, like 4K or 1M.
+    #[arg(short, long, value_name = "SIZE", allow_hyphen_values = true)]
+    size: Option<String>,
+
+    /// Truncate and remove the files after overwriting them, like --remove=wipesync.
+    #[arg(short = 'u', default_value_t = false, overrides_with = "remove")]
+    remove_wipesync: bool,
+
+    /// Truncate and remove the files after overwriting them: unlink them, or first rename them to names
+    /// made of zeros (wipe), syncing the directory after each rename (wipesync, the default).
+    #[arg(
+        long,
+        value_name = "HOW",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "wipesync",
+        overrides_with = "remove_wipesync"
+    )]
+    remove: Option<String>,
+
+    /// Print the passes, and the removals.
+    #[arg(short, long, default_value_t = false)]
+    verbose: bool,
+
+    /// Do not round the sizes up to a multiple of the block size.
+    #[arg(short = 'x', long, default_value_t = false)]
+    exact: bool,
+
+    /// Overwrite with zeros at the end, to hide the shredding.
+    #[arg(short, long, default_value_t = false)]
+    zero: bool,
+
+    /// The files to overwrite.
+    #[arg(value_name = "FILE")]
+    files: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "shred";
+}
+
+/// The description of shred for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run shred with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run shred with the given command line, including the program name, on the given streams, and return
+/// the exit code.
+pub fn run(
+    args: &[OsString],
+    _stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(args.iter().cloned(), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let mut reporter = Reporter::new("shred", stderr);
+    let result = options(&args).map(|options| {
+        for file in &args.files {
+            if let Err(error) = shred(Path::new(file), &options, &mut reporter) {
+                reporter.error(format_args!("{error}"));
+            }
+        }
+    });
+    reporter.finish(result)
+}
+
+fn options(args: &Args) -> UResult<ShredOptions> {
+    let iterations = args.iterations.parse().map_err(|_| {
+        UError::new(
+            EXIT_FAILURE,
+            format!("invalid number of passes: '{}'", args.iterations),
+        )
+    })?;
+    let size = match &args.size {
+        Some(size) => Some(
+            parse_size_u64(size)
+                .ok()
+                .filter(|&size| i64::try_from(size).is_ok())
+                .ok_or_else(|| UError::new(EXIT_FAILURE, format!("invalid file size: '{size}'")))?,
+        ),
+        None => None,
+    };
+    let remove = match (&args.remove, args.remove_wipesync) {
+        (Some(how), _) => Some(Remove::parse(how)?),
+        (None, true) => Some(Remove::WipeSync),
+        (None, false) => None,
+    };
+    if args.files.is_empty() {
+        return Err(UError::usage(EXIT_FAILURE, "missing file operand"));
+    }
+    Ok(ShredOptions {
+        iterations,
+        size,
+        exact: args.exact,
+        zero: args.zero,
+        remove,
+        verbose: args.verbose,
+    })
+}
+
+/// How to remove the files after overwriting them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Remove {
+    /// Only unlink them.
+    Unlink,
+    /// Rename them to shorter and shorter names made of zeros first, to hide their names.
+    Wipe,
+    /// Like [`Remove::Wipe`], syncing the directory after each rename, so that the names reach the disk.
+    WipeSync,
+}
+
+impl Remove {
+    /// The values of `--remove`, which can be abbreviated as long as it is unambiguous.
+    const VALUES: [(&'static str, Remove); 3] = [
+        ("unlink", Remove::Unlink),
+        ("wipe", Remove::Wipe),
+        ("wipesync", Remove::WipeSync),
+    ];
+
+    /// Parse the value of `--remove`.
+    pub fn parse(how: &str) -> UResult<Remove> {
+        if let Some(&(_, remove)) = Self::VALUES.iter().find(|(name, _)| *name == how) {
+            return Ok(remove);
+        }
+        let mut matches = Self::VALUES
+            .iter()
+            .filter(|(name, _)| name.starts_with(how));
+        let problem = match (matches.next(), matches.next()) {
+            (Some(&(_, remove)), None) => return Ok(remove),
+            (Some(_), Some(_)) => "ambiguous",
+            (None, _) => "invalid",
+        };
+        Err(UError::new(
+            EXIT_FAILURE,
+            format!(
+                "{problem} argument '{how}' for '--remove'\n\
+                 Valid arguments are:\n  \
+                 - 'unlink'\n  \
+                 - 'wipe'\n  \
+                 - 'wipesync'"
+            ),
+        ))
+    }
+}
+
+/// The options of shred.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShredOptions {
+    /// How many random passes to make (`-n`).
+    pub iterations: u64,
+    /// How many bytes to overwrite (`-s`), exactly, rather than the size of the file.
+    pub size: Option<u64>,
+    /// Do not round the size of a file up to a multiple of its block size (`-x`).
+    pub exact: bool,
+    /// Make a last pass with zeros (`-z`).
+    pub zero: bool,
+    /// Remove the files afterwards (`-u` and `--remove`).
+    pub remove: Option<Remove>,
+    /// Print the passes and the removals (`-v`).
+    pub verbose: bool,
+}
+
+impl Default for ShredOptions {
+    fn default() -> Self {
+        ShredOptions {
+            iterations: 3,
+            size: None,
+            exact: false,
+            zero: false,
+            remove: None,
+            verbose: false,
+        }
+    }
+}
+
+/// The characters of the names which the files are renamed to before being removed, in the order they are
+/// tried.
+const NAME_CHARACTERS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ_.";
+
+/// Overwrite a file, and remove it with `options.remove`. The passes and the removal are printed through
+/// `reporter` with `options.verbose`.
+pub fn shred<W: Write>(
+    path: &Path,
+    options: &ShredOptions,
+    reporter: &mut Reporter<W>,
+) -> UResult<()> {
+    let name = path.display();
+    let failed = |what: &str, error: io::Error| UError::io(format!("{name}: {what}"), &error);
+    let mut file = OpenOptions::new()
+        .write(true)
+        .open(path)
+        .map_err(|error| failed("failed to open for writing", error))?;
+    let metadata = file
+        .metadata()
+        .map_err(|error| failed("fstat failed", error))?;
+    let block_size = block_size(&metadata);
+    let size = match options.size {
+        Some(size) => size,
+        None if metadata.is_file() && options.exact => metadata.len(),
+        None if metadata.is_file() => metadata.len().div_ceil(block_size) * block_size,
+        // Devices have no size, but they can be sought to their end.
+        None => file
+            .seek(SeekFrom::End(0))
+            .map_err(|error| failed("lseek failed", error))?,
+    };
+
+    let passes = options.iterations + u64::from(options.zero);
+    let mut buffer = vec![0; usize::try_from(block_size).unwrap_or(4096)];
+    for pass in 1..=passes {
+        let random = !(options.zero && pass == passes);
+        if options.verbose {
+            let pattern = if random { "random" } else { "000000" };
+            reporter.warning(format_args!("{name}: pass {pass}/{passes} ({pattern})..."));
+        }
+        file.rewind()
+            .map_err(|error| failed("cannot rewind", error))?;
+        let mut offset = 0;
+        while offset < size {
+            let chunk = &mut buffer[..(size - offset).min(block_size) as usize];
+            match random {
+                true => getrandom::fill(chunk).map_err(io::Error::from)?,
+                false => chunk.fill(0),
+            }
+            file.write_all(chunk)
+                .map_err(|error| failed(&format!("error writing at offset {offset}"), error))?;
+            offset += chunk.len() as u64;
+        }
+        file.sync_data()
+            .map_err(|error| failed("fdatasync failed", error))?;
+    }
+
+    let Some(remove) = options.remove else {
+        return Ok(());
+    };
+    if metadata.is_file() {
+        file.set_len(0)
+            .map_err(|error| failed("error truncating", error))?;
+    }
+    drop(file);
+    remove_file(path, remove, options.verbose, reporter)
+}
+
+/// Get the block size of a file, in which it is written.
+fn block_size(metadata: &fs::Metadata) -> u64 {
+    #[cfg(unix)]
+    let size = std::os::unix::fs::MetadataExt::blksize(metadata);
+    #[cfg(not(unix))]
+    let size = {
+        let _ = metadata;
+        4096
+    };
+    match size {
+        0 => 512,
+        size => size,
+    }
+}
+
+/// Remove a file, renaming it first to hide its name unless `remove` is [`Remove::Unlink`]: to a name of as
+/// many zeros, or of other characters if it exists, then to shorter and shorter names, down to one
+/// character.
+fn remove_file<W: Write>(
+    path: &Path,
+    remove: Remove,
+    verbose: bool,
+    reporter: &mut Reporter<W>,
+) -> UResult<()> {
+    let name = path.display();
+    if verbose {
+        reporter.warning(format_args!("{name}: removing"));
+    }
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    // Only directories which can be opened are synced, like in GNU.
+    let directory = match remove {
+        Remove::WipeSync => File::open(directory).ok(),
+        Remove::Unlink | Remove::Wipe => None,
+    };
+
+    let mut current = path.to_path_buf();
+    let length = match remove {
+        Remove::Unlink => 0,
+        Remove::Wipe | Remove::WipeSync => path.file_name().map_or(0, |name| name.len()),
+    };
+    for length in (1..=length).rev() {
+        let mut new_name = vec![NAME_CHARACTERS[0]; length];
+        loop {
+            let new = current.with_file_name(String::from_utf8_lossy(&new_name).as_ref());
+            match rename_no_replace(&current, &new) {
+                Ok(()) => {
+                    if let Some(directory) = &directory {
+                        let _ = directory.sync_all();
+                    }
+                    if verbose {
+                        let old = current.display();
+                        reporter.warning(format_args!("{old}: renamed to {}", new.display()));
+                    }
+                    current = new;
+                }
+                Err(error) if error.kind() == ErrorKind::AlreadyExists => {
+                    if next_name(&mut new_name) {
+                        continue;
+                    }
+                }
+                // The name is still hidden by the shorter ones.
+                Err(_) => {}
+            }
+            break;
+        }
+    }
+
+    fs::remove_file(&current).map_err(|error| {
+        UError::new(
+            EXIT_FAILURE,
+            format!("{name}: failed to remove: {}", io_error_message(&error)),
+        )
+    })?;
+    if verbose {
+        reporter.warning(format_args!("{name}: removed"));
+    }
+    if let Some(directory) = &directory {
+        let _ = directory.sync_all();
+    }
+    Ok(())
+}
+
+/// Change a name made of [`NAME_CHARACTERS`] to the next one of the same length, counting with them as
+/// digits, or return `false` if it is the last one.
+fn next_name(name: &mut [u8]) -> bool {
+    for character in name.iter_mut().rev() {
+        let index = NAME_CHARACTERS
+            .iter()
+            .position(|c| c == character)
+            .expect("the names are made of the name characters");
+        if let Some(&next) = NAME_CHARACTERS.get(index + 1) {
+            *character = next;
+            return true;
+        }
+        *character = NAME_CHARACTERS[0];
+    }
+    false
+}
+
+/// Rename a file, failing with [`ErrorKind::AlreadyExists`] rather than replacing a file with the new name.
+fn rename_no_replace(old: &Path, new: &Path) -> io::Result<()> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        use crate::common::paths::c_path;
+        let (old_c, new_c) = (c_path(old)?, c_path(new)?);
+        let result = unsafe {
+            libc::renameat2(
+                libc::AT_FDCWD,
+                old_c.as_ptr(),
+                libc::AT_FDCWD,
+                new_c.as_ptr(),
+                libc::RENAME_NOREPLACE,
+            )
+        };
+        let error = io::Error::last_os_error();
+        match result {
+            0 => return Ok(()),
+            // The file systems and the kernels which do not support it fall back to checking first.
+            _ if matches!(error.raw_os_error(), Some(libc::EINVAL | libc::ENOSYS)) => {}
+            _ => return Err(error),
+        }
+    }
+    match fs::symlink_metadata(new) {
+        Ok(_) => Err(ErrorKind::AlreadyExists.into()),
+        Err(error) if error.kind() == ErrorKind::NotFound => fs::rename(old, new),
+        Err(error) => Err(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::run_util;
+
+    #[test]
+    fn test_overwrite() {
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("file");
+        let original = [b'A'; 5000];
+        fs::write(&file, original).unwrap();
+        let path = file.to_str().unwrap();
+        let block_size = block_size(&fs::metadata(&file).unwrap());
+
+        assert_eq!(
+            run_util(run, &["shred", "-v", path]),
+            (
+                0,
+                String::new(),
+                format!(
+                    "shred: {path}: pass 1/3 (random)...\n\
+                    shred: {path}: pass 2/3 (random)...\n\
+                    shred: {path}: pass 3/3 (random)...\n"
+                )
+            )
+        );
+        // The size is rounded up to the block size, and the blocks are random.
+        let contents = fs::read(&file).unwrap();
+        assert_eq!(
+            contents.len() as u64,
+            5000u64.div_ceil(block_size) * block_size
+        );
+        assert!(!contents.windows(32).any(|bytes| bytes == &original[..32]));
+        assert!(!contents.windows(32).any(|bytes| bytes == [0; 32]));
+
+        fs::write(&file, original).unwrap();
+        assert_eq!(run_util(run, &["shred", "-x", "-n", "1", path]).0, 0);
+        let contents = fs::read(&file).unwrap();
+        assert_eq!(contents.len(), 5000);
+        assert!(!contents.windows(32).any(|bytes| bytes == &original[..32]));
+
+        assert_eq!(
+            run_util(run, &["shred", "-v", "-n", "0", "-z", path]),
+            (
+                0,
+                String::new(),
+                format!("shred: {path}: pass 1/1 (000000)...\n")
+            )
+        );
+        assert_eq!(
+            fs::read(&file).unwrap(),
+            vec![0; contents.len().next_multiple_of(block_size as usize)]
+        );
+
+        // Only the beginning is overwritten, without rounding.
+        fs::write(&file, original).unwrap();
+        assert_eq!(
+            run_util(run, &["shred", "-n", "0", "-z", "-s", "1K", path]).0,
+            0
+        );
+        let contents = fs::read(&file).unwrap();
+        assert_eq!(contents[..1024], [0; 1024]);
+        assert_eq!(contents[1024..], original[1024..]);
+    }
+
+    #[test]
+    fn test_remove() {
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("abc");
+        fs::write(&file, "secret").unwrap();
+        let path = file.to_str().unwrap();
+        let renamed = |name: &str| directory.path().join(name).display().to_string();
+
+        assert_eq!(
+            run_util(run, &["shred", "-v", "-n", "1", "-u", path]),
+            (
+                0,
+                String::new(),
+                format!(
+                    "shred: {path}: pass 1/1 (random)...\n\
+                    shred: {path}: removing\n\
+                    shred: {path}: renamed to {}\n\
+                    shred: {}: renamed to {}\n\
+                    shred: {}: renamed to {}\n\
+                    shred: {path}: removed\n",
+                    renamed("000"),
+                    renamed("000"),
+                    renamed("00"),
+                    renamed("00"),
+                    renamed("0"),
+                )
+            )
+        );
+        assert_eq!(fs::read_dir(directory.path()).unwrap().count(), 0);
+
+        // The names which exist are skipped, and kept.
+        fs::write(&file, "secret").unwrap();
+        fs::write(directory.path().join("000"), "").unwrap();
+        fs::write(directory.path().join("0"), "").unwrap();
+        let (code, _, stderr) = run_util(run, &["shred", "-v", "-n", "0", "--remove=wipe", path]);
+        assert_eq!(code, 0);
+        assert!(
+            stderr.contains(&format!("{path}: renamed to {}\n", renamed("001"))),
+            "{stderr}"
+        );
+        assert!(
+            stderr.contains(&format!("renamed to {}\n", renamed("1"))),
+            "{stderr}"
+        );
+        let mut names: Vec<_> = fs::read_dir(directory.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["0", "000"]);
+
+        fs::write(&file, "secret").unwrap();
+        assert_eq!(
+            run_util(run, &["shred", "-v", "-n", "0", "--remove=unl", path]),
+            (
+                0,
+                String::new(),
+                format!("shred: {path}: removing\nshred: {path}: removed\n")
+            )
+        );
+        assert!(!file.exists());
+    }
+
+    #[test]
+    fn test_next_name() {
+        let next = |name: &str| {
+            let mut name = name.as_bytes().to_vec();
+            next_name(&mut name).then(|| String::from_utf8(name).unwrap())
+        };
+        assert_eq!(next("000").as_deref(), Some("001"));
+        assert_eq!(next("09").as_deref(), Some("0a"));
+        assert_eq!(next("0.").as_deref(), Some("10"));
+        assert_eq!(next(".").as_deref(), None);
+        assert_eq!(next("..").as_deref(), None);
+    }
+
+    #[test]
+    fn test_errors() {
+        let directory = tempfile::tempdir().unwrap();
+        let missing = directory.path().join("missing");
+        let missing = missing.to_str().unwrap();
+        let failure = |message: &str| (EXIT_FAILURE, String::new(), format!("shred: {message}\n"));
+        let usage = |message: &str| {
+            failure(&format!(
+                "{message}\nTry 'shred --help' for more information."
+            ))
+        };
+
+        assert_eq!(
+            run_util(run, &["shred", missing]),
+            failure(&format!(
+                "{missing}: failed to open for writing: No such file or directory"
+            ))
+        );
+        assert_eq!(run_util(run, &["shred"]), usage("missing file operand"));
+        assert_eq!(
+            run_util(run, &["shred", "-n", "-1", missing]),
+            failure("invalid number of passes: '-1'")
+        );
+        for size in ["1X", "-1", "8E"] {
+            assert_eq!(
+                run_util(run, &["shred", "-s", size, missing]),
+                failure(&format!("invalid file size: '{size}'"))
+            );
+        }
+        let (code, _, stderr) = run_util(run, &["shred", "--remove=w", missing]);
+        assert_eq!(code, EXIT_FAILURE);
+        assert!(
+            stderr.starts_with("shred: ambiguous argument 'w' for '--remove'\n"),
+            "{stderr}"
+        );
+    }
+}
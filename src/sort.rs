@@ -0,0 +1,1328 @@
+//! Sort the lines of files.
+//!
+//! All the inputs are read with [`read_all`] before anything is written, then sorted with [`sort_lines`]
+//! and written with [`write_lines`]:
+//!
+//! ```
+//! use rust_coreutils::common::input::InputIter;
+//! use rust_coreutils::sort::{read_all, sort_lines, write_lines, SortOptions};
+//!
+//! let mut inputs = InputIter::with_stdin(vec![], Box::new(&b"b\nc\na"[..]));
+//! let options = SortOptions::default();
+//! let data = read_all(&mut inputs, options.delimiter).unwrap();
+//! let mut stdout = Vec::new();
+//! write_lines(&sort_lines(&data, &options), options.delimiter, &mut stdout).unwrap();
+//! assert_eq!(stdout, b"a\nb\nc\n");
+//! ```
+
+use crate::common::compare::{
+    compare_human_numbers, compare_numbers, compare_versions, compare_versions_ignore_case,
+    is_blank,
+};
+use crate::common::error::{io_error_message, Reporter, UError, UResult};
+use crate::common::input::{InputIter, BUFFER_SIZE};
+use crate::common::os::os_str_bytes;
+use crate::common::records::ZeroTerminatedArgs;
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use clap::Parser;
+use std::cmp::Ordering;
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// The exit code of sort when it fails, like GNU's, which is also the code of usage errors.
+const SORT_FAILURE: i32 = 2;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "sort",
+    author,
+    version,
+    about = "Write the sorted lines of all the FILEs to standard output.",
+    long_about = None,
+    args_override_self = true,
+    disable_help_flag = true,
+    disable_version_flag = true
+)]
+struct Args {
+    /// Ignore the blanks at the start of the lines, or of the fields where keys start and end.
+    #[arg(short = 'b', long, default_value_t = false)]
+    ignore_leading_blanks: bool,
+
+    /// Compare the sizes at the start of the lines, like 2K or 1G, as `du -h` prints them.
+    #[arg(short, long = "human-numeric-sort", default_value_t = false)]
+    human_numeric: bool,
+
+    /// Compare the numbers at the start of the lines, with an optional - sign and decimal point. Lines
+    /// which do not start with a number compare as zero.
+    #[arg(short, long = "numeric-sort", default_value_t = false)]
+    numeric: bool,
+
+    /// Compare lower case letters as upper case.
+    #[arg(short = 'f', long, default_value_t = false)]
+    ignore_case: bool,
+
+    /// Reverse the order.
+    #[arg(short, long, default_value_t = false)]
+    reverse: bool,
+
+    /// Only write the first of the lines which compare equal.
+    #[arg(short, long, default_value_t = false)]
+    unique: bool,
+
+    /// Compare the versions in the lines, where runs of digits compare as numbers.
+    #[arg(short = 'V', long = "version-sort", default_value_t = false)]
+    version_sort: bool,
+
+    /// Compare the lines by a key from field F1, character C1 (1 by default) to field F2, character C2
+    /// (the end of the field by default), or the end of the line without F2. The letters b, f, h, n, r, and V
+    /// apply the options of the same names to the key only; a key without letters uses the global ones.
+    /// The keys are compared in order.
+    #[arg(short, long = "key", value_name = "F1[.C1][OPTS][,F2[.C2][OPTS]]")]
+    keys: Vec<String>,
+
+    /// Separate fields with SEP instead of the change from a non-blank to a blank, which starts the next
+    /// field.
+    #[arg(short = 't', long, value_name = "SEP")]
+    field_separator: Vec<OsString>,
+
+    /// Write the result to FILE instead of standard output. FILE can be one of the inputs.
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<OsString>,
+
+    #[command(flatten)]
+    records: ZeroTerminatedArgs,
+
+    /// Print help.
+    #[arg(long, action = clap::ArgAction::Help)]
+    help: Option<bool>,
+
+    /// Print version.
+    #[arg(long, action = clap::ArgAction::Version)]
+    version: Option<bool>,
+
+    /// The files to sort. With no FILE, or when FILE is -, standard input is read.
+    #[arg(value_name = "FILE")]
+    files: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "sort";
+}
+
+/// The description of sort for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run sort with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run sort with the given command line, including the program name, on the given streams, and return the
+/// exit code.
+pub fn run(
+    args: &[OsString],
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(args.iter().cloned(), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let options = match options(&args) {
+        Ok(options) => options,
+        Err(message) => {
+            let error = UError::new(SORT_FAILURE, message);
+            return Reporter::new("sort", stderr).finish(Err(error));
+        }
+    };
+    let mut inputs = InputIter::with_stdin(
+        args.files.clone(),
+        Box::new(BufReader::with_capacity(BUFFER_SIZE, stdin)),
+    );
+    let result = read_all(&mut inputs, options.delimiter).and_then(|data| {
+        let lines = sort_lines(&data, &options);
+        match &args.output {
+            Some(output) => write_file(&lines, options.delimiter, output, &args.files),
+            None => write_lines(&lines, options.delimiter, stdout).or_else(|error| {
+                UError::write_with(error, |error| {
+                    UError::new(
+                        SORT_FAILURE,
+                        format!(
+                            "write failed: 'standard output': {}",
+                            io_error_message(&error)
+                        ),
+                    )
+                })
+            }),
+        }
+    });
+    Reporter::new("sort", stderr).finish(result)
+}
+
+/// Get the options of the command line.
+fn options(args: &Args) -> Result<SortOptions, String> {
+    let mut separator = None;
+    for arg in &args.field_separator {
+        let value = match &*os_str_bytes(arg) {
+            [] => return Err("empty tab".to_owned()),
+            [byte] => *byte,
+            b"\\0" => b'\0',
+            _ => return Err(format!("multi-character tab '{}'", arg.to_string_lossy())),
+        };
+        if separator.is_some_and(|separator| separator != value) {
+            return Err("incompatible tabs".to_owned());
+        }
+        separator = Some(value);
+    }
+
+    // Like GNU, the keys are all parsed before their options are checked, and the global options last.
+    let parsed: Vec<(Key, Option<Letters>)> = args
+        .keys
+        .iter()
+        .map(|spec| parse_key(spec))
+        .collect::<Result<_, _>>()?;
+    let global = Letters {
+        fold_case: args.ignore_case,
+        human_numeric: args.human_numeric,
+        numeric: args.numeric,
+        reverse: args.reverse,
+        version: args.version_sort,
+    };
+    let mut keys = Vec::with_capacity(parsed.len());
+    for (mut key, letters) in parsed {
+        key.options = match letters {
+            Some(letters) => letters.options()?,
+            None => {
+                key.start.skip_blanks = args.ignore_leading_blanks;
+                if let Some(end) = &mut key.end {
+                    end.skip_blanks = args.ignore_leading_blanks;
+                }
+                global.options()?
+            }
+        };
+        keys.push(key);
+    }
+
+    Ok(SortOptions {
+        key: global.options()?,
+        ignore_leading_blanks: args.ignore_leading_blanks,
+        keys,
+        separator,
+        unique: args.unique,
+        delimiter: args.records.delimiter(),
+    })
+}
+
+/// The option letters of a key, or the global options, before they are checked.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Letters {
+    fold_case: bool,
+    human_numeric: bool,
+    numeric: bool,
+    reverse: bool,
+    version: bool,
+}
+
+impl Letters {
+    /// Get the options, unless they choose more than one order.
+    fn options(self) -> Result<KeyOptions, String> {
+        let orders = [
+            (self.human_numeric, 'h', Comparator::HumanNumeric),
+            (self.numeric, 'n', Comparator::Numeric),
+            (self.version, 'V', Comparator::Version),
+        ];
+        let mut chosen = orders.iter().filter(|(set, _, _)| *set);
+        let comparator = match (chosen.next(), chosen.next()) {
+            (None, _) => Comparator::Bytes,
+            (Some((_, _, comparator)), None) => *comparator,
+            (Some(_), Some(_)) => {
+                let letters: String = orders
+                    .iter()
+                    .filter(|(set, _, _)| *set)
+                    .map(|(_, letter, _)| letter)
+                    .collect();
+                let fold = if self.fold_case { "f" } else { "" };
+                return Err(format!("options '-{fold}{letters}' are incompatible"));
+            }
+        };
+        Ok(KeyOptions {
+            comparator,
+            fold_case: self.fold_case,
+            reverse: self.reverse,
+        })
+    }
+}
+
+/// Parse the argument of `-k`, with the option letters if it has any, which apply to the whole key.
+fn parse_key(spec: &str) -> Result<(Key, Option<Letters>), String> {
+    let invalid = |reason: &str| format!("{reason}: invalid field specification '{spec}'");
+    let mut letters = None;
+
+    let (field, rest) = parse_count(spec, "invalid number at field start")?;
+    let Some(field) = field.checked_sub(1) else {
+        return Err(invalid("field number is zero"));
+    };
+    let (character, rest) = match rest.strip_prefix('.') {
+        Some(rest) => parse_count(rest, "invalid number after '.'")?,
+        None => (1, rest),
+    };
+    let Some(character) = character.checked_sub(1) else {
+        return Err(invalid("character offset is zero"));
+    };
+    let mut start = Position {
+        field,
+        character,
+        skip_blanks: false,
+    };
+    let rest = parse_letters(rest, &mut letters, &mut start.skip_blanks);
+
+    let (end, rest) = match rest.strip_prefix(',') {
+        Some(rest) => {
+            let (field, rest) = parse_count(rest, "invalid number after ','")?;
+            let Some(field) = field.checked_sub(1) else {
+                return Err(invalid("field number is zero"));
+            };
+            let (character, rest) = match rest.strip_prefix('.') {
+                Some(rest) => parse_count(rest, "invalid number after '.'")?,
+                None => (0, rest),
+            };
+            let mut end = Position {
+                field,
+                character,
+                skip_blanks: false,
+            };
+            let rest = parse_letters(rest, &mut letters, &mut end.skip_blanks);
+            (Some(end), rest)
+        }
+        None => (None, rest),
+    };
+    if !rest.is_empty() {
+        return Err(invalid("stray character in field spec"));
+    }
+
+    let key = Key {
+        start,
+        end,
+        options: KeyOptions::default(),
+    };
+    Ok((key, letters))
+}
+
+/// Parse the number at the start of a part of a key, which saturates when it is too large.
+fn parse_count<'a>(spec: &'a str, reason: &str) -> Result<(usize, &'a str), String> {
+    let digits = spec.bytes().take_while(u8::is_ascii_digit).count();
+    if digits == 0 {
+        return Err(format!("{reason}: invalid count at start of '{spec}'"));
+    }
+    let (number, rest) = spec.split_at(digits);
+    Ok((number.parse().unwrap_or(usize::MAX), rest))
+}
+
+/// Apply the option letters after a position of a key, and return what follows them.
+fn parse_letters<'a>(
+    spec: &'a str,
+    letters: &mut Option<Letters>,
+    skip_blanks: &mut bool,
+) -> &'a str {
+    let length = spec
+        .bytes()
+        .take_while(|&letter| {
+            if !b"bfhnrV".contains(&letter) {
+                return false;
+            }
+            let letters = letters.get_or_insert_with(Letters::default);
+            match letter {
+                b'b' => *skip_blanks = true,
+                b'f' => letters.fold_case = true,
+                b'h' => letters.human_numeric = true,
+                b'n' => letters.numeric = true,
+                b'r' => letters.reverse = true,
+                _ => letters.version = true,
+            }
+            true
+        })
+        .count();
+    &spec[length..]
+}
+
+/// How two lines, or two keys of them, are compared.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Comparator {
+    /// Byte by byte, like in the C locale, whatever the encoding.
+    #[default]
+    Bytes,
+    /// By the decimal numbers they start with, after blanks, like GNU's `-n` in the C locale.
+    Numeric,
+    /// By the sizes they start with, after blanks, where the unit decides first, like GNU's `-h`.
+    HumanNumeric,
+    /// As versions or file names, like GNU's `-V` and `ls -v`.
+    Version,
+}
+
+impl Comparator {
+    /// Compare two lines, without their delimiters.
+    pub fn compare(self, a: &[u8], b: &[u8]) -> Ordering {
+        match self {
+            Comparator::Bytes => a.cmp(b),
+            Comparator::Numeric => compare_numbers(a, b),
+            Comparator::HumanNumeric => compare_human_numbers(a, b),
+            Comparator::Version => compare_versions(a, b),
+        }
+    }
+}
+
+/// How the lines, or their keys, are compared.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct KeyOptions {
+    /// The order of the lines.
+    pub comparator: Comparator,
+    /// Compare lower case ASCII letters as upper case, when comparing bytes or versions.
+    pub fold_case: bool,
+    /// Reverse the order.
+    pub reverse: bool,
+}
+
+impl KeyOptions {
+    /// Compare two lines, without their delimiters.
+    pub fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        let ordering = match self.comparator {
+            Comparator::Bytes if self.fold_case => a
+                .iter()
+                .map(u8::to_ascii_uppercase)
+                .cmp(b.iter().map(u8::to_ascii_uppercase)),
+            Comparator::Version if self.fold_case => compare_versions_ignore_case(a, b),
+            comparator => comparator.compare(a, b),
+        };
+        if self.reverse {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+/// A position in a line, where a key starts or ends.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Position {
+    /// The field, counting from 0.
+    pub field: usize,
+    /// Where a key starts, the character in the field, counting from 0. Where a key ends, the number of
+    /// characters of the field in the key, with 0 for all of them. Characters are bytes, like in the C
+    /// locale.
+    pub character: usize,
+    /// Skip the blanks at the start of the field before counting the characters.
+    pub skip_blanks: bool,
+}
+
+/// A part of the lines which they are compared by.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Key {
+    /// Where the key starts.
+    pub start: Position,
+    /// Where the key ends, or `None` for the end of the line.
+    pub end: Option<Position>,
+    /// How the key is compared.
+    pub options: KeyOptions,
+}
+
+impl Key {
+    /// Find the key in a line, which is empty if it ends before it starts.
+    pub fn find(&self, line: &[u8], separator: Option<u8>) -> Range<usize> {
+        let start = skip_fields(line, 0, self.start.field, separator, true);
+        let start = match self.start.skip_blanks {
+            true => skip_blanks(line, start),
+            false => start,
+        };
+        let start = start.saturating_add(self.start.character).min(line.len());
+
+        let Some(end) = self.end else {
+            return start..line.len();
+        };
+        // Without a character, the key ends with the field.
+        let (fields, last_separator) = match end.character {
+            0 => (end.field.saturating_add(1), false),
+            _ => (end.field, true),
+        };
+        let mut limit = skip_fields(line, 0, fields, separator, last_separator);
+        if end.character != 0 {
+            if end.skip_blanks {
+                limit = skip_blanks(line, limit);
+            }
+            limit = limit.saturating_add(end.character).min(line.len());
+        }
+        start..limit.max(start)
+    }
+}
+
+/// Skip fields of a line from a position. With a separator, the one after the last field is only skipped
+/// if `last_separator` is set; without one, fields are blanks followed by non-blanks.
+fn skip_fields(
+    line: &[u8],
+    mut at: usize,
+    fields: usize,
+    separator: Option<u8>,
+    last_separator: bool,
+) -> usize {
+    for remaining in (0..fields).rev() {
+        if at >= line.len() {
+            break;
+        }
+        match separator {
+            Some(separator) => {
+                at += line[at..]
+                    .iter()
+                    .position(|&byte| byte == separator)
+                    .unwrap_or(line.len() - at);
+                if at < line.len() && (remaining > 0 || last_separator) {
+                    at += 1;
+                }
+            }
+            None => {
+                at = skip_blanks(line, at);
+                at += line[at..]
+                    .iter()
+                    .take_while(|&&byte| !is_blank(byte))
+                    .count();
+            }
+        }
+    }
+    at
+}
+
+/// Skip the blanks of a line from a position.
+fn skip_blanks(line: &[u8], at: usize) -> usize {
+    at + line[at..]
+        .iter()
+        .take_while(|&&byte| is_blank(byte))
+        .count()
+}
+
+/// The behaviour of sort.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SortOptions {
+    /// How lines are compared, and the keys without options of their own.
+    pub key: KeyOptions,
+    /// Skip the blanks at the start of the lines, when there are no keys.
+    pub ignore_leading_blanks: bool,
+    /// The keys which lines are compared by, in order. Without keys, whole lines are compared.
+    pub keys: Vec<Key>,
+    /// The byte which separates fields, or `None` for fields which start with blanks.
+    pub separator: Option<u8>,
+    /// Only keep the first of the lines which compare equal.
+    pub unique: bool,
+    /// The byte which ends lines.
+    pub delimiter: u8,
+}
+
+impl Default for SortOptions {
+    /// The default behaviour of sort: lines in the order of their bytes.
+    fn default() -> Self {
+        SortOptions {
+            key: KeyOptions::default(),
+            ignore_leading_blanks: false,
+            keys: Vec::new(),
+            separator: None,
+            unique: false,
+            delimiter: b'\n',
+        }
+    }
+}
+
+/// Read all the inputs, one after the other. The last line of an input ends with it: a delimiter is added
+/// if it is missing, so that the line is not joined with the first line of the next input.
+///
+/// Sorting cannot go on without one of the inputs, so the first one which cannot be opened or read stops it.
+pub fn read_all(inputs: &mut InputIter, delimiter: u8) -> UResult<Vec<u8>> {
+    let mut data = Vec::new();
+    while let Some(input) = inputs.next_raw() {
+        let mut input = input.map_err(|error| {
+            UError::new(
+                SORT_FAILURE,
+                format!(
+                    "cannot read: {}: {}",
+                    error.display_name(),
+                    io_error_message(&error.error)
+                ),
+            )
+        })?;
+        let start = data.len();
+        input.reader.read_to_end(&mut data).map_err(|error| {
+            UError::new(
+                SORT_FAILURE,
+                format!(
+                    "read failed: {}: {}",
+                    input.display_name(),
+                    io_error_message(&error)
+                ),
+            )
+        })?;
+        if data.len() > start && data.last() != Some(&delimiter) {
+            data.push(delimiter);
+        }
+    }
+    Ok(data)
+}
+
+/// Split the data into lines and sort them by their keys. Lines whose keys compare equal are compared by
+/// their bytes as a last resort, in the reverse order with `-r`, unless only the first of them is kept when
+/// unique.
+pub fn sort_lines<'a>(data: &'a [u8], options: &SortOptions) -> Vec<&'a [u8]> {
+    let lines: Vec<&[u8]> = match data.strip_suffix(&[options.delimiter]) {
+        Some(data) => data.split(|&byte| byte == options.delimiter).collect(),
+        None => return Vec::new(),
+    };
+    let line_key = [Key {
+        start: Position {
+            skip_blanks: options.ignore_leading_blanks,
+            ..Position::default()
+        },
+        end: None,
+        options: options.key,
+    }];
+    let keys = match options.keys.is_empty() {
+        true => &line_key[..],
+        false => &options.keys,
+    };
+
+    // The keys are found once for all the comparisons of a line.
+    let ranges: Vec<Range<usize>> = lines
+        .iter()
+        .flat_map(|line| keys.iter().map(|key| key.find(line, options.separator)))
+        .collect();
+    let mut entries: Vec<(&[u8], &[Range<usize>])> =
+        lines.into_iter().zip(ranges.chunks(keys.len())).collect();
+    let compare_keys = |(a, a_keys): &(&[u8], &[Range<usize>]),
+                        (b, b_keys): &(&[u8], &[Range<usize>])| {
+        keys.iter()
+            .zip(a_keys.iter().zip(b_keys.iter()))
+            .map(|(key, (a_key, b_key))| key.options.compare(&a[a_key.clone()], &b[b_key.clone()]))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    };
+
+    entries.sort_by(|a, b| {
+        let ordering = compare_keys(a, b);
+        if ordering.is_ne() || options.unique {
+            ordering
+        } else if options.key.reverse {
+            b.0.cmp(a.0)
+        } else {
+            a.0.cmp(b.0)
+        }
+    });
+    if options.unique {
+        entries.dedup_by(|entry, kept| compare_keys(kept, entry).is_eq());
+    }
+    entries.into_iter().map(|(line, _)| line).collect()
+}
+
+/// Write the lines, each followed by the delimiter.
+pub fn write_lines(lines: &[&[u8]], delimiter: u8, stdout: &mut dyn Write) -> io::Result<()> {
+    let mut writer = BufWriter::with_capacity(BUFFER_SIZE, stdout);
+    for line in lines {
+        writer.write_all(line)?;
+        writer.write_all(&[delimiter])?;
+    }
+    writer.flush()
+}
+
+/// Write the lines to the file named by `-o`. When it is one of the inputs, it is written next to it and
+/// renamed over it, so that the input is never seen half overwritten.
+fn write_file(lines: &[&[u8]], delimiter: u8, output: &OsStr, inputs: &[OsString]) -> UResult<()> {
+    let path = Path::new(output);
+    let name = path.display();
+    let open_failed = |error: io::Error| {
+        UError::new(
+            SORT_FAILURE,
+            format!("open failed: {name}: {}", io_error_message(&error)),
+        )
+    };
+    let write_failed = |error: io::Error| {
+        UError::new(
+            SORT_FAILURE,
+            format!("write failed: {name}: {}", io_error_message(&error)),
+        )
+    };
+    let is_input = inputs
+        .iter()
+        .filter(|input| *input != "-")
+        .any(|input| same_file(Path::new(input), path));
+    if !is_input {
+        let mut file = File::create(path).map_err(open_failed)?;
+        return write_lines(lines, delimiter, &mut file)
+            .or_else(|error| UError::write_with(error, write_failed));
+    }
+
+    let (temporary, mut file) = create_temporary(path).map_err(open_failed)?;
+    let result = write_lines(lines, delimiter, &mut file)
+        .and_then(|()| {
+            let permissions = fs::metadata(path)?.permissions();
+            file.set_permissions(permissions)
+        })
+        .and_then(|()| fs::rename(&temporary, path));
+    if let Err(error) = result {
+        let _ = fs::remove_file(&temporary);
+        return UError::write_with(error, write_failed);
+    }
+    Ok(())
+}
+
+/// Check whether two paths name the same file, which both exist.
+fn same_file(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (fs::metadata(a), fs::metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        }
+    }
+    #[cfg(not(unix))]
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Create a new file in the directory of `path`, with a name which is not taken yet.
+fn create_temporary(path: &Path) -> io::Result<(PathBuf, File)> {
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let name = path.file_name().unwrap_or(OsStr::new("sort"));
+    let mut attempt = 0;
+    loop {
+        let mut temporary_name = OsString::from(".");
+        temporary_name.push(name);
+        temporary_name.push(format!(".{}-{attempt}.tmp", process::id()));
+        let temporary = directory.join(temporary_name);
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temporary)
+        {
+            Ok(file) => return Ok((temporary, file)),
+            Err(error) if error.kind() == ErrorKind::AlreadyExists && attempt < 100 => attempt += 1,
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::{run_util_stdin, Fixture};
+
+    #[test]
+    fn test_stdin() {
+        assert_eq!(
+            run_util_stdin(run, &["sort"], b"b\nc\na\n"),
+            (0, "a\nb\nc\n".to_owned(), String::new())
+        );
+        assert_eq!(run_util_stdin(run, &["sort", "-"], b"b\na").1, "a\nb\n");
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(
+            run_util_stdin(run, &["sort"], b""),
+            (0, String::new(), String::new())
+        );
+        // Empty lines are lines.
+        assert_eq!(run_util_stdin(run, &["sort"], b"\n\n").1, "\n\n");
+        assert_eq!(run_util_stdin(run, &["sort"], b"b\n\na\n").1, "\na\nb\n");
+        let fixture = Fixture::new(&[("empty", "")]);
+        let empty = fixture.path("empty");
+        assert_eq!(run_util_stdin(run, &["sort", &empty, &empty], b"").1, "");
+    }
+
+    #[test]
+    fn test_bytes() {
+        // Upper case before lower case, and bytes which are not UTF-8 last, like in the C locale.
+        let args: Vec<OsString> = vec!["sort".into()];
+        let mut stdout = Vec::new();
+        let code = run(
+            &args,
+            &mut &b"b\n\xff\nB\na\n\xc3\xa9\n-\n"[..],
+            &mut stdout,
+            &mut Vec::new(),
+        );
+        assert_eq!(code, 0);
+        assert_eq!(stdout, b"-\nB\na\nb\n\xc3\xa9\n\xff\n");
+        // A prefix sorts first.
+        assert_eq!(
+            run_util_stdin(run, &["sort"], b"ab\na\nabc\n").1,
+            "a\nab\nabc\n"
+        );
+    }
+
+    #[test]
+    fn test_files_are_merged() {
+        let fixture = Fixture::new(&[("a", "pear\napple\n"), ("b", "fig\nbanana")]);
+        assert_eq!(
+            run_util_stdin(
+                run,
+                &["sort", &fixture.path("a"), "-", &fixture.path("b")],
+                b"cherry\n"
+            ),
+            (
+                0,
+                "apple\nbanana\ncherry\nfig\npear\n".to_owned(),
+                String::new()
+            )
+        );
+    }
+
+    #[test]
+    fn test_last_lines_without_newline() {
+        // The last line of each file ends with it.
+        let fixture = Fixture::new(&[("a", "b\na"), ("b", "c")]);
+        assert_eq!(
+            run_util_stdin(run, &["sort", &fixture.path("b"), &fixture.path("a")], b"").1,
+            "a\nb\nc\n"
+        );
+    }
+
+    #[test]
+    fn test_nul_bytes() {
+        let (code, stdout, _) = run_util_stdin(run, &["sort"], b"a\0b\nb\0a\na\n\0\n");
+        assert_eq!(code, 0);
+        assert_eq!(stdout, "\0\na\na\0b\nb\0a\n");
+    }
+
+    #[test]
+    fn test_zero_terminated() {
+        assert_eq!(
+            run_util_stdin(run, &["sort", "-z"], b"b\na\0c\n\0a").1,
+            "a\0b\na\0c\n\0"
+        );
+        let fixture = Fixture::new(&[("a", "b\na"), ("b", "c\n")]);
+        assert_eq!(
+            run_util_stdin(
+                run,
+                &["sort", "-z", &fixture.path("a"), &fixture.path("b")],
+                b""
+            )
+            .1,
+            "b\na\0c\n\0"
+        );
+    }
+
+    #[test]
+    fn test_output() {
+        let fixture = Fixture::new(&[("a", "b\na\n")]);
+        let sorted = fixture.path("sorted");
+        assert_eq!(
+            run_util_stdin(run, &["sort", "-o", &sorted, &fixture.path("a")], b""),
+            (0, String::new(), String::new())
+        );
+        assert_eq!(fs::read_to_string(&sorted).unwrap(), "a\nb\n");
+
+        // An existing file is replaced.
+        fs::write(&sorted, "a much longer content than the result\n").unwrap();
+        assert_eq!(
+            run_util_stdin(run, &["sort", "--output", &sorted], b"c\n").0,
+            0
+        );
+        assert_eq!(fs::read_to_string(&sorted).unwrap(), "c\n");
+    }
+
+    #[test]
+    fn test_output_is_an_input() {
+        let fixture = Fixture::new(&[("a", "pear\napple\n"), ("b", "fig\n")]);
+        let (a, b) = (fixture.path("a"), fixture.path("b"));
+        assert_eq!(
+            run_util_stdin(run, &["sort", "-o", &a, &b, &a, &a], b""),
+            (0, String::new(), String::new())
+        );
+        assert_eq!(
+            fs::read_to_string(&a).unwrap(),
+            "apple\napple\nfig\npear\npear\n"
+        );
+        // The temporary file is gone.
+        assert_eq!(fixture.names(), ["a", "b"]);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&a, fs::Permissions::from_mode(0o640)).unwrap();
+            assert_eq!(run_util_stdin(run, &["sort", "-o", &a, &a], b"").0, 0);
+            let mode = fs::metadata(&a).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o640);
+        }
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let fixture = Fixture::new(&[("a", "a\n")]);
+        let missing = fixture.path("missing");
+        // Nothing is written when an input is missing.
+        assert_eq!(
+            run_util_stdin(run, &["sort", &fixture.path("a"), &missing], b""),
+            (
+                2,
+                String::new(),
+                format!("sort: cannot read: {missing}: No such file or directory\n")
+            )
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_directory() {
+        let fixture = Fixture::new(&[]);
+        let directory = fixture.0.path().to_str().unwrap().to_owned();
+        assert_eq!(
+            run_util_stdin(run, &["sort", &directory], b""),
+            (
+                2,
+                String::new(),
+                format!("sort: read failed: {directory}: Is a directory\n")
+            )
+        );
+        assert_eq!(
+            run_util_stdin(run, &["sort", "-o", &directory], b"a\n"),
+            (
+                2,
+                String::new(),
+                format!("sort: open failed: {directory}: Is a directory\n")
+            )
+        );
+    }
+
+    #[test]
+    fn test_comparator() {
+        assert_eq!(Comparator::Bytes.compare(b"a", b"b"), Ordering::Less);
+        assert_eq!(Comparator::Bytes.compare(b"a", b"a"), Ordering::Equal);
+        assert_eq!(Comparator::Bytes.compare(b"ab", b"a"), Ordering::Greater);
+        assert_eq!(Comparator::Bytes.compare(b"\xff", b"a"), Ordering::Greater);
+
+        for (a, b, ordering) in [
+            (&b"2"[..], &b"10"[..], Ordering::Less),
+            (b"-2", b"-10", Ordering::Greater),
+            (b" 1.50", b"1.5", Ordering::Equal),
+            (b"-0", b"", Ordering::Equal),
+            (b"-0.0", b"0", Ordering::Equal),
+            (b"-0.01", b"0", Ordering::Less),
+            (b"0.01", b".009", Ordering::Greater),
+            (b"00", b"x", Ordering::Equal),
+            (b"+1", b"0", Ordering::Equal),
+        ] {
+            assert_eq!(Comparator::Numeric.compare(a, b), ordering, "{a:?} {b:?}");
+            assert_eq!(Comparator::Numeric.compare(b, a), ordering.reverse());
+        }
+    }
+
+    /// Sort lines given as a string, returning the output.
+    fn sort_text(args: &[&str], input: &str) -> String {
+        let command_line: Vec<&str> = std::iter::once("sort")
+            .chain(args.iter().copied())
+            .collect();
+        let (code, stdout, stderr) = run_util_stdin(run, &command_line, input.as_bytes());
+        assert_eq!((code, stderr.as_str()), (0, ""), "{args:?}");
+        stdout
+    }
+
+    #[test]
+    fn test_numeric() {
+        assert_eq!(sort_text(&["-n"], "10\n9\n1\n"), "1\n9\n10\n");
+        assert_eq!(
+            sort_text(&["--numeric-sort"], "-10\n-9\n-1.5\n-1\n0\n"),
+            "-10\n-9\n-1.5\n-1\n0\n"
+        );
+        // Leading zeros and blanks do not count, and the fraction is compared digit by digit.
+        assert_eq!(
+            sort_text(&["-n"], "010\n  9\n\t8\n1.10\n1.05\n1.1\n"),
+            "1.05\n1.1\n1.10\n\t8\n  9\n010\n"
+        );
+        assert_eq!(
+            sort_text(
+                &["-n"],
+                "100000000000000000000001\n100000000000000000000000\n"
+            ),
+            "100000000000000000000000\n100000000000000000000001\n"
+        );
+        // Lines without a number compare as zero, and so do `+1`, `-`, and `-0`: the bytes break the tie,
+        // which puts the lines without numbers first.
+        assert_eq!(
+            sort_text(&["-n"], "10\n9\nabc\n0\n-1\n\n1\n+1\n-\n-0\n"),
+            "-1\n\n+1\n-\n-0\n0\nabc\n1\n9\n10\n"
+        );
+        // No exponents or thousands separators in the C locale.
+        assert_eq!(
+            sort_text(&["-n"], "1e3\n999\n2,000\n3\n"),
+            "1e3\n2,000\n3\n999\n"
+        );
+        assert_eq!(sort_text(&["-n"], "-.5\n.5\n1.\n"), "-.5\n.5\n1.\n");
+    }
+
+    #[test]
+    fn test_last_resort() {
+        // Equal numbers are ordered by their bytes, and so are equal lines with -f.
+        assert_eq!(sort_text(&["-n"], "1b\n01\n1a\n"), "01\n1a\n1b\n");
+        assert_eq!(sort_text(&["-f"], "a\nA\nb\n"), "A\na\nb\n");
+        // The last resort is reversed too.
+        assert_eq!(sort_text(&["-rn"], "1a\n1b\n2\n"), "2\n1b\n1a\n");
+    }
+
+    #[test]
+    fn test_reverse() {
+        assert_eq!(sort_text(&["-r"], "b\nc\na\n"), "c\nb\na\n");
+        assert_eq!(
+            sort_text(&["--reverse", "-n"], "10\n9\n-1\n"),
+            "10\n9\n-1\n"
+        );
+        assert_eq!(sort_text(&["-r"], ""), "");
+    }
+
+    #[test]
+    fn test_ignore_case() {
+        // Letters are folded to upper case, so `_` sorts after them.
+        assert_eq!(sort_text(&["-f"], "b\n_\nB\na\n"), "a\nB\nb\n_\n");
+        assert_eq!(sort_text(&["--ignore-case"], "é\nE\n"), "E\né\n");
+    }
+
+    #[test]
+    fn test_unique() {
+        assert_eq!(sort_text(&["-u"], "b\na\nb\na\n"), "a\nb\n");
+        // Lines which compare equal are duplicates, and the first of them is kept.
+        assert_eq!(sort_text(&["-un"], "007\n7\n3\n08\n"), "3\n007\n08\n");
+        assert_eq!(sort_text(&["-un"], "7\n007\n"), "7\n");
+        assert_eq!(sort_text(&["-fu"], "a\nA\nb\n"), "a\nb\n");
+        assert_eq!(sort_text(&["--unique", "-n"], "x\n\n0\n"), "x\n");
+        assert_eq!(sort_text(&["-run"], "007\n7\n3\n08\n"), "08\n007\n3\n");
+        assert_eq!(sort_text(&["-ru"], "a\nb\na\n"), "b\na\n");
+    }
+
+    #[test]
+    fn test_keys() {
+        // A key without an end goes to the end of the line.
+        assert_eq!(sort_text(&["-k2"], "a c\nb b\nc a\n"), "c a\nb b\na c\n");
+        assert_eq!(
+            sort_text(&["-k", "2,2"], "x b z\ny b a\nz a q\n"),
+            "z a q\nx b z\ny b a\n"
+        );
+        // The blanks before a field belong to it.
+        assert_eq!(
+            sort_text(&["-k2,2"], "a  c\nb b\nc   a\n"),
+            "c   a\na  c\nb b\n"
+        );
+        assert_eq!(
+            sort_text(&["-k2b,2"], "a  c\nb b\nc   a\n"),
+            "c   a\nb b\na  c\n"
+        );
+        assert_eq!(
+            sort_text(&["--key=1.2,1.2"], "ab\nba\naa\nb\n"),
+            "b\naa\nba\nab\n"
+        );
+        // A key which starts past the end of the line is empty.
+        assert_eq!(sort_text(&["-k5"], "a b\nb a\n"), "a b\nb a\n");
+        assert_eq!(
+            sort_text(&["-k1.5"], "abc\nab\nabcdef\nabcdeg\n"),
+            "ab\nabc\nabcdef\nabcdeg\n"
+        );
+        // So is one which ends before it starts.
+        assert_eq!(sort_text(&["-k1.5,1.2"], "b\na\n"), "a\nb\n");
+        // The keys are compared in order.
+        assert_eq!(
+            sort_text(&["-t,", "-k2,2", "-k1,1"], "b,x\na,x\nc,a\n"),
+            "c,a\na,x\nb,x\n"
+        );
+    }
+
+    #[test]
+    fn test_key_characters() {
+        let input = "x abcd\ny bbbb\nz  zab\n";
+        assert_eq!(sort_text(&["-k2.2,2.3"], input), "z  zab\nx abcd\ny bbbb\n");
+        // The global -b applies to both ends of keys without letters.
+        assert_eq!(
+            sort_text(&["-b", "-k2.2,2.3"], input),
+            "z  zab\ny bbbb\nx abcd\n"
+        );
+        assert_eq!(
+            sort_text(&["-k2.2b,2.3"], input),
+            "z  zab\nx abcd\ny bbbb\n"
+        );
+        assert_eq!(
+            sort_text(&["-t:", "-k2.1,2.0"], "a:xy:1\nb:ab:2\n"),
+            "b:ab:2\na:xy:1\n"
+        );
+    }
+
+    #[test]
+    fn test_key_options() {
+        assert_eq!(
+            sort_text(&["-t:", "-k2,2n", "-k1,1r"], "a:3\nb:1\nc:3\nd:2\n"),
+            "b:1\nd:2\nc:3\na:3\n"
+        );
+        // The global options apply to keys without letters, and to the last resort.
+        assert_eq!(sort_text(&["-n", "-k2"], "x 10\ny 9\n"), "y 9\nx 10\n");
+        assert_eq!(
+            sort_text(&["-r", "-k1,1"], "a 1\na 2\nb 0\n"),
+            "b 0\na 2\na 1\n"
+        );
+        assert_eq!(
+            sort_text(&["-k2n", "-r"], "x 10\ny 9\nz 9\n"),
+            "z 9\ny 9\nx 10\n"
+        );
+        assert_eq!(sort_text(&["-k1f"], "b\nA\na\n"), "A\na\nb\n");
+        // Only the keys count for -u.
+        assert_eq!(sort_text(&["-u", "-k2"], "b x\na x\nc y\n"), "b x\nc y\n");
+    }
+
+    #[test]
+    fn test_field_separator() {
+        assert_eq!(
+            sort_text(&["-t:", "-k3"], "a:b\nc:d:e\nf\n"),
+            "a:b\nf\nc:d:e\n"
+        );
+        // Blanks are not special with a separator.
+        assert_eq!(
+            sort_text(&["--field-separator", " ", "-k2"], "a  b\nb a\n"),
+            "a  b\nb a\n"
+        );
+        assert_eq!(
+            sort_text(&["-t", ":", "-t:", "-k2"], "a:2\nb:1\n"),
+            "b:1\na:2\n"
+        );
+        assert_eq!(
+            run_util_stdin(run, &["sort", "-z", "-t", "\\0", "-k2"], b"a\0"),
+            (0, "a\0".to_owned(), String::new())
+        );
+    }
+
+    #[test]
+    fn test_invalid_keys() {
+        for (args, message) in [
+            (
+                &["-k", "0"][..],
+                "field number is zero: invalid field specification '0'",
+            ),
+            (
+                &["-k", "1.0"],
+                "character offset is zero: invalid field specification '1.0'",
+            ),
+            (
+                &["-k", "x"],
+                "invalid number at field start: invalid count at start of 'x'",
+            ),
+            (
+                &["-k", "1x"],
+                "stray character in field spec: invalid field specification '1x'",
+            ),
+            (
+                &["-k", "1,0"],
+                "field number is zero: invalid field specification '1,0'",
+            ),
+            (
+                &["-k", "1,x"],
+                "invalid number after ',': invalid count at start of 'x'",
+            ),
+            (
+                &["-k", "1."],
+                "invalid number after '.': invalid count at start of ''",
+            ),
+            (
+                &["-k", ""],
+                "invalid number at field start: invalid count at start of ''",
+            ),
+            (&["-t", "ab"], "multi-character tab 'ab'"),
+            (&["-t", ""], "empty tab"),
+            (&["-ta", "-tb"], "incompatible tabs"),
+        ] {
+            let command_line: Vec<&str> = std::iter::once("sort")
+                .chain(args.iter().copied())
+                .collect();
+            assert_eq!(
+                run_util_stdin(run, &command_line, b"a\n"),
+                (2, String::new(), format!("sort: {message}\n")),
+                "{args:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_key() {
+        let (key, letters) = parse_key("2.3b,4.5Vr").unwrap();
+        assert_eq!(
+            key,
+            Key {
+                start: Position {
+                    field: 1,
+                    character: 2,
+                    skip_blanks: true,
+                },
+                end: Some(Position {
+                    field: 3,
+                    character: 5,
+                    skip_blanks: false,
+                }),
+                options: KeyOptions::default(),
+            }
+        );
+        assert_eq!(
+            letters.unwrap().options(),
+            Ok(KeyOptions {
+                comparator: Comparator::Version,
+                fold_case: false,
+                reverse: true,
+            })
+        );
+        // Keys without letters take the global options, and b is a letter.
+        assert_eq!(parse_key("1,1").unwrap().1, None);
+        assert_eq!(parse_key("1b").unwrap().1, Some(Letters::default()));
+        // Large numbers saturate.
+        let (key, _) = parse_key("99999999999999999999999").unwrap();
+        assert_eq!(key.start.field, usize::MAX - 1);
+    }
+
+    #[test]
+    fn test_human_numeric() {
+        assert_eq!(
+            sort_text(&["-h"], "10M\n2G\n900K\n 3M\n2\n1000\n"),
+            "2\n1000\n900K\n 3M\n10M\n2G\n"
+        );
+        assert_eq!(
+            sort_text(&["--human-numeric-sort", "-r"], "1K\n-1K\n1\n"),
+            "1K\n1\n-1K\n"
+        );
+        // Zero has no unit, and equal sizes are ordered by their bytes.
+        assert_eq!(
+            sort_text(&["-h"], "0K\n5\n0.0M\n1k\n1K\n"),
+            "0.0M\n0K\n5\n1K\n1k\n"
+        );
+        assert_eq!(
+            sort_text(&["-k2h"], "a 1G\nb 5M\nc 7\n"),
+            "c 7\nb 5M\na 1G\n"
+        );
+    }
+
+    #[test]
+    fn test_version() {
+        assert_eq!(
+            sort_text(&["-V"], "1.0.10\n1.0.5\nfoo\nfoo~beta\n1.0.5~rc1\n"),
+            "1.0.5~rc1\n1.0.5\n1.0.10\nfoo~beta\nfoo\n"
+        );
+        assert_eq!(
+            sort_text(&["--version-sort"], "b\nA\na\nB\n"),
+            "A\nB\na\nb\n"
+        );
+        assert_eq!(sort_text(&["-fV"], "b\nA\na\nB\n"), "A\na\nB\nb\n");
+        assert_eq!(
+            sort_text(&["-t-", "-k2V", "-k1,1"], "b-1.10\na-1.9\nc-1.9\n"),
+            "a-1.9\nc-1.9\nb-1.10\n"
+        );
+    }
+
+    #[test]
+    fn test_incompatible_orders() {
+        for (args, letters) in [
+            (&["-n", "-h"][..], "hn"),
+            (&["-nhV"], "hnV"),
+            (&["-rfnh"], "fhn"),
+            (&["-k1nh"], "hn"),
+            (&["-k1fb,1Vn"], "fnV"),
+            // The keys are checked first, then the global options, which keys without letters take.
+            (&["-n", "-h", "-k1nV"], "nV"),
+            (&["-n", "-h", "-k1"], "hn"),
+        ] {
+            let command_line: Vec<&str> = std::iter::once("sort")
+                .chain(args.iter().copied())
+                .collect();
+            assert_eq!(
+                run_util_stdin(run, &command_line, b"a\n"),
+                (
+                    2,
+                    String::new(),
+                    format!("sort: options '-{letters}' are incompatible\n")
+                ),
+                "{args:?}"
+            );
+        }
+        assert_eq!(sort_text(&["-n", "-k1,1h"], "2M\n1G\n"), "2M\n1G\n");
+    }
+
+    #[test]
+    fn test_run_write_error() {
+        let args: Vec<OsString> = vec!["sort".into()];
+        let mut stdout = io::Cursor::new([0; 4]);
+        let mut stderr = Vec::new();
+        let code = run(
+            &args,
+            &mut &b"line 2\nline 1\n"[..],
+            &mut stdout,
+            &mut stderr,
+        );
+        assert_eq!(code, 2);
+        assert!(String::from_utf8(stderr)
+            .unwrap()
+            .starts_with("sort: write failed: 'standard output': "));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_broken_pipe() {
+        use assert_cmd::cargo::CommandCargoExt;
+        use std::os::unix::process::ExitStatusExt;
+        use std::process::Stdio;
+
+        let content: String = (0..200_000).rev().map(|n| format!("{n}\n")).collect();
+        let fixture = Fixture::new(&[("a", &content)]);
+        let mut child = std::process::Command::cargo_bin("sort")
+            .unwrap()
+            .arg(fixture.path("a"))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        drop(child.stdout.take());
+        let output = child.wait_with_output().unwrap();
+        assert_eq!(output.status.signal(), Some(13));
+        assert!(output.stderr.is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_full_disk() {
+        use assert_cmd::prelude::*;
+
+        let fixture = Fixture::new(&[("a", "a\n")]);
+        let output = std::process::Command::cargo_bin("sort")
+            .unwrap()
+            .arg(fixture.path("a"))
+            .stdout(File::create("/dev/full").unwrap())
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(2));
+        assert_eq!(
+            String::from_utf8(output.stderr).unwrap(),
+            "sort: write failed: 'standard output': No space left on device\n"
+        );
+
+        let output = std::process::Command::cargo_bin("sort")
+            .unwrap()
+            .args(["-o", "/dev/full", &fixture.path("a")])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(2));
+        assert_eq!(
+            String::from_utf8(output.stderr).unwrap(),
+            "sort: write failed: /dev/full: No space left on device\n"
+        );
+    }
+}
@@ -0,0 +1,707 @@
+//! Split a file into pieces of a number of lines or bytes, written to files named by a prefix and a suffix.
+//!
+//! The suffixes are `aa`, `ab`, …, `zz` with `-a 2`, and fail once they are exhausted. Without `-a`, they
+//! are widened like GNU's instead: the suffixes starting with `z` are kept, so that after `yz` comes `zaaa`,
+//! after `zyzz` comes `zzaaaa`, and so on, keeping the names in order. The output files are only created
+//! when there is something to write to them, so an empty input creates none. GNU's `-n`, `-d`, `-x`,
+//! `--filter`, and the other options which change the suffixes or the kind of pieces are not supported.
+//!
+//! ```
+//! use rust_coreutils::split::{split, Mode, SplitOptions};
+//!
+//! let directory = tempfile::tempdir().unwrap();
+//! let options = SplitOptions {
+//!     mode: Mode::Lines(2),
+//!     prefix: directory.path().join("part.").into_os_string(),
+//!     ..SplitOptions::default()
+//! };
+//! split(&mut &b"1\n2\n3\n"[..], None, &options).unwrap();
+//! let read = |name| std::fs::read_to_string(directory.path().join(name)).unwrap();
+//! assert_eq!((read("part.aa"), read("part.ab")), ("1\n2\n".to_string(), "3\n".to_string()));
+//! ```
+
+use crate::common::error::{io_error_message, Reporter, UError, UResult, EXIT_FAILURE};
+use crate::common::input::{InputIter, BUFFER_SIZE};
+use crate::common::paths::same_file;
+use crate::common::size::{parse_size_u64, SizeError, SizeErrorKind};
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use clap::Parser;
+use std::ffi::OsString;
+use std::fs::{self, File, Metadata, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "split",
+    author,
+    version,
+    about = "Write pieces of INPUT to the files PREFIXaa, PREFIXab, and so on, 1000 lines each by default.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Use suffixes of N letters, failing once they are exhausted, instead of widening them as needed.
+    #[arg(short = 'a', long, value_name = "N", allow_hyphen_values = true)]
+    suffix_length: Option<String>,
+
+    /// Write SIZE bytes to each file. SIZE may have a suffix, like K (1024) or KB (1000).
+    #[arg(short = 'b', long, value_name = "SIZE", allow_hyphen_values = true)]
+    bytes: Option<String>,
+
+    /// Write as many complete lines as fit in SIZE bytes to each file, splitting the longer lines.
+    #[arg(short = 'C', long, value_name = "SIZE", allow_hyphen_values = true)]
+    line_bytes: Option<String>,
+
+    /// Write N lines to each file.
+    #[arg(short = 'l', long, value_name = "N", allow_hyphen_values = true)]
+    lines: Option<String>,
+
+    /// The file to split. With no INPUT, or when INPUT is -, standard input is read.
+    #[arg(value_name = "INPUT")]
+    input: Option<OsString>,
+
+    /// The start of the names of the output files, which is x by default.
+    #[arg(value_name = "PREFIX")]
+    prefix: Option<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "split";
+}
+
+/// The description of split for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run split with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run split with the given command line, including the program name, on the given streams, and return the
+/// exit code.
+pub fn run(
+    args: &[OsString],
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(args.iter().cloned(), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let options = match options(&args) {
+        Ok(options) => options,
+        Err(error) => return Reporter::new("split", stderr).finish(Err(error)),
+    };
+    let mut inputs = InputIter::with_stdin(
+        args.input.into_iter().collect(),
+        Box::new(BufReader::with_capacity(BUFFER_SIZE, stdin)),
+    );
+    let result = match inputs.next_buffered().expect("there is one input") {
+        Ok(mut input) => {
+            let path = input
+                .operand
+                .as_ref()
+                .filter(|_| !input.is_stdin())
+                .map(Path::new);
+            split(&mut input.reader, path, &options)
+        }
+        Err(error) => Err(UError::new(
+            EXIT_FAILURE,
+            format!(
+                "cannot open '{}' for reading: {}",
+                error.display_name(),
+                io_error_message(&error.error)
+            ),
+        )),
+    };
+    Reporter::new("split", stderr).finish(result)
+}
+
+/// Get the options of the command line.
+fn options(args: &Args) -> UResult<SplitOptions> {
+    let invalid = |what: &str, error: SizeError| {
+        UError::new(EXIT_FAILURE, format!("invalid {what}: {error}"))
+    };
+    let positive = |what: &str, arg: &str, count: u64| match count {
+        0 => Err(UError::new(
+            EXIT_FAILURE,
+            format!("invalid {what}: '{arg}': Numerical result out of range"),
+        )),
+        count => Ok(count),
+    };
+
+    let bytes = |bytes: &str| {
+        parse_size_u64(bytes)
+            .map_err(|error| invalid("number of bytes", error))
+            .and_then(|size| positive("number of bytes", bytes, size))
+    };
+    let mode = match (&args.lines, &args.bytes, &args.line_bytes) {
+        (None, None, None) => Mode::default(),
+        (Some(lines), None, None) => parse_count(lines)
+            .map_err(|error| invalid("number of lines", error))
+            .and_then(|count| positive("number of lines", lines, count))
+            .map(Mode::Lines)?,
+        (None, Some(size), None) => Mode::Bytes(bytes(size)?),
+        (None, None, Some(size)) => Mode::LineBytes(bytes(size)?),
+        _ => {
+            return Err(UError::new(
+                EXIT_FAILURE,
+                "cannot split in more than one way",
+            ))
+        }
+    };
+    let suffix_length = match &args.suffix_length {
+        Some(length) => parse_count(length).map_err(|error| invalid("suffix length", error))?,
+        None => 0,
+    };
+
+    Ok(SplitOptions {
+        mode,
+        prefix: args.prefix.clone().unwrap_or_else(|| OsString::from("x")),
+        // Like GNU, a length of 0 is the default, widened as needed.
+        suffix_length: Some(suffix_length).filter(|&length| length > 0),
+    })
+}
+
+/// Parse a count of lines or letters, which are decimal digits alone.
+fn parse_count<T: FromStr>(arg: &str) -> Result<T, SizeError> {
+    let error = |kind| SizeError {
+        kind,
+        input: arg.to_string(),
+    };
+    if arg.is_empty() || !arg.bytes().all(|c| c.is_ascii_digit()) {
+        return Err(error(SizeErrorKind::Invalid));
+    }
+    arg.parse().map_err(|_| error(SizeErrorKind::TooLarge))
+}
+
+/// How the input is split.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Each file has this many lines.
+    Lines(u64),
+    /// Each file has this many bytes.
+    Bytes(u64),
+    /// Each file has as many complete lines as fit in this many bytes, and the lines which are longer are
+    /// split in pieces of this many bytes.
+    LineBytes(u64),
+}
+
+impl Default for Mode {
+    fn default() -> Self {
+        Mode::Lines(1000)
+    }
+}
+
+/// The options of split.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SplitOptions {
+    /// How the input is split.
+    pub mode: Mode,
+    /// The start of the names of the output files, which may include directories.
+    pub prefix: OsString,
+    /// The number of letters of the suffixes, which are exhausted after `z…z`, or `None` for 2 letters,
+    /// widened as needed.
+    pub suffix_length: Option<usize>,
+}
+
+impl Default for SplitOptions {
+    fn default() -> Self {
+        SplitOptions {
+            mode: Mode::default(),
+            prefix: OsString::from("x"),
+            suffix_length: None,
+        }
+    }
+}
+
+/// The suffixes of the output files, in order.
+///
+/// ```
+/// use rust_coreutils::split::Suffixes;
+///
+/// let suffixes: Vec<String> = Suffixes::new(None).skip(648).take(3).collect();
+/// assert_eq!(suffixes, ["yy", "yz", "zaaa"]);
+/// assert_eq!(Suffixes::new(Some(1)).count(), 26);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Suffixes {
+    /// The number of `z`s before the letters, one more each time the suffixes are widened.
+    widened: usize,
+    /// The letters, from 0 for `a` to 25 for `z`, which are empty once the suffixes are exhausted.
+    letters: Vec<u8>,
+    /// Whether the suffixes are widened instead of being exhausted.
+    widen: bool,
+    /// Whether the first suffix was returned.
+    started: bool,
+}
+
+impl Suffixes {
+    /// Get the suffixes of `length` letters, or of 2 letters, widened as needed, with `None`.
+    pub fn new(length: Option<usize>) -> Self {
+        Suffixes {
+            widened: 0,
+            letters: vec![0; length.unwrap_or(2)],
+            widen: length.is_none(),
+            started: false,
+        }
+    }
+
+    /// Move to the next suffix.
+    fn advance(&mut self) {
+        let Some(last) = self.letters.iter().rposition(|&letter| letter < 25) else {
+            self.letters.clear();
+            return;
+        };
+        self.letters[last] += 1;
+        self.letters[last + 1..].fill(0);
+        // The suffixes starting with `z` are not used, but widened with one more letter, so that they sort
+        // after the shorter ones.
+        if self.widen && self.letters[0] == 25 {
+            self.widened += 1;
+            self.letters = vec![0; self.letters.len() + 1];
+        }
+    }
+}
+
+impl Iterator for Suffixes {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        match self.started {
+            true => self.advance(),
+            false => self.started = true,
+        }
+        if self.letters.is_empty() {
+            return None;
+        }
+        let letters = self.letters.iter().map(|&letter| char::from(b'a' + letter));
+        Some("z".repeat(self.widened).chars().chain(letters).collect())
+    }
+}
+
+/// Split the input read from `reader` into files, as the options say.
+///
+/// `input` is the path of the input, if it is a file, so that it is not overwritten by an output file.
+pub fn split(
+    reader: &mut dyn BufRead,
+    input: Option<&Path>,
+    options: &SplitOptions,
+) -> UResult<()> {
+    let name = input.map_or_else(|| "-".to_string(), |path| path.display().to_string());
+    let mut outputs = Outputs {
+        prefix: &options.prefix,
+        suffixes: Suffixes::new(options.suffix_length),
+        input: input.and_then(|path| Some((path, fs::metadata(path).ok()?))),
+        file: None,
+    };
+    let read_error = |error: io::Error| UError::io(&name, &error);
+
+    match options.mode {
+        Mode::Lines(count) => {
+            // The lines written to the current file, which is full before the first one is created.
+            let mut lines = count;
+            loop {
+                let buffer = reader.fill_buf().map_err(read_error)?;
+                if buffer.is_empty() {
+                    break;
+                }
+                if lines == count {
+                    outputs.next()?;
+                    lines = 0;
+                }
+                let mut end = buffer.len();
+                for (i, _) in buffer.iter().enumerate().filter(|(_, &byte)| byte == b'\n') {
+                    lines += 1;
+                    if lines == count {
+                        end = i + 1;
+                        break;
+                    }
+                }
+                outputs.write(&buffer[..end])?;
+                reader.consume(end);
+            }
+        }
+        Mode::Bytes(count) => {
+            let mut left = 0;
+            loop {
+                let buffer = reader.fill_buf().map_err(read_error)?;
+                if buffer.is_empty() {
+                    break;
+                }
+                if left == 0 {
+                    outputs.next()?;
+                    left = count;
+                }
+                let end = buffer
+                    .len()
+                    .min(usize::try_from(left).unwrap_or(usize::MAX));
+                outputs.write(&buffer[..end])?;
+                reader.consume(end);
+                left -= end as u64;
+            }
+        }
+        Mode::LineBytes(count) => {
+            // The bytes written to the current file, which is full before the first one is created.
+            let mut used = count;
+            let mut line = Vec::new();
+            loop {
+                // A line, or as much of it as fits in a file.
+                line.clear();
+                let length = reader
+                    .take(count)
+                    .read_until(b'\n', &mut line)
+                    .map_err(read_error)?;
+                if length == 0 {
+                    break;
+                }
+                if used + length as u64 > count {
+                    outputs.next()?;
+                    used = 0;
+                }
+                outputs.write(&line)?;
+                used += length as u64;
+            }
+        }
+    }
+    outputs.flush()
+}
+
+/// The output files, created one after the other.
+struct Outputs<'a> {
+    prefix: &'a OsString,
+    suffixes: Suffixes,
+    /// The path and metadata of the input, if it is a file.
+    input: Option<(&'a Path, Metadata)>,
+    /// The current file and its path.
+    file: Option<(PathBuf, BufWriter<File>)>,
+}
+
+impl Outputs<'_> {
+    /// Finish the current file, if any, and create the next one.
+    fn next(&mut self) -> UResult<()> {
+        self.flush()?;
+        let suffix = self
+            .suffixes
+            .next()
+            .ok_or_else(|| UError::new(EXIT_FAILURE, "output file suffixes exhausted"))?;
+        let mut name = self.prefix.clone();
+        name.push(suffix);
+        let path = PathBuf::from(name);
+        let file = create(&path, self.input.as_ref())?;
+        self.file = Some((path, BufWriter::with_capacity(BUFFER_SIZE, file)));
+        Ok(())
+    }
+
+    /// Write to the current file.
+    fn write(&mut self, bytes: &[u8]) -> UResult<()> {
+        let (path, writer) = self.file.as_mut().expect("a file was created");
+        writer
+            .write_all(bytes)
+            .map_err(|error| UError::io(path.display(), &error))
+    }
+
+    /// Finish the current file, if any.
+    fn flush(&mut self) -> UResult<()> {
+        match self.file.take() {
+            Some((path, mut writer)) => writer
+                .flush()
+                .map_err(|error| UError::io(path.display(), &error)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Create an output file, or empty it, unless it is the input.
+fn create(path: &Path, input: Option<&(&Path, Metadata)>) -> UResult<File> {
+    let error = |error: io::Error| UError::io(path.display(), &error);
+    // The file is only emptied once it is known not to be the input.
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .map_err(error)?;
+    if let Some((input_path, input_metadata)) = input {
+        let metadata = file.metadata().map_err(error)?;
+        if same_file(input_metadata, &metadata, input_path, path) {
+            return Err(UError::new(
+                EXIT_FAILURE,
+                format!("'{}' would overwrite input; aborting", path.display()),
+            ));
+        }
+    }
+    file.set_len(0).map_err(error)?;
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::run_util_stdin;
+
+    /// A directory in which to split, since the output files cannot be created in the current one.
+    struct Fixture {
+        directory: tempfile::TempDir,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            Fixture {
+                directory: tempfile::tempdir().unwrap(),
+            }
+        }
+
+        fn path(&self, name: &str) -> String {
+            self.directory.path().join(name).display().to_string()
+        }
+
+        /// Split `input` with the prefix `x` in the directory, returning the names and contents of the files.
+        fn split(&self, args: &[&str], input: &str) -> Vec<(String, String)> {
+            let prefix = self.path("x");
+            let command_line = [&["split"], args, &["-", &prefix]].concat();
+            assert_eq!(
+                run_util_stdin(run, &command_line, input.as_bytes()),
+                (0, String::new(), String::new())
+            );
+            self.files()
+        }
+
+        /// Get the names and contents of the files in the directory, sorted by name, removing them.
+        fn files(&self) -> Vec<(String, String)> {
+            let mut files: Vec<_> = fs::read_dir(self.directory.path())
+                .unwrap()
+                .map(|entry| {
+                    let path = entry.unwrap().path();
+                    let content = fs::read_to_string(&path).unwrap();
+                    fs::remove_file(&path).unwrap();
+                    (
+                        path.file_name().unwrap().to_string_lossy().into_owned(),
+                        content,
+                    )
+                })
+                .collect();
+            files.sort();
+            files
+        }
+    }
+
+    /// Pair each name with its content.
+    fn files(files: &[(&str, &str)]) -> Vec<(String, String)> {
+        files
+            .iter()
+            .map(|(name, content)| (name.to_string(), content.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_lines() {
+        let fixture = Fixture::new();
+        assert_eq!(
+            fixture.split(&["-l", "2"], "1\n2\n3\n4\n"),
+            files(&[("xaa", "1\n2\n"), ("xab", "3\n4\n")])
+        );
+        // The last file has what is left, even without a newline.
+        assert_eq!(
+            fixture.split(&["-l", "2"], "1\n2\n3\n4\n5"),
+            files(&[("xaa", "1\n2\n"), ("xab", "3\n4\n"), ("xac", "5")])
+        );
+        assert_eq!(
+            fixture.split(&[], &"line\n".repeat(1001)),
+            files(&[("xaa", &"line\n".repeat(1000)), ("xab", "line\n")])
+        );
+        // Lines spanning the reads are counted once.
+        let line = "x".repeat(BUFFER_SIZE);
+        assert_eq!(
+            fixture.split(&["-l", "1"], &format!("{line}\n{line}\n")),
+            files(&[("xaa", &format!("{line}\n")), ("xab", &format!("{line}\n"))])
+        );
+        assert_eq!(fixture.split(&["-l", "1"], ""), []);
+    }
+
+    #[test]
+    fn test_bytes() {
+        let fixture = Fixture::new();
+        assert_eq!(
+            fixture.split(&["-b", "3"], "abcdef"),
+            files(&[("xaa", "abc"), ("xab", "def")])
+        );
+        assert_eq!(
+            fixture.split(&["-b", "4"], "ab\ncd\nef"),
+            files(&[("xaa", "ab\nc"), ("xab", "d\nef")])
+        );
+        let content = "x".repeat(3 * BUFFER_SIZE + 1);
+        let [first, second] = [&content[..2 * BUFFER_SIZE], &content[2 * BUFFER_SIZE..]];
+        assert_eq!(
+            fixture.split(&["-b", "128K"], &content),
+            files(&[("xaa", first), ("xab", second)])
+        );
+        assert_eq!(fixture.split(&["-b", "1"], ""), []);
+    }
+
+    #[test]
+    fn test_line_bytes() {
+        let fixture = Fixture::new();
+        // The lines are kept whole, unless they are longer than a file.
+        assert_eq!(
+            fixture.split(&["-C", "4"], "ab\ncdefghi\nj\nk\n"),
+            files(&[
+                ("xaa", "ab\n"),
+                ("xab", "cdef"),
+                ("xac", "ghi\n"),
+                ("xad", "j\nk\n")
+            ])
+        );
+        assert_eq!(
+            fixture.split(&["-C", "4"], "a\nb\nc\nd"),
+            files(&[("xaa", "a\nb\n"), ("xab", "c\nd")])
+        );
+        assert_eq!(
+            fixture.split(&["-C", "4"], "abcdefghij"),
+            files(&[("xaa", "abcd"), ("xab", "efgh"), ("xac", "ij")])
+        );
+    }
+
+    #[test]
+    fn test_suffixes() {
+        let mut suffixes = Suffixes::new(None);
+        assert_eq!(suffixes.next().as_deref(), Some("aa"));
+        assert_eq!(suffixes.next().as_deref(), Some("ab"));
+        assert_eq!(suffixes.nth(23).as_deref(), Some("az"));
+        assert_eq!(suffixes.next().as_deref(), Some("ba"));
+        // Each time the suffixes would start with a `z`, another `z` and letter are added.
+        let widened: Vec<String> = Suffixes::new(None).skip(648).take(3).collect();
+        assert_eq!(widened, ["yy", "yz", "zaaa"]);
+        let widened: Vec<String> = Suffixes::new(None)
+            .skip(650 + 25 * 676 - 1)
+            .take(2)
+            .collect();
+        assert_eq!(widened, ["zyzz", "zzaaaa"]);
+
+        assert_eq!(
+            Suffixes::new(Some(1)).collect::<String>(),
+            "abcdefghijklmnopqrstuvwxyz"
+        );
+        assert_eq!(Suffixes::new(Some(3)).count(), 26 * 26 * 26);
+        assert_eq!(Suffixes::new(Some(3)).last().as_deref(), Some("zzz"));
+    }
+
+    #[test]
+    fn test_suffix_length() {
+        let fixture = Fixture::new();
+        let input = "1\n".repeat(652);
+        let names: Vec<String> = fixture
+            .split(&["-l", "1"], &input)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names.len(), 652);
+        assert_eq!(&names[648..], ["xyy", "xyz", "xzaaa", "xzaab"]);
+
+        assert_eq!(
+            fixture.split(&["-a", "3", "-l", "2"], "1\n2\n3\n"),
+            files(&[("xaaa", "1\n2\n"), ("xaab", "3\n")])
+        );
+        assert_eq!(fixture.split(&["-a", "0", "-l", "2"], "1\n").len(), 1);
+
+        // The files are written until the suffixes are exhausted.
+        let prefix = fixture.path("x");
+        assert_eq!(
+            run_util_stdin(
+                run,
+                &["split", "-a", "1", "-b", "1", "-", &prefix],
+                [b'.'; 27]
+            ),
+            (
+                EXIT_FAILURE,
+                String::new(),
+                "split: output file suffixes exhausted\n".to_string()
+            )
+        );
+        let names: String = fixture.files().iter().map(|(name, _)| &name[1..]).collect();
+        assert_eq!(names, "abcdefghijklmnopqrstuvwxyz");
+    }
+
+    #[test]
+    fn test_files() {
+        let fixture = Fixture::new();
+        let input = fixture.path("input");
+        fs::write(&input, "1\n2\n3\n").unwrap();
+        let prefix = fixture.path("part-");
+        assert_eq!(
+            run_util_stdin(run, &["split", "-l2", &input, &prefix], b""),
+            (0, String::new(), String::new())
+        );
+        assert_eq!(
+            fixture.files(),
+            files(&[
+                ("input", "1\n2\n3\n"),
+                ("part-aa", "1\n2\n"),
+                ("part-ab", "3\n")
+            ])
+        );
+
+        // The input is not overwritten, nor the outputs before it.
+        let input = fixture.path("xab");
+        fs::write(&input, "1\n2\n3\n").unwrap();
+        assert_eq!(
+            run_util_stdin(run, &["split", "-l1", &input, &fixture.path("x")], b""),
+            (
+                EXIT_FAILURE,
+                String::new(),
+                format!("split: '{input}' would overwrite input; aborting\n")
+            )
+        );
+        assert_eq!(
+            fixture.files(),
+            files(&[("xaa", "1\n"), ("xab", "1\n2\n3\n")])
+        );
+
+        let missing = fixture.path("missing");
+        assert_eq!(
+            run_util_stdin(run, &["split", &missing], b""),
+            (
+                EXIT_FAILURE,
+                String::new(),
+                format!("split: cannot open '{missing}' for reading: No such file or directory\n")
+            )
+        );
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        for (args, message) in [
+            (&["-l", "0"][..], "invalid number of lines: '0': Numerical result out of range"),
+            (&["-l", "2K"], "invalid number of lines: '2K'"),
+            (&["-l", "-1"], "invalid number of lines: '-1'"),
+            (
+                &["-l", "99999999999999999999999"],
+                "invalid number of lines: '99999999999999999999999': Value too large for defined data type",
+            ),
+            (&["-b", "x"], "invalid number of bytes: 'x'"),
+            (&["-C", "0"], "invalid number of bytes: '0': Numerical result out of range"),
+            (&["-a", "x"], "invalid suffix length: 'x'"),
+            (&["-l", "1", "-b", "1"], "cannot split in more than one way"),
+            (&["-b", "1", "-C", "1"], "cannot split in more than one way"),
+        ] {
+            let command_line = [&["split"], args].concat();
+            assert_eq!(
+                run_util_stdin(run, &command_line, b"nothing is written\n"),
+                (EXIT_FAILURE, String::new(), format!("split: {message}\n")),
+                "{args:?}"
+            );
+        }
+    }
+}
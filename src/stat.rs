@@ -0,0 +1,823 @@
+//! Print the status of files: their size, type, owner, permissions, and times.
+//!
+//! The status comes from a single `lstat`, or `stat` with `-L`, kept in a [`FileStat`] which the output is
+//! formatted from, either like GNU by default, or with the `%` directives of `-c` and `--printf`:
+//!
+//! ```
+//! use rust_coreutils::stat::FileStat;
+//!
+//! let directory = tempfile::tempdir().unwrap();
+//! let path = directory.path().join("file");
+//! std::fs::write(&path, "data").unwrap();
+//! let stat = FileStat::new(path.as_os_str(), false).unwrap();
+//! assert_eq!((stat.size, stat.kind), (4, "regular file"));
+//! assert_eq!(stat.format(b"%s %F|%-3s|%q", false), b"4 regular file|4  |%q");
+//! # #[cfg(unix)]
+//! assert_eq!(stat.links, 1);
+//! ```
+
+use crate::common::error::{io_error_message, Reporter, UError, UResult, EXIT_FAILURE};
+use crate::common::escapes::{expand_escape, EscapeFlavor};
+use crate::common::format::{Field, Spec};
+use crate::common::mode::{file_kind, format_mode, format_type_char, st_mode};
+use crate::common::os::os_str_bytes;
+#[cfg(unix)]
+use crate::common::users;
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use chrono::{DateTime, Local};
+use clap::Parser;
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, Metadata};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "stat",
+    author,
+    version,
+    about = "Print the status of each FILE.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Follow the symbolic links.
+    #[arg(short = 'L', long, default_value_t = false)]
+    dereference: bool,
+
+    /// Print the status with FORMAT, followed by a newline, instead of the default one.
+    #[arg(short = 'c', long, value_name = "FORMAT", overrides_with = "printf")]
+    format: Option<OsString>,
+
+    /// Print the status with FORMAT, whose backslash escapes are interpreted, without a newline.
+    #[arg(long, value_name = "FORMAT", overrides_with = "format")]
+    printf: Option<OsString>,
+
+    /// The files to print the status of.
+    #[arg(value_name = "FILE")]
+    files: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "stat";
+}
+
+/// The description of stat for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run stat with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run stat with the given command line, including the program name, on the given streams, and return the
+/// exit code.
+pub fn run(
+    args: &[OsString],
+    _stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(args.iter().cloned(), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let mut reporter = Reporter::new("stat", stderr);
+    let output = match (args.format, args.printf) {
+        (Some(format), _) => Output::Format(os_str_bytes(&format).into_owned()),
+        (_, Some(format)) => Output::Printf(os_str_bytes(&format).into_owned()),
+        _ => Output::Default,
+    };
+    let result = stat(
+        &args.files,
+        args.dereference,
+        &output,
+        stdout,
+        &mut reporter,
+    );
+    reporter.finish(result)
+}
+
+/// How to print the status of the files.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Output {
+    /// Like GNU stat without a format.
+    Default,
+    /// With a format (`-c`), followed by a newline.
+    Format(Vec<u8>),
+    /// With a format whose backslash escapes are interpreted, without a newline (`--printf`).
+    Printf(Vec<u8>),
+}
+
+/// Print the status of each operand, following the symbolic links with `dereference`, and continuing after
+/// the failures.
+pub fn stat<W: Write>(
+    operands: &[OsString],
+    dereference: bool,
+    output: &Output,
+    stdout: &mut dyn Write,
+    reporter: &mut Reporter<W>,
+) -> UResult<()> {
+    if operands.is_empty() {
+        return Err(UError::usage(EXIT_FAILURE, "missing operand"));
+    }
+    for operand in operands {
+        let stat = match FileStat::new(operand, dereference) {
+            Ok(stat) => stat,
+            Err(error) => {
+                reporter.error(format_args!(
+                    "cannot stat '{}': {}",
+                    Path::new(operand).display(),
+                    io_error_message(&error)
+                ));
+                continue;
+            }
+        };
+        let status = match output {
+            Output::Default => stat.default_format(),
+            Output::Format(format) => {
+                let mut status = stat.format(format, false);
+                status.push(b'\n');
+                status
+            }
+            Output::Printf(format) => stat.format(format, true),
+        };
+        if let Err(error) = stdout.write_all(&status) {
+            return UError::write(error);
+        }
+    }
+    stdout.flush().or_else(UError::write)
+}
+
+/// The format of the status without `-c`, after the name of the file.
+const DEFAULT_FORMAT: &str = concat!(
+    "  Size: %-10s\tBlocks: %-10b IO Block: %-6o %F\n",
+    "Device: %Hd,%Ld\tInode: %-10i  Links: %h\n",
+    "Access: (%04a/%10.10A)  Uid: (%5u/%8U)   Gid: (%5g/%8G)\n",
+    "Access: %x\nModify: %y\nChange: %z\n Birth: %w\n",
+);
+
+/// The format of the status of a block or character device, with its own device numbers.
+const DEVICE_FORMAT: &str = concat!(
+    "  Size: %-10s\tBlocks: %-10b IO Block: %-6o %F\n",
+    "Device: %Hd,%Ld\tInode: %-10i  Links: %-5h Device type: %Hr,%Lr\n",
+    "Access: (%04a/%10.10A)  Uid: (%5u/%8U)   Gid: (%5g/%8G)\n",
+    "Access: %x\nModify: %y\nChange: %z\n Birth: %w\n",
+);
+
+/// The status of a file, as given by `lstat` or `stat`.
+///
+/// Outside of Unix, the ids are zero and the mode is made up from what the system tells.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileStat {
+    /// The name of the file, as given.
+    pub name: OsString,
+    /// The target of the file if it is a symbolic link which is not followed.
+    pub target: Option<PathBuf>,
+    /// The device the file is on.
+    pub device: u64,
+    pub inode: u64,
+    /// The type and permissions, as in `st_mode`.
+    pub mode: u32,
+    /// The number of hard links.
+    pub links: u64,
+    pub uid: u32,
+    pub gid: u32,
+    /// The device the file is, if it is a block or character device.
+    pub rdev: u64,
+    /// The size in bytes.
+    pub size: u64,
+    /// The number of 512-byte blocks allocated.
+    pub blocks: u64,
+    /// The preferred size of the writes.
+    pub block_size: u64,
+    /// The type of the file, like `regular empty file`.
+    pub kind: &'static str,
+    pub accessed: SystemTime,
+    pub modified: SystemTime,
+    /// The time that the status changed.
+    pub changed: SystemTime,
+    /// The time the file was created, if the file system keeps it.
+    pub born: Option<SystemTime>,
+}
+
+impl FileStat {
+    /// Get the status of a file, or of the target of a symbolic link with `dereference`.
+    pub fn new(name: &OsStr, dereference: bool) -> io::Result<Self> {
+        let path = Path::new(name);
+        let metadata = match dereference {
+            true => fs::metadata(path)?,
+            false => fs::symlink_metadata(path)?,
+        };
+        let target = match metadata.file_type().is_symlink() {
+            true => Some(fs::read_link(path)?),
+            false => None,
+        };
+        Ok(FileStat::from_metadata(
+            name.to_os_string(),
+            target,
+            &metadata,
+        ))
+    }
+
+    #[cfg(unix)]
+    fn from_metadata(name: OsString, target: Option<PathBuf>, metadata: &Metadata) -> Self {
+        use std::os::unix::fs::MetadataExt;
+        FileStat {
+            name,
+            target,
+            device: metadata.dev(),
+            inode: metadata.ino(),
+            mode: st_mode(metadata),
+            links: metadata.nlink(),
+            uid: metadata.uid(),
+            gid: metadata.gid(),
+            rdev: metadata.rdev(),
+            size: metadata.size(),
+            blocks: metadata.blocks(),
+            block_size: metadata.blksize(),
+            kind: file_kind(metadata),
+            accessed: system_time(metadata.atime(), metadata.atime_nsec()),
+            modified: system_time(metadata.mtime(), metadata.mtime_nsec()),
+            changed: system_time(metadata.ctime(), metadata.ctime_nsec()),
+            born: metadata.created().ok(),
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn from_metadata(name: OsString, target: Option<PathBuf>, metadata: &Metadata) -> Self {
+        let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+        FileStat {
+            name,
+            target,
+            device: 0,
+            inode: 0,
+            mode: st_mode(metadata),
+            links: 1,
+            uid: 0,
+            gid: 0,
+            rdev: 0,
+            size: metadata.len(),
+            blocks: metadata.len().div_ceil(512),
+            block_size: 4096,
+            kind: file_kind(metadata),
+            accessed: metadata.accessed().unwrap_or(modified),
+            modified,
+            changed: modified,
+            born: metadata.created().ok(),
+        }
+    }
+
+    /// Check whether the file is a block or character device, which has device numbers of its own.
+    pub fn is_device(&self) -> bool {
+        matches!(format_type_char(self.mode), 'b' | 'c')
+    }
+
+    /// Get the name of the owner, or `UNKNOWN` if it has none.
+    pub fn user_name(&self) -> String {
+        #[cfg(unix)]
+        if let Ok(Some(name)) = users::name_for_uid(self.uid) {
+            return name;
+        }
+        "UNKNOWN".to_owned()
+    }
+
+    /// Get the name of the group, or `UNKNOWN` if it has none.
+    pub fn group_name(&self) -> String {
+        #[cfg(unix)]
+        if let Ok(Some(name)) = users::name_for_gid(self.gid) {
+            return name;
+        }
+        "UNKNOWN".to_owned()
+    }
+
+    /// Format the status like GNU stat does without a format.
+    pub fn default_format(&self) -> Vec<u8> {
+        let mut output = b"  File: ".to_vec();
+        output.extend_from_slice(&os_str_bytes(&self.name));
+        if let Some(target) = &self.target {
+            output.extend_from_slice(b" -> ");
+            output.extend_from_slice(&os_str_bytes(target.as_os_str()));
+        }
+        output.push(b'\n');
+        let format = match self.is_device() {
+            true => DEVICE_FORMAT,
+            false => DEFAULT_FORMAT,
+        };
+        output.extend_from_slice(&self.format(format.as_bytes(), false));
+        output
+    }
+
+    /// Format the status with the `%` directives of `format`, and its backslash escapes with `escapes`.
+    ///
+    /// The unknown directives are copied as they are, and `\c` ends the output.
+    pub fn format(&self, format: &[u8], escapes: bool) -> Vec<u8> {
+        let mut output = Vec::new();
+        let mut index = 0;
+        while index < format.len() {
+            match format[index] {
+                b'%' => index += self.directive(&format[index..], &mut output),
+                b'\\' if escapes => {
+                    match expand_escape(&format[index..], EscapeFlavor::Printf, &mut output) {
+                        Some(length) => index += length,
+                        None => break,
+                    }
+                }
+                byte => {
+                    output.push(byte);
+                    index += 1;
+                }
+            }
+        }
+        output
+    }
+
+    /// Print the directive at the start of `format`, which starts with `%`, with its flags, width, and
+    /// precision like in printf, and get its length.
+    fn directive(&self, format: &[u8], output: &mut Vec<u8>) -> usize {
+        if format.get(1) == Some(&b'%') {
+            output.push(b'%');
+            return 2;
+        }
+        let mut spec = Spec::default();
+        let mut index = 1;
+        while let Some(&flag) = format.get(index).filter(|flag| b"-+ #0'I".contains(flag)) {
+            match flag {
+                b'-' => spec.left = true,
+                b'+' => spec.sign = Some(b'+'),
+                b' ' => {
+                    spec.sign.get_or_insert(b' ');
+                }
+                b'#' => spec.alternate = true,
+                b'0' => spec.zero = true,
+                _ => {}
+            }
+            index += 1;
+        }
+        let digits = |index: usize| {
+            let length = format[index..]
+                .iter()
+                .take_while(|byte| byte.is_ascii_digit())
+                .count();
+            let value = String::from_utf8_lossy(&format[index..index + length])
+                .parse()
+                .ok();
+            (value, length)
+        };
+        let (width, length) = digits(index);
+        index += length;
+        // A precision without digits is 0, except for the times, which then get all the digits.
+        let mut precision = None;
+        if format.get(index) == Some(&b'.') {
+            let (value, length) = digits(index + 1);
+            precision = Some(value);
+            index += 1 + length;
+        }
+        let modifier = format
+            .get(index)
+            .copied()
+            .filter(|byte| b"HL".contains(byte));
+        index += usize::from(modifier.is_some());
+        let Some(&directive) = format.get(index) else {
+            output.extend_from_slice(format);
+            return format.len();
+        };
+        index += 1;
+        match self.field(spec, directive, modifier, precision) {
+            Some(field) => field
+                .write(spec.left, width.unwrap_or(0), output)
+                .expect("writing to memory"),
+            None => output.extend_from_slice(&format[..index]),
+        }
+        index
+    }
+
+    /// Format the value of a directive, with `H` or `L` for the major or minor number of a device, or get
+    /// `None` if it is unknown.
+    fn field(
+        &self,
+        spec: Spec,
+        directive: u8,
+        modifier: Option<u8>,
+        precision: Option<Option<usize>>,
+    ) -> Option<Field> {
+        let digits = precision.map(|precision| precision.unwrap_or(0));
+        let unsigned = |conversion, value| Spec { conversion, ..spec }.unsigned(value, digits);
+        let text = |text: &[u8]| Field::text(&text[..text.len().min(digits.unwrap_or(usize::MAX))]);
+        let seconds = |time| {
+            seconds_field(
+                spec,
+                time,
+                precision.map(|precision| precision.unwrap_or(9)),
+            )
+        };
+        let device_number = |device| {
+            let (major, minor) = device_numbers(device);
+            unsigned(b'u', if modifier == Some(b'H') { major } else { minor })
+        };
+        let field = match (modifier, directive) {
+            (Some(_), b'd') => device_number(self.device),
+            (Some(_), b'r') => device_number(self.rdev),
+            (Some(_), _) => return None,
+            (None, b'a') => unsigned(b'o', u64::from(self.mode & 0o7777)),
+            (None, b'A') => text(format_mode(self.mode).as_bytes()),
+            (None, b'b') => unsigned(b'u', self.blocks),
+            (None, b'B') => unsigned(b'u', 512),
+            (None, b'd') => unsigned(b'u', self.device),
+            (None, b'D') => unsigned(b'x', self.device),
+            (None, b'f') => unsigned(b'x', u64::from(self.mode)),
+            (None, b'F') => text(self.kind.as_bytes()),
+            (None, b'g') => unsigned(b'u', u64::from(self.gid)),
+            (None, b'G') => text(self.group_name().as_bytes()),
+            (None, b'h') => unsigned(b'u', self.links),
+            (None, b'i') => unsigned(b'u', self.inode),
+            (None, b'n') => text(&os_str_bytes(&self.name)),
+            (None, b'N') => text(&self.quoted_name()),
+            (None, b'o') => unsigned(b'u', self.block_size),
+            (None, b'r') => unsigned(b'u', self.rdev),
+            // The size is signed, like `off_t`.
+            (None, b's') => Spec {
+                conversion: b'd',
+                ..spec
+            }
+            .signed(self.size as i64, digits),
+            (None, b't') => unsigned(b'x', device_numbers(self.rdev).0),
+            (None, b'T') => unsigned(b'x', device_numbers(self.rdev).1),
+            (None, b'u') => unsigned(b'u', u64::from(self.uid)),
+            (None, b'U') => text(self.user_name().as_bytes()),
+            (None, b'w') => text(
+                self.born
+                    .map_or_else(|| "-".to_owned(), format_time)
+                    .as_bytes(),
+            ),
+            (None, b'W') => self.born.map_or_else(|| unsigned(b'u', 0), seconds),
+            (None, b'x') => text(format_time(self.accessed).as_bytes()),
+            (None, b'X') => seconds(self.accessed),
+            (None, b'y') => text(format_time(self.modified).as_bytes()),
+            (None, b'Y') => seconds(self.modified),
+            (None, b'z') => text(format_time(self.changed).as_bytes()),
+            (None, b'Z') => seconds(self.changed),
+            _ => return None,
+        };
+        Some(field)
+    }
+
+    /// Get the quoted name, followed by the quoted target of a symbolic link, like `'link' -> 'target'`.
+    pub fn quoted_name(&self) -> Vec<u8> {
+        let mut name = quote(&os_str_bytes(&self.name));
+        if let Some(target) = &self.target {
+            name.extend_from_slice(b" -> ");
+            name.extend_from_slice(&quote(&os_str_bytes(target.as_os_str())));
+        }
+        name
+    }
+}
+
+/// Format a time as the number of seconds since the epoch, with `precision` digits of the fraction, like
+/// `1714559400.123`.
+fn seconds_field(spec: Spec, time: SystemTime, precision: Option<usize>) -> Field {
+    let (negative, duration) = match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(duration) => (false, duration),
+        Err(error) => (true, error.duration()),
+    };
+    let Some(precision) = precision else {
+        // The whole seconds are rounded down, like `-1` for half a second before the epoch.
+        let seconds = duration.as_secs() as i64;
+        let seconds = match negative {
+            true => -seconds - i64::from(duration.subsec_nanos() > 0),
+            false => seconds,
+        };
+        return Spec {
+            conversion: b'd',
+            ..spec
+        }
+        .signed(seconds, None);
+    };
+    let mut digits = duration.as_secs().to_string();
+    if precision > 0 {
+        let fraction = format!("{:09}", duration.subsec_nanos());
+        digits.push('.');
+        digits.push_str(&format!("{fraction:0<precision$}")[..precision]);
+    }
+    let sign = if negative { Some(b'-') } else { spec.sign };
+    Field::number(sign.into_iter().collect(), digits, spec.zero)
+}
+
+/// Quote a name like the `shell-escape-always` style of GNU in the C locale: in single quotes, with
+/// `'\''` for a single quote and `$'\t'` for a byte which cannot be printed, or in double quotes if it has
+/// single quotes and no byte which the shell would interpret within double quotes.
+fn quote(name: &[u8]) -> Vec<u8> {
+    let plain = |(index, &byte): (usize, &u8)| match byte {
+        b'#' | b'~' => index == 0,
+        b'{' | b'}' => name.len() == 1,
+        b'!' | b'"' | b'$' | b'&' | b'(' | b')' | b'*' | b';' | b'<' | b'=' | b'>' | b'?'
+        | b'[' | b'\\' | b'^' | b'`' | b'|' => false,
+        _ => byte == b' ' || byte.is_ascii_graphic(),
+    };
+    if name.contains(&b'\'') && name.iter().enumerate().all(plain) {
+        return [&b"\""[..], name, b"\""].concat();
+    }
+    let mut quoted = vec![b'\''];
+    // Whether the bytes which cannot be printed are being escaped in `$'...'`.
+    let mut escaping = false;
+    for &byte in name {
+        if byte == b'\'' {
+            quoted.extend_from_slice(b"'\\''");
+            escaping = false;
+        } else if byte == b' ' || byte.is_ascii_graphic() {
+            if escaping {
+                quoted.extend_from_slice(b"''");
+                escaping = false;
+            }
+            quoted.push(byte);
+        } else {
+            if !escaping {
+                quoted.extend_from_slice(b"'$'");
+                escaping = true;
+            }
+            match b"\x07\x08\x0c\n\r\t\x0b"
+                .iter()
+                .position(|&escape| escape == byte)
+            {
+                Some(position) => quoted.extend_from_slice(&[b'\\', b"abfnrtv"[position]]),
+                None => quoted.extend_from_slice(format!("\\{byte:03o}").as_bytes()),
+            }
+        }
+    }
+    quoted.push(b'\'');
+    quoted
+}
+
+/// Get the time from the seconds and nanoseconds since the epoch, which can be negative.
+#[cfg(unix)]
+fn system_time(seconds: i64, nanoseconds: i64) -> SystemTime {
+    let offset = std::time::Duration::new(seconds.unsigned_abs(), 0);
+    let time = match seconds < 0 {
+        true => SystemTime::UNIX_EPOCH - offset,
+        false => SystemTime::UNIX_EPOCH + offset,
+    };
+    time + std::time::Duration::from_nanos(nanoseconds as u64)
+}
+
+/// Format a time in the local time zone with nanoseconds, like `2024-05-01 12:30:00.123456789 +0200`.
+pub fn format_time(time: SystemTime) -> String {
+    let time: DateTime<Local> = time.into();
+    time.format("%Y-%m-%d %H:%M:%S%.9f %z").to_string()
+}
+
+/// Get the major and minor numbers of a device.
+#[cfg(unix)]
+fn device_numbers(device: u64) -> (u64, u64) {
+    let device = device as libc::dev_t;
+    (libc::major(device).into(), libc::minor(device).into())
+}
+
+#[cfg(not(unix))]
+fn device_numbers(device: u64) -> (u64, u64) {
+    (device >> 8, device & 0xff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::run_util;
+
+    #[test]
+    fn test_missing() {
+        let directory = tempfile::tempdir().unwrap();
+        let missing = directory.path().join("missing").display().to_string();
+        let (code, stdout, stderr) = run_util(run, &["stat", &missing, &missing]);
+        assert_eq!((code, stdout.as_str()), (EXIT_FAILURE, ""));
+        let message = format!("stat: cannot stat '{missing}': No such file or directory\n");
+        assert_eq!(stderr, message.repeat(2));
+        assert_eq!(
+            run_util(run, &["stat"]),
+            (
+                EXIT_FAILURE,
+                String::new(),
+                "stat: missing operand\nTry 'stat --help' for more information.\n".to_owned()
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_default_format() {
+        use predicates::prelude::*;
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("file");
+        fs::write(&path, "12345").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+        fs::hard_link(&path, directory.path().join("link")).unwrap();
+        let name = path.display().to_string();
+
+        let (code, stdout, stderr) = run_util(run, &["stat", &name]);
+        assert_eq!((code, stderr.as_str()), (0, ""));
+        let lines: Vec<&str> = stdout.lines().collect();
+        assert_eq!(lines.len(), 8, "{stdout}");
+        assert_eq!(lines[0], format!("  File: {name}"));
+        assert!(
+            lines[1].starts_with("  Size: 5         \tBlocks: "),
+            "{}",
+            lines[1]
+        );
+        assert!(lines[1].ends_with(" regular file"), "{}", lines[1]);
+        assert!(
+            predicate::str::is_match(r"^Device: \d+,\d+\tInode: \d+ +Links: 2$")
+                .unwrap()
+                .eval(lines[2])
+        );
+        let uid =
+            fs::metadata(&path).map(|metadata| std::os::unix::fs::MetadataExt::uid(&metadata));
+        assert!(lines[3].starts_with(&format!(
+            "Access: (0640/-rw-r-----)  Uid: ({:>5}/",
+            uid.unwrap()
+        )));
+        let time = r"\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}\.\d{9} [+-]\d{4}";
+        for (line, label) in lines[4..]
+            .iter()
+            .zip(["Access", "Modify", "Change", " Birth"])
+        {
+            let pattern = match label {
+                " Birth" => format!("^ Birth: ({time}|-)$"),
+                _ => format!("^{label}: {time}$"),
+            };
+            assert!(
+                predicate::str::is_match(pattern).unwrap().eval(*line),
+                "{line}"
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_dereference() {
+        use predicates::prelude::*;
+
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::write(root.join("file"), "").unwrap();
+        std::os::unix::fs::symlink("file", root.join("link")).unwrap();
+        let link = root.join("link").display().to_string();
+
+        // The link itself, whose size is the length of its target.
+        let (code, stdout, _) = run_util(run, &["stat", &link]);
+        assert_eq!(code, 0);
+        let lines: Vec<&str> = stdout.lines().collect();
+        assert_eq!(lines[0], format!("  File: {link} -> file"));
+        assert!(lines[1].starts_with("  Size: 4  "), "{}", lines[1]);
+        assert!(lines[1].ends_with(" symbolic link"), "{}", lines[1]);
+        assert!(
+            lines[3].starts_with("Access: (0777/lrwxrwxrwx)"),
+            "{}",
+            lines[3]
+        );
+
+        let (code, stdout, _) = run_util(run, &["stat", "-L", &link]);
+        assert_eq!(code, 0);
+        let lines: Vec<&str> = stdout.lines().collect();
+        assert_eq!(lines[0], format!("  File: {link}"));
+        assert!(lines[1].ends_with(" regular empty file"), "{}", lines[1]);
+
+        let (code, stdout, stderr) = run_util(run, &["stat", "--dereference", "/dev/null"]);
+        assert_eq!((code, stderr.as_str()), (0, ""));
+        // The numbers of /dev/null depend on the system.
+        let pattern = predicate::str::is_match(r"  Links: 1 +Device type: \d+,\d+\n").unwrap();
+        assert!(pattern.eval(&stdout), "{stdout}");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_format() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("a file");
+        fs::write(&path, "12345").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+        std::os::unix::fs::symlink("a file", directory.path().join("link")).unwrap();
+        let name = path.display().to_string();
+        let link = directory.path().join("link").display().to_string();
+        let user = FileStat::new(path.as_os_str(), false).unwrap().user_name();
+
+        assert_eq!(
+            run_util(run, &["stat", "-c", "%a %U %s %n", &name, &name]),
+            (0, format!("640 {user} 5 {name}\n").repeat(2), String::new())
+        );
+        // The flags, the width, and the precision are like in printf.
+        assert_eq!(
+            run_util(run, &["stat", "--format=%#a|%-6s|%06s|%.3F|%5h|%A", &name]),
+            (
+                0,
+                "0640|5     |000005|reg|    1|-rw-r-----\n".to_owned(),
+                String::new()
+            )
+        );
+        // The unknown directives are copied as they are, and so is a `%` at the end.
+        assert_eq!(
+            run_util(run, &["stat", "-c", "%q %-5q %Hs %% %", &name]),
+            (0, "%q %-5q %Hs % %\n".to_owned(), String::new())
+        );
+        let (_, stdout, _) = run_util(run, &["stat", "-c", "%N|%N", &name, &link]);
+        let quoted = format!("'{name}'|'{name}'\n'{link}' -> 'a file'|'{link}' -> 'a file'\n");
+        assert_eq!(stdout, quoted);
+        assert_eq!(
+            run_util(run, &["stat", "-L", "-c", "%N %F", &link]),
+            (0, format!("'{link}' regular file\n"), String::new())
+        );
+
+        // Only --printf interprets the escapes, and it adds no newline.
+        assert_eq!(
+            run_util(run, &["stat", "--printf", "%s\\t%a\\n", &name, &name]),
+            (0, "5\t640\n5\t640\n".to_owned(), String::new())
+        );
+        assert_eq!(
+            run_util(run, &["stat", "-c", "%s\\t", &name]),
+            (0, "5\\t\n".to_owned(), String::new())
+        );
+        // The last of -c and --printf wins.
+        assert_eq!(
+            run_util(run, &["stat", "--printf=%s", "-c", "%a", &name]),
+            (0, "640\n".to_owned(), String::new())
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_format_times() {
+        use predicates::prelude::*;
+        use std::time::Duration;
+
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("file");
+        fs::write(&path, "").unwrap();
+        let mut stat = FileStat::new(path.as_os_str(), false).unwrap();
+        stat.modified = SystemTime::UNIX_EPOCH + Duration::new(1_577_836_800, 123_456_789);
+        stat.changed = SystemTime::UNIX_EPOCH - Duration::from_millis(1500);
+        stat.born = None;
+
+        for (format, output) in [
+            ("%Y", "1577836800"),
+            ("%.3Y", "1577836800.123"),
+            ("%.Y", "1577836800.123456789"),
+            ("%.12Y", "1577836800.123456789000"),
+            ("%-16.4Y|", "1577836800.1234 |"),
+            ("%016.2Y", "0001577836800.12"),
+            ("%+Y", "+1577836800"),
+            // Before the epoch, the whole seconds are rounded down.
+            ("%Z %.1Z", "-2 -1.5"),
+            ("%w %W", "- 0"),
+        ] {
+            assert_eq!(
+                stat.format(format.as_bytes(), false),
+                output.as_bytes(),
+                "{format}"
+            );
+        }
+        let time = stat.format(b"%y", false);
+        let time = String::from_utf8(time).unwrap();
+        let pattern = r"^\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}\.123456789 [+-]\d{4}$";
+        assert!(
+            predicate::str::is_match(pattern).unwrap().eval(&time),
+            "{time}"
+        );
+    }
+
+    #[test]
+    fn test_quote() {
+        for (name, quoted) in [
+            (&b"plain"[..], &b"'plain'"[..]),
+            (b"a b", b"'a b'"),
+            (b"", b"''"),
+            // Double quotes, unless the shell would interpret something in them.
+            (b"it's", b"\"it's\""),
+            (b"it's $HOME", b"'it'\\''s $HOME'"),
+            (b"'#", b"''\\''#'"),
+            (b"#'", b"\"#'\""),
+            // The bytes which cannot be printed are escaped between single quotes.
+            (b"a\tb", b"'a'$'\\t''b'"),
+            (b"\x1b", b"''$'\\033'"),
+            (b"caf\xc3\xa9", b"'caf'$'\\303\\251'"),
+            (b"\tit's", b"''$'\\t''it'\\''s'"),
+        ] {
+            assert_eq!(
+                String::from_utf8_lossy(&quote(name)),
+                String::from_utf8_lossy(quoted),
+                "{}",
+                String::from_utf8_lossy(name)
+            );
+        }
+    }
+}
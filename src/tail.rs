@@ -0,0 +1,1436 @@
+//! Print the last lines or bytes of files, or all of them from a given one, and optionally what is appended
+//! to the files afterwards.
+//!
+//! The inputs are printed with [`tail`], so that they can be read from anywhere and written anywhere:
+//!
+//! ```
+//! use rust_coreutils::common::error::Reporter;
+//! use rust_coreutils::common::input::InputIter;
+//! use rust_coreutils::tail::{tail, Count, TailOptions};
+//!
+//! let mut inputs = InputIter::with_stdin(vec![], Box::new(&b"1\n2\n3\n"[..]));
+//! let options = TailOptions {
+//!     count: Count::Lines(2),
+//!     ..TailOptions::default()
+//! };
+//! let mut stdout = Vec::new();
+//! let mut reporter = Reporter::new("tail", Vec::new());
+//! tail(&mut inputs, &options, &mut stdout, &mut reporter).unwrap();
+//! assert_eq!(stdout, b"2\n3\n");
+//! ```
+
+use crate::common::error::{io_error_message, Reporter, UError, UResult, EXIT_FAILURE};
+use crate::common::input::{InputIter, Source, BUFFER_SIZE};
+use crate::common::os::os_str_bytes;
+use crate::common::records::{Record, RecordReader, RecordWriter, ZeroTerminatedArgs};
+use crate::common::size::{parse_signed_size, Sign, SizeError, SizeErrorKind};
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use clap::Parser;
+use std::collections::VecDeque;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::thread;
+use std::time::Duration;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "tail",
+    author,
+    version,
+    about = "Print the last 10 lines of each FILE. With more than one FILE, each is preceded by a header \
+             with its name.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Print the last NUM bytes. With a leading +, print all bytes from the NUMth one. NUM may have a
+    /// multiplier suffix, like K or M.
+    #[arg(
+        short = 'c',
+        long,
+        value_name = "[+]NUM",
+        allow_hyphen_values = true,
+        overrides_with = "lines"
+    )]
+    bytes: Option<String>,
+
+    /// Print the last NUM lines instead of the last 10. With a leading +, print all lines from the NUMth
+    /// one. NUM may have a multiplier suffix, like K or M.
+    #[arg(
+        short = 'n',
+        long,
+        value_name = "[+]NUM",
+        allow_hyphen_values = true,
+        overrides_with = "bytes"
+    )]
+    lines: Option<String>,
+
+    /// Keep printing what is appended to the files. Standard input is not followed once it ends.
+    #[arg(short, long, default_value_t = false)]
+    follow: bool,
+
+    /// With -f, check the files for changes every N seconds, which can be fractional.
+    #[arg(short, long, value_name = "N", allow_hyphen_values = true)]
+    sleep_interval: Option<String>,
+
+    /// Never print headers.
+    #[arg(
+        short,
+        long,
+        visible_alias = "silent",
+        default_value_t = false,
+        overrides_with = "verbose"
+    )]
+    quiet: bool,
+
+    /// Always print headers.
+    #[arg(short, long, default_value_t = false, overrides_with = "quiet")]
+    verbose: bool,
+
+    #[command(flatten)]
+    records: ZeroTerminatedArgs,
+
+    /// The files to print. With no FILE, or when FILE is -, standard input is read.
+    #[arg(value_name = "FILE")]
+    files: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "tail";
+}
+
+/// The description of tail for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run tail with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run tail with the given command line, including the program name, on the given streams, and return the
+/// exit code.
+pub fn run(
+    args: &[OsString],
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(expand_obsolete_count(args.to_vec()), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let mut reporter = Reporter::new("tail", stderr);
+    let count = match (&args.bytes, &args.lines) {
+        (Some(bytes), _) => parse_count(bytes, Count::Bytes, Count::FromByte)
+            .map_err(|error| format!("invalid number of bytes: {error}")),
+        (None, Some(lines)) => parse_count(lines, Count::Lines, Count::FromLine)
+            .map_err(|error| format!("invalid number of lines: {error}")),
+        (None, None) => Ok(Count::Lines(10)),
+    };
+    let count = match count {
+        Ok(count) => count,
+        Err(message) => return reporter.finish(Err(UError::new(EXIT_FAILURE, message))),
+    };
+    let interval = match args.sleep_interval.as_deref().map(parse_interval) {
+        None => Duration::from_secs(1),
+        Some(Some(interval)) => interval,
+        Some(None) => {
+            let message = format!(
+                "invalid number of seconds: '{}'",
+                args.sleep_interval.unwrap_or_default()
+            );
+            return reporter.finish(Err(UError::new(EXIT_FAILURE, message)));
+        }
+    };
+    let options = TailOptions {
+        count,
+        follow: args.follow.then_some(interval),
+        headers: !args.quiet && (args.verbose || args.files.len() > 1),
+        delimiter: args.records.delimiter(),
+    };
+
+    let mut inputs = InputIter::with_stdin(
+        args.files,
+        Box::new(BufReader::with_capacity(BUFFER_SIZE, stdin)),
+    );
+    let result = tail(&mut inputs, &options, stdout, &mut reporter);
+    reporter.finish(result)
+}
+
+/// Rewrite the obsolete form of the count as a first argument, like `-5`, `+5`, or `-2cf`, into options: a
+/// sign, the digits of the count, optionally a unit: `c` for bytes, `b` for blocks of 512 bytes, or `l` for
+/// lines, and optionally `f` to follow.
+fn expand_obsolete_count(mut args: Vec<OsString>) -> Vec<OsString> {
+    let Some(first) = args.get(1).and_then(|arg| arg.to_str()) else {
+        return args;
+    };
+    let (sign, spec) = match first.split_at_checked(1) {
+        Some(("-", spec)) => ("", spec),
+        Some(("+", spec)) => ("+", spec),
+        _ => return args,
+    };
+    let digits = spec.bytes().take_while(u8::is_ascii_digit).count();
+    if digits == 0 {
+        return args;
+    }
+
+    let (number, unit) = spec.split_at(digits);
+    let (unit, follow) = match unit.strip_suffix('f') {
+        Some(unit) => (unit, true),
+        None => (unit, false),
+    };
+    let (option, suffix) = match unit {
+        "" | "l" => ("-n", ""),
+        "c" => ("-c", ""),
+        "b" => ("-c", "b"),
+        // Not the obsolete form, leave it for clap to report.
+        _ => return args,
+    };
+    let count = format!("{sign}{number}{suffix}");
+    let mut expanded = vec![OsString::from(option), OsString::from(count)];
+    if follow {
+        expanded.push(OsString::from("-f"));
+    }
+    args.splice(1..2, expanded);
+    args
+}
+
+/// Parse the argument of `-s`, a non-negative number of seconds.
+fn parse_interval(arg: &str) -> Option<Duration> {
+    Duration::try_from_secs_f64(arg.parse().ok()?).ok()
+}
+
+/// Parse the argument of `-c` or `-n`, where a leading `+` means from the given one, and a leading `-`
+/// changes nothing.
+fn parse_count(
+    arg: &str,
+    last: fn(u64) -> Count,
+    from: fn(u64) -> Count,
+) -> Result<Count, SizeError> {
+    let (sign, value) = parse_signed_size(arg)?;
+    let value = u64::try_from(value).map_err(|_| SizeError {
+        kind: SizeErrorKind::TooLarge,
+        input: arg.to_owned(),
+    })?;
+    Ok(match sign {
+        Some(Sign::Plus) => from(value),
+        _ => last(value),
+    })
+}
+
+/// What to print of each input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Count {
+    /// The last lines.
+    Lines(u64),
+    /// The last bytes.
+    Bytes(u64),
+    /// All lines from the given one, counting from 1. Line 0 is the same as line 1.
+    FromLine(u64),
+    /// All bytes from the given one, counting from 1. Byte 0 is the same as byte 1.
+    FromByte(u64),
+}
+
+/// The behaviour of tail.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TailOptions {
+    /// What to print of each input.
+    pub count: Count,
+    /// Keep printing what is appended to the files, checking them at this interval.
+    pub follow: Option<Duration>,
+    /// Print a header with the name of each input before it.
+    pub headers: bool,
+    /// The byte which ends lines.
+    pub delimiter: u8,
+}
+
+impl Default for TailOptions {
+    /// The default behaviour of tail: the last 10 lines of an input, without a header.
+    fn default() -> Self {
+        TailOptions {
+            count: Count::Lines(10),
+            follow: None,
+            headers: false,
+            delimiter: b'\n',
+        }
+    }
+}
+
+/// Print the last lines or bytes of each input, or all of them from a given one. Regular files are read
+/// backwards from their end, or from where the output starts, so that only what is printed is read; other
+/// inputs are read to their end, keeping only what could still be printed.
+///
+/// When following, the files are then checked for what is appended to them, which is printed until the
+/// process is terminated. Standard input is only read to its end.
+///
+/// An input which cannot be opened or read is reported, and the remaining ones are still printed. A failure
+/// to write is returned, as there is no point in going on. A closed pipe stops without an error.
+pub fn tail<W: Write>(
+    inputs: &mut InputIter,
+    options: &TailOptions,
+    stdout: &mut dyn Write,
+    reporter: &mut Reporter<W>,
+) -> UResult<()> {
+    let mut poll = Poll(options.follow.unwrap_or_default());
+    tail_with(inputs, options, &mut poll, stdout, reporter)
+}
+
+/// Print the inputs like [`tail`], waiting for changes to the followed files with `wait`.
+fn tail_with<W: Write>(
+    inputs: &mut InputIter,
+    options: &TailOptions,
+    wait: &mut dyn Wait,
+    stdout: &mut dyn Write,
+    reporter: &mut Reporter<W>,
+) -> UResult<()> {
+    let mut first = true;
+    let mut followed = Vec::new();
+    // The followed file which was printed last, so that its header is not repeated.
+    let mut current = None;
+    while let Some(input) = inputs.next_source() {
+        let input = match input {
+            Ok(input) => input,
+            Err(error) => {
+                reporter.error(format_args!(
+                    "cannot open '{}' for reading: {}",
+                    error.display_name(),
+                    io_error_message(&error.error)
+                ));
+                continue;
+            }
+        };
+
+        let label = match &input.operand {
+            Some(operand) if !input.is_stdin() => os_str_bytes(operand).into_owned(),
+            _ => b"standard input".to_vec(),
+        };
+        if options.headers {
+            if let Err(error) = write_header(stdout, &label, first) {
+                return UError::write(error);
+            }
+            first = false;
+        }
+
+        let name = input.display_name().into_owned();
+        let (result, watched) = match input.reader {
+            Source::File(mut file) if file.metadata().is_ok_and(|metadata| metadata.is_file()) => {
+                let result = tail_seekable(&mut file, options, stdout);
+                let watched = file
+                    .stream_position()
+                    .map(|position| Watched::Regular { file, position });
+                (result, watched.ok())
+            }
+            Source::File(file) => {
+                let mut reader = BufReader::with_capacity(BUFFER_SIZE, file);
+                let result = tail_stream(&mut reader, options, stdout);
+                (result, Some(Watched::Stream(reader)))
+            }
+            Source::Stdin(stdin) => (tail_stream(stdin, options, stdout), None),
+        };
+        current = None;
+        match result {
+            Ok(()) => {
+                if let (Some(watched), Some(_)) = (watched, options.follow) {
+                    current = Some(followed.len());
+                    followed.push(Followed {
+                        name,
+                        label,
+                        watched,
+                        stopped: false,
+                    });
+                }
+            }
+            Err(Failure::Read(error)) => reporter.error(format_args!(
+                "error reading '{name}': {}",
+                io_error_message(&error)
+            )),
+            Err(Failure::Write(error)) => return UError::write(error),
+        }
+    }
+    stdout.flush().or_else(UError::write)?;
+
+    if options.follow.is_none() || (followed.is_empty() && !reporter.failed()) {
+        return Ok(());
+    }
+    follow(
+        &mut followed,
+        options.headers,
+        current,
+        wait,
+        stdout,
+        reporter,
+    )
+}
+
+/// Print a header with the name of an input, after an empty line unless it is the first one.
+fn write_header(stdout: &mut dyn Write, label: &[u8], first: bool) -> io::Result<()> {
+    let mut header = if first { Vec::new() } else { b"\n".to_vec() };
+    header.extend_from_slice(b"==> ");
+    header.extend_from_slice(label);
+    header.extend_from_slice(b" <==\n");
+    stdout.write_all(&header)
+}
+
+/// A file whose growth is printed.
+struct Followed {
+    /// The name of the file in messages.
+    name: String,
+    /// The name of the file in headers.
+    label: Vec<u8>,
+    watched: Watched,
+    /// Whether the file can no longer be read, so that following it stopped.
+    stopped: bool,
+}
+
+/// How a followed file is read.
+enum Watched {
+    /// A regular file, which is read from where the previous read stopped, unless it was truncated since.
+    Regular { file: File, position: u64 },
+    /// Other files, like named pipes, which are read until they have nothing more for now.
+    Stream(BufReader<File>),
+}
+
+/// Waits until the followed files may have changed.
+///
+/// Following only polls the files for now: a backend which is notified of the changes, like inotify on
+/// Linux, can implement this to wait for them instead of sleeping.
+trait Wait {
+    /// Wait until the files may have changed, and return whether to keep following them.
+    fn wait(&mut self, files: &[Followed]) -> bool;
+}
+
+/// Sleeps for an interval before the files are checked again.
+struct Poll(Duration);
+
+impl Wait for Poll {
+    fn wait(&mut self, _files: &[Followed]) -> bool {
+        thread::sleep(self.0);
+        true
+    }
+}
+
+impl<F: FnMut(&[Followed]) -> bool> Wait for F {
+    fn wait(&mut self, files: &[Followed]) -> bool {
+        self(files)
+    }
+}
+
+/// Print what is appended to the files, with a header whenever the output switches to another file, until
+/// `wait` stops or no file can be read anymore.
+fn follow<W: Write>(
+    files: &mut [Followed],
+    headers: bool,
+    mut current: Option<usize>,
+    wait: &mut dyn Wait,
+    stdout: &mut dyn Write,
+    reporter: &mut Reporter<W>,
+) -> UResult<()> {
+    let mut buffer = vec![0; BUFFER_SIZE];
+    loop {
+        if files.iter().all(|file| file.stopped) {
+            return Err(UError::new(EXIT_FAILURE, "no files remaining"));
+        }
+        if !wait.wait(files) {
+            return Ok(());
+        }
+
+        for (index, file) in files.iter_mut().enumerate() {
+            if file.stopped {
+                continue;
+            }
+            let mut output = |data: &[u8]| -> io::Result<()> {
+                if headers && current != Some(index) {
+                    write_header(stdout, &file.label, false)?;
+                }
+                current = Some(index);
+                stdout.write_all(data)
+            };
+            let result = match &mut file.watched {
+                Watched::Regular {
+                    file: handle,
+                    position,
+                } => read_appended(
+                    handle,
+                    position,
+                    &file.name,
+                    &mut buffer,
+                    &mut output,
+                    reporter,
+                ),
+                Watched::Stream(reader) => read_available(reader, &mut output),
+            };
+            match result {
+                Ok(()) => {}
+                Err(Failure::Read(error)) => {
+                    reporter.error(format_args!(
+                        "error reading '{}': {}",
+                        file.name,
+                        io_error_message(&error)
+                    ));
+                    file.stopped = true;
+                }
+                Err(Failure::Write(error)) => return UError::write(error),
+            }
+        }
+        stdout.flush().or_else(UError::write)?;
+    }
+}
+/// Print what was appended to a regular file since it was last read. A file which became shorter was
+/// truncated, so it is read again from its start.
+fn read_appended<W: Write>(
+    file: &mut File,
+    position: &mut u64,
+    name: &str,
+    buffer: &mut [u8],
+    output: &mut dyn FnMut(&[u8]) -> io::Result<()>,
+    reporter: &mut Reporter<W>,
+) -> Result<(), Failure> {
+    let length = file.metadata().map_err(Failure::Read)?.len();
+    if length < *position {
+        reporter.warning(format_args!("{name}: file truncated"));
+        *position = file.seek(SeekFrom::Start(0)).map_err(Failure::Read)?;
+    }
+    loop {
+        let length = read(file, buffer)?;
+        if length == 0 {
+            return Ok(());
+        }
+        output(&buffer[..length]).map_err(Failure::Write)?;
+        *position += length as u64;
+    }
+}
+
+/// Print what a file which is not regular has for now.
+fn read_available(
+    reader: &mut dyn BufRead,
+    output: &mut dyn FnMut(&[u8]) -> io::Result<()>,
+) -> Result<(), Failure> {
+    loop {
+        let data = fill_buf(reader)?;
+        if data.is_empty() {
+            return Ok(());
+        }
+        output(data).map_err(Failure::Write)?;
+        let length = data.len();
+        reader.consume(length);
+    }
+}
+
+/// Why printing an input stopped.
+enum Failure {
+    Read(io::Error),
+    Write(io::Error),
+}
+
+/// Print an input which can be seeked in, starting at its current position, without reading what is not
+/// printed, except for the lines before a line to print from.
+fn tail_seekable<R: Read + Seek>(
+    reader: &mut R,
+    options: &TailOptions,
+    stdout: &mut dyn Write,
+) -> Result<(), Failure> {
+    match options.count {
+        Count::Lines(lines) => copy_last_lines_seekable(reader, lines, options.delimiter, stdout),
+        Count::Bytes(bytes) => {
+            let start = reader.stream_position().map_err(Failure::Read)?;
+            let end = reader.seek(SeekFrom::End(0)).map_err(Failure::Read)?;
+            let from = end.saturating_sub(bytes).max(start);
+            reader.seek(SeekFrom::Start(from)).map_err(Failure::Read)?;
+            copy_to_end(reader, &mut vec![0; BUFFER_SIZE], stdout)
+        }
+        Count::FromByte(byte) => {
+            let start = reader.stream_position().map_err(Failure::Read)?;
+            let from = start.saturating_add(byte.saturating_sub(1));
+            // Seeking beyond the end is allowed, so there is nothing left to read.
+            reader.seek(SeekFrom::Start(from)).map_err(Failure::Read)?;
+            copy_to_end(reader, &mut vec![0; BUFFER_SIZE], stdout)
+        }
+        Count::FromLine(_) => tail_stream(
+            &mut BufReader::with_capacity(BUFFER_SIZE, reader),
+            options,
+            stdout,
+        ),
+    }
+}
+
+/// Print an input which can only be read forwards.
+fn tail_stream(
+    reader: &mut dyn BufRead,
+    options: &TailOptions,
+    stdout: &mut dyn Write,
+) -> Result<(), Failure> {
+    match options.count {
+        Count::Lines(lines) => copy_last_lines(reader, lines, options.delimiter, stdout),
+        Count::Bytes(bytes) => copy_last_bytes(reader, bytes, stdout),
+        Count::FromLine(line) => {
+            skip_lines(reader, line.saturating_sub(1), options.delimiter)?;
+            copy_rest(reader, stdout)
+        }
+        Count::FromByte(byte) => {
+            skip_bytes(reader, byte.saturating_sub(1))?;
+            copy_rest(reader, stdout)
+        }
+    }
+}
+
+/// Copy the last lines of a seekable input, scanning it backwards from its end in blocks until enough
+/// delimiters are found, so that the rest of it is never read. A final line without a delimiter counts as
+/// a line.
+fn copy_last_lines_seekable<R: Read + Seek>(
+    reader: &mut R,
+    lines: u64,
+    delimiter: u8,
+    stdout: &mut dyn Write,
+) -> Result<(), Failure> {
+    if lines == 0 {
+        // Following starts from the end.
+        reader.seek(SeekFrom::End(0)).map_err(Failure::Read)?;
+        return Ok(());
+    }
+
+    let start = reader.stream_position().map_err(Failure::Read)?;
+    let end = reader.seek(SeekFrom::End(0)).map_err(Failure::Read)?;
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut position = end;
+    let mut from = start;
+    let mut remaining = lines;
+    'scan: while position > start {
+        let length = (position - start).min(BUFFER_SIZE as u64) as usize;
+        position -= length as u64;
+        reader
+            .seek(SeekFrom::Start(position))
+            .map_err(Failure::Read)?;
+        read_exact(reader, &mut buffer[..length])?;
+
+        for (offset, &byte) in buffer[..length].iter().enumerate().rev() {
+            let at = position + offset as u64;
+            // The delimiter of the last line does not start another one.
+            if byte != delimiter || at + 1 == end {
+                continue;
+            }
+            remaining -= 1;
+            if remaining == 0 {
+                from = at + 1;
+                break 'scan;
+            }
+        }
+    }
+
+    reader.seek(SeekFrom::Start(from)).map_err(Failure::Read)?;
+    copy_to_end(reader, &mut buffer, stdout)
+}
+
+/// Copy the last lines of an input which can only be read forwards, keeping a window of the lines which
+/// could still be the last ones. A final line without a delimiter counts as a line.
+fn copy_last_lines(
+    reader: &mut dyn BufRead,
+    lines: u64,
+    delimiter: u8,
+    stdout: &mut dyn Write,
+) -> Result<(), Failure> {
+    if lines == 0 {
+        return Ok(());
+    }
+
+    let mut records = RecordReader::new(reader, delimiter);
+    let mut window: VecDeque<(Vec<u8>, bool)> = VecDeque::new();
+    while let Some(record) = records.next_record() {
+        let record = record.map_err(Failure::Read)?;
+        // Reuse the allocation of the line which leaves the window.
+        let mut content = if window.len() as u64 == lines {
+            window.pop_front().expect("the window is not empty").0
+        } else {
+            Vec::new()
+        };
+        content.clear();
+        content.extend_from_slice(record.content);
+        window.push_back((content, record.terminated));
+    }
+
+    let mut writer = RecordWriter::new(stdout, delimiter);
+    for (content, terminated) in &window {
+        writer
+            .write_as_read(Record {
+                content,
+                terminated: *terminated,
+            })
+            .map_err(Failure::Write)?;
+    }
+    Ok(())
+}
+
+/// Copy the last bytes of an input which can only be read forwards, keeping a window of the bytes which
+/// could still be the last ones. The window is only shifted once it holds a lot more than needed.
+fn copy_last_bytes(
+    reader: &mut dyn BufRead,
+    bytes: u64,
+    stdout: &mut dyn Write,
+) -> Result<(), Failure> {
+    let bytes = usize::try_from(bytes).unwrap_or(usize::MAX);
+    let mut window = Vec::new();
+    loop {
+        let data = fill_buf(reader)?;
+        if data.is_empty() {
+            break;
+        }
+        window.extend_from_slice(data);
+        let length = data.len();
+        reader.consume(length);
+
+        if window.len() > bytes.saturating_mul(2).max(BUFFER_SIZE) {
+            window.drain(..window.len() - bytes);
+        }
+    }
+    let from = window.len().saturating_sub(bytes);
+    stdout.write_all(&window[from..]).map_err(Failure::Write)
+}
+
+/// Skip lines of an input, without reading beyond the buffer after them.
+fn skip_lines(reader: &mut dyn BufRead, lines: u64, delimiter: u8) -> Result<(), Failure> {
+    let mut remaining = lines;
+    while remaining > 0 {
+        let data = fill_buf(reader)?;
+        if data.is_empty() {
+            break;
+        }
+
+        let mut end = data.len();
+        for (position, _) in data
+            .iter()
+            .enumerate()
+            .filter(|&(_, &byte)| byte == delimiter)
+        {
+            remaining -= 1;
+            if remaining == 0 {
+                end = position + 1;
+                break;
+            }
+        }
+        reader.consume(end);
+    }
+    Ok(())
+}
+
+/// Skip bytes of an input, without reading beyond the buffer after them.
+fn skip_bytes(reader: &mut dyn BufRead, bytes: u64) -> Result<(), Failure> {
+    let mut remaining = bytes;
+    while remaining > 0 {
+        let data = fill_buf(reader)?;
+        if data.is_empty() {
+            break;
+        }
+        let end = data
+            .len()
+            .min(usize::try_from(remaining).unwrap_or(usize::MAX));
+        reader.consume(end);
+        remaining -= end as u64;
+    }
+    Ok(())
+}
+
+/// Copy the rest of a buffered input.
+fn copy_rest(reader: &mut dyn BufRead, stdout: &mut dyn Write) -> Result<(), Failure> {
+    loop {
+        let data = fill_buf(reader)?;
+        if data.is_empty() {
+            return Ok(());
+        }
+        stdout.write_all(data).map_err(Failure::Write)?;
+        let length = data.len();
+        reader.consume(length);
+    }
+}
+
+/// Copy the rest of an input, through the buffer.
+fn copy_to_end(
+    reader: &mut impl Read,
+    buffer: &mut [u8],
+    stdout: &mut dyn Write,
+) -> Result<(), Failure> {
+    loop {
+        let length = read(reader, buffer)?;
+        if length == 0 {
+            return Ok(());
+        }
+        stdout
+            .write_all(&buffer[..length])
+            .map_err(Failure::Write)?;
+    }
+}
+
+/// Fill the buffer of a reader, retrying when interrupted.
+fn fill_buf(reader: &mut dyn BufRead) -> Result<&[u8], Failure> {
+    loop {
+        match reader.fill_buf() {
+            // Returning the buffer from inside the loop is rejected by the borrow checker, so it is filled
+            // again outside of it, which returns the same buffer without reading.
+            Ok(_) => break,
+            Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+            Err(error) => return Err(Failure::Read(error)),
+        }
+    }
+    reader.fill_buf().map_err(Failure::Read)
+}
+
+/// Read into the buffer, retrying when interrupted.
+fn read(reader: &mut impl Read, buffer: &mut [u8]) -> Result<usize, Failure> {
+    loop {
+        match reader.read(buffer) {
+            Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+            result => return result.map_err(Failure::Read),
+        }
+    }
+}
+
+/// Fill the buffer, failing if the input became shorter since its size was known.
+fn read_exact(reader: &mut impl Read, buffer: &mut [u8]) -> Result<(), Failure> {
+    reader.read_exact(buffer).map_err(Failure::Read)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::{run_util_stdin, Fixture};
+    use assert_cmd::Command;
+    use std::fs::{self, File};
+
+    /// Get the numbered lines in the range.
+    fn lines(range: std::ops::RangeInclusive<usize>) -> String {
+        range.map(|n| format!("{n}\n")).collect()
+    }
+
+    /// A reader which counts the bytes read through it.
+    struct CountingReader<R> {
+        inner: R,
+        read: u64,
+    }
+
+    impl<R: Read> Read for CountingReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let length = self.inner.read(buf)?;
+            self.read += length as u64;
+            Ok(length)
+        }
+    }
+
+    impl<R: Seek> Seek for CountingReader<R> {
+        fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+            self.inner.seek(position)
+        }
+    }
+
+    #[test]
+    fn test_default() {
+        assert_eq!(
+            run_util_stdin(run, &["tail"], lines(1..=20).as_bytes()),
+            (0, lines(11..=20), String::new())
+        );
+        assert_eq!(run_util_stdin(run, &["tail", "-"], b"a\nb").1, "a\nb");
+        assert_eq!(run_util_stdin(run, &["tail"], b"").1, "");
+    }
+
+    #[test]
+    fn test_lines_from_pipe() {
+        // Fewer lines than requested.
+        assert_eq!(
+            run_util_stdin(run, &["tail", "-n", "5"], b"a\nb\n").1,
+            "a\nb\n"
+        );
+        assert_eq!(
+            run_util_stdin(run, &["tail", "-n", "2"], b"a\nb\nc").1,
+            "b\nc"
+        );
+        assert_eq!(
+            run_util_stdin(run, &["tail", "-n", "1"], b"a\nb\n\n").1,
+            "\n"
+        );
+        assert_eq!(run_util_stdin(run, &["tail", "-n", "0"], b"a\nb\n").1, "");
+        // A leading `-` is the same count.
+        assert_eq!(
+            run_util_stdin(run, &["tail", "-n", "-2"], b"a\nb\nc\n").1,
+            "b\nc\n"
+        );
+        assert_eq!(
+            run_util_stdin(run, &["tail", "--lines=1K"], lines(1..=2000).as_bytes()).1,
+            lines(977..=2000)
+        );
+    }
+
+    #[test]
+    fn test_lines_from_file() {
+        let fixture = Fixture::new(&[
+            ("twenty", &lines(1..=20)),
+            ("unterminated", "a\nb\nc"),
+            ("short", "a\nb\n"),
+            ("blank", "a\nb\n\n"),
+            ("empty", ""),
+            ("one", "only"),
+        ]);
+        let tail_file = |count: &str, name: &str| {
+            run_util_stdin(run, &["tail", "-n", count, &fixture.path(name)], b"").1
+        };
+        assert_eq!(tail_file("10", "twenty"), lines(11..=20));
+        assert_eq!(tail_file("2", "unterminated"), "b\nc");
+        assert_eq!(tail_file("1", "unterminated"), "c");
+        assert_eq!(tail_file("5", "short"), "a\nb\n");
+        assert_eq!(tail_file("2", "short"), "a\nb\n");
+        assert_eq!(tail_file("1", "blank"), "\n");
+        assert_eq!(tail_file("0", "twenty"), "");
+        assert_eq!(tail_file("3", "empty"), "");
+        assert_eq!(tail_file("1", "one"), "only");
+    }
+
+    #[test]
+    fn test_only_the_end_of_a_file_is_read() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("large");
+        let content = lines(1..=500_000);
+        assert!(content.len() > 50 * BUFFER_SIZE);
+        fs::write(&path, &content).unwrap();
+
+        let mut reader = CountingReader {
+            inner: File::open(&path).unwrap(),
+            read: 0,
+        };
+        let mut stdout = Vec::new();
+        assert!(copy_last_lines_seekable(&mut reader, 3, b'\n', &mut stdout).is_ok());
+        assert_eq!(stdout, lines(499_998..=500_000).as_bytes());
+        // A block is scanned, then the last lines are read again to copy them.
+        assert!(reader.read <= BUFFER_SIZE as u64 * 2, "{}", reader.read);
+    }
+
+    #[test]
+    fn test_lines_across_blocks() {
+        // Lines longer than a block, and a count which needs several blocks.
+        let long = "x".repeat(BUFFER_SIZE + 10);
+        let content = format!("{long}\n{long}\n{long}");
+        for (count, expected) in [
+            (1, long.clone()),
+            (2, format!("{long}\n{long}")),
+            (3, content.clone()),
+            (4, content.clone()),
+        ] {
+            let mut stdout = Vec::new();
+            let mut reader = io::Cursor::new(content.as_bytes());
+            assert!(copy_last_lines_seekable(&mut reader, count, b'\n', &mut stdout).is_ok());
+            assert_eq!(stdout, expected.as_bytes());
+        }
+
+        let content = lines(1..=100_000);
+        let mut stdout = Vec::new();
+        let mut reader = io::Cursor::new(content.as_bytes());
+        assert!(copy_last_lines_seekable(&mut reader, 50_000, b'\n', &mut stdout).is_ok());
+        assert_eq!(stdout, lines(50_001..=100_000).as_bytes());
+    }
+
+    #[test]
+    fn test_zero_terminated() {
+        assert_eq!(
+            run_util_stdin(run, &["tail", "-z", "-n", "2"], b"a\0b\nc\0d").1,
+            "b\nc\0d"
+        );
+
+        let fixture = Fixture::new(&[("records", "a\0b\0c\0")]);
+        assert_eq!(
+            run_util_stdin(run, &["tail", "-zn1", &fixture.path("records")], b"").1,
+            "c\0"
+        );
+    }
+
+    #[test]
+    fn test_invalid_lines() {
+        for count in ["x", "1x", "", "+-5"] {
+            let (code, stdout, stderr) =
+                run_util_stdin(run, &["tail", "-n", count, "missing"], b"");
+            assert_eq!(code, 1);
+            assert_eq!(stdout, "");
+            assert_eq!(
+                stderr,
+                format!("tail: invalid number of lines: '{count}'\n")
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_bytes() {
+        let (code, _, stderr) = run_util_stdin(run, &["tail", "-c", "1KX"], b"");
+        assert_eq!(code, 1);
+        assert_eq!(stderr, "tail: invalid number of bytes: '1KX'\n");
+        assert_eq!(
+            run_util_stdin(run, &["tail", "-c", "99999999999999999999999"], b"").2,
+            "tail: invalid number of bytes: '99999999999999999999999': Value too large for defined data \
+             type\n"
+        );
+    }
+
+    /// Run tail on the content, both from a pipe and from a file, and check that both print the same.
+    fn tail_both(args: &[&str], content: &[u8]) -> Vec<u8> {
+        let fixture = Fixture::new(&[]);
+        let path = fixture.path("input");
+        fs::write(&path, content).unwrap();
+
+        let mut outputs = Vec::new();
+        for file in [None, Some(path.as_str())] {
+            let args: Vec<OsString> = std::iter::once("tail")
+                .chain(args.iter().copied())
+                .chain(file)
+                .map(OsString::from)
+                .collect();
+            let mut stdout = Vec::new();
+            let mut stderr = Vec::new();
+            let code = run(&args, &mut &content[..], &mut stdout, &mut stderr);
+            assert_eq!((code, stderr), (0, Vec::new()));
+            outputs.push(stdout);
+        }
+        assert_eq!(outputs[0], outputs[1], "{args:?}");
+        outputs.pop().unwrap()
+    }
+
+    #[test]
+    fn test_bytes() {
+        assert_eq!(tail_both(&["-c", "3"], b"abcdef"), b"def");
+        assert_eq!(tail_both(&["-c", "10"], b"abcdef"), b"abcdef");
+        assert_eq!(tail_both(&["-c", "0"], b"abcdef"), b"");
+        assert_eq!(tail_both(&["-c", "-2"], b"abcdef"), b"ef");
+        assert_eq!(tail_both(&["--bytes=2"], b""), b"");
+
+        let content = "x".repeat(1500) + &"y".repeat(1024);
+        assert_eq!(
+            tail_both(&["-c", "1K"], content.as_bytes()),
+            "y".repeat(1024).as_bytes()
+        );
+        assert_eq!(
+            tail_both(&["-c", "1b"], content.as_bytes()),
+            &content.as_bytes()[2012..]
+        );
+        // More than the window of a pipe.
+        let content = lines(1..=100_000);
+        assert_eq!(
+            tail_both(&["-c", "100K"], content.as_bytes()),
+            &content.as_bytes()[content.len() - 102_400..]
+        );
+    }
+
+    #[test]
+    fn test_from_line() {
+        let content = b"a\nb\nc";
+        assert_eq!(tail_both(&["-n", "+1"], content), content);
+        assert_eq!(tail_both(&["-n", "+0"], content), content);
+        assert_eq!(tail_both(&["-n", "+2"], content), b"b\nc");
+        assert_eq!(tail_both(&["-n", "+3"], content), b"c");
+        // Beyond the end.
+        assert_eq!(tail_both(&["-n", "+4"], content), b"");
+        assert_eq!(tail_both(&["-n", "+99999"], content), b"");
+        assert_eq!(tail_both(&["-zn", "+2"], b"a\0b\nc\0"), b"b\nc\0");
+        assert_eq!(
+            tail_both(&["-n", "+1K"], lines(1..=2000).as_bytes()),
+            lines(1024..=2000).as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_from_byte() {
+        let content = b"abcdef";
+        assert_eq!(tail_both(&["-c", "+1"], content), content);
+        assert_eq!(tail_both(&["-c", "+0"], content), content);
+        assert_eq!(tail_both(&["-c", "+3"], content), b"cdef");
+        assert_eq!(tail_both(&["-c", "+6"], content), b"f");
+        // Beyond the end.
+        assert_eq!(tail_both(&["-c", "+7"], content), b"");
+        assert_eq!(tail_both(&["-c", "+1G"], content), b"");
+
+        let content = lines(1..=100_000);
+        assert_eq!(
+            tail_both(&["-c", "+100K"], content.as_bytes()),
+            &content.as_bytes()[102_399..]
+        );
+    }
+
+    #[test]
+    fn test_binary() {
+        let content: Vec<u8> = (0..=255).cycle().take(300_000).collect();
+        assert_eq!(tail_both(&["-c", "70000"], &content), &content[230_000..]);
+        assert_eq!(tail_both(&["-c", "+70001"], &content), &content[70_000..]);
+        // Bytes which are not UTF-8 have no effect on lines.
+        let content = b"\xff\xfe\n\x80\n\xc3";
+        assert_eq!(tail_both(&["-n", "2"], content), b"\x80\n\xc3");
+        assert_eq!(tail_both(&["-n", "+2"], content), b"\x80\n\xc3");
+    }
+
+    #[test]
+    fn test_last_of_bytes_and_lines_wins() {
+        assert_eq!(tail_both(&["-c", "2", "-n", "1"], b"a\nbc\n"), b"bc\n");
+        assert_eq!(tail_both(&["-n", "1", "-c", "2"], b"a\nbc\n"), b"c\n");
+    }
+
+    #[test]
+    fn test_obsolete_count() {
+        let content = b"1\n2\n3\n4\n5\n";
+        assert_eq!(tail_both(&["-2"], content), b"4\n5\n");
+        assert_eq!(tail_both(&["-2l"], content), b"4\n5\n");
+        assert_eq!(tail_both(&["+2"], content), b"2\n3\n4\n5\n");
+        assert_eq!(tail_both(&["+2l"], content), b"2\n3\n4\n5\n");
+        assert_eq!(tail_both(&["-3c"], content), b"\n5\n");
+        assert_eq!(tail_both(&["+3c"], content), b"2\n3\n4\n5\n");
+        assert_eq!(tail_both(&["-1b"], content), content);
+        assert_eq!(tail_both(&["+9"], content), b"");
+        // Only as the first argument, elsewhere it is an unknown option.
+        assert_eq!(run_util_stdin(run, &["tail", "-n1", "-2"], b"").0, 2);
+        // Anything else after the count is not the obsolete form.
+        assert_eq!(run_util_stdin(run, &["tail", "-2x"], b"").0, 2);
+        // A file named like a count can follow `--`.
+        assert_eq!(run_util_stdin(run, &["tail", "--", "+2"], b"").0, 1);
+    }
+
+    #[test]
+    fn test_headers() {
+        let fixture = Fixture::new(&[("a", "1\n2\n"), ("b", "3\n")]);
+        let (a, b) = (fixture.path("a"), fixture.path("b"));
+
+        assert_eq!(
+            run_util_stdin(run, &["tail", "-n1", &a, "-", &b], b"stdin\n").1,
+            format!("==> {a} <==\n2\n\n==> standard input <==\nstdin\n\n==> {b} <==\n3\n")
+        );
+        assert_eq!(
+            run_util_stdin(run, &["tail", "-q", &a, &b], b"").1,
+            "1\n2\n3\n"
+        );
+        assert_eq!(
+            run_util_stdin(run, &["tail", "--silent", &a, &b], b"").1,
+            "1\n2\n3\n"
+        );
+        assert_eq!(
+            run_util_stdin(run, &["tail", "-v"], b"x\n").1,
+            "==> standard input <==\nx\n"
+        );
+        // The last of -q and -v wins.
+        assert_eq!(
+            run_util_stdin(run, &["tail", "-q", "-v", &b], b"").1,
+            format!("==> {b} <==\n3\n")
+        );
+        assert_eq!(
+            run_util_stdin(run, &["tail", "-v", "-q", &a, &b], b"").1,
+            "1\n2\n3\n"
+        );
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let fixture = Fixture::new(&[("a", "a\n")]);
+        let a = fixture.path("a");
+        let missing = fixture.path("missing");
+
+        let (code, stdout, stderr) = run_util_stdin(run, &["tail", &missing, &a], b"");
+        assert_eq!(code, 1);
+        assert_eq!(stdout, format!("==> {a} <==\na\n"));
+        assert_eq!(
+            stderr,
+            format!("tail: cannot open '{missing}' for reading: No such file or directory\n")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_directory() {
+        let fixture = Fixture::new(&[("a", "a\n")]);
+        let directory = fixture.0.path().to_str().unwrap().to_owned();
+        let (code, stdout, stderr) =
+            run_util_stdin(run, &["tail", &directory, &fixture.path("a")], b"");
+        assert_eq!(code, 1);
+        assert_eq!(
+            stdout,
+            format!("==> {directory} <==\n\n==> {} <==\na\n", fixture.path("a"))
+        );
+        assert_eq!(
+            stderr,
+            format!("tail: error reading '{directory}': Is a directory\n")
+        );
+    }
+
+    #[test]
+    fn test_large_pipe() {
+        let content = lines(1..=100_000);
+        let output = Command::cargo_bin("tail")
+            .unwrap()
+            .args(["-n", "3"])
+            .write_stdin(content)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, lines(99_998..=100_000).as_bytes());
+    }
+
+    /// Run tail in-process on the files, waiting with `wait`, and return the exit code, stdout, and stderr.
+    fn follow_files(
+        files: &[&str],
+        options: &TailOptions,
+        wait: &mut dyn Wait,
+    ) -> (i32, String, String) {
+        let operands = files.iter().map(OsString::from).collect();
+        let mut inputs = InputIter::with_stdin(operands, Box::new(io::empty()));
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let mut reporter = Reporter::new("tail", &mut stderr);
+        let result = tail_with(&mut inputs, options, wait, &mut stdout, &mut reporter);
+        let code = reporter.finish(result);
+        (
+            code,
+            String::from_utf8(stdout).unwrap(),
+            String::from_utf8(stderr).unwrap(),
+        )
+    }
+
+    /// Append to a file.
+    fn append(path: &str, data: &str) {
+        let mut file = fs::OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(data.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn test_follow() {
+        let fixture = Fixture::new(&[("a", "a1\n"), ("b", "b1\n")]);
+        let (a, b) = (fixture.path("a"), fixture.path("b"));
+        let options = TailOptions {
+            follow: Some(Duration::ZERO),
+            headers: true,
+            ..TailOptions::default()
+        };
+        let mut round = 0;
+        let mut wait = |_: &[Followed]| {
+            round += 1;
+            match round {
+                1 => append(&a, "a2\n"),
+                2 => append(&b, "b2\n"),
+                3 => {
+                    append(&b, "b3\n");
+                    fs::write(&a, "new\n").unwrap();
+                }
+                4 => {}
+                _ => return false,
+            }
+            true
+        };
+        assert_eq!(
+            follow_files(&[&a, &b], &options, &mut wait),
+            (
+                0,
+                format!(
+                    "==> {a} <==\na1\n\n==> {b} <==\nb1\n\n==> {a} <==\na2\n\n==> {b} <==\nb2\n\
+                     \n==> {a} <==\nnew\n\n==> {b} <==\nb3\n"
+                ),
+                format!("tail: {a}: file truncated\n")
+            )
+        );
+    }
+
+    #[test]
+    fn test_follow_same_file_has_no_header() {
+        let fixture = Fixture::new(&[("a", "a1\n")]);
+        let a = fixture.path("a");
+        let options = TailOptions {
+            follow: Some(Duration::ZERO),
+            headers: true,
+            ..TailOptions::default()
+        };
+        let mut rounds = ["a2\n", "a3"].into_iter();
+        let mut wait = |_: &[Followed]| rounds.next().map(|data| append(&a, data)).is_some();
+        assert_eq!(
+            follow_files(&[&a], &options, &mut wait).1,
+            format!("==> {a} <==\na1\na2\na3")
+        );
+    }
+
+    #[test]
+    fn test_follow_zero_lines() {
+        let fixture = Fixture::new(&[("a", "old\n")]);
+        let a = fixture.path("a");
+        let options = TailOptions {
+            count: Count::Lines(0),
+            follow: Some(Duration::ZERO),
+            ..TailOptions::default()
+        };
+        let mut appended = false;
+        let mut wait = |_: &[Followed]| {
+            if appended {
+                return false;
+            }
+            append(&a, "new\n");
+            appended = true;
+            true
+        };
+        assert_eq!(
+            follow_files(&[&a], &options, &mut wait),
+            (0, "new\n".to_owned(), String::new())
+        );
+    }
+
+    #[test]
+    fn test_follow_without_files() {
+        // Standard input is read to its end.
+        assert_eq!(
+            run_util_stdin(run, &["tail", "-f", "-s", "0"], b"a\nb\n"),
+            (0, "a\nb\n".to_owned(), String::new())
+        );
+
+        let fixture = Fixture::new(&[]);
+        let missing = fixture.path("missing");
+        assert_eq!(
+            run_util_stdin(run, &["tail", "-f", &missing], b""),
+            (
+                1,
+                String::new(),
+                format!(
+                    "tail: cannot open '{missing}' for reading: No such file or directory\n\
+                     tail: no files remaining\n"
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn test_invalid_sleep_interval() {
+        for interval in ["x", "-1", "", "inf"] {
+            assert_eq!(
+                run_util_stdin(run, &["tail", "-f", "-s", interval], b""),
+                (
+                    1,
+                    String::new(),
+                    format!("tail: invalid number of seconds: '{interval}'\n")
+                )
+            );
+        }
+        assert_eq!(parse_interval("0.25"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_interval("2"), Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn test_obsolete_follow() {
+        let expand = |args: &[&str]| {
+            let args = args.iter().map(OsString::from).collect();
+            expand_obsolete_count(args)
+        };
+        assert_eq!(
+            expand(&["tail", "-2f", "a"]),
+            ["tail", "-n", "2", "-f", "a"]
+        );
+        assert_eq!(expand(&["tail", "+3cf"]), ["tail", "-c", "+3", "-f"]);
+        assert_eq!(expand(&["tail", "-2fc"]), ["tail", "-2fc"]);
+    }
+
+    #[test]
+    fn test_follow_binary() {
+        use assert_cmd::cargo::CommandCargoExt;
+        use std::process::Stdio;
+        use std::sync::mpsc;
+        use std::time::Instant;
+
+        let fixture = Fixture::new(&[("log", "first\n")]);
+        let log = fixture.path("log");
+        let mut child = std::process::Command::cargo_bin("tail")
+            .unwrap()
+            .args(["-f", "-s", "0.05", &log])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut stdout = child.stdout.take().unwrap();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut buffer = [0; 1024];
+            while let Ok(length @ 1..) = stdout.read(&mut buffer) {
+                if sender.send(buffer[..length].to_vec()).is_err() {
+                    break;
+                }
+            }
+        });
+
+        // Read the output until it is the expected one, failing after a timeout.
+        let mut output = Vec::new();
+        let mut wait_for = |expected: &str| {
+            let deadline = Instant::now() + Duration::from_secs(10);
+            while output != expected.as_bytes() {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                match receiver.recv_timeout(timeout) {
+                    Ok(data) => output.extend_from_slice(&data),
+                    Err(_) => panic!(
+                        "expected {expected:?}, got {:?}",
+                        String::from_utf8_lossy(&output)
+                    ),
+                }
+            }
+        };
+        wait_for("first\n");
+        append(&log, "second\n");
+        wait_for("first\nsecond\n");
+        append(&log, "third");
+        wait_for("first\nsecond\nthird");
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn test_run_write_error() {
+        let fixture = Fixture::new(&[("a", &lines(1..=20))]);
+        let args: Vec<OsString> = ["tail", &fixture.path("a")]
+            .iter()
+            .map(OsString::from)
+            .collect();
+        let mut stdout = io::Cursor::new([0; 4]);
+        let mut stderr = Vec::new();
+        let code = run(&args, &mut io::empty(), &mut stdout, &mut stderr);
+        assert_eq!(code, 1);
+        assert!(String::from_utf8(stderr)
+            .unwrap()
+            .starts_with("tail: write error: "));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_broken_pipe() {
+        use assert_cmd::cargo::CommandCargoExt;
+        use std::os::unix::process::ExitStatusExt;
+        use std::process::Stdio;
+
+        let fixture = Fixture::new(&[("a", &lines(1..=200_000))]);
+        let mut child = std::process::Command::cargo_bin("tail")
+            .unwrap()
+            .args(["-n", "200000", &fixture.path("a")])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        drop(child.stdout.take());
+        let output = child.wait_with_output().unwrap();
+        assert_eq!(output.status.signal(), Some(13));
+        assert!(output.stderr.is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_full_disk() {
+        use assert_cmd::prelude::*;
+
+        let fixture = Fixture::new(&[("a", "a\n")]);
+        let output = std::process::Command::cargo_bin("tail")
+            .unwrap()
+            .arg(fixture.path("a"))
+            .stdout(File::create("/dev/full").unwrap())
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(
+            String::from_utf8(output.stderr).unwrap(),
+            "tail: write error: No space left on device\n"
+        );
+    }
+}
@@ -0,0 +1,703 @@
+//! Change the access and modification times of files, creating the missing ones.
+//!
+//! The times are the current time, or the ones given with `-d`, `-t`, or `-r`.
+//!
+//! ```
+//! use rust_coreutils::touch::{touch_file, TouchOptions};
+//!
+//! let directory = tempfile::tempdir().unwrap();
+//! let file = directory.path().join("file");
+//! touch_file(&file, &TouchOptions::default()).unwrap();
+//! assert_eq!(std::fs::read(&file).unwrap(), b"");
+//!
+//! let options = TouchOptions {
+//!     no_create: true,
+//!     ..TouchOptions::default()
+//! };
+//! touch_file(&directory.path().join("missing"), &options).unwrap();
+//! assert!(!directory.path().join("missing").exists());
+//! ```
+
+use crate::common::datetime::parse_datetime;
+use crate::common::error::{io_error_message, Reporter, UError, UResult, EXIT_FAILURE};
+#[cfg(unix)]
+use crate::common::paths::c_path;
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveTime, TimeZone};
+use clap::Parser;
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, ErrorKind, Read, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "touch",
+    author,
+    version,
+    about = "Update the access and modification times of each FILE to the current time, creating the \
+             missing ones as empty files.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Only change the access time.
+    #[arg(short = 'a', default_value_t = false)]
+    access: bool,
+
+    /// Do not create the missing files.
+    #[arg(short = 'c', long, default_value_t = false)]
+    no_create: bool,
+
+    /// Use the time described by STRING instead of the current time.
+    #[arg(short = 'd', long = "date", value_name = "STRING")]
+    date: Option<String>,
+
+    /// Only change the modification time.
+    #[arg(short = 'm', default_value_t = false)]
+    modification: bool,
+
+    /// Use the times of FILE instead of the current time.
+    #[arg(short = 'r', long, value_name = "FILE")]
+    reference: Option<OsString>,
+
+    /// Use [[CC]YY]MMDDhhmm[.ss] instead of the current time.
+    #[arg(short = 't', value_name = "STAMP")]
+    timestamp: Option<String>,
+
+    /// The files to touch.
+    #[arg(value_name = "FILE")]
+    files: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "touch";
+}
+
+/// The description of touch for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run touch with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run touch with the given command line, including the program name, on the given streams, and return
+/// the exit code.
+pub fn run(
+    args: &[OsString],
+    _stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(args.iter().cloned(), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let mut reporter = Reporter::new("touch", stderr);
+    let times = match explicit_times(&args) {
+        Ok(times) => times,
+        Err(error) => return reporter.finish(Err(error)),
+    };
+    // Both times change unless only one of them is selected.
+    let options = TouchOptions {
+        no_create: args.no_create,
+        access: args.access || !args.modification,
+        modification: args.modification || !args.access,
+        times,
+    };
+    let result = touch(&args.files, &options, &mut reporter);
+    reporter.finish(result)
+}
+
+/// Get the access and modification times given with `-d`, `-t`, or `-r`, if any.
+fn explicit_times(args: &Args) -> UResult<Option<(SystemTime, SystemTime)>> {
+    let sources = [
+        args.date.is_some(),
+        args.timestamp.is_some(),
+        args.reference.is_some(),
+    ];
+    if sources.into_iter().filter(|&given| given).count() > 1 {
+        return Err(UError::new(
+            EXIT_FAILURE,
+            "cannot specify times from more than one source",
+        ));
+    }
+    let invalid = |s: &str| UError::new(EXIT_FAILURE, format!("invalid date format '{s}'"));
+    if let Some(date) = &args.date {
+        let time = parse_datetime(date, SystemTime::now()).map_err(|_| invalid(date))?;
+        return Ok(Some((time, time)));
+    }
+    if let Some(stamp) = &args.timestamp {
+        let time = parse_timestamp(stamp, SystemTime::now()).ok_or_else(|| invalid(stamp))?;
+        return Ok(Some((time, time)));
+    }
+    if let Some(reference) = &args.reference {
+        let path = Path::new(reference);
+        let times = fs::metadata(path)
+            .and_then(|metadata| Ok((metadata.accessed()?, metadata.modified()?)));
+        return times
+            .map(Some)
+            .map_err(|error| failure("failed to get attributes of", path, &error));
+    }
+    Ok(None)
+}
+
+/// Parse a `-t` time, `[[CC]YY]MMDDhhmm[.ss]`, in local time.
+///
+/// Without a century, the years 69 to 99 are in the 20th century and the other ones in the 21st, and without
+/// a year, it is the year of `now`. Like in POSIX, the seconds can be 60 for a leap second, which is the
+/// start of the next minute.
+fn parse_timestamp(stamp: &str, now: SystemTime) -> Option<SystemTime> {
+    let (digits, seconds) = match stamp.split_once('.') {
+        Some((digits, seconds)) if seconds.len() == 2 => (digits, seconds),
+        Some(_) => return None,
+        None => (stamp, "00"),
+    };
+    if !digits
+        .bytes()
+        .chain(seconds.bytes())
+        .all(|byte| byte.is_ascii_digit())
+    {
+        return None;
+    }
+    let (year, rest) = match digits.len() {
+        8 => (DateTime::<Local>::from(now).year(), digits),
+        10 => {
+            let year: i32 = digits[..2].parse().ok()?;
+            (
+                if year < 69 { 2000 + year } else { 1900 + year },
+                &digits[2..],
+            )
+        }
+        12 => (digits[..4].parse().ok()?, &digits[4..]),
+        _ => return None,
+    };
+    let field = |start: usize| rest[start..start + 2].parse::<u32>().ok();
+    let second: u32 = seconds.parse().ok()?;
+    if second > 60 {
+        return None;
+    }
+    let date = NaiveDate::from_ymd_opt(year, field(0)?, field(2)?)?;
+    let time = NaiveTime::from_hms_opt(field(4)?, field(6)?, second.min(59))?;
+    let time = Local.from_local_datetime(&date.and_time(time)).earliest()?;
+    Some(SystemTime::from(time) + Duration::from_secs(u64::from(second == 60)))
+}
+
+/// The options of touch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TouchOptions {
+    /// Do not create the missing files (`-c`).
+    pub no_create: bool,
+    /// Change the access time, which `-m` alone leaves alone.
+    pub access: bool,
+    /// Change the modification time, which `-a` alone leaves alone.
+    pub modification: bool,
+    /// The access and modification times to set, instead of the current time.
+    pub times: Option<(SystemTime, SystemTime)>,
+}
+
+impl Default for TouchOptions {
+    fn default() -> Self {
+        TouchOptions {
+            no_create: false,
+            access: true,
+            modification: true,
+            times: None,
+        }
+    }
+}
+
+/// Touch the files, reporting the failures and continuing with the next one.
+pub fn touch<W: Write>(
+    operands: &[OsString],
+    options: &TouchOptions,
+    reporter: &mut Reporter<W>,
+) -> UResult<()> {
+    if operands.is_empty() {
+        return Err(UError::usage(EXIT_FAILURE, "missing file operand"));
+    }
+    for operand in operands {
+        // TODO: GNU touches the file open as standard output for `-`, rather than a file named `-`.
+        if let Err(error) = touch_file(Path::new(operand), options) {
+            reporter.error(format_args!("{error}"));
+        }
+    }
+    Ok(())
+}
+
+/// Set the times of a file selected in the options to `options.times` or the current time, creating it as an
+/// empty file unless `options.no_create`.
+///
+/// The times are set through the file if it can be opened for writing, and through its path otherwise,
+/// like for a directory. A missing file is not an error with `options.no_create`.
+pub fn touch_file(path: &Path, options: &TouchOptions) -> UResult<()> {
+    let file = match options.no_create {
+        true => None,
+        false => Some(open(path)),
+    };
+    let result = match &file {
+        Some(Ok(file)) => set_times(Some(file), path, options),
+        _ => set_times(None, path, options),
+    };
+    match (result, file) {
+        (Ok(()), _) => Ok(()),
+        // The file could not be created, or could not be written to and its times cannot be set either.
+        (Err(_), Some(Err(error))) => Err(failure("cannot touch", path, &error)),
+        (Err(error), _) if options.no_create && error.kind() == ErrorKind::NotFound => Ok(()),
+        (Err(error), _) => Err(failure("setting times of", path, &error)),
+    }
+}
+
+fn failure(operation: &str, path: &Path, error: &io::Error) -> UError {
+    let message = format!(
+        "{operation} '{}': {}",
+        path.display(),
+        io_error_message(error)
+    );
+    UError::new(EXIT_FAILURE, message)
+}
+
+/// Open a file for writing, creating it if it is missing, without blocking on a fifo or making a terminal
+/// the controlling one.
+#[cfg(unix)]
+fn open(path: &Path) -> io::Result<File> {
+    use std::os::unix::fs::OpenOptionsExt;
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .custom_flags(libc::O_NONBLOCK | libc::O_NOCTTY)
+        .mode(0o666)
+        .open(path)
+}
+
+/// Open a file for writing, creating it if it is missing.
+#[cfg(not(unix))]
+fn open(path: &Path) -> io::Result<File> {
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+}
+
+/// Set the selected times of a file, through the open file if there is one.
+#[cfg(unix)]
+fn set_times(file: Option<&File>, path: &Path, options: &TouchOptions) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+    let time = |change: bool, time: Option<SystemTime>| match (change, time) {
+        (true, Some(time)) => timespec(time),
+        (true, None) => libc::timespec {
+            tv_sec: 0,
+            tv_nsec: libc::UTIME_NOW,
+        },
+        (false, _) => libc::timespec {
+            tv_sec: 0,
+            tv_nsec: libc::UTIME_OMIT,
+        },
+    };
+    let times = [
+        time(options.access, options.times.map(|times| times.0)),
+        time(options.modification, options.times.map(|times| times.1)),
+    ];
+    let result = match file {
+        // SAFETY: the descriptor is open, and there are two times.
+        Some(file) => unsafe { libc::futimens(file.as_raw_fd(), times.as_ptr()) },
+        None => {
+            let path = c_path(path)?;
+            // SAFETY: the path is a valid C string, and there are two times.
+            unsafe { libc::utimensat(libc::AT_FDCWD, path.as_ptr(), times.as_ptr(), 0) }
+        }
+    };
+    match result {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// Convert a time to a `timespec`, whose nanoseconds are never negative, even before the epoch.
+#[cfg(unix)]
+fn timespec(time: SystemTime) -> libc::timespec {
+    let (seconds, nanoseconds) = match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(after) => (after.as_secs() as i64, i64::from(after.subsec_nanos())),
+        Err(error) => {
+            let before = error.duration();
+            match before.subsec_nanos() {
+                0 => (-(before.as_secs() as i64), 0),
+                nanoseconds => (
+                    -(before.as_secs() as i64) - 1,
+                    1_000_000_000 - i64::from(nanoseconds),
+                ),
+            }
+        }
+    };
+    libc::timespec {
+        tv_sec: seconds as libc::time_t,
+        tv_nsec: nanoseconds as _,
+    }
+}
+
+/// Set the selected times of a file, through the open file if there is one.
+#[cfg(not(unix))]
+fn set_times(file: Option<&File>, path: &Path, options: &TouchOptions) -> io::Result<()> {
+    let now = SystemTime::now();
+    let (accessed, modified) = options.times.unwrap_or((now, now));
+    let mut times = fs::FileTimes::new();
+    if options.access {
+        times = times.set_accessed(accessed);
+    }
+    if options.modification {
+        times = times.set_modified(modified);
+    }
+    match file {
+        Some(file) => file.set_times(times),
+        None => OpenOptions::new().write(true).open(path)?.set_times(times),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::run_util_stderr;
+    use std::fs::FileTimes;
+
+    /// Set the access and modification times of a file to distinct times in the past.
+    fn set_old_times(path: &Path) -> (SystemTime, SystemTime) {
+        let accessed = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_100_000_000);
+        let times = FileTimes::new()
+            .set_accessed(accessed)
+            .set_modified(modified);
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_times(times)
+            .unwrap();
+        (accessed, modified)
+    }
+
+    /// Get the access and modification times of a file.
+    fn times(path: &Path) -> (SystemTime, SystemTime) {
+        let metadata = fs::metadata(path).unwrap();
+        (metadata.accessed().unwrap(), metadata.modified().unwrap())
+    }
+
+    #[test]
+    fn test_create() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::write(root.join("old"), "contents").unwrap();
+        let (accessed, modified) = set_old_times(&root.join("old"));
+        let path = |name| root.join(name).display().to_string();
+
+        assert_eq!(
+            run_util_stderr(run, &["touch", &path("new"), &path("old")]),
+            (0, String::new())
+        );
+        assert_eq!(fs::read(root.join("new")).unwrap(), b"");
+        // The contents are kept, and both times are now.
+        assert_eq!(fs::read(root.join("old")).unwrap(), b"contents");
+        let (new_accessed, new_modified) = times(&root.join("old"));
+        assert!(new_accessed > accessed && new_modified > modified);
+
+        assert_eq!(
+            run_util_stderr(run, &["touch", &path("missing/file"), &path("other")]),
+            (
+                EXIT_FAILURE,
+                format!(
+                    "touch: cannot touch '{}': No such file or directory\n",
+                    path("missing/file")
+                )
+            )
+        );
+        assert!(root.join("other").exists());
+        assert_eq!(
+            run_util_stderr(run, &["touch"]),
+            (
+                EXIT_FAILURE,
+                "touch: missing file operand\nTry 'touch --help' for more information.\n"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn test_no_create() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        let path = |name| root.join(name).display().to_string();
+
+        for args in [&["touch", "-c"][..], &["touch", "--no-create"]] {
+            let mut command_line = args.to_vec();
+            let (missing, nested) = (path("missing"), path("missing/file"));
+            command_line.extend([missing.as_str(), nested.as_str()]);
+            assert_eq!(run_util_stderr(run, &command_line), (0, String::new()));
+            assert!(!root.join("missing").exists());
+        }
+    }
+
+    #[test]
+    fn test_only_one_time() {
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("file");
+        fs::write(&file, "").unwrap();
+        let name = file.display().to_string();
+
+        let (accessed, modified) = set_old_times(&file);
+        assert_eq!(
+            run_util_stderr(run, &["touch", "-a", &name]),
+            (0, String::new())
+        );
+        let (new_accessed, new_modified) = times(&file);
+        assert!(new_accessed > accessed);
+        assert_eq!(new_modified, modified);
+
+        let (accessed, modified) = set_old_times(&file);
+        assert_eq!(
+            run_util_stderr(run, &["touch", "-m", &name]),
+            (0, String::new())
+        );
+        let (new_accessed, new_modified) = times(&file);
+        assert_eq!(new_accessed, accessed);
+        assert!(new_modified > modified);
+
+        // Both means neither.
+        let (accessed, modified) = set_old_times(&file);
+        assert_eq!(
+            run_util_stderr(run, &["touch", "-am", &name]),
+            (0, String::new())
+        );
+        let (new_accessed, new_modified) = times(&file);
+        assert!(new_accessed > accessed && new_modified > modified);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_read_only_directory() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir(root.join("ro")).unwrap();
+        fs::set_permissions(root.join("ro"), fs::Permissions::from_mode(0o555)).unwrap();
+        let file = root.join("ro/file").display().to_string();
+
+        let result = run_util_stderr(run, &["touch", &file]);
+        fs::set_permissions(root.join("ro"), fs::Permissions::from_mode(0o755)).unwrap();
+        // The superuser can create files in the read-only directory.
+        if unsafe { libc::geteuid() } == 0 {
+            assert_eq!(result, (0, String::new()));
+            return;
+        }
+        assert_eq!(
+            result,
+            (
+                EXIT_FAILURE,
+                format!("touch: cannot touch '{file}': Permission denied\n")
+            )
+        );
+        assert!(!root.join("ro/file").exists());
+    }
+
+    #[test]
+    fn test_directory() {
+        let directory = tempfile::tempdir().unwrap();
+        let name = directory.path().display().to_string();
+        assert_eq!(run_util_stderr(run, &["touch", &name]), (0, String::new()));
+        assert_eq!(
+            run_util_stderr(run, &["touch", "-c", &name]),
+            (0, String::new())
+        );
+    }
+
+    /// Get a local time.
+    fn local(year: i32, month: u32, day: u32, hour: u32, minute: u32, second: u32) -> SystemTime {
+        let time = Local.with_ymd_and_hms(year, month, day, hour, minute, second);
+        SystemTime::from(time.earliest().unwrap())
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        let now = local(2024, 6, 15, 12, 0, 0);
+        let cases = [
+            ("202001020304", local(2020, 1, 2, 3, 4, 0)),
+            ("202001020304.05", local(2020, 1, 2, 3, 4, 5)),
+            ("2001020304", local(2020, 1, 2, 3, 4, 0)),
+            ("6801020304", local(2068, 1, 2, 3, 4, 0)),
+            ("6901020304.59", local(1969, 1, 2, 3, 4, 59)),
+            ("01020304", local(2024, 1, 2, 3, 4, 0)),
+            // A leap second is the next minute.
+            ("01020304.60", local(2024, 1, 2, 3, 5, 0)),
+        ];
+        for (stamp, time) in cases {
+            assert_eq!(parse_timestamp(stamp, now), Some(time), "{stamp}");
+        }
+        for stamp in [
+            "201301020304.61",
+            "201313020304",
+            "201302300304",
+            "201301022404",
+            "201301020360",
+            "1",
+            "0102030",
+            "01020304.5",
+            "01020304.",
+            "+1020304",
+            "0102030a",
+        ] {
+            assert_eq!(parse_timestamp(stamp, now), None, "{stamp}");
+        }
+    }
+
+    #[test]
+    fn test_timestamp() {
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("file");
+        let name = file.display().to_string();
+
+        assert_eq!(
+            run_util_stderr(run, &["touch", "-t", "202001020304.05", &name]),
+            (0, String::new())
+        );
+        let time = local(2020, 1, 2, 3, 4, 5);
+        assert_eq!(times(&file), (time, time));
+
+        // Only the selected time changes.
+        assert_eq!(
+            run_util_stderr(run, &["touch", "-m", "-t", "2101020304", &name]),
+            (0, String::new())
+        );
+        assert_eq!(times(&file), (time, local(2021, 1, 2, 3, 4, 0)));
+    }
+
+    #[test]
+    fn test_date() {
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("file");
+        let name = file.display().to_string();
+
+        assert_eq!(
+            run_util_stderr(run, &["touch", "-d", "@0", &name]),
+            (0, String::new())
+        );
+        assert_eq!(
+            times(&file),
+            (SystemTime::UNIX_EPOCH, SystemTime::UNIX_EPOCH)
+        );
+
+        let (accessed, _) = set_old_times(&file);
+        let date = "1970-01-02 00:00 UTC";
+        assert_eq!(
+            run_util_stderr(run, &["touch", "-m", "--date", date, &name]),
+            (0, String::new())
+        );
+        let day = SystemTime::UNIX_EPOCH + Duration::from_secs(24 * 60 * 60);
+        assert_eq!(times(&file), (accessed, day));
+
+        // Before the epoch.
+        assert_eq!(
+            run_util_stderr(run, &["touch", "-d", "@-1.25", &name]),
+            (0, String::new())
+        );
+        let before = SystemTime::UNIX_EPOCH - Duration::from_millis(1250);
+        assert_eq!(times(&file), (before, before));
+    }
+
+    #[test]
+    fn test_reference() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        let path = |name| root.join(name).display().to_string();
+        fs::write(root.join("reference"), "").unwrap();
+        let (accessed, modified) = set_old_times(&root.join("reference"));
+
+        assert_eq!(
+            run_util_stderr(run, &["touch", "-r", &path("reference"), &path("file")]),
+            (0, String::new())
+        );
+        assert_eq!(times(&root.join("file")), (accessed, modified));
+
+        fs::write(root.join("other"), "").unwrap();
+        let (_, other_modified) = times(&root.join("other"));
+        assert_eq!(
+            run_util_stderr(
+                run,
+                &[
+                    "touch",
+                    "-a",
+                    "--reference",
+                    &path("reference"),
+                    &path("other")
+                ]
+            ),
+            (0, String::new())
+        );
+        let (new_accessed, new_modified) = times(&root.join("other"));
+        assert_eq!(new_accessed, accessed);
+        assert_eq!(new_modified, other_modified);
+
+        assert_eq!(
+            run_util_stderr(run, &["touch", "-r", &path("missing"), &path("new")]),
+            (
+                EXIT_FAILURE,
+                format!(
+                    "touch: failed to get attributes of '{}': No such file or directory\n",
+                    path("missing")
+                )
+            )
+        );
+        assert!(!root.join("new").exists());
+    }
+
+    #[test]
+    fn test_invalid_times() {
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("file");
+        let name = file.display().to_string();
+
+        for (args, message) in [
+            (
+                &["-t", "202013010000"][..],
+                "invalid date format '202013010000'",
+            ),
+            (
+                &["-t", "202001010000.61"],
+                "invalid date format '202001010000.61'",
+            ),
+            (&["-d", "someday"], "invalid date format 'someday'"),
+            (
+                &["-d", "@0", "-t", "01010000"],
+                "cannot specify times from more than one source",
+            ),
+            (
+                &["-d", "@0", "-r", &name],
+                "cannot specify times from more than one source",
+            ),
+            (
+                &["-t", "01010000", "-r", &name],
+                "cannot specify times from more than one source",
+            ),
+        ] {
+            let mut command_line = vec!["touch"];
+            command_line.extend(args);
+            command_line.push(&name);
+            assert_eq!(
+                run_util_stderr(run, &command_line),
+                (EXIT_FAILURE, format!("touch: {message}\n"))
+            );
+            assert!(!file.exists());
+        }
+    }
+}
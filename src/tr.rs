@@ -0,0 +1,1324 @@
+//! Translate, squeeze, and delete bytes from standard input to standard output.
+//!
+//! The sets are parsed with [`Set::parse`], then turned into tables of 256 bytes with [`TrOptions::new`],
+//! and the input is streamed through them with [`tr`]:
+//!
+//! ```
+//! use rust_coreutils::tr::{tr, Flags, Set, TrOptions};
+//!
+//! let mut warnings = Vec::new();
+//! let set1 = Set::parse(b"a-y", &mut warnings).unwrap();
+//! let set2 = Set::parse(b"b-z", &mut warnings).unwrap();
+//! let options = TrOptions::new(&set1, Some(&set2), Flags::default()).unwrap();
+//! let mut stdout = Vec::new();
+//! tr(&mut &b"hal\n"[..], &options, &mut stdout).unwrap();
+//! assert_eq!(stdout, b"ibm\n");
+//! ```
+
+use crate::common::error::{Reporter, UError, UResult, EXIT_FAILURE};
+use crate::common::escapes::{expand_escape, EscapeFlavor};
+use crate::common::input::BUFFER_SIZE;
+use crate::common::os::os_str_bytes;
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use clap::Parser;
+use std::ffi::OsString;
+use std::io::{self, ErrorKind, Read, Write};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "tr",
+    author,
+    version,
+    about = "Translate the bytes of standard input which are in SET1 to the bytes at the same position in \
+             SET2, squeeze repeats of them, or delete them, and write the rest to standard output.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Use the bytes which are not in SET1, in ascending order, instead of SET1.
+    #[arg(short, long, short_alias = 'C', default_value_t = false)]
+    complement: bool,
+
+    /// Delete the bytes of SET1 instead of translating them.
+    #[arg(short, long, default_value_t = false)]
+    delete: bool,
+
+    /// Replace each run of a byte of the last SET with a single copy of the byte, after translating or
+    /// deleting.
+    #[arg(short, long = "squeeze-repeats", default_value_t = false)]
+    squeeze_repeats: bool,
+
+    /// Only translate the first bytes of SET1, as many as SET2 has, instead of repeating the last byte of
+    /// SET2.
+    #[arg(short, long = "truncate-set1", default_value_t = false)]
+    truncate_set1: bool,
+
+    /// The sets of bytes. They are strings of bytes, with backslash escapes like \n and \NNN in octal, ranges
+    /// like a-z, classes like [:alpha:], [=C=] for C, and in SET2, [C*N] for N copies of C, or as many as
+    /// needed to be as long as SET1 without N.
+    #[arg(value_name = "SET")]
+    sets: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "tr";
+}
+
+/// The description of tr for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run tr with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run tr with the given command line, including the program name, on the given streams, and return the
+/// exit code.
+pub fn run(
+    args: &[OsString],
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(args.iter().cloned(), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let mut reporter = Reporter::new("tr", stderr);
+    let options = match options(&args, &mut reporter) {
+        Ok(options) => options,
+        Err(error) => return reporter.finish(Err(error)),
+    };
+    let result = tr(stdin, &options, stdout);
+    reporter.finish(result)
+}
+
+/// Get the options of the command line, printing the warnings about the sets.
+fn options<W: Write>(args: &Args, reporter: &mut Reporter<W>) -> UResult<TrOptions> {
+    let flags = Flags {
+        complement: args.complement,
+        delete: args.delete,
+        squeeze: args.squeeze_repeats,
+        truncate: args.truncate_set1,
+    };
+    // Like GNU, two sets are needed to translate, or to delete and squeeze, and deleting alone takes one.
+    let minimum = if flags.delete == flags.squeeze { 2 } else { 1 };
+    let maximum = if flags.delete && !flags.squeeze { 1 } else { 2 };
+    let sets = &args.sets[..];
+    if sets.is_empty() {
+        return Err(UError::usage(EXIT_FAILURE, "missing operand"));
+    }
+    if sets.len() < minimum {
+        let reason = if flags.squeeze {
+            "Two strings must be given when both deleting and squeezing repeats."
+        } else {
+            "Two strings must be given when translating."
+        };
+        return Err(UError::usage(
+            EXIT_FAILURE,
+            format!(
+                "missing operand after '{}'\n{reason}",
+                sets[sets.len() - 1].to_string_lossy()
+            ),
+        ));
+    }
+    if sets.len() > maximum {
+        let mut message = format!("extra operand '{}'", sets[maximum].to_string_lossy());
+        if sets.len() == 2 {
+            message.push_str(
+                "\nOnly one string may be given when deleting without squeezing repeats.",
+            );
+        }
+        return Err(UError::usage(EXIT_FAILURE, message));
+    }
+
+    let mut warnings = Vec::new();
+    let parsed: Result<Vec<Set>, String> = sets
+        .iter()
+        .map(|set| Set::parse(&os_str_bytes(set), &mut warnings))
+        .collect();
+    for warning in warnings {
+        reporter.warning(format_args!("warning: {warning}"));
+    }
+    let failure = |message| UError::new(EXIT_FAILURE, message);
+    let parsed = parsed.map_err(failure)?;
+    TrOptions::new(&parsed[0], parsed.get(1), flags).map_err(failure)
+}
+
+/// A part of a [`Set`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Element {
+    /// A single byte.
+    Byte(u8),
+    /// The bytes from the first to the last one, included, like `a-z`.
+    Range(u8, u8),
+    /// Copies of a byte, like `[c*3]`, or as many as needed to make SET2 as long as SET1 without a count, like
+    /// `[c*]` or `[c*0]`.
+    Repeat(u8, Option<usize>),
+    /// The bytes of a class in ascending order, like `[:alpha:]`.
+    Class(Class),
+    /// The bytes which are equivalent to a byte, like `[=c=]`, which is only the byte itself.
+    Equivalence(u8),
+}
+
+/// A POSIX character class, over the bytes of the C locale.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Class {
+    /// Letters and digits.
+    Alnum,
+    /// Letters.
+    Alpha,
+    /// Space and tab.
+    Blank,
+    /// The control bytes, from 0 to 31 and 127.
+    Cntrl,
+    /// Decimal digits.
+    Digit,
+    /// The printable bytes except space.
+    Graph,
+    /// Lower case letters.
+    Lower,
+    /// The printable bytes, from space to `~`.
+    Print,
+    /// The printable bytes which are neither letters, digits, nor space.
+    Punct,
+    /// Space, `\t`, `\n`, `\v`, `\f`, and `\r`.
+    Space,
+    /// Upper case letters.
+    Upper,
+    /// Hexadecimal digits, in both cases.
+    Xdigit,
+}
+
+impl Class {
+    /// Find the class with the given name, like `alpha` for `[:alpha:]`.
+    pub fn from_name(name: &[u8]) -> Option<Class> {
+        Some(match name {
+            b"alnum" => Class::Alnum,
+            b"alpha" => Class::Alpha,
+            b"blank" => Class::Blank,
+            b"cntrl" => Class::Cntrl,
+            b"digit" => Class::Digit,
+            b"graph" => Class::Graph,
+            b"lower" => Class::Lower,
+            b"print" => Class::Print,
+            b"punct" => Class::Punct,
+            b"space" => Class::Space,
+            b"upper" => Class::Upper,
+            b"xdigit" => Class::Xdigit,
+            _ => return None,
+        })
+    }
+
+    /// Check whether a byte is in the class.
+    pub fn contains(self, byte: u8) -> bool {
+        match self {
+            Class::Alnum => byte.is_ascii_alphanumeric(),
+            Class::Alpha => byte.is_ascii_alphabetic(),
+            Class::Blank => matches!(byte, b' ' | b'\t'),
+            Class::Cntrl => byte.is_ascii_control(),
+            Class::Digit => byte.is_ascii_digit(),
+            Class::Graph => byte.is_ascii_graphic(),
+            Class::Lower => byte.is_ascii_lowercase(),
+            Class::Print => byte.is_ascii_graphic() || byte == b' ',
+            Class::Punct => byte.is_ascii_punctuation(),
+            // Unlike `u8::is_ascii_whitespace`, this includes the vertical tab.
+            Class::Space => matches!(byte, b' ' | b'\t'..=b'\r'),
+            Class::Upper => byte.is_ascii_uppercase(),
+            Class::Xdigit => byte.is_ascii_hexdigit(),
+        }
+    }
+
+    /// Check whether the class is one of the two which may be translated to, `[:upper:]` and `[:lower:]`.
+    fn is_case(self) -> bool {
+        matches!(self, Class::Upper | Class::Lower)
+    }
+}
+
+/// A set of bytes, as it was given on the command line.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Set {
+    elements: Vec<Element>,
+}
+
+impl Set {
+    /// Parse a set, with the GNU message of the first error. Constructs which are not portable are added to
+    /// `warnings`.
+    ///
+    /// The escapes are expanded first, and an escaped byte is never the start of a construct: `\-` is a
+    /// dash, and `\[` an opening bracket.
+    pub fn parse(arg: &[u8], warnings: &mut Vec<String>) -> Result<Set, String> {
+        let bytes = unescape(arg, warnings);
+        let special = |index: usize, byte: u8| bytes.get(index) == Some(&(byte, false));
+        let mut elements = Vec::new();
+        let mut index = 0;
+        // Like GNU, the constructs are only recognized with at least 3 bytes left, so that a trailing `-` is
+        // a byte.
+        while index + 2 < bytes.len() {
+            let (byte, _) = bytes[index];
+            if special(index, b'[') {
+                let rest = &bytes[index + 1..];
+                let construct = match parse_class(rest)? {
+                    Some(class) => Some(class),
+                    None => parse_repeat(rest)?,
+                };
+                if let Some((element, length)) = construct {
+                    elements.push(element);
+                    index += length;
+                    continue;
+                }
+            }
+            if special(index + 1, b'-') {
+                let (last, _) = bytes[index + 2];
+                if last < byte {
+                    return Err(format!(
+                        "range-endpoints of '{}-{}' are in reverse collating sequence order",
+                        printable(byte),
+                        printable(last)
+                    ));
+                }
+                elements.push(Element::Range(byte, last));
+                index += 3;
+            } else {
+                elements.push(Element::Byte(byte));
+                index += 1;
+            }
+        }
+        elements.extend(bytes[index..].iter().map(|&(byte, _)| Element::Byte(byte)));
+        Ok(Set { elements })
+    }
+
+    /// Get the parts of the set, in order.
+    pub fn elements(&self) -> &[Element] {
+        &self.elements
+    }
+
+    /// Get the number of repeats without a count.
+    fn indefinite_repeats(&self) -> usize {
+        self.elements
+            .iter()
+            .filter(|element| matches!(element, Element::Repeat(_, None)))
+            .count()
+    }
+
+    /// Check whether the set has a class, like `[:alpha:]`.
+    fn has_class(&self) -> bool {
+        self.elements
+            .iter()
+            .any(|element| matches!(element, Element::Class(_)))
+    }
+
+    /// Get the number of bytes of the set, leaving out the repeats without a count.
+    pub fn len(&self) -> usize {
+        self.elements
+            .iter()
+            .fold(0, |length, element| length.saturating_add(element.len()))
+    }
+
+    /// Check whether the set has no bytes, leaving out the repeats without a count.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the bytes of the set in order, where a repeat without a count makes it at least `length` bytes
+    /// long. The repeats are not expanded in memory, so that large counts are cheap.
+    pub fn bytes(&self, length: usize) -> impl Iterator<Item = u8> + '_ {
+        let fill = length.saturating_sub(self.len());
+        self.elements.iter().flat_map(move |element| {
+            let (first, last, count, class) = match *element {
+                Element::Byte(byte) | Element::Equivalence(byte) => (byte, byte, 1, None),
+                Element::Range(first, last) => (first, last, 1, None),
+                Element::Repeat(byte, count) => (byte, byte, count.unwrap_or(fill), None),
+                Element::Class(class) => (0, u8::MAX, 1, Some(class)),
+            };
+            (first..=last)
+                .filter(move |&byte| class.is_none_or(|class| class.contains(byte)))
+                .flat_map(move |byte| std::iter::repeat_n(byte, count))
+        })
+    }
+
+    /// Get the bytes which are in [`Set::bytes`], without going through the repeats.
+    pub fn members(&self, length: usize) -> [bool; 256] {
+        let fill = length.saturating_sub(self.len());
+        let mut members = [false; 256];
+        for element in &self.elements {
+            match *element {
+                Element::Byte(byte) | Element::Equivalence(byte) => members[byte as usize] = true,
+                Element::Range(first, last) => members[first as usize..=last as usize].fill(true),
+                Element::Repeat(byte, count) => members[byte as usize] |= count.unwrap_or(fill) > 0,
+                Element::Class(class) => {
+                    for (byte, member) in members.iter_mut().enumerate() {
+                        *member |= class.contains(byte as u8);
+                    }
+                }
+            }
+        }
+        members
+    }
+
+    /// Get the last byte of [`Set::bytes`], if any.
+    fn last(&self, length: usize) -> Option<u8> {
+        let fill = length.saturating_sub(self.len());
+        self.elements
+            .iter()
+            .rev()
+            .find_map(|element| match *element {
+                Element::Byte(byte) | Element::Range(_, byte) | Element::Equivalence(byte) => {
+                    Some(byte)
+                }
+                Element::Repeat(byte, count) => (count.unwrap_or(fill) > 0).then_some(byte),
+                Element::Class(class) => (0..=u8::MAX).rev().find(|&byte| class.contains(byte)),
+            })
+    }
+}
+
+impl Element {
+    /// Get the number of bytes of the element, which is 0 for a repeat without a count.
+    fn len(&self) -> usize {
+        match *self {
+            Element::Byte(_) | Element::Equivalence(_) => 1,
+            Element::Range(first, last) => usize::from(last - first) + 1,
+            Element::Repeat(_, count) => count.unwrap_or(0),
+            Element::Class(class) => (0..=u8::MAX).filter(|&byte| class.contains(byte)).count(),
+        }
+    }
+}
+
+/// Expand the escapes of a set, remembering which bytes were escaped.
+fn unescape(arg: &[u8], warnings: &mut Vec<String>) -> Vec<(u8, bool)> {
+    let mut bytes = Vec::with_capacity(arg.len());
+    let mut index = 0;
+    let mut expanded = Vec::with_capacity(1);
+    while index < arg.len() {
+        match &arg[index..] {
+            [b'\\'] => {
+                warnings.push("an unescaped backslash at end of string is not portable".to_owned());
+                bytes.push((b'\\', false));
+                index += 1;
+            }
+            // Like GNU, three octal digits above 0o377 are two digits followed by a byte.
+            [b'\\', first @ b'4'..=b'7', second @ b'0'..=b'7', third @ b'0'..=b'7', ..] => {
+                let (first, second, third) =
+                    (char::from(*first), char::from(*second), char::from(*third));
+                warnings.push(format!(
+                    "the ambiguous octal escape \\{first}{second}{third} is being\n\tinterpreted as the \
+                     2-byte sequence \\0{first}{second}, {third}"
+                ));
+                let value =
+                    u8::from_str_radix(&format!("{first}{second}"), 8).expect("octal digits");
+                bytes.push((value, true));
+                index += 3;
+            }
+            [b'\\', ..] => {
+                expanded.clear();
+                let length = expand_escape(&arg[index..], EscapeFlavor::Tr, &mut expanded)
+                    .expect("tr escapes do not stop the output");
+                bytes.extend(expanded.iter().map(|&byte| (byte, true)));
+                index += length;
+            }
+            [byte, ..] => {
+                bytes.push((*byte, false));
+                index += 1;
+            }
+            [] => unreachable!("the index is within the set"),
+        }
+    }
+    bytes
+}
+
+/// Parse a class `[:name:]` or an equivalence class `[=c=]` after its opening bracket, getting it and its
+/// length with the brackets, or `None` if these bytes are not one.
+fn parse_class(bytes: &[(u8, bool)]) -> Result<Option<(Element, usize)>, String> {
+    // Like GNU, the delimiter after the bracket may be escaped, but not the closing one.
+    let Some(&(delimiter @ (b':' | b'='), _)) = bytes.first() else {
+        return Ok(None);
+    };
+    let Some(end) = bytes[1..]
+        .windows(2)
+        .position(|pair| pair == [(delimiter, false), (b']', false)])
+    else {
+        return Ok(None);
+    };
+
+    let operand: Vec<u8> = bytes[1..=end].iter().map(|&(byte, _)| byte).collect();
+    let element = match (delimiter, &operand[..]) {
+        (b':', []) => return Err("missing character class name '[::]'".to_owned()),
+        (_, []) => return Err("missing equivalence class character '[==]'".to_owned()),
+        (b':', name) => Class::from_name(name).map(Element::Class),
+        (_, &[byte]) => Some(Element::Equivalence(byte)),
+        _ => None,
+    };
+    if let Some(element) = element {
+        return Ok(Some((element, end + 4)));
+    }
+    // Something like `[:*3]:]` is a repeat of the delimiter instead.
+    let repeat = bytes[1] == (b'*', false)
+        && bytes[2..]
+            .iter()
+            .find(|&&(byte, escaped)| escaped || !byte.is_ascii_digit())
+            == Some(&(b']', false));
+    if repeat {
+        return Ok(None);
+    }
+    let operand: String = operand.iter().map(|&byte| printable(byte)).collect();
+    Err(if delimiter == b':' {
+        format!("invalid character class '{operand}'")
+    } else {
+        format!("{operand}: equivalence class operand must be a single character")
+    })
+}
+
+/// Parse a repeat `[c*n]` after its opening bracket, getting it and its length with the brackets, or `None`
+/// if these bytes are not a repeat.
+fn parse_repeat(bytes: &[(u8, bool)]) -> Result<Option<(Element, usize)>, String> {
+    let [(byte, _), (b'*', false), rest @ ..] = bytes else {
+        return Ok(None);
+    };
+    let Some(end) = rest
+        .iter()
+        .take_while(|(_, escaped)| !escaped)
+        .position(|&(byte, _)| byte == b']')
+    else {
+        return Ok(None);
+    };
+
+    let digits: Vec<u8> = rest[..end].iter().map(|&(byte, _)| byte).collect();
+    // A count starting with 0 is in octal.
+    let radix = if digits.first() == Some(&b'0') { 8 } else { 10 };
+    let count = match std::str::from_utf8(&digits)
+        .ok()
+        .filter(|digits| digits.bytes().all(|byte| byte.is_ascii_digit()))
+        .map(|digits| usize::from_str_radix(digits, radix))
+    {
+        _ if digits.is_empty() => None,
+        Some(Ok(0)) => None,
+        Some(Ok(count)) => Some(count),
+        _ => {
+            let digits: String = digits.iter().map(|&byte| printable(byte)).collect();
+            return Err(format!(
+                "invalid repeat count '{digits}' in [c*n] construct"
+            ));
+        }
+    };
+    Ok(Some((Element::Repeat(*byte, count), end + 4)))
+}
+
+/// Format a byte of a set for a message.
+fn printable(byte: u8) -> String {
+    match byte {
+        b'\\' => r"\\".to_owned(),
+        b'\x07' => r"\a".to_owned(),
+        b'\x08' => r"\b".to_owned(),
+        b'\x0c' => r"\f".to_owned(),
+        b'\n' => r"\n".to_owned(),
+        b'\r' => r"\r".to_owned(),
+        b'\t' => r"\t".to_owned(),
+        b'\x0b' => r"\v".to_owned(),
+        b' '..=b'~' => char::from(byte).to_string(),
+        _ => format!("\\{byte:03o}"),
+    }
+}
+
+/// The flags of tr which change how the sets are used.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Flags {
+    /// Use the bytes which are not in SET1, in ascending order, instead of SET1.
+    pub complement: bool,
+    /// Delete the bytes of SET1 instead of translating them.
+    pub delete: bool,
+    /// Squeeze the runs of the bytes of the last set in the output.
+    pub squeeze: bool,
+    /// Only translate as many bytes of SET1 as SET2 has.
+    pub truncate: bool,
+}
+
+/// The behaviour of tr.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrOptions {
+    /// The byte which replaces each byte.
+    pub table: [u8; 256],
+    /// The bytes of the input which are deleted.
+    pub delete: [bool; 256],
+    /// The bytes of which only the first of a run is written, after translating.
+    pub squeeze: [bool; 256],
+}
+
+impl Default for TrOptions {
+    /// Copy the input unchanged.
+    fn default() -> Self {
+        TrOptions {
+            table: std::array::from_fn(|byte| byte as u8),
+            delete: [false; 256],
+            squeeze: [false; 256],
+        }
+    }
+}
+
+impl TrOptions {
+    /// Get the options for SET1 and SET2, if any, with the GNU message of the first misuse.
+    ///
+    /// With two sets and without [`Flags::delete`], the bytes of `set1` are translated to the bytes at the
+    /// same position in `set2`. A shorter `set2` is extended by repeating its last byte, unless
+    /// [`Flags::truncate`] is set, in which case the extra bytes of `set1` are not translated. Otherwise,
+    /// the bytes of `set1` are deleted if [`Flags::delete`] is set, and the squeezed bytes are the ones of
+    /// the last set.
+    pub fn new(set1: &Set, set2: Option<&Set>, flags: Flags) -> Result<TrOptions, String> {
+        if set1.indefinite_repeats() > 0 {
+            return Err("the [c*] repeat construct may not appear in string1".to_owned());
+        }
+        let mut members = set1.members(0);
+        let complement: Vec<u8> = if flags.complement {
+            members = members.map(|member| !member);
+            (0..=u8::MAX)
+                .filter(|&byte| members[byte as usize])
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let length = if flags.complement {
+            complement.len()
+        } else {
+            set1.len()
+        };
+
+        let mut options = TrOptions::default();
+        if flags.delete {
+            options.delete = members;
+        }
+        match set2 {
+            Some(set2) if set2.indefinite_repeats() > 1 => {
+                return Err("only one [c*] repeat construct may appear in string2".to_owned());
+            }
+            Some(set2) if !flags.delete => {
+                let from: Box<dyn Iterator<Item = u8>> = if flags.complement {
+                    Box::new(complement.iter().copied())
+                } else {
+                    Box::new(set1.bytes(0))
+                };
+                options.table = translation(set1, set2, from, length, flags)?;
+            }
+            Some(set2) if set2.indefinite_repeats() > 0 => {
+                return Err(
+                    "the [c*] construct may appear in string2 only when translating".to_owned(),
+                );
+            }
+            _ => {}
+        }
+        if flags.squeeze {
+            options.squeeze = set2.map_or(members, |set2| set2.members(length));
+        }
+        Ok(options)
+    }
+}
+
+/// Get the table which translates `from`, the `length` bytes of SET1 after the complement if any, to
+/// `set2`, with the GNU message of the first misuse.
+fn translation(
+    set1: &Set,
+    set2: &Set,
+    from: impl Iterator<Item = u8>,
+    length: usize,
+    flags: Flags,
+) -> Result<[u8; 256], String> {
+    for element in set2.elements() {
+        match element {
+            Element::Equivalence(_) => {
+                return Err(
+                    "[=c=] expressions may not appear in string2 when translating".to_owned(),
+                );
+            }
+            Element::Class(class) if !class.is_case() => {
+                return Err(
+                    "when translating, the only character classes that may appear in\nstring2 \
+                            are 'upper' and 'lower'"
+                        .to_owned(),
+                );
+            }
+            _ => {}
+        }
+    }
+    if !flags.complement && !aligned(set1, set2, length) {
+        return Err("misaligned [:upper:] and/or [:lower:] construct".to_owned());
+    }
+
+    // The length of SET2 once a repeat without a count fills it, then once it is extended.
+    let filled = if set2.indefinite_repeats() > 0 {
+        set2.len().max(length)
+    } else {
+        set2.len()
+    };
+    let extended = if flags.truncate {
+        filled
+    } else {
+        filled.max(length)
+    };
+    let to = set2.bytes(length);
+    let to: Box<dyn Iterator<Item = u8>> = match set2.elements().last() {
+        _ if extended == filled => Box::new(to),
+        None => return Err("when not truncating set1, string2 must be non-empty".to_owned()),
+        Some(Element::Class(_)) => {
+            return Err(
+                "when translating with string1 longer than string2,\nthe latter string must not \
+                        end with a character class"
+                    .to_owned(),
+            );
+        }
+        Some(_) => {
+            let last = set2.last(length).expect("the last element has bytes");
+            Box::new(to.chain(std::iter::repeat(last)))
+        }
+    };
+    // Every byte of a complement with a class could be any byte, so like GNU, they must all be translated
+    // to the same one.
+    if flags.complement && set1.has_class() {
+        let mut bytes = set2.bytes(length);
+        let first = bytes.next();
+        if extended != length || first.is_none() || !bytes.all(|byte| Some(byte) == first) {
+            return Err(
+                "when translating with complemented character classes,\nstring2 must map all \
+                        characters in the domain to one"
+                    .to_owned(),
+            );
+        }
+    }
+
+    let mut table: [u8; 256] = std::array::from_fn(|byte| byte as u8);
+    // When a byte appears more than once in SET1, the last one wins, like in GNU.
+    for (from, to) in from.zip(to) {
+        table[from as usize] = to;
+    }
+    Ok(table)
+}
+
+/// Check that each `[:upper:]` or `[:lower:]` of `set2` starts at the same position as one of them in
+/// `set1`, as long as `set1`, of `length` bytes, is not over.
+fn aligned(set1: &Set, set2: &Set, length: usize) -> bool {
+    let starts = |set: &Set, fill: usize| {
+        let mut position = 0usize;
+        set.elements()
+            .iter()
+            .filter_map(move |element| {
+                let start = position;
+                let length = match element {
+                    Element::Repeat(_, None) => fill,
+                    _ => element.len(),
+                };
+                position = position.saturating_add(length);
+                matches!(element, Element::Class(class) if class.is_case()).then_some(start)
+            })
+            .collect::<Vec<_>>()
+    };
+    let cases = starts(set1, 0);
+    starts(set2, length.saturating_sub(set2.len()))
+        .into_iter()
+        .all(|start| start > length || cases.contains(&start))
+}
+
+/// Copy `reader` to `stdout`, deleting, translating, then squeezing each byte with the tables of the
+/// options.
+///
+/// A closed pipe stops without an error.
+pub fn tr(reader: &mut dyn Read, options: &TrOptions, stdout: &mut dyn Write) -> UResult<()> {
+    match translate(reader, options, stdout) {
+        Ok(()) => stdout.flush().or_else(UError::write),
+        Err(Failure::Read(error)) => Err(UError::io("read error", &error)),
+        Err(Failure::Write(error)) => UError::write(error),
+    }
+}
+
+/// Why translating the input stopped.
+enum Failure {
+    Read(io::Error),
+    Write(io::Error),
+}
+
+/// Translate the input one buffer at a time.
+fn translate(
+    reader: &mut dyn Read,
+    options: &TrOptions,
+    writer: &mut dyn Write,
+) -> Result<(), Failure> {
+    let filter = options.delete.contains(&true) || options.squeeze.contains(&true);
+    let mut buffer = vec![0; BUFFER_SIZE];
+    // The last byte written, which is kept across buffers for squeezing.
+    let mut last = None;
+    loop {
+        let length = match reader.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(length) => length,
+            Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+            Err(error) => return Err(Failure::Read(error)),
+        };
+        let mut kept = length;
+        if filter {
+            kept = 0;
+            for index in 0..length {
+                let byte = buffer[index];
+                if options.delete[byte as usize] {
+                    continue;
+                }
+                let byte = options.table[byte as usize];
+                if options.squeeze[byte as usize] && last == Some(byte) {
+                    continue;
+                }
+                last = Some(byte);
+                buffer[kept] = byte;
+                kept += 1;
+            }
+        } else {
+            for byte in &mut buffer[..length] {
+                *byte = options.table[*byte as usize];
+            }
+        }
+        writer.write_all(&buffer[..kept]).map_err(Failure::Write)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::run_util_stdin;
+
+    /// Run tr on some text, returning the output.
+    fn tr_text(args: &[&str], input: &str) -> String {
+        let command_line: Vec<&str> = std::iter::once("tr").chain(args.iter().copied()).collect();
+        let (code, stdout, stderr) = run_util_stdin(run, &command_line, input.as_bytes());
+        assert_eq!((code, stderr.as_str()), (0, ""), "{args:?}");
+        stdout
+    }
+
+    /// Translate bytes with two sets.
+    fn translate(set1: &[u8], set2: &[u8], input: &[u8]) -> Vec<u8> {
+        let mut warnings = Vec::new();
+        let set1 = Set::parse(set1, &mut warnings).unwrap();
+        let set2 = Set::parse(set2, &mut warnings).unwrap();
+        let options = TrOptions::new(&set1, Some(&set2), Flags::default()).unwrap();
+        let mut stdout = Vec::new();
+        tr(&mut &input[..], &options, &mut stdout).unwrap();
+        stdout
+    }
+
+    #[test]
+    fn test_ranges() {
+        assert_eq!(
+            tr_text(&["a-z", "A-Z"], "Hello, world!\n"),
+            "HELLO, WORLD!\n"
+        );
+        assert_eq!(tr_text(&["a-cx-z", "1-6"], "abcxyz\n"), "123456\n");
+        assert_eq!(tr_text(&["a-a", "b"], "banana\n"), "bbnbnb\n");
+        // A dash at either end is a byte.
+        assert_eq!(tr_text(&["lo", "a-"], "hello\n"), "heaa-\n");
+        assert_eq!(tr_text(&["--", "-a", "xy"], "a-b\n"), "yxb\n");
+        // Brackets are bytes outside of constructs.
+        assert_eq!(tr_text(&["[a-c]", "xyz"], "[b]\n"), "xzz\n");
+    }
+
+    #[test]
+    fn test_escapes() {
+        assert_eq!(tr_text(&[r"\n", " "], "a\nb\n"), "a b ");
+        assert_eq!(tr_text(&["ab", r"\t\\"], "abc\n"), "\t\\c\n");
+        assert_eq!(tr_text(&[r"\101-\103", "x"], "ABCD\n"), "xxxD\n");
+        assert_eq!(tr_text(&[r"\q", "Q"], "q\n"), "Q\n");
+        // An escaped dash is not a range.
+        assert_eq!(tr_text(&[r"a\-c", "xyz"], "abc-\n"), "xbzy\n");
+        // The end of a range can be escaped.
+        assert_eq!(tr_text(&[r"\n-\r", "x"], "a\n\r\x0b\n"), "axxxx");
+        assert_eq!(tr_text(&[r"[\n*2]", "ab"], "\n"), "b");
+    }
+
+    #[test]
+    fn test_warnings() {
+        assert_eq!(
+            run_util_stdin(run, &["tr", r"a\", "bc"], b"a\\\n"),
+            (
+                0,
+                "bc\n".to_owned(),
+                "tr: warning: an unescaped backslash at end of string is not portable\n".to_owned()
+            )
+        );
+        assert_eq!(
+            run_util_stdin(run, &["tr", "ab", r"\400"], b"abc"),
+            (
+                0,
+                " 0c".to_owned(),
+                "tr: warning: the ambiguous octal escape \\400 is being\n\tinterpreted as the 2-byte \
+                 sequence \\040, 0\n"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn test_short_set2() {
+        // The last byte of SET2 is repeated.
+        assert_eq!(tr_text(&["abcd", "xy"], "abcde\n"), "xyyye\n");
+        assert_eq!(tr_text(&["a-z", "_"], "tr 1\n"), "__ 1\n");
+        // Unless SET1 is truncated.
+        assert_eq!(tr_text(&["-t", "abcd", "xy"], "abcde\n"), "xycde\n");
+        assert_eq!(tr_text(&["--truncate-set1", "a", ""], "abc\n"), "abc\n");
+        // A longer SET2 is truncated.
+        assert_eq!(tr_text(&["ab", "xyz"], "abc\n"), "xyc\n");
+        assert_eq!(tr_text(&["", "a"], "abc\n"), "abc\n");
+        assert_eq!(tr_text(&["", ""], "abc\n"), "abc\n");
+    }
+
+    #[test]
+    fn test_duplicates() {
+        // The last translation of a byte wins.
+        assert_eq!(tr_text(&["aa", "xy"], "a\n"), "y\n");
+        assert_eq!(tr_text(&["a-ca", "1234"], "abc\n"), "423\n");
+    }
+
+    #[test]
+    fn test_repeats() {
+        assert_eq!(tr_text(&["lo", "[x*]"], "hello\n"), "hexxx\n");
+        assert_eq!(tr_text(&["helo", "a[x*2]b"], "hello\n"), "axxxb\n");
+        assert_eq!(tr_text(&["abc", "x[y*]z"], "abcd\n"), "xyzd\n");
+        assert_eq!(tr_text(&["a-e", "1[x*0]5"], "abcde\n"), "1xxx5\n");
+        // A count starting with 0 is in octal.
+        assert_eq!(
+            tr_text(&["a-j", "[x*010]y"], "abcdefghij\n"),
+            "xxxxxxxxyy\n"
+        );
+        // The repeat is empty when SET2 is already long enough.
+        assert_eq!(tr_text(&["ab", "wxyz[q*]"], "ab\n"), "wx\n");
+        // A repeat with a count is allowed in SET1.
+        assert_eq!(tr_text(&["[x*2]", "ab"], "x\n"), "b\n");
+        // Things which look like repeats are bytes.
+        assert_eq!(tr_text(&["[x*", "abc"], "[*x\n"), "acb\n");
+        assert_eq!(tr_text(&[r"[x\*]", "abcd"], "[*\n"), "ac\n");
+    }
+
+    #[test]
+    fn test_large_repeats() {
+        assert_eq!(
+            tr_text(&["abc", "[x*18446744073709551614]"], "abcd\n"),
+            "xxxd\n"
+        );
+    }
+
+    #[test]
+    fn test_binary() {
+        let input: Vec<u8> = (0..=u8::MAX).collect();
+        let expected: Vec<u8> = (0..=u8::MAX).map(|byte| byte ^ 0x80).collect();
+        assert_eq!(
+            translate(br"\0-\377", br"\200-\377\0-\177", &input),
+            expected
+        );
+        assert_eq!(
+            translate(b"\xff\xfe", b"\x00\x01", b"\xff\xfe\xfd\n"),
+            b"\x00\x01\xfd\n"
+        );
+        let long = vec![b'a'; 5 * BUFFER_SIZE + 3];
+        assert_eq!(translate(b"a", b"\x80", &long), vec![0x80; long.len()]);
+    }
+
+    #[test]
+    fn test_delete() {
+        assert_eq!(tr_text(&["-d", r"\r"], "a\r\nb\r\n"), "a\nb\n");
+        assert_eq!(tr_text(&["--delete", "a-c"], "abcdabc\n"), "d\n");
+        assert_eq!(tr_text(&["-d", ""], "abc\n"), "abc\n");
+        let mut stdout = Vec::new();
+        let args: Vec<OsString> = ["tr", "-d", r"\0-\177"]
+            .iter()
+            .map(OsString::from)
+            .collect();
+        let code = run(
+            &args,
+            &mut &b"a\x80b\xff\n"[..],
+            &mut stdout,
+            &mut Vec::new(),
+        );
+        assert_eq!((code, stdout), (0, b"\x80\xff".to_vec()));
+    }
+
+    #[test]
+    fn test_squeeze() {
+        assert_eq!(tr_text(&["-s", " "], "a   b  c d\n"), "a b c d\n");
+        assert_eq!(
+            tr_text(&["--squeeze-repeats", "a-z"], "aabbbcxx  \n"),
+            "abcx  \n"
+        );
+        // The translated bytes are squeezed, with SET2.
+        assert_eq!(tr_text(&["-s", "ab", "xy"], "aaabbbab\n"), "xyxy\n");
+        assert_eq!(tr_text(&["-s", "ab", "x"], "aaabbbab\n"), "x\n");
+        assert_eq!(tr_text(&["-s", "a", "[x*]"], "aax\n"), "x\n");
+        assert_eq!(tr_text(&["-st", "ab", "x"], "aaabbx\n"), "xbbx\n");
+        // The runs go on across buffers.
+        let long = "a".repeat(3 * BUFFER_SIZE + 1) + "\n";
+        assert_eq!(tr_text(&["-s", "a"], &long), "a\n");
+    }
+
+    #[test]
+    fn test_complement() {
+        assert_eq!(tr_text(&["-c", "a", "xy"], "abc\n"), "ayyy");
+        assert_eq!(tr_text(&["-C", "a-c\n", "-"], "abcd\n"), "abc-\n");
+        assert_eq!(tr_text(&["--complement", "a", "x"], "ba\n"), "xax");
+        let mut stdout = Vec::new();
+        let args: Vec<OsString> = ["tr", "-ct", "ab", "xyz"]
+            .iter()
+            .map(OsString::from)
+            .collect();
+        let code = run(
+            &args,
+            &mut &b"\0\x01\x02abc\xff"[..],
+            &mut stdout,
+            &mut Vec::new(),
+        );
+        assert_eq!((code, stdout), (0, b"xyzabc\xff".to_vec()));
+        assert_eq!(tr_text(&["-cd", "[:print:]"], "a\x01b\tc\x7f\n"), "abc");
+        assert_eq!(tr_text(&["-c", "[:alpha:]", "[x*]"], "ab1 c\n"), "abxxcx");
+    }
+
+    #[test]
+    fn test_delete_squeeze() {
+        assert_eq!(tr_text(&["-ds", "a", "b"], "abbabba\n"), "b\n");
+        assert_eq!(tr_text(&["-ds", "0-9", " "], "a1 2 3  b\n"), "a b\n");
+        assert_eq!(tr_text(&["-ds", ":", "[:digit:]"], "1:1:2\n"), "12\n");
+        assert_eq!(tr_text(&["-cds", "a", "a"], "bab aab\n"), "a");
+    }
+
+    #[test]
+    fn test_complement_squeeze() {
+        assert_eq!(
+            tr_text(&["-cs", "a-zA-Z", r"\n"], "Hello, big  world!\n"),
+            "Hello\nbig\nworld\n"
+        );
+        assert_eq!(tr_text(&["-cs", "a"], "abbb   cca\n"), "ab ca\n");
+    }
+
+    #[test]
+    fn test_classes() {
+        let all: String = (0..=127u8).map(char::from).collect();
+        for (class, members) in [
+            (
+                "alnum",
+                "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz",
+            ),
+            (
+                "alpha",
+                "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz",
+            ),
+            ("blank", "\t "),
+            ("digit", "0123456789"),
+            ("lower", "abcdefghijklmnopqrstuvwxyz"),
+            ("punct", "!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~"),
+            ("space", "\t\n\x0b\x0c\r "),
+            ("upper", "ABCDEFGHIJKLMNOPQRSTUVWXYZ"),
+            ("xdigit", "0123456789ABCDEFabcdef"),
+        ] {
+            assert_eq!(
+                tr_text(&["-cd", &format!("[:{class}:]")], &all),
+                members,
+                "{class}"
+            );
+        }
+        let control: String = (0..32u8).chain([127]).map(char::from).collect();
+        assert_eq!(tr_text(&["-cd", "[:cntrl:]"], &all), control);
+        assert_eq!(tr_text(&["-d", "[:print:]"], &all), control);
+        assert_eq!(tr_text(&["-d", "[:graph:][:cntrl:]"], &all), " ");
+        // A class is in ascending order, so upper and lower translate to each other.
+        assert_eq!(tr_text(&["[:lower:]", "[:upper:]"], "Hello\n"), "HELLO\n");
+        assert_eq!(
+            tr_text(&["[:upper:][:lower:]", "[:lower:][:upper:]"], "Hello\n"),
+            "hELLO\n"
+        );
+        assert_eq!(tr_text(&["[:digit:]", "x"], "a1b22\n"), "axbxx\n");
+        assert_eq!(tr_text(&["a", "xy[:upper:]"], "abc\n"), "xbc\n");
+        // Things which look like classes are bytes.
+        assert_eq!(tr_text(&["-d", "[:a"], "[:ab\n"), "b\n");
+        assert_eq!(tr_text(&["-d", r"\[:a:]"], "[:a]\n"), "\n");
+        assert_eq!(tr_text(&["-d", "[:*3]"], "a:*\n"), "a*\n");
+    }
+
+    #[test]
+    fn test_equivalence_classes() {
+        assert_eq!(tr_text(&["-d", "[=a=]"], "banana\n"), "bnn\n");
+        assert_eq!(tr_text(&["[=a=]b", "xy"], "abc\n"), "xyc\n");
+        assert_eq!(tr_text(&["-d", r"[=\n=]"], "a\nb\n"), "ab");
+    }
+
+    #[test]
+    fn test_parse() {
+        let parse = |set: &[u8]| Set::parse(set, &mut Vec::new()).unwrap();
+        assert_eq!(
+            parse(br"a-c\n[x*3]y-").elements(),
+            [
+                Element::Range(b'a', b'c'),
+                Element::Byte(b'\n'),
+                Element::Repeat(b'x', Some(3)),
+                Element::Byte(b'y'),
+                Element::Byte(b'-'),
+            ]
+        );
+        assert_eq!(parse(b"[a*]").elements(), [Element::Repeat(b'a', None)]);
+        assert_eq!(
+            parse(b"[:digit:][=x=]").elements(),
+            [Element::Class(Class::Digit), Element::Equivalence(b'x')]
+        );
+        assert_eq!(parse(b"[:upper:]").len(), 26);
+        assert_eq!(
+            parse(b"[:space:]")
+                .members(0)
+                .iter()
+                .filter(|&&member| member)
+                .count(),
+            6
+        );
+        assert_eq!(parse(b"x[a*]").len(), 1);
+        assert!(parse(b"").is_empty());
+    }
+
+    #[test]
+    fn test_invalid_sets() {
+        for (args, message) in [
+            (
+                &["z-a", "A"][..],
+                "range-endpoints of 'z-a' are in reverse collating sequence order",
+            ),
+            (
+                &[r"\200-\100", "x"],
+                r"range-endpoints of '\200-@' are in reverse collating sequence order",
+            ),
+            (
+                &["a", r"b-\n"],
+                r"range-endpoints of 'b-\n' are in reverse collating sequence order",
+            ),
+            (
+                &["a", "[x*9a]"],
+                "invalid repeat count '9a' in [c*n] construct",
+            ),
+            (
+                &["a", "[x*08]"],
+                "invalid repeat count '08' in [c*n] construct",
+            ),
+            (
+                &["a", "[x*+1]"],
+                "invalid repeat count '+1' in [c*n] construct",
+            ),
+            (
+                &["a", "[x*99999999999999999999999]"],
+                "invalid repeat count '99999999999999999999999' in [c*n] construct",
+            ),
+            (
+                &["[x*]", "a"],
+                "the [c*] repeat construct may not appear in string1",
+            ),
+            (
+                &["a", "[x*][y*]"],
+                "only one [c*] repeat construct may appear in string2",
+            ),
+            (
+                &["abc", ""],
+                "when not truncating set1, string2 must be non-empty",
+            ),
+            (&["-d", "[::]"], "missing character class name '[::]'"),
+            (
+                &["-d", "[==]"],
+                "missing equivalence class character '[==]'",
+            ),
+            (&["-d", "[:foo:]"], "invalid character class 'foo'"),
+            (
+                &[
+                    "-d", r"[:
+:]",
+                ],
+                r"invalid character class '\n'",
+            ),
+            (
+                &["-d", "[=ab=]"],
+                "ab: equivalence class operand must be a single character",
+            ),
+            (
+                &["-ds", "a", "[b*]"],
+                "the [c*] construct may appear in string2 only when translating",
+            ),
+        ] {
+            let command_line: Vec<&str> =
+                std::iter::once("tr").chain(args.iter().copied()).collect();
+            assert_eq!(
+                run_util_stdin(run, &command_line, b"abc\n"),
+                (1, String::new(), format!("tr: {message}\n")),
+                "{args:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_set2_classes() {
+        for (args, message) in [
+            (
+                &["a-z", "[:digit:]"][..],
+                "when translating, the only character classes that may appear in\nstring2 are 'upper' \
+                 and 'lower'",
+            ),
+            (
+                &["a", "[=b=]"],
+                "[=c=] expressions may not appear in string2 when translating",
+            ),
+            (
+                &["[:alpha:]", "[:upper:]"],
+                "misaligned [:upper:] and/or [:lower:] construct",
+            ),
+            (
+                &["ab", "x[:upper:]"],
+                "misaligned [:upper:] and/or [:lower:] construct",
+            ),
+            (
+                &["-s", ":", "[:lower:]"],
+                "misaligned [:upper:] and/or [:lower:] construct",
+            ),
+            (
+                &["[:lower:][:upper:]", "[:upper:]"],
+                "when translating with string1 longer than string2,\nthe latter string must not end \
+                 with a character class",
+            ),
+            (
+                &["-c", "[:alpha:]", "x[y*]"],
+                "when translating with complemented character classes,\nstring2 must map all \
+                 characters in the domain to one",
+            ),
+            (
+                &["-ct", "[:alpha:]", "x"],
+                "when translating with complemented character classes,\nstring2 must map all \
+                 characters in the domain to one",
+            ),
+        ] {
+            let command_line: Vec<&str> =
+                std::iter::once("tr").chain(args.iter().copied()).collect();
+            assert_eq!(
+                run_util_stdin(run, &command_line, b"abc\n"),
+                (1, String::new(), format!("tr: {message}\n")),
+                "{args:?}"
+            );
+        }
+        // Without translating, any class is allowed in SET2.
+        assert_eq!(tr_text(&["-ds", "a", "[:punct:]"], "a!!b\n"), "!b\n");
+    }
+
+    #[test]
+    fn test_operands() {
+        for (args, message) in [
+            (&[][..], "missing operand"),
+            (
+                &["abc"],
+                "missing operand after 'abc'\nTwo strings must be given when translating.",
+            ),
+            (&["a", "b", "c"], "extra operand 'c'"),
+            (&["-d"], "missing operand"),
+            (
+                &["-d", "a", "b"],
+                "extra operand 'b'\nOnly one string may be given when deleting without squeezing \
+                 repeats.",
+            ),
+            (&["-d", "a", "b", "c"], "extra operand 'b'"),
+            (&["-s", "a", "b", "c"], "extra operand 'c'"),
+            (
+                &["-ds", "a"],
+                "missing operand after 'a'\nTwo strings must be given when both deleting and \
+                 squeezing repeats.",
+            ),
+            (&["-ds", "a", "b", "c"], "extra operand 'c'"),
+        ] {
+            let command_line: Vec<&str> =
+                std::iter::once("tr").chain(args.iter().copied()).collect();
+            assert_eq!(
+                run_util_stdin(run, &command_line, b"abc\n"),
+                (
+                    1,
+                    String::new(),
+                    format!("tr: {message}\nTry 'tr --help' for more information.\n")
+                ),
+                "{args:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_error() {
+        struct Failing;
+
+        impl Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::from_raw_os_error(21))
+            }
+        }
+
+        let args: Vec<OsString> = vec!["tr".into(), "a".into(), "b".into()];
+        let mut stderr = Vec::new();
+        let code = run(&args, &mut Failing, &mut Vec::new(), &mut stderr);
+        assert_eq!(code, 1);
+        assert_eq!(
+            String::from_utf8(stderr).unwrap(),
+            format!(
+                "tr: read error: {}\n",
+                crate::common::error::io_error_message(&io::Error::from_raw_os_error(21))
+            )
+        );
+    }
+
+    #[test]
+    fn test_run_write_error() {
+        let args: Vec<OsString> = vec!["tr".into(), "a".into(), "b".into()];
+        let mut stdout = io::Cursor::new([0; 4]);
+        let mut stderr = Vec::new();
+        let code = run(
+            &args,
+            &mut &b"line 1\nline 2\n"[..],
+            &mut stdout,
+            &mut stderr,
+        );
+        assert_eq!(code, 1);
+        assert_eq!(stderr, b"tr: write error: failed to write whole buffer\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_broken_pipe() {
+        use assert_cmd::cargo::CommandCargoExt;
+        use std::os::unix::process::ExitStatusExt;
+        use std::process::Stdio;
+
+        let mut child = std::process::Command::cargo_bin("tr")
+            .unwrap()
+            .args(["a", "b"])
+            .stdin(std::fs::File::open("/dev/zero").unwrap())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        drop(child.stdout.take());
+        let output = child.wait_with_output().unwrap();
+        assert_eq!(output.status.signal(), Some(13));
+        assert!(output.stderr.is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_full_disk() {
+        use assert_cmd::prelude::*;
+        use std::fs::File;
+
+        let output = std::process::Command::cargo_bin("tr")
+            .unwrap()
+            .args(["a", "b"])
+            .stdin(File::open("/etc/passwd").unwrap())
+            .stdout(File::create("/dev/full").unwrap())
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(
+            String::from_utf8(output.stderr).unwrap(),
+            "tr: write error: No space left on device\n"
+        );
+    }
+}
@@ -0,0 +1,472 @@
+//! Shrink or extend files to a size, which can be relative to their current size or to that of a reference
+//! file.
+//!
+//! Extending a file leaves a hole, which reads as zeros without taking space, since the size is set with
+//! `ftruncate` rather than by writing. GNU's `-o`, which counts the size in I/O blocks, is not supported.
+//!
+//! ```
+//! use rust_coreutils::truncate::{truncate, Relation, SizeSpec, TruncateOptions};
+//!
+//! let directory = tempfile::tempdir().unwrap();
+//! let file = directory.path().join("file");
+//! std::fs::write(&file, "rounded up to 4K").unwrap();
+//! let size = SizeSpec::parse("%4K").unwrap();
+//! assert_eq!(size, SizeSpec { relation: Relation::RoundUp, size: 4096 });
+//! let options = TruncateOptions {
+//!     size: Some(size),
+//!     ..TruncateOptions::default()
+//! };
+//! truncate(&file, &options).unwrap();
+//! assert_eq!(std::fs::metadata(&file).unwrap().len(), 4096);
+//! ```
+
+use crate::common::error::{io_error_message, Reporter, UError, UResult, EXIT_FAILURE};
+use crate::common::size::{parse_signed_size, Sign, SizeError, SizeErrorKind};
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use clap::Parser;
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Read, Write};
+use std::path::Path;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "truncate",
+    author,
+    version,
+    about = "Shrink or extend each FILE to the given size, creating the FILEs which do not exist.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Do not create the files which do not exist.
+    #[arg(short = 'c', long, default_value_t = false)]
+    no_create: bool,
+
+    /// Use the size of RFILE, to which a relative SIZE is added.
+    #[arg(short, long, value_name = "RFILE")]
+    reference: Option<OsString>,
+
+    /// Set the size to SIZE, or add it with a leading +, or remove it with -, or set it to at most SIZE
+    /// with <, or at least SIZE with >, or round it down to a multiple of SIZE with /, or up with %.
+    #[arg(short, long, value_name = "SIZE", allow_hyphen_values = true)]
+    size: Option<String>,
+
+    /// The files to change.
+    #[arg(value_name = "FILE")]
+    files: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "truncate";
+}
+
+/// The description of truncate for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run truncate with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run truncate with the given command line, including the program name, on the given streams, and
+/// return the exit code.
+pub fn run(
+    args: &[OsString],
+    _stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(args.iter().cloned(), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let mut reporter = Reporter::new("truncate", stderr);
+    let result = options(&args).map(|options| {
+        for file in &args.files {
+            if let Err(error) = truncate(Path::new(file), &options) {
+                reporter.error(format_args!("{error}"));
+            }
+        }
+    });
+    reporter.finish(result)
+}
+
+fn options(args: &Args) -> UResult<TruncateOptions> {
+    let size = args.size.as_deref().map(SizeSpec::parse).transpose()?;
+    match (&size, &args.reference) {
+        (None, None) => {
+            return Err(UError::usage(
+                EXIT_FAILURE,
+                "you must specify either '--size' or '--reference'",
+            ))
+        }
+        (Some(size), Some(_)) if size.relation == Relation::Absolute => {
+            return Err(UError::usage(
+                EXIT_FAILURE,
+                "you must specify a relative '--size' with '--reference'",
+            ))
+        }
+        _ => {}
+    }
+    if args.files.is_empty() {
+        return Err(UError::usage(EXIT_FAILURE, "missing file operand"));
+    }
+    let reference_size = match &args.reference {
+        Some(reference) => Some(
+            fs::metadata(reference)
+                .map_err(|error| {
+                    let reference = Path::new(reference).display();
+                    UError::io(format!("cannot stat '{reference}'"), &error)
+                })?
+                .len(),
+        ),
+        None => None,
+    };
+    Ok(TruncateOptions {
+        no_create: args.no_create,
+        size,
+        reference_size,
+    })
+}
+
+/// How the size of `-s` applies to the current size of a file, or to the size of the reference file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Relation {
+    /// The new size is the size.
+    Absolute,
+    /// The size, which can be negative, is added (`+` or `-`).
+    Adjust,
+    /// The new size is at most the size (`<`).
+    AtMost,
+    /// The new size is at least the size (`>`).
+    AtLeast,
+    /// The size is rounded down to a multiple of the size (`/`).
+    RoundDown,
+    /// The size is rounded up to a multiple of the size (`%`).
+    RoundUp,
+}
+
+/// The argument of `-s`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SizeSpec {
+    /// How the size applies.
+    pub relation: Relation,
+    /// The size, which is only negative with [`Relation::Adjust`].
+    pub size: i64,
+}
+
+impl SizeSpec {
+    /// Parse a size with an optional relation first, like `+1M`, `<4K`, or `%4096`.
+    pub fn parse(spec: &str) -> UResult<SizeSpec> {
+        let spec = spec.trim_start_matches(|c: char| c.is_ascii_whitespace());
+        let relation = match spec.as_bytes().first() {
+            Some(b'<') => Relation::AtMost,
+            Some(b'>') => Relation::AtLeast,
+            Some(b'/') => Relation::RoundDown,
+            Some(b'%') => Relation::RoundUp,
+            _ => Relation::Absolute,
+        };
+        let number = match relation {
+            Relation::Absolute => spec,
+            _ => spec[1..].trim_start_matches(|c: char| c.is_ascii_whitespace()),
+        };
+        if relation != Relation::Absolute && number.starts_with(['+', '-']) {
+            return Err(UError::new(
+                EXIT_FAILURE,
+                "multiple relative modifiers specified",
+            ));
+        }
+
+        let invalid =
+            |error: SizeError| UError::new(EXIT_FAILURE, format!("Invalid number: {error}"));
+        let (sign, size) = parse_signed_size(number).map_err(invalid)?;
+        let size = i64::try_from(size).map_err(|_| {
+            invalid(SizeError {
+                kind: SizeErrorKind::TooLarge,
+                input: number.to_owned(),
+            })
+        })?;
+        let (relation, size) = match (relation, sign) {
+            (Relation::Absolute, Some(Sign::Plus)) => (Relation::Adjust, size),
+            (Relation::Absolute, Some(Sign::Minus)) => (Relation::Adjust, -size),
+            (Relation::RoundDown | Relation::RoundUp, _) if size == 0 => {
+                return Err(UError::new(EXIT_FAILURE, "division by zero"))
+            }
+            (relation, _) => (relation, size),
+        };
+        Ok(SizeSpec { relation, size })
+    }
+
+    /// Get the new size of a file whose size is `current`, or `None` if it does not fit in an `i64`, which
+    /// is `off_t`. A size below zero is zero.
+    pub fn apply(&self, current: u64) -> Option<u64> {
+        let current = i64::try_from(current).ok()?;
+        let size = self.size;
+        let new = match self.relation {
+            Relation::Absolute => size,
+            Relation::Adjust => current.checked_add(size)?,
+            Relation::AtMost => current.min(size),
+            Relation::AtLeast => current.max(size),
+            Relation::RoundDown => current - current % size,
+            Relation::RoundUp => current.checked_add((size - current % size) % size)?,
+        };
+        Some(new.max(0) as u64)
+    }
+}
+
+/// The options of truncate.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TruncateOptions {
+    /// Do not create the files which do not exist (`-c`).
+    pub no_create: bool,
+    /// The new size (`-s`), which must be relative with `reference_size`.
+    pub size: Option<SizeSpec>,
+    /// The size of the reference file (`-r`), which is the new size without `size`, and which `size`
+    /// applies to otherwise.
+    pub reference_size: Option<u64>,
+}
+
+/// Set the size of a file, creating it unless `options.no_create` is set. A file which does not exist
+/// with `options.no_create` is not an error.
+pub fn truncate(path: &Path, options: &TruncateOptions) -> UResult<()> {
+    let file = match OpenOptions::new()
+        .write(true)
+        .create(!options.no_create)
+        .truncate(false)
+        .open(path)
+    {
+        Ok(file) => file,
+        Err(error) if options.no_create && error.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(error) => {
+            return Err(UError::io(
+                format!("cannot open '{}' for writing", path.display()),
+                &error,
+            ))
+        }
+    };
+
+    let size = match (options.size, options.reference_size) {
+        (None, reference) => reference.unwrap_or_default(),
+        (Some(size), reference) => {
+            let current = match reference {
+                Some(reference) => reference,
+                None => file
+                    .metadata()
+                    .map_err(|error| {
+                        UError::io(format!("cannot fstat '{}'", path.display()), &error)
+                    })?
+                    .len(),
+            };
+            let Some(new) = size.apply(current) else {
+                let operation = match size.relation {
+                    Relation::RoundUp => "rounding up",
+                    _ => "extending",
+                };
+                return Err(UError::new(
+                    EXIT_FAILURE,
+                    format!("overflow {operation} size of file '{}'", path.display()),
+                ));
+            };
+            new
+        }
+    };
+    file.set_len(size).map_err(|error| {
+        UError::new(
+            EXIT_FAILURE,
+            format!(
+                "failed to truncate '{}' at {size} bytes: {}",
+                path.display(),
+                io_error_message(&error)
+            ),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::run_util;
+
+    fn spec(relation: Relation, size: i64) -> UResult<SizeSpec> {
+        Ok(SizeSpec { relation, size })
+    }
+
+    fn error(message: &str) -> UResult<SizeSpec> {
+        Err(UError::new(EXIT_FAILURE, message))
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(SizeSpec::parse("5"), spec(Relation::Absolute, 5));
+        assert_eq!(SizeSpec::parse("+1M"), spec(Relation::Adjust, 1 << 20));
+        assert_eq!(SizeSpec::parse(" -2K"), spec(Relation::Adjust, -2048));
+        assert_eq!(SizeSpec::parse("<1KB"), spec(Relation::AtMost, 1000));
+        assert_eq!(SizeSpec::parse("> 3"), spec(Relation::AtLeast, 3));
+        assert_eq!(SizeSpec::parse("/512"), spec(Relation::RoundDown, 512));
+        assert_eq!(SizeSpec::parse("%4096"), spec(Relation::RoundUp, 4096));
+
+        assert_eq!(SizeSpec::parse("1X"), error("Invalid number: '1X'"));
+        assert_eq!(SizeSpec::parse(""), error("Invalid number: ''"));
+        assert_eq!(SizeSpec::parse("+ 5"), error("Invalid number: '+ 5'"));
+        assert_eq!(
+            SizeSpec::parse("<99999999999999999999"),
+            error("Invalid number: '99999999999999999999': Value too large for defined data type")
+        );
+        assert_eq!(
+            SizeSpec::parse("8E"),
+            error("Invalid number: '8E': Value too large for defined data type")
+        );
+        for spec in ["<-5", "%+4K", ">-X"] {
+            assert_eq!(
+                SizeSpec::parse(spec),
+                error("multiple relative modifiers specified")
+            );
+        }
+        assert_eq!(SizeSpec::parse("%0"), error("division by zero"));
+        assert_eq!(SizeSpec::parse("/0"), error("division by zero"));
+    }
+
+    #[test]
+    fn test_apply() {
+        let apply = |spec: &str, current| SizeSpec::parse(spec).unwrap().apply(current);
+        assert_eq!(apply("5", 100), Some(5));
+        assert_eq!(apply("+5", 100), Some(105));
+        assert_eq!(apply("-500", 100), Some(0));
+        assert_eq!(apply("<50", 100), Some(50));
+        assert_eq!(apply("<500", 100), Some(100));
+        assert_eq!(apply(">50", 100), Some(100));
+        assert_eq!(apply(">500", 100), Some(500));
+        assert_eq!(apply("/64", 100), Some(64));
+        assert_eq!(apply("%64", 100), Some(128));
+        assert_eq!(apply("%64", 128), Some(128));
+        assert_eq!(apply("+1", i64::MAX as u64), None);
+        assert_eq!(apply("%2", i64::MAX as u64), None);
+        assert_eq!(apply("-1", u64::MAX), None);
+    }
+
+    #[test]
+    fn test_truncate() {
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("file");
+        fs::write(&file, "hello world\n").unwrap();
+        let path = file.to_str().unwrap();
+        let size = || fs::metadata(&file).unwrap().len();
+        let success = (0, String::new(), String::new());
+
+        assert_eq!(run_util(run, &["truncate", "-s", "5", path]), success);
+        assert_eq!(fs::read(&file).unwrap(), b"hello");
+        assert_eq!(run_util(run, &["truncate", "-s", "8", path]), success);
+        assert_eq!(fs::read(&file).unwrap(), b"hello\0\0\0");
+        assert_eq!(run_util(run, &["truncate", "-s", "+1M", path]), success);
+        assert_eq!(size(), 8 + (1 << 20));
+        assert_eq!(run_util(run, &["truncate", "-s", "%4096", path]), success);
+        assert_eq!(size(), 4096 * 257);
+        assert_eq!(run_util(run, &["truncate", "-s", "-4K", path]), success);
+        assert_eq!(size(), 4096 * 256);
+        // The hole takes no space, on the file systems which support them.
+        #[cfg(unix)]
+        assert!(std::os::unix::fs::MetadataExt::blocks(&fs::metadata(&file).unwrap()) < 1024);
+
+        let copy = directory.path().join("copy");
+        let copy_path = copy.to_str().unwrap();
+        assert_eq!(run_util(run, &["truncate", "-r", path, copy_path]), success);
+        assert_eq!(fs::metadata(&copy).unwrap().len(), size());
+        assert_eq!(
+            run_util(run, &["truncate", "-r", path, "-s", "-1M", copy_path]),
+            success
+        );
+        assert_eq!(fs::metadata(&copy).unwrap().len(), 0);
+
+        let missing = directory.path().join("missing");
+        let missing_path = missing.to_str().unwrap();
+        assert_eq!(
+            run_util(run, &["truncate", "-c", "-s", "1", missing_path]),
+            success
+        );
+        assert!(!missing.exists());
+        assert_eq!(
+            run_util(run, &["truncate", "-s", "1", missing_path]),
+            success
+        );
+        assert_eq!(fs::metadata(&missing).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_errors() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path().to_str().unwrap();
+        let failure = |message: &str| {
+            (
+                EXIT_FAILURE,
+                String::new(),
+                format!("truncate: {message}\n"),
+            )
+        };
+        let usage = |message: &str| {
+            failure(&format!(
+                "{message}\nTry 'truncate --help' for more information."
+            ))
+        };
+
+        assert_eq!(
+            run_util(run, &["truncate", "file"]),
+            usage("you must specify either '--size' or '--reference'")
+        );
+        assert_eq!(
+            run_util(run, &["truncate", "-s", "1"]),
+            usage("missing file operand")
+        );
+        assert_eq!(
+            run_util(run, &["truncate", "-r", root, "-s", "5", "file"]),
+            usage("you must specify a relative '--size' with '--reference'")
+        );
+        let missing = directory.path().join("missing");
+        let missing = missing.to_str().unwrap();
+        assert_eq!(
+            run_util(run, &["truncate", "-r", missing, "file"]),
+            failure(&format!(
+                "cannot stat '{missing}': No such file or directory"
+            ))
+        );
+
+        let nested = directory.path().join("missing").join("file");
+        let nested = nested.to_str().unwrap();
+        let (code, stdout, stderr) = run_util(run, &["truncate", "-s", "0", nested, root]);
+        assert_eq!((code, stdout.as_str()), (EXIT_FAILURE, ""));
+        assert!(
+            stderr.starts_with(&format!(
+                "truncate: cannot open '{nested}' for writing: No such file or directory\n\
+                truncate: cannot open '{root}' for writing: "
+            )),
+            "{stderr}"
+        );
+
+        let file = directory.path().join("file");
+        fs::write(&file, "1").unwrap();
+        let path = file.to_str().unwrap();
+        assert_eq!(
+            run_util(
+                run,
+                &[
+                    "truncate",
+                    "-r",
+                    path,
+                    "-s",
+                    &format!("+{}", i64::MAX),
+                    path
+                ]
+            ),
+            failure(&format!("overflow extending size of file '{path}'"))
+        );
+    }
+}
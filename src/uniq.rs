@@ -0,0 +1,613 @@
+//! Collapse the adjacent duplicate lines of a file.
+//!
+//! The lines are compared as they are read with [`uniq`], keeping only the previous one, so that inputs of
+//! any size can be handled:
+//!
+//! ```
+//! use rust_coreutils::uniq::{uniq, UniqOptions};
+//!
+//! let options = UniqOptions {
+//!     count: true,
+//!     ..UniqOptions::default()
+//! };
+//! let mut stdout = Vec::new();
+//! uniq(&mut &b"a\na\nb\na\n"[..], "-", &options, &mut stdout).unwrap();
+//! assert_eq!(stdout, b"      2 a\n      1 b\n      1 a\n");
+//! ```
+
+use crate::common::compare::is_blank;
+use crate::common::error::{Reporter, UError, UResult, EXIT_FAILURE};
+use crate::common::input::{InputIter, BUFFER_SIZE};
+use crate::common::records::{RecordReader, ZeroTerminatedArgs};
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use clap::Parser;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "uniq",
+    author,
+    version,
+    about = "Write the lines of INPUT to OUTPUT, with only one copy of the adjacent lines which are the same.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Prefix the lines with the number of times they occur.
+    #[arg(short, long, default_value_t = false)]
+    count: bool,
+
+    /// Only print one copy of the lines which are repeated.
+    #[arg(short = 'd', long, default_value_t = false)]
+    repeated: bool,
+
+    /// Only print the lines which are not repeated.
+    #[arg(short, long, default_value_t = false)]
+    unique: bool,
+
+    /// Skip the first N fields of the lines when comparing them. Fields are blanks followed by non-blanks.
+    #[arg(short = 'f', long, value_name = "N", allow_hyphen_values = true)]
+    skip_fields: Option<String>,
+
+    /// Skip the first N characters of the lines, after the fields, when comparing them.
+    #[arg(short = 's', long, value_name = "N", allow_hyphen_values = true)]
+    skip_chars: Option<String>,
+
+    /// Compare at most N characters of the lines, after the skipped ones.
+    #[arg(short = 'w', long, value_name = "N", allow_hyphen_values = true)]
+    check_chars: Option<String>,
+
+    /// Compare lower case ASCII letters as upper case.
+    #[arg(short, long, default_value_t = false)]
+    ignore_case: bool,
+
+    #[command(flatten)]
+    records: ZeroTerminatedArgs,
+
+    /// The file to read. With no INPUT, or when INPUT is -, standard input is read.
+    #[arg(value_name = "INPUT")]
+    input: Option<OsString>,
+
+    /// The file to write. With no OUTPUT, or when OUTPUT is -, standard output is written.
+    #[arg(value_name = "OUTPUT")]
+    output: Option<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "uniq";
+}
+
+/// The description of uniq for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run uniq with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run, args)
+}
+
+/// Run uniq with the given command line, including the program name, on the given streams, and return the
+/// exit code.
+pub fn run(
+    args: &[OsString],
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(args.iter().cloned(), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let options = match options(&args) {
+        Ok(options) => options,
+        Err(error) => return Reporter::new("uniq", stderr).finish(Err(error)),
+    };
+    let mut inputs = InputIter::with_stdin(
+        args.input.into_iter().collect(),
+        Box::new(BufReader::with_capacity(BUFFER_SIZE, stdin)),
+    );
+    let result = match inputs.next_buffered().expect("there is one input") {
+        // Like GNU, the output is only created once the input is opened.
+        Ok(mut input) => {
+            let name = input.display_name().into_owned();
+            match args.output.filter(|output| output != "-") {
+                Some(output) => File::create(&output)
+                    .map_err(|error| UError::io(Path::new(&output).display(), &error))
+                    .and_then(|mut file| uniq(&mut input.reader, &name, &options, &mut file)),
+                None => uniq(&mut input.reader, &name, &options, stdout),
+            }
+        }
+        Err(error) => Err(UError::io(error.display_name(), &error.error)),
+    };
+    Reporter::new("uniq", stderr).finish(result)
+}
+
+/// Get the options of the command line.
+fn options(args: &Args) -> UResult<UniqOptions> {
+    let count = |arg: &Option<String>, what: &str| match arg {
+        Some(arg) => parse_count(arg)
+            .map(Some)
+            .ok_or_else(|| UError::new(EXIT_FAILURE, format!("{arg}: invalid number of {what}"))),
+        None => Ok(None),
+    };
+    Ok(UniqOptions {
+        count: args.count,
+        repeated: args.repeated,
+        unique: args.unique,
+        skip_fields: count(&args.skip_fields, "fields to skip")?.unwrap_or(0),
+        skip_chars: count(&args.skip_chars, "bytes to skip")?.unwrap_or(0),
+        check_chars: count(&args.check_chars, "bytes to compare")?,
+        ignore_case: args.ignore_case,
+        delimiter: args.records.delimiter(),
+    })
+}
+
+/// Parse the argument of `-f`, `-s`, or `-w`, which saturates when it is too large.
+fn parse_count(arg: &str) -> Option<usize> {
+    let digits = arg.strip_prefix('+').unwrap_or(arg);
+    if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    Some(digits.parse().unwrap_or(usize::MAX))
+}
+
+/// The behaviour of uniq.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UniqOptions {
+    /// Prefix the lines with the number of times they occur.
+    pub count: bool,
+    /// Only print the lines which are repeated.
+    pub repeated: bool,
+    /// Only print the lines which are not repeated. With `repeated`, nothing is printed.
+    pub unique: bool,
+    /// The number of fields to skip before comparing lines, where fields are blanks followed by non-blanks.
+    pub skip_fields: usize,
+    /// The number of characters to skip after the fields. Characters are bytes, like in the C locale.
+    pub skip_chars: usize,
+    /// The number of characters to compare after the skipped ones, or `None` for all of them.
+    pub check_chars: Option<usize>,
+    /// Compare lower case ASCII letters as upper case.
+    pub ignore_case: bool,
+    /// The byte which ends lines.
+    pub delimiter: u8,
+}
+
+impl Default for UniqOptions {
+    /// The default behaviour of uniq: one copy of all the lines, without counts.
+    fn default() -> Self {
+        UniqOptions {
+            count: false,
+            repeated: false,
+            unique: false,
+            skip_fields: 0,
+            skip_chars: 0,
+            check_chars: None,
+            ignore_case: false,
+            delimiter: b'\n',
+        }
+    }
+}
+
+impl UniqOptions {
+    /// Get the part of a line which is compared, which is empty when more is skipped than the line has.
+    pub fn key<'a>(&self, line: &'a [u8]) -> &'a [u8] {
+        let mut start = 0;
+        for _ in 0..self.skip_fields {
+            if start == line.len() {
+                break;
+            }
+            start += line[start..]
+                .iter()
+                .take_while(|&&byte| is_blank(byte))
+                .count();
+            start += line[start..]
+                .iter()
+                .take_while(|&&byte| !is_blank(byte))
+                .count();
+        }
+        let key = &line[start.saturating_add(self.skip_chars).min(line.len())..];
+        match self.check_chars {
+            Some(length) => &key[..length.min(key.len())],
+            None => key,
+        }
+    }
+
+    /// Check whether two lines are the same, comparing only their keys.
+    pub fn same(&self, a: &[u8], b: &[u8]) -> bool {
+        let (a, b) = (self.key(a), self.key(b));
+        if self.ignore_case {
+            a.eq_ignore_ascii_case(b)
+        } else {
+            a == b
+        }
+    }
+}
+
+/// Write one copy of each group of adjacent lines which are the same, keeping only the previous line in
+/// memory. The first line of a group is written as it is, whatever parts of the lines are compared. A last
+/// line without a delimiter is the same as one with it, and is written with one.
+///
+/// `name` is the name of the input in messages. A closed pipe stops without an error.
+pub fn uniq(
+    reader: &mut dyn BufRead,
+    name: &str,
+    options: &UniqOptions,
+    stdout: &mut dyn Write,
+) -> UResult<()> {
+    let mut writer = BufWriter::with_capacity(BUFFER_SIZE, stdout);
+    match collapse(reader, options, &mut writer) {
+        Ok(()) => writer.flush().or_else(UError::write),
+        Err(Failure::Read) => Err(UError::new(EXIT_FAILURE, format!("error reading '{name}'"))),
+        Err(Failure::Write(error)) => UError::write(error),
+    }
+}
+
+/// Why collapsing the lines stopped.
+enum Failure {
+    /// GNU does not tell why.
+    Read,
+    Write(io::Error),
+}
+
+/// Write the groups of lines as they end, when a different line is read.
+fn collapse(
+    reader: &mut dyn BufRead,
+    options: &UniqOptions,
+    writer: &mut dyn Write,
+) -> Result<(), Failure> {
+    let mut records = RecordReader::new(reader, options.delimiter);
+    let mut previous = Vec::new();
+    let mut count = 0;
+    while let Some(record) = records.next_record() {
+        let record = record.map_err(|_| Failure::Read)?;
+        if count > 0 && options.same(&previous, record.content) {
+            count += 1;
+            continue;
+        }
+        if count > 0 {
+            write_group(writer, &previous, count, options).map_err(Failure::Write)?;
+        }
+        previous.clear();
+        previous.extend_from_slice(record.content);
+        count = 1;
+    }
+    if count > 0 {
+        write_group(writer, &previous, count, options).map_err(Failure::Write)?;
+    }
+    Ok(())
+}
+
+/// Write the line of a group of `count` lines, unless the options leave it out.
+fn write_group(
+    writer: &mut dyn Write,
+    line: &[u8],
+    count: u64,
+    options: &UniqOptions,
+) -> io::Result<()> {
+    let repeated = count > 1;
+    if (repeated && options.unique) || (!repeated && options.repeated) {
+        return Ok(());
+    }
+    if options.count {
+        write!(writer, "{count:>7} ")?;
+    }
+    writer.write_all(line)?;
+    writer.write_all(&[options.delimiter])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::{run_util_stdin, Fixture};
+    use std::fs;
+
+    /// Run uniq on some text, returning the output.
+    fn uniq_text(args: &[&str], input: &str) -> String {
+        let command_line: Vec<&str> = std::iter::once("uniq")
+            .chain(args.iter().copied())
+            .collect();
+        let (code, stdout, stderr) = run_util_stdin(run, &command_line, input.as_bytes());
+        assert_eq!((code, stderr.as_str()), (0, ""), "{args:?}");
+        stdout
+    }
+
+    #[test]
+    fn test_adjacent() {
+        assert_eq!(uniq_text(&[], "a\na\nb\nb\nb\nc\n"), "a\nb\nc\n");
+        // Only adjacent lines are duplicates.
+        assert_eq!(uniq_text(&[], "a\nb\na\n"), "a\nb\na\n");
+        assert_eq!(uniq_text(&["-"], "\n\n"), "\n");
+        assert_eq!(uniq_text(&[], ""), "");
+    }
+
+    #[test]
+    fn test_last_line_without_newline() {
+        assert_eq!(uniq_text(&[], "a\na"), "a\n");
+        assert_eq!(uniq_text(&[], "a\nb"), "a\nb\n");
+        assert_eq!(uniq_text(&["-c"], "a\na"), "      2 a\n");
+    }
+
+    #[test]
+    fn test_count() {
+        // Runs at the start and at the end of the input.
+        assert_eq!(
+            uniq_text(&["-c"], "a\na\na\nb\nc\nc\n"),
+            "      3 a\n      1 b\n      2 c\n"
+        );
+        assert_eq!(
+            uniq_text(&["--count"], "x\nx\ny\nx\n"),
+            "      2 x\n      1 y\n      1 x\n"
+        );
+        let input = "a\n".repeat(12_345);
+        assert_eq!(uniq_text(&["-c"], &input), "  12345 a\n");
+    }
+
+    #[test]
+    fn test_repeated() {
+        assert_eq!(uniq_text(&["-d"], "a\na\na\nb\nc\nc\n"), "a\nc\n");
+        assert_eq!(uniq_text(&["--repeated", "-c"], "a\nb\nb\n"), "      2 b\n");
+        assert_eq!(uniq_text(&["-d"], "a\nb\na\n"), "");
+    }
+
+    #[test]
+    fn test_unique() {
+        assert_eq!(uniq_text(&["-u"], "a\na\na\nb\nc\nc\n"), "b\n");
+        assert_eq!(uniq_text(&["--unique", "-c"], "a\nb\nb\n"), "      1 a\n");
+        assert_eq!(uniq_text(&["-u"], "a\nb\na\n"), "a\nb\na\n");
+        // Both together print nothing.
+        assert_eq!(uniq_text(&["-du"], "a\na\nb\n"), "");
+        assert_eq!(uniq_text(&["-d", "-u", "-c"], "a\nb\n"), "");
+    }
+
+    #[test]
+    fn test_skip_fields() {
+        assert_eq!(
+            uniq_text(&["-f1"], "1 apple\n2 apple\n3 pear\n"),
+            "1 apple\n3 pear\n"
+        );
+        assert_eq!(
+            uniq_text(&["--skip-fields", "1", "-c"], "1 apple\n2 apple\n"),
+            "      2 1 apple\n"
+        );
+        // The blanks before a field belong to it.
+        assert_eq!(uniq_text(&["-f1"], "a\tx\na  x\nb  x\n"), "a\tx\na  x\n");
+        assert_eq!(uniq_text(&["-f", "2"], " a b c\nx\ty c\n"), " a b c\n");
+        // Lines with fewer fields compare as empty.
+        assert_eq!(
+            uniq_text(&["-f3"], "a b\nc\n\nd e f\nd e f g\n"),
+            "a b\nd e f g\n"
+        );
+        assert_eq!(uniq_text(&["-f", "99999999999999999999"], "a\nb\n"), "a\n");
+    }
+
+    #[test]
+    fn test_skip_chars() {
+        assert_eq!(uniq_text(&["-s2"], "xxab\nyyab\nzzac\n"), "xxab\nzzac\n");
+        // The characters are skipped after the fields, blanks included.
+        assert_eq!(
+            uniq_text(&["-f1", "-s", "2"], "1 aab\n2 bab\n3 cac\n"),
+            "1 aab\n3 cac\n"
+        );
+        assert_eq!(
+            uniq_text(&["--skip-chars=1", "-f1"], "1  a\n2 xa\n3 xa\n"),
+            "1  a\n2 xa\n"
+        );
+        assert_eq!(uniq_text(&["-s5"], "abc\nxyz\n"), "abc\n");
+    }
+
+    #[test]
+    fn test_check_chars() {
+        // The lines differ after the compared characters.
+        assert_eq!(uniq_text(&["-w3"], "abc1\nabc2\nabd1\n"), "abc1\nabd1\n");
+        assert_eq!(
+            uniq_text(&["-s1", "--check-chars", "2"], "xabc\nyabd\nzacd\n"),
+            "xabc\nzacd\n"
+        );
+        assert_eq!(uniq_text(&["-w0"], "a\nb\n"), "a\n");
+        assert_eq!(uniq_text(&["-w10"], "a\nab\n"), "a\nab\n");
+    }
+
+    #[test]
+    fn test_ignore_case() {
+        assert_eq!(
+            uniq_text(&["-i", "-c"], "Apple\napple\nAPPLE\npear\n"),
+            "      3 Apple\n      1 pear\n"
+        );
+        assert_eq!(uniq_text(&["--ignore-case"], "é\nÉ\n"), "é\nÉ\n");
+        assert_eq!(uniq_text(&["-i", "-f1"], "1 A\n2 a\n"), "1 A\n");
+        assert_eq!(uniq_text(&[], "a\nA\n"), "a\nA\n");
+    }
+
+    #[test]
+    fn test_invalid_counts() {
+        for (args, message) in [
+            (["-f", "a"], "a: invalid number of fields to skip"),
+            (["-f", "-1"], "-1: invalid number of fields to skip"),
+            (["-f", ""], ": invalid number of fields to skip"),
+            (["-s", "x"], "x: invalid number of bytes to skip"),
+            (["-w", "1x"], "1x: invalid number of bytes to compare"),
+        ] {
+            let command_line: Vec<&str> = std::iter::once("uniq").chain(args).collect();
+            assert_eq!(
+                run_util_stdin(run, &command_line, b"a\n"),
+                (1, String::new(), format!("uniq: {message}\n")),
+                "{args:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_key() {
+        let options = UniqOptions {
+            skip_fields: 1,
+            skip_chars: 1,
+            check_chars: Some(2),
+            ..UniqOptions::default()
+        };
+        assert_eq!(options.key(b"a  bcde"), b" b");
+        assert_eq!(options.key(b"a"), b"");
+        assert_eq!(options.key(b""), b"");
+        assert_eq!(UniqOptions::default().key(b" a "), b" a ");
+    }
+
+    #[test]
+    fn test_zero_terminated() {
+        assert_eq!(uniq_text(&["-z"], "a\0a\0b\nb\0b\n"), "a\0b\nb\0b\n\0");
+        assert_eq!(uniq_text(&["-z"], "a\n\0a\n"), "a\n\0");
+    }
+
+    #[test]
+    fn test_long_lines() {
+        let line = "x".repeat(3 * BUFFER_SIZE);
+        let input = format!("{line}\n{line}\n{line}y\n");
+        assert_eq!(uniq_text(&[], &input), format!("{line}\n{line}y\n"));
+    }
+
+    #[test]
+    fn test_files() {
+        let fixture = Fixture::new(&[("in", "a\na\nb\n"), ("out", "old content\n")]);
+        let (input, output) = (fixture.path("in"), fixture.path("out"));
+        assert_eq!(uniq_text(&[&input], ""), "a\nb\n");
+        // The output is replaced.
+        assert_eq!(uniq_text(&["-c", &input, &output], ""), "");
+        assert_eq!(
+            fs::read_to_string(&output).unwrap(),
+            "      2 a\n      1 b\n"
+        );
+        assert_eq!(uniq_text(&["-", &output], "x\nx\n"), "");
+        assert_eq!(fs::read_to_string(&output).unwrap(), "x\n");
+        assert_eq!(uniq_text(&["-", "-"], "x\nx\n"), "x\n");
+    }
+
+    #[test]
+    fn test_missing_input() {
+        let fixture = Fixture::new(&[]);
+        let (missing, output) = (fixture.path("missing"), fixture.path("out"));
+        assert_eq!(
+            run_util_stdin(run, &["uniq", &missing, &output], b""),
+            (
+                1,
+                String::new(),
+                format!("uniq: {missing}: No such file or directory\n")
+            )
+        );
+        // The output is not created without an input.
+        assert!(!Path::new(&output).exists());
+    }
+
+    #[test]
+    fn test_invalid_output() {
+        let fixture = Fixture::new(&[]);
+        let output = fixture.path("missing/out");
+        assert_eq!(
+            run_util_stdin(run, &["uniq", "-", &output], b"a\n"),
+            (
+                1,
+                String::new(),
+                format!("uniq: {output}: No such file or directory\n")
+            )
+        );
+    }
+
+    #[test]
+    fn test_extra_operand() {
+        assert_eq!(run_util_stdin(run, &["uniq", "a", "b", "c"], b"").0, 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_directory() {
+        let fixture = Fixture::new(&[]);
+        let directory = fixture.path("");
+        let directory = directory.trim_end_matches('/');
+        assert_eq!(
+            run_util_stdin(run, &["uniq", directory], b""),
+            (
+                1,
+                String::new(),
+                format!("uniq: error reading '{directory}'\n")
+            )
+        );
+    }
+
+    #[test]
+    fn test_run_write_error() {
+        let args: Vec<OsString> = vec!["uniq".into()];
+        let mut stdout = io::Cursor::new([0; 4]);
+        let mut stderr = Vec::new();
+        let code = run(
+            &args,
+            &mut &b"line 1\nline 2\n"[..],
+            &mut stdout,
+            &mut stderr,
+        );
+        assert_eq!(code, 1);
+        assert_eq!(
+            stderr,
+            b"uniq: write error: failed to write the buffered data\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_broken_pipe() {
+        use assert_cmd::cargo::CommandCargoExt;
+        use std::os::unix::process::ExitStatusExt;
+        use std::process::Stdio;
+
+        let content: String = (0..200_000).map(|n| format!("{n}\n")).collect();
+        let fixture = Fixture::new(&[("a", &content)]);
+        let mut child = std::process::Command::cargo_bin("uniq")
+            .unwrap()
+            .arg(fixture.path("a"))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        drop(child.stdout.take());
+        let output = child.wait_with_output().unwrap();
+        assert_eq!(output.status.signal(), Some(13));
+        assert!(output.stderr.is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_full_disk() {
+        use assert_cmd::prelude::*;
+
+        let fixture = Fixture::new(&[("a", "a\n")]);
+        let output = std::process::Command::cargo_bin("uniq")
+            .unwrap()
+            .arg(fixture.path("a"))
+            .stdout(File::create("/dev/full").unwrap())
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(
+            String::from_utf8(output.stderr).unwrap(),
+            "uniq: write error: No space left on device\n"
+        );
+
+        let output = std::process::Command::cargo_bin("uniq")
+            .unwrap()
+            .args([&fixture.path("a"), "/dev/full"])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(
+            String::from_utf8(output.stderr).unwrap(),
+            "uniq: write error: No space left on device\n"
+        );
+    }
+}
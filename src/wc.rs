@@ -0,0 +1,784 @@
+//! Print the newline, word, character, and byte counts of files, and the length of their longest line.
+//!
+//! The counts of an input are computed with [`count`], so that they can be taken from any reader:
+//!
+//! ```
+//! use rust_coreutils::wc::{count, Columns, Counts};
+//!
+//! let columns = Columns {
+//!     chars: true,
+//!     max_line_length: true,
+//!     ..Columns::default()
+//! };
+//! let counts = count(&mut "two lines\nwithout a final newline, café".as_bytes(), &columns).unwrap();
+//! assert_eq!(
+//!     counts,
+//!     Counts {
+//!         lines: 1,
+//!         words: 7,
+//!         chars: 39,
+//!         bytes: 40,
+//!         max_line_length: 29,
+//!     }
+//! );
+//! ```
+
+use crate::common::error::{Reporter, UError, UResult};
+use crate::common::input::{InputIter, BUFFER_SIZE};
+use crate::common::os::os_str_bytes;
+use crate::common::utility::{self, parse_args, UtilCommand, Utility};
+use clap::Parser;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io::{self, BufReader, ErrorKind, Read, Write};
+use std::ops::AddAssign;
+use unicode_width::UnicodeWidthChar;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "wc",
+    author,
+    version,
+    about = "Print the newline, word, and byte counts of each FILE, and a total line if more than one FILE is \
+             given. Without options, these three counts are printed. The counts are always printed in the order \
+             of the options below.",
+    long_about = None,
+    args_override_self = true
+)]
+struct Args {
+    /// Print the newline counts.
+    #[arg(short, long, default_value_t = false)]
+    lines: bool,
+
+    /// Print the word counts.
+    #[arg(short, long, default_value_t = false)]
+    words: bool,
+
+    /// Print the character counts, decoding UTF-8. Each byte which is not part of a valid sequence
+    /// counts as a character.
+    #[arg(short = 'm', long, default_value_t = false)]
+    chars: bool,
+
+    /// Print the byte counts.
+    #[arg(short = 'c', long, default_value_t = false)]
+    bytes: bool,
+
+    /// Print the maximum display width of the lines, where a tab advances to the next multiple of 8.
+    #[arg(short = 'L', long, default_value_t = false)]
+    max_line_length: bool,
+
+    /// The files to count. With no FILE, or when FILE is -, standard input is read.
+    #[arg(value_name = "FILE")]
+    files: Vec<OsString>,
+}
+
+impl UtilCommand for Args {
+    const NAME: &'static str = "wc";
+}
+
+/// The description of wc for the multicall binary.
+pub const UTILITY: Utility = Utility {
+    name: Args::NAME,
+    uumain,
+    run,
+    command: Args::util_command,
+};
+
+/// Run wc with the given command line, including the program name, and return the exit code.
+pub fn uumain<I>(args: I) -> i32
+where
+    I: IntoIterator<Item = OsString>,
+{
+    utility::uumain(run_on_stdin, args)
+}
+
+/// Run wc with the given command line, including the program name, on the given streams, and return the
+/// exit code. Standard input is only known as a reader, so its size is unknown.
+pub fn run(
+    args: &[OsString],
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    run_with_size(args, None, stdin, stdout, stderr)
+}
+
+/// Run wc like [`run`] on the standard input of the process, whose size gives the width of the columns when
+/// it is a regular file, like the size of the files given as operands.
+fn run_on_stdin(
+    args: &[OsString],
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    run_with_size(args, stdin_size(), stdin, stdout, stderr)
+}
+
+/// Get the size of the standard input of the process, if it is a regular file.
+#[cfg(unix)]
+fn stdin_size() -> Option<u64> {
+    use std::os::fd::AsFd;
+
+    // The duplicate is closed without closing standard input.
+    let stdin = io::stdin().as_fd().try_clone_to_owned().ok()?;
+    let metadata = fs::File::from(stdin).metadata().ok()?;
+    metadata.is_file().then_some(metadata.len())
+}
+
+/// Get the size of the standard input of the process, which is unknown.
+#[cfg(not(unix))]
+fn stdin_size() -> Option<u64> {
+    None
+}
+
+fn run_with_size(
+    args: &[OsString],
+    stdin_size: Option<u64>,
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> i32 {
+    let args: Args = match parse_args(args.iter().cloned(), stdout, stderr) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+
+    let mut columns = Columns {
+        lines: args.lines,
+        words: args.words,
+        chars: args.chars,
+        bytes: args.bytes,
+        max_line_length: args.max_line_length,
+    };
+    if columns == Columns::default() {
+        columns = Columns {
+            lines: true,
+            words: true,
+            bytes: true,
+            ..Columns::default()
+        };
+    }
+
+    let options = WcOptions {
+        width: column_width(&args.files, &columns, stdin_size),
+        total: args.files.len() > 1,
+        columns,
+    };
+    let mut inputs = InputIter::with_stdin(
+        args.files,
+        Box::new(BufReader::with_capacity(BUFFER_SIZE, stdin)),
+    );
+    let mut reporter = Reporter::new("wc", stderr);
+    let result = wc(&mut inputs, &options, stdout, &mut reporter);
+    reporter.finish(result)
+}
+
+/// The counts of an input.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Counts {
+    /// The number of newlines, so that a final line without one is not counted.
+    pub lines: u64,
+    /// The number of words, which are runs of bytes separated by ASCII whitespace.
+    pub words: u64,
+    /// The number of UTF-8 characters, and of the bytes which are not part of one.
+    pub chars: u64,
+    /// The number of bytes.
+    pub bytes: u64,
+    /// The display width of the longest line.
+    pub max_line_length: u64,
+}
+
+impl AddAssign for Counts {
+    /// Add up the counts of another input, keeping the longest of the lines.
+    fn add_assign(&mut self, other: Counts) {
+        self.lines += other.lines;
+        self.words += other.words;
+        self.chars += other.chars;
+        self.bytes += other.bytes;
+        self.max_line_length = self.max_line_length.max(other.max_line_length);
+    }
+}
+
+/// An input which could not be read to the end.
+#[derive(Debug)]
+pub struct CountError {
+    /// The counts of what was read before the error.
+    pub counts: Counts,
+    /// The reason why the input could not be read.
+    pub error: io::Error,
+}
+
+/// Count the lines, words, and bytes of `reader`, and its characters and the length of its longest line if
+/// they are selected in `columns`, as those take longer.
+pub fn count(reader: &mut dyn Read, columns: &Columns) -> Result<Counts, CountError> {
+    let mut counter = Counter {
+        counts: Counts::default(),
+        chars: columns.chars || columns.max_line_length,
+        line_length: columns.max_line_length,
+        in_word: false,
+        position: 0,
+        pending: Vec::new(),
+    };
+    let mut buffer = vec![0; BUFFER_SIZE];
+    loop {
+        let n = match reader.read(&mut buffer) {
+            Ok(0) => return Ok(counter.finish()),
+            Ok(n) => n,
+            Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+            Err(error) => {
+                return Err(CountError {
+                    counts: counter.finish(),
+                    error,
+                })
+            }
+        };
+        counter.update(&buffer[..n]);
+    }
+}
+
+/// Counts an input which is read in pieces.
+struct Counter {
+    counts: Counts,
+    /// Whether to decode the characters.
+    chars: bool,
+    /// Whether to measure the lines, which needs the characters.
+    line_length: bool,
+    in_word: bool,
+    /// The display width of the current line so far.
+    position: u64,
+    /// An incomplete UTF-8 sequence at the end of the last piece.
+    pending: Vec<u8>,
+}
+
+impl Counter {
+    fn update(&mut self, data: &[u8]) {
+        self.counts.bytes += data.len() as u64;
+        for &byte in data {
+            if byte == b'\n' {
+                self.counts.lines += 1;
+            }
+            let space = is_space(byte);
+            if !space && !self.in_word {
+                self.counts.words += 1;
+            }
+            self.in_word = !space;
+        }
+
+        if !self.chars {
+            return;
+        }
+        if self.pending.is_empty() {
+            self.decode(data);
+        } else {
+            let mut pending = std::mem::take(&mut self.pending);
+            pending.extend_from_slice(data);
+            self.decode(&pending);
+        }
+    }
+
+    fn decode(&mut self, data: &[u8]) {
+        let mut chunks = data.utf8_chunks().peekable();
+        while let Some(chunk) = chunks.next() {
+            if self.line_length {
+                for c in chunk.valid().chars() {
+                    self.counts.chars += 1;
+                    self.measure(c);
+                }
+            } else {
+                self.counts.chars += chunk.valid().chars().count() as u64;
+            }
+
+            let invalid = chunk.invalid();
+            let incomplete = chunks.peek().is_none()
+                && std::str::from_utf8(invalid).is_err_and(|error| error.error_len().is_none());
+            if incomplete {
+                self.pending = invalid.to_vec();
+            } else {
+                // Bytes which are not valid UTF-8 count as characters, but take no columns, like in GNU.
+                self.counts.chars += invalid.len() as u64;
+            }
+        }
+    }
+
+    /// Advance the position in the line over a character, like GNU: a tab advances to the next multiple of 8,
+    /// carriage returns and form feeds start a new line like newlines do, and control characters take no
+    /// columns.
+    fn measure(&mut self, c: char) {
+        match c {
+            '\n' | '\r' | '\x0c' => self.end_line(),
+            '\t' => self.position += 8 - self.position % 8,
+            _ => self.position += c.width().unwrap_or(0) as u64,
+        }
+    }
+
+    fn end_line(&mut self) {
+        self.counts.max_line_length = self.counts.max_line_length.max(self.position);
+        self.position = 0;
+    }
+
+    /// Get the counts at the end of the input, where a pending incomplete sequence is not valid.
+    fn finish(&mut self) -> Counts {
+        self.counts.chars += std::mem::take(&mut self.pending).len() as u64;
+        self.end_line();
+        self.counts
+    }
+}
+
+/// Check whether a byte separates words, like `isspace` in the C locale, which unlike
+/// [`u8::is_ascii_whitespace`] includes the vertical tab.
+fn is_space(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t'..=b'\r')
+}
+
+/// The counts to print, which are always in the same order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Columns {
+    /// Print the line counts.
+    pub lines: bool,
+    /// Print the word counts.
+    pub words: bool,
+    /// Print the character counts.
+    pub chars: bool,
+    /// Print the byte counts.
+    pub bytes: bool,
+    /// Print the length of the longest line.
+    pub max_line_length: bool,
+}
+
+impl Columns {
+    /// Get the selected counts, in order.
+    fn values(&self, counts: &Counts) -> Vec<u64> {
+        [
+            (self.lines, counts.lines),
+            (self.words, counts.words),
+            (self.chars, counts.chars),
+            (self.bytes, counts.bytes),
+            (self.max_line_length, counts.max_line_length),
+        ]
+        .into_iter()
+        .filter_map(|(selected, value)| selected.then_some(value))
+        .collect()
+    }
+
+    fn len(&self) -> usize {
+        self.values(&Counts::default()).len()
+    }
+}
+
+/// Get the width of the columns, like GNU does before reading anything: the number of digits of the total
+/// size of the regular files, or at least 7 if an input is not a regular file, so that its size is unknown.
+/// When a single count of a single input is printed, it is not padded.
+///
+/// `stdin_size` is the size of standard input if it is a regular file.
+fn column_width(operands: &[OsString], columns: &Columns, stdin_size: Option<u64>) -> usize {
+    if operands.len() <= 1 && columns.len() == 1 {
+        return 1;
+    }
+
+    let mut minimum = 1;
+    let mut regular_total: u64 = 0;
+    let stdin = [OsString::from("-")];
+    let operands = if operands.is_empty() {
+        &stdin[..]
+    } else {
+        operands
+    };
+    for operand in operands {
+        if operand == "-" {
+            match stdin_size {
+                Some(size) => regular_total += size,
+                None => minimum = 7,
+            }
+            continue;
+        }
+        match fs::metadata(operand) {
+            Ok(metadata) if metadata.is_file() => regular_total += metadata.len(),
+            Ok(_) => minimum = 7,
+            // The error is reported when the input is opened.
+            Err(_) => {}
+        }
+    }
+    regular_total.to_string().len().max(minimum)
+}
+
+/// The behaviour of wc.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WcOptions {
+    /// The counts to print.
+    pub columns: Columns,
+    /// The width the counts are right-aligned to.
+    pub width: usize,
+    /// Print a row of the totals after the inputs.
+    pub total: bool,
+}
+
+/// Count each input and print a row for it, followed by a row of the totals if requested.
+///
+/// An input which cannot be opened is reported without a row; one which cannot be read to the end is
+/// reported, and its row has what was counted before the error. A failure to write is returned.
+pub fn wc<W: Write>(
+    inputs: &mut InputIter,
+    options: &WcOptions,
+    stdout: &mut dyn Write,
+    reporter: &mut Reporter<W>,
+) -> UResult<()> {
+    write_counts(inputs, options, stdout, reporter).or_else(UError::write)
+}
+
+fn write_counts<W: Write>(
+    inputs: &mut InputIter,
+    options: &WcOptions,
+    stdout: &mut dyn Write,
+    reporter: &mut Reporter<W>,
+) -> io::Result<()> {
+    let mut total = Counts::default();
+    while let Some(input) = inputs.next_raw() {
+        let mut input = match input {
+            Ok(input) => input,
+            Err(error) => {
+                reporter.io_error(error.display_name(), &error.error);
+                continue;
+            }
+        };
+        let counts = match count(&mut input.reader, &options.columns) {
+            Ok(counts) => counts,
+            Err(error) => {
+                reporter.io_error(input.display_name(), &error.error);
+                error.counts
+            }
+        };
+        total += counts;
+        let row = format_row(&counts, options, input.operand.as_deref());
+        stdout.write_all(&row)?;
+    }
+    if options.total {
+        let row = format_row(&total, options, Some(OsStr::new("total")));
+        stdout.write_all(&row)?;
+    }
+    stdout.flush()
+}
+
+/// Format the selected counts right-aligned, separated by spaces, followed by the name if any.
+fn format_row(counts: &Counts, options: &WcOptions, name: Option<&OsStr>) -> Vec<u8> {
+    let values: Vec<String> = options
+        .columns
+        .values(counts)
+        .iter()
+        .map(|value| format!("{value:>width$}", width = options.width))
+        .collect();
+    let mut row = values.join(" ").into_bytes();
+    if let Some(name) = name {
+        row.push(b' ');
+        row.extend_from_slice(&os_str_bytes(name));
+    }
+    row.push(b'\n');
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::testing::{run_util_stdin, Fixture};
+    use assert_cmd::Command;
+
+    /// Count everything in `data`.
+    fn counts(data: &[u8]) -> Counts {
+        let columns = Columns {
+            chars: true,
+            max_line_length: true,
+            ..Columns::default()
+        };
+        count(&mut &data[..], &columns).unwrap()
+    }
+
+    #[test]
+    fn test_count() {
+        assert_eq!(counts(b""), Counts::default());
+        assert_eq!(
+            counts(b"one two\nthree\n"),
+            Counts {
+                lines: 2,
+                words: 3,
+                chars: 14,
+                bytes: 14,
+                max_line_length: 7,
+            }
+        );
+        // A final line without a newline is not counted, but its words are.
+        assert_eq!(
+            counts(b"one\ntwo"),
+            Counts {
+                lines: 1,
+                words: 2,
+                chars: 7,
+                bytes: 7,
+                max_line_length: 3,
+            }
+        );
+        assert_eq!(counts(b" \t\x0b\x0c\r\n ").words, 0);
+        assert_eq!(counts(b"a\x0bb\x0cc\rd\x00e\xffe").words, 4);
+        assert_eq!(counts(b"\n\n\n").lines, 3);
+    }
+
+    #[test]
+    fn test_count_chars() {
+        let count = |data: &str| counts(data.as_bytes());
+        assert_eq!(count("café").chars, 4);
+        assert_eq!(count("café").bytes, 5);
+        assert_eq!(count("日本語\n😀\n").chars, 6);
+        // Bytes which are not part of a valid sequence count one each.
+        assert_eq!(counts(b"a\xffb\xc3").chars, 4);
+        assert_eq!(counts(b"\xe2\x82x").chars, 3);
+        assert_eq!(counts(b"\xed\xa0\x80").chars, 3);
+    }
+
+    #[test]
+    fn test_count_max_line_length() {
+        let length = |data: &str| counts(data.as_bytes()).max_line_length;
+        assert_eq!(length(""), 0);
+        assert_eq!(length("a\nabc\nab"), 3);
+        assert_eq!(length("abcd\nlongest line"), 12);
+        // Tabs advance to the next multiple of 8.
+        assert_eq!(length("\t"), 8);
+        assert_eq!(length("a\tb"), 9);
+        assert_eq!(length("1234567\t\t"), 16);
+        assert_eq!(length("12345678\t"), 16);
+        // Wide characters take two columns, and combining marks none.
+        assert_eq!(length("日本語"), 6);
+        assert_eq!(length("cafe\u{301}"), 4);
+        // Carriage returns and form feeds start over, and control characters take no columns.
+        assert_eq!(length("abcdef\rab\x0cabc\x01\x7f"), 6);
+        assert_eq!(length("ab\rabc\x0cabcd"), 4);
+        assert_eq!(counts(b"ab\xff\xfe").max_line_length, 2);
+    }
+
+    #[test]
+    fn test_count_across_reads() {
+        let data = b"word ".repeat(BUFFER_SIZE / 3);
+        assert_eq!(counts(&data).words, (BUFFER_SIZE / 3) as u64);
+        let data = b"x".repeat(3 * BUFFER_SIZE);
+        assert_eq!(counts(&data).words, 1);
+        assert_eq!(counts(&data).max_line_length, 3 * BUFFER_SIZE as u64);
+
+        // Multibyte characters split across reads.
+        let data = "é€😀".repeat(BUFFER_SIZE);
+        let expected = counts(data.as_bytes());
+        assert_eq!(expected.chars, 3 * BUFFER_SIZE as u64);
+        assert_eq!(expected.max_line_length, 4 * BUFFER_SIZE as u64);
+        for size in [1, 2, 3, 5, 4096] {
+            let mut counter = Counter {
+                counts: Counts::default(),
+                chars: true,
+                line_length: true,
+                in_word: false,
+                position: 0,
+                pending: Vec::new(),
+            };
+            for piece in data.as_bytes().chunks(size) {
+                counter.update(piece);
+            }
+            assert_eq!(counter.finish(), expected);
+        }
+        let mut counter = Counter {
+            counts: Counts::default(),
+            chars: true,
+            line_length: false,
+            in_word: false,
+            position: 0,
+            pending: Vec::new(),
+        };
+        counter.update(b"\xe2\x82");
+        assert_eq!(counter.finish().chars, 2);
+    }
+
+    #[test]
+    fn test_default_columns() {
+        assert_eq!(
+            run_util_stdin(run, &["wc"], b"one two\nthree"),
+            (0, "      1       3      13\n".to_owned(), String::new())
+        );
+        assert_eq!(
+            run_util_stdin(run, &["wc", "-lwc"], b"one two\nthree").1,
+            run_util_stdin(run, &["wc"], b"one two\nthree").1
+        );
+        assert_eq!(
+            run_util_stdin(run, &["wc", "-"], b"").1,
+            "      0       0       0 -\n"
+        );
+    }
+
+    #[test]
+    fn test_selected_columns() {
+        // A single count of a single input is not padded.
+        assert_eq!(run_util_stdin(run, &["wc", "-l"], b"a\nb\n").1, "2\n");
+        assert_eq!(
+            run_util_stdin(run, &["wc", "-w", "-"], b"a\nb\n").1,
+            "2 -\n"
+        );
+        // The order of the options does not matter.
+        assert_eq!(
+            run_util_stdin(run, &["wc", "-cl"], b"a\nb\n").1,
+            "      2       4\n"
+        );
+        assert_eq!(
+            run_util_stdin(run, &["wc", "--bytes", "--words"], b"a\nb\n").1,
+            "      2       4\n"
+        );
+    }
+
+    #[test]
+    fn test_chars_and_max_line_length() {
+        let text = "日本\ta\ncafé\n";
+        assert_eq!(
+            run_util_stdin(run, &["wc", "-m"], text.as_bytes()).1,
+            "10\n"
+        );
+        assert_eq!(run_util_stdin(run, &["wc", "-L"], text.as_bytes()).1, "9\n");
+        // -m and -c are different columns.
+        assert_eq!(
+            run_util_stdin(run, &["wc", "-cm"], text.as_bytes()).1,
+            "     10      15\n"
+        );
+        // The columns are in the same order, whatever the order of the options.
+        let all = "      2       3      10      15       9\n";
+        assert_eq!(
+            run_util_stdin(run, &["wc", "-lwmcL"], text.as_bytes()).1,
+            all
+        );
+        assert_eq!(
+            run_util_stdin(run, &["wc", "-L", "-c", "-m", "-w", "-l"], text.as_bytes()).1,
+            all
+        );
+        assert_eq!(
+            run_util_stdin(
+                run,
+                &["wc", "--max-line-length", "--chars", "--lines"],
+                text.as_bytes()
+            )
+            .1,
+            "      2      10       9\n"
+        );
+    }
+
+    #[test]
+    fn test_total_max_line_length() {
+        let fixture = Fixture::new(&[("short", "ab\n"), ("long", "abcd\nabc\n")]);
+        let (short, long) = (&fixture.path("short"), &fixture.path("long"));
+        // The total is the longest line of all inputs, not the sum.
+        assert_eq!(
+            run_util_stdin(run, &["wc", "-lL", short, long], b"").1,
+            format!(" 1  2 {short}\n 2  4 {long}\n 3  4 total\n")
+        );
+    }
+
+    #[test]
+    fn test_files() {
+        let fixture = Fixture::new(&[
+            ("empty", ""),
+            ("partial", "no newline"),
+            ("text", "a b\nc\n"),
+        ]);
+        let (empty, partial, text) = (
+            &fixture.path("empty"),
+            &fixture.path("partial"),
+            &fixture.path("text"),
+        );
+        assert_eq!(
+            run_util_stdin(run, &["wc", empty], b"").1,
+            format!("0 0 0 {empty}\n")
+        );
+        assert_eq!(
+            run_util_stdin(run, &["wc", "-l", partial], b"").1,
+            format!("0 {partial}\n")
+        );
+        // The width is the number of digits of the total size, 16 bytes.
+        assert_eq!(
+            run_util_stdin(run, &["wc", partial, text, empty], b""),
+            (
+                0,
+                format!(" 0  2 10 {partial}\n 2  3  6 {text}\n 0  0  0 {empty}\n 2  5 16 total\n"),
+                String::new()
+            )
+        );
+        // Standard input has an unknown size.
+        assert_eq!(
+            run_util_stdin(run, &["wc", "-l", text, "-"], b"\n\n\n").1,
+            format!("      2 {text}\n      3 -\n      5 total\n")
+        );
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let fixture = Fixture::new(&[("text", "a b\nc\n")]);
+        let (missing, text) = (&fixture.path("missing"), &fixture.path("text"));
+        assert_eq!(
+            run_util_stdin(run, &["wc", missing, text], b""),
+            (
+                1,
+                format!("2 3 6 {text}\n2 3 6 total\n"),
+                format!("wc: {missing}: No such file or directory\n")
+            )
+        );
+    }
+
+    #[test]
+    fn test_directory() {
+        let fixture = Fixture::new(&[]);
+        let directory = &fixture.path("");
+        let (code, stdout, stderr) = run_util_stdin(run, &["wc", directory], b"");
+        assert_eq!(code, 1);
+        assert_eq!(stdout, format!("      0       0       0 {directory}\n"));
+        assert!(stderr.starts_with(&format!("wc: {directory}: ")));
+    }
+
+    #[test]
+    fn test_run_write_error() {
+        let mut buffer = [0; 4];
+        let mut stderr = Vec::new();
+        let code = run(
+            &[OsString::from("wc")],
+            &mut &b"\n\n\n\n\n\n\n\n\n\n\n\n"[..],
+            &mut &mut buffer[..],
+            &mut stderr,
+        );
+        assert_eq!(code, 1);
+        assert_eq!(stderr, b"wc: write error: failed to write whole buffer\n");
+    }
+
+    #[test]
+    fn test_binary() {
+        let mut cmd = Command::cargo_bin("wc").unwrap();
+        cmd.write_stdin("one two\nthree\n")
+            .assert()
+            .success()
+            .stdout("      2       3      14\n");
+
+        let mut cmd = Command::cargo_bin("wc").unwrap();
+        cmd.args(["-c", "missing"])
+            .assert()
+            .code(1)
+            .stdout("")
+            .stderr("wc: missing: No such file or directory\n");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_binary_regular_stdin() {
+        use assert_cmd::cargo::CommandCargoExt;
+
+        // Standard input has the size of the file it is, like the operands.
+        let fixture = Fixture::new(&[("text", "one two\nthree\n")]);
+        let text = &fixture.path("text");
+        let output = std::process::Command::cargo_bin("wc")
+            .unwrap()
+            .args(["-", text])
+            .stdin(fs::File::open(text).unwrap())
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            format!(" 2  3 14 -\n 2  3 14 {text}\n 4  6 28 total\n")
+        );
+    }
+}
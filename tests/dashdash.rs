@@ -0,0 +1,552 @@
+//! Operands which look like options, like files named `-n` and `--help`.
+//!
+//! `--` ends the options, so that `cat -- --help` reads the file `--help`, while a lone `-` is an operand
+//! meaning standard input. Every utility which takes file operands has a case here, which is run through
+//! the multicall binary in a directory with such files; utilities without file operands are listed in
+//! [`NO_FILE_OPERANDS`], those taking directories in [`DIRECTORY_OPERANDS`], those taking symbolic links
+//! in [`LINK_OPERANDS`], those changing owners in [`OWNER_OPERANDS`], those taking templates of names in
+//! [`TEMPLATE_OPERANDS`], and those whose output depends on the system in [`VARYING_OUTPUT`].
+
+#![cfg(feature = "multicall")]
+
+use assert_cmd::Command;
+use rust_coreutils::UTILITIES;
+use std::fs;
+
+/// A utility reading the files `-n` and `--help`.
+struct FileCase {
+    util: &'static str,
+    /// The options before `--`.
+    args: &'static [&'static str],
+    /// The output for the files `-n` and `--help`, which contain `dash n` and `dash dash help`, or for
+    /// `--help` alone with the utilities in [`SINGLE_FILE_OPERAND`].
+    stdout: &'static str,
+    /// The output for `-`, with `stdin` as standard input, or nothing with the utilities in
+    /// [`NO_STDIN_OPERAND`], which fail to find a file named `-`.
+    stdin_stdout: &'static str,
+}
+
+/// The cases of the utilities which take file operands.
+const FILE_CASES: &[FileCase] = &[
+    FileCase {
+        util: "base32",
+        args: &[],
+        stdout: "MRQXG2BAMRQXG2BANBSWY4AK\n",
+        stdin_stdout: "ON2GI2LOBI======\n",
+    },
+    FileCase {
+        util: "base64",
+        args: &[],
+        stdout: "ZGFzaCBkYXNoIGhlbHAK\n",
+        stdin_stdout: "c3RkaW4K\n",
+    },
+    FileCase {
+        util: "basenc",
+        args: &["--base16"],
+        stdout: "6461736820646173682068656C700A\n",
+        stdin_stdout: "737464696E0A\n",
+    },
+    FileCase {
+        util: "cat",
+        args: &[],
+        stdout: "dash n\ndash dash help\n",
+        stdin_stdout: "stdin\n",
+    },
+    FileCase {
+        util: "chmod",
+        args: &["644"],
+        stdout: "",
+        stdin_stdout: "",
+    },
+    FileCase {
+        util: "cp",
+        args: &[],
+        // The second operand is the destination, see `test_cp_destination_operand`.
+        stdout: "",
+        stdin_stdout: "",
+    },
+    FileCase {
+        util: "cut",
+        args: &["-b1-"],
+        stdout: "dash n\ndash dash help\n",
+        stdin_stdout: "stdin\n",
+    },
+    FileCase {
+        util: "du",
+        // The apparent sizes, since the disk usage depends on the file system.
+        args: &["-b"],
+        stdout: "7\t-n\n15\t--help\n",
+        stdin_stdout: "",
+    },
+    FileCase {
+        util: "head",
+        args: &[],
+        stdout: "==> -n <==\ndash n\n\n==> --help <==\ndash dash help\n",
+        stdin_stdout: "stdin\n",
+    },
+    FileCase {
+        util: "ln",
+        args: &["-f"],
+        // The second operand is the link, see `test_ln_link_operand`.
+        stdout: "",
+        stdin_stdout: "",
+    },
+    FileCase {
+        util: "ls",
+        args: &[],
+        stdout: "--help\n-n\n",
+        stdin_stdout: "",
+    },
+    FileCase {
+        util: "mv",
+        args: &[],
+        // The second operand is the destination, see `test_mv_destination_operand`.
+        stdout: "",
+        stdin_stdout: "",
+    },
+    FileCase {
+        util: "od",
+        args: &["-c"],
+        stdout: concat!(
+            "0000000   d   a   s   h       n  \\n   d   a   s   h       d   a   s   h\n",
+            "0000020       h   e   l   p  \\n\n",
+            "0000026\n",
+        ),
+        stdin_stdout: "0000000   s   t   d   i   n  \\n\n0000006\n",
+    },
+    FileCase {
+        util: "realpath",
+        // A missing last component is allowed, so `-` is a name like the others.
+        args: &["--relative-to=."],
+        stdout: "-n\n--help\n",
+        stdin_stdout: "-\n",
+    },
+    FileCase {
+        util: "rm",
+        args: &[],
+        stdout: "",
+        stdin_stdout: "",
+    },
+    FileCase {
+        util: "shred",
+        args: &["-n", "1"],
+        stdout: "",
+        stdin_stdout: "",
+    },
+    FileCase {
+        util: "sort",
+        args: &[],
+        stdout: "dash dash help\ndash n\n",
+        stdin_stdout: "stdin\n",
+    },
+    FileCase {
+        util: "split",
+        args: &[],
+        // The second operand is the prefix, see `test_split_prefix_operand`.
+        stdout: "",
+        stdin_stdout: "",
+    },
+    FileCase {
+        util: "stat",
+        // Only the names, as the rest of the status changes from one run to the next.
+        args: &["-c", "%n"],
+        stdout: "-n\n--help\n",
+        stdin_stdout: "",
+    },
+    FileCase {
+        util: "tail",
+        args: &[],
+        stdout: "==> -n <==\ndash n\n\n==> --help <==\ndash dash help\n",
+        stdin_stdout: "stdin\n",
+    },
+    FileCase {
+        util: "touch",
+        args: &[],
+        stdout: "",
+        stdin_stdout: "",
+    },
+    FileCase {
+        util: "truncate",
+        // The file `-` is created.
+        args: &["-s", "+0"],
+        stdout: "",
+        stdin_stdout: "",
+    },
+    FileCase {
+        util: "uniq",
+        args: &[],
+        // The second operand is the output, see `test_uniq_output_operand`.
+        stdout: "",
+        stdin_stdout: "stdin\n",
+    },
+    FileCase {
+        util: "wc",
+        args: &["-l"],
+        // The width is the number of digits of the total size of the files.
+        stdout: " 1 -n\n 1 --help\n 2 total\n",
+        stdin_stdout: "1 -\n",
+    },
+];
+
+/// The utilities which do not take file operands.
+const NO_FILE_OPERANDS: &[&str] = &["echo", "printf", "seq", "tr"];
+
+/// The utilities whose operands are directories rather than files, which have their own tests, like
+/// `test_mkdir_directory_operands`.
+const DIRECTORY_OPERANDS: &[&str] = &["mkdir", "rmdir"];
+
+/// The utilities whose operands are symbolic links, which have their own tests, like
+/// `test_readlink_link_operands`.
+const LINK_OPERANDS: &[&str] = &["readlink"];
+
+/// The utilities which change the owners of their operands, which only exist on Unix, which have their own
+/// tests, like `test_chown_file_operands`.
+const OWNER_OPERANDS: &[&str] = &["chgrp", "chown"];
+
+/// The utilities whose operands are templates of new names, which have their own tests, like
+/// `test_mktemp_template_operand`.
+const TEMPLATE_OPERANDS: &[&str] = &["mktemp"];
+
+/// The utilities whose output depends on the system, like the space left on the file systems, which have
+/// their own tests, like `test_df_file_operands`.
+const VARYING_OUTPUT: &[&str] = &["df"];
+
+/// The utilities which take at most one file operand.
+const SINGLE_FILE_OPERAND: &[&str] = &["base32", "base64", "basenc"];
+
+/// The utilities which take file operands, but never read them from standard input.
+const NO_STDIN_OPERAND: &[&str] = &["chmod", "cp", "du", "ln", "ls", "mv", "rm", "shred", "stat"];
+
+/// Get a command running a utility through the multicall binary, in a directory with the files `-n` and
+/// `--help`.
+fn command(util: &str) -> (Command, tempfile::TempDir) {
+    let directory = tempfile::tempdir().unwrap();
+    fs::write(directory.path().join("-n"), "dash n\n").unwrap();
+    fs::write(directory.path().join("--help"), "dash dash help\n").unwrap();
+    let mut command = Command::cargo_bin("coreutils").unwrap();
+    command.current_dir(directory.path()).arg(util);
+    (command, directory)
+}
+
+/// Get the cases of the utilities which are built.
+fn file_cases() -> impl Iterator<Item = &'static FileCase> {
+    FILE_CASES
+        .iter()
+        .filter(|case| UTILITIES.iter().any(|utility| utility.name == case.util))
+}
+
+#[test]
+fn test_every_utility_has_a_case() {
+    for utility in UTILITIES {
+        assert!(
+            NO_FILE_OPERANDS.contains(&utility.name)
+                || DIRECTORY_OPERANDS.contains(&utility.name)
+                || LINK_OPERANDS.contains(&utility.name)
+                || OWNER_OPERANDS.contains(&utility.name)
+                || TEMPLATE_OPERANDS.contains(&utility.name)
+                || VARYING_OUTPUT.contains(&utility.name)
+                || FILE_CASES.iter().any(|case| case.util == utility.name),
+            "{} has no case",
+            utility.name
+        );
+    }
+}
+
+#[test]
+fn test_file_operands() {
+    for case in file_cases() {
+        let files: &[&str] = match SINGLE_FILE_OPERAND.contains(&case.util) {
+            true => &["--help"],
+            false => &["-n", "--help"],
+        };
+        let (mut command, _directory) = command(case.util);
+        command
+            .args(case.args)
+            .arg("--")
+            .args(files)
+            .assert()
+            .success()
+            .stdout(case.stdout)
+            .stderr("");
+    }
+}
+
+#[test]
+fn test_stdin_operand() {
+    for case in file_cases() {
+        let (mut command, _directory) = command(case.util);
+        let assert = command
+            .args(case.args)
+            .args(["--", "-"])
+            .write_stdin("stdin\n")
+            .assert()
+            .stdout(case.stdin_stdout);
+        if NO_STDIN_OPERAND.contains(&case.util) {
+            assert.failure();
+        } else {
+            assert.success().stderr("");
+        }
+    }
+}
+
+#[test]
+#[cfg(feature = "feat_echo")]
+fn test_echo_prints_dashdash() {
+    // Unlike the other utilities, echo has no operands to separate, so `--` is printed like in GNU.
+    for (args, stdout) in [
+        (&["--", "-n"][..], "-- -n\n"),
+        (&["-n", "--", "x"], "-- x"),
+        (&["--", "--help"], "-- --help\n"),
+        (&["--"], "--\n"),
+    ] {
+        let (mut command, _directory) = command("echo");
+        command.args(args).assert().success().stdout(stdout);
+    }
+}
+
+#[test]
+#[cfg(feature = "feat_uniq")]
+fn test_uniq_output_operand() {
+    let (mut command, directory) = command("uniq");
+    command
+        .args(["--", "-n", "--help"])
+        .assert()
+        .success()
+        .stdout("");
+    assert_eq!(
+        fs::read_to_string(directory.path().join("--help")).unwrap(),
+        "dash n\n"
+    );
+}
+
+#[test]
+#[cfg(feature = "feat_split")]
+fn test_split_prefix_operand() {
+    for (operands, stdin, output, content) in [
+        (&["-n", "--help"][..], "", "--helpaa", "dash n\n"),
+        (&["-"], "stdin\n", "xaa", "stdin\n"),
+    ] {
+        let (mut command, directory) = command("split");
+        command
+            .arg("--")
+            .args(operands)
+            .write_stdin(stdin)
+            .assert()
+            .success()
+            .stdout("");
+        assert_eq!(
+            fs::read_to_string(directory.path().join(output)).unwrap(),
+            content
+        );
+    }
+}
+
+#[test]
+#[cfg(feature = "feat_cp")]
+fn test_cp_destination_operand() {
+    let (mut command, directory) = command("cp");
+    command
+        .args(["--", "-n", "--help"])
+        .assert()
+        .success()
+        .stdout("");
+    assert_eq!(
+        fs::read_to_string(directory.path().join("--help")).unwrap(),
+        "dash n\n"
+    );
+}
+
+#[test]
+#[cfg(feature = "feat_ln")]
+fn test_ln_link_operand() {
+    let (mut hard, _directory) = command("ln");
+    hard.args(["--", "-n", "--help"])
+        .assert()
+        .failure()
+        .stderr("ln: failed to create hard link '--help': File exists\n");
+
+    // With a single operand, the link is made in the current directory.
+    let (mut symbolic, directory) = command("ln");
+    fs::create_dir(directory.path().join("d")).unwrap();
+    symbolic
+        .current_dir(directory.path().join("d"))
+        .args(["-s", "--", "../-n"])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+    assert_eq!(
+        fs::read_to_string(directory.path().join("d/-n")).unwrap(),
+        "dash n\n"
+    );
+}
+
+#[test]
+#[cfg(feature = "feat_mkdir")]
+fn test_mkdir_directory_operands() {
+    let (mut command, directory) = command("mkdir");
+    command
+        .args(["--", "-p", "-"])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+    assert!(directory.path().join("-p").is_dir() && directory.path().join("-").is_dir());
+}
+
+#[test]
+#[cfg(all(feature = "feat_readlink", unix))]
+fn test_readlink_link_operands() {
+    let (mut command, directory) = command("readlink");
+    std::os::unix::fs::symlink("--help", directory.path().join("-s")).unwrap();
+    command
+        .args(["--", "-s", "-n"])
+        .assert()
+        .failure()
+        .stdout("--help\n")
+        .stderr("");
+}
+
+#[test]
+#[cfg(feature = "feat_mktemp")]
+fn test_mktemp_template_operand() {
+    let (mut command, directory) = command("mktemp");
+    let output = command.args(["--", "-nXXX"]).output().unwrap();
+    assert!(output.status.success() && output.stderr.is_empty());
+    let name = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        name.starts_with("-n") && name.len() == "-nXXX\n".len(),
+        "{name}"
+    );
+    assert!(directory.path().join(name.trim_end()).is_file());
+}
+
+#[test]
+#[cfg(feature = "feat_rmdir")]
+fn test_rmdir_directory_operands() {
+    let (mut command, directory) = command("rmdir");
+    fs::create_dir(directory.path().join("-p")).unwrap();
+    command
+        .args(["--", "-p"])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+    assert!(!directory.path().join("-p").exists());
+}
+
+#[test]
+#[cfg(feature = "feat_mv")]
+fn test_mv_destination_operand() {
+    let (mut command, directory) = command("mv");
+    command
+        .args(["--", "-n", "--help"])
+        .assert()
+        .success()
+        .stdout("");
+    assert!(!directory.path().join("-n").exists());
+    assert_eq!(
+        fs::read_to_string(directory.path().join("--help")).unwrap(),
+        "dash n\n"
+    );
+}
+
+#[test]
+#[cfg(feature = "feat_printf")]
+fn test_printf_format_after_dashdash() {
+    // The operands of printf are a format and its arguments rather than files, and only a leading `--` is
+    // skipped, like in GNU.
+    for (args, stdout) in [
+        (&["--", "-n%s", "--help"][..], "-n--help"),
+        (&["--", "--"], "--"),
+        (&["-n"], "-n"),
+    ] {
+        let (mut command, _directory) = command("printf");
+        command.args(args).assert().success().stdout(stdout);
+    }
+}
+
+#[test]
+#[cfg(feature = "feat_seq")]
+fn test_seq_negative_operands() {
+    // The negative numbers are operands, with or without `--`.
+    for args in [&["--", "-2", "-1"][..], &["-2", "-1"]] {
+        let (mut command, _directory) = command("seq");
+        command.args(args).assert().success().stdout("-2\n-1\n");
+    }
+}
+
+#[test]
+#[cfg(feature = "feat_tr")]
+fn test_tr_sets_after_dashdash() {
+    // The operands of tr are sets rather than files.
+    let (mut command, _directory) = command("tr");
+    command
+        .args(["--", "-n", "xy"])
+        .write_stdin("a-n\n")
+        .assert()
+        .success()
+        .stdout("axy\n");
+}
+
+#[test]
+#[cfg(all(feature = "feat_df", target_os = "linux"))]
+fn test_df_file_operands() {
+    use predicates::prelude::*;
+
+    // Both files are on the same file system, so the rows only differ if the space changes in between.
+    let (mut files, _directory) = command("df");
+    let rows = predicate::function(|stdout: &str| {
+        stdout.starts_with("Filesystem ") && stdout.lines().count() == 3
+    });
+    files
+        .args(["--", "-n", "--help"])
+        .assert()
+        .success()
+        .stdout(rows)
+        .stderr("");
+    let (mut stdin, _directory) = command("df");
+    stdin
+        .args(["--", "-"])
+        .assert()
+        .failure()
+        .stderr("df: -: No such file or directory\n");
+}
+
+#[test]
+#[cfg(all(feature = "feat_chgrp", unix))]
+fn test_chgrp_file_operands() {
+    // The files are given to their group, which anyone can do.
+    let (mut files, _directory) = command("chgrp");
+    files
+        .args(["-c", "--reference=-n", "--", "-n", "--help"])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+    let (mut stdin, _directory) = command("chgrp");
+    stdin
+        .args(["--reference=-n", "--", "-"])
+        .assert()
+        .failure()
+        .stderr("chgrp: cannot access '-': No such file or directory\n");
+}
+
+#[test]
+#[cfg(all(feature = "feat_chown", unix))]
+fn test_chown_file_operands() {
+    // The files are given to their owner, which anyone can do.
+    let (mut files, _directory) = command("chown");
+    files
+        .args(["-c", "--reference=-n", "--", "-n", "--help"])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+    let (mut stdin, _directory) = command("chown");
+    stdin
+        .args(["--reference=-n", "--", "-"])
+        .assert()
+        .failure()
+        .stderr("chown: cannot access '-': No such file or directory\n");
+}
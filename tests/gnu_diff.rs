@@ -0,0 +1,1234 @@
+//! Differential tests against the system coreutils.
+//!
+//! Every case is run through our implementation and through the reference binary (`/usr/bin/<util>` or
+//! `/bin/<util>`), and their stdout, whether they wrote anything to stderr, and their exit status must match.
+//! A utility whose reference binary is missing is skipped.
+//!
+//! Run with `cargo test --features compare-gnu --test gnu_diff`.
+
+#![cfg(feature = "compare-gnu")]
+
+use std::fs;
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+/// A single invocation of a utility.
+struct Case {
+    args: &'static [&'static str],
+    stdin: &'static [u8],
+    /// Set for cases where we are known to differ from GNU, with the reason. Such cases are still
+    /// run, and they are reported when they start to match.
+    divergent: Option<&'static str>,
+}
+
+const fn case(args: &'static [&'static str]) -> Case {
+    Case {
+        args,
+        stdin: b"",
+        divergent: None,
+    }
+}
+
+const fn divergent(args: &'static [&'static str], reason: &'static str) -> Case {
+    Case {
+        args,
+        stdin: b"",
+        divergent: Some(reason),
+    }
+}
+
+const fn with_stdin(args: &'static [&'static str], stdin: &'static [u8]) -> Case {
+    Case {
+        args,
+        stdin,
+        divergent: None,
+    }
+}
+
+/// The cases of a single utility.
+struct Suite {
+    util: &'static str,
+    /// Arguments passed to our implementation before the arguments of each case, e.g. to select GNU-compatible
+    /// behavior where our default differs.
+    prefix: &'static [&'static str],
+    /// Normalize outputs that legitimately differ between runs or implementations (timestamps, hostnames).
+    normalize: fn(Vec<u8>) -> Vec<u8>,
+    /// Create the files that the cases work on, in a directory which is the working directory of the runs.
+    /// The files are created again before every run, and what the run leaves of them must match as well.
+    files: Option<fn(&Path)>,
+    cases: &'static [Case],
+}
+
+fn identity(output: Vec<u8>) -> Vec<u8> {
+    output
+}
+
+const SUITES: &[Suite] = &[
+    Suite {
+        util: "base32",
+        prefix: &[],
+        normalize: identity,
+        files: None,
+        cases: &[
+            case(&[]),
+            with_stdin(&[], b"foobar"),
+            with_stdin(&[], b"\0binary\xff\n"),
+            with_stdin(&["-w", "5"], b"hello world"),
+            with_stdin(&["-w0"], b"hello world"),
+            with_stdin(&["-w", "x"], b"ab"),
+            case(&["-", "-"]),
+            case(&["-d", "/"]),
+            with_stdin(&["-d"], b"MZXW6===\nMZXW6YTB\n"),
+            with_stdin(&["-d"], b"MY======MZXQ====\nMZXW6YQ="),
+            with_stdin(&["-d"], b"mzxw6ytb"),
+            with_stdin(&["-d"], b"MZXW6YT*"),
+            with_stdin(&["-d"], b"MZX====="),
+            with_stdin(&["-d"], b"MZXW6Y=="),
+            with_stdin(&["-d"], b"MZXW6YTBOI"),
+            with_stdin(&["--decode"], b"MY=====\n="),
+        ],
+    },
+    Suite {
+        util: "base64",
+        prefix: &[],
+        normalize: identity,
+        files: None,
+        cases: &[
+            case(&[]),
+            with_stdin(&[], b"foobar"),
+            with_stdin(&["-"], b"\0binary\xff\n"),
+            with_stdin(&["-w", "5"], b"hello world"),
+            with_stdin(&["-w0"], b"hello world"),
+            with_stdin(&["--wrap=1"], b"ab"),
+            with_stdin(&["-w", "x"], b"ab"),
+            with_stdin(&["-w", "-1"], b"ab"),
+            case(&["does-not-exist"]),
+            case(&["-", "-"]),
+            case(&["/"]),
+            case(&["-d", "/"]),
+            with_stdin(&["-d"], b"Zm9v\nYmFy\n"),
+            with_stdin(&["-d"], b"YQ==YWI=\nYWJj"),
+            with_stdin(&["--decode"], b"YQ\n=\n=\n"),
+            with_stdin(&["-d"], b"YWJj*"),
+            with_stdin(&["-d"], b"YW*Jj"),
+            with_stdin(&["-d"], b"YWJjZA"),
+            with_stdin(&["-d"], b"Y==="),
+            with_stdin(&["-d"], b"YR=="),
+            with_stdin(&["-d"], b"YWJj\r\n"),
+        ],
+    },
+    Suite {
+        util: "basenc",
+        prefix: &[],
+        normalize: identity,
+        files: None,
+        cases: &[
+            with_stdin(&[], b"data"),
+            with_stdin(&["-w", "x"], b"data"),
+            with_stdin(&["--base64", "--base16"], b"hi"),
+            with_stdin(&["--base64url"], b"\xfb\xff\xfe"),
+            with_stdin(&["--base32hex"], b"foobar"),
+            with_stdin(&["--base16", "-w", "5"], b"\0binary\xff\n"),
+            with_stdin(&["--base2msbf"], b"ab\x80"),
+            with_stdin(&["--base2lsbf", "-w0"], b"ab\x80"),
+            with_stdin(&["--z85"], b"\x86\x4f\xd2\x6f\xb5\x59\xf7\x5b"),
+            with_stdin(&["--z85"], b"abcdefg"),
+            with_stdin(&["--z85", "-w", "3"], b"abcdefgh"),
+            with_stdin(&["--z85", "-d"], b"HelloWorl"),
+            with_stdin(&["--z85", "-d"], b"Hello\nWorld\n"),
+            with_stdin(&["--z85", "-d"], b"%nSc1"),
+            with_stdin(&["--base64url", "-d"], b"-_-_"),
+            with_stdin(&["--base32hex", "-d"], b"CPNMUOJ1E8======"),
+            with_stdin(&["--base16", "-d"], b"666F6F\n"),
+            with_stdin(&["--base16", "-d"], b"666f"),
+            with_stdin(&["--base16", "-d"], b"666"),
+            with_stdin(&["--base2msbf", "-d"], b"01100110\n0110"),
+            with_stdin(&["--base2lsbf", "-d"], b"1000011001000110"),
+            case(&["--base16", "-", "-"]),
+        ],
+    },
+    Suite {
+        util: "cat",
+        prefix: &[],
+        normalize: identity,
+        files: None,
+        cases: &[
+            case(&[]),
+            with_stdin(&[], b"data\nmore data"),
+            with_stdin(&["-"], b"\0binary\xff\n"),
+            with_stdin(&["-", "-"], b"once\n"),
+            case(&["-u"]),
+            case(&["does-not-exist"]),
+            with_stdin(&["-", "does-not-exist", "-"], b"data\n"),
+            case(&["/"]),
+            with_stdin(&["-n"], b"a\n\nb"),
+            with_stdin(&["-b"], b"\na\n\n\nb\n"),
+            with_stdin(&["-n", "-b"], b"\na\n\n\nb\n"),
+            with_stdin(&["-b", "-n"], b"\na\n\n\nb\n"),
+            with_stdin(&["-s"], b"\n\n\na\n\n\n\nb\n\n"),
+            with_stdin(&["-sn", "-", "-"], b"a\n\n\n \n\n"),
+            with_stdin(&["-sb"], b"\n\na\n\n\nb"),
+            with_stdin(&["--number", "--squeeze-blank"], b"x\n\n\ny"),
+            with_stdin(&["--number-nonblank"], b"x\n\n\ny"),
+            with_stdin(&["-v"], b"\x00\x01\x1f\t\x7f\x80\x89\x9f\xa0\xff\n"),
+            with_stdin(&["-A"], b"\x00\x01\x1f\t\x7f\x80\x89\x9f\xa0\xff\n\r\n"),
+            with_stdin(&["-e"], b"a\tb\x7f\n\nc"),
+            with_stdin(&["-t"], b"a\tb\x7f\n\nc"),
+            with_stdin(&["-T", "-E"], b"a\tb\x7f\n\nc"),
+            with_stdin(&["-nET"], b"\ta\n\n\tb"),
+            with_stdin(&["-bsA"], b"\x89\n\n\n\x7f"),
+            Case {
+                args: &["-E"],
+                stdin: b"a\r\nb\r",
+                divergent: Some(
+                    "GNU 9.1 and later show a carriage return before a newline as ^M with -E alone",
+                ),
+            },
+        ],
+    },
+    Suite {
+        util: "cut",
+        prefix: &[],
+        normalize: identity,
+        files: None,
+        cases: &[
+            with_stdin(&["-b2-4,3-6"], b"abcdefgh\n"),
+            with_stdin(&["-b", "5-,-2"], b"abcdefgh\nab\n"),
+            with_stdin(&["-b", "4,1,2-3,1"], b"abcdef\n"),
+            with_stdin(&["-b", "1 3"], b"abcdef\n"),
+            with_stdin(&["-c2-"], b"abc\n\nx"),
+            with_stdin(&["-b9"], b"abc\n"),
+            with_stdin(&["-z", "-b1"], b"ab\0c\nd\0"),
+            with_stdin(&["-b1", "-n"], b"xyz\n"),
+            case(&["-b1", "/etc/passwd", "/etc/passwd"]),
+            case(&["-b1", "does-not-exist", "/etc/passwd"]),
+            case(&["-b1", "/", "/etc/passwd"]),
+            case(&["-b", "0"]),
+            case(&["-b", "3-2"]),
+            case(&["-b", "-0"]),
+            case(&["-b", "1,,2"]),
+            case(&["-b", "1-x"]),
+            case(&["-b", "-"]),
+            case(&["-b", "1-2-3"]),
+            case(&["-b", "99999999999999999999"]),
+            case(&["-b1", "-c1"]),
+            case(&[]),
+            case(&["-d:", "-f1,7", "/etc/passwd"]),
+            case(&["-d:", "-f6-", "--output-delimiter", " -> ", "/etc/passwd"]),
+            case(&["-d:", "--complement", "-f2-", "/etc/passwd"]),
+            with_stdin(&["-f2"], b"a\tb c\td\nnone\n"),
+            with_stdin(&["-d:", "-f2"], b"a:b\nnodelim\n\n"),
+            with_stdin(&["-d:", "-f2", "-s"], b"a:b\nnodelim\n\n"),
+            with_stdin(&["-d:", "-f2-"], b"a:b:c\n::\n:x\n"),
+            with_stdin(&["-d:", "-f5"], b"a:b:c"),
+            with_stdin(&["-d:", "-f2,5", "--output-delimiter=::"], b"a:b:c\n"),
+            with_stdin(&["-d", "-", "-f2"], b"a-b\n"),
+            with_stdin(&["-d", "", "-f2"], b"a\0b\n"),
+            with_stdin(&["-z", "-d:", "-f2"], b"a:b\nc\0"),
+            with_stdin(&["--complement", "-b3-"], b"abcdef\nx\n"),
+            with_stdin(&["-b1-2,4,5-", "--output-delimiter=||"], b"abcdef\nab\n"),
+            with_stdin(
+                &["-b2,4", "--complement", "--output-delimiter=-"],
+                b"abcdef\n",
+            ),
+            case(&["-f", "0"]),
+            case(&["-f", "x"]),
+            case(&["-f", "1-2-3"]),
+            case(&["-f1", "-d", "ab"]),
+            case(&["-b1", "-d:"]),
+            case(&["-c1", "-s"]),
+            case(&["-f1", "-b1"]),
+        ],
+    },
+    Suite {
+        util: "head",
+        prefix: &[],
+        normalize: identity,
+        files: None,
+        cases: &[
+            case(&[]),
+            with_stdin(&[], b"1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n"),
+            with_stdin(&["-n", "2"], b"a\nb\nc\n"),
+            with_stdin(&["-n2"], b"a\nb"),
+            with_stdin(&["-n", "5"], b"a\nb"),
+            with_stdin(&["-n", "0"], b"a\nb"),
+            with_stdin(&["--lines=1"], b"\n\n"),
+            with_stdin(&["-zn", "2"], b"a\nb\0c\0d\0"),
+            with_stdin(&["-v"], b"x\n"),
+            with_stdin(&["-n1", "-", "/etc/passwd"], b"stdin\n"),
+            with_stdin(&["-qn1", "-", "/etc/passwd"], b"stdin\n"),
+            with_stdin(&["-vqn1", "-", "/etc/passwd"], b"stdin\n"),
+            case(&["-n1", "does-not-exist", "/etc/passwd", "does-not-exist"]),
+            case(&["-n1", "/", "/etc/passwd"]),
+            case(&["-n", "1x"]),
+            case(&["-n", ""]),
+            with_stdin(&["-c", "2"], b"abc\n"),
+            with_stdin(&["-c", "10"], b"abc\n"),
+            with_stdin(&["--bytes=1K"], &[b'x'; 3000]),
+            with_stdin(&["-c", "2", "-n", "1"], b"abc"),
+            with_stdin(&["-n", "1", "-c", "2"], b"abc\nd\n"),
+            with_stdin(&["-n", "-1"], b"a\nb\nc"),
+            with_stdin(&["-n", "-0"], b"a\nb\nc"),
+            with_stdin(&["-n", "-5"], b"a\nb\n"),
+            with_stdin(&["-c", "-2"], b"abcdef"),
+            with_stdin(&["-c", "-0"], b"abcdef"),
+            with_stdin(&["-c", "-10"], b"abcdef"),
+            with_stdin(&["-2"], b"a\nb\nc\n"),
+            with_stdin(&["-3c"], b"abcdef"),
+            with_stdin(&["-1vz"], b"a\0b\0"),
+            case(&["-c", "1KX"]),
+            case(&["-n", "-99999999999999999999999"]),
+            divergent(
+                &["-1x"],
+                "clap reports the unknown option as a usage error, with status 2",
+            ),
+        ],
+    },
+    Suite {
+        util: "ls",
+        prefix: &[],
+        normalize: identity,
+        files: None,
+        cases: &[
+            case(&["/"]),
+            case(&["-1", "/etc"]),
+            case(&["-a", "/etc"]),
+            case(&["-A", "/etc"]),
+            case(&["-aA", "/etc"]),
+            case(&["-Aa", "/"]),
+            case(&["/etc/passwd", "/", "/usr", "/etc/hostname"]),
+            case(&["/usr", "/"]),
+            case(&["-C", "/usr/bin"]),
+            case(&["-C", "-w", "40", "/etc"]),
+            case(&["-Ca", "-w", "0", "/etc"]),
+            case(&["-C1", "/"]),
+            case(&["-1C", "-w", "60", "/usr/bin"]),
+            case(&["does-not-exist", "/"]),
+            case(&["does-not-exist"]),
+            case(&["-w", "x", "/"]),
+            case(&["-w", "-1", "/"]),
+            case(&["-l", "/usr"]),
+            case(&["-la", "/etc"]),
+            case(&["-o", "/usr/bin"]),
+            case(&["-g", "/usr"]),
+            case(&["-log", "/usr"]),
+            case(&["-l", "/etc/passwd", "/etc/hostname", "/usr", "/dev/null"]),
+            case(&["-l", "/dev/null", "/dev/zero", "/etc/passwd"]),
+            case(&["-l1", "/usr"]),
+            case(&["-lC1", "/usr"]),
+            case(&["-1l", "/usr"]),
+            case(&["-lC", "-w", "40", "/usr"]),
+            case(&["-lh", "/usr/bin"]),
+            case(&["-l", "--si", "/usr/lib"]),
+            case(&["-lh", "--si", "/etc"]),
+            case(&["-h", "/etc"]),
+            case(&["-t", "/usr/bin"]),
+            case(&["-S", "/usr/bin"]),
+            case(&["-Sr", "/usr/bin"]),
+            case(&["-X", "/usr/bin"]),
+            case(&["-rX", "/etc"]),
+            case(&["-ltr", "/usr"]),
+            case(&["-U", "/etc"]),
+            case(&["-tU", "/etc"]),
+            case(&["-S", "/etc/passwd", "/etc/hostname", "/usr"]),
+            case(&["-R", "/usr/share/zoneinfo/America"]),
+            case(&["-Rl", "/usr/lib/x86_64-linux-gnu/gconv"]),
+            case(&["-Rr", "/etc/ssl", "/etc/default"]),
+            case(&["--color=always", "/usr/bin"]),
+            case(&["--color", "-l", "/dev"]),
+            case(&["--color=yes", "-C", "/etc"]),
+            case(&["--color=always", "-lR", "/etc/ssl"]),
+            case(&["--color=always", "-w", "20", "/usr/lib"]),
+            case(&["--color=auto", "/etc"]),
+            case(&["--color=never", "/etc"]),
+            case(&["--color=bogus", "/etc"]),
+            case(&["--color=a", "/etc"]),
+        ],
+    },
+    Suite {
+        util: "od",
+        prefix: &[],
+        normalize: identity,
+        files: None,
+        cases: &[
+            case(&[]),
+            with_stdin(&[], b"hello world\n\0\x01\xffabc"),
+            with_stdin(&["-c"], b"\0\x07\x08\t\n\x0b\x0c\r\x1b \"\\~\x7f\x80\xff"),
+            with_stdin(&["-b"], b"binary\xff\n"),
+            with_stdin(&["-x"], b"odd"),
+            with_stdin(&["-o", "-o"], b"abcdefghijklmnopq"),
+            with_stdin(&["-c", "-x"], b"hello world\n\0\x01\xffabc"),
+            with_stdin(&["-x", "-b", "-c"], b"hello world\n\0\x01\xffabc"),
+            with_stdin(&["-bc", "-o"], b"a"),
+            with_stdin(&["-c"], &[b'a'; 80]),
+            with_stdin(&["-v", "-c"], &[b'a'; 80]),
+            with_stdin(&["-x"], &[0; 35]),
+            with_stdin(&["-", "does-not-exist", "-"], b"data\n"),
+            with_stdin(&["-c", "/", "-"], b"data\n"),
+            with_stdin(&["-t", "d4"], b"hello world\n\0\x01\xffabc\x80\x7f "),
+            with_stdin(&["-t", "x1z"], b"hello world\n\0\x01\xffabc\x80\x7f "),
+            with_stdin(
+                &["-t", "x2z", "-t", "c", "-t", "a"],
+                b"\0\x01 ~\x7f\x80\xc1\n",
+            ),
+            with_stdin(
+                &["-t", "d1u2o4x8", "-t", "dCuSoIxL"],
+                b"0123456789abcdefghij",
+            ),
+            with_stdin(
+                &["-tfF", "-tfD", "-tfL"],
+                b"0123456789abcdef\x00\x00\x80\x3f",
+            ),
+            with_stdin(&["-A", "x", "-j", "16", "-N", "32"], &[b'a'; 64]),
+            with_stdin(&["-A", "d", "-j", "0x10", "-c"], b"0123456789abcdefghij"),
+            with_stdin(&["-A", "n", "-x", "-c"], b"0123456789abcdefghij"),
+            with_stdin(&["-j", "21"], b"0123456789abcdefghij"),
+            with_stdin(&["-j", "20", "-N", "0"], b"0123456789abcdefghij"),
+            with_stdin(&["-N", "1K", "-j", "1b", "-b"], &[7; 2000]),
+            with_stdin(&["-A", "q"], b""),
+            with_stdin(&["-j", "1e"], b""),
+            with_stdin(&["-t", "d3"], b""),
+            with_stdin(&["-t", "x1zz"], b""),
+        ],
+    },
+    Suite {
+        util: "printf",
+        prefix: &[],
+        normalize: identity,
+        files: None,
+        cases: &[
+            case(&[]),
+            case(&["%s, %s!\n", "Hello", "world"]),
+            case(&["%s=%d\n", "a", "1", "b", "2", "c", "3"]),
+            case(&["%s=%d %c.\n", "a", "1", "b", "b", "2"]),
+            case(&["%d %i %u %o %x %X\n", "-12", "+3", "10", "8", "255", "255"]),
+            case(&["%ld %hhd %zu %%\n", "1", "2", "3"]),
+            case(&["%c%c|%c\n", "abc", "xyz", ""]),
+            case(&["%s\n", r"a\tb\n"]),
+            case(&[r"\101\x42\q\t%s\n", r"\n"]),
+            case(&[r"%s\c%s", "a", "b"]),
+            case(&[r"%d\c", "x"]),
+            case(&["x\n", "a", "b"]),
+            case(&["%d ", "0x1f", "010", "-0x10", " 12", "", "'A", "\"B", "'AB"]),
+            case(&["%u %x\n", "-1", "-1"]),
+            case(&["%d %s\n", "abc", "next"]),
+            case(&["%d %s\n", "12abc", "next"]),
+            case(&["%d %s\n", "08", "next"]),
+            case(&["%d %s\n", "99999999999999999999", "next"]),
+            case(&["%u\n", "18446744073709551616"]),
+            case(&["%y"]),
+            case(&["a%"]),
+            case(&["%d%l%\n", "1"]),
+            case(&["-x"]),
+            case(&["--", "-%s", "n"]),
+            case(&["--help%s", "x"]),
+            case(&[
+                "[%5d|%-5d|%05d|%+d|% d|%.3d|%.0d]\n",
+                "42",
+                "42",
+                "42",
+                "42",
+                "-42",
+                "7",
+                "0",
+            ]),
+            case(&[
+                "[%#o|%#x|%#X|%08.3x|%-#8x]\n",
+                "8",
+                "255",
+                "255",
+                "255",
+                "255",
+            ]),
+            case(&["[%10s|%-10s|%.2s|%3c]\n", "abc", "abc", "abc", "x"]),
+            case(&[
+                "[%*d|%-*d|%.*s|%*.*f]\n",
+                "5",
+                "1",
+                "-4",
+                "2",
+                "2",
+                "abc",
+                "10",
+                "2",
+                "3.14159",
+            ]),
+            case(&[
+                "[%f|%e|%E|%g|%G]\n",
+                "3.14159",
+                "1e100",
+                "-12345.678",
+                "0.0001",
+                "1e-5",
+            ]),
+            case(&[
+                "[%#.0f|%#g|%.3g|%010.2f|%-+8.1e]\n",
+                "2.5",
+                "1",
+                "1234.5",
+                "-3.14159",
+                "2",
+            ]),
+            case(&["[%f|%e|%G|%05f]\n", "inf", "-inf", "nan", "inf"]),
+            case(&["[%f|%f|%f]\n", "0x1.8p1", "'A", "1.5x"]),
+            case(&["%b|", r"a\tb\n", r"\0101\101", r"x\cy", "z"]),
+            case(&["[%*d]", "99999999999"]),
+            case(&["[%#d]"]),
+            case(&["[%5b]"]),
+            case(&[r"\%d|", "1"]),
+            divergent(
+                &["%.1g %.20f\n", "0.15", "0.1"],
+                "the floating point numbers are doubles, while GNU uses long doubles",
+            ),
+        ],
+    },
+    Suite {
+        util: "seq",
+        prefix: &[],
+        normalize: identity,
+        files: None,
+        cases: &[
+            case(&[]),
+            case(&["5"]),
+            case(&["0"]),
+            case(&["-2", "2"]),
+            case(&["1", "3", "10"]),
+            case(&["5", "-2", "0"]),
+            case(&["5", "1"]),
+            case(&["3", "3"]),
+            case(&["--", "-3", "-1"]),
+            case(&["9223372036854775806", "9223372036854775808"]),
+            case(&["18446744073709551614", "18446744073709551616"]),
+            case(&["0x10", "0x12"]),
+            case(&["1e2", "102"]),
+            case(&["1", "inf", "1e10"]),
+            case(&["1", "0", "5"]),
+            case(&["1", "-0.0", "5"]),
+            case(&["x"]),
+            case(&["1", "2x"]),
+            case(&["1", "nan"]),
+            case(&["1", "2", "3", "4"]),
+            case(&["0", "0.1", "1"]),
+            case(&["1", "0.25", "2"]),
+            case(&["0", "0.000001", "0.000003"]),
+            case(&["0.1", "1e-1", "0.3"]),
+            case(&["1.5e1", "17"]),
+            case(&["-.5", "0.5", "1"]),
+            case(&["-w", "8", "10"]),
+            case(&["-w", "-10", "2", "1"]),
+            case(&["-w", "0", "-0.5", "-2"]),
+            case(&["-w", "1", "1.50", "3"]),
+            case(&["-w", ".5", "2"]),
+            case(&["-f", "%.2f", "1", ".5", "3"]),
+            case(&["-f", "a%%b%gc%%", "1", "2"]),
+            case(&["-f", "%-+8.1e|", "-1", "1"]),
+            case(&["-s", ", ", "1", "3"]),
+            case(&["-ws:", "9", "11"]),
+            case(&["-s", "-1", "1", "2"]),
+            case(&["1", "-w", "3"]),
+            case(&["-f", "%d", "1"]),
+            case(&["-f", "%f%f", "1"]),
+            case(&["-f", "%", "1"]),
+            case(&["-w", "-f", "%g", "1"]),
+        ],
+    },
+    Suite {
+        util: "sort",
+        prefix: &[],
+        normalize: identity,
+        files: None,
+        cases: &[
+            case(&[]),
+            with_stdin(&[], b"b\nc\na\n"),
+            with_stdin(&["-"], b"b\na"),
+            with_stdin(&[], b"b\n\xff\nB\na\n\xc3\xa9\n-\n\n"),
+            with_stdin(&[], b"ab\na\nabc\n"),
+            with_stdin(&[], b"a\0b\nb\0a\na\n\0\n"),
+            with_stdin(&["-z"], b"b\na\0c\n\0a"),
+            case(&["/etc/passwd"]),
+            with_stdin(&["/etc/passwd", "-", "/etc/passwd"], b"stdin\n"),
+            case(&["does-not-exist"]),
+            case(&["/etc/passwd", "does-not-exist"]),
+            case(&["/"]),
+            case(&["-o", "/", "/etc/passwd"]),
+            with_stdin(&["-n"], b"10\n9\nabc\n0\n-1\n\n1\n+1\n-\n-0\n"),
+            with_stdin(&["-n"], b"010\n  9\n\t8\n1.10\n1.05\n1.1\n-.5\n.5\n1.\n"),
+            with_stdin(&["-n"], b"-10\n-9\n-1.5\n-1\n-1.50\n1e3\n2,000\n"),
+            with_stdin(&["-n"], b"1b\n01\n1a\n"),
+            with_stdin(&["-rn"], b"1a\n1b\n2\n"),
+            with_stdin(&["-r"], b"b\nc\na\n"),
+            with_stdin(&["-f"], b"b\n_\nB\na\nA\n"),
+            with_stdin(&["-fr"], b"b\n_\nB\na\nA\n"),
+            with_stdin(&["-u"], b"b\na\nb\na\n"),
+            with_stdin(&["-un"], b"007\n7\n3\n08\n"),
+            with_stdin(&["-run"], b"007\n7\n3\n08\n"),
+            with_stdin(&["-fu"], b"a\nA\nb\n"),
+            with_stdin(&["-un"], b"x\n\n0\n"),
+            case(&["-nr", "/etc/passwd"]),
+            case(&["-t:", "-k3,3n", "-k1,1", "/etc/passwd"]),
+            case(&["-t:", "-k7", "-k1.2r,1.3", "/etc/passwd"]),
+            with_stdin(&["-k2"], b"a c\nb b\nc a\n"),
+            with_stdin(&["-k2,2", "-k2b,2"], b"a  c\nb b\nc   a\n"),
+            with_stdin(&["-b", "-k2.2,2.3"], b"x abcd\ny bbbb\nz  zab\n"),
+            with_stdin(&["-k5", "-k1.5,1.2"], b"a b\nb a\n"),
+            with_stdin(&["-u", "-k2n", "-r"], b"x 10\ny 9\nz 9\n"),
+            with_stdin(&["-z", "-t", "\\0", "-k2"], b"a\0b\0"),
+            with_stdin(&["-k", "1.0"], b""),
+            with_stdin(&["-k", "1,x"], b""),
+            with_stdin(&["-t", "ab"], b""),
+            with_stdin(&["-t", "a", "-t", "b"], b""),
+            with_stdin(
+                &["-h"],
+                b"10M\n2G\n900K\n 3M\n2\n1000\n0K\n-1K\n1k\n1.K\n1..5K\n",
+            ),
+            with_stdin(
+                &["-V"],
+                b"1.0.10\n1.0.5\nfoo\nfoo~beta\n.\n..\n.bashrc\n\na.tar.gz\na-1.tar\n",
+            ),
+            with_stdin(&["-fV"], b"b\nA\na\nB\n"),
+            with_stdin(&["-t-", "-k2V", "-k1,1hr"], b"1K-1.10\n1M-1.9\n2G-1.9\n"),
+            with_stdin(&["-n", "-h"], b""),
+            with_stdin(&["-k1fb,1Vn"], b""),
+        ],
+    },
+    Suite {
+        util: "tail",
+        prefix: &[],
+        normalize: identity,
+        files: None,
+        cases: &[
+            case(&[]),
+            with_stdin(&[], b"1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n"),
+            with_stdin(&["-n", "2"], b"a\nb\nc\n"),
+            with_stdin(&["-n2"], b"a\nb\nc"),
+            with_stdin(&["-n", "5"], b"a\nb"),
+            with_stdin(&["-n", "0"], b"a\nb"),
+            with_stdin(&["-n", "-2"], b"a\nb\nc\n"),
+            with_stdin(&["--lines=1"], b"a\nb\n\n"),
+            with_stdin(&["-zn", "2"], b"a\nb\0c\0d\0"),
+            with_stdin(&["-v"], b"x\n"),
+            case(&["/etc/passwd"]),
+            case(&["-n1", "/etc/passwd"]),
+            case(&["-n", "1K", "/etc/passwd"]),
+            with_stdin(&["-n1", "-", "/etc/passwd"], b"stdin\n"),
+            with_stdin(&["-qn1", "-", "/etc/passwd"], b"stdin\n"),
+            with_stdin(&["-vqn1", "-", "/etc/passwd"], b"stdin\n"),
+            case(&["-n1", "does-not-exist", "/etc/passwd", "does-not-exist"]),
+            case(&["-n1", "/", "/etc/passwd"]),
+            case(&["-n", "1x"]),
+            case(&["-n", ""]),
+            with_stdin(&["-c", "3"], b"abcdef"),
+            with_stdin(&["-c", "10"], b"abcdef"),
+            with_stdin(&["--bytes=1K"], &[b'x'; 3000]),
+            case(&["-c", "1b", "/etc/passwd"]),
+            with_stdin(&["-c", "2", "-n", "1"], b"a\nbc\n"),
+            with_stdin(&["-n", "1", "-c", "2"], b"a\nbc\n"),
+            with_stdin(&["-n", "+1"], b"a\nb\nc"),
+            with_stdin(&["-n", "+0"], b"a\nb\nc"),
+            with_stdin(&["-n", "+2"], b"a\nb\nc"),
+            with_stdin(&["-n", "+9"], b"a\nb\nc"),
+            case(&["-n", "+3", "/etc/passwd"]),
+            with_stdin(&["-c", "+3"], b"abcdef"),
+            with_stdin(&["-c", "+0"], b"abcdef"),
+            with_stdin(&["-c", "+9"], b"abcdef"),
+            case(&["-c", "+10", "/etc/passwd"]),
+            case(&["-c", "+1G", "/etc/passwd"]),
+            with_stdin(&["-2"], b"1\n2\n3\n"),
+            with_stdin(&["+2"], b"1\n2\n3\n"),
+            with_stdin(&["-2c"], b"1\n2\n3\n"),
+            with_stdin(&["+2c"], b"1\n2\n3\n"),
+            with_stdin(&["+2l", "-"], b"1\n2\n3\n"),
+            case(&["-1b", "/etc/passwd"]),
+            case(&["-c", "1KX"]),
+            with_stdin(&["-f"], b"a\nb\n"),
+            with_stdin(&["-n1", "-f", "-"], b"a\nb\n"),
+            case(&["-f", "does-not-exist"]),
+            case(&["-s", "x", "/etc/passwd"]),
+            case(&["-s", "-1", "/etc/passwd"]),
+        ],
+    },
+    Suite {
+        util: "tr",
+        prefix: &[],
+        normalize: identity,
+        files: None,
+        cases: &[
+            with_stdin(&["a-z", "A-Z"], b"Hello, world!\n"),
+            with_stdin(&["abcd", "xy"], b"abcde\n"),
+            with_stdin(&["-t", "abcd", "xy"], b"abcde\n"),
+            with_stdin(&["ab", "xyz"], b"abc\n"),
+            with_stdin(&["aa", "xy"], b"a\n"),
+            with_stdin(&["lo", "a-"], b"hello\n"),
+            with_stdin(&["[a-c]", "xyz"], b"[b]\n"),
+            with_stdin(&[r"\n", " "], b"a\nb\n"),
+            with_stdin(&[r"a\-c", "xyz"], b"abc-\n"),
+            with_stdin(&[r"\n-\r", "x"], b"a\n\r\x0b\n"),
+            with_stdin(
+                &[r"\0-\377", r"\200-\377\0-\177"],
+                b"\x00\x7f\x80\xff binary\n",
+            ),
+            with_stdin(&["lo", "[x*]"], b"hello\n"),
+            with_stdin(&["helo", "a[x*2]b"], b"hello\n"),
+            with_stdin(&["abc", "x[y*]z"], b"abcd\n"),
+            with_stdin(&["a-j", "[x*010]y"], b"abcdefghij\n"),
+            with_stdin(&["[x*2]", "ab"], b"x\n"),
+            with_stdin(&["[x*", "abc"], b"[*x\n"),
+            with_stdin(&[r"a\", "bc"], b"a\\\n"),
+            with_stdin(&["ab", r"\400"], b"abc"),
+            with_stdin(&["", "a"], b"abc\n"),
+            case(&["z-a", "A"]),
+            case(&[r"\200-\100", "x"]),
+            case(&["a", "[x*9a]"]),
+            case(&["[x*]", "a"]),
+            case(&["a", "[x*][y*]"]),
+            case(&["abc", ""]),
+            case(&[]),
+            case(&["abc"]),
+            case(&["a", "b", "c"]),
+            with_stdin(&["-d", r"\r"], b"a\r\nb\r\n"),
+            with_stdin(&["-s", " "], b"a   b  c\n"),
+            with_stdin(&["-cd", "[:print:]"], b"a\x01b\tc\xff\n"),
+            with_stdin(&["-cs", "a-zA-Z", r"\n"], b"Hello, big  world!\n"),
+            with_stdin(&["-ds", "0-9", " "], b"a1 2 3  b\n"),
+            with_stdin(&["-s", "ab", "x"], b"aaabbbab\n"),
+            with_stdin(&["-C", "a", "xy"], b"\x00\x01abc\xff"),
+            with_stdin(&["-ct", "ab", "xyz"], b"\x00\x01\x02abc\xff"),
+            with_stdin(&["[:lower:]", "[:upper:]"], b"Hello\n"),
+            with_stdin(&["[:upper:][:lower:]", "[:lower:][:upper:]"], b"Hello\n"),
+            with_stdin(&["-c", "[:alpha:]", "[x*]"], b"ab1 c\n"),
+            with_stdin(&["-d", "[:punct:][:space:]"], b"a, b!\tc\n"),
+            with_stdin(&["-d", "[=a=]"], b"banana\n"),
+            with_stdin(&["-d", "[:*3]"], b"a:*\n"),
+            case(&["-d", "[:foo:]"]),
+            case(&["-d", "[=ab=]"]),
+            case(&["a-z", "[:digit:]"]),
+            case(&["a", "[=b=]"]),
+            case(&["[:alpha:]", "[:upper:]"]),
+            case(&["[:lower:][:upper:]", "[:upper:]"]),
+            case(&["-c", "[:alpha:]", "x[y*]"]),
+            case(&["-ds", "a", "[b*]"]),
+            case(&["-d", "a", "b"]),
+            case(&["-ds", "a"]),
+        ],
+    },
+    Suite {
+        util: "uniq",
+        prefix: &[],
+        normalize: identity,
+        files: None,
+        cases: &[
+            case(&[]),
+            with_stdin(&[], b"a\na\nb\nb\nb\nc\na\n"),
+            with_stdin(&["-"], b"a\na"),
+            with_stdin(&[], b"a\nb"),
+            with_stdin(&[], b"\n\n\xff\n\xff\n"),
+            with_stdin(&["-c"], b"a\na\na\nb\nc\nc\n"),
+            with_stdin(&["-c"], b"a\na"),
+            with_stdin(&["-d"], b"a\na\na\nb\nc\nc\n"),
+            with_stdin(&["-u"], b"a\na\na\nb\nc\nc\n"),
+            with_stdin(&["-du"], b"a\na\nb\n"),
+            with_stdin(&["-cd"], b"a\nb\nb\n"),
+            with_stdin(&["-z"], b"a\0a\0b\nb\0b\n"),
+            case(&["/etc/passwd"]),
+            case(&["-c", "/etc/passwd", "-"]),
+            with_stdin(&["-", "/dev/null"], b"a\n"),
+            case(&["does-not-exist"]),
+            case(&["does-not-exist", "/dev/null"]),
+            case(&["/"]),
+            case(&["/etc/passwd", "/"]),
+            with_stdin(&["-f1"], b"1 apple\n2 apple\n3 pear\n"),
+            with_stdin(&["-f1"], b"a\tx\na  x\nb  x\n"),
+            with_stdin(&["-f3", "-c"], b"a b\nc\n\nd e f\nd e f g\n"),
+            with_stdin(&["-f1", "-s2"], b"1 aab\n2 bab\n3 cac\n"),
+            with_stdin(&["-s1", "-w2"], b"xabc\nyabd\nzacd\n"),
+            with_stdin(&["-w0"], b"a\nb\n"),
+            with_stdin(&["-ic"], b"Apple\napple\nAPPLE\npear\n"),
+            with_stdin(&["-z", "-f1"], b"ab\0\nac\0"),
+            case(&["-f", "-1"]),
+            case(&["-s", "x"]),
+            case(&["-w", "1x"]),
+            case(&["-w", "99999999999999999999999", "/etc/passwd"]),
+            divergent(
+                &["a", "b", "c"],
+                "clap reports the extra operand as a usage error, with status 2",
+            ),
+        ],
+    },
+    Suite {
+        util: "wc",
+        prefix: &[],
+        normalize: identity,
+        files: None,
+        cases: &[
+            case(&[]),
+            with_stdin(&[], b"one two\nthree"),
+            with_stdin(&["-"], b"one two\nthree\n"),
+            with_stdin(&["-l"], b"a\nb\nc"),
+            with_stdin(&["-w"], b" a\tb\x0bc\x0cd\re\n"),
+            with_stdin(&["-c"], b"\0\xff\n"),
+            with_stdin(&["-cl"], b"a\nb\n"),
+            with_stdin(&["-wlc", "-", "-"], b"a\nb\n"),
+            case(&["/etc/passwd"]),
+            case(&["-l", "/etc/passwd"]),
+            with_stdin(&["/etc/passwd", "-"], b"stdin\n"),
+            case(&["/etc/passwd", "/etc/passwd"]),
+            case(&["does-not-exist"]),
+            case(&["does-not-exist", "/etc/passwd"]),
+            case(&["/", "/etc/passwd"]),
+            with_stdin(&["-m"], b"one two\nthree"),
+            with_stdin(&["-L"], b"a\nabc\tx\n1234567\t\tb\n"),
+            with_stdin(&["-L"], b"abcdef\rab\x0cabc\x01\x7f\n"),
+            with_stdin(&["-Lmc"], b"a\tb\n"),
+            with_stdin(&["-L", "-c", "-m", "-w", "-l"], b"one two\nthree\n"),
+            with_stdin(
+                &["-lL", "-", "/etc/passwd"],
+                b"a longer line than in passwd? no\n",
+            ),
+            Case {
+                args: &["-m"],
+                stdin: "café\n".as_bytes(),
+                divergent: Some(
+                    "characters are always decoded as UTF-8, while GNU uses the C locale here",
+                ),
+            },
+            divergent(
+                &["-w", "/bin/true"],
+                "GNU does not count runs of unprintable bytes as words",
+            ),
+        ],
+    },
+    Suite {
+        util: "echo",
+        prefix: &["--compat=gnu"],
+        normalize: identity,
+        files: None,
+        cases: &[
+            case(&[]),
+            case(&["data"]),
+            case(&["data", "more data"]),
+            case(&["  spaces  ", ""]),
+            case(&["-n", "data"]),
+            case(&["-n"]),
+            case(&["-n", "-n", "data"]),
+            case(&["-nn", "data"]),
+            case(&["-x"]),
+            case(&["-n", "-x", "data"]),
+            case(&["-nx", "data"]),
+            case(&["--", "data"]),
+            case(&["-", "data"]),
+            case(&["data", "-n"]),
+            case(&["-s", "a", "b"]),
+            case(&["-e", r"a\tb\nc"]),
+            case(&["-E", r"a\tb"]),
+            case(&["-e", "-E", r"a\tb"]),
+            case(&["-E", "-e", r"a\tb"]),
+            case(&["-eE", r"a\tb"]),
+            case(&["-Ee", r"a\tb"]),
+            case(&["-e", r"\\n"]),
+            case(&["-e", r"\\\n"]),
+            case(&["-e", r"\\\\n"]),
+            case(&["-e", r"\a\b\e\f\r\v"]),
+            case(&["-e", r#"\q\"\'"#]),
+            case(&["-e", "trailing\\"]),
+            case(&["-e", "a\\", "b"]),
+            case(&["-e", r"\c"]),
+            case(&["-e", r"a\cb", "c"]),
+            case(&["-e", "a", r"b\c", "c"]),
+            case(&["-e", r"a\\cb"]),
+            case(&["-ne", r"a\cb"]),
+            case(&["-e", r"\0"]),
+            case(&["-e", r"\0101"]),
+            case(&["-e", r"\01234"]),
+            case(&["-e", r"\0777"]),
+            case(&["-e", r"\08"]),
+            divergent(
+                &["-e", r"\101"],
+                "GNU echo also accepts octal escapes without the leading zero",
+            ),
+            case(&["-e", r"\x41"]),
+            case(&["-e", r"\x414"]),
+            case(&["-e", r"\xA"]),
+            case(&["-e", r"\xff\x00"]),
+            case(&["-e", r"\x"]),
+            case(&["-e", r"\xg"]),
+            divergent(&["-e", r"\u00e9"], "GNU echo does not support \\u"),
+            divergent(&["-e", r"\U0001F600"], "GNU echo does not support \\U"),
+            case(&["-e", r"\u"]),
+        ],
+    },
+    #[cfg(unix)]
+    Suite {
+        util: "readlink",
+        prefix: &[],
+        normalize: identity,
+        files: Some(files),
+        cases: &[
+            case(&["link"]),
+            case(&["link", "dangling", "dirlink"]),
+            case(&["file"]),
+            case(&["-v", "file"]),
+            case(&["-n", "link"]),
+            case(&["-z", "link", "dirlink"]),
+            case(&["-f", "dirlink/sub"]),
+            case(&["-f", "dangling"]),
+            case(&["-f", "missing/x"]),
+            case(&["-e", "dangling"]),
+            case(&["-e", "link"]),
+            case(&["-m", "dangling/x/../y"]),
+        ],
+    },
+    #[cfg(unix)]
+    Suite {
+        util: "realpath",
+        prefix: &[],
+        normalize: identity,
+        files: Some(files),
+        cases: &[
+            case(&["."]),
+            case(&["dirlink/sub", "link"]),
+            case(&["dangling"]),
+            case(&["-e", "dangling"]),
+            case(&["-m", "missing/x/../y"]),
+            case(&["-s", "dirlink/../file"]),
+            case(&["-s", "nope/x"]),
+            case(&["-s", "-m", "nope/x/../../y"]),
+            case(&["-s", "-e", "nope/x"]),
+            case(&["-q", "-e", "nope", "file"]),
+            case(&["file/x"]),
+            case(&["-z", "file", "dir"]),
+            case(&["--relative-to=dir/sub", "file", "dir/a"]),
+            case(&[
+                "--relative-to=dir",
+                "--relative-base=dir",
+                "file",
+                "dir/sub/b",
+            ]),
+        ],
+    },
+    #[cfg(unix)]
+    Suite {
+        util: "stat",
+        prefix: &[],
+        normalize: identity,
+        files: Some(files),
+        cases: &[
+            case(&["-c", "%n %s %F %a %A %h %u %g %U %G", "file", "dir", "link"]),
+            case(&["-L", "-c", "%n %s %F %a", "link", "dirlink"]),
+            case(&["-c", "%N", "link", "file"]),
+            case(&["--printf", r"%n\t%s\n", "file", "dir/a"]),
+            case(&["--printf", "%n", "file"]),
+            case(&["-c", "%%%s%", "file"]),
+            case(&["-c", "%n", "missing", "file"]),
+            case(&["-L", "-c", "%n", "dangling"]),
+        ],
+    },
+    #[cfg(unix)]
+    Suite {
+        util: "du",
+        prefix: &[],
+        normalize: identity,
+        files: Some(files),
+        cases: &[
+            case(&["-b", "file"]),
+            case(&["-ab", "dir/sub"]),
+            case(&["-sb", "dir", "file"]),
+            case(&["-cb", "dir", "file"]),
+            case(&["-a", "--apparent-size", "-B1", "dir/sub"]),
+            case(&["-d", "0", "-b", "dir"]),
+            case(&["-ab", "--exclude=sub", "dir"]),
+            case(&["-b0", "file"]),
+            case(&["-b", "missing", "file"]),
+        ],
+    },
+    Suite {
+        util: "df",
+        prefix: &[],
+        normalize: identity,
+        files: None,
+        cases: &[
+            case(&["/proc"]),
+            case(&["/dev/shm", "/proc"]),
+            case(&["-i", "/proc"]),
+            case(&["-h", "/dev/shm"]),
+            case(&["-H", "/dev/shm"]),
+            case(&["does-not-exist"]),
+        ],
+    },
+    #[cfg(unix)]
+    Suite {
+        util: "chmod",
+        prefix: &[],
+        normalize: identity,
+        files: Some(files),
+        cases: &[
+            case(&["-c", "u+x,go-r", "file"]),
+            case(&["-v", "755", "file", "dir"]),
+            case(&["-c", "644", "file"]),
+            case(&["-c", "a=rX", "file", "dir"]),
+            case(&["-cR", "go=", "dir/sub"]),
+            case(&["-c", "g+s,o+t", "dir"]),
+            case(&["-c", "u=rwx,g=u-w,o=g", "file"]),
+            case(&["-c", "+x", "link"]),
+            case(&["-vR", "u+w", "dirlink/sub"]),
+            case(&["-c", "0600", "missing", "file"]),
+            case(&["-f", "u+x", "missing"]),
+            case(&["u+z", "file"]),
+        ],
+    },
+    #[cfg(unix)]
+    Suite {
+        util: "chown",
+        prefix: &[],
+        normalize: identity,
+        files: Some(files),
+        cases: &[
+            case(&["-c", "nobody", "file"]),
+            case(&["-v", "0:0", "file"]),
+            case(&["-c", "nobody:nogroup", "file", "dir"]),
+            case(&["-cR", "1:1", "dir/sub"]),
+            case(&["-c", ":daemon", "file"]),
+            case(&["-ch", "nobody", "link"]),
+            case(&["-c", "nobody", "link"]),
+            case(&["-c", "--from=1", "nobody", "file"]),
+            case(&["-c", "--reference=dir", "file"]),
+            case(&["-c", "no-such-user", "file"]),
+            case(&["-c", "nobody", "missing"]),
+        ],
+    },
+    #[cfg(unix)]
+    Suite {
+        util: "chgrp",
+        prefix: &[],
+        normalize: identity,
+        files: Some(files),
+        cases: &[
+            case(&["-c", "nogroup", "file"]),
+            case(&["-v", "0", "file"]),
+            case(&["-cR", "daemon", "dir/sub"]),
+            case(&["-ch", "nogroup", "link"]),
+            case(&["-c", "--reference=missing", "file"]),
+            case(&["-c", "no-such-group", "file"]),
+            case(&["-f", "nogroup", "missing"]),
+        ],
+    },
+    #[cfg(unix)]
+    Suite {
+        util: "truncate",
+        prefix: &[],
+        normalize: identity,
+        files: Some(files),
+        cases: &[
+            case(&["-s", "3", "file"]),
+            case(&["-s", "+5", "file"]),
+            case(&["-s", "-2", "file"]),
+            case(&["-s", "1K", "new"]),
+            case(&["-s", "%4", "file", "dir/a"]),
+            case(&["-s", "/4", "dir/a"]),
+            case(&["-s", ">3", "file", "dir/a"]),
+            case(&["-s", "<3", "file", "dir/a"]),
+            case(&["-c", "-s", "3", "new"]),
+            case(&["-r", "file", "new"]),
+            case(&["-r", "file", "-s", "+2", "dir/a"]),
+            case(&["-s", "x", "file"]),
+            case(&["-s", "0", "dir"]),
+            case(&["file"]),
+        ],
+    },
+    #[cfg(unix)]
+    Suite {
+        util: "split",
+        prefix: &[],
+        normalize: identity,
+        files: Some(files),
+        cases: &[
+            case(&["lines"]),
+            case(&["-l", "3", "lines"]),
+            case(&["-l", "4", "lines", "part-"]),
+            case(&["-b", "4", "lines"]),
+            case(&["-b", "1K", "dir/a"]),
+            case(&["-C", "5", "lines"]),
+            case(&["-C", "2", "lines"]),
+            case(&["-a", "1", "-l", "1", "lines"]),
+            case(&["-a", "1", "-b", "1", "dir/a"]),
+            with_stdin(&["-l", "2", "-"], b"a\nb\nc\n"),
+            case(&["-b", "0", "lines"]),
+            case(&["-l", "x", "lines"]),
+            case(&["missing"]),
+        ],
+    },
+];
+
+/// Create the files of the utilities which work on files, in `directory`: a file, the lines of split, a
+/// directory with a file and a subdirectory, and symbolic links to a file, to a directory, and to nothing.
+///
+/// We visit the entries of a directory in the order of their names, and GNU in the order of the directory, so
+/// the recursive cases go through `dir/sub`, which only has one entry.
+#[cfg(unix)]
+fn files(directory: &Path) {
+    let lines: String = (1..=10).map(|line| format!("{line}\n")).collect();
+    for (name, contents) in [
+        ("file", "hello\n"),
+        ("lines", &lines),
+        ("dir/a", &"a".repeat(100)),
+        ("dir/sub/b", "b\n"),
+    ] {
+        let path = directory.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, contents).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+    }
+    for name in ["dir", "dir/sub"] {
+        fs::set_permissions(directory.join(name), fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    for (name, target) in [
+        ("link", "file"),
+        ("dirlink", "dir"),
+        ("dangling", "missing"),
+    ] {
+        std::os::unix::fs::symlink(target, directory.join(name)).unwrap();
+    }
+}
+
+/// Describe the files below `path`, in order, with their modes, owners, and contents or targets.
+fn snapshot(directory: &Path, path: &Path, files: &mut Vec<String>) {
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    entries.sort();
+    for entry in entries {
+        let metadata = fs::symlink_metadata(&entry).unwrap();
+        let contents = if metadata.is_symlink() {
+            format!("-> {}", fs::read_link(&entry).unwrap().display())
+        } else if metadata.is_dir() {
+            String::new()
+        } else {
+            format!("{:?}", String::from_utf8_lossy(&fs::read(&entry).unwrap()))
+        };
+        files.push(format!(
+            "{} {} {contents}",
+            entry.strip_prefix(directory).unwrap().display(),
+            permissions(&metadata)
+        ));
+        if metadata.is_dir() {
+            snapshot(directory, &entry, files);
+        }
+    }
+}
+
+/// Describe the mode and the owners of a file.
+#[cfg(unix)]
+fn permissions(metadata: &fs::Metadata) -> String {
+    format!(
+        "{:o} {}:{}",
+        metadata.mode(),
+        metadata.uid(),
+        metadata.gid()
+    )
+}
+
+/// Describe whether a file is read-only, which is all there is of its mode outside of Unix.
+#[cfg(not(unix))]
+fn permissions(metadata: &fs::Metadata) -> String {
+    format!("readonly={}", metadata.permissions().readonly())
+}
+
+/// Find the reference implementation of a utility.
+fn reference(util: &str) -> Option<PathBuf> {
+    ["/usr/bin", "/bin"]
+        .iter()
+        .map(|directory| Path::new(directory).join(util))
+        .find(|path| path.is_file())
+}
+
+fn run(mut command: Command, stdin: &[u8]) -> Output {
+    // Make sure the reference does not switch to a different mode.
+    command
+        .env_remove("POSIXLY_CORRECT")
+        .env("LC_ALL", "C")
+        // GNU ls only uses its default colors with the terminals that it knows.
+        .env_remove("LS_COLORS")
+        .env("TERM", "xterm")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let mut child = command.spawn().unwrap();
+    // Utilities which fail early, like on an invalid option, may exit before reading their input.
+    match child.stdin.take().unwrap().write_all(stdin) {
+        Err(error) if error.kind() != io::ErrorKind::BrokenPipe => panic!("{error}"),
+        _ => {}
+    }
+    child.wait_with_output().unwrap()
+}
+
+/// Run a case in `directory`, where the files of the suite are created first if it has any, and describe the
+/// observable behavior that must match.
+fn outcome(
+    suite: &Suite,
+    mut command: Command,
+    stdin: &[u8],
+    directory: &Path,
+) -> (String, bool, Option<i32>, Vec<String>) {
+    if let Some(create) = suite.files {
+        if directory.exists() {
+            fs::remove_dir_all(directory).unwrap();
+        }
+        fs::create_dir(directory).unwrap();
+        create(directory);
+        command.current_dir(directory);
+    }
+    let output = run(command, stdin);
+    let mut files = Vec::new();
+    if suite.files.is_some() {
+        snapshot(directory, directory, &mut files);
+    }
+    let stdout = String::from_utf8_lossy(&(suite.normalize)(output.stdout)).into_owned();
+    (
+        stdout,
+        output.stderr.is_empty(),
+        output.status.code(),
+        files,
+    )
+}
+
+#[test]
+fn test_gnu_diff() {
+    let mut failures = Vec::new();
+    // Both implementations run in the same directory, so that the absolute names they print are the same.
+    let root = tempfile::tempdir().unwrap();
+    let directory = root.path().join("files");
+
+    for suite in SUITES {
+        let Some(reference) = reference(suite.util) else {
+            eprintln!("skipping {}: no reference binary found", suite.util);
+            continue;
+        };
+
+        for case in suite.cases {
+            let mut ours = Command::new(env!("CARGO_BIN_EXE_coreutils"));
+            ours.arg(suite.util).args(suite.prefix).args(case.args);
+            let ours = outcome(suite, ours, case.stdin, &directory);
+
+            let mut theirs = Command::new(&reference);
+            theirs.args(case.args);
+            let theirs = outcome(suite, theirs, case.stdin, &directory);
+
+            match (ours == theirs, case.divergent) {
+                (true, None) | (false, Some(_)) => {}
+                (false, None) => failures.push(format!(
+                    "{} {:?}:\n    ours:   {ours:?}\n    theirs: {theirs:?}",
+                    suite.util, case.args
+                )),
+                (true, Some(reason)) => failures.push(format!(
+                    "{} {:?}: marked as divergent ({reason}), but matches now",
+                    suite.util, case.args
+                )),
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}\n", failures.join("\n"));
+}
@@ -0,0 +1,580 @@
+//! Property-based tests of the utilities that transform their input, through their library entry points.
+//!
+//! The generated inputs are [`Bytes`], which are shown as escaped byte strings, so that a shrunk failure
+//! reads like `b"a\n\xff"` rather than a list of numbers.
+//!
+//! Run with `cargo test --test properties`; set `PROPTEST_CASES` to run more cases.
+
+// The generators are shared by the modules of the utilities, which are not all built with fewer features.
+#![cfg_attr(not(feature = "all"), allow(dead_code))]
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+use std::ffi::OsString;
+use std::fmt;
+
+/// Generated input, shown as an escaped byte string.
+#[derive(Clone, PartialEq, Eq)]
+struct Bytes(Vec<u8>);
+
+impl fmt::Debug for Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "b\"{}\"", self.0.escape_ascii())
+    }
+}
+
+/// Arbitrary binary data.
+fn binary() -> impl Strategy<Value = Bytes> {
+    vec(any::<u8>(), 0..2048).prop_map(Bytes)
+}
+
+/// A line without its terminator: usually short and printable, sometimes empty, binary, or very long.
+fn line() -> impl Strategy<Value = Vec<u8>> {
+    let byte = prop_oneof![
+        8 => b' '..=b'~',
+        1 => 0..b'\n',
+        1 => b'\n' + 1..=u8::MAX,
+    ];
+    prop_oneof![
+        2 => Just(Vec::new()),
+        16 => vec(byte.clone(), 1..80),
+        // Repeating a short line is much faster to generate and to shrink.
+        1 => (vec(byte, 1..80), 50..500usize).prop_map(|(line, count)| line.repeat(count)),
+    ]
+}
+
+/// Lines of text, with or without a terminator after the last one.
+fn text() -> impl Strategy<Value = Bytes> {
+    (vec(line(), 0..32), any::<bool>()).prop_map(|(lines, terminated)| {
+        let mut text = lines.join(&b'\n');
+        if terminated && !lines.is_empty() {
+            text.push(b'\n');
+        }
+        Bytes(text)
+    })
+}
+
+/// Turn command-line arguments into what the entry points receive.
+fn args(command_line: &[&[u8]]) -> Vec<OsString> {
+    command_line
+        .iter()
+        .map(|arg| {
+            #[cfg(unix)]
+            {
+                use std::os::unix::ffi::OsStringExt;
+                OsString::from_vec(arg.to_vec())
+            }
+            #[cfg(not(unix))]
+            OsString::from(String::from_utf8(arg.to_vec()).unwrap())
+        })
+        .collect()
+}
+
+/// Run a utility through its library entry point, returning its exit code and output.
+fn run(
+    run: rust_coreutils::common::utility::Run,
+    command_line: &[&[u8]],
+    stdin: &[u8],
+) -> (i32, Bytes, String) {
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    let code = run(
+        &args(command_line),
+        &mut &stdin[..],
+        &mut stdout,
+        &mut stderr,
+    );
+    (
+        code,
+        Bytes(stdout),
+        String::from_utf8_lossy(&stderr).into_owned(),
+    )
+}
+
+#[cfg(feature = "feat_base32")]
+mod base32 {
+    use super::*;
+    use rust_coreutils::base32::run as base32;
+
+    proptest! {
+        #[test]
+        fn decoding_reverts_encoding(input in binary(), wrap in 0..100usize) {
+            let wrap = wrap.to_string();
+            let (code, encoded, stderr) = run(base32, &[b"base32", b"-w", wrap.as_bytes()], &input.0);
+            prop_assert_eq!((code, stderr), (0, String::new()));
+            prop_assert_eq!(run(base32, &[b"base32", b"-d"], &encoded.0), (0, input, String::new()));
+        }
+    }
+}
+
+#[cfg(feature = "feat_base64")]
+mod base64 {
+    use super::*;
+    use rust_coreutils::base64::run as base64;
+
+    proptest! {
+        #[test]
+        fn decoding_reverts_encoding(input in binary(), wrap in 0..100usize) {
+            let wrap = wrap.to_string();
+            let (code, encoded, stderr) = run(base64, &[b"base64", b"-w", wrap.as_bytes()], &input.0);
+            prop_assert_eq!((code, stderr), (0, String::new()));
+            prop_assert_eq!(run(base64, &[b"base64", b"-d"], &encoded.0), (0, input, String::new()));
+        }
+    }
+}
+
+#[cfg(feature = "feat_basenc")]
+mod basenc {
+    use super::*;
+    use rust_coreutils::basenc::run as basenc;
+
+    const ENCODINGS: &[&str] = &[
+        "--base64",
+        "--base64url",
+        "--base32",
+        "--base32hex",
+        "--base16",
+        "--base2msbf",
+        "--base2lsbf",
+    ];
+
+    proptest! {
+        #[test]
+        fn decoding_reverts_encoding(input in binary(), encoding in prop::sample::select(ENCODINGS)) {
+            let (code, encoded, stderr) = run(basenc, &[b"basenc", encoding.as_bytes()], &input.0);
+            prop_assert_eq!((code, stderr), (0, String::new()));
+            let decoded = run(basenc, &[b"basenc", encoding.as_bytes(), b"-d"], &encoded.0);
+            prop_assert_eq!(decoded, (0, input, String::new()));
+        }
+
+        #[test]
+        fn z85_decoding_reverts_encoding(mut input in binary()) {
+            input.0.truncate(input.0.len() / 4 * 4);
+            let (code, encoded, stderr) = run(basenc, &[b"basenc", b"--z85"], &input.0);
+            prop_assert_eq!((code, stderr), (0, String::new()));
+            let decoded = run(basenc, &[b"basenc", b"--z85", b"-d"], &encoded.0);
+            prop_assert_eq!(decoded, (0, input, String::new()));
+        }
+    }
+}
+
+#[cfg(feature = "feat_cat")]
+mod cat {
+    use super::*;
+    use rust_coreutils::cat::run as cat;
+
+    proptest! {
+        #[test]
+        fn stdin_is_copied_unchanged(input in binary()) {
+            prop_assert_eq!(run(cat, &[b"cat"], &input.0), (0, input, String::new()));
+        }
+
+        #[test]
+        fn files_are_concatenated(first in binary(), second in text()) {
+            let directory = tempfile::tempdir().unwrap();
+            let (a, b) = (directory.path().join("a"), directory.path().join("b"));
+            std::fs::write(&a, &first.0).unwrap();
+            std::fs::write(&b, &second.0).unwrap();
+            let (a, b) = (a.to_str().unwrap().as_bytes(), b.to_str().unwrap().as_bytes());
+
+            let expected = Bytes([&first.0[..], &second.0, &first.0].concat());
+            prop_assert_eq!(run(cat, &[b"cat", a, b, a], b""), (0, expected, String::new()));
+        }
+    }
+}
+
+#[cfg(feature = "feat_cut")]
+mod cut {
+    use super::*;
+    use rust_coreutils::cut::run as cut;
+
+    /// Split output into its lines, which all end with a newline, even empty ones.
+    fn lines(output: &[u8]) -> Vec<&[u8]> {
+        match output.strip_suffix(b"\n") {
+            Some(output) => output.split(|&byte| byte == b'\n').collect(),
+            None => Vec::new(),
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn bytes_before_and_after_a_position_make_the_line(input in text(), position in 1..16usize) {
+            let before = format!("-{position}");
+            let after = format!("{}-", position + 1);
+            let (code, head, stderr) = run(cut, &[b"cut", b"-b", before.as_bytes()], &input.0);
+            prop_assert_eq!((code, stderr), (0, String::new()));
+            let (_, tail, _) = run(cut, &[b"cut", b"-b", after.as_bytes()], &input.0);
+            let joined: Vec<Vec<u8>> = lines(&head.0)
+                .iter()
+                .zip(lines(&tail.0))
+                .map(|(head, tail)| [*head, tail].concat())
+                .collect();
+            let (_, whole, _) = run(cut, &[b"cut", b"-b1-"], &input.0);
+            prop_assert_eq!(joined, lines(&whole.0));
+        }
+
+        #[test]
+        fn complement_selects_the_other_fields(input in text(), position in 1..8usize) {
+            let field = format!("{position}");
+            let (code, selected, stderr) = run(cut, &[b"cut", b"-d ", b"-f", field.as_bytes()], &input.0);
+            prop_assert_eq!((code, stderr), (0, String::new()));
+            let complement = [&b"cut"[..], b"-d ", b"--complement", b"-f", field.as_bytes()];
+            let (_, others, _) = run(cut, &complement, &input.0);
+            let (_, whole, _) = run(cut, &[b"cut", b"-b1-"], &input.0);
+            let (selected, others, whole) = (lines(&selected.0), lines(&others.0), lines(&whole.0));
+            prop_assert_eq!((selected.len(), others.len()), (whole.len(), whole.len()));
+            for ((line, selected), others) in whole.into_iter().zip(selected).zip(others) {
+                let mut fields: Vec<&[u8]> = line.split(|&byte| byte == b' ').collect();
+                if fields.len() == 1 {
+                    // Lines without a delimiter are printed whole either way.
+                    prop_assert_eq!((selected, others), (line, line));
+                } else if position <= fields.len() {
+                    prop_assert_eq!(selected, fields.remove(position - 1));
+                    prop_assert_eq!(others, fields.join(&b' '));
+                } else {
+                    prop_assert_eq!((selected, others), (&b""[..], line));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "feat_echo")]
+mod echo {
+    use super::*;
+    use rust_coreutils::common::escapes::{expand_escapes, EscapeFlavor};
+
+    /// Escape each byte in one of the ways `echo -e` understands, picked pseudo-randomly from `seed`. The
+    /// result is ASCII, and never starts with `-` so that it is not taken for an option.
+    fn escape(bytes: &[u8], mut seed: u64) -> String {
+        let mut escaped = String::new();
+        for &byte in bytes {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let named = match byte {
+                0x07 => Some('a'),
+                0x08 => Some('b'),
+                0x1b => Some('e'),
+                0x0c => Some('f'),
+                b'\n' => Some('n'),
+                b'\r' => Some('r'),
+                b'\t' => Some('t'),
+                0x0b => Some('v'),
+                b'\\' => Some('\\'),
+                _ => None,
+            };
+            let literal = (byte.is_ascii_graphic() || byte == b' ')
+                && byte != b'\\'
+                && !(escaped.is_empty() && byte == b'-');
+            match (seed >> 62, named) {
+                (0, _) if literal => escaped.push(byte as char),
+                (1, Some(name)) => {
+                    escaped.push('\\');
+                    escaped.push(name);
+                }
+                (2, _) => escaped.push_str(&format!("\\x{byte:02x}")),
+                // Octal values take up to three digits after the `\0`, so a digit after them is literal.
+                _ => escaped.push_str(&format!("\\0{byte:03o}")),
+            }
+        }
+        escaped
+    }
+
+    proptest! {
+        #[test]
+        fn expand_escaped_binary(data in binary(), seed: u64) {
+            let escaped = escape(&data.0, seed);
+            let result = expand_escapes(escaped.as_bytes(), EscapeFlavor::Echo);
+            prop_assert_eq!(Bytes(result.output), data);
+            prop_assert!(!result.terminated);
+        }
+
+        #[test]
+        fn echo_escaped_text(data in text(), seed: u64) {
+            let escaped = escape(&data.0, seed);
+            let (code, stdout, stderr) = run(
+                rust_coreutils::echo::run,
+                &[b"echo", b"--compat=gnu", b"-ne", escaped.as_bytes()],
+                b"",
+            );
+            prop_assert_eq!((code, stdout, stderr), (0, data, String::new()));
+        }
+
+        #[test]
+        fn expand_literal_text(data in "[^\\\\]*") {
+            let result = expand_escapes(data.as_bytes(), EscapeFlavor::Echo);
+            prop_assert_eq!(Bytes(result.output), Bytes(data.into_bytes()));
+        }
+    }
+}
+
+#[cfg(feature = "feat_od")]
+mod od {
+    use super::*;
+    use rust_coreutils::od::run as od;
+
+    proptest! {
+        #[test]
+        fn every_byte_is_dumped_once(input in binary()) {
+            let (code, stdout, stderr) = run(od, &[b"od", b"-v", b"-b"], &input.0);
+            prop_assert_eq!((code, stderr), (0, String::new()));
+            let text = String::from_utf8(stdout.0).unwrap();
+            let mut lines: Vec<&str> = text.lines().collect();
+            let end = lines.pop().unwrap();
+            prop_assert_eq!(usize::from_str_radix(end, 8).unwrap(), input.0.len());
+            let bytes: Vec<u8> = lines
+                .iter()
+                .flat_map(|line| line.split(' ').skip(1))
+                .map(|field| u8::from_str_radix(field, 8).unwrap())
+                .collect();
+            prop_assert_eq!(Bytes(bytes), input);
+        }
+
+        #[test]
+        fn skip_and_limit_select_the_bytes(input in binary(), skip in 0..100usize, limit in 0..100usize) {
+            let (skip_bytes, read_bytes) = (skip.to_string(), limit.to_string());
+            let command_line: &[&[u8]] = &[
+                b"od", b"-v", b"-A", b"d", b"-t", b"u1",
+                b"-j", skip_bytes.as_bytes(), b"-N", read_bytes.as_bytes(),
+            ];
+            let (code, stdout, stderr) = run(od, command_line, &input.0);
+            if skip > input.0.len() {
+                let message = "od: cannot skip past end of combined input\n";
+                prop_assert_eq!((code, stderr), (1, message.to_owned()));
+                return Ok(());
+            }
+            prop_assert_eq!((code, stderr), (0, String::new()));
+            let text = String::from_utf8(stdout.0).unwrap();
+            let mut lines: Vec<&str> = text.lines().collect();
+            let end = lines.pop().unwrap();
+            let expected = &input.0[skip..input.0.len().min(skip + limit)];
+            prop_assert_eq!(end.parse::<usize>().unwrap(), skip + expected.len());
+            let bytes: Vec<u8> = lines
+                .iter()
+                .flat_map(|line| line.split_whitespace().skip(1))
+                .map(|field| field.parse().unwrap())
+                .collect();
+            prop_assert_eq!(bytes, expected);
+        }
+    }
+}
+
+#[cfg(feature = "feat_printf")]
+mod printf {
+    use super::*;
+    use rust_coreutils::printf::run as printf;
+
+    proptest! {
+        #[test]
+        fn strings_are_printed_as_they_are(strings in vec("[ -~]{0,40}", 0..8)) {
+            let mut command_line: Vec<&[u8]> = vec![b"printf", br"%s\n"];
+            command_line.extend(strings.iter().map(|string| string.as_bytes()));
+            let (code, stdout, stderr) = run(printf, &command_line, b"");
+            prop_assert_eq!((code, stderr), (0, String::new()));
+            let expected: String = if strings.is_empty() {
+                "\n".to_owned()
+            } else {
+                strings.iter().map(|string| format!("{string}\n")).collect()
+            };
+            prop_assert_eq!(stdout.0, expected.into_bytes());
+        }
+
+        #[test]
+        fn integers_are_read_in_any_base(value in any::<i64>()) {
+            let magnitude = value.unsigned_abs();
+            let sign = if value < 0 { "-" } else { "" };
+            let arguments = [
+                value.to_string(),
+                format!("{sign}0x{magnitude:x}"),
+                format!("{sign}0{magnitude:o}"),
+            ];
+            let mut command_line: Vec<&[u8]> = vec![b"printf", b"%d "];
+            command_line.extend(arguments.iter().map(|argument| argument.as_bytes()));
+            let (code, stdout, _) = run(printf, &command_line, b"");
+            prop_assert_eq!(code, 0);
+            prop_assert_eq!(stdout.0, format!("{value} {value} {value} ").into_bytes());
+        }
+
+        #[test]
+        fn fields_are_padded_to_the_width(value in any::<i64>(), width in 0usize..30, left in any::<bool>()) {
+            let format = format!("%{}{width}d", if left { "-" } else { "" });
+            let argument = value.to_string();
+            let command_line: Vec<&[u8]> = vec![b"printf", format.as_bytes(), argument.as_bytes()];
+            let (code, stdout, _) = run(printf, &command_line, b"");
+            prop_assert_eq!(code, 0);
+            let expected = if left {
+                format!("{value:<width$}")
+            } else {
+                format!("{value:>width$}")
+            };
+            prop_assert_eq!(stdout.0, expected.into_bytes());
+        }
+    }
+}
+
+#[cfg(feature = "feat_seq")]
+mod seq {
+    use super::*;
+    use rust_coreutils::seq::run as seq;
+
+    proptest! {
+        #[test]
+        fn integers_are_counted_like_a_range(first in -50i64..50, increment in 1i64..10, last in -50i64..50) {
+            let operands = [first.to_string(), increment.to_string(), last.to_string()];
+            let mut command_line: Vec<&[u8]> = vec![b"seq"];
+            command_line.extend(operands.iter().map(|operand| operand.as_bytes()));
+            let (code, stdout, _) = run(seq, &command_line, b"");
+            prop_assert_eq!(code, 0);
+            let expected: String = (first..=last)
+                .step_by(increment as usize)
+                .map(|value| format!("{value}\n"))
+                .collect();
+            prop_assert_eq!(stdout.0, expected.into_bytes());
+        }
+    }
+}
+
+#[cfg(feature = "feat_sort")]
+mod sort {
+    use super::*;
+    use rust_coreutils::sort::run as sort;
+
+    /// Split output into its lines, which all end with a newline.
+    fn lines(output: &[u8]) -> Vec<&[u8]> {
+        let output = output.strip_suffix(b"\n").unwrap_or(output);
+        if output.is_empty() {
+            return Vec::new();
+        }
+        output.split(|&byte| byte == b'\n').collect()
+    }
+
+    proptest! {
+        #[test]
+        fn output_is_the_sorted_input(input in text()) {
+            let (code, stdout, stderr) = run(sort, &[b"sort"], &input.0);
+            prop_assert_eq!((code, stderr), (0, String::new()));
+            let mut expected = lines(&input.0);
+            expected.sort();
+            prop_assert_eq!(lines(&stdout.0), expected);
+            prop_assert!(stdout.0.is_empty() || stdout.0.ends_with(b"\n"));
+        }
+
+        #[test]
+        fn sorting_twice_changes_nothing(input in text()) {
+            let (_, once, _) = run(sort, &[b"sort"], &input.0);
+            let (_, twice, _) = run(sort, &[b"sort"], &once.0);
+            prop_assert_eq!(once, twice);
+        }
+    }
+}
+
+#[cfg(feature = "feat_tr")]
+mod tr {
+    use super::*;
+    use rust_coreutils::tr::run as tr;
+
+    proptest! {
+        #[test]
+        fn ranges_translate_like_ascii_case(input in binary()) {
+            let (code, stdout, stderr) = run(tr, &[b"tr", b"a-z", b"A-Z"], &input.0);
+            prop_assert_eq!((code, stderr), (0, String::new()));
+            prop_assert_eq!(stdout.0, input.0.to_ascii_uppercase());
+        }
+
+        #[test]
+        fn translating_back_restores_the_input(input in binary(), shift in any::<u8>()) {
+            // Every byte is translated to the byte `shift` after it, wrapping around.
+            let set2: Vec<u8> = (0..=u8::MAX)
+                .flat_map(|byte| format!("\\{:03o}", byte.wrapping_add(shift)).into_bytes())
+                .collect();
+            let (_, translated, _) = run(tr, &[b"tr", br"\0-\377", &set2], &input.0);
+            let (_, restored, _) = run(tr, &[b"tr", &set2, br"\0-\377"], &translated.0);
+            prop_assert_eq!(restored, input);
+        }
+
+        #[test]
+        fn deleting_splits_the_bytes_with_the_complement(input in binary()) {
+            let (_, deleted, _) = run(tr, &[b"tr", b"-d", b"[:alpha:]"], &input.0);
+            let (_, kept, _) = run(tr, &[b"tr", b"-cd", b"[:alpha:]"], &input.0);
+            let (letters, others): (Vec<u8>, Vec<u8>) =
+                input.0.iter().partition(|byte| byte.is_ascii_alphabetic());
+            prop_assert_eq!(deleted.0, others);
+            prop_assert_eq!(kept.0, letters);
+        }
+
+        #[test]
+        fn squeezing_leaves_no_repeats_of_the_set(input in binary()) {
+            let (code, squeezed, _) = run(tr, &[b"tr", b"-s", b"a-z"], &input.0);
+            prop_assert_eq!(code, 0);
+            prop_assert!(squeezed
+                .0
+                .windows(2)
+                .all(|pair| pair[0] != pair[1] || !pair[0].is_ascii_lowercase()));
+            // Squeezing again changes nothing.
+            let (_, again, _) = run(tr, &[b"tr", b"-s", b"a-z"], &squeezed.0);
+            prop_assert_eq!(again, squeezed);
+        }
+    }
+}
+
+#[cfg(feature = "feat_uniq")]
+mod uniq {
+    use super::*;
+    use rust_coreutils::uniq::run as uniq;
+
+    /// Lines of text where lines are often repeated, with or without a terminator after the last one.
+    fn runs() -> impl Strategy<Value = Bytes> {
+        (vec((line(), 1..4usize), 0..16), any::<bool>()).prop_map(|(runs, terminated)| {
+            let lines: Vec<Vec<u8>> = runs
+                .into_iter()
+                .flat_map(|(line, count)| std::iter::repeat_n(line, count))
+                .collect();
+            let mut text = lines.join(&b'\n');
+            if terminated && !lines.is_empty() {
+                text.push(b'\n');
+            }
+            Bytes(text)
+        })
+    }
+
+    /// Split input into its lines, the last of which may have no newline.
+    fn lines(input: &[u8]) -> Vec<&[u8]> {
+        if input.is_empty() {
+            return Vec::new();
+        }
+        let input = input.strip_suffix(b"\n").unwrap_or(input);
+        input.split(|&byte| byte == b'\n').collect()
+    }
+
+    proptest! {
+        #[test]
+        fn counts_expand_to_the_input(input in runs()) {
+            let (code, stdout, stderr) = run(uniq, &[b"uniq", b"-c"], &input.0);
+            prop_assert_eq!((code, stderr), (0, String::new()));
+            let mut expanded = Vec::new();
+            let mut previous: Option<&[u8]> = None;
+            for line in lines(&stdout.0) {
+                let (count, line) = (&line[..7], &line[8..]);
+                let count: usize = String::from_utf8_lossy(count).trim().parse().unwrap();
+                prop_assert!(previous != Some(line), "adjacent duplicates in the output");
+                expanded.extend(std::iter::repeat_n(line, count));
+                previous = Some(line);
+            }
+            prop_assert_eq!(expanded, lines(&input.0));
+        }
+
+        #[test]
+        fn repeated_and_unique_lines_partition_the_output(input in runs()) {
+            let (_, all, _) = run(uniq, &[b"uniq"], &input.0);
+            let (_, repeated, _) = run(uniq, &[b"uniq", b"-d"], &input.0);
+            let (_, unique, _) = run(uniq, &[b"uniq", b"-u"], &input.0);
+            prop_assert_eq!(
+                lines(&all.0).len(),
+                lines(&repeated.0).len() + lines(&unique.0).len()
+            );
+            let (_, twice, _) = run(uniq, &[b"uniq"], &all.0);
+            prop_assert_eq!(twice, all);
+        }
+    }
+}